@@ -0,0 +1,233 @@
+// Subtitle-to-transcript alignment
+//
+// Retimes an existing subtitle file, usually in another language, onto the timeline of a
+// whisper transcription without touching its text. Cues and transcript segments are paired
+// through anchors that survive translation: numbers, and cognates (names and loanwords that
+// share their first characters). A monotonic alignment maximizes the anchor score, weighted by
+// how similar the cue and segment durations are; every cue is then retimed through the
+// piecewise-linear mapping defined by the anchored (cue start, segment start) pairs.
+
+use std::collections::HashSet;
+
+use crate::config::AlignConfig;
+use crate::error::{Result, ShuroError};
+use crate::quality::{Transcription, TranscriptionSegment};
+
+/// A subtitle cue paired with a transcript segment
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anchor {
+    /// Index of the subtitle cue
+    pub cue: usize,
+    /// Index of the transcript segment
+    pub segment: usize,
+    /// Anchor score of the pair
+    pub score: f64,
+}
+
+/// Retime subtitle cues onto the transcript timeline, keeping their text
+pub fn align_subtitles(subtitles: &Transcription, transcript: &Transcription, config: &AlignConfig) -> Result<Transcription> {
+    let anchors = find_anchors(&subtitles.segments, &transcript.segments, config);
+    if anchors.is_empty() {
+        return Err(ShuroError::Alignment(
+            "No numbers or cognates shared between the subtitles and the transcription".to_string()
+        ));
+    }
+
+    let points: Vec<(f64, f64)> = anchors
+        .iter()
+        .map(|a| (subtitles.segments[a.cue].start, transcript.segments[a.segment].start))
+        .collect();
+
+    let mut aligned = subtitles.clone();
+    for segment in &mut aligned.segments {
+        let start = map_time(&points, segment.start);
+        let end = map_time(&points, segment.end);
+        segment.start = start;
+        segment.end = end.max(start);
+    }
+
+    Ok(aligned)
+}
+
+/// Find the monotonic cue-to-segment pairing with the highest total anchor score
+pub fn find_anchors(cues: &[TranscriptionSegment], segments: &[TranscriptionSegment], config: &AlignConfig) -> Vec<Anchor> {
+    let cue_keys: Vec<HashSet<String>> = cues.iter().map(|c| anchor_keys(&c.text, config.cognate_length)).collect();
+    let segment_keys: Vec<HashSet<String>> = segments.iter().map(|s| anchor_keys(&s.text, config.cognate_length)).collect();
+
+    let score = |i: usize, j: usize| -> f64 {
+        let shared = cue_keys[i].intersection(&segment_keys[j]).count();
+        if shared == 0 {
+            return 0.0;
+        }
+        let similarity = duration_similarity(&cues[i], &segments[j]);
+        shared as f64 * (1.0 - config.duration_weight + config.duration_weight * similarity)
+    };
+
+    // best[i][j]: highest score using the first i cues and the first j segments
+    let (n, m) = (cues.len(), segments.len());
+    let mut best = vec![vec![0.0; m + 1]; n + 1];
+    for i in 1..=n {
+        for j in 1..=m {
+            let pair = score(i - 1, j - 1);
+            let matched = if pair > 0.0 { best[i - 1][j - 1] + pair } else { 0.0 };
+            best[i][j] = best[i - 1][j].max(best[i][j - 1]).max(matched);
+        }
+    }
+
+    let mut anchors = Vec::new();
+    let (mut i, mut j) = (n, m);
+    while i > 0 && j > 0 {
+        if best[i][j] == best[i - 1][j] {
+            i -= 1;
+        } else if best[i][j] == best[i][j - 1] {
+            j -= 1;
+        } else {
+            anchors.push(Anchor { cue: i - 1, segment: j - 1, score: score(i - 1, j - 1) });
+            i -= 1;
+            j -= 1;
+        }
+    }
+    anchors.reverse();
+    anchors
+}
+
+/// Kind of character run a token is made of
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenClass {
+    Digit,
+    Latin,
+    OtherLetter,
+}
+
+fn token_class(c: char) -> Option<TokenClass> {
+    if c.is_ascii_digit() {
+        Some(TokenClass::Digit)
+    } else if c.is_alphabetic() && c <= '\u{024F}' {
+        Some(TokenClass::Latin)
+    } else if c.is_alphabetic() {
+        Some(TokenClass::OtherLetter)
+    } else {
+        None
+    }
+}
+
+/// Numbers and cognate prefixes found in the text; tokens break at script changes
+fn anchor_keys(text: &str, cognate_length: usize) -> HashSet<String> {
+    let mut keys = HashSet::new();
+    let mut token = String::new();
+    let mut class = None;
+
+    let mut flush = |token: &mut String, class: Option<TokenClass>| {
+        match class {
+            Some(TokenClass::Digit) => {
+                let number = token.trim_start_matches('0');
+                keys.insert(format!("#{}", if number.is_empty() { "0" } else { number }));
+            }
+            Some(_) if token.chars().count() >= cognate_length => {
+                keys.insert(token.chars().take(cognate_length).collect::<String>().to_lowercase());
+            }
+            _ => {}
+        }
+        token.clear();
+    };
+
+    for c in text.chars() {
+        let current = token_class(c);
+        if current != class {
+            flush(&mut token, class);
+            class = current;
+        }
+        if current.is_some() {
+            token.push(c);
+        }
+    }
+    flush(&mut token, class);
+
+    keys
+}
+
+/// Ratio of the shorter to the longer duration (1.0 for equal durations)
+fn duration_similarity(a: &TranscriptionSegment, b: &TranscriptionSegment) -> f64 {
+    let (da, db) = ((a.end - a.start).max(0.0), (b.end - b.start).max(0.0));
+    if da.max(db) == 0.0 {
+        return 1.0;
+    }
+    da.min(db) / da.max(db)
+}
+
+/// Map a subtitle time through the anchor points, interpolating between them and
+/// shifting by the nearest anchor's offset outside them
+fn map_time(points: &[(f64, f64)], time: f64) -> f64 {
+    let first = points[0];
+    let last = points[points.len() - 1];
+
+    let mapped = if time <= first.0 {
+        time + (first.1 - first.0)
+    } else if time >= last.0 {
+        time + (last.1 - last.0)
+    } else {
+        let window = points.windows(2)
+            .find(|w| w[0].0 <= time && time <= w[1].0)
+            .expect("time lies between the first and last anchor");
+        let ((x0, y0), (x1, y1)) = (window[0], window[1]);
+        if x1 == x0 { y0 } else { y0 + (time - x0) * (y1 - y0) / (x1 - x0) }
+    };
+
+    mapped.max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcription(cues: &[(f64, f64, &str)]) -> Transcription {
+        Transcription {
+            text: String::new(),
+            language: "en".to_string(),
+            segments: cues.iter().enumerate().map(|(id, (start, end, text))| TranscriptionSegment {
+                id: id as i32,
+                start: *start,
+                end: *end,
+                text: text.to_string(),
+                tokens: Vec::new(),
+                temperature: 0.0,
+                avg_logprob: 0.0,
+                compression_ratio: 0.0,
+                no_speech_prob: 0.0,
+                words: Vec::new(),
+                metadata: Default::default(),
+            }).collect(),
+        }
+    }
+
+    #[test]
+    fn test_anchor_keys() {
+        let keys = anchor_keys("東京で2024年にSchneiderと007", 5);
+        assert!(keys.contains("#2024"));
+        assert!(keys.contains("#7"));
+        assert!(keys.contains("schne"));
+    }
+
+    #[test]
+    fn test_align_subtitles_retimes_onto_transcript() {
+        let transcript = transcription(&[
+            (10.0, 12.0, "Welcome to Tokyo, Schneider."),
+            (12.5, 14.0, "We have nothing."),
+            (15.0, 17.0, "It costs 300 yen."),
+        ]);
+        // Same cues, but 5 seconds early and in another language
+        let subtitles = transcription(&[
+            (5.0, 7.0, "ようこそ、Schneiderさん。"),
+            (7.5, 9.0, "何もありません。"),
+            (10.0, 12.0, "300円です。"),
+        ]);
+
+        let aligned = align_subtitles(&subtitles, &transcript, &AlignConfig::default()).unwrap();
+        let timings: Vec<(f64, f64)> = aligned.segments.iter().map(|s| (s.start, s.end)).collect();
+        assert_eq!(timings, vec![(10.0, 12.0), (12.5, 14.0), (15.0, 17.0)]);
+        assert_eq!(aligned.segments[1].text, "何もありません。");
+
+        let unrelated = transcription(&[(0.0, 1.0, "はい")]);
+        assert!(align_subtitles(&unrelated, &transcript, &AlignConfig::default()).is_err());
+    }
+}
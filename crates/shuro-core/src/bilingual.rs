@@ -0,0 +1,83 @@
+// Bilingual subtitles
+//
+// Language learners and mixed-language audiences want to read the original line next to its
+// translation. For the target languages listed in `bilingual.languages`, every cue of the `.srt`
+// (and so the track embedded into the video) and of the styled `.ass` carries both texts stacked:
+// the translation, then the source line, or the other way round with `bilingual.source_first`.
+// Cues of a translation that kept the source's cue timing are paired by position; the cues of a
+// regrouped translation get the source segments whose midpoint falls within them. Forced tracks,
+// translator's notes and reports keep the translation alone.
+
+use crate::config::BilingualConfig;
+use crate::quality::Transcription;
+
+impl BilingualConfig {
+    /// Whether the subtitles in `language` show the source text too
+    pub fn applies_to(&self, language: &str) -> bool {
+        self.languages.iter().any(|l| l.eq_ignore_ascii_case(language))
+    }
+}
+
+/// `translation` with the text of `source` stacked into every cue when `target_language` is one
+/// of the bilingual languages, otherwise unchanged
+pub fn stack_source(source: &Transcription, translation: &Transcription, config: &BilingualConfig, target_language: &str) -> Transcription {
+    let mut stacked = translation.clone();
+    if !config.applies_to(target_language) {
+        return stacked;
+    }
+    let aligned = source.segments.len() == translation.segments.len();
+    for (index, segment) in stacked.segments.iter_mut().enumerate() {
+        let original = if aligned {
+            source.segments[index].text.trim().to_string()
+        } else {
+            source.segments.iter()
+                .filter(|s| (segment.start..segment.end).contains(&((s.start + s.end) / 2.0)))
+                .map(|s| s.text.trim())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let translated = segment.text.trim();
+        if original.is_empty() || original == translated {
+            continue;
+        }
+        segment.text = if config.source_first {
+            format!("{}\n{}", original, translated)
+        } else {
+            format!("{}\n{}", translated, original)
+        };
+    }
+    stacked.text = stacked.segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" ");
+    stacked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::parse_srt;
+
+    #[test]
+    fn test_stack_source() {
+        let source = parse_srt(
+            "1\n00:00:01,000 --> 00:00:02,000\nWhere are we?\n\n2\n00:00:03,000 --> 00:00:04,000\nOK\n\n\
+             3\n00:00:05,000 --> 00:00:06,000\nRun!\n",
+            "en",
+        ).unwrap();
+        let translated = parse_srt(
+            "1\n00:00:01,000 --> 00:00:02,000\nここはどこ？\n\n2\n00:00:03,000 --> 00:00:04,000\nOK\n\n\
+             3\n00:00:05,000 --> 00:00:06,000\n走れ！\n",
+            "ja",
+        ).unwrap();
+        let config = BilingualConfig { languages: vec!["ja".to_string()], source_first: false };
+
+        let stacked = stack_source(&source, &translated, &config, "ja");
+        let texts: Vec<&str> = stacked.segments.iter().map(|s| s.text.as_str()).collect();
+        // A cue reading the same in both languages is not doubled
+        assert_eq!(texts, vec!["ここはどこ？\nWhere are we?", "OK", "走れ！\nRun!"]);
+        assert_eq!(stack_source(&source, &translated, &config, "ko").segments[0].text, "ここはどこ？");
+
+        // A regrouped translation gets the source cues within its time
+        let regrouped = parse_srt("1\n00:00:01,000 --> 00:00:04,500\nここはどこ？OK\n", "ja").unwrap();
+        let config = BilingualConfig { source_first: true, ..config };
+        assert_eq!(stack_source(&source, &regrouped, &config, "JA").segments[0].text, "Where are we? OK\nここはどこ？OK");
+    }
+}
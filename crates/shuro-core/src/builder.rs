@@ -0,0 +1,181 @@
+// Embedding the engine
+//
+// `Shuro::builder()` assembles the engine for an application: the configuration, a data
+// directory holding every cache, batch job state and temporary artifact (`.shuro/` for the
+// command line tool; a `translate.cache_dir` set in the configuration is kept), the project
+// whose glossary, models and translation memory apply, and optionally registries with further
+// transcribers, translators and translation providers. The built `Shuro` hands out the
+// transcriber, translators and media processor, or the `Workflow` running the whole pipeline.
+//
+// A relative data directory is made absolute against the working directory once, when the engine
+// is built; the caches, job states and temporary files of the engine are kept under it wherever
+// the process later changes directory, so several engines with different data directories can run
+// in one process. Other paths of the configuration (a separate `translate.cache_dir`, model
+// files, binaries) are used as given, so relative ones follow the working directory. The engine
+// relies on no other process-wide settings, logs through `tracing` and prints nothing.
+
+use std::path::{Path, PathBuf};
+
+use crate::config::{Config, default_translation_cache_dir};
+use crate::error::{Result, ShuroError};
+use crate::media::MediaProcessorTrait;
+use crate::project::Project;
+use crate::transcribe::{TranscriberRegistry, TranscriberTrait};
+use crate::translate::{BackendRegistry, Translator, TranslatorRegistry};
+use crate::workflow::Workflow;
+
+/// Builder of a [`Shuro`] engine
+#[derive(Default)]
+pub struct ShuroBuilder {
+    config: Option<Config>,
+    data_dir: Option<PathBuf>,
+    project: Option<Project>,
+    transcribers: Option<TranscriberRegistry>,
+    translators: Option<TranslatorRegistry>,
+    backends: Option<BackendRegistry>,
+    subtitles_only: bool,
+}
+
+impl ShuroBuilder {
+    /// Configuration of the engine; the defaults when not given
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Directory of the caches (`cache/`), batch job states (`state/`) and temporary artifacts
+    /// (`tmp/`), made absolute when the engine is built; required
+    pub fn data_dir(mut self, data_dir: impl Into<PathBuf>) -> Self {
+        self.data_dir = Some(data_dir.into());
+        self
+    }
+
+    /// Apply a project's models and glossary, and keep the translation memory in the project
+    pub fn project(mut self, project: Project) -> Self {
+        self.project = Some(project);
+        self
+    }
+
+    /// Resolve transcriber names against `transcribers` instead of the built-in transcribers and
+    /// the plugins of the configuration
+    pub fn transcribers(mut self, transcribers: TranscriberRegistry) -> Self {
+        self.transcribers = Some(transcribers);
+        self
+    }
+
+    /// Resolve translator names against `translators` instead of the built-in translators and
+    /// the plugins of the configuration
+    pub fn translators(mut self, translators: TranslatorRegistry) -> Self {
+        self.translators = Some(translators);
+        self
+    }
+
+    /// Resolve translation providers against `backends` instead of the built-in providers
+    pub fn backends(mut self, backends: BackendRegistry) -> Self {
+        self.backends = Some(backends);
+        self
+    }
+
+    /// Work on subtitle files only, which needs neither ffmpeg nor whisper models
+    pub fn subtitles_only(mut self) -> Self {
+        self.subtitles_only = true;
+        self
+    }
+
+    pub fn build(self) -> Result<Shuro> {
+        let data_dir = self.data_dir
+            .ok_or_else(|| ShuroError::Config("The engine needs a data directory for its caches and state".to_string()))?;
+        let data_dir = std::path::absolute(&data_dir)?;
+        let mut config = self.config.unwrap_or_default();
+        // A translation cache configured elsewhere than the default stays where it is
+        let translation_cache = config.translate.cache_dir.clone();
+        config.use_work_dir(&data_dir);
+        if translation_cache != default_translation_cache_dir() {
+            config.translate.cache_dir = translation_cache;
+        }
+        if let Some(project) = &self.project {
+            config = project.apply(config);
+        }
+
+        let transcribers = self.transcribers
+            .unwrap_or_else(|| TranscriberRegistry::with_builtin().register_external(&config.plugins.transcribers));
+        let translators = self.translators
+            .unwrap_or_else(|| TranslatorRegistry::with_builtin().register_external(&config.plugins.translators));
+        let backends = self.backends.unwrap_or_else(BackendRegistry::with_builtin);
+        let workflow = Workflow::assemble(config, transcribers, translators, backends)?;
+        if !self.subtitles_only {
+            workflow.media().check_availability()?;
+        }
+
+        Ok(Shuro { workflow, data_dir })
+    }
+}
+
+/// The engine, built with [`Shuro::builder`]
+pub struct Shuro {
+    workflow: Workflow,
+    data_dir: PathBuf,
+}
+
+impl Shuro {
+    pub fn builder() -> ShuroBuilder {
+        ShuroBuilder::default()
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// Configuration of the engine, with its paths moved into the data directory
+    pub fn config(&self) -> &Config {
+        self.workflow.config()
+    }
+
+    pub fn transcriber(&self) -> &dyn TranscriberTrait {
+        self.workflow.transcriber()
+    }
+
+    /// Translator for one target language
+    pub async fn translator(&self) -> Result<Box<dyn Translator>> {
+        self.workflow.translator().await
+    }
+
+    pub fn media(&self) -> &dyn MediaProcessorTrait {
+        self.workflow.media()
+    }
+
+    pub fn workflow(&self) -> &Workflow {
+        &self.workflow
+    }
+
+    /// The workflow, to attach progress reporting or batch control to
+    pub fn into_workflow(self) -> Workflow {
+        self.workflow
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths_are_injected() {
+        assert!(Shuro::builder().subtitles_only().build().is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let shuro = Shuro::builder().data_dir(dir.path()).subtitles_only().build().unwrap();
+        let config = shuro.config();
+        assert_eq!(shuro.data_dir(), dir.path());
+        assert_eq!(Path::new(&config.transcriber.cache_dir), dir.path().join("cache"));
+        assert_eq!(Path::new(&config.translate.cache_dir), dir.path().join("cache"));
+        assert_eq!(Path::new(&config.state_dir), dir.path().join("state"));
+        assert_eq!(Path::new(&config.temp_dir), dir.path().join("tmp"));
+        assert_eq!(Path::new(&config.transcriber.temp_dir), dir.path().join("tmp"));
+
+        // A relative data directory is resolved once, when the engine is built
+        let shuro = Shuro::builder().data_dir("engine-data").subtitles_only().build().unwrap();
+        let data_dir = std::env::current_dir().unwrap().join("engine-data");
+        assert_eq!(shuro.data_dir(), data_dir);
+        assert_eq!(Path::new(&shuro.config().transcriber.cache_dir), data_dir.join("cache"));
+    }
+}
@@ -0,0 +1,330 @@
+// Cache archives
+//
+// Transcribing a season takes hours, and every machine or CI runner working on the same series
+// would repeat it. `shuro cache export` bundles cache entries into a gzipped tarball, and
+// `shuro cache import` unpacks one into the local caches, keeping the entries that already exist.
+//
+// Transcription cache files are keyed by the path and modification time of the video, which
+// differ from machine to machine, so transcriptions travel with their audio fingerprints and the
+// tuning profiles of their videos: with `transcriber.fingerprint_dedupe`, tuned mode finds an
+// imported transcription through the fingerprint of the audio (see transcribe/fingerprint.rs),
+// and the tuning profile, keyed by the video's content, skips tempo exploration either way.
+// Fingerprint entries name their transcription by path, which import points at the local cache.
+// Translations and analysis journals are keyed by their contents and are reused as they are.
+// Extracted audio is quick to redo and large, and is never exported.
+//
+// An archive holds `manifest.json` first, then every file as `<kind>/<file name>`. With
+// `--source`, only the transcriptions and tuning profiles of videos whose file name contains the
+// given text are exported.
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use tracing::{debug, info};
+
+use super::CacheCategory;
+use crate::config::Config;
+use crate::error::{Result, ShuroError};
+use crate::transcribe::fingerprint::FingerprintEntry;
+use crate::transcribe::tuning::TuningProfile;
+
+/// Version of the archive layout
+const ARCHIVE_FORMAT: u32 = 1;
+
+/// Name of the manifest in an archive
+const MANIFEST: &str = "manifest.json";
+
+/// Files stored in an archive, by the directory they go to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveKind {
+    Transcriptions,
+    Fingerprints,
+    Tuning,
+    Translations,
+    Analysis,
+}
+
+impl ArchiveKind {
+    const ALL: [Self; 5] = [Self::Transcriptions, Self::Fingerprints, Self::Tuning, Self::Translations, Self::Analysis];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Transcriptions => "transcriptions",
+            Self::Fingerprints => "fingerprints",
+            Self::Tuning => "tuning",
+            Self::Translations => "translations",
+            Self::Analysis => "analysis",
+        }
+    }
+
+    fn dir(&self, config: &Config) -> PathBuf {
+        match self {
+            Self::Transcriptions => CacheCategory::Transcriptions.dir(config),
+            Self::Fingerprints => Path::new(&config.transcriber.cache_dir).join("fingerprints"),
+            Self::Tuning => Path::new(&config.transcriber.cache_dir).join("tuning"),
+            Self::Translations => CacheCategory::Translations.dir(config),
+            Self::Analysis => CacheCategory::Analysis.dir(config),
+        }
+    }
+}
+
+/// Which cache entries to export
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// Transcriptions, translations and/or analysis
+    pub categories: Vec<CacheCategory>,
+    /// Export only the transcriptions and tuning profiles of videos whose file name contains this
+    pub source: Option<String>,
+}
+
+/// Files exported or imported, by kind
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ArchiveReport {
+    pub archive: PathBuf,
+    pub files: BTreeMap<String, usize>,
+    /// Entries already in the local cache, left as they were
+    pub skipped: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveManifest {
+    format: u32,
+    /// Version of shuro that wrote the archive
+    created_by: String,
+    files: BTreeMap<String, usize>,
+}
+
+/// Bundle the cache entries selected by `options` into a gzipped tarball at `archive_path`
+pub fn export_cache(config: &Config, archive_path: &Path, options: &ExportOptions) -> Result<ArchiveReport> {
+    let files = select_files(config, options)?;
+    let mut report = ArchiveReport { archive: archive_path.to_path_buf(), ..ArchiveReport::default() };
+    for (kind, _) in &files {
+        *report.files.entry(kind.name().to_string()).or_default() += 1;
+    }
+
+    let archive = File::create(archive_path)
+        .map_err(|e| ShuroError::Cache(format!("Failed to create {}: {}", archive_path.display(), e)))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(archive, Compression::default()));
+    let manifest = serde_json::to_vec_pretty(&ArchiveManifest {
+        format: ARCHIVE_FORMAT,
+        created_by: env!("CARGO_PKG_VERSION").to_string(),
+        files: report.files.clone(),
+    })?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, MANIFEST, manifest.as_slice())?;
+    for (kind, path) in &files {
+        let name = Path::new(kind.name()).join(path.file_name().unwrap_or_default());
+        builder.append_path_with_name(path, &name)?;
+    }
+    builder.into_inner()?.finish()?;
+
+    info!("📦 Exported {} cache files to {}", files.len(), archive_path.display());
+    Ok(report)
+}
+
+/// Unpack a cache archive into the local caches, keeping entries that already exist
+pub fn import_cache(config: &Config, archive_path: &Path) -> Result<ArchiveReport> {
+    let archive = File::open(archive_path)
+        .map_err(|e| ShuroError::Cache(format!("Failed to open {}: {}", archive_path.display(), e)))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(archive));
+    let mut report = ArchiveReport { archive: archive_path.to_path_buf(), ..ArchiveReport::default() };
+    let transcriptions_dir = std::path::absolute(ArchiveKind::Transcriptions.dir(config))?;
+
+    let mut manifest = None;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        if manifest.is_none() {
+            if path != Path::new(MANIFEST) {
+                return Err(ShuroError::Cache(format!("{} is not a shuro cache archive", archive_path.display())));
+            }
+            let read: ArchiveManifest = serde_json::from_reader(&mut entry)?;
+            if read.format > ARCHIVE_FORMAT {
+                return Err(ShuroError::Cache(format!(
+                    "{} was written by shuro {} in a newer archive format; upgrade to import it",
+                    archive_path.display(), read.created_by
+                )));
+            }
+            manifest = Some(read);
+            continue;
+        }
+
+        let (kind, name) = archive_entry(&path)
+            .ok_or_else(|| ShuroError::Cache(format!("Unexpected entry {} in {}", path.display(), archive_path.display())))?;
+        let dir = kind.dir(config);
+        let target = dir.join(name);
+        if target.exists() {
+            debug!("Keeping cached {}", target.display());
+            report.skipped += 1;
+            continue;
+        }
+        std::fs::create_dir_all(&dir)?;
+        if kind == ArchiveKind::Fingerprints {
+            // Point the entry at the transcription as imported into this cache
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+            let mut fingerprint: FingerprintEntry = serde_json::from_str(&content)?;
+            fingerprint.transcription = transcriptions_dir.join(fingerprint.transcription.file_name().unwrap_or_default());
+            std::fs::write(&target, serde_json::to_string(&fingerprint)?)?;
+        } else {
+            entry.unpack(&target)?;
+        }
+        *report.files.entry(kind.name().to_string()).or_default() += 1;
+    }
+    if manifest.is_none() {
+        return Err(ShuroError::Cache(format!("{} is not a shuro cache archive", archive_path.display())));
+    }
+
+    info!("📦 Imported {} cache files from {} ({} already cached)",
+        report.files.values().sum::<usize>(), archive_path.display(), report.skipped);
+    Ok(report)
+}
+
+/// Kind and file name of an archived file; `None` for anything but `<kind>/<file name>`
+fn archive_entry(path: &Path) -> Option<(ArchiveKind, &str)> {
+    let mut components = path.components();
+    let (Some(Component::Normal(kind)), Some(Component::Normal(name)), None) =
+        (components.next(), components.next(), components.next())
+    else {
+        return None;
+    };
+    let kind = ArchiveKind::ALL.into_iter().find(|k| kind.to_str() == Some(k.name()))?;
+    Some((kind, name.to_str()?))
+}
+
+/// Files to export with their kinds
+fn select_files(config: &Config, options: &ExportOptions) -> Result<Vec<(ArchiveKind, PathBuf)>> {
+    let matches = |name: &str| options.source.as_ref().is_none_or(|source| name.contains(source.as_str()));
+    let mut files = Vec::new();
+    for category in &options.categories {
+        match category {
+            CacheCategory::Transcriptions => {
+                let fingerprints: Vec<(PathBuf, FingerprintEntry)> = json_files(&ArchiveKind::Fingerprints.dir(config))
+                    .into_iter()
+                    .filter_map(|path| {
+                        let entry = serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+                        Some((path, entry))
+                    })
+                    .collect();
+                let transcriptions = json_files(&ArchiveKind::Transcriptions.dir(config));
+                if options.source.is_none() {
+                    files.extend(transcriptions.into_iter().map(|path| (ArchiveKind::Transcriptions, path)));
+                }
+                for (path, entry) in fingerprints.into_iter().filter(|(_, entry)| matches(&entry.source)) {
+                    let transcription = ArchiveKind::Transcriptions.dir(config).join(entry.transcription.file_name().unwrap_or_default());
+                    if options.source.is_some() && transcription.exists() {
+                        files.push((ArchiveKind::Transcriptions, transcription));
+                    }
+                    files.push((ArchiveKind::Fingerprints, path));
+                }
+                for path in json_files(&ArchiveKind::Tuning.dir(config)) {
+                    let source = std::fs::read_to_string(&path).ok()
+                        .and_then(|content| serde_json::from_str::<TuningProfile>(&content).ok())
+                        .map(|profile| profile.source);
+                    if source.is_some_and(|source| matches(&source)) {
+                        files.push((ArchiveKind::Tuning, path));
+                    }
+                }
+            }
+            CacheCategory::Translations => {
+                files.extend(json_files(&ArchiveKind::Translations.dir(config)).into_iter().map(|path| (ArchiveKind::Translations, path)));
+            }
+            CacheCategory::Analysis => {
+                files.extend(json_files(&ArchiveKind::Analysis.dir(config)).into_iter().map(|path| (ArchiveKind::Analysis, path)));
+            }
+            CacheCategory::Audio => {
+                return Err(ShuroError::Config("Extracted audio is not exported; choose transcriptions, translations or analysis".to_string()));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// JSON files directly in `dir`, sorted
+fn json_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcribe::fingerprint::AudioFingerprint;
+
+    fn config_in(dir: &Path) -> Config {
+        let mut config = Config::default();
+        config.use_work_dir(dir);
+        config
+    }
+
+    #[test]
+    fn test_export_and_import() {
+        let (here, there) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let (source_config, target_config) = (config_in(here.path()), config_in(there.path()));
+
+        let transcriptions = ArchiveKind::Transcriptions.dir(&source_config);
+        let fingerprints = ArchiveKind::Fingerprints.dir(&source_config);
+        let translations = ArchiveKind::Translations.dir(&source_config);
+        for dir in [&transcriptions, &fingerprints, &translations] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        for (episode, hash) in [("Show.S01E01.mkv", "tuned_e01"), ("Other.S01E01.mkv", "tuned_x01")] {
+            let transcription = transcriptions.join(format!("{}.json", hash));
+            std::fs::write(&transcription, "{}").unwrap();
+            let entry = FingerprintEntry {
+                source: episode.to_string(),
+                settings: "settings".to_string(),
+                transcription,
+                fingerprint: AudioFingerprint { duration: 1.0, frames: vec![1, 2, 3] },
+            };
+            std::fs::write(fingerprints.join(format!("{}.json", hash)), serde_json::to_string(&entry).unwrap()).unwrap();
+        }
+        std::fs::write(translations.join("0123456789abcdef.json"), "{}").unwrap();
+
+        let archive = here.path().join("cache.tar.gz");
+        let options = ExportOptions {
+            categories: vec![CacheCategory::Transcriptions, CacheCategory::Translations],
+            source: Some("Show.".to_string()),
+        };
+        let exported = export_cache(&source_config, &archive, &options).unwrap();
+        assert_eq!(exported.files, BTreeMap::from([
+            ("fingerprints".to_string(), 1),
+            ("transcriptions".to_string(), 1),
+            ("translations".to_string(), 1),
+        ]));
+
+        let imported = import_cache(&target_config, &archive).unwrap();
+        assert_eq!(imported.files, exported.files);
+        let local = ArchiveKind::Transcriptions.dir(&target_config).join("tuned_e01.json");
+        assert!(local.exists());
+        assert!(!ArchiveKind::Transcriptions.dir(&target_config).join("tuned_x01.json").exists());
+        let content = std::fs::read_to_string(ArchiveKind::Fingerprints.dir(&target_config).join("tuned_e01.json")).unwrap();
+        let entry: FingerprintEntry = serde_json::from_str(&content).unwrap();
+        assert_eq!(entry.transcription, std::path::absolute(&local).unwrap());
+
+        // A second import keeps what is already there
+        let again = import_cache(&target_config, &archive).unwrap();
+        assert!(again.files.is_empty());
+        assert_eq!(again.skipped, 3);
+
+        assert!(archive_entry(Path::new("../transcriptions/x.json")).is_none());
+        assert!(archive_entry(Path::new("tuning/a/b.json")).is_none());
+    }
+}
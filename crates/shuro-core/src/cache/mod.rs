@@ -0,0 +1,293 @@
+// Cache size limits
+//
+// The caches grow with every video, the extracted audio alone by about 115 MB per hour of input.
+// `cache.max_cache_size_mb` caps each category: extracted audio, transcriptions, translations and
+// the analysis journals of the llm translation mode. After every `process`, `batch` and
+// `translate` run, a category over its limit loses its least recently used entries until it
+// fits; `shuro cache prune` does the same on demand, and with `--max-size` prunes all categories
+// together to one limit instead. `shuro cache clean --days N` removes the entries of every category
+// not used for N days, and `--dry-run` lists them without removing anything.
+//
+// An entry (a file, or a directory as a whole) was last used at the later of its access and
+// modification times. Cache hits set the access time of their file with `touch`, as mounts with
+// `noatime` or `relatime` would not. The translation memory, tuning profiles and fingerprints are
+// not caches and are never evicted. `shuro cache export` and `import` share caches between
+// machines (see archive.rs).
+
+mod archive;
+
+pub use archive::{ArchiveReport, ExportOptions, export_cache, import_cache};
+
+use serde::Serialize;
+use std::fs::{File, FileTimes};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, info, warn};
+
+use crate::config::{CacheLimits, Config};
+
+/// Bytes per MB of the configured limits
+const MB: u64 = 1024 * 1024;
+
+/// Kind of cached data, each with its own directory and size limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheCategory {
+    Audio,
+    Transcriptions,
+    Translations,
+    Analysis,
+}
+
+impl CacheCategory {
+    pub const ALL: [Self; 4] = [Self::Audio, Self::Transcriptions, Self::Translations, Self::Analysis];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Audio => "audio",
+            Self::Transcriptions => "transcriptions",
+            Self::Translations => "translations",
+            Self::Analysis => "analysis",
+        }
+    }
+
+    /// Directory holding the category's entries
+    pub fn dir(&self, config: &Config) -> PathBuf {
+        match self {
+            Self::Audio => Path::new(&config.transcriber.cache_dir).join("audio"),
+            Self::Transcriptions => Path::new(&config.transcriber.cache_dir).join("transcriptions"),
+            Self::Translations => Path::new(&config.translate.cache_dir).join("translations"),
+            Self::Analysis => Path::new(&config.translate.cache_dir).join("analysis"),
+        }
+    }
+
+    /// Configured limit in MB, 0 for none
+    pub fn limit_mb(&self, limits: &CacheLimits) -> u64 {
+        match self {
+            Self::Audio => limits.audio,
+            Self::Transcriptions => limits.transcriptions,
+            Self::Translations => limits.translations,
+            Self::Analysis => limits.analysis,
+        }
+    }
+}
+
+/// File or directory in a cache, evicted as a whole
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheEntry {
+    pub path: PathBuf,
+    /// Bytes, of every file below a directory
+    pub size: u64,
+    pub last_used: SystemTime,
+}
+
+/// What pruning or cleaning a category, or all of them together, removed
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PruneReport {
+    /// Category name, `all` when pruned together
+    pub category: String,
+    pub removed: usize,
+    /// Bytes freed
+    pub freed: u64,
+    /// Bytes left in the cache
+    pub remaining: u64,
+    /// Removed entries, or those that would be removed in a dry run
+    pub paths: Vec<PathBuf>,
+}
+
+/// Mark a cache file as used now, for eviction by last use
+pub fn touch(path: &Path) {
+    let touched = File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_times(FileTimes::new().set_accessed(SystemTime::now())));
+    if let Err(e) = touched {
+        debug!("Could not mark cache entry {} as used: {}", path.display(), e);
+    }
+}
+
+/// Entries directly in `dir`, none when it does not exist
+pub fn entries(dir: &Path) -> Vec<CacheEntry> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    read_dir
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let (size, last_used) = usage(&e.path())?;
+            Some(CacheEntry { path: e.path(), size, last_used })
+        })
+        .collect()
+}
+
+/// Size and last use of a file, or of everything below a directory
+fn usage(path: &Path) -> Option<(u64, SystemTime)> {
+    let metadata = std::fs::symlink_metadata(path).ok()?;
+    let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+    let accessed = metadata.accessed().unwrap_or(SystemTime::UNIX_EPOCH);
+    let mut size = metadata.len();
+    let mut last_used = modified.max(accessed);
+    if metadata.is_dir() {
+        size = 0;
+        for entry in std::fs::read_dir(path).ok()?.filter_map(|e| e.ok()) {
+            if let Some((entry_size, entry_used)) = usage(&entry.path()) {
+                size += entry_size;
+                last_used = last_used.max(entry_used);
+            }
+        }
+    }
+    Some((size, last_used))
+}
+
+/// Entries to evict, least recently used first, so the others fit in `max_bytes`
+pub fn select_evictions(mut entries: Vec<CacheEntry>, max_bytes: u64) -> Vec<CacheEntry> {
+    let mut total: u64 = entries.iter().map(|e| e.size).sum();
+    entries.sort_by(|a, b| a.last_used.cmp(&b.last_used).then_with(|| a.path.cmp(&b.path)));
+    entries
+        .into_iter()
+        .take_while(|entry| {
+            let over = total > max_bytes;
+            if over {
+                total -= entry.size;
+            }
+            over
+        })
+        .collect()
+}
+
+/// Entries last used before `cutoff`
+pub fn select_stale(entries: Vec<CacheEntry>, cutoff: SystemTime) -> Vec<CacheEntry> {
+    let mut stale: Vec<CacheEntry> = entries.into_iter().filter(|e| e.last_used < cutoff).collect();
+    stale.sort_by(|a, b| a.last_used.cmp(&b.last_used).then_with(|| a.path.cmp(&b.path)));
+    stale
+}
+
+/// Evict the least recently used entries of `dirs`, together, until they fit in `max_bytes`
+pub fn prune(category: &str, dirs: &[PathBuf], max_bytes: u64) -> PruneReport {
+    let entries: Vec<CacheEntry> = dirs.iter().flat_map(|dir| entries(dir)).collect();
+    let total = entries.iter().map(|e| e.size).sum();
+    remove(category, total, select_evictions(entries, max_bytes), false)
+}
+
+/// Remove the entries of `dirs` not used for `max_age`; with `dry_run`, only report them
+pub fn clean(category: &str, dirs: &[PathBuf], max_age: Duration, dry_run: bool) -> PruneReport {
+    let entries: Vec<CacheEntry> = dirs.iter().flat_map(|dir| entries(dir)).collect();
+    let total = entries.iter().map(|e| e.size).sum();
+    let cutoff = SystemTime::now().checked_sub(max_age).unwrap_or(SystemTime::UNIX_EPOCH);
+    remove(category, total, select_stale(entries, cutoff), dry_run)
+}
+
+/// Remove `selected` from a cache category holding `total` bytes
+fn remove(category: &str, total: u64, selected: Vec<CacheEntry>, dry_run: bool) -> PruneReport {
+    let mut report = PruneReport { category: category.to_string(), remaining: total, ..PruneReport::default() };
+    for entry in selected {
+        let removed = match dry_run {
+            true => Ok(()),
+            false if entry.path.is_dir() => std::fs::remove_dir_all(&entry.path),
+            false => std::fs::remove_file(&entry.path),
+        };
+        match removed {
+            Ok(()) => {
+                if !dry_run {
+                    debug!("Evicted {} from the {} cache", entry.path.display(), category);
+                }
+                report.removed += 1;
+                report.freed += entry.size;
+                report.remaining -= entry.size;
+                report.paths.push(entry.path);
+            }
+            Err(e) => warn!("Failed to evict {} from the {} cache: {}", entry.path.display(), category, e),
+        }
+    }
+    report
+}
+
+/// Prune every category to its configured limit, or, with `max_size_mb`, all of them together
+/// to that size
+pub fn prune_caches(config: &Config, max_size_mb: Option<u64>) -> Vec<PruneReport> {
+    if let Some(max_size_mb) = max_size_mb {
+        let dirs: Vec<PathBuf> = CacheCategory::ALL.iter().map(|c| c.dir(config)).collect();
+        return vec![prune("all", &dirs, max_size_mb * MB)];
+    }
+    CacheCategory::ALL
+        .iter()
+        .filter_map(|category| {
+            let limit = category.limit_mb(&config.cache.max_cache_size_mb);
+            (limit > 0).then(|| prune(category.name(), &[category.dir(config)], limit * MB))
+        })
+        .collect()
+}
+
+/// Clean every category of the entries not used for `days` days
+pub fn clean_caches(config: &Config, days: u64, dry_run: bool) -> Vec<PruneReport> {
+    let max_age = Duration::from_secs(days * 24 * 60 * 60);
+    CacheCategory::ALL
+        .iter()
+        .map(|category| clean(category.name(), &[category.dir(config)], max_age, dry_run))
+        .collect()
+}
+
+/// Enforce the configured limits after a run, logging what was evicted
+pub fn enforce_limits(config: &Config) {
+    for report in prune_caches(config, None) {
+        if report.removed > 0 {
+            info!("🧹 Evicted {} least recently used entries ({:.1} MB) from the {} cache, {:.1} MB left",
+                report.removed,
+                report.freed as f64 / MB as f64,
+                report.category,
+                report.remaining as f64 / MB as f64
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, size: u64, used: u64) -> CacheEntry {
+        CacheEntry {
+            path: PathBuf::from(name),
+            size,
+            last_used: SystemTime::UNIX_EPOCH + Duration::from_secs(used),
+        }
+    }
+
+    #[test]
+    fn test_select_evictions() {
+        let entries = vec![entry("c.wav", 300, 30), entry("a.wav", 500, 10), entry("b.wav", 200, 20)];
+        let names = |evicted: Vec<CacheEntry>| -> Vec<PathBuf> { evicted.into_iter().map(|e| e.path).collect() };
+        assert!(select_evictions(entries.clone(), 1000).is_empty());
+        assert_eq!(names(select_evictions(entries.clone(), 600)), vec![PathBuf::from("a.wav")]);
+        assert_eq!(names(select_evictions(entries.clone(), 300)), vec![PathBuf::from("a.wav"), PathBuf::from("b.wav")]);
+        assert_eq!(select_evictions(entries, 0).len(), 3);
+    }
+
+    #[test]
+    fn test_select_stale() {
+        let entries = vec![entry("c.wav", 300, 30), entry("a.wav", 500, 10), entry("b.wav", 200, 20)];
+        let cutoff = SystemTime::UNIX_EPOCH + Duration::from_secs(25);
+        let stale: Vec<PathBuf> = select_stale(entries, cutoff).into_iter().map(|e| e.path).collect();
+        assert_eq!(stale, vec![PathBuf::from("a.wav"), PathBuf::from("b.wav")]);
+    }
+
+    #[test]
+    fn test_prune_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old.json");
+        let recent = dir.path().join("recent.json");
+        std::fs::write(&old, vec![b'x'; 600]).unwrap();
+        std::fs::write(&recent, vec![b'x'; 600]).unwrap();
+        let long_ago = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        File::options().write(true).open(&old).unwrap()
+            .set_times(FileTimes::new().set_accessed(long_ago).set_modified(long_ago)).unwrap();
+
+        let dry_run = clean("translations", &[dir.path().to_path_buf()], Duration::from_secs(3600), true);
+        assert_eq!(dry_run.paths, vec![old.clone()]);
+        assert!(old.exists());
+
+        let report = prune("translations", &[dir.path().to_path_buf()], 1000);
+        assert_eq!((report.removed, report.freed, report.remaining), (1, 600, 600));
+        assert!(!old.exists());
+        assert!(recent.exists());
+    }
+}
@@ -0,0 +1,109 @@
+//! Cancellation and deadlines for long-running jobs
+//!
+//! A `Cancellation` is passed to every transcriber, translator and media processor call.
+//! Work is aborted when the token is cancelled or the deadline passes; child processes are
+//! spawned with `kill_on_drop` so aborting a stage also stops its ffmpeg/whisper process.
+
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use crate::error::{Result, ShuroError};
+
+/// Cancellation token with an optional deadline
+#[derive(Debug, Clone, Default)]
+pub struct Cancellation {
+    token: CancellationToken,
+    deadline: Option<Instant>,
+}
+
+impl Cancellation {
+    /// Create a token that is never cancelled unless `cancel` is called
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a child that is cancelled together with this token but can also be cancelled alone
+    pub fn child(&self) -> Self {
+        Self {
+            token: self.token.child_token(),
+            deadline: self.deadline,
+        }
+    }
+
+    /// Return a copy whose deadline is at most `timeout` from now
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        let deadline = Instant::now() + timeout;
+        Self {
+            token: self.token.clone(),
+            deadline: Some(self.deadline.map_or(deadline, |d| d.min(deadline))),
+        }
+    }
+
+    /// Cancel this token and all of its children
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// Whether the token was cancelled (a passed deadline is not reported here)
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Fail if the token was cancelled or the deadline has passed
+    pub fn check(&self) -> Result<()> {
+        if self.token.is_cancelled() {
+            return Err(ShuroError::Cancelled);
+        }
+        if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            return Err(ShuroError::DeadlineExceeded);
+        }
+        Ok(())
+    }
+
+    /// Run a future until it completes, the token is cancelled, or the deadline passes
+    pub async fn run<F, T>(&self, future: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        self.check()?;
+
+        let deadline = async {
+            match self.deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            biased;
+            _ = self.token.cancelled() => Err(ShuroError::Cancelled),
+            _ = deadline => Err(ShuroError::DeadlineExceeded),
+            result = future => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_stops_on_cancel_and_deadline() {
+        let parent = Cancellation::new();
+        let child = parent.child();
+        parent.cancel();
+        let result = child.run(async { Ok(()) }).await;
+        assert!(matches!(result, Err(ShuroError::Cancelled)));
+
+        let timed = Cancellation::new().with_timeout(Duration::from_millis(10));
+        let result = timed.run(async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        }).await;
+        assert!(matches!(result, Err(ShuroError::DeadlineExceeded)));
+
+        assert_eq!(Cancellation::new().run(async { Ok(7) }).await.unwrap(), 7);
+    }
+}
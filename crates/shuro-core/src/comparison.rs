@@ -0,0 +1,279 @@
+// Multi-language comparison report
+//
+// A run translating into several languages writes `{stem}.comparison.md` next to the outputs, so
+// reviewers can tell which translations need a human pass first. Each translated segment is
+// checked for the problems that can be recognized without a model (empty, left untranslated,
+// meta-commentary instead of a translation, longer than the language pair allows, missing the
+// required rendering of a mandatory glossary term). The report
+// lists the languages in review order (highest share of flagged segments first, then retries
+// per segment), the distribution of translation lengths relative to the expected length, and the
+// segments flagged in more than one language, which usually point at the transcription rather
+// than at a translation.
+
+use std::collections::BTreeMap;
+
+use crate::config::{GlossaryTerm, LengthValidationConfig};
+use crate::media::same_language;
+use crate::quality::{Transcription, glossary_violations, is_translation_too_long, relative_translation_length};
+use crate::translate::guard::meta_commentary;
+
+/// Upper bounds of the relative length buckets; the last bucket is open
+const LENGTH_BUCKETS: [f64; 4] = [0.5, 0.8, 1.25, 2.0];
+
+/// Problem found in a translated segment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SegmentFlag {
+    Empty,
+    Untranslated,
+    MetaCommentary,
+    TooLong,
+    /// A mandatory glossary term is not translated as required
+    Glossary,
+}
+
+impl SegmentFlag {
+    pub const ALL: [SegmentFlag; 5] = [Self::Empty, Self::Untranslated, Self::MetaCommentary, Self::TooLong, Self::Glossary];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Empty => "Empty",
+            Self::Untranslated => "Untranslated",
+            Self::MetaCommentary => "Meta-commentary",
+            Self::TooLong => "Too long",
+            Self::Glossary => "Glossary",
+        }
+    }
+}
+
+/// First problem found in the translation of `source`, if any
+pub fn flag_segment(
+    config: &LengthValidationConfig,
+    glossary: &[GlossaryTerm],
+    source: &str,
+    translation: &str,
+    source_language: &str,
+    target_language: &str,
+) -> Option<SegmentFlag> {
+    let (source, translation) = (source.trim(), translation.trim());
+    if source.is_empty() {
+        None
+    } else if translation.is_empty() {
+        Some(SegmentFlag::Empty)
+    } else if translation == source
+        && !same_language(source_language, target_language)
+        && source.chars().any(char::is_alphabetic) {
+        Some(SegmentFlag::Untranslated)
+    } else if meta_commentary(source, translation).is_some() {
+        Some(SegmentFlag::MetaCommentary)
+    } else if is_translation_too_long(config, source, translation, source_language, target_language) {
+        Some(SegmentFlag::TooLong)
+    } else if !glossary_violations(glossary, source, translation, target_language).is_empty() {
+        Some(SegmentFlag::Glossary)
+    } else {
+        None
+    }
+}
+
+/// Quality summary of one target language
+#[derive(Debug, Clone)]
+pub struct LanguageQuality {
+    pub language: String,
+    pub segments: usize,
+    /// Translation attempts rejected and retried by the translator
+    pub retries: usize,
+    /// Flag of each flagged segment, by segment index
+    pub flagged: BTreeMap<usize, SegmentFlag>,
+    /// Segments per relative length bucket (see `LENGTH_BUCKETS`)
+    pub length_buckets: [usize; 5],
+    pub median_length: Option<f64>,
+}
+
+impl LanguageQuality {
+    pub fn flagged_share(&self) -> f64 {
+        self.flagged.len() as f64 / self.segments.max(1) as f64
+    }
+
+    fn count(&self, flag: SegmentFlag) -> usize {
+        self.flagged.values().filter(|f| **f == flag).count()
+    }
+}
+
+/// Segment flagged in more than one language
+#[derive(Debug, Clone, PartialEq)]
+pub struct SharedFlag {
+    pub index: usize,
+    pub start: f64,
+    pub source: String,
+    pub languages: Vec<String>,
+}
+
+/// Side-by-side quality of the translations of one transcription
+#[derive(Debug, Clone)]
+pub struct ComparisonReport {
+    pub source_language: String,
+    pub segments: usize,
+    /// Languages in review order
+    pub languages: Vec<LanguageQuality>,
+    pub shared: Vec<SharedFlag>,
+}
+
+impl ComparisonReport {
+    /// Compare the `(language, translation, retries)` of `source`; translations hold the
+    /// translated segments of `source` in the same order
+    pub fn new(
+        config: &LengthValidationConfig,
+        glossary: &[GlossaryTerm],
+        source: &Transcription,
+        translations: &[(String, Transcription, usize)],
+    ) -> Self {
+        let mut languages: Vec<LanguageQuality> = translations
+            .iter()
+            .map(|(language, translation, retries)| {
+                let mut flagged = BTreeMap::new();
+                let mut lengths = Vec::new();
+                for (index, (original, translated)) in source.segments.iter().zip(&translation.segments).enumerate() {
+                    if let Some(flag) = flag_segment(config, glossary, &original.text, &translated.text, &source.language, language) {
+                        flagged.insert(index, flag);
+                    }
+                    lengths.extend(relative_translation_length(config, &original.text, &translated.text, &source.language, language));
+                }
+
+                let mut length_buckets = [0; 5];
+                for length in &lengths {
+                    length_buckets[LENGTH_BUCKETS.iter().take_while(|bound| length >= bound).count()] += 1;
+                }
+                lengths.sort_by(f64::total_cmp);
+                LanguageQuality {
+                    language: language.clone(),
+                    segments: translation.segments.len(),
+                    retries: *retries,
+                    flagged,
+                    length_buckets,
+                    median_length: lengths.get(lengths.len() / 2).copied(),
+                }
+            })
+            .collect();
+        languages.sort_by(|a, b| {
+            let retry_rate = |l: &LanguageQuality| l.retries as f64 / l.segments.max(1) as f64;
+            b.flagged_share().total_cmp(&a.flagged_share())
+                .then(retry_rate(b).total_cmp(&retry_rate(a)))
+                .then(a.language.cmp(&b.language))
+        });
+
+        let shared = source.segments
+            .iter()
+            .enumerate()
+            .filter_map(|(index, segment)| {
+                let flagged_in: Vec<String> = languages.iter()
+                    .filter(|l| l.flagged.contains_key(&index))
+                    .map(|l| l.language.clone())
+                    .collect();
+                (flagged_in.len() > 1).then(|| SharedFlag {
+                    index,
+                    start: segment.start,
+                    source: segment.text.trim().to_string(),
+                    languages: flagged_in,
+                })
+            })
+            .collect();
+
+        Self { source_language: source.language.clone(), segments: source.segments.len(), languages, shared }
+    }
+
+    /// Markdown report
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "# Translation comparison\n\n{} segments translated from {} into {} languages, listed in review order \
+             (most flagged segments first).\n\n",
+            self.segments, self.source_language, self.languages.len()
+        );
+
+        out.push_str("| Language | Flagged | Retries |");
+        for flag in SegmentFlag::ALL {
+            out.push_str(&format!(" {} |", flag.label()));
+        }
+        out.push_str(" Median length |\n|---|---|---|");
+        out.push_str(&"---|".repeat(SegmentFlag::ALL.len() + 1));
+        out.push('\n');
+        for language in &self.languages {
+            out.push_str(&format!(
+                "| {} | {} ({:.1}%) | {} |",
+                language.language, language.flagged.len(), language.flagged_share() * 100.0, language.retries
+            ));
+            for flag in SegmentFlag::ALL {
+                out.push_str(&format!(" {} |", language.count(flag)));
+            }
+            match language.median_length {
+                Some(median) => out.push_str(&format!(" {:.2} |\n", median)),
+                None => out.push_str(" - |\n"),
+            }
+        }
+
+        out.push_str(
+            "\n## Length distribution\n\nSegments by translation length relative to the length expected for the \
+             language pair.\n\n| Language | < 0.5 | 0.5-0.8 | 0.8-1.25 | 1.25-2 | ≥ 2 |\n|---|---|---|---|---|---|\n",
+        );
+        for language in &self.languages {
+            out.push_str(&format!("| {} |", language.language));
+            for count in language.length_buckets {
+                out.push_str(&format!(" {} |", count));
+            }
+            out.push('\n');
+        }
+
+        out.push_str("\n## Segments flagged in several languages\n\n");
+        if self.shared.is_empty() {
+            out.push_str("None.\n");
+        } else {
+            out.push_str("Usually a transcription problem rather than a translation one.\n\n| # | Time | Source | Languages |\n|---|---|---|---|\n");
+            for shared in &self.shared {
+                out.push_str(&format!(
+                    "| {} | {} | {} | {} |\n",
+                    shared.index + 1, format_time(shared.start), shared.source.replace('|', "\\|"), shared.languages.join(", ")
+                ));
+            }
+        }
+        out
+    }
+}
+
+/// `hh:mm:ss` of a time in seconds
+fn format_time(seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u64;
+    format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::parse_srt;
+
+    #[test]
+    fn test_comparison_report() {
+        let srt = |texts: [&str; 3], language: &str| {
+            let content: String = texts.iter().enumerate()
+                .map(|(i, text)| format!("{}\n00:00:0{},000 --> 00:00:0{},500\n{}\n\n", i + 1, i * 2, i * 2, text))
+                .collect();
+            parse_srt(&content, language).unwrap()
+        };
+        let source = srt(["Good morning.", "Whoa whoa whoa.", "See you tomorrow."], "en");
+        let translations = vec![
+            ("ja".to_string(), srt(["おはよう。", "Whoa whoa whoa.", "また明日。"], "ja"), 0),
+            ("fr".to_string(), srt(["Bonjour.", "Whoa whoa whoa.", "Here is the translation: À demain."], "fr"), 3),
+        ];
+
+        let report = ComparisonReport::new(&LengthValidationConfig::default(), &[], &source, &translations);
+        assert_eq!(report.languages.iter().map(|l| l.language.as_str()).collect::<Vec<_>>(), vec!["fr", "ja"]);
+        assert_eq!(report.languages[0].flagged.get(&2), Some(&SegmentFlag::MetaCommentary));
+        assert_eq!(report.shared, vec![SharedFlag {
+            index: 1,
+            start: 2.0,
+            source: "Whoa whoa whoa.".to_string(),
+            languages: vec!["fr".to_string(), "ja".to_string()],
+        }]);
+
+        let rendered = report.render();
+        assert!(rendered.contains("| fr | 2 (66.7%) | 3 | 0 | 1 | 1 | 0 |"));
+        assert!(rendered.contains("| 2 | 00:00:02 | Whoa whoa whoa. | fr, ja |"));
+    }
+}
@@ -0,0 +1,2037 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use crate::error::{Result, ShuroError};
+use crate::style::SubtitleStyle;
+use crate::translate::backend;
+use crate::translate::work::WorkContext;
+
+// Default values for LLM mode configuration
+fn default_llm_window_size() -> usize {
+    6
+}
+
+fn default_llm_confidence_threshold() -> f64 {
+    0.6
+}
+
+fn default_llm_window_stride() -> usize {
+    1
+}
+
+fn default_metrics_log_interval() -> usize {
+    25
+}
+
+fn default_stream_chunk_size() -> usize {
+    10
+}
+
+fn default_partial_flush_segments() -> usize {
+    50
+}
+
+fn default_check_disk_space() -> bool {
+    true
+}
+
+fn default_disk_headroom_mb() -> u64 {
+    512
+}
+
+fn default_preflight_batch_files() -> usize {
+    5
+}
+
+fn default_audio_cache_mb() -> u64 {
+    10_240
+}
+
+fn default_webhook_timeout() -> u64 {
+    10
+}
+
+fn default_retry_max_attempts() -> u32 {
+    4
+}
+
+fn default_retry_initial_backoff_ms() -> u64 {
+    1_000
+}
+
+fn default_retry_max_backoff_ms() -> u64 {
+    30_000
+}
+
+fn default_retry_jitter() -> f64 {
+    0.25
+}
+
+fn default_retry_file_budget() -> u32 {
+    100
+}
+
+fn default_cost_currency() -> String {
+    "USD".to_string()
+}
+
+fn default_detect_work() -> bool {
+    true
+}
+
+fn default_summary_chunk_chars() -> usize {
+    12000
+}
+
+fn default_openai_base_url() -> String {
+    "https://api.openai.com/v1".to_string()
+}
+
+fn default_openai_api_key_env() -> String {
+    "OPENAI_API_KEY".to_string()
+}
+
+fn default_openai_max_retries() -> u32 {
+    5
+}
+
+fn default_openai_initial_backoff_ms() -> u64 {
+    1000
+}
+
+fn default_openai_max_backoff_ms() -> u64 {
+    60_000
+}
+
+fn default_evaluation_concurrency() -> usize {
+    4
+}
+
+fn default_translation_concurrency() -> usize {
+    4
+}
+
+fn default_adaptive_context() -> bool {
+    true
+}
+
+fn default_explore_sample_windows() -> u32 {
+    3
+}
+
+fn default_explore_sample_seconds() -> u32 {
+    120
+}
+
+fn default_structured_outputs() -> bool {
+    true
+}
+
+fn default_max_context_tokens() -> usize {
+    8192
+}
+
+fn default_provider() -> String {
+    backend::OLLAMA.to_string()
+}
+
+fn default_health_check_interval() -> u64 {
+    30
+}
+
+fn default_nlp_max_sentence_chars() -> usize {
+    800
+}
+
+fn default_adaptive_max_simple_chars() -> usize {
+    40
+}
+
+fn default_adaptive_min_confidence() -> f64 {
+    0.6
+}
+
+fn default_adaptive_escalation() -> TranslationMode {
+    TranslationMode::Context
+}
+
+fn default_adaptive_bridge_segments() -> usize {
+    1
+}
+
+fn default_max_length_ratio() -> f64 {
+    3.0
+}
+
+fn default_min_length_limit() -> usize {
+    20
+}
+
+fn default_no_speech_prob() -> f32 {
+    0.8
+}
+
+fn default_no_speech_max_compression() -> f32 {
+    1.5
+}
+
+fn default_max_compression_ratio() -> f32 {
+    3.0
+}
+
+fn default_ngram_size() -> usize {
+    3
+}
+
+fn default_max_ngram_repeats() -> usize {
+    3
+}
+
+fn default_builtin_phrases() -> bool {
+    true
+}
+
+fn default_max_chars_per_second() -> f64 {
+    30.0
+}
+
+fn default_min_chars_per_second() -> f64 {
+    0.5
+}
+
+fn default_sparse_min_duration() -> f64 {
+    10.0
+}
+
+fn default_readable_chars_per_second() -> f64 {
+    17.0
+}
+
+fn default_max_line_length() -> usize {
+    42
+}
+
+fn default_max_lines() -> usize {
+    2
+}
+
+fn default_min_cue_duration() -> f64 {
+    1.0
+}
+
+pub(crate) fn default_translation_cache_dir() -> String {
+    ".shuro/cache".to_string()
+}
+
+fn default_transcriber_cache_dir() -> String {
+    ".shuro/cache".to_string()
+}
+
+fn default_state_dir() -> String {
+    ".shuro/state".to_string()
+}
+
+fn default_temp_dir() -> String {
+    ".shuro/tmp".to_string()
+}
+
+fn default_embedding_model() -> String {
+    "nomic-embed-text".to_string()
+}
+
+fn default_semantic_examples() -> usize {
+    3
+}
+
+fn default_semantic_min_similarity() -> f64 {
+    0.75
+}
+
+fn default_memory_min_similarity() -> f64 {
+    0.95
+}
+
+fn default_notes_batch_size() -> usize {
+    20
+}
+
+fn default_transcriber_implementation() -> String {
+    "whisper_cpp".to_string()
+}
+
+fn default_word_timestamps() -> bool {
+    true
+}
+
+fn default_language_id_model() -> String {
+    "tiny".to_string()
+}
+
+fn default_fpcalc_path() -> String {
+    "fpcalc".to_string()
+}
+
+fn default_transcribe_workers() -> usize {
+    2
+}
+
+fn default_vad_noise_db() -> f64 {
+    -35.0
+}
+
+fn default_vad_min_silence() -> f64 {
+    2.0
+}
+
+fn default_vad_padding() -> f64 {
+    0.25
+}
+
+fn default_whisper_server_url() -> String {
+    "http://127.0.0.1:8178".to_string()
+}
+
+fn default_whisper_server_binary() -> String {
+    "whisper-server".to_string()
+}
+
+fn default_whisper_server_startup_timeout() -> u64 {
+    120
+}
+
+fn default_fingerprint_similarity() -> f64 {
+    0.9
+}
+
+fn default_language_id_window() -> u32 {
+    30
+}
+
+fn default_source_language() -> String {
+    "en".to_string()
+}
+
+fn default_align_cognate_length() -> usize {
+    5
+}
+
+fn default_align_duration_weight() -> f64 {
+    0.5
+}
+
+fn default_shot_scene_threshold() -> f64 {
+    0.3
+}
+
+fn default_shot_tolerance() -> f64 {
+    0.5
+}
+
+fn default_shot_gap() -> f64 {
+    0.08
+}
+
+fn default_shot_min_duration() -> f64 {
+    0.7
+}
+
+fn default_segment_min_duration() -> f64 {
+    1.0
+}
+
+fn default_segment_max_duration() -> f64 {
+    7.0
+}
+
+fn default_segment_max_chars() -> usize {
+    84
+}
+
+fn default_segment_min_gap() -> f64 {
+    0.08
+}
+
+fn default_segment_merge_gap() -> f64 {
+    1.0
+}
+
+fn default_shout_db() -> f64 {
+    8.0
+}
+
+fn default_whisper_db() -> f64 {
+    12.0
+}
+
+fn default_singing_sustain() -> f64 {
+    0.6
+}
+
+fn default_diarization_args() -> Vec<String> {
+    vec!["{audio}".to_string()]
+}
+
+fn default_hint_translator() -> bool {
+    true
+}
+
+fn default_shouting_caps() -> bool {
+    true
+}
+
+fn default_singing_notes() -> bool {
+    true
+}
+
+fn default_mark_output() -> bool {
+    true
+}
+
+fn default_whispering_label() -> String {
+    "(whispering)".to_string()
+}
+
+fn default_ffmpeg_path() -> String {
+    "ffmpeg".to_string()
+}
+
+fn default_ffprobe_path() -> String {
+    "ffprobe".to_string()
+}
+
+fn default_include_source() -> bool {
+    true
+}
+
+fn default_video_codec() -> String {
+    "libx264".to_string()
+}
+
+fn default_output_template() -> String {
+    "{stem}_{lang}".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub transcriber: TranscriberConfig,
+    pub translate: TranslateConfig,
+    pub quality: QualityConfig,
+    pub media: MediaConfig,
+    #[serde(default)]
+    pub plugins: PluginsConfig,
+    #[serde(default)]
+    pub align: AlignConfig,
+    #[serde(default)]
+    pub shots: ShotsConfig,
+    #[serde(default)]
+    pub segmentation: SegmentationConfig,
+    #[serde(default)]
+    pub diarization: DiarizationConfig,
+    #[serde(default)]
+    pub forced: ForcedConfig,
+    #[serde(default)]
+    pub bilingual: BilingualConfig,
+    #[serde(default)]
+    pub resources: ResourcesConfig,
+    #[serde(default)]
+    pub preflight: PreflightConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub webhook: WebhookConfig,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    #[serde(default)]
+    pub batch: BatchConfig,
+    #[serde(default)]
+    pub cost: CostConfig,
+    #[serde(default)]
+    pub delivery: DeliveryConfig,
+    #[serde(default)]
+    pub locale: LocaleConfig,
+    /// Directory of batch job states (see job.rs); moved by `Config::use_work_dir`
+    #[serde(skip, default = "default_state_dir")]
+    pub state_dir: String,
+    /// Directory of temporary artifacts (see temp.rs); moved by `Config::use_work_dir`
+    #[serde(skip, default = "default_temp_dir")]
+    pub temp_dir: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriberConfig {
+    /// Path to transcriber binary (e.g., whisper-cli)
+    pub binary_path: String,
+    /// Transcription mode: Simple or Tuned
+    pub mode: TranscriptionMode,
+    /// Model to use for exploration phase
+    pub explore_model: String,
+    /// Model to use for transcription
+    pub transcribe_model: String,
+    /// Acceptable source languages
+    pub acceptable_languages: String,
+    /// Fallback language when detection fails
+    pub fallback_language: String,
+    /// Number of steps for tempo exploration
+    pub explore_steps: i32,
+    /// Maximum tempo range for exploration
+    pub explore_range_max: i32,
+    /// Minimum tempo range for exploration
+    pub explore_range_min: i32,
+    /// Temperature for transcription
+    pub temperature: f32,
+    /// Registered transcriber name (built-in: whisper_cpp, whisper_server, openai; or an external plugin name)
+    #[serde(default = "default_transcriber_implementation")]
+    pub implementation: String,
+    /// whisper.cpp HTTP server used by the `whisper_server` transcriber
+    #[serde(default)]
+    pub server: WhisperServerConfig,
+    /// Silence removal before whisper runs
+    #[serde(default)]
+    pub vad: VadConfig,
+    /// Request word-level timestamps from the transcriber (used to re-time NLP translations)
+    #[serde(default = "default_word_timestamps")]
+    pub word_timestamps: bool,
+    /// Audio track to transcribe (position among the video's audio tracks); when unset, videos
+    /// with several audio tracks use the track whose detected language matches the source language
+    #[serde(default)]
+    pub audio_track: Option<usize>,
+    /// Number of windows sampled across the audio for tempo exploration (0 = the full audio)
+    #[serde(default = "default_explore_sample_windows")]
+    pub explore_sample_windows: u32,
+    /// Length in seconds of each exploration sample window (0 = the full audio)
+    #[serde(default = "default_explore_sample_seconds")]
+    pub explore_sample_seconds: u32,
+    /// Tuning profile applied instead of exploring tempos in tuned mode: the path of a video
+    /// tuned before or a profile ID (see `transcribe::tuning`)
+    #[serde(default)]
+    pub tuning_profile: Option<String>,
+    /// Reuse the tuned transcription of another file with the same audio, matched by chromaprint
+    /// fingerprint (see `transcribe::fingerprint`)
+    #[serde(default)]
+    pub fingerprint_dedupe: bool,
+    /// Path to chromaprint's fpcalc binary
+    #[serde(default = "default_fpcalc_path")]
+    pub fpcalc_path: String,
+    /// Share of matching fingerprint bits (0.0-1.0) for two files to count as the same audio
+    #[serde(default = "default_fingerprint_similarity")]
+    pub fingerprint_similarity: f64,
+    /// Language spoken in the audio, given to whisper; when unset, whisper detects the language of
+    /// each file (`--source-lang` sets it)
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Whisper model used to identify the language of each audio track
+    #[serde(default = "default_language_id_model")]
+    pub language_id_model: String,
+    /// Seconds of audio sampled from each track for language identification
+    #[serde(default = "default_language_id_window")]
+    pub language_id_window: u32,
+    /// Minutes of audio per chunk of the final whisper pass, transcribed in parallel and
+    /// stitched back together (0 = the whole audio in one pass; see `transcribe::chunking`)
+    #[serde(default)]
+    pub chunk_minutes: u32,
+    /// Whisper processes transcribing chunks at once
+    #[serde(default = "default_transcribe_workers")]
+    pub transcribe_workers: usize,
+    /// Use a text subtitle track in the source language embedded in the video as the
+    /// transcription instead of running the transcriber
+    #[serde(default)]
+    pub prefer_embedded_subs: bool,
+    /// Transcript file (see transcript.rs) used as the transcription instead of running the
+    /// transcriber; set by `process --transcript`
+    #[serde(skip)]
+    pub transcript: Option<String>,
+    /// ffmpeg used for audio extraction; taken from `media.binary_path` when the workflow is built
+    #[serde(skip, default = "default_ffmpeg_path")]
+    pub ffmpeg_path: String,
+    /// ffprobe used to list audio tracks; taken from `media.ffprobe_path` when the workflow is built
+    #[serde(skip, default = "default_ffprobe_path")]
+    pub ffprobe_path: String,
+    /// Language the audio track is chosen for; taken from `translate.source_language` when the workflow is built
+    #[serde(skip, default = "default_source_language")]
+    pub source_language: String,
+    /// Run whisper on the CPU for passes streamed to a running translation; set from `resources.gpu_scheduling`
+    #[serde(skip)]
+    pub cpu_while_translating: bool,
+    /// Directory of the transcription, audio, tuning and fingerprint caches; moved by `Config::use_work_dir`
+    #[serde(skip, default = "default_transcriber_cache_dir")]
+    pub cache_dir: String,
+    /// Directory of temporary artifacts; taken from `Config::temp_dir` when the workflow is built
+    #[serde(skip, default = "default_temp_dir")]
+    pub temp_dir: String,
+}
+
+/// Voice activity detection cutting silences out of the audio whisper transcribes (see
+/// transcribe/vad.rs)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VadConfig {
+    /// Cut long silences out before every whisper pass of the whisper_cpp and whisper_server transcribers
+    #[serde(default)]
+    pub enabled: bool,
+    /// Level (dBFS) below which audio counts as silence
+    #[serde(default = "default_vad_noise_db")]
+    pub noise_db: f64,
+    /// Seconds a silence must last to be cut out
+    #[serde(default = "default_vad_min_silence")]
+    pub min_silence: f64,
+    /// Seconds of silence kept on each side of the speech around a cut
+    #[serde(default = "default_vad_padding")]
+    pub padding: f64,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            noise_db: default_vad_noise_db(),
+            min_silence: default_vad_min_silence(),
+            padding: default_vad_padding(),
+        }
+    }
+}
+
+/// whisper.cpp server the `whisper_server` transcriber sends whisper passes to (see
+/// transcribe/whisper_server.rs)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhisperServerConfig {
+    /// Base URL of the server
+    #[serde(default = "default_whisper_server_url")]
+    pub url: String,
+    /// Start the server with `transcribe_model` on the host and port of `url` when none answers there
+    #[serde(default)]
+    pub spawn: bool,
+    /// Server binary started with `spawn`
+    #[serde(default = "default_whisper_server_binary")]
+    pub binary_path: String,
+    /// Seconds a started server may take to load its model and answer
+    #[serde(default = "default_whisper_server_startup_timeout")]
+    pub startup_timeout: u64,
+}
+
+impl Default for WhisperServerConfig {
+    fn default() -> Self {
+        Self {
+            url: default_whisper_server_url(),
+            spawn: false,
+            binary_path: default_whisper_server_binary(),
+            startup_timeout: default_whisper_server_startup_timeout(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TranscriptionMode {
+    /// Simple: Use default settings without tempo optimization
+    Simple,
+    /// Tuned: Find optimal tempo using smaller model first, then transcribe with best tempo
+    Tuned,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranslateConfig {
+    /// Backend the translation model is served by: "Ollama", "OpenAI", or a name registered in
+    /// the workflow's `BackendRegistry` (see translate/backend.rs)
+    #[serde(default = "default_provider")]
+    pub provider: String,
+    /// OpenAI-compatible API used with `provider = "OpenAI"` (see translate/openai.rs)
+    #[serde(default)]
+    pub openai: OpenAiConfig,
+    /// Ollama endpoint URL
+    pub endpoint: String,
+    /// Ollama endpoints requests are spread across; when empty, only `endpoint` is used
+    #[serde(default)]
+    pub endpoints: Vec<String>,
+    /// How requests are dispatched to `endpoints`
+    #[serde(default)]
+    pub load_balancing: LoadBalancing,
+    /// Seconds an unreachable endpoint is skipped before it is tried again
+    #[serde(default = "default_health_check_interval")]
+    pub health_check_interval: u64,
+    /// LLM model to use for translation
+    pub model: String,
+    /// Source language for translation (language code)
+    pub source_language: String,
+    /// Maximum retries for failed translations
+    pub max_retries: u32,
+    /// Constrain responses to a JSON schema (Ollama 0.5+); when false, only JSON mode is requested
+    #[serde(default = "default_structured_outputs")]
+    pub structured_outputs: bool,
+    /// Translation optimization mode
+    pub mode: TranslationMode,
+    /// Minimum gap between segments (seconds) to trigger hard stop in NLP mode
+    pub nlp_gap_threshold: f64,
+    /// Maximum context window size for context mode
+    pub context_window_size: usize,
+    /// Maximum length of a reconstructed sentence in NLP mode (characters)
+    #[serde(default = "default_nlp_max_sentence_chars")]
+    pub nlp_max_sentence_chars: usize,
+    /// Which segments Adaptive mode escalates from Simple translation
+    #[serde(default)]
+    pub adaptive: AdaptiveConfig,
+    /// Size `context_window_size`, `llm_window_size` and `nlp_max_sentence_chars` to the model's context length
+    #[serde(default = "default_adaptive_context")]
+    pub adaptive_context: bool,
+    /// Upper bound on the context requested from Ollama when `adaptive_context` is set (tokens)
+    #[serde(default = "default_max_context_tokens")]
+    pub max_context_tokens: usize,
+    /// Context requested from Ollama (`num_ctx`); set by adaptive sizing, otherwise the model default
+    #[serde(skip)]
+    pub num_ctx: Option<usize>,
+    /// Window size for LLM mode (number of segments to analyze at once)
+    #[serde(default = "default_llm_window_size")]
+    pub llm_window_size: usize,
+    /// Minimum confidence threshold for sentence boundaries in LLM mode
+    #[serde(default = "default_llm_confidence_threshold")]
+    pub llm_confidence_threshold: f64,
+    /// Segments the LLM mode analysis window advances by
+    #[serde(default = "default_llm_window_stride")]
+    pub llm_window_stride: usize,
+    /// Upper bound on analysis windows per minute of content in LLM mode; the stride grows to
+    /// stay within it (0 = unbounded)
+    #[serde(default)]
+    pub llm_max_windows_per_minute: usize,
+    /// Jump past the current window once this many consecutive windows detect no new sentence
+    /// in LLM mode (0 = never)
+    #[serde(default)]
+    pub llm_early_exit_windows: usize,
+    /// Model used to judge translation quality; defaults to `model`
+    #[serde(default)]
+    pub judge_model: Option<String>,
+    /// Number of quality evaluations run concurrently while translation proceeds
+    #[serde(default = "default_evaluation_concurrency")]
+    pub evaluation_concurrency: usize,
+    /// Number of segment translation requests in flight at once per target language in Simple
+    /// and Context mode
+    #[serde(default = "default_translation_concurrency")]
+    pub concurrency: usize,
+    /// Length validation limits; taken from `quality.length` when the workflow is built
+    #[serde(skip)]
+    pub length_validation: LengthValidationConfig,
+    /// Mention the `delivery` of segments in prompts; set from `delivery` when the workflow is built
+    #[serde(skip)]
+    pub delivery_hints: bool,
+    /// Mention the speaker of segments in prompts; set from `diarization` when the workflow is built
+    #[serde(skip)]
+    pub speaker_hints: bool,
+    /// Key Context-mode cache entries on source text, language and model only, treating the context as advisory
+    #[serde(default)]
+    pub segment_level_cache: bool,
+    /// Log per-model latency and throughput every N model requests (0 disables periodic logging)
+    #[serde(default = "default_metrics_log_interval")]
+    pub metrics_log_interval: usize,
+    /// Number of streamed segments translated together while transcription is running (0 waits for the full transcription)
+    #[serde(default = "default_stream_chunk_size")]
+    pub stream_chunk_size: usize,
+    /// Translated segments after which the in-progress `.partial.srt` is rewritten (0 = never);
+    /// flushes happen at chunk boundaries (see partial.rs)
+    #[serde(default = "default_partial_flush_segments")]
+    pub partial_flush_segments: usize,
+    /// Registered translator name; when unset, the translator registered for `mode` is used
+    #[serde(default)]
+    pub implementation: Option<String>,
+    /// Embeddings index of cached translations used to inject similar examples into prompts
+    #[serde(default)]
+    pub semantic_cache: SemanticCacheConfig,
+    /// Translation memory reused across files and runs
+    #[serde(default)]
+    pub memory: MemoryConfig,
+    /// Translator's notes written to a file next to each subtitle file
+    #[serde(default)]
+    pub notes: NotesConfig,
+    /// Directory holding the translation cache and semantic index (translation memory)
+    #[serde(default = "default_translation_cache_dir")]
+    pub cache_dir: String,
+    /// Terms that must be translated exactly as given
+    #[serde(default)]
+    pub glossary: Vec<GlossaryTerm>,
+    /// TOML or CSV file of further glossary terms, added to `glossary` when the workflow is built
+    /// (see translate/glossary.rs)
+    #[serde(default)]
+    pub glossary_file: Option<String>,
+    /// Title and synopsis sources of the background added to translation prompts
+    #[serde(default)]
+    pub work: WorkConfig,
+    /// Title and synopsis of the file being translated; set per file by the workflow (see translate/work.rs)
+    #[serde(skip)]
+    pub work_context: Option<WorkContext>,
+    /// Prompt replacing the built-in translation prompt; `{language}`, `{language_code}`,
+    /// `{notes}`, `{text}` and `{context}` are filled in, and the answer format is appended
+    #[serde(default)]
+    pub prompt_template: Option<String>,
+    /// Register translations are written in (`formality` in earlier versions)
+    #[serde(default, alias = "formality")]
+    pub tone: Tone,
+    /// Instruction on the register of translations, used with `tone = "Custom"`
+    #[serde(default)]
+    pub tone_instruction: Option<String>,
+    /// Settings of each target language, overriding the ones above (see `for_language`)
+    #[serde(default)]
+    pub languages: HashMap<String, LanguageOverrides>,
+}
+
+impl TranslateConfig {
+    /// Ollama endpoints to dispatch requests to
+    pub fn endpoint_urls(&self) -> Vec<String> {
+        if self.endpoints.is_empty() {
+            vec![self.endpoint.clone()]
+        } else {
+            self.endpoints.clone()
+        }
+    }
+
+    /// Configuration of translations into `language`: the base settings with the
+    /// `[translate.languages.<code>]` overrides of that language applied
+    pub fn for_language(&self, language: &str) -> TranslateConfig {
+        let mut config = self.clone();
+        if let Some(overrides) = self.overrides(language) {
+            if let Some(model) = &overrides.model {
+                config.model = model.clone();
+            }
+            if let Some(template) = &overrides.prompt_template {
+                config.prompt_template = Some(template.clone());
+            }
+            if let Some(tone) = overrides.tone {
+                config.tone = tone;
+            }
+            if let Some(instruction) = &overrides.tone_instruction {
+                config.tone_instruction = Some(instruction.clone());
+            }
+        }
+        config
+    }
+
+    /// Overrides configured for `language`, whatever the case of its code
+    pub fn overrides(&self, language: &str) -> Option<&LanguageOverrides> {
+        self.languages.iter().find(|(code, _)| code.eq_ignore_ascii_case(language)).map(|(_, overrides)| overrides)
+    }
+}
+
+/// Register of translations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Tone {
+    /// Whatever the model chooses
+    #[default]
+    Unspecified,
+    /// Standard polite address without honorifics or slang (desu/masu)
+    Neutral,
+    /// Polite address and formal wording (keigo, Sie, vous)
+    Formal,
+    /// Casual address and everyday wording
+    Informal,
+    /// As `tone_instruction` describes
+    Custom,
+}
+
+/// Translation settings of one target language; unset settings are the general ones
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LanguageOverrides {
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub prompt_template: Option<String>,
+    #[serde(default, alias = "formality")]
+    pub tone: Option<Tone>,
+    #[serde(default)]
+    pub tone_instruction: Option<String>,
+    /// Reading speed limit of the language's subtitles, over `quality.readability`
+    #[serde(default)]
+    pub max_chars_per_second: Option<f64>,
+    /// Line length limit of the language's subtitles, over `quality.readability`
+    #[serde(default)]
+    pub max_line_length: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAiConfig {
+    /// API base URL including the version path
+    #[serde(default = "default_openai_base_url")]
+    pub base_url: String,
+    /// API key; when unset it is read from the `api_key_env` environment variable
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Environment variable holding the API key
+    #[serde(default = "default_openai_api_key_env")]
+    pub api_key_env: String,
+    /// Retries of rate-limited (429) and failed (5xx, dropped connection) requests
+    #[serde(default = "default_openai_max_retries")]
+    pub max_retries: u32,
+    /// Wait before the first retry, doubled for every further one (milliseconds)
+    #[serde(default = "default_openai_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// Longest wait between retries (milliseconds)
+    #[serde(default = "default_openai_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+}
+
+impl Default for OpenAiConfig {
+    fn default() -> Self {
+        Self {
+            base_url: default_openai_base_url(),
+            api_key: None,
+            api_key_env: default_openai_api_key_env(),
+            max_retries: default_openai_max_retries(),
+            initial_backoff_ms: default_openai_initial_backoff_ms(),
+            max_backoff_ms: default_openai_max_backoff_ms(),
+        }
+    }
+}
+
+impl OpenAiConfig {
+    /// The configured API key, or the one in `api_key_env`; local servers need none
+    pub fn api_key(&self) -> Option<String> {
+        self.api_key.clone()
+            .or_else(|| std::env::var(&self.api_key_env).ok())
+            .filter(|key| !key.is_empty())
+    }
+}
+
+/// Dispatch strategy across Ollama endpoints
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum LoadBalancing {
+    /// Endpoints take turns
+    RoundRobin,
+    /// The endpoint with the fewest requests in flight
+    #[default]
+    LeastBusy,
+}
+
+/// Title, synopsis and summary of the translated work (see translate/work.rs)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkConfig {
+    /// Title of the film or series, overriding the detected one
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Synopsis, characters or setting, overriding the detected synopsis
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Detect the title and synopsis from container tags and the file name
+    #[serde(default = "default_detect_work")]
+    pub detect: bool,
+    /// Ask the translation model for a synopsis of the titled work when none is known
+    #[serde(default)]
+    pub llm_lookup: bool,
+    /// Summarize the whole transcription with the translation model before translating it
+    #[serde(default)]
+    pub summarize: bool,
+    /// Characters of transcription summarized per request; longer ones are summarized in parts
+    #[serde(default = "default_summary_chunk_chars")]
+    pub summary_chunk_chars: usize,
+}
+
+impl Default for WorkConfig {
+    fn default() -> Self {
+        Self {
+            title: None,
+            description: None,
+            detect: default_detect_work(),
+            llm_lookup: false,
+            summarize: false,
+            summary_chunk_chars: default_summary_chunk_chars(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotesConfig {
+    /// Generate time-coded translator's notes (wordplay, cultural references) for each translation
+    #[serde(default)]
+    pub enabled: bool,
+    /// File format of the notes
+    #[serde(default)]
+    pub format: NotesFormat,
+    /// Cues reviewed per request
+    #[serde(default = "default_notes_batch_size")]
+    pub batch_size: usize,
+}
+
+impl Default for NotesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: NotesFormat::default(),
+            batch_size: default_notes_batch_size(),
+        }
+    }
+}
+
+/// File format of translator's notes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum NotesFormat {
+    /// WebVTT NOTE blocks (`.notes.vtt`)
+    #[default]
+    Vtt,
+    /// JSON array of notes (`.notes.json`)
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlossaryTerm {
+    /// Term as it appears in the source language
+    pub source: String,
+    /// Whether the term is a character name
+    #[serde(default)]
+    pub character: bool,
+    /// Whether a translation leaving out the required rendering is rejected and retried, and
+    /// flagged in the comparison report when retries run out
+    #[serde(default)]
+    pub mandatory: bool,
+    /// Required translation per target language code (e.g. `ja = "..."`)
+    #[serde(flatten)]
+    pub translations: HashMap<String, String>,
+}
+
+/// Translation memory shared across files and runs (see translate/memory.rs)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryConfig {
+    /// Reuse the translations of identical or near-identical lines translated before, and record
+    /// new translations
+    #[serde(default)]
+    pub enabled: bool,
+    /// Minimum similarity of a remembered line for its translation to be reused (0.0 to 1.0;
+    /// 1.0 only reuses the same line, ignoring case and spacing)
+    #[serde(default = "default_memory_min_similarity")]
+    pub min_similarity: f64,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_similarity: default_memory_min_similarity(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticCacheConfig {
+    /// Index cached translations and retrieve similar ones as prompt examples on a cache miss
+    #[serde(default)]
+    pub enabled: bool,
+    /// Ollama embedding model used to index source segments
+    #[serde(default = "default_embedding_model")]
+    pub embedding_model: String,
+    /// Maximum number of previous translations injected as examples
+    #[serde(default = "default_semantic_examples")]
+    pub examples: usize,
+    /// Minimum cosine similarity for a previous translation to be used as an example
+    #[serde(default = "default_semantic_min_similarity")]
+    pub min_similarity: f64,
+}
+
+impl Default for SemanticCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            embedding_model: default_embedding_model(),
+            examples: default_semantic_examples(),
+            min_similarity: default_semantic_min_similarity(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TranslationMode {
+    /// Simple: Translate each segment individually without context
+    Simple,
+    /// Context: Use surrounding segments as context but only translate the target segment
+    Context,
+    /// NLP: Reconstruct complete sentences using natural language processing
+    Nlp,
+    /// LLM: Use sliding window approach with LLM to split segments by contextual sentences
+    Llm,
+    /// Adaptive: Simple translation for short, confidently transcribed segments; long or
+    /// uncertain stretches are escalated to Context or NLP translation
+    Adaptive,
+}
+
+impl TranslationMode {
+    /// Name under which the built-in translator for this mode is registered
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Simple => "simple",
+            Self::Context => "context",
+            Self::Nlp => "nlp",
+            Self::Llm => "llm",
+            Self::Adaptive => "adaptive",
+        }
+    }
+}
+
+/// Segment selection of Adaptive translation mode (see translate/adaptive.rs)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdaptiveConfig {
+    /// Segments longer than this are escalated (characters)
+    #[serde(default = "default_adaptive_max_simple_chars")]
+    pub max_simple_chars: usize,
+    /// Segments transcribed with a lower confidence, or flagged as hallucinations, are escalated
+    /// (0.0 to 1.0; segments without a confidence count as confident)
+    #[serde(default = "default_adaptive_min_confidence")]
+    pub min_confidence: f64,
+    /// Mode escalated stretches are translated in: Context or Nlp
+    #[serde(default = "default_adaptive_escalation")]
+    pub escalation: TranslationMode,
+    /// Easy segments between two escalated stretches that are escalated with them, so short
+    /// stretches keep their neighbors as context
+    #[serde(default = "default_adaptive_bridge_segments")]
+    pub bridge_segments: usize,
+}
+
+impl Default for AdaptiveConfig {
+    fn default() -> Self {
+        Self {
+            max_simple_chars: default_adaptive_max_simple_chars(),
+            min_confidence: default_adaptive_min_confidence(),
+            escalation: default_adaptive_escalation(),
+            bridge_segments: default_adaptive_bridge_segments(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityConfig {
+    /// Repetitive segment threshold
+    pub repetitive_segment_threshold: f64,
+    /// Maximum tokens per segment threshold
+    pub max_tokens_threshold: f64,
+    /// Minimum quality score required
+    pub min_quality_score: f64,
+    /// Limits for detecting translations that run too long for their language pair
+    #[serde(default)]
+    pub length: LengthValidationConfig,
+    /// Rules flagging transcribed segments as possible hallucinations (see hallucination.rs)
+    #[serde(default)]
+    pub hallucination: HallucinationConfig,
+    /// Reading-speed and line-length limits translated cues are re-wrapped and split to (see readability.rs)
+    #[serde(default)]
+    pub readability: ReadabilityConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LengthValidationConfig {
+    /// Maximum translation length as a multiple of the length expected for the language pair
+    #[serde(default = "default_max_length_ratio")]
+    pub max_ratio: f64,
+    /// Translations up to this many graphemes are never considered too long
+    #[serde(default = "default_min_length_limit")]
+    pub min_limit: usize,
+    /// Typical grapheme length of text in each language relative to English, overriding the built-in table
+    #[serde(default)]
+    pub languages: HashMap<String, f64>,
+}
+
+impl Default for LengthValidationConfig {
+    fn default() -> Self {
+        Self {
+            max_ratio: default_max_length_ratio(),
+            min_limit: default_min_length_limit(),
+            languages: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HallucinationConfig {
+    /// Segments whisper rates more likely than this to be silence are flagged when their text is not repetitive
+    #[serde(default = "default_no_speech_prob")]
+    pub no_speech_prob: f32,
+    /// Compression ratio below which a likely-silent segment counts as not repetitive
+    #[serde(default = "default_no_speech_max_compression")]
+    pub no_speech_max_compression: f32,
+    /// Segments whose text compresses better than this are flagged as repetitive
+    #[serde(default = "default_max_compression_ratio")]
+    pub max_compression_ratio: f32,
+    /// Length in words of the phrases counted by the repeated n-gram rule (0 disables it)
+    #[serde(default = "default_ngram_size")]
+    pub ngram_size: usize,
+    /// Segments repeating one n-gram more often than this are flagged
+    #[serde(default = "default_max_ngram_repeats")]
+    pub max_ngram_repeats: usize,
+    /// Flag segments containing the built-in phrases whisper invents ("thanks for watching")
+    #[serde(default = "default_builtin_phrases")]
+    pub builtin_phrases: bool,
+    /// Further phrases flagged in each language, by language code
+    #[serde(default)]
+    pub phrases: HashMap<String, Vec<String>>,
+    /// Segments with more characters per second than anyone speaks are flagged (0 disables)
+    #[serde(default = "default_max_chars_per_second")]
+    pub max_chars_per_second: f64,
+    /// Long segments with fewer characters per second than this are flagged (0 disables)
+    #[serde(default = "default_min_chars_per_second")]
+    pub min_chars_per_second: f64,
+    /// Segments shorter than this many seconds are never flagged for too little text
+    #[serde(default = "default_sparse_min_duration")]
+    pub sparse_min_duration: f64,
+    /// Write `{video}.hallucinations.json` next to the outputs, listing the rules fired per segment
+    #[serde(default)]
+    pub report: bool,
+}
+
+impl Default for HallucinationConfig {
+    fn default() -> Self {
+        Self {
+            no_speech_prob: default_no_speech_prob(),
+            no_speech_max_compression: default_no_speech_max_compression(),
+            max_compression_ratio: default_max_compression_ratio(),
+            ngram_size: default_ngram_size(),
+            max_ngram_repeats: default_max_ngram_repeats(),
+            builtin_phrases: default_builtin_phrases(),
+            phrases: HashMap::new(),
+            max_chars_per_second: default_max_chars_per_second(),
+            min_chars_per_second: default_min_chars_per_second(),
+            sparse_min_duration: default_sparse_min_duration(),
+            report: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadabilityConfig {
+    /// Re-wrap, split and lengthen the cues of translated subtitles that break the limits
+    #[serde(default)]
+    pub enabled: bool,
+    /// Most characters per second viewers are asked to read
+    #[serde(default = "default_readable_chars_per_second")]
+    pub max_chars_per_second: f64,
+    /// Most characters on one line of a cue
+    #[serde(default = "default_max_line_length")]
+    pub max_line_length: usize,
+    /// Most lines in one cue
+    #[serde(default = "default_max_lines")]
+    pub max_lines: usize,
+    /// Cues are not split into parts shorter than this many seconds
+    #[serde(default = "default_min_cue_duration")]
+    pub min_duration: f64,
+    /// Limits of each target language, overriding the ones above and the built-in CJK limits
+    #[serde(default)]
+    pub languages: HashMap<String, ReadabilityLimits>,
+}
+
+impl Default for ReadabilityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_chars_per_second: default_readable_chars_per_second(),
+            max_line_length: default_max_line_length(),
+            max_lines: default_max_lines(),
+            min_duration: default_min_cue_duration(),
+            languages: HashMap::new(),
+        }
+    }
+}
+
+/// Readability limits of one target language; unset limits are the built-in or general ones
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReadabilityLimits {
+    #[serde(default)]
+    pub max_chars_per_second: Option<f64>,
+    #[serde(default)]
+    pub max_line_length: Option<usize>,
+    #[serde(default)]
+    pub max_lines: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlignConfig {
+    /// Words at least this long are matched as cognates by their first `cognate_length` characters
+    #[serde(default = "default_align_cognate_length")]
+    pub cognate_length: usize,
+    /// Weight of cue/segment duration similarity in anchor scores (0.0 ignores durations)
+    #[serde(default = "default_align_duration_weight")]
+    pub duration_weight: f64,
+}
+
+impl Default for AlignConfig {
+    fn default() -> Self {
+        Self {
+            cognate_length: default_align_cognate_length(),
+            duration_weight: default_align_duration_weight(),
+        }
+    }
+}
+
+/// Snapping of cue times to shot changes (see shots.rs)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShotsConfig {
+    /// Detect the video's shot changes and snap nearby cue in/out times to them
+    #[serde(default)]
+    pub enabled: bool,
+    /// ffmpeg scene change score above which a frame starts a new shot (0.0-1.0)
+    #[serde(default = "default_shot_scene_threshold")]
+    pub scene_threshold: f64,
+    /// Largest distance in seconds a cue time is moved to reach a shot change
+    #[serde(default = "default_shot_tolerance")]
+    pub tolerance: f64,
+    /// Seconds between a snapped out-time and the shot change (about two frames)
+    #[serde(default = "default_shot_gap")]
+    pub gap: f64,
+    /// Snapping never makes a cue shorter than this many seconds
+    #[serde(default = "default_shot_min_duration")]
+    pub min_duration: f64,
+}
+
+impl Default for ShotsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            scene_threshold: default_shot_scene_threshold(),
+            tolerance: default_shot_tolerance(),
+            gap: default_shot_gap(),
+            min_duration: default_shot_min_duration(),
+        }
+    }
+}
+
+/// Merging and splitting of transcribed segments before translation (see segmentation.rs)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentationConfig {
+    /// Merge fragments and split overlong segments of transcriptions before they are translated
+    #[serde(default)]
+    pub enabled: bool,
+    /// Segments shorter than this many seconds are merged with the next one
+    #[serde(default = "default_segment_min_duration")]
+    pub min_duration: f64,
+    /// Segments longer than this many seconds are split
+    #[serde(default = "default_segment_max_duration")]
+    pub max_duration: f64,
+    /// Segments with more characters than this are split; merged segments stay within it
+    #[serde(default = "default_segment_max_chars")]
+    pub max_chars: usize,
+    /// Seconds kept between the end of a segment and the start of the next
+    #[serde(default = "default_segment_min_gap")]
+    pub min_gap: f64,
+    /// Segments further apart than this many seconds are never merged
+    #[serde(default = "default_segment_merge_gap")]
+    pub merge_gap: f64,
+}
+
+impl Default for SegmentationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_duration: default_segment_min_duration(),
+            max_duration: default_segment_max_duration(),
+            max_chars: default_segment_max_chars(),
+            min_gap: default_segment_min_gap(),
+            merge_gap: default_segment_merge_gap(),
+        }
+    }
+}
+
+/// Speaker labels from a diarization program run on the extracted audio (see diarization.rs)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiarizationConfig {
+    /// Label transcribed segments with the speakers `command` finds
+    #[serde(default)]
+    pub enabled: bool,
+    /// Diarization program printing RTTM speaker turns, e.g. a pyannote or ONNX model script
+    #[serde(default)]
+    pub command: String,
+    /// Arguments of `command`; `{audio}` is replaced with the path of the extracted audio
+    #[serde(default = "default_diarization_args")]
+    pub args: Vec<String>,
+    /// Names of the speakers `command` reports, e.g. `SPEAKER_00 = "Alice"`
+    #[serde(default)]
+    pub speaker_names: BTreeMap<String, String>,
+    /// Prefix of cues where another speaker starts talking, `{speaker}` replaced with the speaker;
+    /// empty for none
+    #[serde(default)]
+    pub label_format: String,
+    /// Mention the speaker in translation prompts
+    #[serde(default = "default_hint_translator")]
+    pub hint_translator: bool,
+}
+
+impl Default for DiarizationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            command: String::new(),
+            args: default_diarization_args(),
+            speaker_names: BTreeMap::new(),
+            label_format: String::new(),
+            hint_translator: default_hint_translator(),
+        }
+    }
+}
+
+/// Forced-narrative subtitle tracks (see forced.rs)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ForcedConfig {
+    /// Also write `{stem}_{lang}.forced.srt` with only the lines in another language than the
+    /// main audio (foreign dialogue, signs)
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// Unit, clock, number and date normalization of translations (see locale.rs)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocaleConfig {
+    /// Rules per target language code; languages not listed are left as translated
+    #[serde(default)]
+    pub languages: BTreeMap<String, LocaleRules>,
+}
+
+/// Normalization rules of one target language
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocaleRules {
+    /// Convert miles, feet, pounds, °F and other imperial units to metric ones
+    #[serde(default)]
+    pub metric_units: bool,
+    /// Write "7 pm" as "19:00"
+    #[serde(default)]
+    pub clock_24h: bool,
+    /// Give numbers copied from the source the language's decimal and grouping separators
+    #[serde(default)]
+    pub numbers: bool,
+    /// Reorder numeric dates copied from the source for the language
+    #[serde(default)]
+    pub dates: bool,
+}
+
+impl LocaleConfig {
+    /// Rules of `language`, or of its primary subtag ("pt" for "pt-BR")
+    pub fn rules(&self, language: &str) -> Option<&LocaleRules> {
+        self.languages.get(language).or_else(|| {
+            let primary = language.split(['-', '_']).next().unwrap_or_default();
+            self.languages.get(primary)
+        })
+    }
+}
+
+/// Subtitles showing the source text with the translation (see bilingual.rs)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BilingualConfig {
+    /// Target languages whose `.srt` and `.ass` stack the source line with the translation in
+    /// every cue
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// Put the source line above the translation instead of below it
+    #[serde(default)]
+    pub source_first: bool,
+}
+
+/// Shouting, whispering and singing hints measured on the audio (see delivery.rs)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryConfig {
+    /// Measure every transcribed segment on the extracted audio and tag the ones that stand out
+    #[serde(default)]
+    pub enabled: bool,
+    /// Decibels above the median level of the audio from which a voiced segment is shouted
+    #[serde(default = "default_shout_db")]
+    pub shout_db: f64,
+    /// Decibels below the median level of the audio from which an unvoiced segment is whispered
+    #[serde(default = "default_whisper_db")]
+    pub whisper_db: f64,
+    /// Seconds a segment holds one pitch when it is sung
+    #[serde(default = "default_singing_sustain")]
+    pub singing_sustain: f64,
+    /// Mention the delivery in translation prompts
+    #[serde(default = "default_hint_translator")]
+    pub hint_translator: bool,
+    /// Write shouted lines in capitals
+    #[serde(default = "default_shouting_caps")]
+    pub shouting_caps: bool,
+    /// Put ♪ around sung lines
+    #[serde(default = "default_singing_notes")]
+    pub singing_notes: bool,
+    /// Label before whispered lines; empty for none
+    #[serde(default = "default_whispering_label")]
+    pub whispering_label: String,
+    /// Outputs the hints are marked in
+    #[serde(default)]
+    pub marks: DeliveryMarks,
+}
+
+impl Default for DeliveryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            shout_db: default_shout_db(),
+            whisper_db: default_whisper_db(),
+            singing_sustain: default_singing_sustain(),
+            hint_translator: default_hint_translator(),
+            shouting_caps: default_shouting_caps(),
+            singing_notes: default_singing_notes(),
+            whispering_label: default_whispering_label(),
+            marks: DeliveryMarks::default(),
+        }
+    }
+}
+
+/// Outputs delivery hints are marked in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryMarks {
+    /// The `.srt` file and the video it is burned into
+    #[serde(default = "default_mark_output")]
+    pub srt: bool,
+    /// The styled `.ass` file
+    #[serde(default = "default_mark_output")]
+    pub ass: bool,
+    /// The forced-narrative `.forced.srt` file
+    #[serde(default)]
+    pub forced: bool,
+}
+
+impl Default for DeliveryMarks {
+    fn default() -> Self {
+        Self { srt: default_mark_output(), ass: default_mark_output(), forced: false }
+    }
+}
+
+/// How whisper and Ollama share the machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourcesConfig {
+    /// Scheduling of the GPU-heavy stages, transcription and translation
+    #[serde(default)]
+    pub gpu_scheduling: GpuScheduling,
+    /// Check free space in the cache and output locations before extracting audio or muxing
+    #[serde(default = "default_check_disk_space")]
+    pub check_disk_space: bool,
+    /// Space in MB to keep free on top of the estimated needs
+    #[serde(default = "default_disk_headroom_mb")]
+    pub disk_headroom_mb: u64,
+    /// Target languages translated and written at once (0 = all)
+    #[serde(default)]
+    pub max_parallel_languages: usize,
+}
+
+impl Default for ResourcesConfig {
+    fn default() -> Self {
+        Self {
+            gpu_scheduling: GpuScheduling::default(),
+            check_disk_space: default_check_disk_space(),
+            disk_headroom_mb: default_disk_headroom_mb(),
+            max_parallel_languages: 0,
+        }
+    }
+}
+
+/// Health checks run before long batches (see preflight.rs)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreflightConfig {
+    /// Run the preflight checks before batches of at least this many videos (0 = never)
+    #[serde(default = "default_preflight_batch_files")]
+    pub batch_files: usize,
+}
+
+impl Default for PreflightConfig {
+    fn default() -> Self {
+        Self {
+            batch_files: default_preflight_batch_files(),
+        }
+    }
+}
+
+/// How `batch` handles videos that fail
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchConfig {
+    #[serde(default)]
+    pub on_error: ErrorPolicy,
+}
+
+/// What a batch does when a video fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ErrorPolicy {
+    /// Go on with the next video and list the failures at the end
+    #[default]
+    Continue,
+    /// Stop the batch at the first failure
+    Abort,
+    /// Go on, and list the failed videos in `retry-list.txt` in the output directory
+    Quarantine,
+}
+
+/// Token usage and estimated cost of model requests (see cost.rs)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostConfig {
+    /// Price of each model by name; models without one are counted at no cost
+    #[serde(default)]
+    pub prices: HashMap<String, ModelPrice>,
+    /// Estimated cost a run may reach before it is aborted (0 = no budget)
+    #[serde(default)]
+    pub budget: f64,
+    /// Currency the prices and the budget are given in, shown with costs
+    #[serde(default = "default_cost_currency")]
+    pub currency: String,
+}
+
+impl Default for CostConfig {
+    fn default() -> Self {
+        Self {
+            prices: HashMap::new(),
+            budget: 0.0,
+            currency: default_cost_currency(),
+        }
+    }
+}
+
+/// Price of a model per million tokens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    #[serde(default)]
+    pub prompt_per_million: f64,
+    #[serde(default)]
+    pub completion_per_million: f64,
+}
+
+/// Size limits of the caches (see cache.rs)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheConfig {
+    /// Size in MB each cache category may grow to before its least recently used entries are evicted
+    #[serde(default)]
+    pub max_cache_size_mb: CacheLimits,
+}
+
+/// Size limits in MB per cache category (0 = unlimited)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheLimits {
+    /// Audio extracted from videos for whisper
+    #[serde(default = "default_audio_cache_mb")]
+    pub audio: u64,
+    /// Transcriptions, simple and tuned
+    #[serde(default)]
+    pub transcriptions: u64,
+    /// Translated segments
+    #[serde(default)]
+    pub translations: u64,
+    /// Sentence analysis journals of the LLM translator
+    #[serde(default)]
+    pub analysis: u64,
+}
+
+impl Default for CacheLimits {
+    fn default() -> Self {
+        Self {
+            audio: default_audio_cache_mb(),
+            transcriptions: 0,
+            translations: 0,
+            analysis: 0,
+        }
+    }
+}
+
+/// Notifications posted when each video of `process` or `batch` finishes or fails (see webhook.rs)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// URL the notifications are posted to; none are sent without one
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Headers sent with every notification, such as an authorization token
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Body replacing the default JSON payload, with `{{event}}`, `{{file}}`, `{{file_name}}`,
+    /// `{{languages}}`, `{{subtitles}}` and `{{error}}` substituted
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Seconds to wait for the receiver before giving up on a notification
+    #[serde(default = "default_webhook_timeout")]
+    pub timeout_seconds: u64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            headers: BTreeMap::new(),
+            template: None,
+            timeout_seconds: default_webhook_timeout(),
+        }
+    }
+}
+
+/// Retries of requests to Ollama, OpenAI-compatible APIs and whisper-server (see retry.rs)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryConfig {
+    /// Attempts of a request, the first one included (1 = no retries)
+    #[serde(default = "default_retry_max_attempts")]
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubled for every further one (milliseconds)
+    #[serde(default = "default_retry_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// Longest wait between two attempts (milliseconds)
+    #[serde(default = "default_retry_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// Share (0.0-1.0) of each wait cut off at random, so parallel requests do not retry in step
+    #[serde(default = "default_retry_jitter")]
+    pub jitter: f64,
+    /// Retries all requests for one video may take together (0 = unlimited)
+    #[serde(default = "default_retry_file_budget")]
+    pub file_budget: u32,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_retry_max_attempts(),
+            initial_backoff_ms: default_retry_initial_backoff_ms(),
+            max_backoff_ms: default_retry_max_backoff_ms(),
+            jitter: default_retry_jitter(),
+            file_budget: default_retry_file_budget(),
+        }
+    }
+}
+
+/// Language codes in output names (see naming.rs)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum LanguageCodes {
+    /// The codes the target languages are given with (`ja`)
+    #[default]
+    AsGiven,
+    /// ISO 639-2 codes (`jpn`), which every media server recognizes
+    ThreeLetter,
+}
+
+/// Where `batch` writes the outputs of each video (see naming.rs)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum OutputPlacement {
+    /// All into the output directory
+    #[default]
+    OutputDir,
+    /// Into the output directory, in the subdirectories the videos are in under the input directory
+    Mirror,
+    /// Next to each video, as media servers look for external subtitles
+    NextToSource,
+}
+
+/// What `process` and `batch` do with the outputs of an earlier run (see naming.rs)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ExistingOutputs {
+    /// Write every language again
+    #[default]
+    Overwrite,
+    /// Skip a language whose subtitles and video exist and are newer than the input video
+    SkipExisting,
+}
+
+/// Scheduling of transcription and translation when they share one GPU
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum GpuScheduling {
+    /// Translate while the final transcription pass is running (separate GPUs or enough memory)
+    #[default]
+    Concurrent,
+    /// One stage at a time: Ollama models are unloaded before whisper runs, and translation starts once whisper has exited
+    Serialized,
+    /// Translate concurrently, but run whisper on the CPU while translation is active
+    CpuWhisper,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaConfig {
+    /// Path to ffmpeg binary
+    pub binary_path: String,
+    /// Additional encoding options for burned-in subtitles and preview clips
+    /// Common options: ["-preset", "medium"] (encoding speed), ["-crf", "23"] (quality, 0-51,
+    /// lower is better), ["-pix_fmt", "yuv420p"] (pixel format for compatibility); passed after
+    /// the options of `encoding`, so a preset or CRF here overrides its own
+    pub subtitle_options: Vec<String>,
+    /// Path to ffprobe binary
+    #[serde(default = "default_ffprobe_path")]
+    pub ffprobe_path: String,
+    /// Base name of the subtitle and video outputs of a file (see naming.rs for placeholders)
+    #[serde(default = "default_output_template")]
+    pub output_template: String,
+    /// Base name of forced-narrative subtitles, with the placeholders of `output_template`;
+    /// `None` names them `{output name}.forced`
+    #[serde(default)]
+    pub forced_template: Option<String>,
+    /// Language codes in output names: as given, or ISO 639-2 (`jpn`)
+    #[serde(default)]
+    pub output_language_codes: LanguageCodes,
+    /// Where `batch` writes the outputs of each video
+    #[serde(default)]
+    pub output_placement: OutputPlacement,
+    /// Whether outputs newer than the video are written again or their language is skipped
+    #[serde(default)]
+    pub existing_outputs: ExistingOutputs,
+    /// Style preset of burned-in subtitles and generated ASS files (see style.rs); `None` keeps
+    /// the libass defaults
+    #[serde(default)]
+    pub style: Option<String>,
+    /// User-defined style presets by name, overriding built-ins of the same name
+    #[serde(default)]
+    pub styles: BTreeMap<String, SubtitleStyle>,
+    /// Write `{output}.ass` next to each `.srt` even without a style preset (in the default style)
+    #[serde(default)]
+    pub write_ass: bool,
+    /// Static ffmpeg/ffprobe builds downloaded into .shuro/bin when not found on PATH
+    #[serde(default)]
+    pub bundled: BundledMediaConfig,
+    /// Reject malformed subtitle input files instead of repairing them (see subtitle.rs)
+    #[serde(default)]
+    pub strict_subtitles: bool,
+    /// Burn subtitles into the video stream instead of muxing them as a soft subtitle track, for
+    /// players and platforms without subtitle track support (always done before this option)
+    #[serde(default)]
+    pub burn_in: bool,
+    /// Video encoding of burned-in subtitles and preview clips
+    #[serde(default)]
+    pub encoding: EncodingConfig,
+    /// One Matroska file with the subtitle tracks of every language instead of a video per language
+    #[serde(default)]
+    pub multi_track: MultiTrackConfig,
+}
+
+/// Muxing every language's subtitles into one Matroska file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiTrackConfig {
+    /// Write `{output}.mkv` (the output name with `{lang}` = "multi") holding the subtitle tracks
+    /// of all target languages, instead of one video per language
+    #[serde(default)]
+    pub enabled: bool,
+    /// Also add the transcription as a track in the source language
+    #[serde(default = "default_include_source")]
+    pub include_source: bool,
+    /// Language of the track players show by default; `None` for the first target language
+    #[serde(default)]
+    pub default_track: Option<String>,
+    /// Language whose track players show even with subtitles turned off
+    #[serde(default)]
+    pub forced_track: Option<String>,
+}
+
+impl Default for MultiTrackConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            include_source: default_include_source(),
+            default_track: None,
+            forced_track: None,
+        }
+    }
+}
+
+/// Video encoder settings for re-encoding a video with subtitles burned in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EncodingConfig {
+    /// ffmpeg video encoder, e.g. libx264, libx265 or h264_videotoolbox
+    #[serde(default = "default_video_codec")]
+    pub video_codec: String,
+    /// Constant rate factor (lower = better quality); `None` keeps the encoder's default
+    #[serde(default)]
+    pub crf: Option<u32>,
+    /// Encoder speed preset (ultrafast ... veryslow for libx264); `None` keeps the encoder's default
+    #[serde(default)]
+    pub preset: Option<String>,
+}
+
+impl Default for EncodingConfig {
+    fn default() -> Self {
+        Self {
+            video_codec: default_video_codec(),
+            crf: None,
+            preset: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BundledMediaConfig {
+    /// Download the configured builds when ffmpeg or ffprobe is not found
+    #[serde(default)]
+    pub enabled: bool,
+    /// Downloads, one per binary
+    #[serde(default)]
+    pub binaries: Vec<BundledBinaryConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundledBinaryConfig {
+    /// Binary name (ffmpeg or ffprobe)
+    pub name: String,
+    /// Download URL; `.zip` archives are unpacked, anything else is used as the executable itself
+    pub url: String,
+    /// Expected SHA-256 of the downloaded file, hex encoded
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PluginsConfig {
+    /// External translator executables, registered by name
+    #[serde(default)]
+    pub translators: Vec<ExternalPluginConfig>,
+    /// External transcriber executables, registered by name
+    #[serde(default)]
+    pub transcribers: Vec<ExternalPluginConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalPluginConfig {
+    /// Name used to select the plugin from config
+    pub name: String,
+    /// Executable to run; it receives a JSON request on stdin and writes a JSON response to stdout
+    pub command: String,
+    /// Extra arguments passed to the executable
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            transcriber: TranscriberConfig {
+                binary_path: "whisper-cli".to_string(),
+                mode: TranscriptionMode::Tuned,
+                explore_model: "base".to_string(),
+                transcribe_model: "medium".to_string(),
+                acceptable_languages: "en,ja,ko,zh,fr,de,es,ru,it,pt,pl,nl,tr,ar,hi,th,vi,sv,da,no,fi,he,hu,cs,sk,bg,hr,sl,et,lv,lt,mt,ga,cy,eu,ca,gl,is,mk,sq,be,uk,az,kk,ky,uz,tg,am,ka,hy,ne,si,my,km,lo,ka,gu,pa,ta,te,kn,ml,bn,as,or,mr".to_string(),
+                fallback_language: "en".to_string(),
+                explore_steps: 10,
+                explore_range_max: 110,
+                explore_range_min: 80,
+                temperature: 0.0,
+                implementation: default_transcriber_implementation(),
+                server: WhisperServerConfig::default(),
+                vad: VadConfig::default(),
+                word_timestamps: default_word_timestamps(),
+                audio_track: None,
+                explore_sample_windows: default_explore_sample_windows(),
+                explore_sample_seconds: default_explore_sample_seconds(),
+                tuning_profile: None,
+                fingerprint_dedupe: false,
+                fpcalc_path: default_fpcalc_path(),
+                fingerprint_similarity: default_fingerprint_similarity(),
+                language: None,
+                language_id_model: default_language_id_model(),
+                language_id_window: default_language_id_window(),
+                chunk_minutes: 0,
+                transcribe_workers: default_transcribe_workers(),
+                prefer_embedded_subs: false,
+                transcript: None,
+                ffmpeg_path: default_ffmpeg_path(),
+                ffprobe_path: default_ffprobe_path(),
+                source_language: default_source_language(),
+                cpu_while_translating: false,
+                cache_dir: default_transcriber_cache_dir(),
+                temp_dir: default_temp_dir(),
+            },
+            translate: TranslateConfig {
+                provider: default_provider(),
+                openai: OpenAiConfig::default(),
+                endpoint: "http://localhost:11434".to_string(),
+                endpoints: Vec::new(),
+                load_balancing: LoadBalancing::default(),
+                health_check_interval: default_health_check_interval(),
+                model: "gemma3n:e4b".to_string(),
+                source_language: "en".to_string(),
+                max_retries: 3,
+                structured_outputs: default_structured_outputs(),
+                mode: TranslationMode::Simple,
+                nlp_gap_threshold: 2.0,
+                context_window_size: 2,
+                nlp_max_sentence_chars: default_nlp_max_sentence_chars(),
+                adaptive: AdaptiveConfig::default(),
+                adaptive_context: default_adaptive_context(),
+                max_context_tokens: default_max_context_tokens(),
+                num_ctx: None,
+                llm_window_size: 15,
+                llm_confidence_threshold: 0.6,
+                llm_window_stride: default_llm_window_stride(),
+                llm_max_windows_per_minute: 0,
+                llm_early_exit_windows: 0,
+                judge_model: None,
+                evaluation_concurrency: default_evaluation_concurrency(),
+                concurrency: default_translation_concurrency(),
+                length_validation: LengthValidationConfig::default(),
+                delivery_hints: false,
+                speaker_hints: false,
+                segment_level_cache: false,
+                metrics_log_interval: default_metrics_log_interval(),
+                stream_chunk_size: default_stream_chunk_size(),
+                partial_flush_segments: default_partial_flush_segments(),
+                implementation: None,
+                semantic_cache: SemanticCacheConfig::default(),
+                memory: MemoryConfig::default(),
+                notes: NotesConfig::default(),
+                cache_dir: default_translation_cache_dir(),
+                glossary: Vec::new(),
+                glossary_file: None,
+                work: WorkConfig::default(),
+                work_context: None,
+                prompt_template: None,
+                tone: Tone::Unspecified,
+                tone_instruction: None,
+                languages: HashMap::new(),
+            },
+            quality: QualityConfig {
+                repetitive_segment_threshold: 0.8,
+                max_tokens_threshold: 50.0,
+                min_quality_score: 0.7,
+                length: LengthValidationConfig::default(),
+                hallucination: HallucinationConfig::default(),
+                readability: ReadabilityConfig::default(),
+            },
+            media: MediaConfig {
+                binary_path: default_ffmpeg_path(),
+                subtitle_options: vec![
+                    // Example encoding options users can customize:
+                    // "-preset".to_string(), "medium".to_string(), // Encoding speed
+                    // "-crf".to_string(), "23".to_string(),        // Quality (lower = better)
+                    // "-pix_fmt".to_string(), "yuv420p".to_string(), // Pixel format for compatibility
+                ],
+                ffprobe_path: default_ffprobe_path(),
+                output_template: default_output_template(),
+                forced_template: None,
+                output_language_codes: LanguageCodes::default(),
+                output_placement: OutputPlacement::default(),
+                existing_outputs: ExistingOutputs::default(),
+                style: None,
+                styles: BTreeMap::new(),
+                write_ass: false,
+                bundled: BundledMediaConfig::default(),
+                strict_subtitles: false,
+                burn_in: false,
+                encoding: EncodingConfig::default(),
+                multi_track: MultiTrackConfig::default(),
+            },
+            plugins: PluginsConfig::default(),
+            align: AlignConfig::default(),
+            shots: ShotsConfig::default(),
+            segmentation: SegmentationConfig::default(),
+            diarization: DiarizationConfig::default(),
+            forced: ForcedConfig::default(),
+            bilingual: BilingualConfig::default(),
+            resources: ResourcesConfig::default(),
+            preflight: PreflightConfig::default(),
+            cache: CacheConfig::default(),
+            webhook: WebhookConfig::default(),
+            retry: RetryConfig::default(),
+            batch: BatchConfig::default(),
+            cost: CostConfig::default(),
+            delivery: DeliveryConfig::default(),
+            locale: LocaleConfig::default(),
+            state_dir: default_state_dir(),
+            temp_dir: default_temp_dir(),
+        }
+    }
+}
+
+impl Config {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| ShuroError::Config(format!("Failed to read config file: {}", e)))?;
+        
+        toml::from_str(&content)
+            .map_err(|e| ShuroError::Config(format!("Failed to parse config file: {}", e)))
+    }
+
+    /// Keep the caches, batch job states and temporary artifacts of this run in `work_dir/cache`,
+    /// `work_dir/state` and `work_dir/tmp` instead of `.shuro/`, so all of its intermediate state
+    /// can be deleted with the directory
+    pub fn use_work_dir(&mut self, work_dir: &Path) {
+        let cache_dir = work_dir.join("cache").to_string_lossy().into_owned();
+        self.transcriber.cache_dir = cache_dir.clone();
+        self.translate.cache_dir = cache_dir;
+        self.state_dir = work_dir.join("state").to_string_lossy().into_owned();
+        self.temp_dir = work_dir.join("tmp").to_string_lossy().into_owned();
+        self.transcriber.temp_dir = self.temp_dir.clone();
+    }
+
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| ShuroError::Config(format!("Failed to serialize config: {}", e)))?;
+        
+        std::fs::write(path, content)
+            .map_err(|e| ShuroError::Config(format!("Failed to write config file: {}", e)))?;
+        
+        Ok(())
+    }
+} 
\ No newline at end of file
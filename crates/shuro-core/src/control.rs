@@ -0,0 +1,129 @@
+// Batch control
+//
+// A supervisor of a running batch (a desktop front end, a process manager talking to the
+// control socket of `batch --control-socket`) steers it through a `BatchControl` given with
+// `Workflow::with_control`. Pausing takes effect between files: the file being processed is
+// finished, and the next one starts when the batch is resumed. Skipping cancels the current file
+// only; it is reported as skipped rather than failed, so `batch --resume` tries it again.
+// Aborting is cancelling the batch's `Cancellation`.
+
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+use crate::cancel::Cancellation;
+use crate::error::{Result, ShuroError};
+
+/// Handle to pause a batch or skip its current file, shared with the supervisor
+#[derive(Debug, Clone)]
+pub struct BatchControl {
+    paused: Arc<watch::Sender<bool>>,
+    current: Arc<Mutex<CurrentFile>>,
+}
+
+#[derive(Debug, Default)]
+struct CurrentFile {
+    cancel: Option<Cancellation>,
+    skipped: bool,
+}
+
+impl Default for BatchControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BatchControl {
+    pub fn new() -> Self {
+        Self {
+            paused: Arc::new(watch::channel(false).0),
+            current: Arc::default(),
+        }
+    }
+
+    /// Hold the batch before its next file
+    pub fn pause(&self) {
+        self.paused.send_replace(true);
+    }
+
+    pub fn resume(&self) {
+        self.paused.send_replace(false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Cancel the file being processed, if any; returns whether there was one
+    pub fn skip_current(&self) -> bool {
+        let mut current = self.lock();
+        match &current.cancel {
+            Some(cancel) => {
+                cancel.cancel();
+                current.skipped = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Wait until the batch is not paused, or fail when `cancel` is cancelled meanwhile
+    pub async fn wait_while_paused(&self, cancel: &Cancellation) -> Result<()> {
+        let mut paused = self.paused.subscribe();
+        cancel.run(async move {
+            paused.wait_for(|paused| !paused).await.map_err(|_| ShuroError::Cancelled)?;
+            Ok(())
+        }).await
+    }
+
+    /// Cancellation of the next file, a child of the batch's `cancel` that skipping cancels
+    pub fn start_file(&self, cancel: &Cancellation) -> Cancellation {
+        let file_cancel = cancel.child();
+        *self.lock() = CurrentFile { cancel: Some(file_cancel.clone()), skipped: false };
+        file_cancel
+    }
+
+    /// Forget the current file; returns whether it was skipped
+    pub fn finish_file(&self) -> bool {
+        std::mem::take(&mut *self.lock()).skipped
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CurrentFile> {
+        self.current.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_batch_control() {
+        let control = BatchControl::new();
+        let batch = Cancellation::new();
+        assert!(!control.skip_current());
+
+        let file = control.start_file(&batch);
+        assert!(control.skip_current());
+        assert!(file.is_cancelled() && !batch.is_cancelled());
+        assert!(control.finish_file());
+        control.start_file(&batch);
+        assert!(!control.finish_file());
+
+        control.pause();
+        let waiting = tokio::spawn({
+            let control = control.clone();
+            let batch = batch.clone();
+            async move { control.wait_while_paused(&batch).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished());
+        control.resume();
+        waiting.await.unwrap().unwrap();
+
+        // Aborting the batch ends the pause
+        control.pause();
+        batch.cancel();
+        assert!(matches!(control.wait_while_paused(&batch).await, Err(ShuroError::Cancelled)));
+    }
+}
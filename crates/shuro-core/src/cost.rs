@@ -0,0 +1,145 @@
+// Token usage and cost
+//
+// Metered backends (OpenAI-compatible APIs) bill by token, and even a local Ollama is worth
+// measuring. Every generate request of the translation backend, whether it carries a
+// translation, a quality evaluation, a translator's note or a title lookup, goes through one
+// `CostTracker`, which adds up the prompt and completion tokens the backend reports and prices
+// them by `[cost.prices."<model>"]` (per million tokens; models without a price cost nothing).
+// The usage of each video is shown in the run report (see report.rs), and the usage of the
+// requests that produced a cached translation is stored in its cache entry.
+//
+// With `cost.budget` set, a run stops spending once its estimated cost reaches the budget:
+// further requests fail at once, the video being processed fails before its outputs are written,
+// and a batch is aborted whatever `batch.on_error` says. A workflow that serves many runs
+// (`shuro serve`, one per job) starts counting each of them from zero.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+use crate::config::CostConfig;
+use crate::error::{Result, ShuroError};
+use crate::translate::metrics::TokenUsage;
+
+/// Tokens of model requests and their estimated cost
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// In `cost.currency`
+    pub cost: f64,
+}
+
+impl Usage {
+    pub fn add(&mut self, other: Usage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cost += other.cost;
+    }
+
+    pub fn tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+/// Usage of the model requests of a workflow, for the run and the current video
+#[derive(Debug, Default)]
+pub struct CostTracker {
+    config: CostConfig,
+    /// Usage of the run and of the current video
+    spent: Mutex<(Usage, Usage)>,
+}
+
+impl CostTracker {
+    pub fn new(config: &CostConfig) -> Self {
+        Self { config: config.clone(), spent: Mutex::new((Usage::default(), Usage::default())) }
+    }
+
+    /// Count the usage of the next run from zero, giving it the whole budget
+    pub fn start_run(&self) {
+        *self.spent.lock().expect("cost lock poisoned") = (Usage::default(), Usage::default());
+    }
+
+    /// Count the usage of the next video from zero
+    pub fn start_file(&self) {
+        self.spent.lock().expect("cost lock poisoned").1 = Usage::default();
+    }
+
+    /// Usage of a request to `model` reporting `tokens`
+    pub fn price(&self, model: &str, tokens: &TokenUsage) -> Usage {
+        let price = self.config.prices.get(model).copied().unwrap_or_default();
+        let (prompt_tokens, completion_tokens) = (tokens.prompt_tokens.unwrap_or(0), tokens.generated_tokens.unwrap_or(0));
+        Usage {
+            prompt_tokens,
+            completion_tokens,
+            cost: (prompt_tokens as f64 * price.prompt_per_million + completion_tokens as f64 * price.completion_per_million) / 1e6,
+        }
+    }
+
+    /// Add a request to `model` reporting `tokens` to the run and the current video
+    pub fn record(&self, model: &str, tokens: &TokenUsage) {
+        let usage = self.price(model, tokens);
+        let mut spent = self.spent.lock().expect("cost lock poisoned");
+        spent.0.add(usage);
+        spent.1.add(usage);
+    }
+
+    /// Fail once the run has spent its budget
+    pub fn check(&self) -> Result<()> {
+        let spent = self.run_usage();
+        if self.config.budget > 0.0 && spent.cost >= self.config.budget {
+            return Err(ShuroError::BudgetExceeded(format!(
+                "the run has spent an estimated {:.2} {} of its budget of {:.2}",
+                spent.cost, self.config.currency, self.config.budget
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn run_usage(&self) -> Usage {
+        self.spent.lock().expect("cost lock poisoned").0
+    }
+
+    pub fn file_usage(&self) -> Usage {
+        self.spent.lock().expect("cost lock poisoned").1
+    }
+
+    pub fn currency(&self) -> &str {
+        &self.config.currency
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModelPrice;
+
+    #[test]
+    fn test_costs_and_budget() {
+        let mut config = CostConfig { budget: 0.01, ..CostConfig::default() };
+        config.prices.insert("gpt-4o-mini".to_string(), ModelPrice { prompt_per_million: 0.15, completion_per_million: 0.6 });
+        let tracker = CostTracker::new(&config);
+        let tokens = TokenUsage { prompt_tokens: Some(20_000), generated_tokens: Some(5_000), generation_ns: None };
+
+        tracker.record("gemma3:12b", &tokens);
+        assert_eq!(tracker.run_usage().tokens(), 25_000);
+        assert_eq!(tracker.run_usage().cost, 0.0);
+
+        tracker.start_file();
+        tracker.record("gpt-4o-mini", &tokens);
+        assert!((tracker.file_usage().cost - 0.006).abs() < 1e-9);
+        assert_eq!(tracker.file_usage().prompt_tokens, 20_000);
+        assert_eq!(tracker.run_usage().prompt_tokens, 40_000);
+        assert!(tracker.check().is_ok());
+
+        tracker.record("gpt-4o-mini", &tokens);
+        assert!(matches!(tracker.check(), Err(ShuroError::BudgetExceeded(_))));
+
+        // The next run has the whole budget again
+        tracker.start_run();
+        assert_eq!(tracker.run_usage(), Usage::default());
+        tracker.record("gpt-4o-mini", &tokens);
+        assert!(tracker.check().is_ok());
+        tracker.record("gpt-4o-mini", &tokens);
+        assert!(matches!(tracker.check(), Err(ShuroError::BudgetExceeded(_))));
+    }
+}
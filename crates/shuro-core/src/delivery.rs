@@ -0,0 +1,421 @@
+// Delivery hints from the audio
+//
+// Whisper transcribes the words, not how they are said: a shouted warning, a whispered aside and
+// a sung verse all come out as plain lines. With `delivery.enabled`, every transcribed segment is
+// measured on the extracted audio before it is translated:
+//
+// - loudness: level of its non-silent frames, relative to the median level of the file's audio
+// - voicing: share of its non-silent frames with a clear pitch (autocorrelation peak between
+//   70 and 500 Hz)
+// - sustained pitch: the longest run of voiced frames whose pitch stays within a semitone
+//
+// A voiced segment `shout_db` louder than the median is shouting; a segment holding one pitch
+// for `singing_sustain` seconds is singing; one `whisper_db` quieter with little voicing is
+// whispering. The hint is stored as `delivery` segment metadata, mentioned in the prompts of the
+// Simple and Context translators (`hint_translator`) and marked in the outputs selected under
+// `[delivery.marks]`: CAPS for shouting, ♪ around sung lines and a label before whispered ones.
+// Videos translated from embedded subtitles have no extracted audio and get no hints.
+
+use serde::{Deserialize, Serialize};
+use std::io::SeekFrom;
+use std::path::Path;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::config::DeliveryConfig;
+use crate::error::{Result, ShuroError};
+use crate::metadata::DELIVERY;
+use crate::quality::{Transcription, TranscriptionSegment};
+
+/// Frames quieter than this level (dBFS) are silence
+const SILENCE_DB: f64 = -50.0;
+
+/// Length of an analysis frame in seconds
+const FRAME_SECONDS: f64 = 0.032;
+
+/// Sample rate frames are analyzed at; higher rates are decimated to about this rate
+const ANALYSIS_RATE: u32 = 8_000;
+
+/// Pitch range searched for voicing, in Hz
+const MIN_PITCH: f64 = 70.0;
+const MAX_PITCH: f64 = 500.0;
+
+/// Normalized autocorrelation above which a frame is voiced
+const VOICING_THRESHOLD: f64 = 0.5;
+
+/// Voicing share a shouted segment has at least
+const SHOUT_MIN_VOICING: f64 = 0.5;
+
+/// Voicing share a whispered segment has at most
+const WHISPER_MAX_VOICING: f64 = 0.3;
+
+/// How a segment is spoken, when it stands out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Delivery {
+    Shouting,
+    Whispering,
+    Singing,
+}
+
+impl Delivery {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Shouting => "shouting",
+            Self::Whispering => "whispering",
+            Self::Singing => "singing",
+        }
+    }
+
+    /// Note on the delivery for a translation prompt
+    pub fn prompt_note(&self) -> &'static str {
+        match self {
+            Self::Shouting => "The line is shouted; an exclamatory translation fits.",
+            Self::Whispering => "The line is whispered; keep the translation hushed and brief.",
+            Self::Singing => "The line is sung; translate it as lyrics, without explanations.",
+        }
+    }
+}
+
+/// Measurements of a stretch of audio
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioFeatures {
+    /// Level of the non-silent frames in dBFS; `None` when every frame is silent
+    pub loudness_db: Option<f64>,
+    /// Share of the non-silent frames with a clear pitch
+    pub voicing: f64,
+    /// Longest run of voiced frames within a semitone of each other, in seconds
+    pub sustained_pitch: f64,
+}
+
+/// Measure loudness, voicing and sustained pitch of mono samples
+pub fn analyze(samples: &[i16], sample_rate: u32) -> AudioFeatures {
+    let factor = (sample_rate / ANALYSIS_RATE).max(1) as usize;
+    let rate = sample_rate as f64 / factor as f64;
+    let decimated: Vec<f64> = samples
+        .chunks(factor)
+        .map(|chunk| chunk.iter().map(|&s| s as f64).sum::<f64>() / chunk.len() as f64 / 32_768.0)
+        .collect();
+    let frame_len = ((rate * FRAME_SECONDS) as usize).max(1);
+
+    let mut power_sum = 0.0;
+    let (mut audible, mut voiced) = (0usize, 0usize);
+    let (mut run, mut longest_run) = (0usize, 0usize);
+    let mut previous_pitch: Option<f64> = None;
+    for frame in decimated.chunks_exact(frame_len) {
+        let power = frame.iter().map(|x| x * x).sum::<f64>() / frame.len() as f64;
+        if to_db(power) < SILENCE_DB {
+            (run, previous_pitch) = (0, None);
+            continue;
+        }
+        audible += 1;
+        power_sum += power;
+
+        let pitch = frame_pitch(frame, rate);
+        if pitch.is_some() {
+            voiced += 1;
+        }
+        run = match (pitch, previous_pitch) {
+            (Some(pitch), Some(previous)) if (12.0 * (pitch / previous).log2()).abs() <= 1.0 => run + 1,
+            (Some(_), _) => 1,
+            (None, _) => 0,
+        };
+        longest_run = longest_run.max(run);
+        previous_pitch = pitch;
+    }
+
+    AudioFeatures {
+        loudness_db: (audible > 0).then(|| to_db(power_sum / audible as f64)),
+        voicing: if audible > 0 { voiced as f64 / audible as f64 } else { 0.0 },
+        sustained_pitch: longest_run as f64 * frame_len as f64 / rate,
+    }
+}
+
+/// Pitch of a frame in Hz, `None` when it has no clear pitch
+fn frame_pitch(frame: &[f64], rate: f64) -> Option<f64> {
+    let min_lag = (rate / MAX_PITCH).floor().max(1.0) as usize;
+    let max_lag = ((rate / MIN_PITCH).ceil() as usize).min(frame.len() / 2);
+    let correlations: Vec<(usize, f64)> = (min_lag..=max_lag)
+        .map(|lag| {
+            let (head, tail) = (&frame[..frame.len() - lag], &frame[lag..]);
+            let product: f64 = head.iter().zip(tail).map(|(a, b)| a * b).sum();
+            let energy = head.iter().map(|a| a * a).sum::<f64>() * tail.iter().map(|b| b * b).sum::<f64>();
+            (lag, if energy > 0.0 { product / energy.sqrt() } else { 0.0 })
+        })
+        .collect();
+    let best = correlations.iter().map(|(_, r)| *r).fold(f64::MIN, f64::max);
+    if best < VOICING_THRESHOLD {
+        return None;
+    }
+    // Multiples of the period correlate almost as well; the shortest strong lag is the pitch
+    correlations.iter()
+        .find(|(_, r)| *r >= best * 0.9)
+        .map(|(lag, _)| rate / *lag as f64)
+}
+
+fn to_db(power: f64) -> f64 {
+    10.0 * power.max(1e-12).log10()
+}
+
+/// Delivery of a segment with `features`, given the median level of the file's audio
+pub fn classify(features: &AudioFeatures, median_db: f64, config: &DeliveryConfig) -> Option<Delivery> {
+    let loudness = features.loudness_db?;
+    if loudness - median_db >= config.shout_db && features.voicing >= SHOUT_MIN_VOICING {
+        Some(Delivery::Shouting)
+    } else if features.sustained_pitch >= config.singing_sustain {
+        Some(Delivery::Singing)
+    } else if median_db - loudness >= config.whisper_db && features.voicing <= WHISPER_MAX_VOICING {
+        Some(Delivery::Whispering)
+    } else {
+        None
+    }
+}
+
+/// 16-bit PCM WAV file read in stretches, so long audio is never loaded whole
+pub struct WavReader {
+    file: File,
+    data_offset: u64,
+    data_len: u64,
+    sample_rate: u32,
+    channels: u16,
+}
+
+impl WavReader {
+    pub async fn open(path: &Path) -> Result<Self> {
+        let invalid = |reason: &str| ShuroError::UnsupportedFormat(format!("{}: {}", path.display(), reason));
+        let mut file = File::open(path).await?;
+        let mut header = [0u8; 12];
+        file.read_exact(&mut header).await?;
+        if &header[..4] != b"RIFF" || &header[8..] != b"WAVE" {
+            return Err(invalid("not a WAV file"));
+        }
+
+        let mut format: Option<(u16, u32)> = None;
+        let mut offset = 12u64;
+        loop {
+            let mut chunk = [0u8; 8];
+            file.read_exact(&mut chunk).await.map_err(|_| invalid("no data chunk"))?;
+            let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as u64;
+            offset += 8;
+            match &chunk[..4] {
+                b"fmt " => {
+                    let mut fmt = vec![0u8; size as usize];
+                    file.read_exact(&mut fmt).await?;
+                    let u16_at = |i: usize| fmt.get(i..i + 2)
+                        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+                        .ok_or_else(|| invalid("short fmt chunk"));
+                    let (encoding, channels, bits) = (u16_at(0)?, u16_at(2)?, u16_at(14)?);
+                    let sample_rate = fmt.get(4..8)
+                        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                        .ok_or_else(|| invalid("short fmt chunk"))?;
+                    if encoding != 1 || bits != 16 || channels == 0 {
+                        return Err(invalid("only 16-bit PCM is supported"));
+                    }
+                    format = Some((channels, sample_rate));
+                }
+                b"data" => {
+                    let (channels, sample_rate) = format.ok_or_else(|| invalid("data chunk before fmt chunk"))?;
+                    return Ok(Self { file, data_offset: offset, data_len: size, sample_rate, channels });
+                }
+                _ => {
+                    file.seek(SeekFrom::Current(size as i64)).await?;
+                }
+            }
+            // Chunks are padded to an even size
+            offset += size + size % 2;
+            if size % 2 == 1 {
+                file.seek(SeekFrom::Current(1)).await?;
+            }
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Length of the audio in seconds
+    pub fn duration(&self) -> f64 {
+        self.data_len as f64 / (2 * self.channels as u64) as f64 / self.sample_rate as f64
+    }
+
+    /// Samples of the first channel between `start` and `end` seconds
+    pub async fn read(&mut self, start: f64, end: f64) -> Result<Vec<i16>> {
+        let frame_bytes = 2 * self.channels as u64;
+        let frames = self.data_len / frame_bytes;
+        let position = |seconds: f64| ((seconds.max(0.0) * self.sample_rate as f64) as u64).min(frames);
+        let (first, last) = (position(start), position(end));
+        if last <= first {
+            return Ok(Vec::new());
+        }
+
+        let mut bytes = vec![0u8; ((last - first) * frame_bytes) as usize];
+        self.file.seek(SeekFrom::Start(self.data_offset + first * frame_bytes)).await?;
+        self.file.read_exact(&mut bytes).await?;
+        Ok(bytes
+            .chunks_exact(frame_bytes as usize)
+            .map(|frame| i16::from_le_bytes([frame[0], frame[1]]))
+            .collect())
+    }
+}
+
+/// Tags segments with their delivery, measured against the median level of one audio file
+pub struct DeliveryTagger {
+    reader: WavReader,
+    median_db: f64,
+    config: DeliveryConfig,
+}
+
+impl DeliveryTagger {
+    /// Measure the median level of `audio_path` one second at a time; `None` when the file is silent
+    pub async fn open(audio_path: &Path, config: &DeliveryConfig) -> Result<Option<Self>> {
+        let mut reader = WavReader::open(audio_path).await?;
+        let mut levels = Vec::new();
+        let mut second = 0.0;
+        while second < reader.duration() {
+            let samples = reader.read(second, second + 1.0).await?;
+            let power = samples.iter().map(|&s| (s as f64 / 32_768.0).powi(2)).sum::<f64>() / samples.len().max(1) as f64;
+            if to_db(power) >= SILENCE_DB {
+                levels.push(to_db(power));
+            }
+            second += 1.0;
+        }
+        if levels.is_empty() {
+            return Ok(None);
+        }
+        levels.sort_by(f64::total_cmp);
+        let median_db = levels[levels.len() / 2];
+        Ok(Some(Self { reader, median_db, config: config.clone() }))
+    }
+
+    /// Store the delivery of every segment that stands out; returns how many were tagged
+    pub async fn tag(&mut self, segments: &mut [TranscriptionSegment]) -> Result<usize> {
+        let mut tagged = 0;
+        for segment in segments {
+            let samples = self.reader.read(segment.start, segment.end).await?;
+            let features = analyze(&samples, self.reader.sample_rate());
+            match classify(&features, self.median_db, &self.config) {
+                Some(delivery) => {
+                    segment.metadata.insert(&DELIVERY, delivery);
+                    tagged += 1;
+                }
+                None => segment.metadata.remove(&DELIVERY),
+            }
+        }
+        Ok(tagged)
+    }
+}
+
+/// Output a transcription is written to, each selected separately under `[delivery.marks]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeliveryOutput {
+    /// The `.srt` file and the video it is burned into
+    Srt,
+    /// The styled `.ass` file
+    Ass,
+    /// The forced-narrative `.forced.srt` file
+    Forced,
+}
+
+/// Copy of `transcription` with the delivery of its segments marked in their text, when `output`
+/// is selected in `delivery.marks`
+pub fn mark_deliveries(transcription: &Transcription, config: &DeliveryConfig, output: DeliveryOutput) -> Transcription {
+    let selected = match output {
+        DeliveryOutput::Srt => config.marks.srt,
+        DeliveryOutput::Ass => config.marks.ass,
+        DeliveryOutput::Forced => config.marks.forced,
+    };
+    let mut marked = transcription.clone();
+    if !selected {
+        return marked;
+    }
+    for segment in &mut marked.segments {
+        if let Some(delivery) = segment.metadata.get(&DELIVERY) {
+            segment.text = mark(&segment.text, delivery, config);
+        }
+    }
+    marked.text = marked.segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" ");
+    marked
+}
+
+fn mark(text: &str, delivery: Delivery, config: &DeliveryConfig) -> String {
+    match delivery {
+        Delivery::Shouting if config.shouting_caps => text.to_uppercase(),
+        Delivery::Singing if config.singing_notes => {
+            text.lines().map(|line| format!("♪ {} ♪", line.trim())).collect::<Vec<_>>().join("\n")
+        }
+        Delivery::Whispering if !config.whispering_label.is_empty() => format!("{} {}", config.whispering_label, text),
+        _ => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::parse_srt;
+
+    const RATE: u32 = 16_000;
+
+    /// Tone of `amplitude` whose pitch switches between `pitches` every `switch` seconds
+    fn tone(seconds: f64, amplitude: f64, pitches: &[f64], switch: f64) -> Vec<i16> {
+        let mut phase = 0.0;
+        (0..(seconds * RATE as f64) as usize)
+            .map(|i| {
+                let t = i as f64 / RATE as f64;
+                let pitch = pitches[(t / switch) as usize % pitches.len()];
+                phase += std::f64::consts::TAU * pitch / RATE as f64;
+                (phase.sin() * amplitude) as i16
+            })
+            .collect()
+    }
+
+    fn noise(seconds: f64, amplitude: f64) -> Vec<i16> {
+        let mut state = 12_345u32;
+        (0..(seconds * RATE as f64) as usize)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                ((state >> 16) as f64 / 32_768.0 - 1.0) * amplitude
+            })
+            .map(|s| s as i16)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_delivery_hints() {
+        let config = DeliveryConfig::default();
+        let speech = tone(2.0, 8_000.0, &[150.0, 220.0, 180.0], 0.1);
+        let shout = tone(2.0, 30_000.0, &[180.0, 260.0], 0.1);
+        let song = tone(2.0, 8_000.0, &[220.0, 330.0], 1.0);
+        let whisper = noise(2.0, 400.0);
+
+        let features = analyze(&speech, RATE);
+        assert!(features.voicing > 0.9 && features.sustained_pitch < 0.2, "{:?}", features);
+        assert!(analyze(&whisper, RATE).voicing < 0.3);
+        assert_eq!(analyze(&vec![0; 16_000], RATE).loudness_db, None);
+
+        // The file is mostly ordinary speech, which sets the median level
+        let mut samples = speech.repeat(4);
+        for part in [&shout, &song, &whisper] {
+            samples.extend_from_slice(part);
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audio.wav");
+        std::fs::write(&path, crate::preflight::pcm_wav(&samples)).unwrap();
+
+        let mut transcription = parse_srt(
+            "1\n00:00:00,000 --> 00:00:02,000\nWhere were you?\n\n\
+             2\n00:00:08,000 --> 00:00:10,000\nGet down!\nNow!\n\n\
+             3\n00:00:10,000 --> 00:00:12,000\nHappy birthday to you\n\n\
+             4\n00:00:12,000 --> 00:00:14,000\nThey can hear us\n",
+            "en",
+        ).unwrap();
+        let mut tagger = DeliveryTagger::open(&path, &config).await.unwrap().unwrap();
+        assert_eq!(tagger.tag(&mut transcription.segments).await.unwrap(), 3);
+        let deliveries: Vec<Option<Delivery>> = transcription.segments.iter().map(|s| s.metadata.get(&DELIVERY)).collect();
+        assert_eq!(deliveries, vec![None, Some(Delivery::Shouting), Some(Delivery::Singing), Some(Delivery::Whispering)]);
+
+        let marked = mark_deliveries(&transcription, &config, DeliveryOutput::Srt);
+        let texts: Vec<&str> = marked.segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["Where were you?", "GET DOWN!\nNOW!", "♪ Happy birthday to you ♪", "(whispering) They can hear us"]);
+        assert_eq!(mark_deliveries(&transcription, &config, DeliveryOutput::Forced).segments[1].text, "Get down!\nNow!");
+    }
+}
@@ -0,0 +1,233 @@
+// Speaker diarization
+//
+// Whisper does not tell speakers apart. With `diarization.enabled`, `diarization.command` is run
+// on the extracted audio before transcription starts; it prints who speaks when as RTTM lines
+// (`SPEAKER <file> <channel> <onset> <duration> <NA> <NA> <speaker> <NA> <NA>`), the format
+// pyannote writes and most diarization models can be scripted to print. Turns are cached next to
+// the extracted audio, so a rerun only diarizes again when the command changes.
+//
+// Each transcribed segment, streamed ones included, gets the `SPEAKER` metadata entry of the
+// speaker who talks most during it, named through `diarization.speaker_names`. Segment
+// normalization then never merges lines of two speakers. Speakers show up in the outputs as:
+//
+// - `label_format` prefixes on the cues where another speaker starts talking
+// - the `Name` field of ASS events, and their own color with the style's `speaker_colors`
+// - `<v>` voice tags in WebVTT files
+// - a `[Speaker]` section in translation prompts, and speaker names on context lines, with
+//   `hint_translator`, so pronouns and formality stay consistent per speaker
+//
+// Imported transcripts and embedded subtitles are not diarized; speakers they name are kept.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::sync::mpsc;
+use tracing::info;
+
+use crate::cancel::Cancellation;
+use crate::config::DiarizationConfig;
+use crate::error::{Result, ShuroError};
+use crate::metadata::SPEAKER;
+use crate::platform;
+use crate::quality::{Transcription, TranscriptionSegment};
+
+/// Stretch of audio one speaker talks in
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeakerTurn {
+    pub start: f64,
+    pub end: f64,
+    pub speaker: String,
+}
+
+/// Speaker turns of an audio file, in order of start time
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Speakers {
+    pub turns: Vec<SpeakerTurn>,
+}
+
+impl Speakers {
+    /// Turns of the `SPEAKER` lines of RTTM content, the speakers renamed by `names`; other and
+    /// unreadable lines are skipped
+    pub fn parse_rttm(content: &str, names: &BTreeMap<String, String>) -> Self {
+        let mut turns: Vec<SpeakerTurn> = content.lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                if fields.len() < 8 || fields[0] != "SPEAKER" {
+                    return None;
+                }
+                let start = fields[3].parse::<f64>().ok()?;
+                let duration = fields[4].parse::<f64>().ok()?;
+                let speaker = names.get(fields[7]).cloned().unwrap_or_else(|| fields[7].to_string());
+                Some(SpeakerTurn { start, end: start + duration, speaker })
+            })
+            .collect();
+        turns.sort_by(|a, b| a.start.total_cmp(&b.start));
+        Self { turns }
+    }
+
+    /// Speaker talking most between `start` and `end`, if anyone does
+    pub fn speaker_between(&self, start: f64, end: f64) -> Option<&str> {
+        let mut talk: HashMap<&str, f64> = HashMap::new();
+        for turn in self.turns.iter().filter(|t| t.start < end && t.end > start) {
+            *talk.entry(turn.speaker.as_str()).or_insert(0.0) += turn.end.min(end) - turn.start.max(start);
+        }
+        talk.into_iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(speaker, _)| speaker)
+    }
+
+    /// Label `segment` with its speaker unless it names one already; whether it was labelled
+    pub fn label(&self, segment: &mut TranscriptionSegment) -> bool {
+        if segment.metadata.contains(&SPEAKER) {
+            return false;
+        }
+        match self.speaker_between(segment.start, segment.end) {
+            Some(speaker) => {
+                segment.metadata.insert(&SPEAKER, speaker.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Label every segment of `transcription`, returning how many were labelled
+    pub fn label_all(&self, transcription: &mut Transcription) -> usize {
+        transcription.segments.iter_mut().map(|segment| self.label(segment)).filter(|&labelled| labelled).count()
+    }
+
+    /// Number of distinct speakers
+    pub fn count(&self) -> usize {
+        let mut speakers: Vec<&str> = self.turns.iter().map(|t| t.speaker.as_str()).collect();
+        speakers.sort_unstable();
+        speakers.dedup();
+        speakers.len()
+    }
+}
+
+/// Cached RTTM output of the configured command for `audio_path`
+fn cache_path(audio_path: &Path, config: &DiarizationConfig) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    config.command.hash(&mut hasher);
+    config.args.hash(&mut hasher);
+    audio_path.with_extension(format!("{:016x}.rttm", hasher.finish()))
+}
+
+/// Speaker turns of the audio at `audio_path`, found by `config.command` or read from the cache
+pub async fn diarize(audio_path: &Path, config: &DiarizationConfig, cancel: &Cancellation) -> Result<Speakers> {
+    if config.command.trim().is_empty() {
+        return Err(ShuroError::Config("diarization.enabled is set without a diarization.command".to_string()));
+    }
+    let cache_path = cache_path(audio_path, config);
+    if let Ok(rttm) = fs::read_to_string(&cache_path).await {
+        info!("Using cached speaker turns");
+        return Ok(Speakers::parse_rttm(&rttm, &config.speaker_names));
+    }
+
+    info!("🗣️ Diarizing speakers with {}", config.command);
+    let audio = platform::process_path(audio_path);
+    let args: Vec<String> = config.args.iter().map(|arg| arg.replace("{audio}", &audio)).collect();
+    let output = cancel.run(async {
+        platform::command(&config.command)?
+            .args(&args)
+            .output()
+            .await
+            .map_err(|e| ShuroError::Diarization(format!("Failed to execute {}: {}", config.command, e)))
+    }).await?;
+    if !output.status.success() {
+        return Err(ShuroError::Diarization(format!(
+            "{} failed: {}", config.command, String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let rttm = String::from_utf8_lossy(&output.stdout).to_string();
+    let speakers = Speakers::parse_rttm(&rttm, &config.speaker_names);
+    if speakers.turns.is_empty() {
+        return Err(ShuroError::Diarization(format!("{} printed no RTTM speaker turns", config.command)));
+    }
+    fs::write(&cache_path, rttm).await?;
+    info!("🗣️ Found {} speakers in {} turns", speakers.count(), speakers.turns.len());
+    Ok(speakers)
+}
+
+/// Segments of `segments` labelled with their speakers as they arrive; without diarized
+/// `speakers` they are passed on as they are
+pub fn label_stream(
+    mut segments: mpsc::UnboundedReceiver<TranscriptionSegment>,
+    speakers: Option<Speakers>,
+) -> mpsc::UnboundedReceiver<TranscriptionSegment> {
+    let Some(speakers) = speakers else {
+        return segments;
+    };
+    let (sender, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(mut segment) = segments.recv().await {
+            speakers.label(&mut segment);
+            // The receiver may have stopped early, like the transcriber's
+            let _ = sender.send(segment);
+        }
+    });
+    receiver
+}
+
+/// `transcription` with `label_format` put before the cues where another speaker starts talking
+pub fn label_speakers(transcription: &Transcription, label_format: &str) -> Transcription {
+    let mut labelled = transcription.clone();
+    if label_format.is_empty() {
+        return labelled;
+    }
+    let mut previous = None;
+    for segment in &mut labelled.segments {
+        let speaker = segment.metadata.get(&SPEAKER);
+        if let Some(name) = &speaker
+            && previous.as_ref() != Some(name) {
+            segment.text = format!("{}{}", label_format.replace("{speaker}", name), segment.text.trim_start());
+        }
+        previous = speaker;
+    }
+    labelled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::parse_srt;
+
+    #[test]
+    fn test_label_speakers() {
+        let rttm = "\
+SPEAKER ep1 1 0.500 2.000 <NA> <NA> SPEAKER_00 <NA> <NA>\n\
+SPEAKER ep1 1 2.400 3.100 <NA> <NA> SPEAKER_01 <NA> <NA>\n\
+;; comment\n\
+SPEAKER ep1 1 6.000 oops <NA> <NA> SPEAKER_00 <NA> <NA>\n\
+SPEAKER ep1 1 6.000 2.000 <NA> <NA> SPEAKER_00 <NA> <NA>\n";
+        let names = BTreeMap::from([("SPEAKER_00".to_string(), "Mika".to_string())]);
+        let speakers = Speakers::parse_rttm(rttm, &names);
+        assert_eq!(speakers.turns.len(), 3);
+        assert_eq!(speakers.count(), 2);
+        assert_eq!(speakers.turns[1], SpeakerTurn { start: 2.4, end: 5.5, speaker: "SPEAKER_01".to_string() });
+
+        let mut transcription = parse_srt(
+            "1\n00:00:00,400 --> 00:00:02,600\nWhere were you?\n\n\
+             2\n00:00:03,000 --> 00:00:04,000\nOut.\n\n\
+             3\n00:00:04,000 --> 00:00:05,000\nAll night?\n\n\
+             4\n00:00:05,200 --> 00:00:07,500\nAnswer me.\n\n\
+             5\n00:00:09,000 --> 00:00:10,000\n(door slams)\n",
+            "en",
+        ).unwrap();
+        transcription.segments[2].metadata.insert(&SPEAKER, "Ren".to_string());
+        assert_eq!(speakers.label_all(&mut transcription), 3);
+        let labels: Vec<Option<String>> = transcription.segments.iter().map(|s| s.metadata.get(&SPEAKER)).collect();
+        let name = |n: &str| Some(n.to_string());
+        // The speaker talking most wins; speakers named already are kept
+        assert_eq!(labels, vec![name("Mika"), name("SPEAKER_01"), name("Ren"), name("Mika"), None]);
+
+        let labelled = label_speakers(&transcription, "{speaker}: ");
+        let texts: Vec<&str> = labelled.segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["Mika: Where were you?", "SPEAKER_01: Out.", "Ren: All night?", "Mika: Answer me.", "(door slams)"]);
+        transcription.segments[2].metadata.insert(&SPEAKER, "SPEAKER_01".to_string());
+        assert_eq!(label_speakers(&transcription, "- ").segments[2].text, "All night?");
+        assert_eq!(label_speakers(&transcription, "").segments[0].text, "Where were you?");
+    }
+}
@@ -0,0 +1,174 @@
+// Free disk space checks
+//
+// ffmpeg dies with a cryptic write error when the disk fills up halfway through extracting audio
+// or writing a video. Before those steps, the space they need is estimated from the duration and
+// bitrate ffprobe reports for the input and compared with the free space in the cache and output
+// locations, so a run that cannot fit fails before it starts with an error naming the location.
+// Locations on the same filesystem are checked against their combined needs, and
+// `resources.disk_headroom_mb` is kept free on top of the estimate. Free space is queried on Unix
+// only; elsewhere, and with `resources.check_disk_space = false`, nothing is checked.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use crate::error::{Result, ShuroError};
+
+/// Bytes per second of the 16 kHz mono 16-bit audio extracted for whisper
+const AUDIO_BYTES_PER_SECOND: f64 = 32_000.0;
+
+/// Duration and size of a media file, as reported by ffprobe
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MediaSize {
+    /// Seconds
+    pub duration: Option<f64>,
+    /// Bits per second over all streams
+    pub bit_rate: Option<u64>,
+    /// Bytes
+    pub size: Option<u64>,
+}
+
+impl MediaSize {
+    /// Bytes of a copy of the file with the same streams (muxing, or a burn-in at a similar
+    /// bitrate)
+    pub fn video_bytes(&self) -> Option<u64> {
+        match (self.duration, self.bit_rate) {
+            (Some(duration), Some(bit_rate)) => Some((duration * bit_rate as f64 / 8.0) as u64),
+            _ => self.size,
+        }
+    }
+
+    /// Bytes of the extracted audio, including the tempo variant tuned mode writes next to it
+    pub fn audio_bytes(&self) -> Option<u64> {
+        self.duration.map(|duration| (duration.max(0.0) * AUDIO_BYTES_PER_SECOND * 2.0) as u64)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeOutput {
+    format: Option<ProbeFormat>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeFormat {
+    duration: Option<String>,
+    bit_rate: Option<String>,
+    size: Option<String>,
+}
+
+/// Parse `ffprobe -show_entries format=duration,bit_rate,size -of json` output
+pub fn parse_media_size(json: &str) -> Result<MediaSize> {
+    let output: ProbeOutput = serde_json::from_str(json)
+        .map_err(|e| ShuroError::Media(format!("Failed to parse ffprobe output: {}", e)))?;
+    let Some(format) = output.format else {
+        return Ok(MediaSize::default());
+    };
+    Ok(MediaSize {
+        duration: format.duration.and_then(|d| d.parse().ok()),
+        bit_rate: format.bit_rate.and_then(|b| b.parse().ok()),
+        size: format.size.and_then(|s| s.parse().ok()),
+    })
+}
+
+/// Space a step needs in one location
+#[derive(Debug, Clone)]
+pub struct SpaceRequirement {
+    pub path: PathBuf,
+    pub bytes: u64,
+    /// What the space is for, e.g. "extracted audio"
+    pub purpose: String,
+}
+
+impl SpaceRequirement {
+    pub fn new(path: &Path, bytes: u64, purpose: &str) -> Self {
+        Self { path: path.to_path_buf(), bytes, purpose: purpose.to_string() }
+    }
+}
+
+/// Free bytes and filesystem id of the filesystem holding `path`, or its closest existing
+/// ancestor; `None` where free space is unknown
+#[cfg(unix)]
+pub fn free_space(path: &Path) -> Option<(u64, u64)> {
+    let existing = path.ancestors().find(|p| !p.as_os_str().is_empty() && p.exists()).unwrap_or(Path::new("."));
+    match rustix::fs::statvfs(existing) {
+        Ok(stat) => Some((stat.f_bavail.saturating_mul(stat.f_frsize), stat.f_fsid)),
+        Err(e) => {
+            debug!("Failed to query free space of {}: {}", existing.display(), e);
+            None
+        }
+    }
+}
+
+/// Free bytes and filesystem id of the filesystem holding `path`, or its closest existing
+/// ancestor; `None` where free space is unknown
+#[cfg(not(unix))]
+pub fn free_space(path: &Path) -> Option<(u64, u64)> {
+    debug!("Free space of {} is not checked on this platform", path.display());
+    None
+}
+
+/// Fail when a filesystem lacks the space the requirements on it add up to, plus `headroom` bytes
+pub fn check_free_space(requirements: &[SpaceRequirement], headroom: u64) -> Result<()> {
+    let mut filesystems: Vec<(u64, u64, Vec<&SpaceRequirement>)> = Vec::new();
+    for requirement in requirements.iter().filter(|r| r.bytes > 0) {
+        let Some((free, id)) = free_space(&requirement.path) else { continue };
+        match filesystems.iter_mut().find(|(fs_id, _, _)| *fs_id == id) {
+            Some((_, _, on_fs)) => on_fs.push(requirement),
+            None => filesystems.push((id, free, vec![requirement])),
+        }
+    }
+
+    for (_, free, on_fs) in filesystems {
+        let needed = on_fs.iter().map(|r| r.bytes).sum::<u64>().saturating_add(headroom);
+        debug!("Disk space: {} needed, {} free", format_bytes(needed), format_bytes(free));
+        if needed > free {
+            let uses: Vec<String> = on_fs.iter()
+                .map(|r| format!("{} for {} in {}", format_bytes(r.bytes), r.purpose, r.path.display()))
+                .collect();
+            return Err(ShuroError::DiskSpace(format!(
+                "about {} is needed ({}, plus {} headroom) but only {} is free; free up space or choose \
+                 another location (set resources.check_disk_space = false to skip this check)",
+                format_bytes(needed), uses.join(", "), format_bytes(headroom), format_bytes(free)
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Human-readable size in binary units
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_space_estimates_and_check() {
+        let size = parse_media_size(r#"{"format": {"duration": "1800.000", "bit_rate": "4000000", "size": "901234567"}}"#).unwrap();
+        assert_eq!(size.video_bytes(), Some(900_000_000));
+        assert_eq!(size.audio_bytes(), Some(115_200_000));
+        assert_eq!(parse_media_size(r#"{"format": {"size": "1000"}}"#).unwrap().video_bytes(), Some(1000));
+        assert_eq!(format_bytes(1536 * 1024 * 1024), "1.5 GB");
+
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("not").join("created");
+        assert!(check_free_space(&[SpaceRequirement::new(&missing, 1, "subtitles")], 0).is_ok());
+        if free_space(dir.path()).is_some() {
+            let huge = [SpaceRequirement::new(dir.path(), u64::MAX / 2, "burned-in videos")];
+            assert!(matches!(check_free_space(&huge, 0), Err(ShuroError::DiskSpace(_))));
+        }
+    }
+}
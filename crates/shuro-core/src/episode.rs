@@ -0,0 +1,135 @@
+// Series and episode numbering
+//
+// Library files are usually named like `Show.Name.S01E02.1080p.mkv` or `show_name_1x02.mp4`. The
+// series, season and episode parsed from the file name are recorded in the processing manifest,
+// available to the `media.output_template` naming template and written to the container tags of
+// burned-in and muxed outputs, so the outputs of a batch run stay sorted by episode in media
+// libraries. Files without such a pattern keep their plain names.
+
+use serde::{Deserialize, Serialize};
+
+/// Series, season and episode of a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpisodeInfo {
+    /// Series name: the file name before the pattern with separators turned into spaces
+    pub series: Option<String>,
+    pub season: u32,
+    pub episode: u32,
+}
+
+impl EpisodeInfo {
+    /// Parse `SxxEyy` (any case, also `S01.E02` and `S01 E02`) or `1x02` from a file stem
+    pub fn parse(stem: &str) -> Option<Self> {
+        let bytes = stem.as_bytes();
+        let (position, season, episode, _) = (0..bytes.len())
+            .filter(|&i| i == 0 || !bytes[i - 1].is_ascii_alphanumeric())
+            .find_map(|i| season_episode(&stem[i..]).map(|(season, episode, len)| (i, season, episode, len)))?;
+
+        let series = stem[..position]
+            .split(['.', '_', '-', ' '])
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        Some(Self { series: (!series.is_empty()).then_some(series), season, episode })
+    }
+
+    /// `S01E02` form of the season and episode
+    pub fn code(&self) -> String {
+        format!("S{:02}E{:02}", self.season, self.episode)
+    }
+
+    /// Container tags describing the episode, as `key=value` metadata
+    pub fn container_tags(&self) -> Vec<String> {
+        let mut tags = vec![
+            format!("season_number={}", self.season),
+            format!("episode_sort={}", self.episode),
+            format!("episode_id={}", self.code()),
+        ];
+        if let Some(series) = &self.series {
+            tags.push(format!("show={}", series));
+        }
+        tags
+    }
+}
+
+/// Season, episode and matched length of a pattern at the start of `text`; the pattern must not
+/// run into further digits or letters
+fn season_episode(text: &str) -> Option<(u32, u32, usize)> {
+    let (season, episode, len) = if let Some(rest) = text.strip_prefix(['S', 's']) {
+        let (season, season_len) = leading_number(rest)?;
+        let rest = &rest[season_len..];
+        let separator = rest.len() - rest.trim_start_matches(['.', ' ', '_']).len();
+        let rest = rest[separator..].strip_prefix(['E', 'e'])?;
+        let (episode, episode_len) = leading_number(rest)?;
+        (season, episode, 1 + season_len + separator + 1 + episode_len)
+    } else {
+        let (season, season_len) = leading_number(text)?;
+        let rest = text[season_len..].strip_prefix(['x', 'X'])?;
+        let (episode, episode_len) = leading_number(rest)?;
+        if season_len > 2 || episode_len < 2 {
+            return None;
+        }
+        (season, episode, season_len + 1 + episode_len)
+    };
+    match text.as_bytes().get(len) {
+        Some(next) if next.is_ascii_alphanumeric() => None,
+        _ => Some((season, episode, len)),
+    }
+}
+
+/// Number made of the leading ASCII digits (at most 3) of `text` and its length
+fn leading_number(text: &str) -> Option<(u32, usize)> {
+    let len = text.bytes().take_while(u8::is_ascii_digit).count();
+    if len == 0 || len > 3 {
+        return None;
+    }
+    Some((text[..len].parse().ok()?, len))
+}
+
+/// Render an output name template for the outputs of `stem` in `language`
+///
+/// Placeholders: `{stem}`, `{lang}`, `{series}`, `{season}`, `{episode}` (zero-padded to two
+/// digits) and `{code}` (`S01E02`). Templates using episode placeholders fall back to
+/// `{stem}_{lang}` for files without an episode pattern.
+pub fn render_output_name(template: &str, stem: &str, language: &str, episode: Option<&EpisodeInfo>) -> String {
+    let uses_episode = ["{series}", "{season}", "{episode}", "{code}"].iter().any(|p| template.contains(p));
+    let mut name = match episode {
+        None if uses_episode => return format!("{}_{}", stem, language),
+        _ => template.replace("{stem}", stem).replace("{lang}", language),
+    };
+    if let Some(episode) = episode {
+        name = name
+            .replace("{series}", episode.series.as_deref().unwrap_or(stem))
+            .replace("{season}", &format!("{:02}", episode.season))
+            .replace("{episode}", &format!("{:02}", episode.episode))
+            .replace("{code}", &episode.code());
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_episode() {
+        let info = EpisodeInfo::parse("Show.Name.S01E02.1080p.WEB").unwrap();
+        assert_eq!(info, EpisodeInfo { series: Some("Show Name".to_string()), season: 1, episode: 2 });
+        assert_eq!(info.code(), "S01E02");
+
+        assert_eq!(EpisodeInfo::parse("show_name_s2.e10").unwrap().episode, 10);
+        assert_eq!(EpisodeInfo::parse("show - 3x07").unwrap(), EpisodeInfo { series: Some("show".to_string()), season: 3, episode: 7 });
+        assert_eq!(EpisodeInfo::parse("S03E04").unwrap().series, None);
+        assert_eq!(EpisodeInfo::parse("movie.1920x1080"), None);
+        assert_eq!(EpisodeInfo::parse("Seasons.Greetings"), None);
+        assert_eq!(EpisodeInfo::parse("ep1"), None);
+    }
+
+    #[test]
+    fn test_render_output_name() {
+        let info = EpisodeInfo::parse("Show.Name.S01E02.1080p").unwrap();
+        assert_eq!(render_output_name("{stem}_{lang}", "movie", "ja", None), "movie_ja");
+        assert_eq!(render_output_name("{series} - {code} [{lang}]", "Show.Name.S01E02.1080p", "ja", Some(&info)), "Show Name - S01E02 [ja]");
+        assert_eq!(render_output_name("{series} {season}x{episode}", "movie", "ja", None), "movie_ja");
+    }
+}
@@ -0,0 +1,94 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ShuroError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("TOML parsing error: {0}")]
+    Toml(#[from] toml::de::Error),
+
+    #[error("HTTP request error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Service unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("Transcription error: {0}")]
+    Transcriber(String),
+
+    #[error("Translation error: {0}")]
+    Translation(String),
+
+    #[error("Media processing error: {0}")]
+    Media(String),
+
+    #[error("Quality validation error: {0}")]
+    Quality(String),
+
+    #[error("Hallucination detected in transcription")]
+    Hallucination,
+
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    #[error("File not found: {0}")]
+    FileNotFound(String),
+
+    #[error("Unsupported format: {0}")]
+    UnsupportedFormat(String),
+
+    #[error("Cache error: {0}")]
+    Cache(String),
+
+    #[error("Insufficient disk space: {0}")]
+    DiskSpace(String),
+
+    #[error("Preflight check failed: {0}")]
+    Preflight(String),
+
+    #[error("Alignment error: {0}")]
+    Alignment(String),
+
+    #[error("Diarization error: {0}")]
+    Diarization(String),
+
+    #[error("Invalid transcript: {0}")]
+    Transcript(String),
+
+    #[error("Cost budget exceeded: {0}")]
+    BudgetExceeded(String),
+
+    #[error("Operation cancelled")]
+    Cancelled,
+
+    #[error("Deadline exceeded")]
+    DeadlineExceeded,
+}
+
+impl ShuroError {
+    /// Whether this error was caused by cancellation or an expired deadline
+    pub fn is_cancellation(&self) -> bool {
+        matches!(self, Self::Cancelled | Self::DeadlineExceeded)
+    }
+
+    /// Whether this error is a failure of an external service that may be gone on a retry: an
+    /// unreachable or overloaded service, a timeout, or a 429 or 5xx answer
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Unavailable(_) => true,
+            Self::Http(e) => e.is_connect() || e.is_timeout() || e.status().is_some_and(is_transient_status),
+            _ => false,
+        }
+    }
+}
+
+/// Whether an HTTP answer with `status` is worth retrying
+pub fn is_transient_status(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+pub type Result<T> = std::result::Result<T, ShuroError>; 
\ No newline at end of file
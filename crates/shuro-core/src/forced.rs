@@ -0,0 +1,141 @@
+// Forced-narrative subtitles
+//
+// A forced track only carries the lines a viewer who understands the main audio language still
+// needs: foreign dialogue and on-screen text. With `forced.enabled`, every translation also gets
+// a `{stem}_{lang}.forced.srt` with the cues whose source segment is in another language than the
+// main audio. A segment counts as foreign when the transcriber tagged it with another language,
+// or when its text is written in another script than the main language (whisper tags whole files
+// with one language, so Japanese lines in English audio are recognized by their script, while
+// French lines in English audio are not). Lines already in the target language's script are left
+// out, since the viewer reads them as spoken. Muxing marks `.forced.` files with the forced
+// disposition.
+
+use crate::media::same_language;
+use crate::metadata::SOURCE_LANGUAGE;
+use crate::quality::{Transcription, TranscriptionSegment};
+
+/// Writing system of a text or language
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    /// Han characters and kana (Chinese and Japanese)
+    Cjk,
+    Hangul,
+    Thai,
+    Devanagari,
+}
+
+impl Script {
+    fn of_char(c: char) -> Option<Self> {
+        match c {
+            'a'..='z' | 'A'..='Z' | '\u{00C0}'..='\u{024F}' => Some(Self::Latin),
+            '\u{0370}'..='\u{03FF}' => Some(Self::Greek),
+            '\u{0400}'..='\u{04FF}' => Some(Self::Cyrillic),
+            '\u{0590}'..='\u{05FF}' => Some(Self::Hebrew),
+            '\u{0600}'..='\u{06FF}' => Some(Self::Arabic),
+            '\u{0900}'..='\u{097F}' => Some(Self::Devanagari),
+            '\u{0E00}'..='\u{0E7F}' => Some(Self::Thai),
+            '\u{3040}'..='\u{30FF}' | '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' => Some(Self::Cjk),
+            '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' => Some(Self::Hangul),
+            _ => None,
+        }
+    }
+
+    /// Script most letters of `text` are written in
+    pub fn dominant(text: &str) -> Option<Self> {
+        let mut counts: Vec<(Self, usize)> = Vec::new();
+        for script in text.chars().filter_map(Self::of_char) {
+            match counts.iter_mut().find(|(s, _)| *s == script) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((script, 1)),
+            }
+        }
+        counts.into_iter().max_by_key(|(_, count)| *count).map(|(script, _)| script)
+    }
+
+    /// Script a language is usually written in, `None` for unknown languages
+    pub fn of_language(code: &str) -> Option<Self> {
+        let code = code.to_lowercase();
+        let code = code.split(['-', '_']).next().unwrap_or_default();
+        match code {
+            "ja" | "zh" => Some(Self::Cjk),
+            "ko" => Some(Self::Hangul),
+            "ru" | "uk" | "be" | "bg" | "mk" | "sr" | "kk" | "ky" | "tg" => Some(Self::Cyrillic),
+            "el" => Some(Self::Greek),
+            "ar" | "fa" | "ur" => Some(Self::Arabic),
+            "he" | "yi" => Some(Self::Hebrew),
+            "th" => Some(Self::Thai),
+            "hi" | "mr" | "ne" => Some(Self::Devanagari),
+            "en" | "fr" | "de" | "es" | "it" | "pt" | "pl" | "nl" | "tr" | "vi" | "sv" | "da" | "no"
+            | "fi" | "hu" | "cs" | "sk" | "hr" | "sl" | "et" | "lv" | "lt" | "mt" | "ga" | "cy" | "eu"
+            | "ca" | "gl" | "is" | "sq" | "az" | "uz" | "ro" | "id" | "ms" => Some(Self::Latin),
+            _ => None,
+        }
+    }
+}
+
+/// Whether a source segment is in another language than the main audio language
+pub fn is_foreign(segment: &TranscriptionSegment, main_language: &str) -> bool {
+    if segment.metadata.get(&SOURCE_LANGUAGE).is_some_and(|language| !same_language(&language, main_language)) {
+        return true;
+    }
+    match (Script::dominant(&segment.text), Script::of_language(main_language)) {
+        (Some(script), Some(main)) => script != main,
+        _ => false,
+    }
+}
+
+/// Cues of `translated` whose source segment is foreign dialogue the viewer needs subtitled
+///
+/// `translated` holds the translations of `source`'s segments in the same order.
+pub fn forced_subtitles(source: &Transcription, translated: &Transcription, target_language: &str) -> Transcription {
+    let target_script = Script::of_language(target_language);
+    let segments: Vec<TranscriptionSegment> = source.segments
+        .iter()
+        .zip(&translated.segments)
+        .filter(|(original, _)| is_foreign(original, &source.language))
+        .filter(|(original, _)| target_script.is_none() || Script::dominant(&original.text) != target_script)
+        .enumerate()
+        .map(|(id, (_, cue))| TranscriptionSegment { id: id as i32, ..cue.clone() })
+        .collect();
+
+    Transcription {
+        text: segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" "),
+        segments,
+        language: translated.language.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::parse_srt;
+
+    #[test]
+    fn test_forced_subtitles_keep_foreign_lines() {
+        let source = parse_srt(
+            "1\n00:00:01,000 --> 00:00:02,000\nWhere are we?\n\n2\n00:00:03,000 --> 00:00:04,000\nここはどこ？\n\n\
+             3\n00:00:05,000 --> 00:00:06,000\nПривет\n",
+            "en",
+        ).unwrap();
+        let translated = parse_srt(
+            "1\n00:00:01,000 --> 00:00:02,000\n¿Dónde estamos?\n\n2\n00:00:03,000 --> 00:00:04,000\n¿Dónde es esto?\n\n\
+             3\n00:00:05,000 --> 00:00:06,000\nHola\n",
+            "es",
+        ).unwrap();
+
+        let forced = forced_subtitles(&source, &translated, "es");
+        let texts: Vec<&str> = forced.segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["¿Dónde es esto?", "Hola"]);
+        assert_eq!(forced.segments[0].start, 3.0);
+
+        // Japanese viewers read the Japanese line as spoken
+        let forced = forced_subtitles(&source, &translated, "ja");
+        assert_eq!(forced.segments.len(), 1);
+        assert_eq!(forced.segments[0].text, "Hola");
+    }
+}
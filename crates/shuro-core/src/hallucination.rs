@@ -0,0 +1,248 @@
+// Hallucination rules
+//
+// Whisper invents text for silence, music and noise: the sign-offs of the videos it was trained
+// on ("Thanks for watching!"), a phrase looped until the window ends, or a single word stretched
+// over half a minute. Every transcribed segment is checked against the rules below, each with its
+// thresholds in `quality.hallucination`:
+//
+// - no_speech: whisper rates the segment likely silence and its text is not repetitive
+// - compression: the text compresses too well, i.e. it repeats itself
+// - repeated_ngram: one run of `ngram_size` words occurs more than `max_ngram_repeats` times
+// - known_phrase: the text contains a phrase whisper is known to invent, from the built-in list
+//   of the segment's language and `phrases`
+// - too_fast / too_slow: more characters per second than anyone speaks, or a long segment with
+//   hardly any text
+//
+// A segment any rule fires on gets the `HALLUCINATION` metadata entry with the highest confidence
+// of its hits. `HallucinationReport` lists the hits per segment, written as
+// `{video}.hallucinations.json` with `hallucination.report`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::config::HallucinationConfig;
+use crate::metadata::SOURCE_LANGUAGE;
+use crate::quality::{Transcription, TranscriptionSegment};
+
+/// Phrases whisper invents, by language; from subtitle credits and video sign-offs in its training data
+const BUILTIN_PHRASES: &[(&str, &[&str])] = &[
+    ("en", &[
+        "thanks for watching", "thank you for watching", "please subscribe", "like and subscribe",
+        "subscribe to my channel", "subtitles by the amara.org community",
+    ]),
+    ("ja", &["ご視聴ありがとうございました", "チャンネル登録よろしくお願いします"]),
+    ("zh", &["感谢观看", "字幕由amara.org社区提供", "请不吝点赞 订阅 转发 打赏支持明镜与点点栏目"]),
+    ("ko", &["시청해주셔서 감사합니다", "구독과 좋아요 부탁드립니다"]),
+    ("de", &["vielen dank fürs zuschauen", "untertitel im auftrag des zdf", "untertitel der amara.org-community"]),
+    ("fr", &["merci d'avoir regardé", "sous-titres réalisés par la communauté d'amara.org"]),
+    ("es", &["gracias por ver el video", "subtítulos realizados por la comunidad de amara.org"]),
+];
+
+/// Rule flagging a segment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HallucinationRule {
+    NoSpeech,
+    Compression,
+    RepeatedNgram,
+    KnownPhrase,
+    TooFast,
+    TooSlow,
+}
+
+/// A rule firing on a segment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleHit {
+    pub rule: HallucinationRule,
+    /// How sure the rule is (0.0 to 1.0)
+    pub confidence: f64,
+    /// What the rule found, for people reading the report
+    pub detail: String,
+}
+
+/// Segment with the rules that fired on it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlaggedSegment {
+    pub id: i32,
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    pub hits: Vec<RuleHit>,
+}
+
+/// Segments of a transcription flagged by the hallucination rules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HallucinationReport {
+    pub language: String,
+    pub segments: Vec<FlaggedSegment>,
+}
+
+impl HallucinationReport {
+    pub fn new(transcription: &Transcription, config: &HallucinationConfig) -> Self {
+        let segments = transcription.segments.iter()
+            .filter_map(|segment| {
+                let hits = check_segment(segment, &transcription.language, config);
+                (!hits.is_empty()).then(|| FlaggedSegment {
+                    id: segment.id,
+                    start: segment.start,
+                    end: segment.end,
+                    text: segment.text.clone(),
+                    hits,
+                })
+            })
+            .collect();
+        Self { language: transcription.language.clone(), segments }
+    }
+}
+
+/// Every rule firing on `segment` of a transcription in `language`
+pub fn check_segment(segment: &TranscriptionSegment, language: &str, config: &HallucinationConfig) -> Vec<RuleHit> {
+    let text = segment.text.trim();
+    if text.is_empty() {
+        return Vec::new();
+    }
+    let language = segment.metadata.get(&SOURCE_LANGUAGE).unwrap_or_else(|| language.to_string());
+    let mut hits = Vec::new();
+
+    if segment.no_speech_prob > config.no_speech_prob && segment.compression_ratio < config.no_speech_max_compression {
+        hits.push(RuleHit {
+            rule: HallucinationRule::NoSpeech,
+            confidence: segment.no_speech_prob as f64,
+            detail: format!("no speech probability {:.2}", segment.no_speech_prob),
+        });
+    }
+    if segment.compression_ratio > config.max_compression_ratio {
+        hits.push(RuleHit {
+            rule: HallucinationRule::Compression,
+            confidence: ((segment.compression_ratio - config.max_compression_ratio) as f64 / 10.0).min(1.0),
+            detail: format!("compression ratio {:.2}", segment.compression_ratio),
+        });
+    }
+    hits.extend(repeated_ngram(text, config));
+    hits.extend(known_phrase(text, &language, config));
+    hits.extend(reading_speed(text, segment.end - segment.start, config));
+    hits
+}
+
+/// Hit for the n-gram repeated most often, when it repeats too often
+fn repeated_ngram(text: &str, config: &HallucinationConfig) -> Option<RuleHit> {
+    let size = config.ngram_size;
+    let words: Vec<String> = text.unicode_words().map(|w| w.to_lowercase()).collect();
+    if size == 0 || words.len() < size * (config.max_ngram_repeats + 1) {
+        return None;
+    }
+    let mut counts: HashMap<&[String], usize> = HashMap::new();
+    for ngram in words.windows(size) {
+        *counts.entry(ngram).or_insert(0) += 1;
+    }
+    let (ngram, count) = counts.into_iter().max_by_key(|&(ngram, count)| (count, std::cmp::Reverse(ngram)))?;
+    (count > config.max_ngram_repeats).then(|| RuleHit {
+        rule: HallucinationRule::RepeatedNgram,
+        confidence: ((count * size) as f64 / words.len() as f64).min(1.0),
+        detail: format!("\"{}\" repeated {} times", ngram.join(" "), count),
+    })
+}
+
+/// Lowercase words of `text` separated by single spaces, padded with a space on both sides
+fn normalize(text: &str) -> String {
+    let words: String = text.chars()
+        .map(|c| if c.is_alphanumeric() { c.to_lowercase().collect() } else { " ".to_string() })
+        .collect();
+    format!(" {} ", words.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Hit for the longest known phrase of `language` the text contains
+fn known_phrase(text: &str, language: &str, config: &HallucinationConfig) -> Option<RuleHit> {
+    let builtin = BUILTIN_PHRASES.iter()
+        .filter(|(code, _)| config.builtin_phrases && code.eq_ignore_ascii_case(language))
+        .flat_map(|(_, phrases)| phrases.iter().copied());
+    let configured = config.phrases.iter()
+        .filter(|(code, _)| code.eq_ignore_ascii_case(language))
+        .flat_map(|(_, phrases)| phrases.iter().map(String::as_str));
+
+    let normalized = normalize(text);
+    let phrase = builtin.chain(configured)
+        .filter(|phrase| normalize(phrase).len() > 2 && normalized.contains(&normalize(phrase)))
+        .max_by_key(|phrase| phrase.chars().count())?;
+    // Confidence is the share of the text the phrase makes up
+    let share = normalize(phrase).chars().count() as f64 / normalized.chars().count() as f64;
+    Some(RuleHit {
+        rule: HallucinationRule::KnownPhrase,
+        confidence: share.min(1.0),
+        detail: format!("contains \"{}\"", phrase),
+    })
+}
+
+/// Hit for text far too long or far too short for the segment's duration
+fn reading_speed(text: &str, duration: f64, config: &HallucinationConfig) -> Option<RuleHit> {
+    if duration <= 0.0 {
+        return None;
+    }
+    let characters = text.graphemes(true).filter(|g| !g.trim().is_empty()).count();
+    let speed = characters as f64 / duration;
+    if config.max_chars_per_second > 0.0 && speed > config.max_chars_per_second {
+        return Some(RuleHit {
+            rule: HallucinationRule::TooFast,
+            confidence: ((speed - config.max_chars_per_second) / config.max_chars_per_second).min(1.0),
+            detail: format!("{} characters in {:.1}s", characters, duration),
+        });
+    }
+    if config.min_chars_per_second > 0.0 && duration >= config.sparse_min_duration && speed < config.min_chars_per_second {
+        return Some(RuleHit {
+            rule: HallucinationRule::TooSlow,
+            confidence: 1.0 - speed / config.min_chars_per_second,
+            detail: format!("{} characters in {:.1}s", characters, duration),
+        });
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::parse_srt;
+
+    fn rules(transcription: &Transcription, config: &HallucinationConfig) -> Vec<Vec<HallucinationRule>> {
+        transcription.segments.iter()
+            .map(|s| check_segment(s, &transcription.language, config).iter().map(|hit| hit.rule).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_check_segment() {
+        let transcription = parse_srt(
+            "1\n00:00:01,000 --> 00:00:03,000\nWe should go before dark.\n\n\
+             2\n00:00:04,000 --> 00:00:06,000\nThanks for watching!\n\n\
+             3\n00:00:07,000 --> 00:00:12,000\nI know, I know, I know, I know, I know, I know, I know.\n\n\
+             4\n00:00:13,000 --> 00:00:13,200\nThis line cannot possibly be said in a fifth of a second.\n\n\
+             5\n00:00:14,000 --> 00:00:44,000\nYou\n",
+            "en",
+        ).unwrap();
+        let config = HallucinationConfig::default();
+        use HallucinationRule::*;
+        assert_eq!(rules(&transcription, &config), vec![
+            vec![], vec![KnownPhrase], vec![RepeatedNgram], vec![TooFast], vec![TooSlow],
+        ]);
+
+        let hit = &check_segment(&transcription.segments[2], "en", &config)[0];
+        assert_eq!(hit.detail, "\"i know i\" repeated 6 times");
+
+        // Phrases are looked up in the segment's language, built-in ones can be turned off
+        assert!(check_segment(&transcription.segments[1], "de", &config).is_empty());
+        let config = HallucinationConfig {
+            builtin_phrases: false,
+            phrases: HashMap::from([("en".to_string(), vec!["before dark".to_string()])]),
+            ngram_size: 0,
+            max_chars_per_second: 0.0,
+            min_chars_per_second: 0.0,
+            ..config
+        };
+        assert_eq!(rules(&transcription, &config), vec![vec![KnownPhrase], vec![], vec![], vec![], vec![]]);
+
+        let japanese = parse_srt("1\n00:00:01,000 --> 00:00:03,000\n皆さん、ご視聴ありがとうございました。\n", "ja").unwrap();
+        let report = HallucinationReport::new(&japanese, &HallucinationConfig::default());
+        assert_eq!(report.segments[0].hits[0].rule, KnownPhrase);
+        assert!(serde_json::to_string(&report).unwrap().contains("\"rule\":\"known_phrase\""));
+    }
+}
@@ -0,0 +1,226 @@
+// Batch job state
+//
+// An overnight batch that dies at file 40 of 200 should not start over. `process_directory`
+// checkpoints its progress in `.shuro/state/<job-id>.json` (`state/` of the `--work-dir`) after
+// every language of every file, and `batch --resume` skips what the file records as done: files
+// with all their languages written are skipped, a file interrupted between languages is processed
+// again for the missing languages only, and failed files are retried. The job ID is derived from
+// the input and output directories and the set of target languages, so rerunning the same command
+// with `--resume` finds the state without naming it; without `--resume` a batch starts over and
+// replaces the state. Files are recorded by their path relative to the input directory. The
+// state is replaced atomically, so a crash while writing it leaves the previous checkpoint.
+//
+// `batch.on_error` decides what a failed file does to the rest of the batch: `Continue` goes on
+// and lists the failures at the end, `Abort` stops at the first one, and `Quarantine` goes on and
+// also keeps the failed videos in `retry-list.txt` in the output directory, one path per line,
+// rewritten after every failure and removed once a run of the batch has none.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::error::{Result, ShuroError};
+
+/// Progress of one batch job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobState {
+    pub id: String,
+    pub input_dir: PathBuf,
+    pub output_dir: PathBuf,
+    pub languages: BTreeSet<String>,
+    /// Unix timestamp of the first run of the job
+    pub started_at: u64,
+    /// Unix timestamp of the last checkpoint
+    pub updated_at: u64,
+    /// Languages written per file
+    #[serde(default)]
+    pub completed: BTreeMap<String, BTreeSet<String>>,
+    /// Error of the last attempt per failed file
+    #[serde(default)]
+    pub failed: BTreeMap<String, String>,
+}
+
+impl JobState {
+    pub fn new(input_dir: &Path, output_dir: &Path, languages: &[String]) -> Self {
+        let now = unix_now();
+        Self {
+            id: job_id(input_dir, output_dir, languages),
+            input_dir: input_dir.to_path_buf(),
+            output_dir: output_dir.to_path_buf(),
+            languages: languages.iter().cloned().collect(),
+            started_at: now,
+            updated_at: now,
+            completed: BTreeMap::new(),
+            failed: BTreeMap::new(),
+        }
+    }
+
+    /// Location of the state of job `id` in `state_dir`
+    pub fn path(state_dir: &Path, id: &str) -> PathBuf {
+        state_dir.join(format!("{}.json", id))
+    }
+
+    /// Saved state at `path`, if any
+    pub async fn load(path: &Path) -> Result<Option<Self>> {
+        if !fs::try_exists(path).await? {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path).await?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| ShuroError::Cache(format!("Failed to parse job state {}: {}", path.display(), e)))
+    }
+
+    /// Write the state to `path` through a temporary file
+    pub async fn save(&mut self, path: &Path) -> Result<()> {
+        self.updated_at = unix_now();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let temporary = path.with_extension("json.tmp");
+        fs::write(&temporary, serde_json::to_string_pretty(self)?).await?;
+        fs::rename(&temporary, path).await?;
+        Ok(())
+    }
+
+    /// Key a file is recorded under
+    pub fn file_key(&self, path: &Path) -> String {
+        path.strip_prefix(&self.input_dir).unwrap_or(path).to_string_lossy().into_owned()
+    }
+
+    /// Target languages of the job not yet written for `file`, in job order
+    pub fn remaining(&self, file: &str, languages: &[String]) -> Vec<String> {
+        let done = self.completed.get(file);
+        languages.iter()
+            .filter(|language| !done.is_some_and(|done| done.contains(*language)))
+            .cloned()
+            .collect()
+    }
+
+    pub fn complete(&mut self, file: &str, language: &str) {
+        self.completed.entry(file.to_string()).or_default().insert(language.to_string());
+        self.failed.remove(file);
+    }
+
+    pub fn fail(&mut self, file: &str, error: &str) {
+        self.failed.insert(file.to_string(), error.to_string());
+    }
+}
+
+/// File the failed videos of a batch are listed in with `batch.on_error = "Quarantine"`
+pub const RETRY_LIST: &str = "retry-list.txt";
+
+/// Write `files` to the retry list at `path`, one per line
+pub async fn write_retry_list(path: &Path, files: &[&Path]) -> Result<()> {
+    let lines: String = files.iter().map(|file| format!("{}\n", file.display())).collect();
+    fs::write(path, lines).await?;
+    Ok(())
+}
+
+/// Job state checkpointed to its file on every change
+pub struct JobProgress {
+    pub state: JobState,
+    path: PathBuf,
+}
+
+impl JobProgress {
+    /// State of the batch over `input_dir` into `output_dir` for `languages` in `state_dir`; the
+    /// saved one with `resume`, otherwise a new one
+    pub async fn open(state_dir: &Path, input_dir: &Path, output_dir: &Path, languages: &[String], resume: bool) -> Result<Self> {
+        let state = JobState::new(input_dir, output_dir, languages);
+        let path = JobState::path(state_dir, &state.id);
+        let saved = if resume { JobState::load(&path).await? } else { None };
+        let mut progress = Self { state: saved.unwrap_or(state), path };
+        progress.state.save(&progress.path).await?;
+        Ok(progress)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record that `language` of `file` is written
+    pub async fn complete(&mut self, file: &Path, language: &str) -> Result<()> {
+        let file = self.state.file_key(file);
+        self.state.complete(&file, language);
+        self.state.save(&self.path).await
+    }
+
+    /// Record that processing `file` failed
+    pub async fn fail(&mut self, file: &Path, error: &str) -> Result<()> {
+        let file = self.state.file_key(file);
+        self.state.fail(&file, error);
+        self.state.save(&self.path).await
+    }
+
+    /// Target languages not yet written for `file`
+    pub fn remaining(&self, file: &Path, languages: &[String]) -> Vec<String> {
+        self.state.remaining(&self.state.file_key(file), languages)
+    }
+}
+
+/// Stable ID of the batch over `input_dir` into `output_dir` for `languages` (in any order)
+pub fn job_id(input_dir: &Path, output_dir: &Path, languages: &[String]) -> String {
+    let absolute = |path: &Path| std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let languages: BTreeSet<&String> = languages.iter().collect();
+
+    let mut hasher = Sha256::new();
+    hasher.update(absolute(input_dir).to_string_lossy().as_bytes());
+    hasher.update([0]);
+    hasher.update(absolute(output_dir).to_string_lossy().as_bytes());
+    for language in languages {
+        hasher.update([0]);
+        hasher.update(language.as_bytes());
+    }
+    hasher.finalize().iter().take(8).map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_job_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let languages = vec!["ja".to_string(), "de".to_string()];
+        let id = job_id(dir.path(), dir.path(), &languages);
+        assert_eq!(id.len(), 16);
+        assert_eq!(id, job_id(dir.path(), dir.path(), &["de".to_string(), "ja".to_string()]));
+        assert_ne!(id, job_id(dir.path(), dir.path(), &["ja".to_string()]));
+
+        let mut state = JobState::new(dir.path(), dir.path(), &languages);
+        let file = state.file_key(&dir.path().join("season1").join("e01.mkv"));
+        assert_eq!(Path::new(&file), Path::new("season1/e01.mkv"));
+        state.fail(&file, "whisper crashed");
+        state.complete(&file, "ja");
+        assert_eq!(state.remaining(&file, &languages), vec!["de".to_string()]);
+        assert_eq!(state.remaining("e02.mkv", &languages), languages);
+        assert!(state.failed.is_empty());
+
+        let path = JobState::path(&dir.path().join("state"), &state.id);
+        assert_eq!(JobState::load(&path).await.unwrap(), None);
+        state.save(&path).await.unwrap();
+        assert_eq!(JobState::load(&path).await.unwrap(), Some(state));
+
+        // Resuming picks the saved state up; a new run replaces it
+        let state_dir = dir.path().join("state");
+        let resumed = JobProgress::open(&state_dir, dir.path(), dir.path(), &languages, true).await.unwrap();
+        assert_eq!(resumed.remaining(&dir.path().join("season1/e01.mkv"), &languages), vec!["de".to_string()]);
+        let restarted = JobProgress::open(&state_dir, dir.path(), dir.path(), &languages, false).await.unwrap();
+        assert!(restarted.state.completed.is_empty());
+        assert!(JobState::load(restarted.path()).await.unwrap().unwrap().completed.is_empty());
+
+        let list = dir.path().join(RETRY_LIST);
+        write_retry_list(&list, &[Path::new("/videos/e01.mkv"), Path::new("/videos/e 02.mkv")]).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&list).unwrap(), "/videos/e01.mkv\n/videos/e 02.mkv\n");
+    }
+}
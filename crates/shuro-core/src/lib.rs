@@ -0,0 +1,124 @@
+//! Shuro Core - Subtitle Translation Engine
+//!
+//! The engine behind the `shuro` command line tool: audio extraction, transcription,
+//! translation, subtitle generation and embedding, orchestrated by [`Workflow`].
+//! Applications embed the engine by depending on this crate; it installs no logging
+//! subscriber and parses no command line arguments.
+//!
+//! # Public API
+//!
+//! The items below form the stable API surface and follow semantic versioning:
+//!
+//! - [`builder`] - [`Shuro`] engine built with [`ShuroBuilder`], all of its paths injected
+//! - [`workflow`] - [`Workflow`] orchestration of the full video pipeline
+//! - [`config`] - [`Config`] and its sections, loaded from TOML
+//! - [`transcribe`] - [`TranscriberTrait`](transcribe::TranscriberTrait), factory and registry
+//! - [`translate`] - [`Translator`](translate::Translator), factory, registry and metrics
+//! - [`subtitle`] - SRT, WebVTT and ASS reading and generation
+//! - [`transcript`] - Versioned transcript files for editing between transcription and translation
+//! - [`align`] - Retiming existing subtitles onto a transcription
+//! - [`segmentation`] - Merging fragments and splitting overlong transcribed segments
+//! - [`diarization`] - Speaker labels from a diarization program
+//! - [`shots`] - Snapping cue times to shot changes
+//! - [`timeline`] - Cue timing shared by the translations into every language
+//! - [`style`] - Style presets for burned-in subtitles and ASS files
+//! - [`forced`] - Forced-narrative subtitles for foreign dialogue
+//! - [`bilingual`] - Subtitles stacking the source line with the translation
+//! - [`delivery`] - Shouting, whispering and singing hints measured on the audio
+//! - [`locale`] - Unit, clock, number and date normalization of translations
+//! - [`partial`] - In-progress subtitle files flushed during translation
+//! - [`media`] - [`MediaProcessorTrait`](media::MediaProcessorTrait) and the ffmpeg processor
+//! - [`quality`] - [`Transcription`](quality::Transcription) model and quality validation
+//! - [`hallucination`] - Configurable rules flagging invented transcription segments
+//! - [`readability`] - Reading-speed and line-length limits of translated cues
+//! - [`job`] - Batch progress checkpoints for resuming interrupted runs
+//! - [`control`] - Pausing a running batch and skipping its current file
+//! - [`manifest`] - Per-file processing manifests written next to the outputs
+//! - [`comparison`] - Quality comparison of the translations into several languages
+//! - [`disk`] - Free disk space checks before audio extraction and muxing
+//! - [`cache`] - Cache size limits with least-recently-used eviction, and cache archives
+//! - [`retry`] - Retries with backoff of requests to the translation model and whisper-server
+//! - [`cost`] - Token usage, estimated cost and budget of model requests
+//! - [`progress`] - Progress events and observers for front ends supervising a run
+//! - [`webhook`] - Notifications posted when each video finishes or fails
+//! - [`plan`] - Dry-run plans of the steps, cache hits and outputs of a run
+//! - [`report`] - End-of-run reports of timings, cache hits, translation checks and failures
+//! - [`preflight`] - Health checks of the translation endpoint, models and transcriber
+//! - [`selftest`] - Pipeline run on a synthetic video with stand-in transcriber and model
+//! - [`episode`] - Series, season and episode parsed from file names
+//! - [`naming`] - Output names, language codes and placement for media servers
+//! - [`metadata`] - Typed per-segment metadata (speaker, confidence, hallucination flags)
+//! - [`cancel`] - [`Cancellation`] tokens and deadlines
+//! - [`temp`] - Tracked temporary directories and cleanup of leftovers
+//! - [`platform`] - Executable resolution and child-process paths
+//! - [`project`] - Series workspaces with glossary, models and translation memory
+//! - [`error`] - [`ShuroError`] and [`Result`]
+//!
+//! ```no_run
+//! use shuro_core::{Cancellation, Config, Shuro};
+//! use std::path::Path;
+//!
+//! # async fn run() -> shuro_core::Result<()> {
+//! let shuro = Shuro::builder()
+//!     .config(Config::from_file("config.toml")?)
+//!     .data_dir("/var/lib/myapp/shuro")
+//!     .build()?;
+//! let workflow = shuro.workflow();
+//! let languages = vec!["ja".to_string()];
+//! workflow.process_single_file(Path::new("video.mp4"), &languages, None::<&Path>, &Cancellation::new()).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod align;
+pub mod bilingual;
+pub mod builder;
+pub mod cache;
+pub mod cancel;
+pub mod comparison;
+pub mod config;
+pub mod control;
+pub mod cost;
+pub mod delivery;
+pub mod diarization;
+pub mod disk;
+pub mod episode;
+pub mod error;
+pub mod forced;
+pub mod hallucination;
+pub mod job;
+pub mod locale;
+pub mod manifest;
+pub mod partial;
+pub mod media;
+pub mod metadata;
+pub mod naming;
+pub mod plan;
+pub mod platform;
+pub mod preflight;
+pub mod progress;
+pub mod project;
+pub mod quality;
+pub mod readability;
+pub mod report;
+pub mod retry;
+pub mod segmentation;
+pub mod selftest;
+pub mod shots;
+pub mod style;
+pub mod subtitle;
+pub mod temp;
+pub mod timeline;
+pub mod transcript;
+pub mod transcribe;
+pub mod translate;
+pub mod webhook;
+pub mod workflow;
+
+mod plugin;
+
+pub use builder::{Shuro, ShuroBuilder};
+pub use cancel::Cancellation;
+pub use config::Config;
+pub use error::{Result, ShuroError};
+pub use workflow::Workflow;
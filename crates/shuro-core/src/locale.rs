@@ -0,0 +1,533 @@
+// Locale normalization of translations
+//
+// Models render measurements, clock times and numbers inconsistently: "5 miles" may stay "5
+// Meilen" in German, "7 pm" may stay "7 pm" in Japanese, and "1,234.5" may keep its English
+// separators. With rules enabled for a target language in `[locale.languages.<lang>]`, a
+// deterministic pass rewrites the finished translation before its subtitles are written:
+//
+// - `metric_units`: miles, mph, feet, inches, yards, lb, ounces, gallons and °F after a number are
+//   converted to km, km/h, m, cm, kg, g, l and °C, in the target language's number format
+// - `clock_24h`: "7:30 pm" becomes "19:30", and the 午後/下午/오후 markers of Japanese, Chinese
+//   and Korean are folded into the hour
+// - `numbers`: numbers with separators copied from the source ("1,234.5") get the target
+//   language's decimal and grouping separators ("1.234,5")
+// - `dates`: numeric dates copied from the source ("3/5/2024") are reordered for the target
+//   language ("5.3.2024", "2024年3月5日")
+//
+// Numbers and dates are only rewritten when they appear verbatim in the source segment, so their
+// meaning is read with the source language's conventions and a number the model already
+// localized is never converted twice. Languages without rules are left as translated.
+
+use crate::config::{LocaleConfig, LocaleRules};
+use crate::quality::Transcription;
+
+/// How a language writes numeric dates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DateOrder {
+    /// `M/D/Y`
+    Mdy,
+    /// `D{sep}M{sep}Y`
+    Dmy(char),
+    /// `Y年M月D日` with the language's year, month and day suffixes
+    Ymd(&'static str, &'static str, &'static str),
+}
+
+/// Number and date conventions of a language
+#[derive(Debug, Clone, Copy)]
+struct Conventions {
+    decimal: char,
+    group: char,
+    date: DateOrder,
+}
+
+const ENGLISH: Conventions = Conventions { decimal: '.', group: ',', date: DateOrder::Mdy };
+
+/// Conventions of a language code ("pt-BR" uses those of "pt"); English for unknown languages
+fn conventions(language: &str) -> Conventions {
+    let primary = language.split(['-', '_']).next().unwrap_or_default().to_lowercase();
+    let (decimal, group, date) = match primary.as_str() {
+        "de" | "da" | "nb" | "no" => (',', '.', DateOrder::Dmy('.')),
+        "es" | "it" | "pt" | "el" | "tr" => (',', '.', DateOrder::Dmy('/')),
+        "nl" => (',', '.', DateOrder::Dmy('-')),
+        "fr" => (',', '\u{202f}', DateOrder::Dmy('/')),
+        "ru" | "uk" | "pl" | "cs" | "fi" | "sv" => (',', '\u{a0}', DateOrder::Dmy('.')),
+        "ja" | "zh" => ('.', ',', DateOrder::Ymd("年", "月", "日")),
+        "ko" => ('.', ',', DateOrder::Ymd("년 ", "월 ", "일")),
+        _ => return ENGLISH,
+    };
+    Conventions { decimal, group, date }
+}
+
+/// Imperial unit and its metric replacement
+struct Unit {
+    /// Spellings after the number, longest first where one starts another
+    spellings: &'static [&'static str],
+    symbol: &'static str,
+    convert: fn(f64) -> f64,
+}
+
+const UNITS: &[Unit] = &[
+    Unit {
+        spellings: &["miles per hour", "miles an hour", "mph", "マイル毎時", "英里每小时", "마일/시"],
+        symbol: "km/h",
+        convert: |v| v * 1.609344,
+    },
+    Unit {
+        spellings: &["miles", "mile", "mi", "Meilen", "Meile", "milles", "millas", "milla", "miglia", "miglio",
+                     "milhas", "milha", "mijl", "мили", "миль", "миля", "マイル", "英里", "마일"],
+        symbol: "km",
+        convert: |v| v * 1.609344,
+    },
+    Unit {
+        spellings: &["feet", "foot", "ft", "Fuß", "pieds", "pied", "pies", "piedi", "pés", "футов", "фута", "фут",
+                     "フィート", "英尺", "피트"],
+        symbol: "m",
+        convert: |v| v * 0.3048,
+    },
+    Unit {
+        spellings: &["inches", "inch", "Zoll", "pouces", "pouce", "pulgadas", "pulgada", "pollici", "polegadas",
+                     "дюймов", "дюйма", "インチ", "英寸", "인치"],
+        symbol: "cm",
+        convert: |v| v * 2.54,
+    },
+    Unit {
+        spellings: &["yards", "yard", "yds", "yd", "yardas", "iarde", "ヤード", "码", "야드"],
+        symbol: "m",
+        convert: |v| v * 0.9144,
+    },
+    Unit {
+        spellings: &["lbs", "lb"],
+        symbol: "kg",
+        convert: |v| v * 0.453_592_37,
+    },
+    Unit {
+        spellings: &["ounces", "ounce", "oz", "Unzen", "Unze", "onces", "onzas", "onza", "オンス", "盎司", "온스"],
+        symbol: "g",
+        convert: |v| v * 28.349_523_125,
+    },
+    Unit {
+        spellings: &["gallons", "gallon", "gal", "Gallonen", "Gallone", "galones", "galón", "galloni", "galões",
+                     "ガロン", "加仑", "갤런"],
+        symbol: "l",
+        convert: |v| v * 3.785_411_784,
+    },
+    Unit {
+        spellings: &["degrees Fahrenheit", "Grad Fahrenheit", "degrés Fahrenheit", "grados Fahrenheit",
+                     "gradi Fahrenheit", "graus Fahrenheit", "Fahrenheit", "°F", "℉"],
+        symbol: "°C",
+        convert: |v| (v - 32.0) * 5.0 / 9.0,
+    },
+];
+
+/// Morning and afternoon markers written before the hour, with whether they mean afternoon
+const HOUR_MARKERS: &[(&str, bool)] = &[
+    ("午前", false), ("午後", true), ("上午", false), ("下午", true), ("晚上", true), ("오전", false), ("오후", true),
+];
+
+/// Apply the rules of `target_language` to the translated segments of `translation`, reading
+/// copied numbers and dates with the conventions of `source_language`; `source` holds the
+/// segments that were translated. Returns how many segments changed.
+pub fn normalize_transcription(
+    config: &LocaleConfig,
+    source: &Transcription,
+    translation: &mut Transcription,
+    source_language: &str,
+    target_language: &str,
+) -> usize {
+    let Some(rules) = config.rules(target_language) else {
+        return 0;
+    };
+    let (from, to) = (conventions(source_language), conventions(target_language));
+    // Regrouped translations no longer line up with their source segments
+    let whole_source = (source.segments.len() != translation.segments.len())
+        .then(|| source.segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join("\n"));
+
+    let mut changed = 0;
+    for (index, segment) in translation.segments.iter_mut().enumerate() {
+        let source_text = match &whole_source {
+            Some(text) => text.as_str(),
+            None => source.segments[index].text.as_str(),
+        };
+        let normalized = normalize_text(&segment.text, source_text, rules, from, to);
+        if normalized != segment.text {
+            segment.text = normalized;
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// `text` with the enabled rules applied
+fn normalize_text(text: &str, source: &str, rules: &LocaleRules, from: Conventions, to: Conventions) -> String {
+    let mut text = text.to_string();
+    if rules.dates {
+        text = rewrite_dates(&text, source, from, to);
+    }
+    if rules.clock_24h {
+        text = rewrite_clock_times(&text, source);
+    }
+    if rules.metric_units {
+        text = rewrite_units(&text, source, from, to);
+    }
+    if rules.numbers {
+        text = rewrite_numbers(&text, source, from, to);
+    }
+    text
+}
+
+/// Byte ranges of the numbers in `text`: digits with single `.` or `,` between them, not glued to
+/// a word ("MP3", "A4")
+fn number_tokens(text: &str) -> Vec<(usize, usize)> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_digit() || text[..i].chars().next_back().is_some_and(|c| c.is_alphanumeric() || c == '.' || c == ',') {
+            i += text[i..].chars().next().map_or(1, char::len_utf8);
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() {
+            let separated = (bytes[i] == b'.' || bytes[i] == b',') && bytes.get(i + 1).is_some_and(u8::is_ascii_digit);
+            if bytes[i].is_ascii_digit() || separated {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+        tokens.push((start, i));
+    }
+    tokens
+}
+
+/// Whether `token` is one of the numbers of `source`
+fn in_source(token: &str, source: &str) -> bool {
+    number_tokens(source).into_iter().any(|(start, end)| &source[start..end] == token)
+}
+
+/// Value of a number written with `conventions`; `None` when its separators do not fit them
+fn parse_number(token: &str, conventions: Conventions) -> Option<f64> {
+    let (integer, fraction) = split_number(token, conventions)?;
+    format!("{}.{}", integer, if fraction.is_empty() { "0" } else { fraction }).parse().ok()
+}
+
+/// Integer digits and fraction digits of a number written with `conventions`; groups must have
+/// three digits
+fn split_number(token: &str, conventions: Conventions) -> Option<(String, &str)> {
+    let (integer, fraction) = match token.rfind(conventions.decimal) {
+        Some(position) => (&token[..position], &token[position + conventions.decimal.len_utf8()..]),
+        None => (token, ""),
+    };
+    let mut groups = integer.split(conventions.group);
+    let first = groups.next()?;
+    let mut digits = first.to_string();
+    for group in groups {
+        if group.len() != 3 || first.is_empty() || first.len() > 3 {
+            return None;
+        }
+        digits.push_str(group);
+    }
+    let all_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    (all_digits(&digits) && (fraction.is_empty() || all_digits(fraction))).then_some((digits, fraction))
+}
+
+/// Integer digits grouped by thousands with `group`
+fn group_digits(digits: &str, group: char) -> String {
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(group);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// A converted value: one decimal below 10, whole numbers above, grouped from 10 000
+fn format_value(value: f64, conventions: Conventions) -> String {
+    let rounded = if value.abs() < 10.0 { (value * 10.0).round() / 10.0 } else { value.round() };
+    let text = format!("{:.1}", rounded.abs());
+    let (integer, fraction) = text.split_once('.').unwrap_or((&text, "0"));
+    let integer = if integer.len() > 4 { group_digits(integer, conventions.group) } else { integer.to_string() };
+    let sign = if rounded < 0.0 { "-" } else { "" };
+    if fraction == "0" {
+        format!("{}{}", sign, integer)
+    } else {
+        format!("{}{}{}{}", sign, integer, conventions.decimal, fraction)
+    }
+}
+
+/// End of `spelling` when `text` starts with it, ignoring case; spellings ending in a letter must
+/// not run into a longer word
+fn match_spelling(text: &str, spelling: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    for expected in spelling.chars() {
+        let (_, c) = chars.next()?;
+        if !c.to_lowercase().eq(expected.to_lowercase()) {
+            return None;
+        }
+    }
+    let end = chars.next().map_or(text.len(), |(i, _)| i);
+    let word_like = |c: char| c.is_alphabetic() && (c as u32) < 0x2E80;
+    let runs_on = spelling.chars().next_back().is_some_and(word_like) && text[end..].chars().next().is_some_and(char::is_alphanumeric);
+    (!runs_on).then_some(end)
+}
+
+/// Imperial measurements converted to metric ones
+fn rewrite_units(text: &str, source: &str, from: Conventions, to: Conventions) -> String {
+    let mut result = String::new();
+    let mut copied = 0;
+    for (start, end) in number_tokens(text) {
+        if start < copied {
+            continue;
+        }
+        let token = &text[start..end];
+        let negative = text[..start].ends_with(['-', '−']);
+        let after = &text[end..];
+        let space = after.chars().next().filter(|c| *c == ' ' || *c == '\u{a0}');
+        let rest = &after[space.map_or(0, char::len_utf8)..];
+        let Some((unit, length)) = UNITS.iter()
+            .find_map(|unit| unit.spellings.iter().find_map(|s| match_spelling(rest, s)).map(|length| (unit, length)))
+        else {
+            continue;
+        };
+        // A number copied from the source is read as the source wrote it
+        let reading = if in_source(token, source) { from } else { to };
+        let Some(mut value) = parse_number(token, reading).or_else(|| parse_number(token, ENGLISH)) else {
+            continue;
+        };
+        let number_start = if negative && unit.symbol == "°C" {
+            value = -value;
+            start - text[..start].chars().next_back().map_or(0, char::len_utf8)
+        } else {
+            start
+        };
+        result.push_str(&text[copied..number_start]);
+        result.push_str(&format_value((unit.convert)(value), to));
+        if let Some(space) = space {
+            result.push(space);
+        }
+        result.push_str(unit.symbol);
+        copied = end + space.map_or(0, char::len_utf8) + length;
+    }
+    result.push_str(&text[copied..]);
+    result
+}
+
+/// 24-hour times of the "7 pm" times in `text`
+fn western_times(text: &str) -> Vec<String> {
+    text.char_indices()
+        .filter(|(i, _)| !text[..*i].chars().next_back().is_some_and(|c| c.is_alphanumeric() || c == ':'))
+        .filter_map(|(i, _)| western_time(&text[i..]).map(|(_, time)| time))
+        .collect()
+}
+
+/// 12-hour clock times turned into 24-hour ones; "7 pm" only when the source has that time too,
+/// since "am" is also a word ("um 5 am Bahnhof")
+fn rewrite_clock_times(text: &str, source: &str) -> String {
+    let source_times = western_times(source);
+    let mut result = String::new();
+    let mut copied = 0;
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        let preceded = text[..i].chars().next_back().is_some_and(|c| c.is_alphanumeric() || c == ':');
+        let western = if preceded { None } else { western_time(rest).filter(|(_, time)| source_times.contains(time)) };
+        let replaced = western.or_else(|| marked_hour(rest));
+        match replaced {
+            Some((length, time)) => {
+                result.push_str(&text[copied..i]);
+                result.push_str(&time);
+                i += length;
+                copied = i;
+            }
+            None => i += rest.chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    result.push_str(&text[copied..]);
+    result
+}
+
+/// Hour from 1 to 12 at the start of `text`, and its length
+fn leading_hour(text: &str) -> Option<(u32, usize)> {
+    let length = text.bytes().take(2).take_while(u8::is_ascii_digit).count();
+    let hour: u32 = text[..length].parse().ok()?;
+    (1..=12).contains(&hour).then_some((hour, length))
+}
+
+fn to_24h(hour: u32, afternoon: bool) -> u32 {
+    match (hour, afternoon) {
+        (12, false) => 0,
+        (12, true) => 12,
+        (hour, true) => hour + 12,
+        (hour, false) => hour,
+    }
+}
+
+/// "7 pm", "7:30 p.m." at the start of `text` as "19:00", "19:30"
+fn western_time(text: &str) -> Option<(usize, String)> {
+    let (hour, mut length) = leading_hour(text)?;
+    let mut minutes = 0;
+    if let Some(rest) = text[length..].strip_prefix(':') {
+        if rest.len() < 2 || !rest.as_bytes()[..2].iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        minutes = rest[..2].parse().ok().filter(|m| *m < 60)?;
+        length += 3;
+    }
+    if text[length..].starts_with(' ') || text[length..].starts_with('\u{a0}') {
+        length += text[length..].chars().next().map_or(0, char::len_utf8);
+    }
+    let (afternoon, marker) = [("a.m.", false), ("p.m.", true), ("am", false), ("pm", true)]
+        .into_iter()
+        .find_map(|(marker, afternoon)| match_spelling(&text[length..], marker).map(|end| (afternoon, end)))?;
+    Some((length + marker, format!("{:02}:{:02}", to_24h(hour, afternoon), minutes)))
+}
+
+/// "午後7時", "下午7点", "오후 7시" at the start of `text` as "19時", "19点", "19시"
+fn marked_hour(text: &str) -> Option<(usize, String)> {
+    let (marker, afternoon) = HOUR_MARKERS.iter().find(|(marker, _)| text.starts_with(marker))?;
+    let mut length = marker.len();
+    if text[length..].starts_with(' ') {
+        length += 1;
+    }
+    let (hour, digits) = leading_hour(&text[length..])?;
+    if !["時", "点", "點", "시"].iter().any(|suffix| text[length + digits..].starts_with(suffix)) {
+        return None;
+    }
+    Some((length + digits, to_24h(hour, *afternoon).to_string()))
+}
+
+/// Numbers copied from the source written with the target language's separators
+fn rewrite_numbers(text: &str, source: &str, from: Conventions, to: Conventions) -> String {
+    if (from.decimal, from.group) == (to.decimal, to.group) {
+        return text.to_string();
+    }
+    let mut result = String::new();
+    let mut copied = 0;
+    for (start, end) in number_tokens(text) {
+        let token = &text[start..end];
+        if !token.contains(['.', ',']) || !in_source(token, source) {
+            continue;
+        }
+        let Some((integer, fraction)) = split_number(token, from) else {
+            continue;
+        };
+        let grouped = token.contains(from.group);
+        result.push_str(&text[copied..start]);
+        result.push_str(&if grouped { group_digits(&integer, to.group) } else { integer });
+        if !fraction.is_empty() {
+            result.push(to.decimal);
+            result.push_str(fraction);
+        }
+        copied = end;
+    }
+    result.push_str(&text[copied..]);
+    result
+}
+
+/// Numeric date at the start of `text`: its length and its three parts in written order
+fn date_at(text: &str) -> Option<(usize, [&str; 3], char)> {
+    let digits = |s: &str| s.bytes().take_while(u8::is_ascii_digit).count();
+    let first = digits(text);
+    let separator = text[first..].chars().next().filter(|c| matches!(c, '/' | '.' | '-'))?;
+    let second_start = first + 1;
+    let second = digits(&text[second_start..]);
+    if !text[second_start + second..].starts_with(separator) {
+        return None;
+    }
+    let third_start = second_start + second + 1;
+    let third = digits(&text[third_start..]);
+    let end = third_start + third;
+    let lengths = [first, second, third];
+    let runs_on = text[end..].starts_with(|c: char| c.is_ascii_alphanumeric())
+        || (text[end..].starts_with(['/', '.', '-']) && text[end + 1..].starts_with(|c: char| c.is_ascii_digit()));
+    if lengths.iter().any(|l| *l == 0 || *l > 4) || runs_on {
+        return None;
+    }
+    Some((end, [&text[..first], &text[second_start..second_start + second], &text[third_start..end]], separator))
+}
+
+/// Year, month and day of a date written in the order of `conventions`
+fn date_fields(parts: [&str; 3], conventions: Conventions) -> Option<(&str, &str, &str)> {
+    let (year, month, day) = if parts[0].len() == 4 {
+        (parts[0], parts[1], parts[2])
+    } else {
+        match conventions.date {
+            DateOrder::Mdy => (parts[2], parts[0], parts[1]),
+            DateOrder::Dmy(_) => (parts[2], parts[1], parts[0]),
+            DateOrder::Ymd(..) => (parts[0], parts[1], parts[2]),
+        }
+    };
+    let valid = |s: &str, max: u32| s.len() <= 2 && s.parse::<u32>().is_ok_and(|v| (1..=max).contains(&v));
+    (valid(month, 12) && valid(day, 31) && matches!(year.len(), 2 | 4)).then_some((year, month, day))
+}
+
+/// Dates copied from the source in the order of the target language
+fn rewrite_dates(text: &str, source: &str, from: Conventions, to: Conventions) -> String {
+    let mut result = String::new();
+    let mut copied = 0;
+    let mut i = 0;
+    while i < text.len() {
+        let preceded = text[..i].chars().next_back().is_some_and(|c| c.is_alphanumeric() || matches!(c, '/' | '.' | '-'));
+        let date = if preceded { None } else { date_at(&text[i..]) };
+        let Some((length, parts, separator)) = date else {
+            i += text[i..].chars().next().map_or(1, char::len_utf8);
+            continue;
+        };
+        let written = &text[i..i + length];
+        let fields = source.contains(written).then(|| date_fields(parts, from)).flatten()
+            // ISO dates read the same in every language
+            .filter(|_| separator != '-' || parts[0].len() != 4);
+        if let Some((year, month, day)) = fields {
+            let number = |s: &str| s.trim_start_matches('0').to_string();
+            let rewritten = match to.date {
+                DateOrder::Mdy => format!("{}/{}/{}", month, day, year),
+                DateOrder::Dmy(sep) => format!("{}{sep}{}{sep}{}", day, month, year),
+                DateOrder::Ymd(y, m, d) => format!("{}{}{}{}{}{}", year, y, number(month), m, number(day), d),
+            };
+            result.push_str(&text[copied..i]);
+            result.push_str(&rewritten);
+            copied = i + length;
+        }
+        i += length;
+    }
+    result.push_str(&text[copied..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::parse_srt;
+
+    #[test]
+    fn test_normalize_translations() {
+        let all = LocaleRules { metric_units: true, clock_24h: true, numbers: true, dates: true };
+        let (en, de, ja) = (conventions("en"), conventions("de-AT"), conventions("ja"));
+
+        assert_eq!(normalize_text("Noch 5 Meilen bis zur Stadt.", "5 miles to town.", &all, en, de), "Noch 8 km bis zur Stadt.");
+        assert_eq!(normalize_text("Es hat 1,500 miles.", "It has 1,500 miles.", &all, en, de), "Es hat 2414 km.");
+        assert_eq!(normalize_text("Er fuhr 65 mph, bei 98.6°F.", "He drove 65 mph at 98.6°F.", &all, en, de), "Er fuhr 105 km/h, bei 37°C.");
+        assert_eq!(normalize_text("Es waren -4 °F.", "It was -4 °F.", &all, en, de), "Es waren -20 °C.");
+        assert_eq!(normalize_text("Ein 6-Fuß-Mann, 3 Zoll", "", &all, en, de), "Ein 6-Fuß-Mann, 7,6 cm");
+        assert_eq!(normalize_text("Treffen um 7:30 pm oder 12 a.m.", "Meet at 7:30 pm or 12 a.m.", &all, en, de), "Treffen um 19:30 oder 00:00");
+        // German "am" is not a clock marker when the source has no such time
+        assert_eq!(normalize_text("Um 5 am Bahnhof", "At five at the station", &all, en, de), "Um 5 am Bahnhof");
+        assert_eq!(normalize_text("午後7時30分に会おう", "", &all, en, ja), "19時30分に会おう");
+        assert_eq!(normalize_text("Es kostet 1,234.5 Dollar.", "It costs 1,234.5 dollars.", &all, en, de), "Es kostet 1.234,5 Dollar.");
+        assert_eq!(normalize_text("Am 3/5/2024 kam er.", "On 3/5/2024 he came.", &all, en, de), "Am 5.3.2024 kam er.");
+        assert_eq!(normalize_text("3/5/2024に来た", "On 3/5/2024 he came.", &all, en, ja), "2024年3月5日に来た");
+        // Nothing from the source: numbers and dates stay, and words are not units
+        assert_eq!(normalize_text("Um 1,5 und am 13/14/2024, MP3 mi", "", &all, en, de), "Um 1,5 und am 13/14/2024, MP3 mi");
+        assert_eq!(normalize_text("5 milestones", "5 milestones", &all, en, de), "5 milestones");
+
+        let mut config = LocaleConfig::default();
+        config.languages.insert("de".to_string(), LocaleRules { metric_units: true, ..LocaleRules::default() });
+        let source = parse_srt("1\n00:00:01,000 --> 00:00:02,000\n10 miles\n\n2\n00:00:03,000 --> 00:00:04,000\nat 7 pm\n", "en").unwrap();
+        let mut translation = parse_srt("1\n00:00:01,000 --> 00:00:02,000\n10 Meilen\n\n2\n00:00:03,000 --> 00:00:04,000\num 7 pm\n", "de").unwrap();
+        assert_eq!(normalize_transcription(&config, &source, &mut translation, "en", "de"), 1);
+        assert_eq!(translation.segments[0].text, "16 km");
+        assert_eq!(normalize_transcription(&config, &source, &mut translation, "en", "fr"), 0);
+    }
+}
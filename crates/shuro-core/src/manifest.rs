@@ -0,0 +1,213 @@
+// Processing manifests
+//
+// Every processed video gets a `{stem}.shuro.json` manifest next to its outputs. It records what
+// produced the subtitles: the SHA-256 of the input, the models, a snapshot of the effective
+// configuration, how long each step took and a quality summary of the transcription. Comparing a
+// manifest against the current input and configuration tells whether outputs are reproducible
+// from them or need a refresh.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncReadExt;
+
+use crate::config::{Config, TranslateConfig};
+use crate::episode::EpisodeInfo;
+use crate::error::{Result, ShuroError};
+use crate::metadata::HALLUCINATION;
+use crate::transcribe::TuneResult;
+
+/// File name suffix of manifests
+pub const MANIFEST_SUFFIX: &str = ".shuro.json";
+
+/// Record of how the outputs of one input file were produced
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessingManifest {
+    /// Version of shuro that wrote the manifest
+    pub shuro_version: String,
+    /// Unix timestamp of the run
+    pub created_at: u64,
+    pub input: InputRecord,
+    /// Series, season and episode parsed from the input file name
+    #[serde(default)]
+    pub episode: Option<EpisodeInfo>,
+    pub models: ModelRecord,
+    /// Effective configuration, with translator sizes fitted to the model
+    pub config: Value,
+    pub timings: TimingRecord,
+    pub quality: QualityRecord,
+    pub outputs: Vec<OutputRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputRecord {
+    pub path: PathBuf,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelRecord {
+    pub explore_model: String,
+    pub transcribe_model: String,
+    pub translate_model: String,
+    pub judge_model: Option<String>,
+    pub embedding_model: Option<String>,
+    /// Context tokens requested from the translation model
+    pub num_ctx: Option<usize>,
+}
+
+/// Seconds spent in each step; transcription and translation overlap and are timed together
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TimingRecord {
+    pub audio_extraction: f64,
+    pub transcription_and_translation: f64,
+    pub output: f64,
+    pub total: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityRecord {
+    pub segments: usize,
+    pub best_tempo: i32,
+    pub best_temperature: f32,
+    /// Smoothness score of the chosen transcription (lower is better)
+    pub quality_score: f64,
+    pub hallucination_segments: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputRecord {
+    pub language: String,
+    pub subtitles: PathBuf,
+    pub video: Option<PathBuf>,
+    /// Translator's notes file, when notes are enabled
+    #[serde(default)]
+    pub notes: Option<PathBuf>,
+    /// Forced-narrative subtitle file, when forced tracks are enabled and the video has foreign lines
+    #[serde(default)]
+    pub forced: Option<PathBuf>,
+    /// ASS subtitle file in the selected style preset, when one is selected
+    #[serde(default)]
+    pub styled: Option<PathBuf>,
+}
+
+impl ProcessingManifest {
+    /// Manifest of a run that produced `outputs` from `input_path`
+    pub async fn new(
+        input_path: &Path,
+        config: &Config,
+        translate_config: &TranslateConfig,
+        tune_result: &TuneResult,
+        timings: TimingRecord,
+        outputs: Vec<OutputRecord>,
+    ) -> Result<Self> {
+        let size = tokio::fs::metadata(input_path).await?.len();
+
+        let mut snapshot = config.clone();
+        snapshot.translate = translate_config.clone();
+
+        Ok(Self {
+            shuro_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            input: InputRecord {
+                path: input_path.to_path_buf(),
+                size,
+                sha256: file_sha256(input_path).await?,
+            },
+            episode: input_path.file_stem().and_then(|stem| EpisodeInfo::parse(&stem.to_string_lossy())),
+            models: ModelRecord {
+                explore_model: config.transcriber.explore_model.clone(),
+                transcribe_model: config.transcriber.transcribe_model.clone(),
+                translate_model: translate_config.model.clone(),
+                judge_model: translate_config.judge_model.clone(),
+                embedding_model: translate_config.semantic_cache.enabled
+                    .then(|| translate_config.semantic_cache.embedding_model.clone()),
+                num_ctx: translate_config.num_ctx,
+            },
+            config: serde_json::to_value(&snapshot)?,
+            timings,
+            quality: QualityRecord {
+                segments: tune_result.best_transcription.segments.len(),
+                best_tempo: tune_result.best_tempo,
+                best_temperature: tune_result.best_temperature,
+                quality_score: tune_result.quality_score,
+                hallucination_segments: tune_result.best_transcription.segments
+                    .iter()
+                    .filter(|s| s.metadata.contains(&HALLUCINATION))
+                    .count(),
+            },
+            outputs,
+        })
+    }
+
+    /// Manifest path for the outputs of `stem` in `output_dir`
+    pub fn path(output_dir: &Path, stem: &str) -> PathBuf {
+        output_dir.join(format!("{}{}", stem, MANIFEST_SUFFIX))
+    }
+
+    pub async fn load(path: &Path) -> Result<Self> {
+        let content = tokio::fs::read_to_string(path).await?;
+        serde_json::from_str(&content)
+            .map_err(|e| ShuroError::Config(format!("Invalid manifest {}: {}", path.display(), e)))
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        tokio::fs::write(path, serde_json::to_string_pretty(self)?).await?;
+        Ok(())
+    }
+}
+
+/// Hex SHA-256 of a file's content
+pub async fn file_sha256(path: &Path) -> Result<String> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 1 << 20];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quality::Transcription;
+
+    #[tokio::test]
+    async fn test_manifest_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("Show.S01E02.mp4");
+        tokio::fs::write(&input, b"abc").await.unwrap();
+        assert_eq!(file_sha256(&input).await.unwrap(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        let tune_result = TuneResult {
+            best_transcription: Transcription { text: String::new(), segments: Vec::new(), language: "en".to_string() },
+            best_tempo: 100,
+            best_temperature: 0.0,
+            quality_score: 0.5,
+            all_attempts: Vec::new(),
+            tested_parameters: Vec::new(),
+        };
+        let config = Config::default();
+        let outputs = vec![OutputRecord { language: "ja".to_string(), subtitles: dir.path().join("movie_ja.srt"), video: None, notes: None, forced: None, styled: None }];
+        let manifest = ProcessingManifest::new(&input, &config, &config.translate, &tune_result, TimingRecord::default(), outputs)
+            .await
+            .unwrap();
+        assert_eq!(manifest.input.size, 3);
+        assert_eq!(manifest.episode.as_ref().map(EpisodeInfo::code).as_deref(), Some("S01E02"));
+
+        let path = ProcessingManifest::path(dir.path(), "movie");
+        assert_eq!(path.file_name().unwrap(), "movie.shuro.json");
+        manifest.save(&path).await.unwrap();
+        assert_eq!(ProcessingManifest::load(&path).await.unwrap(), manifest);
+    }
+}
@@ -0,0 +1,508 @@
+use std::ops::Range;
+use std::path::Path;
+use tracing::debug;
+
+use super::{SubtitleTrack, bibliographic_language};
+
+use crate::config::EncodingConfig;
+use crate::episode::EpisodeInfo;
+use crate::error::{Result, ShuroError};
+use crate::platform;
+use crate::subtitle::SubtitleFormat;
+
+/// Abstract media processing command representation
+#[derive(Debug, Clone)]
+pub struct MediaCommand {
+    pub binary_path: String,
+    pub args: Vec<String>,
+    pub description: String,
+}
+
+impl MediaCommand {
+    /// Create a new media processing command
+    pub fn new<S1: Into<String>, S2: Into<String>>(binary_path: S1, description: S2) -> Self {
+        Self {
+            binary_path: binary_path.into(),
+            args: Vec::new(),
+            description: description.into(),
+        }
+    }
+
+    /// Add an argument
+    pub fn arg<S: Into<String>>(mut self, arg: S) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Add multiple arguments
+    pub fn args<I, S>(mut self, args: I) -> Self 
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(|s| s.into()));
+        self
+    }
+
+    /// Add input file
+    pub fn input<P: AsRef<Path>>(self, path: P) -> Self {
+        self.arg("-i").arg(platform::process_path(path.as_ref()))
+    }
+
+    /// Add output file
+    pub fn output<P: AsRef<Path>>(self, path: P) -> Self {
+        self.arg(platform::process_path(path.as_ref()))
+    }
+
+    /// Force overwrite output
+    pub fn overwrite(self) -> Self {
+        self.arg("-y")
+    }
+
+    /// Set video codec
+    pub fn video_codec<S: Into<String>>(self, codec: S) -> Self {
+        self.arg("-c:v").arg(codec)
+    }
+
+    /// Set audio codec
+    pub fn audio_codec<S: Into<String>>(self, codec: S) -> Self {
+        self.arg("-c:a").arg(codec)
+    }
+
+    /// Copy video stream
+    pub fn copy_video(self) -> Self {
+        self.video_codec("copy")
+    }
+
+    /// Copy audio stream
+    pub fn copy_audio(self) -> Self {
+        self.audio_codec("copy")
+    }
+
+    /// Disable video
+    pub fn no_video(self) -> Self {
+        self.arg("-vn")
+    }
+
+    /// Disable audio
+    pub fn no_audio(self) -> Self {
+        self.arg("-an")
+    }
+
+    /// Set audio sample rate
+    pub fn audio_sample_rate(self, rate: u32) -> Self {
+        self.arg("-ar").arg(rate.to_string())
+    }
+
+    /// Set audio channels
+    pub fn audio_channels(self, channels: u32) -> Self {
+        self.arg("-ac").arg(channels.to_string())
+    }
+
+    /// Add video filter
+    pub fn video_filter<S: Into<String>>(self, filter: S) -> Self {
+        self.arg("-vf").arg(filter)
+    }
+
+    /// Add audio filter
+    pub fn audio_filter<S: Into<String>>(self, filter: S) -> Self {
+        self.arg("-af").arg(filter)
+    }
+
+    /// Tag the output with the series, season and episode parsed from the input file name
+    pub fn episode_metadata<P: AsRef<Path>>(mut self, video_path: P) -> Self {
+        let episode = video_path.as_ref()
+            .file_stem()
+            .and_then(|stem| EpisodeInfo::parse(&stem.to_string_lossy()));
+        for tag in episode.iter().flat_map(EpisodeInfo::container_tags) {
+            self = self.arg("-metadata").arg(tag);
+        }
+        self
+    }
+
+    /// Execute the command
+    pub async fn execute(&self) -> Result<()> {
+        self.execute_output().await.map(|_| ())
+    }
+
+    /// Execute the command and return its output
+    pub async fn execute_output(&self) -> Result<std::process::Output> {
+        debug!("Executing media processing command: {} {:?}", self.binary_path, self.args);
+        debug!("Description: {}", self.description);
+
+        let mut cmd = platform::command(&self.binary_path)?;
+        cmd.args(&self.args);
+
+        let output = cmd.output().await
+            .map_err(|e| ShuroError::Media(format!("Failed to execute media processor: {}", e)))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(ShuroError::Media(format!(
+                "{} failed: {}",
+                self.description,
+                stderr
+            )));
+        }
+
+        Ok(output)
+    }
+}
+
+/// Map every stream of the first input except the given subtitle streams
+fn map_without_subtitles(mut cmd: MediaCommand, removed: &[usize]) -> MediaCommand {
+    cmd = cmd.arg("-map").arg("0");
+    for stream in removed {
+        cmd = cmd.arg("-map").arg(format!("-0:s:{}", stream));
+    }
+    cmd
+}
+
+/// Whether a file is a Matroska container, which tags tracks with ISO 639-2 language codes
+fn is_matroska(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e.eq_ignore_ascii_case("mkv") || e.eq_ignore_ascii_case("mka"))
+}
+
+/// Subtitle codec the output container stores a soft subtitle file in; Matroska keeps ASS
+/// files as ASS with their styles
+fn soft_subtitle_codec(output_path: &Path, subtitle_path: &Path) -> &'static str {
+    let extension = output_path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    match extension.as_str() {
+        "mp4" | "m4v" | "mov" => "mov_text",
+        "webm" => "webvtt",
+        _ if SubtitleFormat::is_ass_path(subtitle_path) => "ass",
+        _ => "srt",
+    }
+}
+
+/// Escape a value for use as a filter option inside an ffmpeg filtergraph
+///
+/// Applies both escaping levels described in the ffmpeg filters documentation, so drive
+/// letters (`C:`), backslash separators and commas in paths are passed through intact.
+pub fn escape_filter_value(value: &str) -> String {
+    let escape = |text: &str, special: &[char]| -> String {
+        text.chars().fold(String::new(), |mut escaped, c| {
+            if special.contains(&c) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+            escaped
+        })
+    };
+    let option_level = escape(value, &['\\', '\'', ':']);
+    escape(&option_level, &['\\', '\'', '[', ']', ',', ';'])
+}
+
+/// `subtitles` filter burning in a subtitle file, with the ASS `force_style` of a style preset
+fn subtitles_filter(subtitle_path: &Path, force_style: Option<&str>) -> String {
+    let mut filter = format!("subtitles={}", escape_filter_value(&subtitle_path.to_string_lossy()));
+    if let Some(force_style) = force_style {
+        filter.push_str(&format!(":force_style={}", escape_filter_value(force_style)));
+    }
+    filter
+}
+
+/// Builder for common media processing operations
+pub struct MediaCommandBuilder {
+    binary_path: String,
+    /// Encoder of videos with subtitles burned in
+    encoding: EncodingConfig,
+}
+
+impl MediaCommandBuilder {
+    /// Create a new command builder
+    pub fn new<S: Into<String>>(binary_path: S) -> Self {
+        Self {
+            binary_path: binary_path.into(),
+            encoding: EncodingConfig::default(),
+        }
+    }
+
+    /// Encode videos with subtitles burned in with the given encoder settings
+    pub fn with_encoding(mut self, encoding: EncodingConfig) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Re-encode the video of `cmd` with the configured encoder, then the `additional_options`
+    fn encode_video(&self, mut cmd: MediaCommand, additional_options: &[String]) -> MediaCommand {
+        cmd = cmd.video_codec(&self.encoding.video_codec);
+        if let Some(preset) = &self.encoding.preset {
+            cmd = cmd.arg("-preset").arg(preset);
+        }
+        if let Some(crf) = self.encoding.crf {
+            cmd = cmd.arg("-crf").arg(crf.to_string());
+        }
+        for option in additional_options {
+            cmd = cmd.arg(option);
+        }
+        cmd
+    }
+
+    /// Build command burning subtitles into the video stream
+    pub fn burn_subtitles<P: AsRef<Path>>(
+        &self,
+        video_path: P,
+        subtitle_path: P,
+        output_path: P,
+        force_style: Option<&str>,
+        additional_options: &[String],
+    ) -> MediaCommand {
+        let cmd = MediaCommand::new(&self.binary_path, "Subtitle burn-in")
+            .overwrite()
+            .input(&video_path)
+            .video_filter(subtitles_filter(subtitle_path.as_ref(), force_style));
+        self.encode_video(cmd, additional_options)
+            .copy_audio()
+            .episode_metadata(&video_path)
+            .output(output_path)
+    }
+
+    /// Build command rendering the `window` (in seconds) of a video with subtitles burned in
+    ///
+    /// Seeking before the input resets the clip's timestamps to zero, so the subtitle file must be
+    /// timed relative to the start of the window.
+    pub fn preview_clip<P: AsRef<Path>>(
+        &self,
+        video_path: P,
+        subtitle_path: P,
+        window: Range<f64>,
+        output_path: P,
+        force_style: Option<&str>,
+        additional_options: &[String],
+    ) -> MediaCommand {
+        let cmd = MediaCommand::new(&self.binary_path, "Preview clip")
+            .overwrite()
+            .arg("-ss").arg(format!("{:.3}", window.start))
+            .arg("-t").arg(format!("{:.3}", window.end - window.start))
+            .input(&video_path)
+            .video_filter(subtitles_filter(subtitle_path.as_ref(), force_style));
+        self.encode_video(cmd, additional_options)
+            .copy_audio()
+            .output(output_path)
+    }
+
+    /// Build soft subtitle muxing command
+    ///
+    /// Every stream of the video is copied unchanged except the `removed` subtitle streams, and the
+    /// subtitle files are appended as new tracks after the `existing_subtitle_streams` subtitle
+    /// tracks the video already has.
+    pub fn mux_subtitles<P: AsRef<Path>>(
+        &self,
+        video_path: P,
+        tracks: &[SubtitleTrack],
+        existing_subtitle_streams: usize,
+        removed: &[usize],
+        output_path: P,
+    ) -> MediaCommand {
+        let mut cmd = MediaCommand::new(&self.binary_path, "Subtitle muxing")
+            .overwrite()
+            .input(&video_path);
+        for track in tracks {
+            cmd = cmd.input(&track.path);
+        }
+
+        cmd = map_without_subtitles(cmd, removed);
+        for input in 1..=tracks.len() {
+            cmd = cmd.arg("-map").arg(input.to_string());
+        }
+
+        cmd = cmd.arg("-c").arg("copy").episode_metadata(&video_path);
+        let matroska = is_matroska(output_path.as_ref());
+        for (offset, track) in tracks.iter().enumerate() {
+            let stream = existing_subtitle_streams - removed.len() + offset;
+            let language = if matroska { bibliographic_language(&track.language) } else { track.language.clone() };
+            cmd = cmd
+                .arg(format!("-c:s:{}", stream)).arg(soft_subtitle_codec(output_path.as_ref(), &track.path))
+                .arg(format!("-metadata:s:s:{}", stream)).arg(format!("language={}", language));
+            let disposition = match (track.default, track.forced) {
+                (true, true) => Some("default+forced"),
+                (true, false) => Some("default"),
+                (false, true) => Some("forced"),
+                (false, false) => None,
+            };
+            if let Some(disposition) = disposition {
+                cmd = cmd.arg(format!("-disposition:s:{}", stream)).arg(disposition);
+            }
+        }
+
+        cmd.output(output_path)
+    }
+
+    /// Build command copying every stream of the video except the `removed` subtitle streams
+    pub fn remove_subtitle_tracks<P: AsRef<Path>>(
+        &self,
+        video_path: P,
+        removed: &[usize],
+        output_path: P,
+    ) -> MediaCommand {
+        let cmd = MediaCommand::new(&self.binary_path, "Subtitle track removal")
+            .overwrite()
+            .input(&video_path);
+        map_without_subtitles(cmd, removed)
+            .arg("-c").arg("copy")
+            .output(output_path)
+    }
+
+    /// Build command converting one subtitle stream (position among the video's subtitle streams) to SRT
+    pub fn extract_subtitle_track<P: AsRef<Path>>(
+        &self,
+        video_path: P,
+        stream: usize,
+        output_path: P,
+    ) -> MediaCommand {
+        MediaCommand::new(&self.binary_path, "Subtitle track extraction")
+            .overwrite()
+            .input(video_path)
+            .arg("-map").arg(format!("0:s:{}", stream))
+            .arg("-c:s").arg("srt")
+            .output(output_path)
+    }
+
+    /// Build audio extraction command
+    pub fn extract_audio<P: AsRef<Path>>(
+        &self,
+        video_path: P,
+        audio_path: P,
+    ) -> MediaCommand {
+        MediaCommand::new(&self.binary_path, "Audio extraction")
+            .input(video_path)
+            .no_video()
+            .audio_codec("pcm_s16le")
+            .audio_sample_rate(16000)
+            .audio_channels(1)
+            .overwrite()
+            .output(audio_path)
+    }
+
+    /// Build shot change detection command; `showinfo` logs the time of every frame whose scene
+    /// change score exceeds `threshold` on stderr
+    pub fn detect_scenes<P: AsRef<Path>>(&self, video_path: P, threshold: f64) -> MediaCommand {
+        MediaCommand::new(&self.binary_path, "Shot change detection")
+            .input(video_path)
+            .no_audio()
+            .video_filter(format!("select='gt(scene,{})',showinfo", threshold))
+            .arg("-f").arg("null")
+            .arg("-")
+    }
+
+    /// Build version check command
+    pub fn version_check(&self) -> MediaCommand {
+        MediaCommand::new(&self.binary_path, "Version check")
+            .arg("-version")
+    }
+
+    /// Build custom command
+    pub fn custom<S: Into<String>>(&self, description: S) -> MediaCommand {
+        MediaCommand::new(&self.binary_path, description.into())
+    }
+}
+
+/// Preset commands for common operations
+pub struct MediaPresets;
+
+impl MediaPresets {
+    /// Create a high-quality video encoding preset
+    pub fn high_quality_video(_builder: &MediaCommandBuilder) -> impl Fn(MediaCommand) -> MediaCommand + '_ {
+        move |cmd| {
+            cmd.video_codec("libx264")
+                .arg("-crf").arg("18")
+                .arg("-preset").arg("slow")
+        }
+    }
+
+    /// Create a fast encoding preset
+    pub fn fast_encoding(_builder: &MediaCommandBuilder) -> impl Fn(MediaCommand) -> MediaCommand + '_ {
+        move |cmd| {
+            cmd.video_codec("libx264")
+                .arg("-preset").arg("ultrafast")
+        }
+    }
+
+    /// Create a web-optimized preset
+    pub fn web_optimized(_builder: &MediaCommandBuilder) -> impl Fn(MediaCommand) -> MediaCommand + '_ {
+        move |cmd| {
+            cmd.video_codec("libx264")
+                .arg("-movflags").arg("+faststart")
+                .arg("-pix_fmt").arg("yuv420p")
+        }
+    }
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::flag_tracks;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_escape_filter_value() {
+        // Example from the "Notes on filtergraph escaping" section of the ffmpeg documentation
+        assert_eq!(
+            escape_filter_value("this is a 'string': may contain one, or more, special characters"),
+            r"this is a \\\'string\\\'\\: may contain one\, or more\, special characters"
+        );
+        assert_eq!(escape_filter_value(r"C:\subs\video.srt"), r"C\\:\\\\subs\\\\video.srt");
+    }
+
+    #[test]
+    fn test_burn_subtitles_tags_episode() {
+        let command = MediaCommandBuilder::new("ffmpeg")
+            .burn_subtitles(Path::new("Show.S01E02.mkv"), Path::new("ep.srt"), Path::new("out.mp4"), None, &[]);
+        assert!(command.args.join(" ").contains(
+            "-c:a copy -metadata season_number=1 -metadata episode_sort=2 -metadata episode_id=S01E02 -metadata show=Show out.mp4"
+        ));
+
+        let encoding = EncodingConfig { video_codec: "libx265".to_string(), crf: Some(20), preset: Some("slow".to_string()) };
+        let command = MediaCommandBuilder::new("ffmpeg")
+            .with_encoding(encoding)
+            .burn_subtitles(Path::new("ep1.mkv"), Path::new("ep.srt"), Path::new("out.mp4"), None, &["-pix_fmt".to_string(), "yuv420p".to_string()]);
+        assert!(command.args.join(" ").contains("-vf subtitles=ep.srt -c:v libx265 -preset slow -crf 20 -pix_fmt yuv420p -c:a copy"));
+    }
+
+    #[test]
+    fn test_preview_clip_seeks_before_input() {
+        let command = MediaCommandBuilder::new("ffmpeg")
+            .preview_clip(Path::new("ep1.mkv"), Path::new("clip.srt"), 1200.0..1230.0, Path::new("ep1_ja_preview.mp4"), None, &[]);
+        assert_eq!(
+            command.args.join(" "),
+            "-y -ss 1200.000 -t 30.000 -i ep1.mkv -vf subtitles=clip.srt -c:v libx264 -c:a copy ep1_ja_preview.mp4"
+        );
+
+        let command = MediaCommandBuilder::new("ffmpeg")
+            .preview_clip(Path::new("ep1.mkv"), Path::new("clip.srt"), 0.0..30.0, Path::new("clip.mp4"), Some("FontName=Arial,FontSize=20"), &[]);
+        assert!(command.args.contains(&r"subtitles=clip.srt:force_style=FontName=Arial\,FontSize=20".to_string()));
+    }
+
+    #[test]
+    fn test_mux_subtitles_appends_tracks_after_kept_ones() {
+        let tracks = vec![
+            SubtitleTrack { path: PathBuf::from("ep1_ja.srt"), language: "ja".to_string(), forced: false, default: false },
+            SubtitleTrack { path: PathBuf::from("ep1_ja.forced.srt"), language: "ja".to_string(), forced: true, default: false },
+        ];
+        let command = MediaCommandBuilder::new("ffmpeg")
+            .mux_subtitles(Path::new("ep1.mp4"), &tracks, 2, &[0], Path::new("out/ep1.mp4"));
+        let args = command.args.join(" ");
+
+        assert!(args.starts_with("-y -i ep1.mp4 -i ep1_ja.srt -i ep1_ja.forced.srt -map 0 -map -0:s:0 -map 1 -map 2 -c copy"));
+        assert!(args.contains("-c:s:1 mov_text -metadata:s:s:1 language=ja"));
+        assert!(args.contains("-c:s:2 mov_text -metadata:s:s:2 language=ja -disposition:s:2 forced"));
+        assert!(args.ends_with("out/ep1.mp4"));
+
+        let tracks = vec![SubtitleTrack { path: PathBuf::from("ep1_ja.ass"), language: "ja".to_string(), forced: false, default: false }];
+        let command = MediaCommandBuilder::new("ffmpeg")
+            .mux_subtitles(Path::new("ep1.mkv"), &tracks, 0, &[], Path::new("out/ep1.mkv"));
+        assert!(command.args.join(" ").contains("-c:s:0 ass -metadata:s:s:0 language=jpn"));
+
+        // Every language in one Matroska file, the Japanese track shown by default
+        let mut tracks = vec![
+            SubtitleTrack { path: PathBuf::from("ep1_en.srt"), language: "en".to_string(), forced: false, default: false },
+            SubtitleTrack { path: PathBuf::from("ep1_ja.srt"), language: "ja".to_string(), forced: false, default: false },
+        ];
+        flag_tracks(&mut tracks, Some("ja"), None);
+        let args = MediaCommandBuilder::new("ffmpeg")
+            .mux_subtitles(Path::new("ep1.mp4"), &tracks, 0, &[], Path::new("out/ep1_multi.mkv"))
+            .args
+            .join(" ");
+        assert!(args.contains("-c:s:0 srt -metadata:s:s:0 language=eng -c:s:1 srt -metadata:s:s:1 language=jpn -disposition:s:1 default"));
+    }
+}
@@ -0,0 +1,343 @@
+// Media inspection
+//
+// One ffprobe call (`-show_format -show_streams -show_chapters`) describes a media file: its
+// container, duration, streams with their codecs, language tags and dispositions, and chapters.
+// The workflow probes every input before any work starts, so a file without audio or an
+// `audio_track` it does not have fails at once instead of after minutes of extraction; audio
+// track selection reads the tracks from the same description. `shuro inspect` prints it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::disk::MediaSize;
+use crate::error::{Result, ShuroError};
+use crate::platform;
+
+/// Kind of a stream in a container
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamKind {
+    Video,
+    Audio,
+    Subtitle,
+    /// Data, attachment and other streams
+    Other,
+}
+
+impl StreamKind {
+    fn from_codec_type(codec_type: &str) -> Self {
+        match codec_type {
+            "video" => Self::Video,
+            "audio" => Self::Audio,
+            "subtitle" => Self::Subtitle,
+            _ => Self::Other,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Video => "video",
+            Self::Audio => "audio",
+            Self::Subtitle => "subtitle",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// Stream of a media file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StreamInfo {
+    /// Index among all streams of the container
+    pub index: usize,
+    /// Position among the container's streams of the same kind (the `a:N` / `s:N` specifier)
+    pub position: usize,
+    pub kind: StreamKind,
+    /// Codec name reported by ffprobe
+    pub codec: String,
+    pub language: Option<String>,
+    pub title: Option<String>,
+    /// Stream players pick by default
+    pub default: bool,
+    pub forced: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channels: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<u32>,
+}
+
+/// Chapter of a media file, in seconds
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Chapter {
+    pub start: f64,
+    pub end: f64,
+    pub title: Option<String>,
+}
+
+/// Container, streams and chapters of a media file
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MediaInfo {
+    /// Container format names reported by ffprobe (e.g. "matroska,webm")
+    pub format: String,
+    /// Seconds
+    pub duration: Option<f64>,
+    /// Bits per second over all streams
+    pub bit_rate: Option<u64>,
+    /// Bytes
+    pub size: Option<u64>,
+    pub streams: Vec<StreamInfo>,
+    pub chapters: Vec<Chapter>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeOutput {
+    #[serde(default)]
+    streams: Vec<ProbeStream>,
+    #[serde(default)]
+    chapters: Vec<ProbeChapter>,
+    format: Option<ProbeFormat>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeStream {
+    #[serde(default)]
+    index: usize,
+    #[serde(default)]
+    codec_type: String,
+    #[serde(default)]
+    codec_name: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    channels: Option<u32>,
+    sample_rate: Option<String>,
+    #[serde(default)]
+    disposition: HashMap<String, u8>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeChapter {
+    start_time: Option<String>,
+    end_time: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeFormat {
+    #[serde(default)]
+    format_name: String,
+    duration: Option<String>,
+    bit_rate: Option<String>,
+    size: Option<String>,
+}
+
+/// Parse `ffprobe -show_format -show_streams -show_chapters -of json` output
+pub fn parse_media_info(json: &str) -> Result<MediaInfo> {
+    let output: ProbeOutput = serde_json::from_str(json)
+        .map_err(|e| ShuroError::Media(format!("Failed to parse ffprobe output: {}", e)))?;
+
+    let mut positions: HashMap<StreamKind, usize> = HashMap::new();
+    let streams = output.streams.into_iter().map(|stream| {
+        let kind = StreamKind::from_codec_type(&stream.codec_type);
+        let position = positions.entry(kind).or_insert(0);
+        let info = StreamInfo {
+            index: stream.index,
+            position: *position,
+            kind,
+            codec: stream.codec_name,
+            language: stream.tags.get("language").filter(|l| !l.is_empty() && *l != "und").cloned(),
+            title: stream.tags.get("title").cloned(),
+            default: stream.disposition.get("default").is_some_and(|&d| d != 0),
+            forced: stream.disposition.get("forced").is_some_and(|&d| d != 0),
+            width: stream.width,
+            height: stream.height,
+            channels: stream.channels,
+            sample_rate: stream.sample_rate.and_then(|r| r.parse().ok()),
+        };
+        *position += 1;
+        info
+    }).collect();
+
+    let chapters = output.chapters.into_iter().map(|chapter| Chapter {
+        start: chapter.start_time.and_then(|t| t.parse().ok()).unwrap_or(0.0),
+        end: chapter.end_time.and_then(|t| t.parse().ok()).unwrap_or(0.0),
+        title: chapter.tags.get("title").cloned(),
+    }).collect();
+
+    let format = output.format;
+    Ok(MediaInfo {
+        format: format.as_ref().map(|f| f.format_name.clone()).unwrap_or_default(),
+        duration: format.as_ref().and_then(|f| f.duration.as_ref()).and_then(|d| d.parse().ok()),
+        bit_rate: format.as_ref().and_then(|f| f.bit_rate.as_ref()).and_then(|b| b.parse().ok()),
+        size: format.as_ref().and_then(|f| f.size.as_ref()).and_then(|s| s.parse().ok()),
+        streams,
+        chapters,
+    })
+}
+
+/// Describe a media file with the ffprobe at `ffprobe_path`
+pub async fn probe_media(ffprobe_path: &str, path: &Path) -> Result<MediaInfo> {
+    let output = platform::command(ffprobe_path)?
+        .args(["-v", "error", "-show_format", "-show_streams", "-show_chapters", "-of", "json"])
+        .arg(platform::process_path(path))
+        .output()
+        .await
+        .map_err(|e| ShuroError::Media(format!("Failed to execute ffprobe: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ShuroError::Media(format!("ffprobe failed for {}: {}", path.display(), stderr.trim())));
+    }
+
+    parse_media_info(&String::from_utf8_lossy(&output.stdout))
+}
+
+impl MediaInfo {
+    /// Streams of one kind, in container order
+    pub fn streams_of(&self, kind: StreamKind) -> impl Iterator<Item = &StreamInfo> {
+        self.streams.iter().filter(move |s| s.kind == kind)
+    }
+
+    /// Duration, bitrate and size, as used for disk space checks
+    pub fn media_size(&self) -> MediaSize {
+        MediaSize { duration: self.duration, bit_rate: self.bit_rate, size: self.size }
+    }
+
+    /// Check that `path` can be processed: it has streams, audio when `needs_audio` is set, and
+    /// the configured `audio_track` (position among its audio streams) if there is one
+    pub fn check_input(&self, path: &Path, needs_audio: bool, audio_track: Option<usize>) -> Result<()> {
+        if self.streams.is_empty() {
+            return Err(ShuroError::Media(format!("{} has no audio, video or subtitle streams", path.display())));
+        }
+        let audio = self.streams_of(StreamKind::Audio).count();
+        if needs_audio && audio == 0 {
+            return Err(ShuroError::Media(format!(
+                "{} has no audio stream to transcribe; import a transcript or use its subtitle track instead",
+                path.display()
+            )));
+        }
+        if needs_audio
+            && let Some(track) = audio_track
+            && track >= audio {
+            return Err(ShuroError::Config(format!(
+                "transcriber.audio_track is {} but {} has {} audio streams (numbered from 0)",
+                track, path.display(), audio
+            )));
+        }
+        Ok(())
+    }
+
+    /// Human-readable table of the container, streams and chapters
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Format:   {}", if self.format.is_empty() { "unknown" } else { &self.format });
+        let _ = writeln!(out, "Duration: {}", self.duration.map(format_time).unwrap_or_else(|| "unknown".to_string()));
+        if let Some(bit_rate) = self.bit_rate {
+            let _ = writeln!(out, "Bitrate:  {} kb/s", bit_rate / 1000);
+        }
+        if let Some(size) = self.size {
+            let _ = writeln!(out, "Size:     {:.1} MB", size as f64 / 1024.0 / 1024.0);
+        }
+
+        let _ = writeln!(out, "\n{:<7} {:<10} {:<16} {:<10} {:<16} {:<16} Title", "Stream", "Type", "Codec", "Language", "Details", "Flags");
+        let _ = writeln!(out, "{}", "-".repeat(90));
+        for stream in &self.streams {
+            let details = match stream.kind {
+                StreamKind::Video => stream.width.zip(stream.height).map(|(w, h)| format!("{}x{}", w, h)),
+                StreamKind::Audio => stream.channels.map(|c| match stream.sample_rate {
+                    Some(rate) => format!("{} ch, {} Hz", c, rate),
+                    None => format!("{} ch", c),
+                }),
+                _ => None,
+            };
+            let flags: Vec<&str> = [(stream.default, "default"), (stream.forced, "forced")]
+                .iter()
+                .filter(|(set, _)| *set)
+                .map(|(_, flag)| *flag)
+                .collect();
+            let _ = writeln!(out, "{:<7} {:<10} {:<16} {:<10} {:<16} {:<16} {}",
+                format!("{}:{}", stream.kind.name().chars().next().unwrap_or('?'), stream.position),
+                stream.kind.name(),
+                stream.codec,
+                stream.language.as_deref().unwrap_or("und"),
+                details.unwrap_or_default(),
+                flags.join(","),
+                stream.title.as_deref().unwrap_or_default(),
+            );
+        }
+
+        if !self.chapters.is_empty() {
+            let _ = writeln!(out, "\nChapters:");
+            for chapter in &self.chapters {
+                let _ = writeln!(out, "  {} - {}  {}", format_time(chapter.start), format_time(chapter.end), chapter.title.as_deref().unwrap_or_default());
+            }
+        }
+        out
+    }
+}
+
+/// `h:mm:ss.mmm`
+fn format_time(seconds: f64) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!("{}:{:02}:{:02}.{:03}", millis / 3_600_000, millis / 60_000 % 60, millis / 1000 % 60, millis % 1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_media_info() {
+        let json = r#"{
+            "streams": [
+                {"index": 0, "codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080, "disposition": {"default": 1, "forced": 0}},
+                {"index": 1, "codec_type": "audio", "codec_name": "aac", "channels": 2, "sample_rate": "48000", "tags": {"language": "jpn"}},
+                {"index": 2, "codec_type": "audio", "codec_name": "ac3", "channels": 6, "tags": {"language": "und", "title": "Commentary"}},
+                {"index": 3, "codec_type": "subtitle", "codec_name": "subrip", "tags": {"language": "eng"}, "disposition": {"default": 0, "forced": 1}},
+                {"index": 4, "codec_type": "attachment", "codec_name": "ttf"}
+            ],
+            "chapters": [
+                {"start_time": "0.000000", "end_time": "90.500000", "tags": {"title": "Opening"}},
+                {"start_time": "90.500000", "end_time": "1440.000000"}
+            ],
+            "format": {"format_name": "matroska,webm", "duration": "1440.000000", "bit_rate": "4000000", "size": "720000000"}
+        }"#;
+        let info = parse_media_info(json).unwrap();
+        assert_eq!(info.format, "matroska,webm");
+        assert_eq!(info.media_size().video_bytes(), Some(720_000_000));
+        assert_eq!(info.streams.len(), 5);
+
+        let audio: Vec<(usize, usize, Option<&str>)> = info.streams_of(StreamKind::Audio)
+            .map(|s| (s.index, s.position, s.language.as_deref()))
+            .collect();
+        assert_eq!(audio, vec![(1, 0, Some("jpn")), (2, 1, None)]);
+        assert_eq!(info.streams[1].sample_rate, Some(48000));
+        assert!(info.streams[0].default && !info.streams[0].forced);
+        assert!(info.streams[3].forced);
+        assert_eq!(info.streams[3].position, 0);
+        assert_eq!(info.streams[4].kind, StreamKind::Other);
+        assert_eq!(info.chapters[0], Chapter { start: 0.0, end: 90.5, title: Some("Opening".to_string()) });
+
+        let path = Path::new("ep1.mkv");
+        assert!(info.check_input(path, true, Some(1)).is_ok());
+        assert!(info.check_input(path, true, Some(2)).is_err());
+        assert!(info.check_input(path, false, Some(2)).is_ok());
+
+        let silent = parse_media_info(r#"{"streams": [{"index": 0, "codec_type": "video", "codec_name": "h264"}]}"#).unwrap();
+        assert!(silent.check_input(path, true, None).is_err());
+        assert!(silent.check_input(path, false, None).is_ok());
+        assert!(MediaInfo::default().check_input(path, false, None).is_err());
+
+        let table = info.render();
+        assert!(table.contains("0:00:00.000 - 0:01:30.500  Opening"));
+        assert!(table.contains("2 ch, 48000 Hz"));
+        assert!(table.contains("Commentary"));
+    }
+}
@@ -0,0 +1,125 @@
+// Modular media processing architecture
+//
+// This module provides a clean abstraction over media processing operations:
+// - Processor: Main implementation with abstract command building
+// - Commands: Command builders and abstractions
+// - Info: Streams, codecs and chapters of a media file, from ffprobe
+// - Tracks: Subtitle tracks inside containers
+
+pub mod commands;
+pub mod info;
+pub mod processor;
+pub mod tracks;
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::Path;
+
+pub use commands::*;
+pub use info::*;
+pub use processor::*;
+pub use tracks::*;
+
+use crate::cancel::Cancellation;
+use crate::config::MediaConfig;
+use crate::disk::MediaSize;
+use crate::error::Result;
+
+/// Main trait for media processing operations
+#[async_trait]
+pub trait MediaProcessorTrait: Send + Sync {
+    /// Burn subtitles into the video stream of a video file, re-encoding it
+    async fn burn_subtitles(
+        &self,
+        video_path: &Path,
+        subtitle_path: &Path,
+        output_path: &Path,
+        cancel: &Cancellation,
+    ) -> Result<()>;
+
+    /// Render a short clip of a video with subtitles burned in; the subtitles are timed
+    /// relative to `start`
+    async fn render_preview(
+        &self,
+        video_path: &Path,
+        subtitle_path: &Path,
+        start: f64,
+        duration: f64,
+        output_path: &Path,
+        cancel: &Cancellation,
+    ) -> Result<()>;
+
+    /// Mux subtitle files into a video as soft subtitle tracks, keeping its existing streams
+    ///
+    /// With `replace`, existing subtitle tracks in the language of a new track are dropped.
+    async fn mux_subtitles(
+        &self,
+        video_path: &Path,
+        tracks: &[SubtitleTrack],
+        replace: bool,
+        output_path: &Path,
+        cancel: &Cancellation,
+    ) -> Result<()>;
+
+    /// Times in seconds of the shot changes of a video, in ascending order
+    ///
+    /// A frame starts a new shot when its scene change score exceeds `threshold` (0.0-1.0).
+    async fn detect_shot_changes(&self, video_path: &Path, threshold: f64, cancel: &Cancellation) -> Result<Vec<f64>>;
+
+    /// Container, streams (codecs, language tags, dispositions) and chapters of a media file
+    async fn probe(&self, video_path: &Path) -> Result<MediaInfo>;
+
+    /// Duration, bitrate and size of a media file
+    async fn media_size(&self, video_path: &Path) -> Result<MediaSize>;
+
+    /// Container-level metadata tags of a media file (title, description, ...)
+    async fn format_tags(&self, video_path: &Path) -> Result<HashMap<String, String>>;
+
+    /// List the subtitle tracks of a media file
+    async fn subtitle_tracks(&self, video_path: &Path) -> Result<Vec<SubtitleStream>>;
+
+    /// Copy a video without the given subtitle streams (positions among its subtitle streams)
+    async fn remove_subtitle_tracks(
+        &self,
+        video_path: &Path,
+        streams: &[usize],
+        output_path: &Path,
+        cancel: &Cancellation,
+    ) -> Result<()>;
+
+    /// Extract one subtitle stream (position among its subtitle streams) of a video as SRT
+    async fn extract_subtitle_track(
+        &self,
+        video_path: &Path,
+        stream: usize,
+        output_path: &Path,
+        cancel: &Cancellation,
+    ) -> Result<()>;
+
+    /// Extract audio from video
+    async fn extract_audio(
+        &self,
+        video_path: &Path,
+        audio_path: &Path,
+        cancel: &Cancellation,
+    ) -> Result<()>;
+
+    /// Check if media processor is available
+    fn check_availability(&self) -> Result<()>;
+
+    /// Get media processor version information
+    async fn get_version_info(&self) -> Result<String>;
+
+    /// Execute custom media processing command
+    async fn execute_command(&self, command: MediaCommand, cancel: &Cancellation) -> Result<()>;
+}
+
+/// Factory for creating media processor instances
+pub struct MediaProcessorFactory;
+
+impl MediaProcessorFactory {
+    /// Create the default media processor implementation (FFmpeg-based)
+    pub fn create_processor(config: MediaConfig) -> Box<dyn MediaProcessorTrait> {
+        Box::new(processor::MediaProcessorImpl::new(config))
+    }
+} 
\ No newline at end of file
@@ -0,0 +1,385 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use tracing::{info, debug};
+
+use crate::cancel::Cancellation;
+use crate::config::MediaConfig;
+use crate::disk::{MediaSize, parse_media_size};
+use crate::error::{Result, ShuroError};
+use crate::platform;
+use crate::shots::parse_shot_changes;
+use crate::style::selected_style;
+use crate::subtitle::SubtitleFormat;
+use crate::translate::work::parse_format_tags;
+use super::{MediaProcessorTrait, MediaCommand, MediaCommandBuilder, MediaInfo, SubtitleStream, probe_media, SubtitleTrack, parse_subtitle_streams, superseded_streams};
+
+/// Concrete implementation of media processor (FFmpeg-based)
+pub struct MediaProcessorImpl {
+    config: MediaConfig,
+    command_builder: MediaCommandBuilder,
+}
+
+impl MediaProcessorImpl {
+    /// Create a new media processor implementation
+    pub fn new(config: MediaConfig) -> Self {
+        let command_builder = MediaCommandBuilder::new(&config.binary_path).with_encoding(config.encoding.clone());
+        
+        Self {
+            config,
+            command_builder,
+        }
+    }
+
+    /// `force_style` of the selected style preset
+    fn force_style(&self) -> Result<Option<String>> {
+        selected_style(&self.config)?.map(|style| style.force_style()).transpose()
+    }
+}
+
+#[async_trait]
+impl MediaProcessorTrait for MediaProcessorImpl {
+    /// Burn subtitles into the video stream of a video file
+    async fn burn_subtitles(
+        &self,
+        video_path: &Path,
+        subtitle_path: &Path,
+        output_path: &Path,
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        info!("Burning subtitles from {} into {} -> {}", 
+              subtitle_path.display(), video_path.display(), output_path.display());
+
+        // ASS files carry their own styles
+        let force_style = if SubtitleFormat::is_ass_path(subtitle_path) { None } else { self.force_style()? };
+        let command = self.command_builder.burn_subtitles(
+            video_path,
+            subtitle_path,
+            output_path,
+            force_style.as_deref(),
+            &self.config.subtitle_options,
+        );
+
+        cancel.run(command.execute()).await?;
+
+        info!("Subtitle burn-in completed successfully");
+        Ok(())
+    }
+
+    /// Render a short clip of a video with subtitles burned in; the subtitles are timed
+    /// relative to `start`
+    async fn render_preview(
+        &self,
+        video_path: &Path,
+        subtitle_path: &Path,
+        start: f64,
+        duration: f64,
+        output_path: &Path,
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        info!("Rendering {:.0}s preview of {} from {:.1}s -> {}",
+              duration, video_path.display(), start, output_path.display());
+
+        let force_style = self.force_style()?;
+        let command = self.command_builder.preview_clip(
+            video_path,
+            subtitle_path,
+            start..start + duration,
+            output_path,
+            force_style.as_deref(),
+            &self.config.subtitle_options,
+        );
+        cancel.run(command.execute()).await?;
+
+        info!("Preview clip completed successfully");
+        Ok(())
+    }
+
+    /// Mux subtitle files into a video as soft subtitle tracks, keeping its existing streams
+    async fn mux_subtitles(
+        &self,
+        video_path: &Path,
+        tracks: &[SubtitleTrack],
+        replace: bool,
+        output_path: &Path,
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        info!("Muxing {} subtitle tracks into {} -> {}", tracks.len(), video_path.display(), output_path.display());
+
+        let existing = cancel.run(self.subtitle_tracks(video_path)).await?;
+        let removed = if replace { superseded_streams(&existing, tracks) } else { Vec::new() };
+        if !removed.is_empty() {
+            info!("Replacing {} existing subtitle tracks", removed.len());
+        }
+        let command = self.command_builder.mux_subtitles(video_path, tracks, existing.len(), &removed, output_path);
+        cancel.run(command.execute()).await?;
+
+        info!("Subtitle muxing completed successfully");
+        Ok(())
+    }
+
+    /// Times in seconds of the shot changes of a video, in ascending order
+    async fn detect_shot_changes(&self, video_path: &Path, threshold: f64, cancel: &Cancellation) -> Result<Vec<f64>> {
+        info!("Detecting shot changes in {}", video_path.display());
+
+        let command = self.command_builder.detect_scenes(video_path, threshold);
+        let output = cancel.run(command.execute_output()).await?;
+        let shots = parse_shot_changes(&String::from_utf8_lossy(&output.stderr));
+
+        info!("Found {} shot changes", shots.len());
+        Ok(shots)
+    }
+
+    /// Container, streams (codecs, language tags, dispositions) and chapters of a media file
+    async fn probe(&self, video_path: &Path) -> Result<MediaInfo> {
+        probe_media(&self.config.ffprobe_path, video_path).await
+    }
+
+    /// Duration, bitrate and size of a media file
+    async fn media_size(&self, video_path: &Path) -> Result<MediaSize> {
+        let output = platform::command(&self.config.ffprobe_path)?
+            .args(["-v", "error", "-show_entries", "format=duration,bit_rate,size", "-of", "json"])
+            .arg(platform::process_path(video_path))
+            .output()
+            .await
+            .map_err(|e| ShuroError::Media(format!("Failed to execute ffprobe: {}", e)))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(ShuroError::Media(format!("ffprobe failed for {}: {}", video_path.display(), stderr)));
+        }
+
+        parse_media_size(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Container-level metadata tags of a media file (title, description, ...)
+    async fn format_tags(&self, video_path: &Path) -> Result<HashMap<String, String>> {
+        let output = platform::command(&self.config.ffprobe_path)?
+            .args(["-v", "error", "-show_entries", "format_tags", "-of", "json"])
+            .arg(platform::process_path(video_path))
+            .output()
+            .await
+            .map_err(|e| ShuroError::Media(format!("Failed to execute ffprobe: {}", e)))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(ShuroError::Media(format!("ffprobe failed for {}: {}", video_path.display(), stderr)));
+        }
+
+        parse_format_tags(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// List the subtitle tracks of a media file
+    async fn subtitle_tracks(&self, video_path: &Path) -> Result<Vec<SubtitleStream>> {
+        let output = platform::command(&self.config.ffprobe_path)?
+            .args(["-v", "error", "-select_streams", "s", "-show_entries", "stream=index,codec_name:stream_tags=language,title", "-of", "json"])
+            .arg(platform::process_path(video_path))
+            .output()
+            .await
+            .map_err(|e| ShuroError::Media(format!("Failed to execute ffprobe: {}", e)))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(ShuroError::Media(format!("ffprobe failed for {}: {}", video_path.display(), stderr)));
+        }
+
+        parse_subtitle_streams(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Copy a video without the given subtitle streams (positions among its subtitle streams)
+    async fn remove_subtitle_tracks(
+        &self,
+        video_path: &Path,
+        streams: &[usize],
+        output_path: &Path,
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        info!("Removing {} subtitle tracks from {} -> {}", streams.len(), video_path.display(), output_path.display());
+
+        let command = self.command_builder.remove_subtitle_tracks(video_path, streams, output_path);
+        cancel.run(command.execute()).await?;
+
+        info!("Subtitle track removal completed successfully");
+        Ok(())
+    }
+
+    /// Extract one subtitle stream (position among its subtitle streams) of a video as SRT
+    async fn extract_subtitle_track(
+        &self,
+        video_path: &Path,
+        stream: usize,
+        output_path: &Path,
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        info!("Extracting subtitle track {} from {} to {}", stream, video_path.display(), output_path.display());
+
+        let command = self.command_builder.extract_subtitle_track(video_path, stream, output_path);
+        cancel.run(command.execute()).await?;
+
+        info!("Subtitle track extraction completed");
+        Ok(())
+    }
+
+    /// Extract audio from video
+    async fn extract_audio(
+        &self,
+        video_path: &Path,
+        audio_path: &Path,
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        info!("Extracting audio from {} to {}", video_path.display(), audio_path.display());
+
+        let command = self.command_builder.extract_audio(video_path, audio_path);
+        cancel.run(command.execute()).await?;
+
+        info!("Audio extraction completed");
+        Ok(())
+    }
+
+    /// Check if media processor is available
+    fn check_availability(&self) -> Result<()> {
+        let output = Command::new(platform::resolve_binary(&self.config.binary_path)?)
+            .arg("-version")
+            .output()
+            .map_err(|e| ShuroError::Media(format!("Media processor not found: {}", e)))?;
+
+        if output.status.success() {
+            info!("Media processor is available");
+            Ok(())
+        } else {
+            Err(ShuroError::Media("Media processor version check failed".to_string()))
+        }
+    }
+
+    /// Get media processor version information
+    async fn get_version_info(&self) -> Result<String> {
+        debug!("Getting media processor version information");
+
+        let output = Command::new(platform::resolve_binary(&self.config.binary_path)?)
+            .arg("-version")
+            .output()
+            .map_err(|e| ShuroError::Media(format!("Failed to execute media processor: {}", e)))?;
+
+        if output.status.success() {
+            let version_info = String::from_utf8_lossy(&output.stdout);
+            // Extract the first line which typically contains the version
+            let first_line = version_info.lines().next().unwrap_or("Unknown version");
+            Ok(first_line.to_string())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(ShuroError::Media(format!("Media processor version check failed: {}", stderr)))
+        }
+    }
+
+    /// Execute custom media processing command
+    async fn execute_command(&self, command: MediaCommand, cancel: &Cancellation) -> Result<()> {
+        info!("Executing custom media processing command: {}", command.description);
+        cancel.run(command.execute()).await
+    }
+}
+
+/// Additional utility functions for media operations
+impl MediaProcessorImpl {
+    /// Create a command for converting video format
+    pub fn convert_video_format<P: AsRef<Path>>(
+        &self,
+        input_path: P,
+        output_path: P,
+        output_format: &str,
+    ) -> MediaCommand {
+        self.command_builder
+            .custom(format!("Convert to {}", output_format))
+            .input(input_path)
+            .video_codec("libx264")
+            .copy_audio()
+            .overwrite()
+            .output(output_path)
+    }
+
+    /// Create a command for resizing video
+    pub fn resize_video<P: AsRef<Path>>(
+        &self,
+        input_path: P,
+        output_path: P,
+        width: u32,
+        height: u32,
+    ) -> MediaCommand {
+        self.command_builder
+            .custom(format!("Resize to {}x{}", width, height))
+            .input(input_path)
+            .video_filter(format!("scale={}:{}", width, height))
+            .video_codec("libx264")
+            .copy_audio()
+            .overwrite()
+            .output(output_path)
+    }
+
+    /// Create a command for extracting video frames
+    pub fn extract_frames<P: AsRef<Path>>(
+        &self,
+        input_path: P,
+        output_pattern: P,
+        fps: f32,
+    ) -> MediaCommand {
+        self.command_builder
+            .custom("Extract frames")
+            .input(input_path)
+            .video_filter(format!("fps={}", fps))
+            .overwrite()
+            .output(output_pattern)
+    }
+
+    /// Create a command for creating video from images
+    pub fn create_video_from_images<P: AsRef<Path>>(
+        &self,
+        input_pattern: P,
+        output_path: P,
+        fps: f32,
+    ) -> MediaCommand {
+        self.command_builder
+            .custom("Create video from images")
+            .arg("-framerate").arg(fps.to_string())
+            .input(input_pattern)
+            .video_codec("libx264")
+            .arg("-pix_fmt").arg("yuv420p")
+            .overwrite()
+            .output(output_path)
+    }
+
+    /// Create a command for trimming video
+    pub fn trim_video<P: AsRef<Path>>(
+        &self,
+        input_path: P,
+        output_path: P,
+        start_time: f32,
+        duration: f32,
+    ) -> MediaCommand {
+        self.command_builder
+            .custom(format!("Trim video ({}s from {}s)", duration, start_time))
+            .input(input_path)
+            .arg("-ss").arg(start_time.to_string())
+            .arg("-t").arg(duration.to_string())
+            .copy_video()
+            .copy_audio()
+            .overwrite()
+            .output(output_path)
+    }
+
+    /// Create a command for concatenating videos
+    pub fn concatenate_videos<P: AsRef<Path>>(
+        &self,
+        input_list_file: P,
+        output_path: P,
+    ) -> MediaCommand {
+        self.command_builder
+            .custom("Concatenate videos")
+            .arg("-f").arg("concat")
+            .arg("-safe").arg("0")
+            .input(input_list_file)
+            .copy_video()
+            .copy_audio()
+            .overwrite()
+            .output(output_path)
+    }
+} 
\ No newline at end of file
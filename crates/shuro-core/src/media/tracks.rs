@@ -0,0 +1,219 @@
+// Subtitle tracks inside media containers
+//
+// Soft subtitle tracks are listed with ffprobe and addressed by their position among the
+// container's subtitle streams (the `s:N` ffmpeg stream specifier). Containers usually tag
+// tracks with ISO 639-2 codes (`jpn`) while shuro names languages with ISO 639-1 codes (`ja`),
+// so languages are compared after normalizing both forms.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::error::{Result, ShuroError};
+
+/// Subtitle file muxed into a video as a soft subtitle track
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleTrack {
+    pub path: PathBuf,
+    /// Language code written to the track metadata
+    pub language: String,
+    /// Forced-narrative track (foreign dialogue and signs only), muxed with the forced disposition
+    pub forced: bool,
+    /// Track players show by default, muxed with the default disposition
+    pub default: bool,
+}
+
+/// Subtitle stream already present in a container
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SubtitleStream {
+    /// Position among the container's subtitle streams
+    pub index: usize,
+    /// Codec name reported by ffprobe
+    pub codec: String,
+    pub language: Option<String>,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeOutput {
+    #[serde(default)]
+    streams: Vec<ProbeStream>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeStream {
+    #[serde(default)]
+    codec_name: String,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+/// Parse `ffprobe -select_streams s -of json` output into subtitle streams
+pub fn parse_subtitle_streams(json: &str) -> Result<Vec<SubtitleStream>> {
+    let output: ProbeOutput = serde_json::from_str(json)
+        .map_err(|e| ShuroError::Media(format!("Failed to parse ffprobe output: {}", e)))?;
+
+    Ok(output.streams.into_iter().enumerate().map(|(index, stream)| SubtitleStream {
+        index,
+        codec: stream.codec_name,
+        language: stream.tags.get("language").filter(|l| !l.is_empty() && *l != "und").cloned(),
+        title: stream.tags.get("title").cloned(),
+    }).collect())
+}
+
+/// Subtitle streams tagged with the given language
+pub fn streams_in_language(streams: &[SubtitleStream], language: &str) -> Vec<usize> {
+    streams
+        .iter()
+        .filter(|s| s.language.as_deref().is_some_and(|l| same_language(l, language)))
+        .map(|s| s.index)
+        .collect()
+}
+
+/// Whether a subtitle codec holds text rather than images (PGS, VobSub, DVB)
+pub fn is_text_codec(codec: &str) -> bool {
+    matches!(codec, "subrip" | "srt" | "ass" | "ssa" | "mov_text" | "webvtt" | "text")
+}
+
+/// First text subtitle stream tagged with the given language
+pub fn text_stream_in_language<'a>(streams: &'a [SubtitleStream], language: &str) -> Option<&'a SubtitleStream> {
+    streams
+        .iter()
+        .find(|s| is_text_codec(&s.codec) && s.language.as_deref().is_some_and(|l| same_language(l, language)))
+}
+
+/// Existing subtitle streams superseded by new tracks in the same language
+pub fn superseded_streams(streams: &[SubtitleStream], tracks: &[SubtitleTrack]) -> Vec<usize> {
+    let mut superseded: Vec<usize> = tracks
+        .iter()
+        .flat_map(|t| streams_in_language(streams, &t.language))
+        .collect();
+    superseded.sort_unstable();
+    superseded.dedup();
+    superseded
+}
+
+/// Whether two language codes name the same language, in ISO 639-1 or 639-2 form
+pub fn same_language(a: &str, b: &str) -> bool {
+    normalize_language(a) == normalize_language(b)
+}
+
+/// ISO 639-1 codes with their ISO 639-2 codes, the bibliographic one first where there are two
+const ISO_639_2: &[(&str, &[&str])] = &[
+    ("ja", &["jpn"]),
+    ("ko", &["kor"]),
+    ("zh", &["chi", "zho"]),
+    ("en", &["eng"]),
+    ("fr", &["fre", "fra"]),
+    ("de", &["ger", "deu"]),
+    ("es", &["spa"]),
+    ("ru", &["rus"]),
+    ("it", &["ita"]),
+    ("pt", &["por"]),
+    ("pl", &["pol"]),
+    ("nl", &["dut", "nld"]),
+    ("tr", &["tur"]),
+    ("ar", &["ara"]),
+    ("hi", &["hin"]),
+    ("th", &["tha"]),
+    ("vi", &["vie"]),
+    ("sv", &["swe"]),
+    ("da", &["dan"]),
+    ("no", &["nor"]),
+    ("fi", &["fin"]),
+    ("he", &["heb"]),
+    ("hu", &["hun"]),
+    ("cs", &["cze", "ces"]),
+    ("sk", &["slo", "slk"]),
+    ("bg", &["bul"]),
+    ("hr", &["hrv"]),
+    ("sl", &["slv"]),
+    ("et", &["est"]),
+    ("lv", &["lav"]),
+    ("lt", &["lit"]),
+    ("uk", &["ukr"]),
+    ("el", &["gre", "ell"]),
+    ("id", &["ind"]),
+    ("ms", &["may", "msa"]),
+];
+
+/// ISO 639-1 code for a language code, or the lowercased code when unknown
+fn normalize_language(code: &str) -> String {
+    let code = code.to_lowercase();
+    ISO_639_2.iter()
+        .find(|(_, long)| long.contains(&code.as_str()))
+        .map_or(code, |(short, _)| short.to_string())
+}
+
+/// ISO 639-2 bibliographic code for a language code, the form Matroska tags tracks with; unknown
+/// codes are kept as they are
+pub fn bibliographic_language(code: &str) -> String {
+    let short = normalize_language(code);
+    ISO_639_2.iter()
+        .find(|(s, _)| *s == short)
+        .map_or_else(|| code.to_string(), |(_, long)| long[0].to_string())
+}
+
+/// Flag the first full track in `default_language` as the one players show by default, and every
+/// full track in `forced_language` as forced, so players show it even with subtitles turned off
+pub fn flag_tracks(tracks: &mut [SubtitleTrack], default_language: Option<&str>, forced_language: Option<&str>) {
+    let full_in = |track: &SubtitleTrack, language: Option<&str>| {
+        !track.forced && language.is_some_and(|l| same_language(&track.language, l))
+    };
+    if let Some(track) = tracks.iter_mut().find(|t| full_in(t, default_language)) {
+        track.default = true;
+    }
+    for track in tracks.iter_mut().filter(|t| full_in(t, forced_language)) {
+        track.forced = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_subtitle_streams() {
+        let json = r#"{"streams": [
+            {"index": 2, "codec_name": "subrip", "tags": {"language": "jpn", "title": "Old"}},
+            {"index": 3, "codec_name": "ass"},
+            {"index": 4, "codec_name": "subrip", "tags": {"language": "ko"}}
+        ]}"#;
+        let streams = parse_subtitle_streams(json).unwrap();
+        assert_eq!(streams.len(), 3);
+        assert_eq!(streams[0].title.as_deref(), Some("Old"));
+        assert_eq!(streams[1].language, None);
+        assert_eq!(streams[2].index, 2);
+
+        assert_eq!(streams_in_language(&streams, "ja"), vec![0]);
+        assert_eq!(text_stream_in_language(&streams, "ko").map(|s| s.index), Some(2));
+        assert_eq!(text_stream_in_language(&streams, "en"), None);
+        assert!(!is_text_codec("hdmv_pgs_subtitle"));
+        let tracks = vec![
+            SubtitleTrack { path: PathBuf::from("ep1_ko.srt"), language: "ko".to_string(), forced: false, default: false },
+            SubtitleTrack { path: PathBuf::from("ep1_fr.srt"), language: "fr".to_string(), forced: false, default: false },
+        ];
+        assert_eq!(superseded_streams(&streams, &tracks), vec![2]);
+    }
+
+    #[test]
+    fn test_flag_tracks() {
+        let track = |path: &str, language: &str, forced: bool| SubtitleTrack {
+            path: PathBuf::from(path), language: language.to_string(), forced, default: false,
+        };
+        let mut tracks = vec![
+            track("ep1_en.srt", "en", false),
+            track("ep1_ja.forced.srt", "ja", true),
+            track("ep1_ja.srt", "ja", false),
+            track("ep1_ko.srt", "ko", false),
+        ];
+        flag_tracks(&mut tracks, Some("jpn"), Some("ko"));
+        let flags: Vec<(bool, bool)> = tracks.iter().map(|t| (t.default, t.forced)).collect();
+        assert_eq!(flags, vec![(false, false), (false, true), (true, false), (false, true)]);
+
+        assert_eq!(bibliographic_language("de"), "ger");
+        assert_eq!(bibliographic_language("zho"), "chi");
+        assert_eq!(bibliographic_language("tlh"), "tlh");
+        assert!(same_language("fra", "fre"));
+    }
+}
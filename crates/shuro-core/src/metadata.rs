@@ -0,0 +1,131 @@
+// Segment metadata
+//
+// Optional per-segment facts (speaker, confidence, source language, hallucination flags, delivery) travel
+// on `TranscriptionSegment::metadata` from the transcriber through translation into subtitle
+// writers and reports. Entries are stored by name as JSON values so transcription caches and
+// plugin payloads carry them unchanged; typed `MetadataKey` constants give every entry a single
+// name and value type. New entries get a new key instead of a new segment field.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+use crate::delivery::Delivery;
+
+/// Name and value type of a metadata entry
+pub struct MetadataKey<T> {
+    name: &'static str,
+    value: PhantomData<fn() -> T>,
+}
+
+impl<T> MetadataKey<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self { name, value: PhantomData }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// Speaker of the segment
+pub const SPEAKER: MetadataKey<String> = MetadataKey::new("speaker");
+
+/// Transcriber confidence in the segment text (0.0 to 1.0)
+pub const CONFIDENCE: MetadataKey<f64> = MetadataKey::new("confidence");
+
+/// Language the segment was spoken in
+pub const SOURCE_LANGUAGE: MetadataKey<String> = MetadataKey::new("source_language");
+
+/// Confidence that the segment is a hallucination, set when the quality checks flag it
+pub const HALLUCINATION: MetadataKey<f64> = MetadataKey::new("hallucination");
+
+/// How the segment is spoken when it stands out from ordinary speech (see delivery.rs)
+pub const DELIVERY: MetadataKey<Delivery> = MetadataKey::new("delivery");
+
+/// Metadata entries of a segment
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SegmentMetadata(BTreeMap<String, Value>);
+
+impl SegmentMetadata {
+    /// Value of an entry, `None` when it is missing or holds another type
+    pub fn get<T: DeserializeOwned>(&self, key: &MetadataKey<T>) -> Option<T> {
+        self.0.get(key.name).and_then(|v| T::deserialize(v).ok())
+    }
+
+    pub fn insert<T: Serialize>(&mut self, key: &MetadataKey<T>, value: T) {
+        let value = serde_json::to_value(value).expect("metadata values serialize to JSON");
+        self.0.insert(key.name.to_string(), value);
+    }
+
+    pub fn remove<T>(&mut self, key: &MetadataKey<T>) {
+        self.0.remove(key.name);
+    }
+
+    pub fn contains<T>(&self, key: &MetadataKey<T>) -> bool {
+        self.0.contains_key(key.name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Entries by name, including ones without a key constant (e.g. from plugins)
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.0.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Add the entries of `other` that are not set here
+    pub fn merge(&mut self, other: &SegmentMetadata) {
+        for (name, value) in &other.0 {
+            self.0.entry(name.clone()).or_insert_with(|| value.clone());
+        }
+    }
+
+    /// Entries with the same value in every given metadata, for segments merged into one
+    pub fn shared<'a>(mut all: impl Iterator<Item = &'a SegmentMetadata>) -> SegmentMetadata {
+        let Some(first) = all.next() else {
+            return SegmentMetadata::default();
+        };
+        let mut shared = first.clone();
+        for metadata in all {
+            shared.0.retain(|name, value| metadata.0.get(name) == Some(value));
+        }
+        shared
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typed_entries() {
+        let mut metadata = SegmentMetadata::default();
+        metadata.insert(&SPEAKER, "Alice".to_string());
+        metadata.insert(&CONFIDENCE, 0.75);
+        assert_eq!(metadata.get(&SPEAKER).as_deref(), Some("Alice"));
+        assert_eq!(metadata.get(&CONFIDENCE), Some(0.75));
+        assert_eq!(metadata.get(&HALLUCINATION), None);
+
+        let json = serde_json::to_string(&metadata).unwrap();
+        assert_eq!(json, r#"{"confidence":0.75,"speaker":"Alice"}"#);
+        assert_eq!(serde_json::from_str::<SegmentMetadata>(&json).unwrap(), metadata);
+    }
+
+    #[test]
+    fn test_shared_entries() {
+        let mut a = SegmentMetadata::default();
+        a.insert(&SOURCE_LANGUAGE, "ja".to_string());
+        a.insert(&SPEAKER, "Alice".to_string());
+        let mut b = a.clone();
+        b.insert(&SPEAKER, "Bob".to_string());
+
+        let shared = SegmentMetadata::shared([&a, &b].into_iter());
+        assert_eq!(shared.get(&SOURCE_LANGUAGE).as_deref(), Some("ja"));
+        assert!(!shared.contains(&SPEAKER));
+    }
+}
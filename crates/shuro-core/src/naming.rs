@@ -0,0 +1,155 @@
+// Output naming
+//
+// The subtitle files, styled copies and videos written for a video are named by
+// `media.output_template`, with `{stem}` (the video's file name without extension), `{lang}`,
+// and the episode placeholders `{series}`, `{season}`, `{episode}` and `{code}` (see
+// episode.rs); a trailing `.{ext}` may be written and stands for each output's own extension.
+// Forced-narrative subtitles are named by `media.forced_template`, or the output name with
+// `.forced` appended.
+//
+// Media servers (Jellyfin, Plex, Emby) pick up external subtitles next to the video named
+// `{stem}.{lang}.srt`, forced ones `{stem}.{lang}.forced.srt`, and recognize ISO 639-2 codes,
+// which `media.output_language_codes = "ThreeLetter"` puts in `{lang}`. For `batch`,
+// `media.output_placement` writes the outputs of every video into the output directory, into the
+// same subdirectories there as the videos are in under the input directory, or next to each video.
+//
+// With `media.existing_outputs = "SkipExisting"`, a re-run over a library skips the languages
+// whose `.srt` and video exist and were modified after the input video, so only new videos,
+// replaced videos and added languages are processed. With `multi_track`, the languages share one
+// video, which is only skipped with all of them.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::config::{ExistingOutputs, LanguageCodes, MediaConfig, OutputPlacement};
+use crate::episode::{EpisodeInfo, render_output_name};
+use crate::media::bibliographic_language;
+
+/// Base name of the outputs of `stem` in `language`
+pub fn output_name(media: &MediaConfig, stem: &str, language: &str, episode: Option<&EpisodeInfo>) -> String {
+    render_output_name(base_template(&media.output_template), stem, &language_code(media, language), episode)
+}
+
+/// Base name of the forced-narrative subtitles of `stem` in `language`, whose outputs are named
+/// `output_name`
+pub fn forced_name(media: &MediaConfig, output_name: &str, stem: &str, language: &str, episode: Option<&EpisodeInfo>) -> String {
+    match &media.forced_template {
+        Some(template) => render_output_name(base_template(template), stem, &language_code(media, language), episode),
+        None => format!("{}.forced", output_name),
+    }
+}
+
+/// Directory the outputs of `video` go to in a batch over `input_dir` into `output_dir`
+pub fn batch_output_dir(placement: OutputPlacement, input_dir: &Path, output_dir: &Path, video: &Path) -> PathBuf {
+    let video_dir = video.parent().unwrap_or(input_dir);
+    match placement {
+        OutputPlacement::OutputDir => output_dir.to_path_buf(),
+        OutputPlacement::Mirror => match video_dir.strip_prefix(input_dir) {
+            Ok(relative) => output_dir.join(relative),
+            Err(_) => output_dir.to_path_buf(),
+        },
+        OutputPlacement::NextToSource => video_dir.to_path_buf(),
+    }
+}
+
+/// Languages of `languages` whose outputs of `video` in `output_dir` are newer than the video and
+/// are kept; none unless `media.existing_outputs` skips them
+pub fn up_to_date_languages(media: &MediaConfig, video: &Path, output_dir: &Path, languages: &[String]) -> Vec<String> {
+    if media.existing_outputs != ExistingOutputs::SkipExisting {
+        return Vec::new();
+    }
+    let (Some(stem), Some(source)) = (video.file_stem(), modified(video)) else {
+        return Vec::new();
+    };
+    let stem = stem.to_string_lossy();
+    let episode = EpisodeInfo::parse(&stem);
+    let newer = |name: &str, extension: &str| {
+        modified(&output_dir.join(format!("{}.{}", name, extension))).is_some_and(|time| time > source)
+    };
+
+    let current: Vec<String> = languages.iter()
+        .filter(|language| {
+            let name = output_name(media, &stem, language, episode.as_ref());
+            newer(&name, "srt") && (media.multi_track.enabled || newer(&name, "mp4"))
+        })
+        .cloned()
+        .collect();
+    if media.multi_track.enabled
+        && (current.len() < languages.len() || !newer(&output_name(media, &stem, "multi", episode.as_ref()), "mkv"))
+    {
+        return Vec::new();
+    }
+    current
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// `template` without a trailing `.{ext}`, which each output replaces with its extension
+fn base_template(template: &str) -> &str {
+    template.strip_suffix(".{ext}").unwrap_or(template)
+}
+
+fn language_code(media: &MediaConfig, language: &str) -> String {
+    match media.output_language_codes {
+        LanguageCodes::AsGiven => language.to_string(),
+        LanguageCodes::ThreeLetter => bibliographic_language(language),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_media_server_names() {
+        let mut media = Config::default().media;
+        assert_eq!(output_name(&media, "Movie", "ja", None), "Movie_ja");
+        assert_eq!(forced_name(&media, "Movie_ja", "Movie", "ja", None), "Movie_ja.forced");
+
+        media.output_template = "{stem}.{lang}.{ext}".to_string();
+        media.forced_template = Some("{stem}.{lang}.forced".to_string());
+        media.output_language_codes = LanguageCodes::ThreeLetter;
+        assert_eq!(output_name(&media, "Movie (2001)", "ja", None), "Movie (2001).jpn");
+        assert_eq!(forced_name(&media, "Movie (2001).jpn", "Movie (2001)", "de", None), "Movie (2001).ger.forced");
+        assert_eq!(output_name(&media, "Movie", "multi", None), "Movie.multi");
+
+        let (input, output) = (Path::new("/shows"), Path::new("/subs"));
+        let video = Path::new("/shows/Show/Season 01/e01.mkv");
+        assert_eq!(batch_output_dir(OutputPlacement::OutputDir, input, output, video), PathBuf::from("/subs"));
+        assert_eq!(batch_output_dir(OutputPlacement::Mirror, input, output, video), PathBuf::from("/subs/Show/Season 01"));
+        assert_eq!(batch_output_dir(OutputPlacement::NextToSource, input, output, video), PathBuf::from("/shows/Show/Season 01"));
+    }
+
+    #[test]
+    fn test_up_to_date_languages() {
+        let dir = tempfile::tempdir().unwrap();
+        let video = dir.path().join("e01.mkv");
+        let written = SystemTime::now();
+        let touch = |name: &str, time: SystemTime| {
+            std::fs::File::create(dir.path().join(name)).unwrap().set_modified(time).unwrap();
+        };
+        touch("e01.mkv", written - std::time::Duration::from_secs(60));
+        touch("e01_ja.srt", written);
+        touch("e01_ja.mp4", written);
+        touch("e01_de.srt", written);
+        touch("e01_fr.srt", written - std::time::Duration::from_secs(120));
+        touch("e01_fr.mp4", written);
+        let languages: Vec<String> = ["ja", "de", "fr"].map(String::from).to_vec();
+
+        let mut media = Config::default().media;
+        assert!(up_to_date_languages(&media, &video, dir.path(), &languages).is_empty());
+        media.existing_outputs = ExistingOutputs::SkipExisting;
+        assert_eq!(up_to_date_languages(&media, &video, dir.path(), &languages), vec!["ja"]);
+
+        // One video holds every language
+        media.multi_track.enabled = true;
+        let subtitled = ["ja".to_string(), "de".to_string()];
+        assert!(up_to_date_languages(&media, &video, dir.path(), &subtitled).is_empty());
+        touch("e01_multi.mkv", written);
+        assert_eq!(up_to_date_languages(&media, &video, dir.path(), &subtitled), subtitled);
+        assert!(up_to_date_languages(&media, &video, dir.path(), &languages).is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+// Partial subtitle output
+//
+// A long video can take hours to translate, and a crash near the end used to leave nothing behind.
+// While translations stream in, every `translate.partial_flush_segments` translated segments the
+// cues translated so far are written to `{output}.partial.srt` next to the final subtitles; the
+// `.partial` infix marks the file as in progress, and it is removed once the complete `.srt` is
+// written. A run restarted on the same video seeds its translation from the partial file: each
+// streamed chunk whose cues all appear in it with the same timing takes the stored translations
+// instead of translating again. Chunks that differ (e.g. after a new transcription) are
+// translated as usual.
+
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+use crate::error::{Result, ShuroError};
+use crate::quality::{Transcription, TranscriptionSegment};
+use crate::subtitle::{SubtitleFormat, format_subtitles, parse_srt};
+
+/// Seconds cue times may differ between a partial file and the transcription it seeds
+/// (SRT stores milliseconds)
+const TIME_TOLERANCE: f64 = 0.002;
+
+/// Path of the partial subtitle file of an output named `output_name`
+pub fn partial_path(output_dir: &Path, output_name: &str) -> PathBuf {
+    output_dir.join(format!("{}.partial.srt", output_name))
+}
+
+/// In-progress subtitle file of one target language
+pub struct PartialOutput {
+    path: PathBuf,
+    language: String,
+    /// Translated segments this output is flushed after (0 = never)
+    flush_every: usize,
+    /// Segments in the file when the last flush happened
+    flushed: usize,
+    /// Cues of the partial file left by an interrupted run
+    seed: Vec<TranscriptionSegment>,
+}
+
+impl PartialOutput {
+    /// Open the partial file at `path`, reading the cues an interrupted run left there
+    pub async fn open(path: PathBuf, language: &str, flush_every: usize) -> Self {
+        let seed = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => match parse_srt(&content, language) {
+                Ok(transcription) => {
+                    info!("📝 Resuming {} from {} translated cues in {}", language, transcription.segments.len(), path.display());
+                    transcription.segments
+                }
+                Err(e) => {
+                    warn!("Ignoring unreadable partial subtitles {}: {}", path.display(), e);
+                    Vec::new()
+                }
+            },
+            Err(_) => Vec::new(),
+        };
+        Self { path, language: language.to_string(), flush_every, flushed: 0, seed }
+    }
+
+    /// Translations of `chunk` taken from the partial file, when it holds every cue of the chunk
+    /// at position `offset` with the same timing
+    pub fn seeded(&self, offset: usize, chunk: &[TranscriptionSegment]) -> Option<Vec<TranscriptionSegment>> {
+        let stored = self.seed.get(offset..offset + chunk.len())?;
+        let same_timing = chunk.iter().zip(stored).all(|(source, cue)| {
+            (source.start - cue.start).abs() <= TIME_TOLERANCE && (source.end - cue.end).abs() <= TIME_TOLERANCE
+        });
+        same_timing.then(|| {
+            chunk.iter()
+                .zip(stored)
+                .map(|(source, cue)| TranscriptionSegment { text: cue.text.clone(), ..source.clone() })
+                .collect()
+        })
+    }
+
+    /// Write `translated` to the partial file once `flush_every` segments were added since the last flush
+    pub async fn flush(&mut self, translated: &[TranscriptionSegment]) -> Result<()> {
+        if self.flush_every == 0 || translated.len() < self.flushed + self.flush_every {
+            return Ok(());
+        }
+        let transcription = Transcription {
+            text: String::new(),
+            segments: translated.to_vec(),
+            language: self.language.clone(),
+        };
+        // Write through a temporary file so a crash never leaves the partial file truncated
+        let temp = self.path.with_extension("srt.tmp");
+        tokio::fs::write(&temp, format_subtitles(&transcription, SubtitleFormat::Srt)).await
+            .map_err(|e| ShuroError::Cache(format!("Failed to write partial subtitles: {}", e)))?;
+        tokio::fs::rename(&temp, &self.path).await
+            .map_err(|e| ShuroError::Cache(format!("Failed to write partial subtitles: {}", e)))?;
+        self.flushed = translated.len();
+        debug!("Flushed {} translated cues to {}", translated.len(), self.path.display());
+        Ok(())
+    }
+
+    /// Remove the partial file once the complete subtitles are written
+    pub async fn finish(&self) -> Result<()> {
+        match tokio::fs::remove_file(&self.path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(ShuroError::Io(e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_partial_output_seeds_resumed_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = parse_srt(
+            "1\n00:00:01,000 --> 00:00:02,000\nHello\n\n2\n00:00:03,000 --> 00:00:04,000\nWorld\n\n\
+             3\n00:00:05,000 --> 00:00:06,000\nAgain\n",
+            "en",
+        ).unwrap().segments;
+        let translated: Vec<TranscriptionSegment> = source[..2].iter()
+            .zip(["Hola", "Mundo"])
+            .map(|(s, text)| TranscriptionSegment { text: text.to_string(), ..s.clone() })
+            .collect();
+
+        let path = partial_path(dir.path(), "movie_es");
+        let mut output = PartialOutput::open(path.clone(), "es", 2).await;
+        output.flush(&translated[..1]).await.unwrap();
+        assert!(!path.exists());
+        output.flush(&translated).await.unwrap();
+
+        let resumed = PartialOutput::open(path.clone(), "es", 2).await;
+        let seeded = resumed.seeded(0, &source[..2]).unwrap();
+        assert_eq!(seeded.iter().map(|s| s.text.as_str()).collect::<Vec<_>>(), vec!["Hola", "Mundo"]);
+        assert!(resumed.seeded(1, &source[1..]).is_none());
+        assert!(resumed.seeded(0, &source[1..2]).is_none());
+
+        resumed.finish().await.unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,153 @@
+// Dry runs
+//
+// `process --dry-run` and `batch --dry-run` plan a run instead of doing it, to check a
+// configuration against a large library before hours of work depend on it. Every video is probed
+// with ffprobe and looked up in the audio and transcription caches; whisper, the translation model
+// and ffmpeg never run, and nothing is written. The plan lists per video the steps that would run
+// and those whose results would come from the cache, how many segments would be translated, and
+// the outputs that would be written.
+//
+// The segment count is exact when the transcription is cached or read from a transcript file, and
+// otherwise estimated from the duration at one segment per `SECONDS_PER_SEGMENT` seconds. Cached
+// translations are looked up per segment and its context while translating, so the plan cannot
+// tell them apart and lists every translation as running.
+
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Seconds of video per transcribed segment, for estimates without a transcription
+pub const SECONDS_PER_SEGMENT: f64 = 4.0;
+
+/// Step a run would take for one video
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlannedStep {
+    pub name: String,
+    /// Whether the result would be taken from the cache instead of the step running
+    pub cached: bool,
+    /// Model, track or cache file involved
+    pub detail: String,
+}
+
+impl PlannedStep {
+    pub fn run(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self { name: name.into(), cached: false, detail: detail.into() }
+    }
+
+    pub fn cached(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self { name: name.into(), cached: true, detail: detail.into() }
+    }
+}
+
+/// Plan of one video
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FilePlan {
+    pub file: PathBuf,
+    /// Seconds
+    pub duration: Option<f64>,
+    pub steps: Vec<PlannedStep>,
+    /// Segments translated into each language
+    pub segments: usize,
+    /// Whether `segments` is estimated from the duration
+    pub estimated: bool,
+    pub outputs: Vec<PathBuf>,
+    /// Why the video could not be processed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl FilePlan {
+    /// Plan of a video the run would fail on
+    pub fn failed(file: PathBuf, error: String) -> Self {
+        Self { file, error: Some(error), ..Self::default() }
+    }
+}
+
+/// Plans of all videos of a run, in processing order
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProcessingPlan {
+    pub files: Vec<FilePlan>,
+}
+
+impl ProcessingPlan {
+    /// Segments estimated from the duration of `seconds`
+    pub fn estimate_segments(seconds: f64) -> usize {
+        (seconds / SECONDS_PER_SEGMENT).ceil() as usize
+    }
+
+    /// Videos the run would fail on
+    pub fn failed(&self) -> usize {
+        self.files.iter().filter(|f| f.error.is_some()).count()
+    }
+
+    /// Steps per video, then a summary line
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        for file in &self.files {
+            let duration = file.duration.map(|d| format!(" ({})", format_duration(d))).unwrap_or_default();
+            text.push_str(&format!("{}{}\n", file.file.display(), duration));
+            if let Some(error) = &file.error {
+                text.push_str(&format!("  FAIL    {}\n", error));
+                continue;
+            }
+            let width = file.steps.iter().map(|s| s.name.chars().count()).max().unwrap_or(0);
+            for step in &file.steps {
+                let status = if step.cached { "cached" } else { "run" };
+                text.push_str(&format!("  {:<7} {:<width$}  {}\n", status, step.name, step.detail));
+            }
+            let approximately = if file.estimated { "~" } else { "" };
+            text.push_str(&format!("  {}{} segments per language\n", approximately, file.segments));
+            for output in &file.outputs {
+                text.push_str(&format!("  -> {}\n", output.display()));
+            }
+        }
+
+        let cached = self.files.iter().filter(|f| f.steps.iter().any(|s| s.name == "transcribe" && s.cached)).count();
+        let segments: usize = self.files.iter().map(|f| f.segments).sum();
+        text.push_str(&format!(
+            "{} videos, {} with cached transcriptions, {} failing; ~{} segments to translate per language\n",
+            self.files.len(), cached, self.failed(), segments
+        ));
+        text
+    }
+}
+
+fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    match seconds / 3600 {
+        0 => format!("{}m {:02}s", seconds / 60, seconds % 60),
+        hours => format!("{}h {:02}m", hours, seconds % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_plan() {
+        let plan = ProcessingPlan {
+            files: vec![
+                FilePlan {
+                    file: "e01.mkv".into(),
+                    duration: Some(1420.0),
+                    steps: vec![
+                        PlannedStep::cached("extract audio", "cache/audio/e01.wav"),
+                        PlannedStep::run("transcribe", "Tuned with large-v3"),
+                        PlannedStep::run("translate ja", "gemma3:12b"),
+                    ],
+                    segments: ProcessingPlan::estimate_segments(1420.0),
+                    estimated: true,
+                    outputs: vec!["e01_ja.srt".into()],
+                    error: None,
+                },
+                FilePlan::failed("e02.mkv".into(), "e02.mkv has no audio stream".to_string()),
+            ],
+        };
+        let text = plan.render();
+        assert!(text.contains("e01.mkv (23m 40s)\n"));
+        assert!(text.contains("  cached  extract audio  cache/audio/e01.wav\n"));
+        assert!(text.contains("  ~355 segments per language\n  -> e01_ja.srt\n"));
+        assert!(text.contains("  FAIL    e02.mkv has no audio stream\n"));
+        assert!(text.ends_with("2 videos, 0 with cached transcriptions, 1 failing; ~355 segments to translate per language\n"));
+    }
+}
@@ -0,0 +1,136 @@
+// Platform-specific process and path handling
+//
+// Binaries are configured either by name ("ffmpeg") or by path ("/opt/bin/whisper-cli",
+// "C:\tools\ffmpeg"). `resolve_binary` turns both forms into an existing executable,
+// searching PATH for names and, on Windows, trying every PATHEXT extension so that
+// "ffmpeg" finds "ffmpeg.exe". Paths handed to child processes go through `process_path`,
+// which adds the `\\?\` prefix on Windows once a path exceeds MAX_PATH.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use crate::error::{Result, ShuroError};
+
+/// Longest path Windows APIs accept without the extended-length prefix
+const WINDOWS_MAX_PATH: usize = 260;
+
+/// Extensions tried when PATHEXT is not set
+const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
+/// Resolve a configured binary name or path to an existing executable
+pub fn resolve_binary(binary: &str) -> Result<PathBuf> {
+    let extensions = executable_extensions();
+    let path = Path::new(binary);
+
+    let found = if path.is_absolute() || path.components().count() > 1 {
+        executable_candidates(path, &extensions).into_iter().find(|c| c.is_file())
+    } else {
+        std::env::var_os("PATH")
+            .map(|paths| std::env::split_paths(&paths).collect::<Vec<_>>())
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|dir| executable_candidates(&dir.join(path), &extensions))
+            .find(|c| c.is_file())
+    };
+
+    found.ok_or_else(|| ShuroError::FileNotFound(format!("Executable '{}' not found in PATH", binary)))
+}
+
+/// Create a command for a configured binary; the child is killed when the command is dropped
+pub fn command(binary: &str) -> Result<Command> {
+    let mut cmd = Command::new(resolve_binary(binary)?);
+    cmd.kill_on_drop(true);
+    Ok(cmd)
+}
+
+/// Convert a path into the form passed as an argument to child processes
+pub fn process_path(path: &Path) -> String {
+    let display = path.to_string_lossy().to_string();
+    if cfg!(windows) {
+        extended_length_path(&display)
+    } else {
+        display
+    }
+}
+
+/// Extensions that make a file executable on this platform (empty outside Windows)
+fn executable_extensions() -> Vec<String> {
+    if !cfg!(windows) {
+        return Vec::new();
+    }
+    std::env::var("PATHEXT")
+        .unwrap_or_else(|_| DEFAULT_PATHEXT.to_string())
+        .split(';')
+        .filter(|ext| !ext.is_empty())
+        .map(|ext| ext.to_string())
+        .collect()
+}
+
+/// Files that may hold the executable: the path itself, then the path with each extension
+fn executable_candidates(path: &Path, extensions: &[String]) -> Vec<PathBuf> {
+    let mut candidates = vec![path.to_path_buf()];
+    if path.extension().is_none() {
+        candidates.extend(extensions.iter().map(|ext| {
+            let mut name = OsString::from(path.as_os_str());
+            name.push(ext);
+            PathBuf::from(name)
+        }));
+    }
+    candidates
+}
+
+/// Add the Windows extended-length prefix to absolute paths longer than MAX_PATH
+fn extended_length_path(path: &str) -> String {
+    if path.len() < WINDOWS_MAX_PATH || path.starts_with(r"\\?\") {
+        return path.to_string();
+    }
+    if let Some(unc) = path.strip_prefix(r"\\") {
+        return format!(r"\\?\UNC\{}", unc);
+    }
+    let bytes = path.as_bytes();
+    if bytes.len() > 2 && bytes[1] == b':' && bytes[0].is_ascii_alphabetic() {
+        return format!(r"\\?\{}", path.replace('/', r"\"));
+    }
+    path.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_executable_candidates() {
+        let extensions = vec![".EXE".to_string(), ".CMD".to_string()];
+        assert_eq!(
+            executable_candidates(Path::new("bin/ffmpeg"), &extensions),
+            vec![PathBuf::from("bin/ffmpeg"), PathBuf::from("bin/ffmpeg.EXE"), PathBuf::from("bin/ffmpeg.CMD")]
+        );
+        assert_eq!(
+            executable_candidates(Path::new("whisper.exe"), &extensions),
+            vec![PathBuf::from("whisper.exe")]
+        );
+    }
+
+    #[test]
+    fn test_extended_length_path() {
+        let long_name = "a".repeat(WINDOWS_MAX_PATH);
+        assert_eq!(extended_length_path(r"C:\short.wav"), r"C:\short.wav");
+        assert_eq!(extended_length_path(&format!("C:/{}", long_name)), format!(r"\\?\C:\{}", long_name));
+        assert_eq!(extended_length_path(&format!(r"\\server\{}", long_name)), format!(r"\\?\UNC\server\{}", long_name));
+        assert_eq!(extended_length_path(&format!(r"\\?\C:\{}", long_name)), format!(r"\\?\C:\{}", long_name));
+    }
+
+    #[tokio::test]
+    async fn test_command_spawns_resolved_binary() {
+        // Cargo sets CARGO to its own executable; dropping the extension exercises PATHEXT on Windows
+        let cargo = PathBuf::from(std::env::var("CARGO").expect("CARGO is set by cargo test"));
+        let binary = cargo.with_extension("");
+        let output = command(&binary.to_string_lossy()).unwrap()
+            .arg("--version")
+            .output()
+            .await
+            .unwrap();
+        assert!(output.status.success());
+    }
+}
@@ -0,0 +1,92 @@
+// External plugin protocol
+//
+// An external plugin is an executable registered in the `[plugins]` config section.
+// For every request shuro spawns the executable, writes a single JSON request to its
+// stdin, closes stdin, and reads a single JSON response from its stdout. A non-zero
+// exit status is treated as a failure and stderr is included in the error.
+//
+// Translator request:   {"source_language", "target_language", "model", "context", "work"?: {"title"?, "year"?, "episode"?, "synopsis"?},
+//                        "segments": [{"id", "text", "metadata"?}]}
+// Translator response:  {"segments": [{"id", "text", "metadata"?}]}
+// Transcriber request:  {"audio_path", "language", "model", "temperature"}
+// Transcriber response: AbstractTranscription JSON (see transcribe/README.md)
+
+use serde::{Serialize, de::DeserializeOwned};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tracing::debug;
+
+use crate::config::ExternalPluginConfig;
+use crate::error::{Result, ShuroError};
+use crate::platform;
+
+/// Run an external plugin with a JSON request and parse its JSON response
+pub async fn invoke<Req, Resp>(
+    plugin: &ExternalPluginConfig,
+    request: &Req,
+    error: fn(String) -> ShuroError,
+) -> Result<Resp>
+where
+    Req: Serialize,
+    Resp: DeserializeOwned,
+{
+    let payload = serde_json::to_vec(request)?;
+
+    debug!("Invoking plugin '{}': {} {:?}", plugin.name, plugin.command, plugin.args);
+
+    let mut child = platform::command(&plugin.command)?
+        .args(&plugin.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| error(format!("Failed to start plugin '{}' ({}): {}", plugin.name, plugin.command, e)))?;
+
+    // The request is written while the output is read, so a plugin answering (or logging) before
+    // it has read a large request does not block on a full pipe
+    let mut stdin = child.stdin.take()
+        .ok_or_else(|| error(format!("Failed to open stdin of plugin '{}'", plugin.name)))?;
+    let write = async move {
+        let written = stdin.write_all(&payload).await;
+        drop(stdin);
+        written
+    };
+    let (written, output) = tokio::join!(write, child.wait_with_output());
+    let output = output
+        .map_err(|e| error(format!("Failed to wait for plugin '{}': {}", plugin.name, e)))?;
+
+    if !output.status.success() {
+        return Err(error(format!(
+            "Plugin '{}' failed with {}: {}",
+            plugin.name,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    written.map_err(|e| error(format!("Failed to write request to plugin '{}': {}", plugin.name, e)))?;
+
+    serde_json::from_slice(&output.stdout)
+        .map_err(|e| error(format!("Invalid response from plugin '{}': {}", plugin.name, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_plugin_echoing_large_payload() {
+        // Answers and logs more than a pipe buffer while the request is still being written
+        let plugin = ExternalPluginConfig {
+            name: "echo".to_string(),
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), "tee /dev/stderr".to_string()],
+        };
+        let text = "x".repeat(4 * 1024 * 1024);
+        let request = serde_json::json!({ "segments": [{ "id": 0, "text": text }] });
+        let response: Value = invoke(&plugin, &request, ShuroError::Translation).await.unwrap();
+        assert_eq!(response, request);
+    }
+}
@@ -0,0 +1,227 @@
+// Preflight checks
+//
+// A batch left running overnight is wasted when the translation model was never pulled or
+// whisper cannot load its model, and that used to show only after the first video had been
+// transcribed. `shuro preflight`, and every batch of at least `preflight.batch_files` videos
+// before its first file, exercises each external dependency once on a tiny input:
+//
+// - every Ollama endpoint answers `/api/version`
+// - every Ollama model the translator uses is pulled (`/api/show`); whether it is already loaded
+//   into memory (`/api/ps`) is reported as well
+// - a test sentence is translated into each target language, and the translation passes the
+//   checks of the comparison report (not empty, not left untranslated, no meta-commentary, not
+//   too long)
+// - the transcriber produces a transcript of a two-second test clip built into the binary
+//
+// The endpoint and model checks are those of the translation backend (see translate/backend.rs).
+// With the OpenAI provider, the chat completions API is checked instead of the Ollama endpoints:
+// it must accept the API key and should list the translation model. Backend checks are skipped
+// for external translator plugins, which talk to their own backends.
+// Every check runs even when an earlier one failed, so one report lists all problems.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::error::{Result, ShuroError};
+use crate::translate::openai::ChatCompletions;
+
+/// English sentence translated into each target language
+pub const TEST_SENTENCE: &str = "Good morning. The train leaves at seven, so please don't be late.";
+
+/// Sample rate whisper expects
+const SAMPLE_RATE: u32 = 16_000;
+
+/// Seconds Ollama requests of the endpoint and model checks may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Outcome of one check
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PreflightCheck {
+    pub name: String,
+    pub passed: bool,
+    /// What was found, or why the check failed
+    pub detail: String,
+}
+
+/// Outcomes of all checks, in the order they ran
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PreflightReport {
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl PreflightReport {
+    /// Record the outcome of a check
+    pub fn record(&mut self, name: impl Into<String>, outcome: Result<String>) {
+        let (passed, detail) = match outcome {
+            Ok(detail) => (true, detail),
+            Err(e) => (false, e.to_string()),
+        };
+        self.checks.push(PreflightCheck { name: name.into(), passed, detail });
+    }
+
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.passed)
+    }
+
+    /// One line per check
+    pub fn render(&self) -> String {
+        let width = self.checks.iter().map(|c| c.name.chars().count()).max().unwrap_or(0);
+        self.checks
+            .iter()
+            .map(|c| format!("{} {:<width$}  {}\n", if c.passed { "OK  " } else { "FAIL" }, c.name, c.detail))
+            .collect()
+    }
+
+    /// Fail with the failed checks unless every check passed
+    pub fn into_result(self) -> Result<()> {
+        let failed: Vec<String> = self.checks
+            .iter()
+            .filter(|c| !c.passed)
+            .map(|c| format!("{} ({})", c.name, c.detail))
+            .collect();
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(ShuroError::Preflight(failed.join("; ")))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionResponse {
+    version: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RunningModels {
+    #[serde(default)]
+    models: Vec<RunningModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RunningModel {
+    name: String,
+}
+
+/// Check that every Ollama endpoint in `urls` answers and has `models` pulled
+pub async fn check_ollama(urls: &[&str], models: &[&str], report: &mut PreflightReport) {
+    let client = Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("HTTP client creation should not fail");
+
+    for url in urls {
+        let suffix = if urls.len() > 1 { format!(" on {}", url) } else { String::new() };
+        let version = endpoint_version(&client, url).await;
+        let reachable = version.is_ok();
+        report.record(format!("Ollama endpoint {}", url), version.map(|v| format!("Ollama {}", v)));
+        if !reachable {
+            continue;
+        }
+        for model in models {
+            report.record(format!("Model {}{}", model, suffix), model_status(&client, url, model).await);
+        }
+    }
+}
+
+/// Check that the chat completions API accepts its key and lists `models`
+pub async fn check_openai(chat: &ChatCompletions, models: &[&str], report: &mut PreflightReport) {
+    let client = Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("HTTP client creation should not fail");
+    report.record(format!("Chat completions API {}", chat.base_url()), chat.check(&client, models).await);
+}
+
+async fn endpoint_version(client: &Client, url: &str) -> Result<String> {
+    let response = client.get(format!("{}/api/version", url)).send().await
+        .map_err(|e| ShuroError::Translation(format!("Failed to connect to Ollama: {}", e)))?;
+    let version: VersionResponse = response.error_for_status()?.json().await?;
+    Ok(version.version)
+}
+
+/// Whether `model` is pulled on the endpoint at `url`, and loaded into memory
+async fn model_status(client: &Client, url: &str, model: &str) -> Result<String> {
+    let response = client.post(format!("{}/api/show", url)).json(&serde_json::json!({ "name": model })).send().await?;
+    if !response.status().is_success() {
+        return Err(ShuroError::Translation(format!("not found; pull it first: ollama pull {}", model)));
+    }
+
+    let running: RunningModels = client.get(format!("{}/api/ps", url)).send().await?.error_for_status()?.json().await?;
+    Ok(if running.models.iter().any(|m| same_model(&m.name, model)) {
+        "pulled and loaded".to_string()
+    } else {
+        "pulled, loads on first request".to_string()
+    })
+}
+
+/// Whether two Ollama model names refer to the same model (`llama3` is `llama3:latest`)
+fn same_model(a: &str, b: &str) -> bool {
+    let tagged = |name: &str| if name.contains(':') { name.to_string() } else { format!("{}:latest", name) };
+    tagged(a) == tagged(b)
+}
+
+/// 16 kHz mono 16-bit PCM WAV of `samples`, the input format whisper expects
+pub fn pcm_wav(samples: &[i16]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+/// Two-second test clip for the transcriber: a short rising tone between pauses
+pub fn test_clip() -> Vec<u8> {
+    let samples: Vec<i16> = (0..SAMPLE_RATE * 2)
+        .map(|i| {
+            let t = i as f64 / SAMPLE_RATE as f64;
+            if !(0.5..1.5).contains(&t) {
+                return 0;
+            }
+            let frequency = 220.0 + 220.0 * (t - 0.5);
+            (f64::sin(std::f64::consts::TAU * frequency * t) * 8_000.0) as i16
+        })
+        .collect();
+    pcm_wav(&samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preflight_report() {
+        let mut report = PreflightReport::default();
+        report.record("Ollama endpoint http://localhost:11434", Ok("Ollama 0.6.2".to_string()));
+        assert!(report.passed());
+        report.record("Translation into ja", Err(ShuroError::Translation("Empty translation received".to_string())));
+        assert!(!report.passed());
+
+        let rendered = report.render();
+        assert!(rendered.starts_with("OK   Ollama endpoint http://localhost:11434  Ollama 0.6.2\n"));
+        assert!(rendered.contains("FAIL Translation into ja                     Translation error: Empty translation received\n"));
+        assert!(matches!(report.into_result(), Err(ShuroError::Preflight(e)) if e.starts_with("Translation into ja (")));
+
+        assert!(same_model("llama3", "llama3:latest"));
+        assert!(!same_model("gemma3n:e4b", "gemma3n:e2b"));
+
+        let clip = test_clip();
+        assert_eq!(clip.len(), 44 + 64_000);
+        assert_eq!(u32::from_le_bytes(clip[4..8].try_into().unwrap()) as usize, clip.len() - 8);
+        assert!(clip[44..].iter().any(|b| *b != 0));
+    }
+}
@@ -0,0 +1,203 @@
+// Progress events
+//
+// Front ends that supervise a run, such as the HTTP API of `shuro serve` or the control socket of
+// `batch --control-socket`, follow it through events the workflow sends to every channel given
+// with `Workflow::with_progress`: when a video starts and each stage it enters, every segment
+// translated and how many each language has translated, the transcribed segments the
+// hallucination rules flag, when the transcription is final, when a language's subtitles and
+// video are written, how the video was processed (see report.rs) and how it ended, and when a
+// batch pauses. Sending never blocks the work, and a dropped receiver is ignored. Events
+// serialize to JSON objects tagged with `"event"`.
+//
+// Applications that would rather be called back, such as GUIs updating widgets or bots posting
+// messages, implement `WorkflowObserver` (closures taking an event do) and attach it with
+// `Workflow::with_observer`. Observers are called on the workflow's task, in event order, and
+// should hand anything slow off to their own tasks.
+//
+// Each video passes through the stages of `Stage`; translation events carry the number of
+// segments in the transcription when it is known up front (imported transcripts, embedded
+// subtitles) and an estimate of the seconds left, extrapolated from how far into the audio the
+// translated segments reach. The `shuro` command line draws them as progress bars.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tokio::sync::mpsc;
+
+use crate::hallucination::FlaggedSegment;
+use crate::report::FileSummary;
+
+/// Stage a video is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    ExtractingAudio,
+    Diarizing,
+    /// Transcription, with segments translated as they arrive
+    Transcribing,
+    /// Subtitle files of every language
+    Writing,
+    /// Subtitles muxed into or burned into the video
+    Embedding,
+}
+
+impl Stage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ExtractingAudio => "extracting audio",
+            Self::Diarizing => "diarizing",
+            Self::Transcribing => "transcribing and translating",
+            Self::Writing => "writing subtitles",
+            Self::Embedding => "embedding",
+        }
+    }
+}
+
+/// Step of a run reported to a front end
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    FileStarted { file: PathBuf, languages: Vec<String> },
+    StageStarted { file: PathBuf, stage: Stage },
+    /// Segments translated into `language` so far, of `total` when known, with the estimated
+    /// seconds left
+    Translated {
+        file: PathBuf,
+        language: String,
+        segments: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        total: Option<usize>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        eta: Option<f64>,
+    },
+    /// A segment translated into `language`
+    SegmentTranslated { file: PathBuf, language: String, start: f64, end: f64, text: String },
+    /// A transcribed segment the hallucination rules flag, with the rules it failed
+    QualityRejected { file: PathBuf, segment: FlaggedSegment },
+    /// The final transcription has `segments` segments
+    Transcribed { file: PathBuf, segments: usize },
+    LanguageCompleted { file: PathBuf, language: String, subtitles: PathBuf },
+    /// `language` is not translated for `reason`, e.g. the video being in that language already
+    LanguageSkipped { file: PathBuf, language: String, reason: String },
+    /// Timings, cache use and translation checks of a finished video, sent before `FileCompleted`
+    FileSummarized { summary: FileSummary },
+    FileCompleted { file: PathBuf },
+    FileFailed { file: PathBuf, error: String },
+    /// Finished by an earlier run of a resumed batch, skipped by its supervisor, or with outputs
+    /// newer than the video (`media.existing_outputs`)
+    FileSkipped { file: PathBuf },
+    /// The batch holds before its next file until it is resumed
+    Paused,
+    Resumed,
+}
+
+pub type ProgressSender = mpsc::UnboundedSender<ProgressEvent>;
+
+/// Callback receiving the events of a run
+pub trait WorkflowObserver: Send + Sync {
+    fn on_event(&self, event: &ProgressEvent);
+}
+
+impl<F: Fn(&ProgressEvent) + Send + Sync> WorkflowObserver for F {
+    fn on_event(&self, event: &ProgressEvent) {
+        self(event)
+    }
+}
+
+/// A channel given with `Workflow::with_progress`
+pub(crate) struct ChannelObserver(pub ProgressSender);
+
+impl WorkflowObserver for ChannelObserver {
+    fn on_event(&self, event: &ProgressEvent) {
+        // A front end that stopped listening does not stop the work
+        let _ = self.0.send(event.clone());
+    }
+}
+
+/// Translation progress of one video, for the totals and estimates of its events
+#[derive(Debug, Clone)]
+pub struct FileProgress {
+    pub file: PathBuf,
+    started: Instant,
+    /// Seconds of audio
+    duration: Option<f64>,
+    /// Segments of the transcription, when it is complete before translation starts
+    total: Option<usize>,
+}
+
+impl FileProgress {
+    pub fn new(file: &Path, duration: Option<f64>, total: Option<usize>) -> Self {
+        Self { file: file.to_path_buf(), started: Instant::now(), duration, total }
+    }
+
+    /// Seconds left once the segments up to `position` seconds into the audio are translated,
+    /// at the pace so far
+    pub fn eta(&self, position: f64) -> Option<f64> {
+        estimate_left(self.started.elapsed().as_secs_f64(), position, self.duration?)
+    }
+
+    /// Event for `segments` translated into `language`, the last ending `position` seconds into the audio
+    pub fn translated(&self, language: &str, segments: usize, position: f64) -> ProgressEvent {
+        ProgressEvent::Translated {
+            file: self.file.clone(),
+            language: language.to_string(),
+            segments,
+            total: self.total,
+            eta: self.eta(position),
+        }
+    }
+}
+
+/// Seconds left for `duration` when `position` took `elapsed` seconds
+fn estimate_left(elapsed: f64, position: f64, duration: f64) -> Option<f64> {
+    (position > 0.0 && duration > 0.0).then(|| (elapsed * (duration - position).max(0.0) / position).round())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_event_json() {
+        let event = ProgressEvent::Translated { file: PathBuf::from("e01.mkv"), language: "ja".to_string(), segments: 40, total: None, eta: None };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"translated","file":"e01.mkv","language":"ja","segments":40}"#
+        );
+        let event = ProgressEvent::StageStarted { file: PathBuf::from("e01.mkv"), stage: Stage::ExtractingAudio };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"stage_started","file":"e01.mkv","stage":"extracting_audio"}"#
+        );
+
+        // A quarter of the audio in 60 seconds leaves 180
+        assert_eq!(estimate_left(60.0, 300.0, 1200.0), Some(180.0));
+        assert_eq!(estimate_left(60.0, 0.0, 1200.0), None);
+        let progress = FileProgress::new(Path::new("e01.mkv"), None, Some(120));
+        assert_eq!(progress.translated("ja", 40, 300.0), ProgressEvent::Translated {
+            file: PathBuf::from("e01.mkv"), language: "ja".to_string(), segments: 40, total: Some(120), eta: None,
+        });
+    }
+
+    #[test]
+    fn test_observers() {
+        let seen = std::sync::Mutex::new(Vec::new());
+        let observer = |event: &ProgressEvent| seen.lock().unwrap().push(event.clone());
+        let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+        let channel = ChannelObserver(events_tx);
+        let observers: Vec<&dyn WorkflowObserver> = vec![&observer, &channel];
+
+        let event = ProgressEvent::SegmentTranslated {
+            file: PathBuf::from("e01.mkv"), language: "ja".to_string(), start: 1.0, end: 2.5, text: "こんにちは".to_string(),
+        };
+        for observer in observers {
+            observer.on_event(&event);
+        }
+        assert_eq!(*seen.lock().unwrap(), vec![event.clone()]);
+        assert_eq!(events_rx.try_recv().unwrap(), event);
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"segment_translated","file":"e01.mkv","language":"ja","start":1.0,"end":2.5,"text":"こんにちは"}"#
+        );
+    }
+}
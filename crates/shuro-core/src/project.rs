@@ -0,0 +1,190 @@
+// Series/project workspaces
+//
+// A project is a directory containing a `shuro-project.toml` workspace file. It records the
+// series' glossary, character names and chosen models, and keeps the accumulated translation
+// memory (translation cache and semantic index) in `translation-memory/` next to it. Any file
+// processed inside the directory, or one of its subdirectories, picks the project up and has
+// it applied on top of the regular configuration.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config::{Config, GlossaryTerm};
+use crate::error::{Result, ShuroError};
+
+/// Name of the workspace file that marks a project directory
+pub const PROJECT_FILE_NAME: &str = "shuro-project.toml";
+
+/// Directory, relative to the project root, holding the translation memory
+pub const PROJECT_MEMORY_DIR: &str = "translation-memory";
+
+/// Models chosen for the series; unset entries keep the configured model
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectModels {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explore_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcribe_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translate_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<String>,
+}
+
+/// Contents of the workspace file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectFile {
+    /// Series name
+    pub name: String,
+    #[serde(default)]
+    pub models: ProjectModels,
+    /// Terms that must be translated exactly as given
+    #[serde(default)]
+    pub glossary: Vec<GlossaryTerm>,
+    /// Character names and their fixed translations
+    #[serde(default)]
+    pub characters: Vec<GlossaryTerm>,
+}
+
+/// A project workspace located on disk
+#[derive(Debug, Clone)]
+pub struct Project {
+    root: PathBuf,
+    file: ProjectFile,
+}
+
+impl Project {
+    /// Create a new project in `root`; fails if the directory already holds one
+    pub fn init<P: AsRef<Path>>(root: P, name: &str) -> Result<Self> {
+        let root = std::path::absolute(root.as_ref())?;
+        if root.join(PROJECT_FILE_NAME).exists() {
+            return Err(ShuroError::Config(format!("Project already exists in {}", root.display())));
+        }
+
+        std::fs::create_dir_all(root.join(PROJECT_MEMORY_DIR))?;
+        let project = Self {
+            root,
+            file: ProjectFile {
+                name: name.to_string(),
+                models: ProjectModels::default(),
+                glossary: Vec::new(),
+                characters: Vec::new(),
+            },
+        };
+        project.save()?;
+        Ok(project)
+    }
+
+    /// Load the project whose workspace file is in `root`
+    pub fn load<P: AsRef<Path>>(root: P) -> Result<Self> {
+        let root = std::path::absolute(root.as_ref())?;
+        let content = std::fs::read_to_string(root.join(PROJECT_FILE_NAME))?;
+        let file: ProjectFile = toml::from_str(&content)?;
+        Ok(Self { root, file })
+    }
+
+    /// Find the project enclosing `path` by walking up its ancestors
+    pub fn discover<P: AsRef<Path>>(path: P) -> Result<Option<Self>> {
+        let path = std::path::absolute(path.as_ref())?;
+        match path.ancestors().find(|dir| dir.join(PROJECT_FILE_NAME).is_file()) {
+            Some(root) => Ok(Some(Self::load(root)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Write the workspace file
+    pub fn save(&self) -> Result<()> {
+        let content = toml::to_string_pretty(&self.file)
+            .map_err(|e| ShuroError::Config(format!("Failed to serialize project: {}", e)))?;
+        std::fs::write(self.root.join(PROJECT_FILE_NAME), content)?;
+        Ok(())
+    }
+
+    /// Project root directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Workspace file contents
+    pub fn file(&self) -> &ProjectFile {
+        &self.file
+    }
+
+    /// Directory holding the project's translation memory
+    pub fn memory_dir(&self) -> PathBuf {
+        self.root.join(PROJECT_MEMORY_DIR)
+    }
+
+    /// Add or replace the translation of a glossary term (or character name when `character` is set)
+    pub fn with_term(mut self, source: &str, language: &str, translation: &str, character: bool) -> Self {
+        let terms = if character { &mut self.file.characters } else { &mut self.file.glossary };
+        match terms.iter_mut().find(|t| t.source == source) {
+            Some(term) => {
+                term.translations.insert(language.to_string(), translation.to_string());
+            }
+            None => terms.push(GlossaryTerm {
+                source: source.to_string(),
+                character,
+                mandatory: false,
+                translations: [(language.to_string(), translation.to_string())].into(),
+            }),
+        }
+        self
+    }
+
+    /// Apply the project's models, glossary and translation memory on top of `config`
+    pub fn apply(&self, mut config: Config) -> Config {
+        let models = &self.file.models;
+        if let Some(model) = &models.explore_model {
+            config.transcriber.explore_model = model.clone();
+        }
+        if let Some(model) = &models.transcribe_model {
+            config.transcriber.transcribe_model = model.clone();
+        }
+        if let Some(model) = &models.translate_model {
+            config.translate.model = model.clone();
+        }
+        if let Some(model) = &models.embedding_model {
+            config.translate.semantic_cache.embedding_model = model.clone();
+        }
+
+        config.translate.glossary.extend(self.file.glossary.iter().cloned());
+        config.translate.glossary.extend(self.file.characters.iter().cloned().map(|term| GlossaryTerm {
+            character: true,
+            ..term
+        }));
+        config.translate.cache_dir = self.memory_dir().to_string_lossy().to_string();
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_and_apply() {
+        let dir = tempfile::tempdir().unwrap();
+        let episode_dir = dir.path().join("season1");
+        std::fs::create_dir_all(&episode_dir).unwrap();
+
+        let project = Project::init(dir.path(), "Space Saga").unwrap()
+            .with_term("Luke", "ja", "ルーク", true)
+            .with_term("hyperdrive", "ja", "ハイパードライブ", false)
+            .with_term("Luke", "ko", "루크", true);
+        project.save().unwrap();
+        assert!(Project::init(dir.path(), "Again").is_err());
+
+        let found = Project::discover(episode_dir.join("e01.mp4")).unwrap().unwrap();
+        assert_eq!(found.file().name, "Space Saga");
+        assert_eq!(found.file().characters[0].translations.len(), 2);
+
+        let config = found.apply(Config::default());
+        assert_eq!(config.translate.glossary.len(), 2);
+        assert!(config.translate.glossary.iter().any(|t| t.source == "Luke" && t.character));
+        assert_eq!(PathBuf::from(&config.translate.cache_dir), found.memory_dir());
+
+        let outside = tempfile::tempdir().unwrap();
+        assert!(Project::discover(outside.path()).unwrap().is_none());
+    }
+}
@@ -0,0 +1,316 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use unicode_segmentation::UnicodeSegmentation;
+use crate::config::{GlossaryTerm, HallucinationConfig, LengthValidationConfig, QualityConfig};
+use crate::error::{Result, ShuroError};
+use crate::hallucination::check_segment;
+use crate::metadata::{SegmentMetadata, HALLUCINATION};
+use crate::translate::mentions_term;
+
+// whisper.cpp output types live in the transcribe module; re-exported here for existing callers
+pub use crate::transcribe::whisper_cpp::{
+    WhisperCppOutput, WhisperCppResult, WhisperCppSegment, WhisperCppTimestamps, WhisperCppOffsets,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptionQuality {
+    pub tokens_max_segment: i32,
+    pub repetitive_segments: f64,
+    pub hallucination_periods: Vec<HallucinationPeriod>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HallucinationPeriod {
+    pub start: f64,
+    pub end: f64,
+    pub confidence: f64,
+}
+
+/// Timing of a single spoken word
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WordTiming {
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptionSegment {
+    pub id: i32,
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    pub tokens: Vec<i32>,
+    pub temperature: f32,
+    pub avg_logprob: f32,
+    pub compression_ratio: f32,
+    pub no_speech_prob: f32,
+    /// Word-level timestamps, when the transcriber provides them
+    #[serde(default)]
+    pub words: Vec<WordTiming>,
+    /// Speaker, confidence, source language and other optional facts about the segment
+    #[serde(default, skip_serializing_if = "SegmentMetadata::is_empty")]
+    pub metadata: SegmentMetadata,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transcription {
+    pub text: String,
+    pub segments: Vec<TranscriptionSegment>,
+    pub language: String,
+}
+
+impl TranscriptionQuality {
+    pub fn score(&self) -> f64 {
+        let mut score = 0.0;
+        
+        // Factor in token count (higher = worse)
+        let token_penalty = (self.tokens_max_segment as f64) / 100.0;
+        score += token_penalty;
+        
+        // Factor in repetitive segments (higher = worse)
+        score += self.repetitive_segments;
+        
+        // Factor in hallucinations (count * severity)
+        let hallucination_penalty = self.hallucination_periods.len() as f64 * 0.5;
+        score += hallucination_penalty;
+        
+        score
+    }
+
+    pub fn has_hallucinations(&self) -> bool {
+        !self.hallucination_periods.is_empty()
+    }
+}
+
+impl Transcription {
+    pub fn quality(&self, rules: &HallucinationConfig) -> TranscriptionQuality {
+        let mut tokens_max = 0;
+        let mut text_counts: HashMap<String, i32> = HashMap::new();
+        
+        // Analyze segments for quality metrics
+        for segment in &self.segments {
+            if segment.tokens.len() > tokens_max {
+                tokens_max = segment.tokens.len();
+            }
+            
+            // Count text repetitions
+            let normalized_text = segment.text.trim().to_lowercase();
+            if !normalized_text.is_empty() {
+                *text_counts.entry(normalized_text).or_insert(0) += 1;
+            }
+        }
+        
+        // Calculate repetitive segment ratio
+        let total_segments = self.segments.len() as f64;
+        let repetitive_count = text_counts.values()
+            .filter(|&&count| count > 1)
+            .map(|&count| count - 1) // Only count duplicates
+            .sum::<i32>() as f64;
+        
+        let repetitive_segments = if total_segments > 0.0 {
+            repetitive_count / total_segments
+        } else {
+            0.0
+        };
+        
+        // Detect potential hallucinations with the rules of hallucination.rs
+        let hallucination_periods = self.detect_hallucinations(rules);
+        
+        TranscriptionQuality {
+            tokens_max_segment: tokens_max as i32,
+            repetitive_segments,
+            hallucination_periods,
+        }
+    }
+
+    fn detect_hallucinations(&self, rules: &HallucinationConfig) -> Vec<HallucinationPeriod> {
+        self.segments
+            .iter()
+            .flat_map(|segment| check_segment(segment, &self.language, rules).into_iter().map(|hit| HallucinationPeriod {
+                start: segment.start,
+                end: segment.end,
+                confidence: hit.confidence,
+            }))
+            .collect()
+    }
+
+    /// Record the hallucination rules on the segments they flag, in their metadata
+    pub fn flag_hallucinations(&mut self, rules: &HallucinationConfig) {
+        for segment in &mut self.segments {
+            let hits = check_segment(segment, &self.language, rules);
+            match hits.into_iter().map(|hit| hit.confidence).reduce(f64::max) {
+                Some(confidence) => segment.metadata.insert(&HALLUCINATION, confidence),
+                None => segment.metadata.remove(&HALLUCINATION),
+            }
+        }
+    }
+}
+
+pub struct QualityValidator {
+    repetitive_threshold: f64,
+    max_tokens_threshold: f64,
+    min_quality_score: f64,
+    hallucination: HallucinationConfig,
+}
+
+impl QualityValidator {
+    pub fn new(repetitive_threshold: f64, max_tokens_threshold: f64, min_quality_score: f64, hallucination: HallucinationConfig) -> Self {
+        Self {
+            repetitive_threshold,
+            max_tokens_threshold,
+            min_quality_score,
+            hallucination,
+        }
+    }
+
+    /// Validator with the thresholds and hallucination rules of `[quality]`
+    pub fn from_config(quality: &QualityConfig) -> Self {
+        Self::new(
+            quality.repetitive_segment_threshold,
+            quality.max_tokens_threshold,
+            quality.min_quality_score,
+            quality.hallucination.clone(),
+        )
+    }
+
+    /// Quality of a transcription under the configured hallucination rules
+    pub fn quality(&self, transcription: &Transcription) -> TranscriptionQuality {
+        transcription.quality(&self.hallucination)
+    }
+
+    pub fn validate_transcription(&self, transcription: &Transcription) -> Result<()> {
+        let quality = self.quality(transcription);
+        
+        if quality.has_hallucinations() {
+            return Err(ShuroError::Hallucination);
+        }
+        
+        if quality.repetitive_segments > self.repetitive_threshold {
+            return Err(ShuroError::Quality(format!(
+                "Too many repetitive segments: {} > {}",
+                quality.repetitive_segments, self.repetitive_threshold
+            )));
+        }
+        
+        if quality.tokens_max_segment as f64 > self.max_tokens_threshold {
+            return Err(ShuroError::Quality(format!(
+                "Segment too long: {} tokens > {}",
+                quality.tokens_max_segment, self.max_tokens_threshold
+            )));
+        }
+        
+        if quality.score() > self.min_quality_score {
+            return Err(ShuroError::Quality(format!(
+                "Quality score too low: {} > {}",
+                quality.score(), self.min_quality_score
+            )));
+        }
+        
+        Ok(())
+    }
+} 
+
+/// Typical grapheme length of text in each language relative to English
+const DEFAULT_LENGTH_RATIOS: &[(&str, f64)] = &[
+    ("en", 1.0), ("de", 1.2), ("fr", 1.2), ("es", 1.2), ("it", 1.15), ("pt", 1.15),
+    ("nl", 1.15), ("pl", 1.1), ("ru", 1.1), ("uk", 1.1), ("tr", 1.05), ("vi", 1.1),
+    ("ar", 0.9), ("he", 0.8), ("hi", 1.0), ("th", 0.9),
+    ("ja", 0.45), ("zh", 0.3), ("ko", 0.5),
+];
+
+/// Grapheme length ratio of a language relative to English (1.0 when unknown)
+fn length_ratio(config: &LengthValidationConfig, language: &str) -> f64 {
+    let language = language.to_lowercase();
+    config.languages.get(&language).copied()
+        .or_else(|| DEFAULT_LENGTH_RATIOS.iter().find(|(code, _)| *code == language).map(|(_, ratio)| *ratio))
+        .unwrap_or(1.0)
+}
+
+/// Expected grapheme length of a translation of `source`
+fn expected_length(config: &LengthValidationConfig, source: &str, source_language: &str, target_language: &str) -> f64 {
+    source.graphemes(true).count() as f64 * length_ratio(config, target_language) / length_ratio(config, source_language)
+}
+
+/// Whether a translation is longer than the language pair allows, comparing grapheme counts
+pub fn is_translation_too_long(
+    config: &LengthValidationConfig,
+    source: &str,
+    translation: &str,
+    source_language: &str,
+    target_language: &str,
+) -> bool {
+    let translation_length = translation.graphemes(true).count();
+    let expected = expected_length(config, source, source_language, target_language);
+    let limit = (expected * config.max_ratio).max(config.min_limit as f64);
+    translation_length as f64 > limit
+}
+
+/// Mandatory glossary terms mentioned in `source` whose required translation into
+/// `target_language` is missing from `translation`, with that translation
+pub fn glossary_violations<'a>(
+    glossary: &'a [GlossaryTerm],
+    source: &str,
+    translation: &str,
+    target_language: &str,
+) -> Vec<(&'a GlossaryTerm, &'a str)> {
+    glossary
+        .iter()
+        .filter(|term| term.mandatory && mentions_term(source, &term.source))
+        .filter_map(|term| term.translations.get(target_language).map(|required| (term, required.as_str())))
+        .filter(|(_, required)| !mentions_term(translation, required))
+        .collect()
+}
+
+/// Grapheme length of a translation relative to the length expected for the language pair
+/// (1.0 = as expected), `None` for empty sources
+pub fn relative_translation_length(
+    config: &LengthValidationConfig,
+    source: &str,
+    translation: &str,
+    source_language: &str,
+    target_language: &str,
+) -> Option<f64> {
+    let expected = expected_length(config, source, source_language, target_language);
+    (expected > 0.0).then(|| translation.graphemes(true).count() as f64 / expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_translation_too_long() {
+        let config = LengthValidationConfig::default();
+        let english = "We need to leave before the storm reaches the harbor tonight.";
+        let japanese = "今夜嵐が港に着く前に出発しなければならない。";
+
+        // CJK translations of English are short in graphemes and long in bytes
+        assert!(!is_translation_too_long(&config, english, japanese, "en", "ja"));
+        assert!(!is_translation_too_long(&config, japanese, english, "ja", "en"));
+
+        let runaway = english.repeat(4);
+        assert!(is_translation_too_long(&config, english, &runaway, "en", "fr"));
+        assert!(is_translation_too_long(&config, japanese, &runaway, "ja", "en"));
+
+        // Short source texts are allowed up to the minimum limit
+        assert!(!is_translation_too_long(&config, "OK", "わかりました", "en", "ja"));
+    }
+
+    #[test]
+    fn test_glossary_violations() {
+        let term = |source: &str, ja: &str, mandatory: bool| GlossaryTerm {
+            source: source.to_string(),
+            character: false,
+            mandatory,
+            translations: HashMap::from([("ja".to_string(), ja.to_string())]),
+        };
+        let glossary = vec![term("Luke", "ルーク", true), term("hyperdrive", "ハイパードライブ", false)];
+
+        let violations = glossary_violations(&glossary, "Luke, start the hyperdrive", "ルカ、超空間航法を起動して", "ja");
+        assert_eq!(violations.len(), 1);
+        assert_eq!((violations[0].0.source.as_str(), violations[0].1), ("Luke", "ルーク"));
+        assert!(glossary_violations(&glossary, "Luke, start the hyperdrive", "ルーク、起動して", "ja").is_empty());
+        assert!(glossary_violations(&glossary, "Where is Luke?", "Wo ist Lukas?", "de").is_empty());
+    }
+}
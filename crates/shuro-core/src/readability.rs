@@ -0,0 +1,359 @@
+// Subtitle readability
+//
+// Translations run longer or shorter than the source line they replace, so a cue timed for the
+// source can end up with lines too long for the screen, more lines than fit, or more text than
+// can be read before it disappears. With `quality.readability.enabled`, the cues of the subtitle
+// files written for every target language (forced tracks included) are brought within the limits:
+//
+// - a cue with a line longer than `max_line_length` or more than `max_lines` lines is re-wrapped
+//   into balanced lines, breaking between words, or between characters in Chinese and Japanese
+// - a cue still longer than `max_lines` lines is split into consecutive cues of about equal text,
+//   sharing its time in proportion to their text, none shorter than `min_duration`
+// - a cue read faster than `max_chars_per_second` is lengthened into the gap before the next cue;
+//   starts never move, so cues still appear with the speech
+//
+// Chinese, Japanese and Korean get the shorter lines and slower reading speeds of common subtitle
+// style guides; `languages`, and the limits set in `[translate.languages.<code>]`, override the
+// limits of any language. Cues within the limits are left as translated. Translator's notes,
+// comparison reports and manifests see the translation before these changes, cue for cue with the
+// source.
+
+use std::collections::HashMap;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::config::{LanguageOverrides, ReadabilityConfig};
+use crate::forced::Script;
+use crate::quality::{Transcription, TranscriptionSegment};
+
+/// (language, characters per second, characters per line) of languages read slower than the default
+const BUILTIN_LIMITS: &[(&str, f64, usize)] = &[("ja", 4.0, 13), ("zh", 9.0, 16), ("ko", 12.0, 16)];
+
+/// Punctuation kept on the line of the character before it
+const CLOSING_PUNCTUATION: &str = "、。，．！？：；）」』】〕,.!?:;)]…ー";
+
+/// Limits in effect for one target language
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    pub chars_per_second: f64,
+    pub line_length: usize,
+    pub lines: usize,
+}
+
+impl ReadabilityConfig {
+    /// Limits of subtitles in `language`
+    pub fn limits(&self, language: &str) -> Limits {
+        let builtin = BUILTIN_LIMITS.iter().find(|(code, _, _)| code.eq_ignore_ascii_case(language));
+        let configured = self.languages.iter().find(|(code, _)| code.eq_ignore_ascii_case(language)).map(|(_, limits)| limits);
+        Limits {
+            chars_per_second: configured.and_then(|l| l.max_chars_per_second)
+                .or(builtin.map(|(_, cps, _)| *cps))
+                .unwrap_or(self.max_chars_per_second),
+            line_length: configured.and_then(|l| l.max_line_length)
+                .or(builtin.map(|(_, _, length)| *length))
+                .unwrap_or(self.max_line_length),
+            lines: configured.and_then(|l| l.max_lines).unwrap_or(self.max_lines).max(1),
+        }
+    }
+
+    /// Take the reading speed and line length limits set in `[translate.languages.<code>]` over
+    /// the ones of `languages`
+    pub fn apply_overrides(&mut self, overrides: &HashMap<String, LanguageOverrides>) {
+        for (language, overrides) in overrides {
+            if overrides.max_chars_per_second.is_none() && overrides.max_line_length.is_none() {
+                continue;
+            }
+            let code = self.languages.keys().find(|code| code.eq_ignore_ascii_case(language)).cloned();
+            let limits = self.languages.entry(code.unwrap_or_else(|| language.clone())).or_default();
+            limits.max_chars_per_second = overrides.max_chars_per_second.or(limits.max_chars_per_second);
+            limits.max_line_length = overrides.max_line_length.or(limits.max_line_length);
+        }
+    }
+}
+
+/// Limit a cue breaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    TooFast,
+    LineTooLong,
+    TooManyLines,
+}
+
+/// What bringing a translation within the limits changed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReadabilityChanges {
+    /// Cues re-wrapped into other lines
+    pub rewrapped: usize,
+    /// Cues split into several
+    pub split: usize,
+    /// Cues lengthened into the gap after them
+    pub lengthened: usize,
+    /// Cues still read faster than the limit
+    pub too_fast: usize,
+}
+
+impl ReadabilityChanges {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Punctuation ending a sentence
+const SENTENCE_END: &str = ".!?。！？";
+
+/// Characters of `text` shown on screen, line breaks excluded
+pub(crate) fn characters(text: &str) -> usize {
+    text.lines().map(|line| line.trim().graphemes(true).count()).sum()
+}
+
+/// Characters per second of a cue showing `text` for `duration` seconds
+fn reading_speed(text: &str, duration: f64) -> f64 {
+    if duration <= 0.0 {
+        return f64::INFINITY;
+    }
+    characters(text) as f64 / duration
+}
+
+/// Every limit `segment` breaks
+pub fn violations(segment: &TranscriptionSegment, limits: &Limits) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    if reading_speed(&segment.text, segment.end - segment.start) > limits.chars_per_second {
+        violations.push(Violation::TooFast);
+    }
+    if segment.text.lines().any(|line| line.trim().graphemes(true).count() > limits.line_length) {
+        violations.push(Violation::LineTooLong);
+    }
+    if segment.text.trim().lines().count() > limits.lines {
+        violations.push(Violation::TooManyLines);
+    }
+    violations
+}
+
+/// Whether `text` is written without spaces between words
+pub(crate) fn unspaced(text: &str) -> bool {
+    Script::dominant(text) == Some(Script::Cjk)
+}
+
+/// Units lines are broken between, with the separator joining them
+fn tokens(text: &str) -> (Vec<String>, &'static str) {
+    if !unspaced(text) {
+        return (text.split_whitespace().map(str::to_string).collect(), " ");
+    }
+    let mut tokens: Vec<String> = Vec::new();
+    for grapheme in text.lines().map(str::trim).collect::<String>().graphemes(true) {
+        match tokens.last_mut() {
+            Some(last) if grapheme.trim().is_empty() || CLOSING_PUNCTUATION.contains(grapheme) => last.push_str(grapheme),
+            _ => tokens.push(grapheme.to_string()),
+        }
+    }
+    (tokens, "")
+}
+
+/// Length of the line joining `tokens`
+fn line_length(tokens: &[String], separator: &str) -> usize {
+    tokens.iter().map(|t| t.graphemes(true).count()).sum::<usize>()
+        + separator.len() * tokens.len().saturating_sub(1)
+}
+
+/// `text` broken into lines of at most `length` characters; a word longer than that gets a line
+/// of its own, and text fitting two lines is broken where they are closest in length
+pub fn wrap(text: &str, length: usize) -> Vec<String> {
+    let (tokens, separator) = tokens(text);
+    let mut breaks = vec![0];
+    let mut start = 0;
+    for end in 1..=tokens.len() {
+        if end - start > 1 && line_length(&tokens[start..end], separator) > length {
+            breaks.push(end - 1);
+            start = end - 1;
+        }
+    }
+    breaks.push(tokens.len());
+
+    // Two lines are balanced, the top one no longer than the bottom one when they cannot be equal
+    if breaks.len() == 3 {
+        let best = (1..tokens.len())
+            .filter(|&at| line_length(&tokens[..at], separator) <= length && line_length(&tokens[at..], separator) <= length)
+            .min_by_key(|&at| {
+                let (top, bottom) = (line_length(&tokens[..at], separator), line_length(&tokens[at..], separator));
+                (top.max(bottom), top > bottom)
+            });
+        if let Some(at) = best {
+            breaks[1] = at;
+        }
+    }
+    breaks.windows(2)
+        .filter(|pair| pair[0] < pair[1])
+        .map(|pair| tokens[pair[0]..pair[1]].join(separator))
+        .collect()
+}
+
+/// `text` divided into `parts` runs of about equal length, cut after a sentence end when one is
+/// near the even cut
+pub(crate) fn divide(text: &str, parts: usize) -> Vec<String> {
+    let (tokens, separator) = tokens(text);
+    let total = line_length(&tokens, separator);
+    let slack = total / parts.max(1) / 4;
+    let mut cuts = vec![0];
+    for part in 1..parts {
+        let target = total * part / parts;
+        let previous = *cuts.last().unwrap_or(&0);
+        let cut = (previous + 1..tokens.len()).min_by_key(|&at| {
+            let distance = line_length(&tokens[..at], separator).abs_diff(target);
+            let sentence_end = tokens[at - 1].chars().last().is_some_and(|c| SENTENCE_END.contains(c));
+            if sentence_end { distance.saturating_sub(slack) } else { distance }
+        });
+        cuts.extend(cut);
+    }
+    cuts.push(tokens.len());
+    cuts.windows(2)
+        .filter(|pair| pair[0] < pair[1])
+        .map(|pair| tokens[pair[0]..pair[1]].join(separator))
+        .collect()
+}
+
+/// `segment` as consecutive cues dividing its text into runs of about equal length, each wrapped
+/// into lines, sharing its time in proportion to their text; `parts` cues at most, fewer when
+/// they would be shorter than `min_duration`
+fn split(segment: &TranscriptionSegment, parts: usize, limits: &Limits, min_duration: f64) -> Vec<TranscriptionSegment> {
+    let duration = segment.end - segment.start;
+    let parts = parts.min(((duration / min_duration.max(0.01)).floor() as usize).max(1));
+    let texts: Vec<String> = divide(&segment.text, parts).iter()
+        .map(|run| wrap(run, limits.line_length).join("\n"))
+        .collect();
+
+    let total = texts.iter().map(|t| characters(t)).sum::<usize>().max(1) as f64;
+    let mut start = segment.start;
+    let mut shown = 0;
+    texts.iter()
+        .map(|text| {
+            shown += characters(text);
+            let end = segment.start + duration * shown as f64 / total;
+            let part = TranscriptionSegment {
+                start,
+                end,
+                text: text.clone(),
+                // Word times are those of the source line
+                words: Vec::new(),
+                ..segment.clone()
+            };
+            start = end;
+            part
+        })
+        .collect()
+}
+
+/// `translation` into `language` with its cues brought within the readability limits
+pub fn conform(translation: &Transcription, config: &ReadabilityConfig, language: &str) -> (Transcription, ReadabilityChanges) {
+    let limits = config.limits(language);
+    let mut changes = ReadabilityChanges::default();
+    let mut segments = Vec::new();
+    for segment in &translation.segments {
+        let violations = violations(segment, &limits);
+        if !violations.contains(&Violation::LineTooLong) && !violations.contains(&Violation::TooManyLines) {
+            segments.push(segment.clone());
+            continue;
+        }
+        let lines = wrap(&segment.text, limits.line_length);
+        if lines.len() <= limits.lines {
+            changes.rewrapped += 1;
+            segments.push(TranscriptionSegment { text: lines.join("\n"), ..segment.clone() });
+        } else {
+            let parts = split(segment, lines.len().div_ceil(limits.lines), &limits, config.min_duration);
+            if parts.len() > 1 {
+                changes.split += 1;
+            } else {
+                changes.rewrapped += 1;
+            }
+            segments.extend(parts);
+        }
+    }
+
+    // Lengthen cues read too fast, up to the start of the next one
+    for index in 0..segments.len() {
+        let next_start = segments.get(index + 1).map_or(f64::INFINITY, |next| next.start);
+        let segment = &mut segments[index];
+        if reading_speed(&segment.text, segment.end - segment.start) <= limits.chars_per_second {
+            continue;
+        }
+        let needed = segment.start + characters(&segment.text) as f64 / limits.chars_per_second;
+        let end = needed.min(next_start).max(segment.end);
+        if end > segment.end {
+            segment.end = end;
+            changes.lengthened += 1;
+        }
+        if end < needed {
+            changes.too_fast += 1;
+        }
+    }
+
+    for (id, segment) in segments.iter_mut().enumerate() {
+        segment.id = id as i32;
+    }
+    let text = segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" ");
+    (Transcription { text, segments, language: translation.language.clone() }, changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ReadabilityLimits;
+    use crate::subtitle::parse_srt;
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("We need to leave before the storm reaches the harbor tonight.", 42), vec![
+            "We need to leave before the", "storm reaches the harbor tonight.",
+        ]);
+        assert_eq!(wrap("Short line", 42), vec!["Short line"]);
+        // Closing punctuation stays with the character before it
+        assert_eq!(wrap("今夜、嵐が港に着く前に出発しなければならない。", 13), vec![
+            "今夜、嵐が港に着く前に", "出発しなければならない。",
+        ]);
+        assert_eq!(wrap("one two three four five six seven", 10), vec!["one two", "three four", "five six", "seven"]);
+    }
+
+    #[test]
+    fn test_conform() {
+        let translation = parse_srt(
+            "1\n00:00:01,000 --> 00:00:04,000\nWe need to leave before the storm reaches the harbor tonight.\n\n\
+             2\n00:00:05,000 --> 00:00:11,000\nThe harbor master said the last ferry leaves at nine, \
+             and if we miss it there is no other way off the island until Monday morning.\n\n\
+             3\n00:00:12,000 --> 00:00:13,000\nThen we should hurry up and pack our bags.\n\n\
+             4\n00:00:14,000 --> 00:00:16,000\nOK.\n",
+            "en",
+        ).unwrap();
+        let config = ReadabilityConfig { enabled: true, ..Default::default() };
+
+        let (conformed, changes) = conform(&translation, &config, "en");
+        let cues: Vec<(f64, f64, &str)> = conformed.segments.iter().map(|s| (s.start, s.end, s.text.as_str())).collect();
+        assert_eq!(cues.len(), 5);
+        assert_eq!(cues[0].2, "We need to leave before the\nstorm reaches the harbor tonight.");
+        // Lengthened just enough to be read
+        assert!((cues[0].1 - (1.0 + 60.0 / 17.0)).abs() < 1e-9);
+        // The long cue is split in two at the time its text divides, the second part lengthened
+        assert_eq!(cues[1].0, 5.0);
+        assert!(cues[1].1 > 7.5 && cues[1].1 < 8.5 && cues[1].1 == cues[2].0);
+        assert!(cues[2].1 > 11.0 && cues[2].1 < 12.0);
+        assert!(cues[1].2.lines().count() == 2 && cues[2].2.lines().count() == 2);
+        // Lengthened only up to the next cue, which stays untouched
+        assert_eq!((cues[3].0, cues[3].1), (12.0, 14.0));
+        assert_eq!((cues[4].0, cues[4].1, cues[4].2), (14.0, 16.0, "OK."));
+        // The first part of the split cue and the cue before "OK." stay too fast
+        assert_eq!(changes, ReadabilityChanges { rewrapped: 1, split: 1, lengthened: 3, too_fast: 2 });
+        let limits = config.limits("en");
+        assert!(conformed.segments.iter().all(|s| violations(s, &limits).iter().all(|v| *v == Violation::TooFast)));
+
+        // Limits per language: built-in CJK ones and configured overrides
+        assert_eq!(config.limits("ja").line_length, 13);
+        let config = ReadabilityConfig {
+            languages: [("ja".to_string(), ReadabilityLimits { max_line_length: Some(16), ..Default::default() })].into(),
+            ..config
+        };
+        assert_eq!(config.limits("JA"), Limits { chars_per_second: 4.0, line_length: 16, lines: 2 });
+
+        // Limits of `[translate.languages]` win over them
+        let mut config = config;
+        let overrides = LanguageOverrides { max_chars_per_second: Some(6.0), ..Default::default() };
+        config.apply_overrides(&[("JA".to_string(), overrides)].into());
+        assert_eq!(config.limits("ja"), Limits { chars_per_second: 6.0, line_length: 16, lines: 2 });
+    }
+}
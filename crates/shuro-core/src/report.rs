@@ -0,0 +1,455 @@
+// Run reports
+//
+// After `process` or `batch`, the command line prints one line per video with the seconds spent
+// extracting audio, transcribing and translating (timed together, as they overlap) and writing
+// the outputs, which of the audio and transcription came from the cache, and per language the
+// share of segments translated from the cache or the translation memory, the translation attempts
+// retried, and the segments flagged by the checks of comparison.rs. Segments whose translation
+// is still their source text, as when every attempt at translating them failed, are listed with
+// their times so they can be fixed by hand; so are the videos that failed. `--report` writes the
+// same report as JSON or HTML, chosen by the file's extension.
+//
+// Each finished video sends its summary as a `FileSummarized` progress event before
+// `FileCompleted`, and `RunReport::collect` gathers them with the `FileFailed` events.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::comparison::{LanguageQuality, SegmentFlag};
+use crate::cost::Usage;
+use crate::error::{Result, ShuroError};
+use crate::manifest::TimingRecord;
+use crate::progress::{ProgressEvent, ProgressSender};
+use crate::quality::Transcription;
+
+/// File format of a written report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Html,
+}
+
+impl ReportFormat {
+    /// Format of a report written to `path`, by its extension
+    pub fn for_path(path: &Path) -> Result<Self> {
+        let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("json") => Ok(Self::Json),
+            Some("html" | "htm") => Ok(Self::Html),
+            _ => Err(ShuroError::Config(format!(
+                "Report {} must end in .json or .html", path.display()
+            ))),
+        }
+    }
+}
+
+/// Segment whose translation is its source text
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KeptSegment {
+    pub index: usize,
+    /// Seconds
+    pub start: f64,
+    pub text: String,
+}
+
+/// Translation of one video into one language
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LanguageSummary {
+    pub language: String,
+    pub segments: usize,
+    /// Segments translated from the cache or the translation memory
+    pub cache_hits: usize,
+    /// Translation attempts rejected and retried
+    pub retries: usize,
+    /// Flagged segments per problem
+    pub flags: BTreeMap<String, usize>,
+    /// Segments left in the source language
+    pub kept_original: Vec<KeptSegment>,
+}
+
+impl LanguageSummary {
+    /// Summary of the translation checked as `quality`, of the segments of `source`
+    pub fn new(quality: &LanguageQuality, source: &Transcription, cache_hits: usize) -> Self {
+        let mut flags = BTreeMap::new();
+        for flag in quality.flagged.values() {
+            *flags.entry(flag.label().to_string()).or_insert(0) += 1;
+        }
+        let kept_original = quality.flagged
+            .iter()
+            .filter(|(_, flag)| **flag == SegmentFlag::Untranslated)
+            .filter_map(|(&index, _)| source.segments.get(index).map(|segment| KeptSegment {
+                index,
+                start: segment.start,
+                text: segment.text.trim().to_string(),
+            }))
+            .collect();
+        Self {
+            language: quality.language.clone(),
+            segments: quality.segments,
+            cache_hits,
+            retries: quality.retries,
+            flags,
+            kept_original,
+        }
+    }
+
+    /// Segments flagged for any problem
+    pub fn flagged(&self) -> usize {
+        self.flags.values().sum()
+    }
+}
+
+/// How one video was processed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileSummary {
+    pub file: PathBuf,
+    pub timings: TimingRecord,
+    pub audio_cached: bool,
+    pub transcription_cached: bool,
+    /// Segments of the transcription
+    pub segments: usize,
+    /// Transcribed segments flagged as hallucinations
+    pub hallucinations: usize,
+    /// Tokens and estimated cost of the video's model requests
+    pub usage: Usage,
+    /// Language of the video, given or detected by whisper
+    pub source_language: String,
+    /// Target languages not translated, the video being in them already
+    pub skipped: Vec<String>,
+    /// Target languages kept from an earlier run, their outputs being newer than the video
+    pub up_to_date: Vec<String>,
+    pub languages: Vec<LanguageSummary>,
+}
+
+impl FileSummary {
+    /// Share of the translated segments taken from the cache, over every language
+    pub fn cache_hit_rate(&self) -> f64 {
+        let segments: usize = self.languages.iter().map(|l| l.segments).sum();
+        let hits: usize = self.languages.iter().map(|l| l.cache_hits).sum();
+        hits as f64 / segments.max(1) as f64
+    }
+}
+
+/// Video that failed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FailedFile {
+    pub file: PathBuf,
+    pub error: String,
+}
+
+/// Report of a `process` or `batch` run
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RunReport {
+    pub files: Vec<FileSummary>,
+    pub failed: Vec<FailedFile>,
+}
+
+impl RunReport {
+    /// Collect the report from the progress events sent to the returned sender; the task ends
+    /// with it once every sender is dropped
+    pub fn collect() -> (ProgressSender, JoinHandle<RunReport>) {
+        let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(async move {
+            let mut report = RunReport::default();
+            while let Some(event) = events_rx.recv().await {
+                report.record(event);
+            }
+            report
+        });
+        (events_tx, task)
+    }
+
+    pub fn record(&mut self, event: ProgressEvent) {
+        match event {
+            ProgressEvent::FileSummarized { summary } => self.files.push(summary),
+            ProgressEvent::FileFailed { file, error } => self.failed.push(FailedFile { file, error }),
+            _ => {}
+        }
+    }
+
+    /// Usage of the model requests of every video
+    pub fn usage(&self) -> Usage {
+        let mut usage = Usage::default();
+        for file in &self.files {
+            usage.add(file.usage);
+        }
+        usage
+    }
+
+    /// Table of the videos, then the segments kept in the source language and the failures
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        let width = self.files.iter().map(|f| file_name(&f.file).chars().count()).max().unwrap_or(0).max(4);
+        text.push_str(&format!(
+            "{:<width$}  {:<6}  {:>8}  {:>10}  {:>8}  {:>8}  {:<18}  {:>8}  {:>6}  {:>7}  {:>7}  {:>4}  {:>9}  {:>8}\n",
+            "File", "Source", "Audio", "Transcribe", "Write", "Total", "Cached", "Segments", "Hits", "Retries", "Flagged", "Kept", "Tokens", "Cost"
+        ));
+        for file in &self.files {
+            let cached = match (file.audio_cached, file.transcription_cached) {
+                (true, true) => "audio, transcript",
+                (true, false) => "audio",
+                (false, true) => "transcript",
+                (false, false) => "-",
+            };
+            text.push_str(&format!(
+                "{:<width$}  {:<6}  {:>8}  {:>10}  {:>8}  {:>8}  {:<18}  {:>8}  {:>5.0}%  {:>7}  {:>7}  {:>4}  {:>9}  {:>8.2}\n",
+                file_name(&file.file),
+                file.source_language,
+                format_seconds(file.timings.audio_extraction),
+                format_seconds(file.timings.transcription_and_translation),
+                format_seconds(file.timings.output),
+                format_seconds(file.timings.total),
+                cached,
+                file.segments,
+                file.cache_hit_rate() * 100.0,
+                file.languages.iter().map(|l| l.retries).sum::<usize>(),
+                file.languages.iter().map(LanguageSummary::flagged).sum::<usize>(),
+                file.languages.iter().map(|l| l.kept_original.len()).sum::<usize>(),
+                file.usage.tokens(),
+                file.usage.cost,
+            ));
+        }
+
+        let mut flags: BTreeMap<&str, usize> = BTreeMap::new();
+        for language in self.files.iter().flat_map(|f| &f.languages) {
+            for (flag, count) in &language.flags {
+                *flags.entry(flag).or_insert(0) += count;
+            }
+        }
+        if !flags.is_empty() {
+            let counts: Vec<String> = flags.iter().map(|(flag, count)| format!("{} {}", flag, count)).collect();
+            text.push_str(&format!("Flagged segments: {}\n", counts.join(", ")));
+        }
+
+        for file in self.files.iter().filter(|f| !f.skipped.is_empty()) {
+            text.push_str(&format!("Not translated, already in the source language ({}): {}\n", file_name(&file.file), file.skipped.join(", ")));
+        }
+        for file in self.files.iter().filter(|f| !f.up_to_date.is_empty()) {
+            text.push_str(&format!("Kept, outputs newer than the video ({}): {}\n", file_name(&file.file), file.up_to_date.join(", ")));
+        }
+        for file in &self.files {
+            for language in file.languages.iter().filter(|l| !l.kept_original.is_empty()) {
+                text.push_str(&format!("Kept in the source language ({}, {}):\n", file_name(&file.file), language.language));
+                for segment in &language.kept_original {
+                    text.push_str(&format!("  #{} {}  {}\n", segment.index + 1, format_timestamp(segment.start), segment.text));
+                }
+            }
+        }
+        for failed in &self.failed {
+            text.push_str(&format!("FAILED {}: {}\n", failed.file.display(), failed.error));
+        }
+        let total: f64 = self.files.iter().map(|f| f.timings.total).sum();
+        let usage = self.usage();
+        text.push_str(&format!(
+            "{} videos processed in {}, {} failed; {} prompt and {} completion tokens, estimated cost {:.2}\n",
+            self.files.len(), format_seconds(total), self.failed.len(), usage.prompt_tokens, usage.completion_tokens, usage.cost
+        ));
+        text
+    }
+
+    /// Self-contained HTML page of the report
+    pub fn to_html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>shuro run report</title>\n\
+             <style>body{font-family:sans-serif}table{border-collapse:collapse}\
+             td,th{border:1px solid #ccc;padding:2px 8px;text-align:right}td:first-child{text-align:left}</style>\n\
+             </head>\n<body>\n<h1>shuro run report</h1>\n",
+        );
+        html.push_str(
+            "<table>\n<tr><th>File</th><th>Source</th><th>Language</th><th>Audio</th><th>Transcribe</th><th>Write</th><th>Total</th>\
+             <th>Cached</th><th>Segments</th><th>Cache hits</th><th>Retries</th><th>Flagged</th><th>Kept</th>\
+             <th>Tokens</th><th>Cost</th></tr>\n",
+        );
+        for file in &self.files {
+            let cached: Vec<&str> = [(file.audio_cached, "audio"), (file.transcription_cached, "transcript")]
+                .into_iter()
+                .filter_map(|(cached, name)| cached.then_some(name))
+                .collect();
+            // The usage is the video's, over every language; a video without translated languages
+            // (all skipped or kept) still gets a row
+            let rows = file.languages.len().max(1);
+            let usage = format!(
+                "<td rowspan=\"{}\">{}</td><td rowspan=\"{}\">{:.2}</td>",
+                rows, file.usage.tokens(), rows, file.usage.cost
+            );
+            let languages: Vec<Option<&LanguageSummary>> = match file.languages.is_empty() {
+                true => vec![None],
+                false => file.languages.iter().map(Some).collect(),
+            };
+            for (i, language) in languages.into_iter().enumerate() {
+                let (name, counts) = match language {
+                    Some(language) => (
+                        language.language.as_str(),
+                        format!(
+                            "<td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>",
+                            language.segments, language.cache_hits, language.retries, language.flagged(), language.kept_original.len()
+                        ),
+                    ),
+                    None => ("", "<td></td>".repeat(5)),
+                };
+                html.push_str(&format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>{}{}</tr>\n",
+                    escape_html(&file.file.display().to_string()),
+                    escape_html(&file.source_language),
+                    escape_html(name),
+                    format_seconds(file.timings.audio_extraction),
+                    format_seconds(file.timings.transcription_and_translation),
+                    format_seconds(file.timings.output),
+                    format_seconds(file.timings.total),
+                    cached.join(", "),
+                    counts,
+                    if i == 0 { usage.as_str() } else { "" },
+                ));
+            }
+        }
+        html.push_str("</table>\n");
+        let usage = self.usage();
+        html.push_str(&format!(
+            "<p>{} prompt and {} completion tokens, estimated cost {:.2}</p>\n",
+            usage.prompt_tokens, usage.completion_tokens, usage.cost
+        ));
+
+        for file in self.files.iter().filter(|f| !f.skipped.is_empty()) {
+            html.push_str(&format!(
+                "<p>Not translated, already in the source language ({}): {}</p>\n",
+                escape_html(&file.file.display().to_string()), escape_html(&file.skipped.join(", "))
+            ));
+        }
+        for file in self.files.iter().filter(|f| !f.up_to_date.is_empty()) {
+            html.push_str(&format!(
+                "<p>Kept, outputs newer than the video ({}): {}</p>\n",
+                escape_html(&file.file.display().to_string()), escape_html(&file.up_to_date.join(", "))
+            ));
+        }
+        for file in &self.files {
+            for language in file.languages.iter().filter(|l| !l.kept_original.is_empty()) {
+                html.push_str(&format!(
+                    "<h2>Kept in the source language: {} ({})</h2>\n<ul>\n",
+                    escape_html(&file.file.display().to_string()), escape_html(&language.language)
+                ));
+                for segment in &language.kept_original {
+                    html.push_str(&format!(
+                        "<li>#{} {} {}</li>\n", segment.index + 1, format_timestamp(segment.start), escape_html(&segment.text)
+                    ));
+                }
+                html.push_str("</ul>\n");
+            }
+        }
+        if !self.failed.is_empty() {
+            html.push_str("<h2>Failed</h2>\n<ul>\n");
+            for failed in &self.failed {
+                html.push_str(&format!(
+                    "<li>{}: {}</li>\n", escape_html(&failed.file.display().to_string()), escape_html(&failed.error)
+                ));
+            }
+            html.push_str("</ul>\n");
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// Write the report to `path`, as JSON or HTML by its extension
+    pub async fn write(&self, path: &Path) -> Result<()> {
+        let content = match ReportFormat::for_path(path)? {
+            ReportFormat::Json => serde_json::to_string_pretty(self)?,
+            ReportFormat::Html => self.to_html(),
+        };
+        tokio::fs::write(path, content).await?;
+        Ok(())
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned()
+}
+
+fn format_seconds(seconds: f64) -> String {
+    if seconds < 60.0 {
+        return format!("{:.1}s", seconds);
+    }
+    let seconds = seconds.round() as u64;
+    match seconds / 3600 {
+        0 => format!("{}m {:02}s", seconds / 60, seconds % 60),
+        hours => format!("{}h {:02}m", hours, seconds % 3600 / 60),
+    }
+}
+
+/// `h:mm:ss` of `seconds` into the video
+fn format_timestamp(seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u64;
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds % 3600 / 60, seconds % 60)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_report() {
+        let summary = FileSummary {
+            file: "/videos/e01.mkv".into(),
+            timings: TimingRecord { audio_extraction: 4.2, transcription_and_translation: 605.0, output: 12.0, total: 621.2 },
+            audio_cached: true,
+            transcription_cached: false,
+            segments: 300,
+            hallucinations: 2,
+            usage: Usage { prompt_tokens: 90_000, completion_tokens: 12_000, cost: 0.0207 },
+            source_language: "en".to_string(),
+            skipped: vec!["en".to_string()],
+            up_to_date: vec!["de".to_string()],
+            languages: vec![LanguageSummary {
+                language: "ja".to_string(),
+                segments: 300,
+                cache_hits: 150,
+                retries: 7,
+                flags: BTreeMap::from([("Untranslated".to_string(), 1), ("Too long".to_string(), 2)]),
+                kept_original: vec![KeptSegment { index: 41, start: 754.3, text: "See you <tomorrow>".to_string() }],
+            }],
+        };
+        let mut report = RunReport::default();
+        report.record(ProgressEvent::FileSummarized { summary });
+        report.record(ProgressEvent::FileCompleted { file: "/videos/e01.mkv".into() });
+        report.record(ProgressEvent::FileFailed { file: "/videos/e02.mkv".into(), error: "no audio stream".into() });
+
+        let text = report.render();
+        assert!(text.contains("e01.mkv  en          4.2s     10m 05s     12.0s   10m 21s  audio                    300     50%        7        3     1     102000      0.02\n"));
+        assert!(text.contains("Flagged segments: Too long 2, Untranslated 1\n"));
+        assert!(text.contains("Not translated, already in the source language (e01.mkv): en\n"));
+        assert!(text.contains("Kept, outputs newer than the video (e01.mkv): de\n"));
+        assert!(text.contains("Kept in the source language (e01.mkv, ja):\n  #42 0:12:34  See you <tomorrow>\n"));
+        assert!(text.contains("FAILED /videos/e02.mkv: no audio stream\n"));
+        assert!(text.ends_with("1 videos processed in 10m 21s, 1 failed; 90000 prompt and 12000 completion tokens, estimated cost 0.02\n"));
+
+        assert!(report.to_html().contains("<li>#42 0:12:34 See you &lt;tomorrow&gt;</li>"));
+
+        // A video whose languages were all skipped still gets a row
+        let mut report = RunReport::default();
+        report.record(ProgressEvent::FileSummarized { summary: FileSummary {
+            file: "e03.mkv".into(),
+            timings: TimingRecord::default(),
+            audio_cached: false,
+            transcription_cached: true,
+            segments: 280,
+            hallucinations: 0,
+            usage: Usage::default(),
+            source_language: "ja".to_string(),
+            skipped: vec!["ja".to_string()],
+            up_to_date: vec!["de".to_string()],
+            languages: Vec::new(),
+        } });
+        let html = report.to_html();
+        assert!(html.contains("<td>e03.mkv</td><td>ja</td><td></td>"));
+        assert!(html.contains("<td></td><td></td><td></td><td></td><td></td><td rowspan=\"1\">0</td>"));
+        assert!(!html.contains("rowspan=\"0\""));
+        assert!(report.render().contains("e03.mkv  ja    "));
+        assert_eq!(ReportFormat::for_path(Path::new("run.HTML")).unwrap(), ReportFormat::Html);
+        assert!(ReportFormat::for_path(Path::new("run.txt")).is_err());
+    }
+}
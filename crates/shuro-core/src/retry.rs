@@ -0,0 +1,148 @@
+// Retries of external services
+//
+// Ollama, OpenAI-compatible APIs and whisper-server are reached over the network, where a refused
+// connection, a timeout or a 429 or 5xx answer is often gone a moment later. A segment whose
+// translation request hit one of those used to keep its source text. Requests to these services
+// now go through one `RetryPolicy` from `[retry]`: a transient failure (see
+// `ShuroError::is_transient`) is retried up to `max_attempts` attempts in all, waiting
+// `initial_backoff_ms` before the first retry and doubling the wait up to `max_backoff_ms`, each
+// wait shortened at random by up to `jitter` of it so that parallel requests do not retry in
+// step. Other failures (a missing model, a bad API key, an unparsable answer) fail at once.
+//
+// The workflow shares one policy between the translation backend, whose requests carry
+// translations, quality evaluations, notes and title lookups, and the whisper-server transcriber.
+// All retries for one video draw on a budget of `file_budget`, renewed when the next video
+// starts, so a service that is down fails the video after a bounded delay instead of every one of
+// its segments waiting out its own retries. The OpenAI backend additionally honors `Retry-After`
+// with its own `openai.max_retries` (see translate/openai.rs).
+
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::config::RetryConfig;
+use crate::error::Result;
+
+/// Retry policy shared by the requests of a workflow
+#[derive(Debug, Default)]
+pub struct RetryPolicy {
+    config: RetryConfig,
+    /// Retries taken for the current video
+    spent: AtomicU32,
+}
+
+impl RetryPolicy {
+    pub fn new(config: &RetryConfig) -> Self {
+        Self { config: config.clone(), spent: AtomicU32::new(0) }
+    }
+
+    /// Renew the retry budget for the next video
+    pub fn start_file(&self) {
+        self.spent.store(0, Ordering::Relaxed);
+    }
+
+    /// Run `attempt` until it succeeds, fails with an error that is not transient, or attempts or
+    /// the video's budget run out; `service` names what is retried in the log
+    pub async fn run<T, F, Fut>(&self, service: &str, mut attempt: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let attempts = self.config.max_attempts.max(1);
+        let mut tried = 0;
+        loop {
+            tried += 1;
+            let error = match attempt().await {
+                Ok(value) => return Ok(value),
+                Err(e) if !e.is_transient() || tried >= attempts => return Err(e),
+                Err(e) => e,
+            };
+            if !self.take_retry() {
+                warn!("{} request failed ({}); the retry budget of this video is used up", service, error);
+                return Err(error);
+            }
+            let delay = self.delay(tried);
+            warn!("{} request failed ({}), retrying in {:.1}s ({}/{})", service, error, delay.as_secs_f64(), tried, attempts - 1);
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Take one retry from the budget of the current video
+    fn take_retry(&self) -> bool {
+        let budget = self.config.file_budget;
+        self.spent
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |spent| (budget == 0 || spent < budget).then_some(spent + 1))
+            .is_ok()
+    }
+
+    /// Wait before retry `retry` (from 1)
+    fn delay(&self, retry: u32) -> Duration {
+        let jitter = self.config.jitter.clamp(0.0, 1.0) * random_fraction();
+        backoff(retry, &self.config).mul_f64(1.0 - jitter)
+    }
+}
+
+/// Wait before retry `retry` (from 1) without jitter: the initial backoff doubled per retry, never
+/// more than the maximum
+fn backoff(retry: u32, config: &RetryConfig) -> Duration {
+    Duration::from_millis(config.initial_backoff_ms)
+        .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+        .min(Duration::from_millis(config.max_backoff_ms))
+}
+
+/// Random number in [0, 1)
+fn random_fraction() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ShuroError;
+
+    #[tokio::test]
+    async fn test_retries() {
+        let config = RetryConfig { initial_backoff_ms: 1, max_backoff_ms: 4, file_budget: 3, ..RetryConfig::default() };
+        assert_eq!(backoff(1, &config), Duration::from_millis(1));
+        assert_eq!(backoff(3, &config), Duration::from_millis(4));
+        assert_eq!(backoff(40, &config), Duration::from_millis(4));
+
+        let policy = RetryPolicy::new(&config);
+        let calls = AtomicU32::new(0);
+        let flaky = || async {
+            match calls.fetch_add(1, Ordering::SeqCst) {
+                0 | 1 => Err(ShuroError::Unavailable("connection refused".to_string())),
+                _ => Ok("translated"),
+            }
+        };
+        assert_eq!(policy.run("Ollama", flaky).await.unwrap(), "translated");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // Errors that are not transient fail at once
+        calls.store(0, Ordering::SeqCst);
+        let broken = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(ShuroError::Translation("model not found".to_string()))
+        };
+        assert!(policy.run("Ollama", broken).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // One retry is left of the video's budget of 3
+        calls.store(0, Ordering::SeqCst);
+        let down = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(ShuroError::Unavailable("503 Service Unavailable".to_string()))
+        };
+        assert!(policy.run("Ollama", down).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        policy.start_file();
+        calls.store(0, Ordering::SeqCst);
+        assert!(policy.run("Ollama", down).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+}
@@ -0,0 +1,263 @@
+// Segment normalization
+//
+// Whisper cuts its output where its decoding windows end, not where subtitles should: a lone "Yes."
+// flashes by in a third of a second, and a monologue runs on for fifteen seconds in one segment.
+// With `segmentation.enabled`, transcribed segments are normalized before they are translated:
+//
+// - a segment shorter than `min_duration` is merged with the next one when they are at most
+//   `merge_gap` seconds apart, the same speaker says both, and the merged segment stays within
+//   `max_duration` and `max_chars`
+// - a segment longer than `max_duration` or `max_chars` is split into segments of about equal
+//   text, cut after a sentence end when one is near; the time is shared in proportion to the
+//   text, and word timestamps go with the piece they fall in
+// - a segment ending less than `min_gap` seconds before the next one starts ends earlier
+//
+// `SegmentNormalizer` works on segments as whisper streams them, holding back only the last one,
+// so translation still starts while whisper runs; the final transcription is normalized the same
+// way to the same cues. Streamed segments carry no word timestamps while the final transcription
+// may, so words never move a cut: the translations of the streamed cues must fit the final ones. Embedded and imported subtitles are cues already and are left alone.
+// `shuro subtitle normalize` applies the same pass to a subtitle file.
+
+use tokio::sync::mpsc;
+
+use crate::config::SegmentationConfig;
+use crate::metadata::{CONFIDENCE, HALLUCINATION, SOURCE_LANGUAGE, SPEAKER};
+use crate::quality::{Transcription, TranscriptionSegment};
+use crate::readability::{characters, divide, unspaced};
+
+/// Merges and splits segments as they arrive, in order
+pub struct SegmentNormalizer {
+    config: SegmentationConfig,
+    /// Segment that may still be merged with the next one
+    pending: Option<TranscriptionSegment>,
+    /// Finished segment whose end waits for the start of the next one
+    held: Option<TranscriptionSegment>,
+    next_id: i32,
+}
+
+impl SegmentNormalizer {
+    pub fn new(config: SegmentationConfig) -> Self {
+        Self { config, pending: None, held: None, next_id: 0 }
+    }
+
+    /// Take the next transcribed segment, returning the segments it finishes
+    pub fn push(&mut self, segment: TranscriptionSegment) -> Vec<TranscriptionSegment> {
+        let mut finished = Vec::new();
+        for piece in self.split(segment) {
+            match self.pending.take() {
+                Some(pending) if self.can_merge(&pending, &piece) => self.pending = Some(merge(pending, piece)),
+                Some(pending) => {
+                    self.finish_segment(pending, &mut finished);
+                    self.pending = Some(piece);
+                }
+                None => self.pending = Some(piece),
+            }
+        }
+        finished
+    }
+
+    /// The segments held back after the last one was pushed
+    pub fn finish(mut self) -> Vec<TranscriptionSegment> {
+        let mut finished = Vec::new();
+        if let Some(pending) = self.pending.take() {
+            self.finish_segment(pending, &mut finished);
+        }
+        if let Some(mut held) = self.held.take() {
+            held.id = self.next_id;
+            finished.push(held);
+        }
+        finished
+    }
+
+    /// Hold `segment` back until the next one starts; the segment held before it is finished
+    fn finish_segment(&mut self, segment: TranscriptionSegment, finished: &mut Vec<TranscriptionSegment>) {
+        if let Some(mut held) = self.held.take() {
+            if segment.start - held.end < self.config.min_gap {
+                held.end = (segment.start - self.config.min_gap).max(held.start);
+            }
+            held.id = self.next_id;
+            self.next_id += 1;
+            finished.push(held);
+        }
+        self.held = Some(segment);
+    }
+
+    fn can_merge(&self, first: &TranscriptionSegment, second: &TranscriptionSegment) -> bool {
+        let short = |s: &TranscriptionSegment| s.end - s.start < self.config.min_duration;
+        (short(first) || short(second))
+            && second.start - first.end <= self.config.merge_gap
+            && second.end - first.start <= self.config.max_duration
+            && characters(&join_texts(&first.text, &second.text)) <= self.config.max_chars
+            && first.metadata.get(&SPEAKER) == second.metadata.get(&SPEAKER)
+            && first.metadata.get(&SOURCE_LANGUAGE) == second.metadata.get(&SOURCE_LANGUAGE)
+    }
+
+    /// `segment` as segments within `max_duration` and `max_chars`, none shorter than `min_duration`
+    fn split(&self, segment: TranscriptionSegment) -> Vec<TranscriptionSegment> {
+        let duration = segment.end - segment.start;
+        let by_duration = (duration / self.config.max_duration.max(0.1)).ceil() as usize;
+        let by_length = characters(&segment.text).div_ceil(self.config.max_chars.max(1));
+        let most = ((duration / self.config.min_duration.max(0.1)).floor() as usize).max(1);
+        let parts = by_duration.max(by_length).min(most);
+        if parts <= 1 {
+            return vec![segment];
+        }
+
+        let texts = divide(&segment.text, parts);
+        let total = texts.iter().map(|t| characters(t)).sum::<usize>().max(1) as f64;
+        let mut shown = 0;
+        let mut start = segment.start;
+        texts.iter()
+            .enumerate()
+            .map(|(index, text)| {
+                shown += characters(text);
+                let end = if index + 1 == texts.len() {
+                    segment.end
+                } else {
+                    segment.start + duration * shown as f64 / total
+                };
+                let piece = TranscriptionSegment {
+                    start,
+                    end,
+                    text: text.clone(),
+                    tokens: Vec::new(),
+                    words: segment.words.iter()
+                        .filter(|w| (start..end).contains(&((w.start + w.end) / 2.0)))
+                        .cloned()
+                        .collect(),
+                    ..segment.clone()
+                };
+                start = end;
+                piece
+            })
+            .collect()
+    }
+}
+
+/// Texts of two consecutive segments as one
+fn join_texts(first: &str, second: &str) -> String {
+    let (first, second) = (first.trim(), second.trim());
+    if unspaced(first) && unspaced(second) {
+        format!("{}{}", first, second)
+    } else {
+        format!("{} {}", first, second)
+    }
+}
+
+/// Two consecutive segments as one
+fn merge(first: TranscriptionSegment, second: TranscriptionSegment) -> TranscriptionSegment {
+    let mut metadata = first.metadata.clone();
+    let confidence = [&first, &second].iter().filter_map(|s| s.metadata.get(&CONFIDENCE)).reduce(f64::min);
+    if let Some(confidence) = confidence {
+        metadata.insert(&CONFIDENCE, confidence);
+    }
+    let hallucination = [&first, &second].iter().filter_map(|s| s.metadata.get(&HALLUCINATION)).reduce(f64::max);
+    if let Some(hallucination) = hallucination {
+        metadata.insert(&HALLUCINATION, hallucination);
+    }
+    TranscriptionSegment {
+        id: first.id,
+        start: first.start,
+        end: second.end,
+        text: join_texts(&first.text, &second.text),
+        tokens: [first.tokens, second.tokens].concat(),
+        temperature: first.temperature.max(second.temperature),
+        avg_logprob: first.avg_logprob.min(second.avg_logprob),
+        compression_ratio: first.compression_ratio.max(second.compression_ratio),
+        no_speech_prob: first.no_speech_prob.min(second.no_speech_prob),
+        words: [first.words, second.words].concat(),
+        metadata,
+    }
+}
+
+/// Segments of `segments` merged and split by `config` as they arrive
+pub fn normalize_stream(
+    mut segments: mpsc::UnboundedReceiver<TranscriptionSegment>,
+    config: SegmentationConfig,
+) -> mpsc::UnboundedReceiver<TranscriptionSegment> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut normalizer = SegmentNormalizer::new(config);
+        while let Some(segment) = segments.recv().await {
+            for segment in normalizer.push(segment) {
+                // The receiver may have stopped early, like the transcriber's
+                let _ = sender.send(segment);
+            }
+        }
+        for segment in normalizer.finish() {
+            let _ = sender.send(segment);
+        }
+    });
+    receiver
+}
+
+/// `transcription` with its segments merged and split by `config`
+pub fn normalize_segments(transcription: &Transcription, config: &SegmentationConfig) -> Transcription {
+    let mut normalizer = SegmentNormalizer::new(config.clone());
+    let mut segments: Vec<TranscriptionSegment> = transcription.segments.iter()
+        .flat_map(|segment| normalizer.push(segment.clone()))
+        .collect();
+    segments.extend(normalizer.finish());
+    Transcription {
+        text: segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" "),
+        segments,
+        language: transcription.language.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quality::WordTiming;
+    use crate::subtitle::parse_srt;
+
+    #[test]
+    fn test_normalize_segments() {
+        let mut transcription = parse_srt(
+            "1\n00:00:01,000 --> 00:00:01,400\nYes.\n\n\
+             2\n00:00:01,500 --> 00:00:03,000\nI'll be there.\n\n\
+             3\n00:00:03,020 --> 00:00:18,000\nThe ferry to the mainland leaves at nine tonight. If we miss it, there \
+             is no other way off the island until Monday morning, and nobody wants that.\n\n\
+             4\n00:00:20,000 --> 00:00:20,500\nRight.\n\n\
+             5\n00:00:25,000 --> 00:00:25,300\nGo!\n",
+            "en",
+        ).unwrap();
+        let config = SegmentationConfig { enabled: true, ..Default::default() };
+
+        let normalized = normalize_segments(&transcription, &config);
+        let cues: Vec<(f64, f64, &str)> = normalized.segments.iter().map(|s| (s.start, s.end, s.text.as_str())).collect();
+        // Merged, and ended `min_gap` before the next cue
+        assert_eq!(cues[0], (1.0, 3.02 - 0.08, "Yes. I'll be there."));
+        // 15 seconds of text in three parts, the first cut after the sentence
+        assert_eq!(cues[1].2, "The ferry to the mainland leaves at nine tonight.");
+        assert_eq!(cues.len(), 6);
+        assert_eq!((cues[1].0, cues[3].1), (3.02, 18.0));
+        // Too far apart to merge
+        assert_eq!(&cues[4..], &[(20.0, 20.5, "Right."), (25.0, 25.3, "Go!")]);
+        assert!(normalized.segments.iter().enumerate().all(|(i, s)| s.id == i as i32));
+
+        // Word timestamps go with their pieces but leave the cues as they are without them, as
+        // streamed segments have none
+        let word = |text: &str, start: f64, end: f64| WordTiming { text: text.to_string(), start, end };
+        transcription.segments[2].words = vec![
+            word("The ferry to the mainland leaves at nine tonight.", 3.1, 8.3),
+            word("If we miss it, there is no other way off the", 8.6, 12.4),
+            word("island until Monday morning, and nobody wants that.", 12.6, 17.8),
+        ];
+        let with_words = normalize_segments(&transcription, &config);
+        let cue = |s: &TranscriptionSegment| (s.start, s.end, s.text.clone());
+        assert_eq!(with_words.segments.iter().map(cue).collect::<Vec<_>>(), normalized.segments.iter().map(cue).collect::<Vec<_>>());
+        assert!(with_words.segments[1..4].iter().all(|s| s.words.len() == 1));
+        let normalized = with_words;
+
+        // Streaming gives the same cues
+        let mut normalizer = SegmentNormalizer::new(config);
+        let mut streamed = Vec::new();
+        for segment in transcription.segments.clone() {
+            streamed.extend(normalizer.push(segment));
+        }
+        streamed.extend(normalizer.finish());
+        assert_eq!(streamed.len(), normalized.segments.len());
+        assert!(streamed.iter().zip(&normalized.segments).all(|(a, b)| (a.start, a.end, &a.text) == (b.start, b.end, &b.text)));
+    }
+}
@@ -0,0 +1,374 @@
+// Installation self-test
+//
+// `shuro selftest` verifies that an installation can run the whole video pipeline without
+// needing a video, an Ollama server or whisper models. ffmpeg generates a short synthetic video
+// (a test pattern with a sine tone) in a work directory, and the configured workflow processes it
+// with two stand-ins registered in place of the real services:
+//
+// - `SelftestTranscriber` extracts the audio with ffmpeg like every transcriber, then returns a
+//   fixed English script instead of running whisper
+// - `SelftestBackend` answers translation requests with the quoted source text prefixed by the
+//   target language code (`[ja] Good morning.`), evaluations with GOOD and title lookups with
+//   an unknown work
+//
+// Everything else (audio extraction, subtitle generation, styling, embedding, manifests) is the
+// real code with the user's configuration, except that extras depending on a real model
+// (semantic cache, translation memory, translator's notes, title lookup) are switched off and
+// translation uses the Simple mode. The outputs are then validated: every subtitle cue carries its
+// language marker and every output video has an embedded subtitle track. Both stand-ins are
+// public so embedding applications and CI can run the pipeline without external services too.
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::info;
+
+use crate::cancel::Cancellation;
+use crate::config::{Config, TranscriberConfig, TranslationMode};
+use crate::error::{Result, ShuroError};
+use crate::manifest::ProcessingManifest;
+use crate::media::commands::MediaCommandBuilder;
+use crate::preflight::PreflightReport;
+use crate::quality::{Transcription, TranscriptionSegment};
+use crate::subtitle::parse_srt;
+use crate::transcribe::{AudioCache, CacheInfo, TranscriberRegistry, TranscriberTrait, TranscriptionCache, TuneResult};
+use crate::transcribe::common::{extract_audio, WhisperUtils};
+use crate::translate::backend::{BackendRegistry, ModelBackend};
+use crate::translate::common::{TranslationRequest, TranslationResponse};
+use crate::translate::guard::{CLOSE_TAG, OPEN_TAG};
+use crate::translate::registry::TranslatorRegistry;
+use crate::workflow::Workflow;
+
+/// Name the stand-ins are registered under, as transcriber implementation and translation provider
+pub const SELFTEST: &str = "selftest";
+
+/// Seconds of the synthetic video
+const VIDEO_SECONDS: u32 = 4;
+
+/// Start, end and text of the cues the stand-in transcriber returns
+pub const SCRIPT: &[(f64, f64, &str)] = &[
+    (0.2, 1.2, "Good morning."),
+    (1.4, 2.6, "The train leaves at seven."),
+    (2.8, 3.8, "Please don't be late."),
+];
+
+/// Transcriber returning `SCRIPT` for any audio
+pub struct SelftestTranscriber {
+    config: TranscriberConfig,
+    audio_cache_dir: PathBuf,
+}
+
+impl SelftestTranscriber {
+    pub fn new(config: TranscriberConfig) -> Self {
+        let audio_cache_dir = Path::new(&config.cache_dir).join("audio");
+
+        Self { config, audio_cache_dir }
+    }
+
+    fn audio_cache_path(&self, video_path: &Path) -> Result<PathBuf> {
+        let cache_key = WhisperUtils::generate_file_hash(video_path, &["audio_extraction", SELFTEST])?;
+        Ok(self.audio_cache_dir.join(format!("{}.wav", cache_key)))
+    }
+}
+
+/// Transcription of `SCRIPT`
+pub fn script_transcription() -> Transcription {
+    let segments: Vec<TranscriptionSegment> = SCRIPT.iter()
+        .enumerate()
+        .map(|(id, &(start, end, text))| TranscriptionSegment {
+            id: id as i32,
+            start,
+            end,
+            text: text.to_string(),
+            tokens: Vec::new(),
+            temperature: 0.0,
+            avg_logprob: 0.0,
+            compression_ratio: 1.0,
+            no_speech_prob: 0.0,
+            words: Vec::new(),
+            metadata: Default::default(),
+        })
+        .collect();
+    Transcription {
+        text: SCRIPT.iter().map(|(_, _, text)| *text).collect::<Vec<_>>().join(" "),
+        segments,
+        language: "en".to_string(),
+    }
+}
+
+#[async_trait]
+impl TranscriberTrait for SelftestTranscriber {
+    async fn transcribe(&self, audio_path: &Path, _language: Option<&str>, cancel: &Cancellation) -> Result<Transcription> {
+        cancel.check()?;
+        if !audio_path.exists() {
+            return Err(ShuroError::FileNotFound(audio_path.display().to_string()));
+        }
+        Ok(script_transcription())
+    }
+
+    async fn tune_transcription(&self, audio_path: &Path, cancel: &Cancellation) -> Result<TuneResult> {
+        let transcription = self.transcribe(audio_path, None, cancel).await?;
+
+        Ok(TuneResult {
+            best_transcription: transcription,
+            best_tempo: 100,
+            best_temperature: self.config.temperature,
+            quality_score: 1.0,
+            all_attempts: vec![(100, 1.0)],
+            tested_parameters: vec![format!("{}-single-pass", SELFTEST)],
+        })
+    }
+
+    async fn extract_and_cache_audio(&self, video_path: &Path, cancel: &Cancellation) -> Result<PathBuf> {
+        cancel.run(async {
+            let audio_path = self.audio_cache_path(video_path)?;
+            WhisperUtils::ensure_directory(&self.audio_cache_dir).await?;
+
+            let original_name = video_path.file_name().and_then(|n| n.to_str());
+            extract_audio(video_path, audio_path.as_path(), &self.config.ffmpeg_path, self.config.audio_track, original_name).await?;
+
+            Ok(audio_path)
+        }).await
+    }
+
+    async fn get_cached_audio(&self, _video_path: &Path) -> Result<Option<PathBuf>> {
+        // Audio extraction is part of what the self-test checks
+        Ok(None)
+    }
+
+    async fn clear_cache(&self) -> Result<u64> {
+        Ok(0)
+    }
+
+    async fn list_cache(&self) -> Result<Vec<TranscriptionCache>> {
+        Ok(Vec::new())
+    }
+
+    async fn cache_info(&self) -> Result<CacheInfo> {
+        Ok(CacheInfo {
+            total_files: 0,
+            total_size: 0,
+            audio_files: 0,
+            audio_size: 0,
+            oldest_entry: None,
+            newest_entry: None,
+            models_used: vec![SELFTEST.to_string()],
+        })
+    }
+
+    async fn clear_audio_cache(&self) -> Result<u64> {
+        Ok(0)
+    }
+
+    async fn list_audio_cache(&self) -> Result<Vec<AudioCache>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Model backend answering every request without a model
+#[derive(Debug)]
+pub struct SelftestBackend;
+
+/// Marker the stand-in translation of a text into `language` starts with
+pub fn language_marker(language: &str) -> String {
+    format!("[{}]", language)
+}
+
+/// Stand-in translation of the text a translation prompt quotes
+fn translate_prompt(prompt: &str) -> String {
+    let language = prompt.split("(language code: ")
+        .nth(1)
+        .and_then(|rest| rest.split(')').next())
+        .unwrap_or("xx");
+    let quoted = prompt.find("Text to translate")
+        .map(|start| &prompt[start..])
+        .and_then(|rest| rest.split_once(OPEN_TAG))
+        .and_then(|(_, rest)| rest.split_once(CLOSE_TAG))
+        .map(|(text, _)| text.trim())
+        .unwrap_or_default();
+    format!("{} {}", language_marker(language), quoted)
+}
+
+#[async_trait]
+impl ModelBackend for SelftestBackend {
+    async fn generate(&self, _client: &Client, request: &TranslationRequest) -> Result<TranslationResponse> {
+        let properties = &request.format["properties"];
+        let answer = if properties.get("evaluation").is_some() {
+            json!({ "evaluation": "GOOD" })
+        } else if properties.get("sentences").is_some() {
+            json!({ "sentences": [] })
+        } else if properties.get("known").is_some() {
+            json!({ "known": false, "synopsis": "" })
+        } else {
+            json!({ "text": translate_prompt(&request.prompt) })
+        };
+        Ok(TranslationResponse {
+            response: answer.to_string(),
+            done: true,
+            prompt_eval_count: None,
+            eval_count: None,
+            eval_duration: None,
+        })
+    }
+
+    async fn check_models(&self, _models: &[&str]) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Configuration of a self-test run in `work_dir`, based on `config`
+fn selftest_config(mut config: Config, work_dir: &Path) -> Config {
+    config.use_work_dir(work_dir);
+    config.transcriber.implementation = SELFTEST.to_string();
+    config.transcriber.transcript = None;
+    config.transcriber.prefer_embedded_subs = false;
+    config.translate.provider = SELFTEST.to_string();
+    config.translate.implementation = None;
+    config.translate.mode = TranslationMode::Simple;
+    config.translate.source_language = "en".to_string();
+    // Response schemas tell the stand-in backend which answer a request expects
+    config.translate.structured_outputs = true;
+    config.translate.semantic_cache.enabled = false;
+    config.translate.memory.enabled = false;
+    config.translate.notes.enabled = false;
+    config.translate.work.llm_lookup = false;
+    config
+}
+
+/// Generate the synthetic test video at `path`
+async fn generate_video(ffmpeg_path: &str, path: &Path) -> Result<String> {
+    MediaCommandBuilder::new(ffmpeg_path)
+        .custom("Synthetic test video")
+        .arg("-f").arg("lavfi")
+        .arg("-i").arg(format!("testsrc=size=320x240:rate=25:duration={}", VIDEO_SECONDS))
+        .arg("-f").arg("lavfi")
+        .arg("-i").arg(format!("sine=frequency=440:duration={}", VIDEO_SECONDS))
+        .video_codec("mpeg4")
+        .audio_codec("aac")
+        .arg("-shortest")
+        .overwrite()
+        .output(path)
+        .execute()
+        .await?;
+    Ok(format!("{} s test pattern with a 440 Hz tone", VIDEO_SECONDS))
+}
+
+/// Outcome of checking the subtitles written into `language`
+fn check_subtitles(content: &str, language: &str) -> Result<String> {
+    let subtitles = parse_srt(content, language)?;
+    if subtitles.segments.is_empty() {
+        return Err(ShuroError::Translation("the subtitles have no cues".to_string()));
+    }
+    let marker = language_marker(language);
+    if let Some(cue) = subtitles.segments.iter().find(|s| !s.text.contains(&marker)) {
+        return Err(ShuroError::Translation(format!("cue \"{}\" is not translated", cue.text)));
+    }
+    Ok(format!("{} cues translated", subtitles.segments.len()))
+}
+
+/// Run the pipeline on a synthetic video in `work_dir` and check its outputs
+///
+/// Every step is recorded into the report; a failed step skips the steps depending on it.
+pub async fn run_selftest(config: Config, target_languages: &[String], work_dir: &Path, cancel: &Cancellation) -> Result<PreflightReport> {
+    let mut report = PreflightReport::default();
+    let config = selftest_config(config, work_dir);
+    let video_path = work_dir.join("selftest.mp4");
+    let output_dir = work_dir.join("output");
+    tokio::fs::create_dir_all(work_dir).await?;
+
+    let generated = generate_video(&config.media.binary_path, &video_path).await;
+    let failed = generated.is_err();
+    report.record("Synthetic video", generated);
+    if failed {
+        return Ok(report);
+    }
+
+    let transcribers = TranscriberRegistry::with_builtin()
+        .register(SELFTEST, |config, _validator, _retry| Box::new(SelftestTranscriber::new(config)) as Box<dyn TranscriberTrait>);
+    let backends = BackendRegistry::with_builtin()
+        .register(SELFTEST, |_config, _pool| Ok(Arc::new(SelftestBackend) as Arc<dyn ModelBackend>));
+    let workflow = match Workflow::with_registries(config, transcribers, TranslatorRegistry::with_builtin(), backends) {
+        Ok(workflow) => workflow,
+        Err(e) => {
+            report.record("Workflow", Err(e));
+            return Ok(report);
+        }
+    };
+
+    info!("🧪 Running the pipeline on {}", video_path.display());
+    let processed = workflow.process_single_file(&video_path, target_languages, Some(&output_dir), cancel).await
+        .map(|_| format!("{} languages processed", target_languages.len()));
+    let failed = processed.is_err();
+    report.record("Pipeline", processed);
+    if failed {
+        return Ok(report);
+    }
+
+    let manifest = ProcessingManifest::load(&ProcessingManifest::path(&output_dir, "selftest")).await;
+    let manifest = match manifest {
+        Ok(manifest) => {
+            report.record("Processing manifest", Ok(format!("{} outputs recorded", manifest.outputs.len())));
+            manifest
+        }
+        Err(e) => {
+            report.record("Processing manifest", Err(e));
+            return Ok(report);
+        }
+    };
+    for language in target_languages {
+        let Some(output) = manifest.outputs.iter().find(|o| &o.language == language) else {
+            report.record(format!("Subtitles ({})", language), Err(ShuroError::FileNotFound(format!("{} subtitles", language))));
+            continue;
+        };
+        let subtitles = match tokio::fs::read_to_string(&output.subtitles).await {
+            Ok(content) => check_subtitles(&content, language),
+            Err(e) => Err(e.into()),
+        };
+        report.record(format!("Subtitles ({})", language), subtitles);
+
+        let tracks = match &output.video {
+            Some(video) => workflow.list_subtitle_tracks(video).await.and_then(|tracks| match tracks.len() {
+                0 => Err(ShuroError::Media(format!("{} has no subtitle track", video.display()))),
+                count => Ok(format!("{} subtitle tracks embedded", count)),
+            }),
+            None => Err(ShuroError::FileNotFound(format!("{} video", language))),
+        };
+        report.record(format!("Video ({})", language), tracks);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::translate::guard;
+
+    #[tokio::test]
+    async fn test_selftest_backend() {
+        let prompt = format!(
+            "The target language is: Japanese (language code: ja)\n\n{}\nText to translate:\n{}\n",
+            guard::DATA_NOTICE, guard::quote("Good morning."),
+        );
+        let request = |format| TranslationRequest {
+            model: "any".to_string(),
+            prompt: prompt.clone(),
+            stream: false,
+            format,
+            options: None,
+        };
+        let backend = SelftestBackend;
+        let client = Client::new();
+
+        let translation = backend.generate(&client, &request(crate::translate::schema::translation())).await.unwrap();
+        assert_eq!(translation.response, r#"{"text":"[ja] Good morning."}"#);
+        let evaluation = backend.generate(&client, &request(crate::translate::schema::evaluation())).await.unwrap();
+        assert_eq!(evaluation.response, r#"{"evaluation":"GOOD"}"#);
+
+        let srt = "1\n00:00:00,200 --> 00:00:01,200\n[ja] Good morning.\n\n2\n00:00:01,400 --> 00:00:02,600\nThe train\n";
+        assert_eq!(check_subtitles(srt, "ja").unwrap_err().to_string(), "Translation error: cue \"The train\" is not translated");
+        assert_eq!(check_subtitles(&srt[..srt.find("\n\n2").unwrap()], "ja").unwrap(), "1 cues translated");
+        assert_eq!(script_transcription().segments.len(), SCRIPT.len());
+    }
+}
@@ -0,0 +1,98 @@
+// Shot change anchoring
+//
+// Professional subtitles start on a shot change and end shortly before one when the speech
+// boundary is close to it; cues that straddle a cut by a few frames flicker across it and look
+// machine-made. With `shots.enabled`, ffmpeg's scene change score finds the cuts of the video and
+// every cue in- or out-time within `tolerance` seconds of a cut is moved onto it (out-times stop
+// `gap` seconds before the cut). Snapping never reorders cues, makes them overlap or shortens one
+// below `min_duration`.
+
+use crate::config::ShotsConfig;
+use crate::quality::Transcription;
+
+/// Times of the shot changes logged by ffmpeg's `showinfo` filter, in ascending order
+pub fn parse_shot_changes(log: &str) -> Vec<f64> {
+    let mut shots: Vec<f64> = log
+        .lines()
+        .filter(|line| line.contains("Parsed_showinfo"))
+        .filter_map(|line| {
+            let value = line.split("pts_time:").nth(1)?;
+            value.split_whitespace().next()?.parse().ok()
+        })
+        .collect();
+    shots.sort_by(f64::total_cmp);
+    shots.dedup();
+    shots
+}
+
+/// Move cue in- and out-times onto nearby shot changes; returns the number of times moved
+pub fn snap_to_shots(transcription: &mut Transcription, shots: &[f64], config: &ShotsConfig) -> usize {
+    let mut moved = 0;
+    let count = transcription.segments.len();
+    for i in 0..count {
+        let previous_end = if i > 0 { transcription.segments[i - 1].end } else { 0.0 };
+        let next_start = transcription.segments.get(i + 1).map_or(f64::MAX, |s| s.start);
+        let segment = &mut transcription.segments[i];
+
+        if let Some(shot) = nearest_shot(shots, segment.start, config.tolerance)
+            && shot != segment.start
+            && shot >= previous_end
+            && segment.end - shot >= config.min_duration {
+            segment.start = shot;
+            moved += 1;
+        }
+
+        if let Some(shot) = nearest_shot(shots, segment.end, config.tolerance) {
+            let end = shot - config.gap;
+            if end != segment.end && end <= next_start && end - segment.start >= config.min_duration {
+                segment.end = end;
+                moved += 1;
+            }
+        }
+    }
+    moved
+}
+
+/// Shot change closest to `time`, if one lies within `tolerance`
+fn nearest_shot(shots: &[f64], time: f64, tolerance: f64) -> Option<f64> {
+    let index = shots.partition_point(|&shot| shot < time);
+    [index.checked_sub(1), Some(index)]
+        .into_iter()
+        .flatten()
+        .filter_map(|i| shots.get(i).copied())
+        .filter(|shot| (shot - time).abs() <= tolerance)
+        .min_by(|a, b| (a - time).abs().total_cmp(&(b - time).abs()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::parse_srt;
+
+    #[test]
+    fn test_parse_shot_changes() {
+        let log = "\
+[Parsed_showinfo_1 @ 0x600] n:   0 pts:  61061 pts_time:2.54421 duration:1001 pos: 1234 fmt:yuv420p\n\
+frame=  100 fps=0.0 q=-0.0 size=N/A time=00:00:04.00\n\
+[Parsed_showinfo_1 @ 0x600] n:   1 pts: 120120 pts_time:5.005   duration:1001 pos: 5678 fmt:yuv420p\n";
+        assert_eq!(parse_shot_changes(log), vec![2.54421, 5.005]);
+    }
+
+    #[test]
+    fn test_snap_to_shots() {
+        let mut transcription = parse_srt(
+            "1\n00:00:01,200 --> 00:00:03,900\nFirst\n\n2\n00:00:04,000 --> 00:00:04,500\nSecond\n\n3\n00:00:05,000 --> 00:00:06,300\nThird\n",
+            "en",
+        ).unwrap();
+        let config = ShotsConfig { enabled: true, tolerance: 0.5, gap: 0.125, min_duration: 0.25, ..ShotsConfig::default() };
+
+        let moved = snap_to_shots(&mut transcription, &[1.0, 4.25, 6.0], &config);
+        let timings: Vec<(i64, i64)> = transcription.segments.iter()
+            .map(|s| ((s.start * 1000.0).round() as i64, (s.end * 1000.0).round() as i64))
+            .collect();
+        // The first cue cannot end before the cut at 4.25 without overlapping the second, and the
+        // second cannot end before a cut that lies before its new start
+        assert_eq!(moved, 3);
+        assert_eq!(timings, vec![(1000, 3900), (4250, 4500), (5000, 5875)]);
+    }
+}
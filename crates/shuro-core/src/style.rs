@@ -0,0 +1,210 @@
+// Subtitle style presets
+//
+// Burned-in subtitles used libass defaults, which look different on every machine. A named preset
+// (`--style`, `media.style`) fixes the font, size, colors, outline or box and position of the
+// subtitles. It is applied to burn-ins and preview clips as the `force_style` of ffmpeg's
+// subtitles filter, and the full pipeline also writes `{output}.ass` with the same style for
+// players and editors that read ASS. Built-in presets:
+//
+// - `netflix-like`: white sans-serif text with a thin black outline and soft shadow
+// - `cinema`: smaller white text close to the bottom edge, as in theatrical prints
+// - `high-contrast`: bold yellow text on an opaque black box
+// - `dyslexia-friendly`: larger Verdana with extra letter spacing, cream on a translucent box
+//
+// Presets defined under `[media.styles.<name>]` take precedence over built-ins of the same name;
+// fields they leave out keep the `netflix-like` values. Sizes and margins are in libass script
+// pixels of a 288-line script, the resolution libass renders SRT files at.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::MediaConfig;
+use crate::error::{Result, ShuroError};
+
+/// Names of the built-in presets
+pub const BUILT_IN_STYLES: [&str; 4] = ["netflix-like", "cinema", "high-contrast", "dyslexia-friendly"];
+
+/// Vertical script resolution sizes and margins are given in
+pub(crate) const PLAY_RES_Y: u32 = 288;
+
+/// Look of burned-in subtitles
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubtitleStyle {
+    pub font_name: String,
+    pub font_size: u32,
+    /// Text color, `#RRGGBB` or `#RRGGBBAA` (AA = opacity)
+    pub text_color: String,
+    pub outline_color: String,
+    /// Shadow color, or box color for boxed styles
+    pub back_color: String,
+    pub bold: bool,
+    pub italic: bool,
+    /// Opaque box behind the text instead of an outline
+    pub boxed: bool,
+    /// Outline width, or box padding for boxed styles
+    pub outline: f64,
+    pub shadow: f64,
+    /// Extra space between letters
+    pub spacing: f64,
+    /// Position on screen in numpad layout: 1-3 bottom, 4-6 middle, 7-9 top (left, center, right)
+    pub alignment: u8,
+    /// Distance from the left edge
+    pub margin_l: u32,
+    /// Distance from the right edge
+    pub margin_r: u32,
+    /// Distance from the bottom edge, or the top edge for top alignments
+    pub margin_v: u32,
+    /// Text colors of speakers in ASS files, given out in order of appearance; empty for one
+    /// color for everyone
+    pub speaker_colors: Vec<String>,
+}
+
+impl Default for SubtitleStyle {
+    fn default() -> Self {
+        Self {
+            font_name: "Arial".to_string(),
+            font_size: 20,
+            text_color: "#FFFFFF".to_string(),
+            outline_color: "#000000".to_string(),
+            back_color: "#00000080".to_string(),
+            bold: false,
+            italic: false,
+            boxed: false,
+            outline: 1.2,
+            shadow: 0.6,
+            spacing: 0.0,
+            alignment: 2,
+            margin_l: 20,
+            margin_r: 20,
+            margin_v: 22,
+            speaker_colors: Vec::new(),
+        }
+    }
+}
+
+impl SubtitleStyle {
+    /// Built-in preset by name
+    pub fn built_in(name: &str) -> Option<Self> {
+        let base = Self::default();
+        match name {
+            "netflix-like" => Some(base),
+            "cinema" => Some(Self { font_size: 16, outline: 0.8, shadow: 0.0, margin_v: 12, ..base }),
+            "high-contrast" => Some(Self {
+                font_size: 22,
+                text_color: "#FFFF00".to_string(),
+                back_color: "#000000".to_string(),
+                bold: true,
+                boxed: true,
+                outline: 2.0,
+                shadow: 0.0,
+                ..base
+            }),
+            "dyslexia-friendly" => Some(Self {
+                font_name: "Verdana".to_string(),
+                font_size: 22,
+                text_color: "#FFFDD0".to_string(),
+                back_color: "#1A1A1AB0".to_string(),
+                boxed: true,
+                outline: 3.0,
+                shadow: 0.0,
+                spacing: 1.5,
+                margin_v: 26,
+                ..base
+            }),
+            _ => None,
+        }
+    }
+
+    /// ASS style fields shared by `force_style` and ASS files, in ASS `Format` order
+    pub(crate) fn fields(&self) -> Result<Vec<(&'static str, String)>> {
+        if !(1..=9).contains(&self.alignment) {
+            return Err(ShuroError::Config(format!(
+                "Invalid subtitle style alignment {}, expected 1-9 (numpad layout)", self.alignment
+            )));
+        }
+        Ok(vec![
+            ("FontName", self.font_name.clone()),
+            ("FontSize", self.font_size.to_string()),
+            ("PrimaryColour", ass_color(&self.text_color)?),
+            ("OutlineColour", ass_color(&self.outline_color)?),
+            ("BackColour", ass_color(&self.back_color)?),
+            ("Bold", if self.bold { "-1" } else { "0" }.to_string()),
+            ("Italic", if self.italic { "-1" } else { "0" }.to_string()),
+            ("Spacing", self.spacing.to_string()),
+            ("BorderStyle", if self.boxed { "3" } else { "1" }.to_string()),
+            ("Outline", self.outline.to_string()),
+            ("Shadow", self.shadow.to_string()),
+            ("Alignment", self.alignment.to_string()),
+            ("MarginL", self.margin_l.to_string()),
+            ("MarginR", self.margin_r.to_string()),
+            ("MarginV", self.margin_v.to_string()),
+        ])
+    }
+
+    /// Value of the `force_style` option of ffmpeg's subtitles filter
+    pub fn force_style(&self) -> Result<String> {
+        Ok(self.fields()?
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(","))
+    }
+}
+
+/// Style selected by `media.style`, looked up among the presets of `media.styles` first
+pub fn selected_style(config: &MediaConfig) -> Result<Option<SubtitleStyle>> {
+    let Some(name) = &config.style else {
+        return Ok(None);
+    };
+    config.styles.get(name)
+        .cloned()
+        .or_else(|| SubtitleStyle::built_in(name))
+        .map(Some)
+        .ok_or_else(|| {
+            let mut known: Vec<&str> = BUILT_IN_STYLES.to_vec();
+            known.extend(config.styles.keys().map(String::as_str).filter(|n| !BUILT_IN_STYLES.contains(n)));
+            ShuroError::Config(format!("Unknown subtitle style '{}' (available: {})", name, known.join(", ")))
+        })
+}
+
+/// `&HAABBGGRR` ASS color (AA = transparency) of a `#RRGGBB` or `#RRGGBBAA` color
+pub(crate) fn ass_color(color: &str) -> Result<String> {
+    let hex = color.trim().trim_start_matches('#');
+    let invalid = || ShuroError::Config(format!("Invalid subtitle style color '{}', expected #RRGGBB or #RRGGBBAA", color));
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+    let opacity = if hex.len() == 8 { channel(6)? } else { 0xFF };
+    Ok(format!("&H{:02X}{:02X}{:02X}{:02X}", 0xFF - opacity, channel(4)?, channel(2)?, channel(0)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_style_presets() {
+        let mut config = crate::Config::default().media;
+        assert_eq!(selected_style(&config).unwrap(), None);
+
+        config.style = Some("high-contrast".to_string());
+        let style = selected_style(&config).unwrap().unwrap();
+        assert!(style.force_style().unwrap().starts_with(
+            "FontName=Arial,FontSize=22,PrimaryColour=&H0000FFFF,OutlineColour=&H00000000,BackColour=&H00000000,Bold=-1"
+        ));
+
+        // User presets override built-ins and default the fields they leave out
+        config.styles = toml::from_str("[high-contrast]\nfont_size = 30\nback_color = \"#10203040\"\n").unwrap();
+        let style = selected_style(&config).unwrap().unwrap();
+        assert_eq!((style.font_size, style.bold), (30, false));
+        assert!(style.force_style().unwrap().contains("BackColour=&HBF302010"));
+
+        config.style = Some("comic".to_string());
+        assert!(selected_style(&config).is_err());
+
+        let top = SubtitleStyle { alignment: 8, margin_v: 30, ..SubtitleStyle::default() };
+        assert!(top.force_style().unwrap().ends_with("Alignment=8,MarginL=20,MarginR=20,MarginV=30"));
+        assert!(SubtitleStyle { alignment: 0, ..top }.force_style().is_err());
+    }
+}
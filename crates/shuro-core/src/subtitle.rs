@@ -0,0 +1,820 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+// SRT time formatting doesn't require chrono for this implementation
+use tokio::fs;
+use tracing::{info, warn};
+
+use crate::error::{Result, ShuroError};
+use crate::media::SubtitleTrack;
+use crate::metadata::SPEAKER;
+use crate::quality::{Transcription, TranscriptionSegment};
+use crate::style::{PLAY_RES_Y, SubtitleStyle, ass_color};
+
+/// Extensions of subtitle files that can be read, embedded and muxed
+pub const SUBTITLE_EXTENSIONS: &[&str] = &["srt", "vtt", "ass", "ssa"];
+
+/// Subtitle file format
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SubtitleFormat {
+    Srt,
+    WebVtt,
+    /// Advanced SubStation Alpha (SSA files are read as well)
+    Ass,
+}
+
+impl SubtitleFormat {
+    /// Detect the format from the content, falling back to the file extension
+    pub fn detect(path: &Path, content: &str) -> Result<Self> {
+        if content.trim_start_matches('\u{feff}').starts_with("WEBVTT") {
+            return Ok(Self::WebVtt);
+        }
+        if content.trim_start_matches('\u{feff}').trim_start().starts_with("[Script Info]") {
+            return Ok(Self::Ass);
+        }
+        let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        match extension.as_str() {
+            "vtt" => Ok(Self::WebVtt),
+            "srt" => Ok(Self::Srt),
+            "ass" | "ssa" => Ok(Self::Ass),
+            _ if content.contains("-->") => Ok(Self::Srt),
+            _ => Err(ShuroError::UnsupportedFormat(format!("Unrecognized subtitle format: {}", path.display()))),
+        }
+    }
+
+    /// File extension of the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::WebVtt => "vtt",
+            Self::Ass => "ass",
+        }
+    }
+
+    /// Whether a subtitle file is ASS or SSA, judging by its extension
+    pub fn is_ass_path(path: &Path) -> bool {
+        path.extension().is_some_and(|e| e.eq_ignore_ascii_case("ass") || e.eq_ignore_ascii_case("ssa"))
+    }
+}
+
+/// Problem in a malformed subtitle file that lenient parsing worked around
+///
+/// Real-world files have duplicated or out-of-order indices, cues without the blank line between
+/// them, stray BOMs and lines in a legacy encoding. Lenient parsing (the default) repairs what it
+/// can, drops cues it cannot read and lists what it did; strict parsing fails on the first problem.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// 1-based line of the problem
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Diagnostics of one file, or the error of the first one in strict mode
+struct Diagnostics {
+    strict: bool,
+    found: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    fn new(strict: bool) -> Self {
+        Self { strict, found: Vec::new() }
+    }
+
+    fn report(&mut self, line: usize, message: impl Into<String>) -> Result<()> {
+        let diagnostic = Diagnostic { line, message: message.into() };
+        if self.strict {
+            return Err(ShuroError::UnsupportedFormat(format!("Malformed subtitles at {}", diagnostic)));
+        }
+        self.found.push(diagnostic);
+        Ok(())
+    }
+}
+
+/// Generate SRT subtitle file from transcription
+pub async fn generate_srt<P: AsRef<Path>>(
+    transcription: &Transcription,
+    output_path: P,
+) -> Result<()> {
+    write_subtitles(transcription, output_path, SubtitleFormat::Srt).await
+}
+
+/// Write a transcription as a subtitle file in the given format
+pub async fn write_subtitles<P: AsRef<Path>>(
+    transcription: &Transcription,
+    output_path: P,
+    format: SubtitleFormat,
+) -> Result<()> {
+    let output_path = output_path.as_ref();
+    info!("Generating {} file: {}", format.extension().to_uppercase(), output_path.display());
+
+    fs::write(output_path, format_subtitles(transcription, format)).await
+        .map_err(ShuroError::Io)?;
+
+    info!("Subtitle file generated successfully");
+    Ok(())
+}
+
+/// Write a transcription as an ASS file with the given style
+pub async fn write_ass<P: AsRef<Path>>(
+    transcription: &Transcription,
+    output_path: P,
+    style: &SubtitleStyle,
+) -> Result<()> {
+    let output_path = output_path.as_ref();
+    info!("Generating ASS file: {}", output_path.display());
+    fs::write(output_path, format_ass(transcription, style)?).await
+        .map_err(ShuroError::Io)?;
+    Ok(())
+}
+
+/// Render a transcription as subtitle file content; ASS files get the default style
+pub fn format_subtitles(transcription: &Transcription, format: SubtitleFormat) -> String {
+    let mut content = match format {
+        SubtitleFormat::Srt => String::new(),
+        SubtitleFormat::WebVtt => "WEBVTT\n\n".to_string(),
+        SubtitleFormat::Ass => {
+            return format_ass(transcription, &SubtitleStyle::default()).expect("the default style is valid");
+        }
+    };
+
+    for (index, segment) in transcription.segments.iter().enumerate() {
+        let (start_time, end_time) = match format {
+            SubtitleFormat::WebVtt => (format_vtt_time(segment.start), format_vtt_time(segment.end)),
+            _ => (format_srt_time(segment.start), format_srt_time(segment.end)),
+        };
+        // WebVTT names the speaker with a voice tag; SRT has no place for it
+        let text = match (format, segment.metadata.get(&SPEAKER)) {
+            (SubtitleFormat::WebVtt, Some(speaker)) => format!("<v {}>{}", speaker, segment.text.trim()),
+            _ => segment.text.trim().to_string(),
+        };
+        if format == SubtitleFormat::Srt {
+            content.push_str(&format!("{}\n", index + 1));
+        }
+        content.push_str(&format!("{} --> {}\n{}\n\n", start_time, end_time, text));
+    }
+    content
+}
+
+/// Read a subtitle file of any supported format, returning the detected format; the problems
+/// lenient parsing worked around are logged as warnings, and fail the read when `strict`
+pub async fn read_subtitles<P: AsRef<Path>>(input_path: P, language: &str, strict: bool) -> Result<(Transcription, SubtitleFormat)> {
+    let input_path = input_path.as_ref();
+    let bytes = fs::read(input_path).await
+        .map_err(ShuroError::Io)?;
+    let mut diagnostics = Diagnostics::new(strict);
+    let content = decode_subtitles(&bytes, &mut diagnostics)
+        .map_err(|e| ShuroError::UnsupportedFormat(format!("{}: {}", input_path.display(), e)))?;
+
+    let format = SubtitleFormat::detect(input_path, &content)?;
+    info!("Reading {} file: {}", format.extension().to_uppercase(), input_path.display());
+    let transcription = parse_with(&content, format, language, &mut diagnostics)
+        .map_err(|e| ShuroError::UnsupportedFormat(format!("{}: {}", input_path.display(), e)))?;
+
+    for diagnostic in &diagnostics.found {
+        warn!("{}: {}", input_path.display(), diagnostic);
+    }
+    if !diagnostics.found.is_empty() {
+        warn!("⚠️ {} problems worked around in {} (--strict rejects the file)", diagnostics.found.len(), input_path.display());
+    }
+    Ok((transcription, format))
+}
+
+/// Parse subtitle content in the given format, returning the problems lenient parsing worked
+/// around; with `strict`, the first problem is an error instead
+pub fn parse_subtitles(content: &str, format: SubtitleFormat, language: &str, strict: bool) -> Result<(Transcription, Vec<Diagnostic>)> {
+    let mut diagnostics = Diagnostics::new(strict);
+    let transcription = parse_with(content, format, language, &mut diagnostics)?;
+    Ok((transcription, diagnostics.found))
+}
+
+fn parse_with(content: &str, format: SubtitleFormat, language: &str, diagnostics: &mut Diagnostics) -> Result<Transcription> {
+    match format {
+        SubtitleFormat::Srt => parse_cues(content, language, false, diagnostics),
+        SubtitleFormat::WebVtt => parse_cues(content, language, true, diagnostics),
+        SubtitleFormat::Ass => parse_ass_events(content, language, diagnostics),
+    }
+}
+
+/// Text of a subtitle file: UTF-16 with a BOM, otherwise UTF-8 line by line, reading lines that
+/// are not valid UTF-8 as Windows-1252 (files stitched together from differently encoded parts)
+fn decode_subtitles(bytes: &[u8], diagnostics: &mut Diagnostics) -> Result<String> {
+    let utf16 = |bytes: &[u8], unit: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]])).collect();
+        String::from_utf16_lossy(&units)
+    };
+    if let Some(rest) = bytes.strip_prefix(&[0xff, 0xfe]) {
+        return Ok(utf16(rest, u16::from_le_bytes));
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xfe, 0xff]) {
+        return Ok(utf16(rest, u16::from_be_bytes));
+    }
+
+    let mut content = String::with_capacity(bytes.len());
+    let mut legacy_lines = Vec::new();
+    for (index, line) in bytes.split_inclusive(|&b| b == b'\n').enumerate() {
+        match std::str::from_utf8(line) {
+            Ok(line) => content.push_str(line),
+            Err(_) => {
+                legacy_lines.push(index + 1);
+                content.extend(line.iter().map(|&b| windows_1252(b)));
+            }
+        }
+    }
+    if let Some(first) = legacy_lines.first() {
+        diagnostics.report(*first, format!("{} lines are not UTF-8 and were read as Windows-1252", legacy_lines.len()))?;
+    }
+    Ok(content)
+}
+
+/// Character of a Windows-1252 byte
+fn windows_1252(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+        '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+    ];
+    match byte {
+        0x80..=0x9f => HIGH[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
+
+/// Render a transcription as an ASS file with one style; a segment's speaker goes into the
+/// `Name` field of its event. With `speaker_colors`, each speaker gets a copy of the style in
+/// their own color.
+pub fn format_ass(transcription: &Transcription, style: &SubtitleStyle) -> Result<String> {
+    let fields = style.fields()?;
+    let value = |key: &str| fields.iter().find(|(k, _)| *k == key).map(|(_, v)| v.as_str()).unwrap_or_default();
+    let style_line = |name: &str, text_color: &str| format!(
+        "Style: {},{},{},{},&H000000FF,{},{},{},{},0,0,100,100,{},0,{},{},{},{},{},{},{},1\n",
+        name, value("FontName"), value("FontSize"), text_color, value("OutlineColour"), value("BackColour"),
+        value("Bold"), value("Italic"), value("Spacing"), value("BorderStyle"), value("Outline"), value("Shadow"),
+        value("Alignment"), value("MarginL"), value("MarginR"), value("MarginV"),
+    );
+
+    let mut speakers: Vec<String> = Vec::new();
+    if !style.speaker_colors.is_empty() {
+        for speaker in transcription.segments.iter().filter_map(|s| s.metadata.get(&SPEAKER)) {
+            if !speakers.contains(&speaker) {
+                speakers.push(speaker);
+            }
+        }
+    }
+    let mut content = format!(
+        "[Script Info]\nScriptType: v4.00+\nPlayResX: {}\nPlayResY: {}\nWrapStyle: 0\nScaledBorderAndShadow: yes\n\n\
+         [V4+ Styles]\nFormat: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, \
+         Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, \
+         MarginL, MarginR, MarginV, Encoding\n{}",
+        PLAY_RES_Y * 4 / 3, PLAY_RES_Y, style_line("Default", value("PrimaryColour")),
+    );
+    for index in 0..speakers.len() {
+        let color = ass_color(&style.speaker_colors[index % style.speaker_colors.len()])?;
+        content.push_str(&style_line(&format!("Speaker{}", index + 1), &color));
+    }
+    content.push_str("\n[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n");
+
+    for segment in &transcription.segments {
+        // ASS has no escape for override blocks, so braces in the text are replaced
+        let text = segment.text.trim().replace('{', "(").replace('}', ")").replace('\n', "\\N");
+        let speaker = segment.metadata.get(&SPEAKER).unwrap_or_default();
+        let style_name = match speakers.iter().position(|s| *s == speaker) {
+            Some(index) => format!("Speaker{}", index + 1),
+            None => "Default".to_string(),
+        };
+        content.push_str(&format!(
+            "Dialogue: 0,{},{},{},{},0,0,0,,{}\n",
+            format_ass_time(segment.start), format_ass_time(segment.end), style_name, speaker.replace(',', " "), text
+        ));
+    }
+    Ok(content)
+}
+
+/// Parse ASS or SSA content: the `Dialogue` events of the `[Events]` section in order of start
+/// time, with override blocks (`{\\i1}`) removed and `\\N` turned into line breaks; the `Name`
+/// field becomes the segment's speaker. Unreadable events are skipped.
+pub fn parse_ass(content: &str, language: &str) -> Result<Transcription> {
+    parse_ass_events(content, language, &mut Diagnostics::new(false))
+}
+
+fn parse_ass_events(content: &str, language: &str, diagnostics: &mut Diagnostics) -> Result<Transcription> {
+    let mut in_events = false;
+    let mut columns: Option<Vec<String>> = None;
+    let mut segments = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[Events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+        if let Some(format) = line.strip_prefix("Format:") {
+            columns = Some(format.split(',').map(|c| c.trim().to_lowercase()).collect());
+        } else if let Some(event) = line.strip_prefix("Dialogue:") {
+            let Some(columns) = columns.as_ref() else {
+                return Err(ShuroError::UnsupportedFormat("ASS event before the Format line of [Events]".to_string()));
+            };
+            // The text is the last column and may contain commas
+            let values: Vec<&str> = event.splitn(columns.len(), ',').collect();
+            let value = |name: &str| columns.iter().position(|c| c == name).and_then(|i| values.get(i).copied());
+            let time = |name: &str| value(name).and_then(|t| parse_ass_time(t.trim()).ok());
+            let (Some(start), Some(end), Some(text)) = (time("start"), time("end"), value("text")) else {
+                diagnostics.report(index + 1, format!("Unreadable event skipped: {}", line))?;
+                continue;
+            };
+
+            let mut segment = cue(segments.len(), start, end, ass_text(text));
+            if let Some(speaker) = value("name").map(str::trim).filter(|n| !n.is_empty()) {
+                segment.metadata.insert(&SPEAKER, speaker.to_string());
+            }
+            segments.push(segment);
+        }
+    }
+
+    segments.sort_by(|a, b| a.start.total_cmp(&b.start));
+    for (id, segment) in segments.iter_mut().enumerate() {
+        segment.id = id as i32;
+    }
+    Ok(Transcription {
+        text: segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" "),
+        segments,
+        language: language.to_string(),
+    })
+}
+
+/// Plain text of an ASS event: override blocks removed, `\\N` and `\\n` as line breaks and `\\h` as a space
+fn ass_text(text: &str) -> String {
+    let mut plain = String::new();
+    let mut depth = 0usize;
+    for c in text.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => plain.push(c),
+            _ => {}
+        }
+    }
+    plain.replace("\\N", "\n").replace("\\n", "\n").replace("\\h", " ").trim().to_string()
+}
+
+/// Parse an ASS time (`H:MM:SS.cc`) into seconds
+fn parse_ass_time(value: &str) -> Result<f64> {
+    let invalid = || ShuroError::UnsupportedFormat(format!("Invalid subtitle time: {}", value));
+    let (clock, centis) = value.split_once('.').ok_or_else(invalid)?;
+    let parts: Vec<u64> = clock
+        .split(':')
+        .map(|p| p.parse::<u64>().map_err(|_| invalid()))
+        .collect::<Result<_>>()?;
+    let [hours, minutes, seconds] = parts.as_slice() else {
+        return Err(invalid());
+    };
+    let fraction: f64 = format!("0.{}", centis).parse().map_err(|_| invalid())?;
+    Ok((hours * 3600 + minutes * 60 + seconds) as f64 + fraction)
+}
+
+/// `H:MM:SS.cc` time of an ASS event
+fn format_ass_time(seconds: f64) -> String {
+    let centiseconds = (seconds.max(0.0) * 100.0).round() as u64;
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        centiseconds / 360_000, centiseconds / 6000 % 60, centiseconds / 100 % 60, centiseconds % 100
+    )
+}
+
+/// Read an SRT subtitle file into a transcription in the given language
+pub async fn read_srt<P: AsRef<Path>>(input_path: P, language: &str) -> Result<Transcription> {
+    let input_path = input_path.as_ref();
+    info!("Reading SRT file: {}", input_path.display());
+
+    let bytes = fs::read(input_path).await
+        .map_err(ShuroError::Io)?;
+    let mut diagnostics = Diagnostics::new(false);
+    let content = decode_subtitles(&bytes, &mut diagnostics)?;
+    parse_cues(&content, language, false, &mut diagnostics)
+}
+
+/// Subtitle files belonging to a video, named `{video_stem}_{lang}.srt` or `{video_stem}.{lang}.srt`
+/// (or `.vtt`, `.ass`, `.ssa`)
+///
+/// A `.forced` suffix (`{video_stem}_{lang}.forced.srt`) marks a forced-narrative track. Tracks are
+/// sorted by language, full tracks first; files without a language suffix are not matched.
+pub fn match_subtitle_files(video_stem: &str, subtitle_paths: &[PathBuf]) -> Vec<SubtitleTrack> {
+    let mut tracks: Vec<SubtitleTrack> = subtitle_paths
+        .iter()
+        .filter(|path| path.extension().is_some_and(|e| SUBTITLE_EXTENSIONS.iter().any(|ext| e.eq_ignore_ascii_case(ext))))
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_str()?;
+            let language = stem.strip_prefix(video_stem)?.strip_prefix(['_', '.'])?;
+            let (language, forced) = match language.strip_suffix(".forced") {
+                Some(language) => (language, true),
+                None => (language, false),
+            };
+            let valid = !language.is_empty() && language.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+            valid.then(|| SubtitleTrack { path: path.clone(), language: language.to_string(), forced, default: false })
+        })
+        .collect();
+    tracks.sort_by(|a, b| (&a.language, a.forced).cmp(&(&b.language, b.forced)));
+    tracks
+}
+
+/// Parse SRT content leniently (see `Diagnostic`); cues are numbered sequentially regardless of
+/// the indices in the file
+pub fn parse_srt(content: &str, language: &str) -> Result<Transcription> {
+    parse_cues(content, language, false, &mut Diagnostics::new(false))
+}
+
+/// Parse WebVTT content leniently, skipping the header and NOTE, STYLE and REGION blocks; a
+/// leading voice tag (`<v Speaker>`) becomes the segment's speaker
+pub fn parse_vtt(content: &str, language: &str) -> Result<Transcription> {
+    parse_cues(content, language, true, &mut Diagnostics::new(false))
+}
+
+/// Parse cues made of an optional identifier line, a timing line and the cue text
+///
+/// Cues are found by their timing lines rather than by blank lines, so a missing blank line only
+/// costs a diagnostic: a number directly before a timing line is the next cue's index. Cues
+/// with an unreadable timing line are dropped, and a cue ending before it starts is cut to zero
+/// length.
+fn parse_cues(content: &str, language: &str, vtt: bool, diagnostics: &mut Diagnostics) -> Result<Transcription> {
+    let lines: Vec<&str> = content.lines().map(|line| line.trim_matches('\u{feff}').trim_end()).collect();
+    let mut segments = Vec::new();
+    // Start, end and text lines of the cue being read
+    let mut current: Option<(f64, f64, Vec<&str>)> = None;
+    // Lines of a dropped cue, or of a WebVTT header, NOTE, STYLE or REGION block
+    let mut skipping = false;
+    let mut last_index: Option<u64> = None;
+
+    let flush = |current: &mut Option<(f64, f64, Vec<&str>)>, segments: &mut Vec<TranscriptionSegment>| {
+        if let Some((start, end, text)) = current.take() {
+            segments.push(cue(segments.len(), start, end, text.join("\n")));
+        }
+    };
+
+    for (index, line) in lines.iter().enumerate() {
+        let number = index + 1;
+        if line.trim().is_empty() {
+            flush(&mut current, &mut segments);
+            skipping = false;
+            continue;
+        }
+
+        if line.contains("-->") {
+            if current.is_some() {
+                diagnostics.report(number, "Missing blank line before cue")?;
+            }
+            flush(&mut current, &mut segments);
+            skipping = false;
+            match parse_timing(line) {
+                Ok((start, end)) if end < start => {
+                    diagnostics.report(number, format!("Cue ends before it starts: {}", line.trim()))?;
+                    current = Some((start, start, Vec::new()));
+                }
+                Ok((start, end)) => current = Some((start, end, Vec::new())),
+                Err(e) => {
+                    diagnostics.report(number, format!("{}; cue skipped", e))?;
+                    skipping = true;
+                }
+            }
+            continue;
+        }
+
+        // Identifier of the next cue; in SRT, a line of text before it unless it is a number
+        let identifier = vtt || current.is_none() || line.trim().parse::<u64>().is_ok();
+        if identifier && lines.get(index + 1).is_some_and(|next| next.contains("-->")) {
+            if current.is_some() || skipping {
+                diagnostics.report(number, "Missing blank line before cue")?;
+            }
+            flush(&mut current, &mut segments);
+            skipping = false;
+            if !vtt && let Ok(cue_index) = line.trim().parse::<u64>() {
+                if let Some(last) = last_index
+                    && cue_index <= last {
+                    diagnostics.report(number, format!("Cue index {} does not follow {}", cue_index, last))?;
+                }
+                last_index = Some(cue_index);
+            }
+            continue;
+        }
+
+        if skipping {
+            continue;
+        }
+        match &mut current {
+            Some((_, _, text)) => text.push(line),
+            None if vtt && ["WEBVTT", "NOTE", "STYLE", "REGION"].iter().any(|keyword| line.starts_with(keyword)) => {
+                skipping = true;
+            }
+            None => {
+                diagnostics.report(number, format!("Text outside of a cue skipped: {}", line.trim()))?;
+                skipping = true;
+            }
+        }
+    }
+    flush(&mut current, &mut segments);
+
+    if vtt {
+        for segment in &mut segments {
+            if let Some(tagged) = segment.text.strip_prefix("<v ")
+                && let Some((speaker, text)) = tagged.split_once('>')
+            {
+                segment.metadata.insert(&SPEAKER, speaker.trim().to_string());
+                segment.text = text.replace("</v>", "").trim().to_string();
+            }
+        }
+    }
+    Ok(Transcription {
+        text: segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" "),
+        segments,
+        language: language.to_string(),
+    })
+}
+
+/// Start and end of a timing line; WebVTT cue settings (`align:start` ...) follow the end time
+fn parse_timing(line: &str) -> Result<(f64, f64)> {
+    let (start, end) = line.split_once("-->").ok_or_else(|| ShuroError::UnsupportedFormat(format!(
+        "Invalid timing line: {}", line
+    )))?;
+    let end = end.split_whitespace().next().unwrap_or_default();
+    Ok((parse_srt_time(start.trim())?, parse_srt_time(end.trim())?))
+}
+
+/// Segment of a subtitle cue
+fn cue(id: usize, start: f64, end: f64, text: String) -> TranscriptionSegment {
+    TranscriptionSegment {
+        id: id as i32,
+        start,
+        end,
+        text,
+        tokens: Vec::new(),
+        temperature: 0.0,
+        avg_logprob: 0.0,
+        compression_ratio: 0.0,
+        no_speech_prob: 0.0,
+        words: Vec::new(),
+        metadata: Default::default(),
+    }
+}
+
+/// Parse SRT time format (HH:MM:SS,mmm, also accepting a '.' separator and WebVTT's MM:SS.mmm) into seconds
+fn parse_srt_time(value: &str) -> Result<f64> {
+    let invalid = || ShuroError::UnsupportedFormat(format!("Invalid subtitle time: {}", value));
+
+    let (clock, millis) = value.split_once([',', '.']).ok_or_else(invalid)?;
+    let parts: Vec<u64> = clock
+        .split(':')
+        .map(|p| p.parse::<u64>().map_err(|_| invalid()))
+        .collect::<Result<_>>()?;
+    let millis: u64 = millis.parse().map_err(|_| invalid())?;
+
+    match parts.as_slice() {
+        [hours, minutes, seconds] => Ok(((hours * 3600 + minutes * 60 + seconds) * 1000 + millis) as f64 / 1000.0),
+        [minutes, seconds] => Ok(((minutes * 60 + seconds) * 1000 + millis) as f64 / 1000.0),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parse a command-line time: `HH:MM:SS[.mmm]`, `MM:SS[.mmm]`, or seconds with an optional
+/// `s`, `m` or `h` unit (`90`, `30s`, `1.5m`)
+pub fn parse_timecode(value: &str) -> Result<f64> {
+    let invalid = || ShuroError::Config(format!("Invalid time: {} (use HH:MM:SS, MM:SS or a duration like 30s)", value));
+    let value = value.trim();
+
+    if value.contains(':') {
+        let mut seconds = 0.0;
+        let parts: Vec<&str> = value.split(':').collect();
+        if parts.len() > 3 {
+            return Err(invalid());
+        }
+        for part in parts {
+            let part: f64 = part.parse().map_err(|_| invalid())?;
+            if part < 0.0 {
+                return Err(invalid());
+            }
+            seconds = seconds * 60.0 + part;
+        }
+        return Ok(seconds);
+    }
+
+    let (number, scale) = match value.char_indices().last() {
+        Some((i, 's')) => (&value[..i], 1.0),
+        Some((i, 'm')) => (&value[..i], 60.0),
+        Some((i, 'h')) => (&value[..i], 3600.0),
+        _ => (value, 1.0),
+    };
+    let seconds: f64 = number.parse().map_err(|_| invalid())?;
+    if !seconds.is_finite() || seconds < 0.0 {
+        return Err(invalid());
+    }
+    Ok(seconds * scale)
+}
+
+/// Cues overlapping `start..start + duration`, clipped to the window and shifted so it starts at zero
+pub fn clip_transcription(transcription: &Transcription, start: f64, duration: f64) -> Transcription {
+    let end = start + duration;
+    let segments: Vec<TranscriptionSegment> = transcription.segments
+        .iter()
+        .filter(|s| s.end > start && s.start < end)
+        .enumerate()
+        .map(|(id, s)| {
+            let mut segment = s.clone();
+            segment.id = id as i32;
+            segment.start = (s.start.max(start) - start).min(duration);
+            segment.end = (s.end.min(end) - start).max(segment.start);
+            segment.words.retain(|w| w.end > start && w.start < end);
+            for word in &mut segment.words {
+                word.start = word.start.max(start) - start;
+                word.end = word.end.min(end) - start;
+            }
+            segment
+        })
+        .collect();
+
+    Transcription {
+        text: segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" "),
+        segments,
+        language: transcription.language.clone(),
+    }
+}
+
+/// Format time in seconds to SRT time format (HH:MM:SS,mmm)
+fn format_srt_time(seconds: f64) -> String {
+    let total_milliseconds = (seconds * 1000.0) as u64;
+    let hours = total_milliseconds / 3_600_000;
+    let minutes = (total_milliseconds % 3_600_000) / 60_000;
+    let secs = (total_milliseconds % 60_000) / 1_000;
+    let millis = total_milliseconds % 1_000;
+    
+    format!("{:02}:{:02}:{:02},{:03}", hours, minutes, secs, millis)
+}
+
+/// Format time in seconds to WebVTT time format (HH:MM:SS.mmm)
+pub(crate) fn format_vtt_time(seconds: f64) -> String {
+    format_srt_time(seconds).replace(',', ".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_srt_time() {
+        assert_eq!(format_srt_time(0.0), "00:00:00,000");
+        assert_eq!(format_srt_time(65.123), "00:01:05,123");
+        assert_eq!(format_srt_time(3661.500), "01:01:01,500");
+    }
+
+    #[test]
+    fn test_parse_timecode_and_clip() {
+        assert_eq!(parse_timecode("00:20:00").unwrap(), 1200.0);
+        assert_eq!(parse_timecode("1:30.5").unwrap(), 90.5);
+        assert_eq!(parse_timecode("30s").unwrap(), 30.0);
+        assert_eq!(parse_timecode("1.5m").unwrap(), 90.0);
+        assert_eq!(parse_timecode("45").unwrap(), 45.0);
+        assert!(parse_timecode("soon").is_err());
+        assert!(parse_timecode("-5s").is_err());
+
+        let transcription = parse_srt(
+            "1\n00:19:58,000 --> 00:20:01,000\nBefore\n\n2\n00:20:10,000 --> 00:20:12,000\nInside\n\n3\n00:20:40,000 --> 00:20:42,000\nAfter\n",
+            "ja",
+        ).unwrap();
+        let clip = clip_transcription(&transcription, 1200.0, 30.0);
+        assert_eq!(clip.segments.len(), 2);
+        assert_eq!((clip.segments[0].start, clip.segments[0].end), (0.0, 1.0));
+        assert_eq!((clip.segments[1].start, clip.segments[1].end), (10.0, 12.0));
+        assert_eq!(clip.segments[1].id, 1);
+    }
+
+    #[test]
+    fn test_parse_srt() {
+        let content = "\u{feff}1\r\n00:00:01,000 --> 00:00:02,500\r\nこんにちは\r\n\r\n7\n00:01:05.123 --> 00:01:06,000\n二行目\n三行目\n";
+        let transcription = parse_srt(content, "ja").unwrap();
+        assert_eq!(transcription.segments.len(), 2);
+        assert_eq!(transcription.segments[0].start, 1.0);
+        assert_eq!(transcription.segments[0].end, 2.5);
+        assert_eq!(transcription.segments[1].id, 1);
+        assert_eq!(transcription.segments[1].start, 65.123);
+        assert_eq!(transcription.segments[1].text, "二行目\n三行目");
+
+        assert!(parse_subtitles("1\n00:00:01 --> 00:00:02\ntext", SubtitleFormat::Srt, "ja", true).is_err());
+    }
+
+    #[test]
+    fn test_lenient_parsing() {
+        // Duplicated index, missing blank lines, a stray BOM, a bad timing line and reversed times
+        let content = "1\n00:00:01,000 --> 00:00:02,000\nFirst\n1\n00:00:03,000 --> 00:00:04,000\nSecond\n\
+                       00:00:05,000 --> 00:00:06,000\nThird\n\n\u{feff}4\n00:00:07 --> 00:00:08\nLost\n\n\
+                       5\n00:00:10,000 --> 00:00:09,000\nBackwards\n";
+        let (transcription, diagnostics) = parse_subtitles(content, SubtitleFormat::Srt, "en", false).unwrap();
+        let texts: Vec<&str> = transcription.segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["First", "Second", "Third", "Backwards"]);
+        assert_eq!(transcription.segments[3].end, 10.0);
+        let lines: Vec<usize> = diagnostics.iter().map(|d| d.line).collect();
+        assert_eq!(lines, [4, 4, 7, 11, 15]);
+        assert!(diagnostics[1].message.contains("Cue index 1 does not follow 1"));
+        assert!(parse_subtitles(content, SubtitleFormat::Srt, "en", true).unwrap_err().to_string().contains("line 4"));
+
+        let broken_event = "[Events]\nFormat: Start, End, Text\nDialogue: 0:00:01.00,soon,Hi\nDialogue: 0:00:02.00,0:00:03.00,Bye\n";
+        let (ass, diagnostics) = parse_subtitles(broken_event, SubtitleFormat::Ass, "en", false).unwrap();
+        assert_eq!((ass.segments.len(), diagnostics[0].line), (1, 3));
+
+        // Latin-1 lines among UTF-8 ones, and UTF-16 with a BOM
+        let mut diagnostics = Diagnostics::new(false);
+        let mixed = decode_subtitles(b"caf\xc3\xa9\ncaf\xe9 \x93ok\x94\n", &mut diagnostics).unwrap();
+        assert_eq!(mixed, "café\ncafé “ok”\n");
+        assert_eq!(diagnostics.found[0].line, 2);
+        let utf16: Vec<u8> = [0xff, 0xfe].into_iter().chain("字幕".encode_utf16().flat_map(u16::to_le_bytes)).collect();
+        assert_eq!(decode_subtitles(&utf16, &mut diagnostics).unwrap(), "字幕");
+        assert!(decode_subtitles(b"\xe9", &mut Diagnostics::new(true)).is_err());
+    }
+
+    #[test]
+    fn test_srt_round_trip() {
+        let content = "1\n00:00:01,250 --> 00:00:03,000\nFirst line\nsecond line\n\n2\n01:02:03,456 --> 01:02:05,000\nLast\n\n";
+        let transcription = parse_srt(content, "en").unwrap();
+        assert_eq!(format_subtitles(&transcription, SubtitleFormat::Srt), content);
+
+        // Converting through WebVTT keeps timing and text
+        let vtt = parse_vtt(&format_subtitles(&transcription, SubtitleFormat::WebVtt), "en").unwrap();
+        assert_eq!(format_subtitles(&vtt, SubtitleFormat::Srt), content);
+    }
+
+    #[test]
+    fn test_parse_and_format_vtt() {
+        let content = "WEBVTT - episode 1\n\nNOTE translated by hand\n\nintro\n00:01.000 --> 00:02.500 align:start\nHello\n\n01:00:00.000 --> 01:00:01.000\nBye\n";
+        assert_eq!(SubtitleFormat::detect(Path::new("ep1.txt"), content).unwrap(), SubtitleFormat::WebVtt);
+        assert_eq!(SubtitleFormat::detect(Path::new("ep1.srt"), "1\n00:00:01,000 --> 00:00:02,000\nHi").unwrap(), SubtitleFormat::Srt);
+        assert!(SubtitleFormat::detect(Path::new("notes.txt"), "just text").is_err());
+
+        let transcription = parse_vtt(content, "en").unwrap();
+        assert_eq!(transcription.segments.len(), 2);
+        assert_eq!((transcription.segments[0].start, transcription.segments[0].end), (1.0, 2.5));
+        assert_eq!(transcription.segments[1].start, 3600.0);
+
+        let formatted = format_subtitles(&transcription, SubtitleFormat::WebVtt);
+        assert!(formatted.starts_with("WEBVTT\n\n00:00:01.000 --> 00:00:02.500\nHello\n\n"));
+        assert_eq!(parse_vtt(&formatted, "en").unwrap().segments.len(), 2);
+
+        let voiced = parse_vtt("WEBVTT\n\n00:01.000 --> 00:02.000\n<v Alice>Hi there</v>\n", "en").unwrap();
+        assert_eq!(voiced.segments[0].text, "Hi there");
+        assert_eq!(voiced.segments[0].metadata.get(&SPEAKER).as_deref(), Some("Alice"));
+        assert!(format_subtitles(&voiced, SubtitleFormat::WebVtt).contains("\n<v Alice>Hi there\n"));
+        assert!(!format_subtitles(&voiced, SubtitleFormat::Srt).contains("Alice"));
+    }
+
+    #[test]
+    fn test_parse_and_format_ass() {
+        let content = "\u{feff}[Script Info]\nScriptType: v4.00+\n\n[V4+ Styles]\nFormat: Name, Fontname\nStyle: Default,Arial\n\n\
+                       [Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+                       Comment: 0,0:00:00.00,0:00:01.00,Default,,0,0,0,,timing note\n\
+                       Dialogue: 0,0:00:05.00,0:00:06.50,Default,,0,0,0,,{\\an8}Sign, text\n\
+                       Dialogue: 0,0:00:01.25,0:00:03.00,Default,Alice,0,0,0,,{\\i1}Hello{\\i0}\\Nthere\\hfriend\n";
+        assert_eq!(SubtitleFormat::detect(Path::new("ep1.txt"), content).unwrap(), SubtitleFormat::Ass);
+        assert_eq!(SubtitleFormat::detect(Path::new("ep1.ssa"), "").unwrap(), SubtitleFormat::Ass);
+
+        let transcription = parse_ass(content, "en").unwrap();
+        assert_eq!(transcription.segments.len(), 2);
+        assert_eq!((transcription.segments[0].start, transcription.segments[0].end), (1.25, 3.0));
+        assert_eq!(transcription.segments[0].text, "Hello\nthere friend");
+        assert_eq!(transcription.segments[0].metadata.get(&SPEAKER).as_deref(), Some("Alice"));
+        assert_eq!((transcription.segments[1].id, transcription.segments[1].text.as_str()), (1, "Sign, text"));
+
+        let style = SubtitleStyle { font_name: "Noto Sans".to_string(), alignment: 8, ..SubtitleStyle::built_in("cinema").unwrap() };
+        let ass = format_ass(&transcription, &style).unwrap();
+        assert!(ass.contains("Style: Default,Noto Sans,16,&H00FFFFFF,&H000000FF,&H00000000,&H7F000000,0,0,"));
+        assert!(ass.contains(",8,20,20,12,1\n"));
+        assert!(ass.contains("Dialogue: 0,0:00:01.25,0:00:03.00,Default,Alice,0,0,0,,Hello\\Nthere friend\n"));
+        let round_trip = parse_ass(&ass, "en").unwrap();
+        assert_eq!(format_subtitles(&round_trip, SubtitleFormat::Srt), format_subtitles(&transcription, SubtitleFormat::Srt));
+
+        // Speakers get their own style in their color
+        let style = SubtitleStyle { speaker_colors: vec!["#FFFF00".to_string()], ..style };
+        let ass = format_ass(&transcription, &style).unwrap();
+        assert!(ass.contains("Style: Speaker1,Noto Sans,16,&H0000FFFF,"));
+        assert!(ass.contains("Dialogue: 0,0:00:01.25,0:00:03.00,Speaker1,Alice,"));
+        assert!(ass.contains("Dialogue: 0,0:00:05.00,0:00:06.50,Default,,"));
+
+        let braces = parse_srt("1\n00:01:02,340 --> 00:01:04,000\nHello {there}\n", "en").unwrap();
+        assert!(format_subtitles(&braces, SubtitleFormat::Ass).ends_with("Dialogue: 0,0:01:02.34,0:01:04.00,Default,,0,0,0,,Hello (there)\n"));
+        assert!(parse_ass("[Events]\nDialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,Hi\n", "en").is_err());
+    }
+
+    #[test]
+    fn test_match_subtitle_files() {
+        let paths: Vec<PathBuf> = ["ep1_ko.forced.srt", "ep1_ko.srt", "ep1.ja.srt", "ep10_ja.srt", "ep1.srt", "ep1_director_cut_en.srt", "ep1_en.txt"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        let tracks = match_subtitle_files("ep1", &paths);
+        assert_eq!(tracks, vec![
+            SubtitleTrack { path: PathBuf::from("ep1.ja.srt"), language: "ja".to_string(), forced: false, default: false },
+            SubtitleTrack { path: PathBuf::from("ep1_ko.srt"), language: "ko".to_string(), forced: false, default: false },
+            SubtitleTrack { path: PathBuf::from("ep1_ko.forced.srt"), language: "ko".to_string(), forced: true, default: false },
+        ]);
+    }
+} 
\ No newline at end of file
@@ -0,0 +1,170 @@
+// Temporary artifacts
+//
+// Intermediate files (whisper output directories, tempo-variant WAVs, language identification
+// samples, extracted subtitle tracks) live in directories under `Config::temp_dir` (`.shuro/tmp/`,
+// or `tmp/` of the `--work-dir` or of the data directory of a `Shuro`) named `{prefix}-{pid}-{random}`. Each directory is removed when its
+// `TempArtifact` is dropped, on success and on failure alike, and every live directory is listed
+// in a process-wide registry that `remove_registered` empties before the process exits, so an
+// interrupted run leaves nothing behind either. A run killed outright cannot clean up; `shuro clean-temp` removes the
+// directories whose process is no longer running, along with tempo-variant directories older
+// versions created in the audio cache.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{debug, warn};
+
+/// Directories of the live artifacts of this process
+static REGISTRY: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Prefix of the tempo-variant directories older versions created in the audio cache
+const LEGACY_VARIANTS_PREFIX: &str = "tempo_variants";
+
+/// Temporary directory, removed when dropped
+pub struct TempArtifact {
+    dir: tempfile::TempDir,
+}
+
+impl TempArtifact {
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+impl Drop for TempArtifact {
+    fn drop(&mut self) {
+        registry().retain(|path| path != self.dir.path());
+    }
+}
+
+/// Create a tracked temporary directory in `root` (the configured `temp_dir`) whose name starts
+/// with `prefix`
+pub fn temp_dir(root: &Path, prefix: &str) -> std::io::Result<TempArtifact> {
+    std::fs::create_dir_all(root)?;
+    let dir = tempfile::Builder::new()
+        .prefix(&format!("{}-{}-", prefix, std::process::id()))
+        .tempdir_in(root)?;
+    registry().push(dir.path().to_path_buf());
+    Ok(TempArtifact { dir })
+}
+
+/// Remove the directories of all live artifacts; returns how many were removed
+pub fn remove_registered() -> usize {
+    let paths: Vec<PathBuf> = registry().drain(..).collect();
+    paths.iter().filter(|path| std::fs::remove_dir_all(path).is_ok()).count()
+}
+
+/// Removes the directories of all live artifacts when dropped, so an application holding it
+/// for its lifetime cleans up however it exits
+pub struct CleanupGuard;
+
+impl Drop for CleanupGuard {
+    fn drop(&mut self) {
+        let removed = remove_registered();
+        if removed > 0 {
+            debug!("Removed {} temporary directories", removed);
+        }
+    }
+}
+
+fn registry() -> std::sync::MutexGuard<'static, Vec<PathBuf>> {
+    REGISTRY.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Temporary directories in `root` left behind by runs that are no longer running, plus the
+/// tempo-variant directories of older versions in `audio_cache_dir`; with `all`, every
+/// temporary directory regardless of its process
+pub fn leftovers(root: &Path, audio_cache_dir: &Path, all: bool) -> Vec<PathBuf> {
+    let entries = |dir: &Path| -> Vec<(PathBuf, String)> {
+        std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .map(|e| (e.path(), e.file_name().to_string_lossy().into_owned()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let mut found: Vec<PathBuf> = entries(root)
+        .into_iter()
+        .filter(|(_, name)| all || owner(name).is_none_or(|pid| !is_running(pid)))
+        .map(|(path, _)| path)
+        .collect();
+    found.extend(
+        entries(audio_cache_dir)
+            .into_iter()
+            .filter(|(path, name)| path.is_dir() && name.starts_with(LEGACY_VARIANTS_PREFIX))
+            .map(|(path, _)| path),
+    );
+    found.sort();
+    found
+}
+
+/// Remove leftover temporary files and directories; returns the removed paths
+pub fn remove_leftovers(paths: &[PathBuf]) -> Vec<PathBuf> {
+    paths.iter()
+        .filter(|path| {
+            let removed = if path.is_dir() { std::fs::remove_dir_all(path) } else { std::fs::remove_file(path) };
+            if let Err(e) = &removed {
+                warn!("Failed to remove {}: {}", path.display(), e);
+            }
+            removed.is_ok()
+        })
+        .cloned()
+        .collect()
+}
+
+/// Process id in a `{prefix}-{pid}-{random}` directory name
+fn owner(name: &str) -> Option<u32> {
+    let mut parts = name.rsplitn(3, '-');
+    let (_random, pid, _prefix) = (parts.next()?, parts.next()?, parts.next()?);
+    pid.parse().ok()
+}
+
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    let Some(pid) = i32::try_from(pid).ok().and_then(rustix::process::Pid::from_raw) else {
+        return false;
+    };
+    // EPERM means the process exists but belongs to another user
+    !matches!(rustix::process::test_kill_process(pid), Err(rustix::io::Errno::SRCH))
+}
+
+/// Processes cannot be checked on this platform; only `clean-temp --all` removes directories
+#[cfg(not(unix))]
+fn is_running(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temp_artifacts_are_tracked_and_removed() {
+        let base = tempfile::tempdir().unwrap();
+        let (root, audio_cache) = (base.path().join("tmp"), base.path().join("audio"));
+        let artifact = temp_dir(&root, "whisper").unwrap();
+        let path = artifact.path().to_path_buf();
+        assert!(path.is_dir());
+        assert_eq!(owner(&path.file_name().unwrap().to_string_lossy()), Some(std::process::id()));
+        drop(artifact);
+        assert!(!path.exists());
+        assert!(!registry().contains(&path));
+
+        // Directories of this process are in use; ones of a finished process are leftovers
+        let live = temp_dir(&root, "tempo_variants").unwrap();
+        let crashed = root.join(format!("whisper-{}-abc123", u32::MAX));
+        std::fs::create_dir(&crashed).unwrap();
+        std::fs::create_dir_all(audio_cache.join("tempo_variantsX1y2")).unwrap();
+        std::fs::write(audio_cache.join("0123abcd.wav"), b"").unwrap();
+
+        let found = leftovers(&root, &audio_cache, false);
+        if cfg!(unix) {
+            assert_eq!(found, vec![audio_cache.join("tempo_variantsX1y2"), crashed.clone()]);
+        }
+        assert!(leftovers(&root, &audio_cache, true).contains(&live.path().to_path_buf()));
+        assert_eq!(remove_leftovers(&found), found);
+        assert!(!crashed.exists() && live.path().exists());
+    }
+}
@@ -0,0 +1,63 @@
+// Shared cue timeline
+//
+// Translations into several languages of the same video are watched side by side during review
+// and switched between mid-playback, which only works when every language shows its cues at the
+// same moments. Timing is therefore decided once per file, before any language is written: the
+// final transcription is the timeline, shot snapping moves its cues once, and each language only
+// substitutes its texts into that timeline. Translators that regroup cues into sentences (`llm`
+// mode) keep their own timing, since their cues no longer correspond to the timeline's.
+
+use crate::error::{Result, ShuroError};
+use crate::metadata::DELIVERY;
+use crate::quality::{Transcription, TranscriptionSegment};
+
+/// `timeline` with the texts of `translated`, which must line up with its cues one-to-one
+pub fn substitute_texts(timeline: &Transcription, translated: Vec<TranscriptionSegment>) -> Result<Transcription> {
+    if translated.len() != timeline.segments.len() {
+        return Err(ShuroError::Translation(format!(
+            "Translated {} segments but the timeline has {}",
+            translated.len(),
+            timeline.segments.len()
+        )));
+    }
+
+    let mut result = timeline.clone();
+    for (segment, translated_segment) in result.segments.iter_mut().zip(translated) {
+        segment.text = translated_segment.text;
+        // Delivery hints are measured on the streamed segments only
+        if let Some(delivery) = translated_segment.metadata.get(&DELIVERY) {
+            segment.metadata.insert(&DELIVERY, delivery);
+        }
+    }
+    result.text = result.segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" ");
+    Ok(result)
+}
+
+/// Whether two transcriptions show their cues at exactly the same times
+pub fn same_timing(a: &Transcription, b: &Transcription) -> bool {
+    a.segments.len() == b.segments.len()
+        && a.segments.iter().zip(&b.segments).all(|(a, b)| a.start == b.start && a.end == b.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delivery::Delivery;
+    use crate::subtitle::parse_srt;
+
+    #[test]
+    fn test_substitute_texts() {
+        let timeline = parse_srt("1\n00:00:01,000 --> 00:00:02,500\nHello\n\n2\n00:00:03,000 --> 00:00:04,000\nBye\n", "en").unwrap();
+        let mut translated = parse_srt("1\n00:00:01,100 --> 00:00:02,400\nこんにちは\n\n2\n00:00:03,000 --> 00:00:04,200\nさようなら\n", "ja").unwrap();
+        translated.segments[1].metadata.insert(&DELIVERY, Delivery::Shouting);
+        assert!(!same_timing(&timeline, &translated));
+
+        let result = substitute_texts(&timeline, translated.segments.clone()).unwrap();
+        assert!(same_timing(&timeline, &result));
+        assert_eq!(result.text, "こんにちは さようなら");
+        assert_eq!(result.segments[1].metadata.get(&DELIVERY), Some(Delivery::Shouting));
+
+        translated.segments.pop();
+        assert!(substitute_texts(&timeline, translated.segments).is_err());
+    }
+}
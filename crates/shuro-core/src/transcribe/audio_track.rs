@@ -0,0 +1,251 @@
+// Audio track selection by spoken language
+//
+// Videos with several audio tracks (dubs, commentary) are transcribed from the track in the
+// source language. A short window from the middle of every track is sampled and whisper
+// identifies its language with a small model (`language_id_model`, `--detect-language`). The
+// track detected in the source language with the highest probability wins, then the first track
+// tagged with it in the container, then the first track. Setting `audio_track` skips detection.
+//
+// The same identification on the extracted audio tells the language of a video before it is
+// transcribed, so translators are created for the right source language and targets in the
+// spoken language are skipped before any translation is requested.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::cancel::Cancellation;
+use crate::config::TranscriberConfig;
+use crate::error::{Result, ShuroError};
+use crate::media::{MediaInfo, StreamKind, probe_media, same_language};
+use crate::platform;
+use crate::temp;
+
+/// Audio track of a video with its container tag and detected language
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioTrack {
+    /// Position among the video's audio tracks
+    pub index: usize,
+    /// Language tag stored in the container
+    pub tag: Option<String>,
+    /// Language identified by whisper and its probability
+    pub detected: Option<(String, f64)>,
+}
+
+/// Chooses the audio track to transcribe, remembering the choice per video
+pub struct AudioTrackSelector {
+    config: TranscriberConfig,
+    selected: Mutex<HashMap<PathBuf, Option<usize>>>,
+}
+
+impl AudioTrackSelector {
+    pub fn new(config: TranscriberConfig) -> Self {
+        Self {
+            config,
+            selected: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Track chosen for `video_path` without running language identification, `None` while
+    /// identification is still needed; the inner `None` means the default audio stream
+    pub async fn known(&self, video_path: &Path) -> Result<Option<Option<usize>>> {
+        if let Some(track) = self.config.audio_track {
+            return Ok(Some(Some(track)));
+        }
+        if let Some(track) = self.selected.lock().expect("audio track lock poisoned").get(video_path) {
+            return Ok(Some(*track));
+        }
+        let (tracks, _) = self.probe(video_path).await?;
+        Ok((tracks.len() <= 1).then_some(None))
+    }
+
+    /// Audio track to transcribe from `video_path` (`None` for the default audio stream)
+    pub async fn select(&self, video_path: &Path, cancel: &Cancellation) -> Result<Option<usize>> {
+        if let Some(track) = self.config.audio_track {
+            info!("🔊 Using configured audio track {}", track);
+            return Ok(Some(track));
+        }
+        if let Some(track) = self.selected.lock().expect("audio track lock poisoned").get(video_path) {
+            return Ok(*track);
+        }
+
+        let (mut tracks, duration) = self.probe(video_path).await?;
+        let selected = if tracks.len() <= 1 {
+            None
+        } else {
+            info!("🔊 {} audio tracks found, identifying their languages", tracks.len());
+            let start = sample_start(duration, self.config.language_id_window as f64);
+            for track in &mut tracks {
+                match cancel.run(self.identify(video_path, track.index, start)).await {
+                    Ok(detected) => track.detected = detected,
+                    Err(e) if cancel.is_cancelled() => return Err(e),
+                    Err(e) => warn!("Language identification failed for audio track {}: {}", track.index, e),
+                }
+                info!("   • Track {} (tag {}): detected {}",
+                    track.index,
+                    track.tag.as_deref().unwrap_or("none"),
+                    track.detected.as_ref().map(|(l, p)| format!("{} (p = {:.2})", l, p)).unwrap_or_else(|| "nothing".to_string())
+                );
+            }
+
+            let (index, reason) = choose_track(&tracks, &self.config.source_language);
+            info!("🔊 Selected audio track {}: {} (set transcriber.audio_track to override)", index, reason);
+            Some(index)
+        };
+
+        self.selected.lock().expect("audio track lock poisoned").insert(video_path.to_path_buf(), selected);
+        Ok(selected)
+    }
+
+    /// Language spoken in the extracted audio at `audio_path` and its probability, identified on
+    /// a window from its middle
+    pub async fn identify_language(&self, audio_path: &Path, cancel: &Cancellation) -> Result<Option<(String, f64)>> {
+        let (_, duration) = self.probe(audio_path).await?;
+        let start = sample_start(duration, self.config.language_id_window as f64);
+        cancel.run(self.identify(audio_path, 0, start)).await
+    }
+
+    /// Audio tracks of a media file and its duration in seconds, as reported by ffprobe
+    async fn probe(&self, video_path: &Path) -> Result<(Vec<AudioTrack>, Option<f64>)> {
+        let info = probe_media(&self.config.ffprobe_path, video_path).await?;
+        Ok((audio_tracks(&info), info.duration))
+    }
+
+    /// Sample a window of one track and identify its language with whisper
+    async fn identify(&self, video_path: &Path, track: usize, start: f64) -> Result<Option<(String, f64)>> {
+        let temp_dir = temp::temp_dir(Path::new(&self.config.temp_dir), "language_id")
+            .map_err(|e| ShuroError::Transcriber(format!("Failed to create temp directory: {}", e)))?;
+        let sample_path = temp_dir.path().join("sample.wav");
+
+        let output = platform::command(&self.config.ffmpeg_path)?
+            .arg("-ss").arg(format!("{:.3}", start))
+            .arg("-t").arg(self.config.language_id_window.to_string())
+            .arg("-i").arg(platform::process_path(video_path))
+            .arg("-map").arg(format!("0:a:{}", track))
+            .arg("-vn")
+            .arg("-acodec").arg("pcm_s16le")
+            .arg("-ar").arg("16000")
+            .arg("-ac").arg("1")
+            .arg("-y")
+            .arg(platform::process_path(&sample_path))
+            .output()
+            .await
+            .map_err(|e| ShuroError::Transcriber(format!("Failed to execute ffmpeg: {}", e)))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(ShuroError::Transcriber(format!("Audio sampling failed: {}", stderr)));
+        }
+
+        let output = platform::command(&self.config.binary_path)?
+            .arg("-f").arg(platform::process_path(&sample_path))
+            .arg("-m").arg(platform::process_path(Path::new(&self.config.language_id_model)))
+            .arg("-l").arg("auto")
+            .arg("--detect-language")
+            .output()
+            .await
+            .map_err(|e| ShuroError::Transcriber(format!("Failed to execute whisper: {}", e)))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(ShuroError::Transcriber(format!("Whisper language detection failed: {}", stderr)));
+        }
+
+        let log = format!("{}{}", String::from_utf8_lossy(&output.stderr), String::from_utf8_lossy(&output.stdout));
+        Ok(parse_detected_language(&log))
+    }
+}
+
+/// Audio tracks of a probed media file, without detected languages
+fn audio_tracks(info: &MediaInfo) -> Vec<AudioTrack> {
+    info.streams_of(StreamKind::Audio).map(|stream| AudioTrack {
+        index: stream.position,
+        tag: stream.language.clone(),
+        detected: None,
+    }).collect()
+}
+
+/// Language reported by `whisper-cli --detect-language`
+fn parse_detected_language(log: &str) -> Option<(String, f64)> {
+    let line = log.lines().find_map(|l| l.split_once("auto-detected language:").map(|(_, rest)| rest))?;
+    let (language, probability) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+    let probability = probability
+        .trim()
+        .trim_start_matches("(p =")
+        .trim_end_matches(')')
+        .trim()
+        .parse()
+        .unwrap_or(0.0);
+    (!language.is_empty()).then(|| (language.to_string(), probability))
+}
+
+/// Start of the sample window, centred in the file when its duration is known
+fn sample_start(duration: Option<f64>, window: f64) -> f64 {
+    duration.map(|d| ((d - window) / 2.0).max(0.0)).unwrap_or(0.0)
+}
+
+/// Track to transcribe for the source language, with the reason it was chosen
+fn choose_track(tracks: &[AudioTrack], source_language: &str) -> (usize, String) {
+    let detected = tracks.iter()
+        .filter(|t| t.detected.as_ref().is_some_and(|(l, _)| same_language(l, source_language)))
+        .max_by(|a, b| {
+            let (pa, pb) = (a.detected.as_ref().map(|d| d.1), b.detected.as_ref().map(|d| d.1));
+            pa.partial_cmp(&pb).unwrap_or(std::cmp::Ordering::Equal).then(b.index.cmp(&a.index))
+        });
+    if let Some(track) = detected {
+        return (track.index, format!("detected language matches source language {}", source_language));
+    }
+    if let Some(track) = tracks.iter().find(|t| t.tag.as_deref().is_some_and(|l| same_language(l, source_language))) {
+        return (track.index, format!("no track detected as {}, using the track tagged with it", source_language));
+    }
+    (0, format!("no track detected or tagged as {}, using the first track", source_language))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::parse_media_info;
+
+    fn track(index: usize, tag: Option<&str>, detected: Option<(&str, f64)>) -> AudioTrack {
+        AudioTrack {
+            index,
+            tag: tag.map(str::to_string),
+            detected: detected.map(|(l, p)| (l.to_string(), p)),
+        }
+    }
+
+    #[test]
+    fn test_parse_detected_language() {
+        let log = "whisper_init_from_file: loading model\nwhisper_full_with_state: auto-detected language: ja (p = 0.948195)\n";
+        assert_eq!(parse_detected_language(log), Some(("ja".to_string(), 0.948195)));
+        assert_eq!(parse_detected_language("no detection here"), None);
+    }
+
+    #[test]
+    fn test_parse_audio_tracks() {
+        let json = r#"{"streams": [
+            {"index": 0, "codec_type": "video"},
+            {"index": 1, "codec_type": "audio", "tags": {"language": "eng"}},
+            {"index": 2, "codec_type": "audio"}
+        ], "format": {"duration": "1440.5"}}"#;
+        let info = parse_media_info(json).unwrap();
+        assert_eq!(audio_tracks(&info), vec![track(0, Some("eng"), None), track(1, None, None)]);
+        let duration = info.duration;
+        assert_eq!(duration, Some(1440.5));
+        assert_eq!(sample_start(duration, 30.0), 705.25);
+    }
+
+    #[test]
+    fn test_choose_track() {
+        let tracks = vec![
+            track(0, Some("eng"), Some(("en", 0.9))),
+            track(1, Some("jpn"), Some(("ja", 0.7))),
+            track(2, None, Some(("ja", 0.95))),
+        ];
+        assert_eq!(choose_track(&tracks, "ja").0, 2);
+        assert_eq!(choose_track(&tracks, "en").0, 0);
+
+        let undetected = vec![track(0, Some("eng"), None), track(1, Some("jpn"), None)];
+        assert_eq!(choose_track(&undetected, "ja").0, 1);
+        assert_eq!(choose_track(&undetected, "fr").0, 0);
+    }
+}
@@ -0,0 +1,179 @@
+// Chunked transcription
+//
+// One whisper process works through a multi-hour file on a single core group while the rest of
+// the machine idles. With `transcriber.chunk_minutes` set, the final whisper pass of the
+// whisper_cpp and whisper_server transcribers cuts audio longer than that into chunks of
+// `chunk_minutes`, each running `OVERLAP_SECONDS` into the next so words cut at a chunk end are
+// heard whole in the next chunk. Up to `transcribe_workers` chunks are transcribed at once.
+//
+// Chunks are stitched back in order: each chunk keeps the segments centred before the middle of
+// its overlap with the next chunk, and a segment repeating the text of the one before it across
+// the cut is dropped. Segments are streamed chunk by chunk as soon as every chunk before them is
+// done. Tempo exploration runs on samples and is never chunked.
+
+use std::path::Path;
+
+use crate::config::TranscriberConfig;
+use crate::error::{Result, ShuroError};
+use crate::platform;
+use crate::quality::{Transcription, TranscriptionSegment};
+
+/// Seconds consecutive chunks share
+const OVERLAP_SECONDS: f64 = 15.0;
+
+impl TranscriberConfig {
+    /// Part of transcription cache keys, so chunked and whole-file transcriptions are cached apart
+    pub fn chunk_key(&self) -> Option<String> {
+        (self.chunk_minutes > 0).then(|| format!("chunks{}m", self.chunk_minutes))
+    }
+}
+
+/// Stretch of the audio transcribed by one whisper process, in seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioChunk {
+    pub start: f64,
+    pub length: f64,
+}
+
+/// Chunks of `chunk_seconds` (plus the overlap) covering audio of `duration` seconds; empty when
+/// chunking is off or the audio fits in one chunk
+pub fn plan_chunks(duration: f64, chunk_seconds: f64) -> Vec<AudioChunk> {
+    if chunk_seconds <= 0.0 || duration <= chunk_seconds + OVERLAP_SECONDS {
+        return Vec::new();
+    }
+    // A last chunk shorter than the overlap is covered by the one before it
+    let count = ((duration - OVERLAP_SECONDS) / chunk_seconds).ceil() as usize;
+    (0..count)
+        .map(|i| {
+            let start = i as f64 * chunk_seconds;
+            AudioChunk { start, length: (chunk_seconds + OVERLAP_SECONDS).min(duration - start) }
+        })
+        .collect()
+}
+
+/// Cut `chunk` out of the extracted audio into `chunk_path`
+pub async fn cut_chunk(audio_path: &Path, chunk_path: &Path, ffmpeg_path: &str, chunk: &AudioChunk) -> Result<()> {
+    let output = platform::command(ffmpeg_path)?
+        .arg("-ss").arg(format!("{:.3}", chunk.start))
+        .arg("-t").arg(format!("{:.3}", chunk.length))
+        .arg("-i").arg(platform::process_path(audio_path))
+        .arg("-acodec").arg("pcm_s16le")
+        .arg("-ar").arg("16000")
+        .arg("-ac").arg("1")
+        .arg("-y")
+        .arg(platform::process_path(chunk_path))
+        .output()
+        .await
+        .map_err(|e| ShuroError::Transcriber(format!("Failed to execute ffmpeg: {}", e)))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ShuroError::Transcriber(format!("Audio chunking failed: {}", stderr)));
+    }
+    Ok(())
+}
+
+/// Joins the transcriptions of consecutive chunks into one timeline
+#[derive(Debug, Default)]
+pub struct ChunkStitcher {
+    /// Time from which the next chunk owns the segments
+    owned_from: f64,
+    last: Option<TranscriptionSegment>,
+    next_id: i32,
+}
+
+impl ChunkStitcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take the transcription of `chunk`, the chunk after it being `next`; returns the segments
+    /// it adds to the timeline, in audio time
+    pub fn push(&mut self, chunk: &AudioChunk, next: Option<&AudioChunk>, transcription: Transcription) -> Vec<TranscriptionSegment> {
+        let owned_until = next.map_or(f64::INFINITY, |n| n.start + OVERLAP_SECONDS / 2.0);
+        let mut added = Vec::new();
+        for mut segment in transcription.segments {
+            shift(&mut segment, chunk.start);
+            let middle = (segment.start + segment.end) / 2.0;
+            if middle < self.owned_from || middle >= owned_until || self.repeats_last(&segment) {
+                continue;
+            }
+            segment.id = self.next_id;
+            self.next_id += 1;
+            self.last = Some(segment.clone());
+            added.push(segment);
+        }
+        self.owned_from = owned_until;
+        added
+    }
+
+    /// Whether `segment` overlaps the last kept segment and says the same
+    fn repeats_last(&self, segment: &TranscriptionSegment) -> bool {
+        self.last.as_ref().is_some_and(|last| {
+            let (a, b) = (comparable(&last.text), comparable(&segment.text));
+            segment.start < last.end && !b.is_empty() && (a.contains(&b) || b.contains(&a))
+        })
+    }
+}
+
+/// Move a segment and its words `offset` seconds later
+fn shift(segment: &mut TranscriptionSegment, offset: f64) {
+    segment.start += offset;
+    segment.end += offset;
+    for word in &mut segment.words {
+        word.start += offset;
+        word.end += offset;
+    }
+}
+
+/// Lowercased letters and digits of a text, to compare texts differing in punctuation
+fn comparable(text: &str) -> String {
+    text.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::parse_srt;
+
+    #[test]
+    fn test_stitch_chunks() {
+        assert!(plan_chunks(600.0, 0.0).is_empty());
+        assert!(plan_chunks(610.0, 600.0).is_empty());
+        let chunks = plan_chunks(1810.0, 600.0);
+        assert_eq!(chunks, vec![
+            AudioChunk { start: 0.0, length: 615.0 },
+            AudioChunk { start: 600.0, length: 615.0 },
+            AudioChunk { start: 1200.0, length: 610.0 },
+        ]);
+
+        // Chunk times; the overlap of the first two chunks is 600-615, cut at 607.5
+        let first = parse_srt(
+            "1\n00:09:50,000 --> 00:09:58,000\nThe ferry leaves at nine.\n\n\
+             2\n00:10:03,000 --> 00:10:09,000\nDon't miss it\n\n\
+             3\n00:10:10,000 --> 00:10:14,000\nor you stay\n",
+            "en",
+        ).unwrap();
+        let second = parse_srt(
+            "1\n00:00:00,000 --> 00:00:01,000\nat nine.\n\n\
+             2\n00:00:07,000 --> 00:00:09,000\nDon't miss it!\n\n\
+             3\n00:00:10,000 --> 00:00:14,000\nor you stay here.\n\n\
+             4\n00:10:05,000 --> 00:10:10,000\nUntil Monday.\n",
+            "en",
+        ).unwrap();
+        let mut stitcher = ChunkStitcher::new();
+        let mut timeline = stitcher.push(&chunks[0], Some(&chunks[1]), first);
+        timeline.extend(stitcher.push(&chunks[1], Some(&chunks[2]), second));
+        let cues: Vec<(f64, &str)> = timeline.iter().map(|s| (s.start, s.text.as_str())).collect();
+        // "Don't miss it" is centred at 606 and kept from the first chunk; its repeat centred at
+        // 608 in the second chunk is dropped; "or you stay" comes from the second chunk, which
+        // heard it whole
+        assert_eq!(cues, vec![
+            (590.0, "The ferry leaves at nine."),
+            (603.0, "Don't miss it"),
+            (610.0, "or you stay here."),
+        ]);
+        assert!(timeline.iter().enumerate().all(|(i, s)| s.id == i as i32));
+        // The second chunk owns its audio up to the middle of the overlap with the third
+        assert_eq!(stitcher.owned_from, 1207.5);
+    }
+}
@@ -1,12 +1,15 @@
+use std::io::Write;
 use std::path::Path;
-use std::process::Command;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
-use serde::{Serialize, Deserialize};
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
 use tracing::info;
 
+use crate::config::HallucinationConfig;
 use crate::error::{Result, ShuroError};
-use crate::quality::{Transcription, TranscriptionSegment};
+use crate::platform;
+use crate::metadata::{SegmentMetadata, CONFIDENCE, SOURCE_LANGUAGE};
+use crate::quality::{Transcription, TranscriptionSegment, WordTiming};
 
 /// Abstract transcription segment that is service-agnostic
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +20,11 @@ pub struct AbstractTranscriptionSegment {
     pub text: String,
     pub confidence: Option<f32>,
     pub language: Option<String>,
+    #[serde(default)]
+    pub words: Vec<WordTiming>,
+    /// Entries beyond confidence and language, e.g. the speaker reported by a plugin
+    #[serde(default)]
+    pub metadata: SegmentMetadata,
 }
 
 /// Abstract transcription result that is service-agnostic
@@ -43,24 +51,38 @@ impl From<AbstractTranscription> for Transcription {
     fn from(abstract_transcription: AbstractTranscription) -> Self {
         let segments = abstract_transcription.segments
             .into_iter()
-            .map(|seg| TranscriptionSegment {
-                id: seg.id,
-                start: seg.start_time,
-                end: seg.end_time,
-                text: seg.text,
-                tokens: vec![], // Not always available
-                temperature: 0.0, // Default value
-                avg_logprob: seg.confidence.unwrap_or(0.0),
-                compression_ratio: 1.0, // Default value
-                no_speech_prob: 1.0 - seg.confidence.unwrap_or(0.5), // Inverse of confidence
+            .map(|seg| {
+                let mut metadata = seg.metadata;
+                if let Some(confidence) = seg.confidence {
+                    metadata.insert(&CONFIDENCE, confidence as f64);
+                }
+                let language = seg.language.unwrap_or_else(|| abstract_transcription.language.clone());
+                metadata.insert(&SOURCE_LANGUAGE, language);
+
+                TranscriptionSegment {
+                    id: seg.id,
+                    start: seg.start_time,
+                    end: seg.end_time,
+                    text: seg.text,
+                    tokens: vec![], // Not always available
+                    temperature: 0.0, // Default value
+                    avg_logprob: seg.confidence.unwrap_or(0.0),
+                    compression_ratio: 1.0, // Default value
+                    no_speech_prob: 1.0 - seg.confidence.unwrap_or(0.5), // Inverse of confidence
+                    words: seg.words,
+                    metadata,
+                }
             })
             .collect();
 
-        Transcription {
+        let mut transcription = Transcription {
             text: abstract_transcription.text,
             segments,
             language: abstract_transcription.language,
-        }
+        };
+        // Default rules; the workflow flags again with the configured ones
+        transcription.flag_hallucinations(&HallucinationConfig::default());
+        transcription
     }
 }
 
@@ -174,6 +196,34 @@ impl WhisperUtils {
         Ok(metadata.len())
     }
 
+    /// Parse a JSON file (whisper output, cache entries) without reading it into a string first
+    ///
+    /// Transcriptions of 10+ hour recordings run to hundreds of MB; parsing from a buffered reader
+    /// on a blocking thread keeps only the parsed value in memory.
+    pub async fn read_json<T: DeserializeOwned + Send + 'static>(path: &Path) -> Result<T> {
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            let file = std::fs::File::open(&path)?;
+            Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+        })
+        .await
+        .map_err(|e| ShuroError::Cache(format!("Failed to read JSON: {}", e)))?
+    }
+
+    /// Write `value` as compact JSON, serialized straight into the file
+    ///
+    /// The file is replaced through a temporary file, so an interrupted write never leaves a
+    /// truncated cache entry behind.
+    pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+        let temporary = path.with_extension("json.tmp");
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(&temporary)?);
+        serde_json::to_writer(&mut writer, value)?;
+        writer.flush()?;
+        drop(writer);
+        std::fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
     /// Clean old cache entries based on age
     pub async fn clean_cache_by_age<P: AsRef<Path>>(
         cache_dir: P,
@@ -191,20 +241,15 @@ impl WhisperUtils {
         
         if let Ok(mut entries) = tokio::fs::read_dir(cache_dir).await {
             while let Ok(Some(entry)) = entries.next_entry().await {
-                if let Some(extension) = entry.path().extension() {
-                    if extension == file_extension {
-                        if let Ok(metadata) = entry.metadata().await {
-                            if let Ok(modified) = metadata.modified() {
-                                if let Ok(duration) = modified.duration_since(std::time::UNIX_EPOCH) {
-                                    let age_seconds = current_time.saturating_sub(duration.as_secs());
-                                    if age_seconds > max_age_seconds {
-                                        if tokio::fs::remove_file(entry.path()).await.is_ok() {
-                                            removed_count += 1;
-                                        }
-                                    }
-                                }
-                            }
-                        }
+                if let Some(extension) = entry.path().extension()
+                    && extension == file_extension
+                    && let Ok(metadata) = entry.metadata().await
+                    && let Ok(modified) = metadata.modified()
+                    && let Ok(duration) = modified.duration_since(std::time::UNIX_EPOCH) {
+                    let age_seconds = current_time.saturating_sub(duration.as_secs());
+                    if age_seconds > max_age_seconds
+                        && tokio::fs::remove_file(entry.path()).await.is_ok() {
+                        removed_count += 1;
                     }
                 }
             }
@@ -226,21 +271,19 @@ impl WhisperUtils {
         
         if let Ok(mut entries) = tokio::fs::read_dir(cache_dir).await {
             while let Ok(Some(entry)) = entries.next_entry().await {
-                if let Some(extension) = entry.path().extension() {
-                    if extension == file_extension {
-                        total_files += 1;
+                if let Some(extension) = entry.path().extension()
+                    && extension == file_extension {
+                    total_files += 1;
                         
-                        if let Ok(metadata) = entry.metadata().await {
-                            total_size += metadata.len();
+                    if let Ok(metadata) = entry.metadata().await {
+                        total_size += metadata.len();
                             
-                            if let Ok(modified) = metadata.modified() {
-                                if let Ok(duration) = modified.duration_since(std::time::UNIX_EPOCH) {
-                                    let timestamp = duration.as_secs();
+                        if let Ok(modified) = metadata.modified()
+                            && let Ok(duration) = modified.duration_since(std::time::UNIX_EPOCH) {
+                            let timestamp = duration.as_secs();
                                     
-                                    oldest_entry = Some(oldest_entry.map_or(timestamp, |o| o.min(timestamp)));
-                                    newest_entry = Some(newest_entry.map_or(timestamp, |n| n.max(timestamp)));
-                                }
-                            }
+                            oldest_entry = Some(oldest_entry.map_or(timestamp, |o| o.min(timestamp)));
+                            newest_entry = Some(newest_entry.map_or(timestamp, |n| n.max(timestamp)));
                         }
                     }
                 }
@@ -251,11 +294,12 @@ impl WhisperUtils {
     }
 }
 
-/// Extract audio from video using ffmpeg
+/// Extract audio from video using ffmpeg, from the given audio track or the default one
 pub async fn extract_audio<P: AsRef<Path>>(
     video_path: P,
     audio_path: P,
     ffmpeg_path: &str,
+    audio_track: Option<usize>,
     original_file_name: Option<&str>,
 ) -> Result<()> {
     let video_path = video_path.as_ref();
@@ -269,15 +313,20 @@ pub async fn extract_audio<P: AsRef<Path>>(
     
     info!("{}", log_message);
 
-    let output = Command::new(ffmpeg_path)
-        .arg("-i").arg(video_path)
+    let mut cmd = platform::command(ffmpeg_path)?;
+    cmd.arg("-i").arg(platform::process_path(video_path));
+    if let Some(track) = audio_track {
+        cmd.arg("-map").arg(format!("0:a:{}", track));
+    }
+    let output = cmd
         .arg("-vn") // No video
         .arg("-acodec").arg("pcm_s16le") // PCM 16-bit for whisper
         .arg("-ar").arg("16000") // 16kHz sample rate
         .arg("-ac").arg("1") // Mono
         .arg("-y") // Overwrite output
-        .arg(audio_path)
+        .arg(platform::process_path(audio_path))
         .output()
+        .await
         .map_err(|e| ShuroError::Transcriber(format!("Failed to execute ffmpeg: {}", e)))?;
 
     if !output.status.success() {
@@ -292,53 +341,81 @@ pub async fn extract_audio<P: AsRef<Path>>(
     Ok(())
 }
 
-/// Extract audio with specific tempo adjustment using ffmpeg
-pub async fn extract_audio_with_tempo<P: AsRef<Path>>(
-    video_path: P,
+/// Derive a tempo-adjusted copy of already extracted audio using ffmpeg
+///
+/// With `windows` (start and length in seconds of the source audio), only those parts are
+/// converted, joined back to back.
+pub async fn derive_tempo_audio<P: AsRef<Path>>(
+    source_audio: P,
     audio_path: P,
     ffmpeg_path: &str,
     tempo_percentage: i32,
-    original_file_name: Option<&str>,
+    windows: &[(f64, f64)],
 ) -> Result<()> {
-    let video_path = video_path.as_ref();
+    let source_audio = source_audio.as_ref();
     let audio_path = audio_path.as_ref();
 
-    let log_message = if let Some(original_name) = original_file_name {
-        format!("Extracting audio from {} with tempo {}%", original_name, tempo_percentage)
-    } else {
-        format!("Extracting audio from {} to {} with tempo {}%", 
-                video_path.display(), audio_path.display(), tempo_percentage)
-    };
-    
-    info!("{}", log_message);
-
     // Convert percentage to ffmpeg atempo value (e.g., 110% -> 1.1, 80% -> 0.8)
     let tempo_factor = tempo_percentage as f64 / 100.0;
-    
-    let output = Command::new(ffmpeg_path)
-        .arg("-i").arg(video_path)
-        .arg("-vn") // No video
+
+    let output = platform::command(ffmpeg_path)?
+        .arg("-i").arg(platform::process_path(source_audio))
         .arg("-acodec").arg("pcm_s16le") // PCM 16-bit for whisper
         .arg("-ar").arg("16000") // 16kHz sample rate
         .arg("-ac").arg("1") // Mono
-        .arg("-af").arg(format!("atempo={}", tempo_factor)) // Apply tempo adjustment
+        .arg("-af").arg(tempo_filter(tempo_factor, windows)) // Apply tempo adjustment
         .arg("-y") // Overwrite output
-        .arg(audio_path)
+        .arg(platform::process_path(audio_path))
         .output()
+        .await
         .map_err(|e| ShuroError::Transcriber(format!("Failed to execute ffmpeg: {}", e)))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         return Err(ShuroError::Transcriber(format!(
-            "Audio extraction with tempo adjustment failed: {}",
+            "Tempo adjustment failed: {}",
             stderr
         )));
     }
 
-    info!("Audio extraction with tempo adjustment completed");
     Ok(())
 }
 
+/// Duration in seconds of a 16 kHz mono 16-bit WAV file, estimated from its size
+pub fn wav_duration(file_size: u64) -> f64 {
+    const WAV_HEADER_BYTES: u64 = 44;
+    const BYTES_PER_SECOND: f64 = 16000.0 * 2.0;
+    file_size.saturating_sub(WAV_HEADER_BYTES) as f64 / BYTES_PER_SECOND
+}
+
+/// `count` windows (start, length) of `seconds` each, spread evenly across audio of `duration`
+/// seconds; empty when sampling is off or the windows would cover the whole audio
+pub fn sample_windows(duration: f64, count: u32, seconds: u32) -> Vec<(f64, f64)> {
+    let length = seconds as f64;
+    if count == 0 || seconds == 0 || count as f64 * length >= duration {
+        return Vec::new();
+    }
+    (0..count)
+        .map(|i| {
+            let centre = (i as f64 + 0.5) * duration / count as f64;
+            ((centre - length / 2.0).clamp(0.0, duration - length), length)
+        })
+        .collect()
+}
+
+/// Audio filter applying `tempo_factor`, after keeping only `windows` of the input when given
+fn tempo_filter(tempo_factor: f64, windows: &[(f64, f64)]) -> String {
+    if windows.is_empty() {
+        return format!("atempo={}", tempo_factor);
+    }
+    let selection = windows
+        .iter()
+        .map(|(start, length)| format!("between(t,{:.3},{:.3})", start, start + length))
+        .collect::<Vec<_>>()
+        .join("+");
+    format!("aselect='{}',asetpts=N/SR/TB,atempo={}", selection, tempo_factor)
+}
+
 /// Calculate segment smoothness score - lower score means more evenly distributed segments
 pub fn calculate_segment_smoothness(transcription: &Transcription) -> f64 {
     if transcription.segments.len() < 2 {
@@ -397,4 +474,44 @@ pub fn generate_tempo_range(min_tempo: i32, max_tempo: i32, steps: i32) -> Vec<i
 /// Format duration in seconds to a human-readable string
 pub fn format_duration(seconds: u64) -> String {
     WhisperUtils::format_duration(seconds)
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_windows() {
+        assert_eq!(wav_duration(44 + 32000 * 90), 90.0);
+        assert_eq!(sample_windows(1800.0, 3, 120), vec![(240.0, 120.0), (840.0, 120.0), (1440.0, 120.0)]);
+        assert!(sample_windows(300.0, 3, 120).is_empty());
+        assert!(sample_windows(1800.0, 0, 120).is_empty());
+
+        assert_eq!(tempo_filter(1.1, &[]), "atempo=1.1");
+        assert_eq!(
+            tempo_filter(0.9, &[(240.0, 120.0), (840.0, 120.0)]),
+            "aselect='between(t,240.000,360.000)+between(t,840.000,960.000)',asetpts=N/SR/TB,atempo=0.9"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_json_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tuned_abc.json");
+        let result = TuneResult {
+            best_transcription: crate::subtitle::parse_srt("1\n00:00:01,000 --> 00:00:02,000\nHello\n", "en").unwrap(),
+            best_tempo: 110,
+            best_temperature: 0.0,
+            quality_score: 0.5,
+            all_attempts: vec![(100, 0.7), (110, 0.5)],
+            tested_parameters: vec!["tempo=110%".to_string()],
+        };
+        WhisperUtils::write_json(&path, &result).unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains('\n'));
+        assert!(!path.with_extension("json.tmp").exists());
+
+        let read: TuneResult = WhisperUtils::read_json(&path).await.unwrap();
+        assert_eq!(read.best_transcription.segments[0].text, "Hello");
+        assert_eq!(read.all_attempts, result.all_attempts);
+        assert!(WhisperUtils::read_json::<TuneResult>(&dir.path().join("missing.json")).await.is_err());
+    }
+}
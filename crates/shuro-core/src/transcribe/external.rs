@@ -0,0 +1,137 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use crate::cache;
+use crate::cancel::Cancellation;
+use crate::config::{ExternalPluginConfig, TranscriberConfig};
+use crate::error::{Result, ShuroError};
+use crate::plugin;
+use crate::quality::Transcription;
+use super::{TranscriberTrait, TuneResult, TranscriptionCache, AudioCache, CacheInfo, common::{WhisperUtils, AbstractTranscription}};
+
+#[derive(Debug, Serialize)]
+struct ExternalTranscribeRequest<'a> {
+    audio_path: String,
+    language: Option<&'a str>,
+    model: &'a str,
+    temperature: f32,
+}
+
+/// Transcriber backed by an external executable speaking the plugin protocol
+///
+/// Audio extraction and caching stay in shuro; transcription caching is left to the plugin.
+pub struct ExternalTranscriber {
+    plugin: ExternalPluginConfig,
+    config: TranscriberConfig,
+    audio_cache_dir: PathBuf,
+}
+
+impl ExternalTranscriber {
+    pub fn new(plugin: ExternalPluginConfig, config: TranscriberConfig) -> Self {
+        let audio_cache_dir = Path::new(&config.cache_dir).join("audio");
+
+        Self { plugin, config, audio_cache_dir }
+    }
+
+    fn audio_cache_path(&self, video_path: &Path) -> Result<PathBuf> {
+        let cache_key = WhisperUtils::generate_file_hash(video_path, &["audio_extraction"])?;
+        Ok(self.audio_cache_dir.join(format!("{}.wav", cache_key)))
+    }
+}
+
+#[async_trait]
+impl TranscriberTrait for ExternalTranscriber {
+    async fn transcribe(&self, audio_path: &Path, language: Option<&str>, cancel: &Cancellation) -> Result<Transcription> {
+        cancel.run(async {
+            info!("Transcribing {} via plugin '{}'", audio_path.display(), self.plugin.name);
+
+            let request = ExternalTranscribeRequest {
+                audio_path: audio_path.to_string_lossy().to_string(),
+                language,
+                model: &self.config.transcribe_model,
+                temperature: self.config.temperature,
+            };
+
+            let abstract_transcription: AbstractTranscription =
+                plugin::invoke(&self.plugin, &request, ShuroError::Transcriber).await?;
+
+            Ok(abstract_transcription.into())
+        }).await
+    }
+
+    async fn tune_transcription(&self, audio_path: &Path, cancel: &Cancellation) -> Result<TuneResult> {
+        // Plugins do not take part in tempo exploration: a single pass at the original tempo
+        let transcription = self.transcribe(audio_path, self.config.language.as_deref(), cancel).await?;
+
+        Ok(TuneResult {
+            best_transcription: transcription,
+            best_tempo: 100,
+            best_temperature: self.config.temperature,
+            quality_score: 1.0,
+            all_attempts: vec![(100, 1.0)],
+            tested_parameters: vec![format!("plugin-{}-single-pass", self.plugin.name)],
+        })
+    }
+
+    async fn extract_and_cache_audio(&self, video_path: &Path, cancel: &Cancellation) -> Result<PathBuf> {
+        cancel.run(async {
+            let audio_path = self.audio_cache_path(video_path)?;
+            WhisperUtils::ensure_directory(&self.audio_cache_dir).await?;
+
+            if audio_path.exists() {
+                cache::touch(&audio_path);
+                return Ok(audio_path);
+            }
+
+            let original_name = video_path.file_name().and_then(|n| n.to_str());
+            super::common::extract_audio(video_path, audio_path.as_path(), &self.config.ffmpeg_path, self.config.audio_track, original_name).await?;
+
+            Ok(audio_path)
+        }).await
+    }
+
+    async fn get_cached_audio(&self, video_path: &Path) -> Result<Option<PathBuf>> {
+        let audio_path = self.audio_cache_path(video_path)?;
+        Ok(audio_path.exists().then_some(audio_path))
+    }
+
+    async fn clear_cache(&self) -> Result<u64> {
+        Ok(0)
+    }
+
+    async fn list_cache(&self) -> Result<Vec<TranscriptionCache>> {
+        Ok(Vec::new())
+    }
+
+    async fn cache_info(&self) -> Result<CacheInfo> {
+        let (audio_files, audio_size, _, _) = WhisperUtils::get_cache_stats(&self.audio_cache_dir, "wav").await?;
+
+        Ok(CacheInfo {
+            total_files: 0,
+            total_size: 0,
+            audio_files,
+            audio_size,
+            oldest_entry: None,
+            newest_entry: None,
+            models_used: vec![self.plugin.name.clone()],
+        })
+    }
+
+    async fn clear_audio_cache(&self) -> Result<u64> {
+        let mut count = 0;
+        if let Ok(mut entries) = tokio::fs::read_dir(&self.audio_cache_dir).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                if tokio::fs::remove_file(entry.path()).await.is_ok() {
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    async fn list_audio_cache(&self) -> Result<Vec<AudioCache>> {
+        Ok(Vec::new())
+    }
+}
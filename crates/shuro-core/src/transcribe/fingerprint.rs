@@ -0,0 +1,178 @@
+// Audio fingerprint dedupe
+//
+// Transcription caches are keyed by the video file, so a 720p and a 1080p rip of the same episode
+// are transcribed twice. With `transcriber.fingerprint_dedupe`, tuned mode fingerprints the
+// extracted audio with chromaprint's `fpcalc` and records the fingerprint of every cached
+// transcription in `.shuro/cache/fingerprints/`. On a cache miss, a recorded transcription made
+// with the same settings whose audio has about the same length and a fingerprint at least
+// `fingerprint_similarity` similar is reused for the new file. Re-encoded audio does not yield
+// bit-identical fingerprints, so fingerprints are compared by the share of matching bits at the
+// best alignment within a few frames. Without `fpcalc` the step is skipped with a warning.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+use crate::error::{Result, ShuroError};
+use crate::platform;
+
+/// Fingerprint frames two fingerprints may be shifted against each other (about 0.12 s each)
+const MAX_OFFSET: usize = 8;
+
+/// Seconds the audio lengths of the same content may differ
+const MAX_DURATION_DIFFERENCE: f64 = 2.0;
+
+/// Chromaprint fingerprint of an audio file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioFingerprint {
+    pub duration: f64,
+    pub frames: Vec<u32>,
+}
+
+impl AudioFingerprint {
+    /// Fingerprint the whole audio file with `fpcalc`
+    pub async fn compute(fpcalc_path: &str, audio_path: &Path) -> Result<Self> {
+        let output = platform::command(fpcalc_path)?
+            .arg("-raw")
+            .arg("-length").arg("0")
+            .arg(platform::process_path(audio_path))
+            .output()
+            .await
+            .map_err(|e| ShuroError::Transcriber(format!("Failed to execute fpcalc: {}", e)))?;
+        if !output.status.success() {
+            return Err(ShuroError::Transcriber(format!(
+                "fpcalc failed: {}", String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        parse_fpcalc(&String::from_utf8_lossy(&output.stdout))
+            .ok_or_else(|| ShuroError::Transcriber("fpcalc printed no fingerprint".to_string()))
+    }
+
+    /// Share of matching bits at the best alignment, 0.0 for audio of different length
+    pub fn similarity(&self, other: &Self) -> f64 {
+        if (self.duration - other.duration).abs() > MAX_DURATION_DIFFERENCE {
+            return 0.0;
+        }
+        let (a, b) = (&self.frames, &other.frames);
+        (0..=MAX_OFFSET)
+            .flat_map(|offset| [(offset, 0), (0, offset)])
+            .filter_map(|(skip_a, skip_b)| {
+                let pairs: Vec<(&u32, &u32)> = a.iter().skip(skip_a).zip(b.iter().skip(skip_b)).collect();
+                if pairs.is_empty() {
+                    return None;
+                }
+                let differing: u32 = pairs.iter().map(|(x, y)| (*x ^ *y).count_ones()).sum();
+                Some(1.0 - differing as f64 / (pairs.len() * 32) as f64)
+            })
+            .fold(0.0, f64::max)
+    }
+}
+
+/// Fingerprint in the `DURATION=`/`FINGERPRINT=` output of `fpcalc -raw`
+fn parse_fpcalc(output: &str) -> Option<AudioFingerprint> {
+    let value = |key: &str| output.lines().find_map(|line| line.trim().strip_prefix(key));
+    let duration = value("DURATION=")?.parse().ok()?;
+    let frames = value("FINGERPRINT=")?
+        .split(',')
+        .map(|frame| frame.trim().parse::<i64>().ok().map(|v| v as u32))
+        .collect::<Option<Vec<u32>>>()?;
+    (!frames.is_empty()).then_some(AudioFingerprint { duration, frames })
+}
+
+/// Fingerprint of the audio behind one cached transcription
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FingerprintEntry {
+    /// File name of the video the transcription was made from
+    pub source: String,
+    /// Hash of the transcription settings the cache entry was made with
+    pub settings: String,
+    /// Cached transcription file
+    pub transcription: PathBuf,
+    pub fingerprint: AudioFingerprint,
+}
+
+/// Directory of fingerprint entries, one file per cached transcription
+pub struct FingerprintIndex {
+    dir: PathBuf,
+}
+
+impl FingerprintIndex {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Most similar entry made with `settings` reaching `threshold` whose transcription still exists
+    pub async fn find(&self, fingerprint: &AudioFingerprint, settings: &str, threshold: f64) -> Result<Option<FingerprintEntry>> {
+        let Ok(mut entries) = tokio::fs::read_dir(&self.dir).await else {
+            return Ok(None);
+        };
+        let mut best: Option<(f64, FingerprintEntry)> = None;
+        while let Some(file) = entries.next_entry().await? {
+            let Ok(content) = tokio::fs::read_to_string(file.path()).await else { continue };
+            let Ok(entry) = serde_json::from_str::<FingerprintEntry>(&content) else {
+                debug!("Skipping unreadable fingerprint entry {}", file.path().display());
+                continue;
+            };
+            if entry.settings != settings || !entry.transcription.exists() {
+                continue;
+            }
+            let similarity = fingerprint.similarity(&entry.fingerprint);
+            debug!("Audio of {} is {:.1}% similar", entry.source, similarity * 100.0);
+            if similarity >= threshold && best.as_ref().is_none_or(|(s, _)| similarity > *s) {
+                best = Some((similarity, entry));
+            }
+        }
+        if let Some((similarity, entry)) = &best {
+            info!("🔉 Audio matches {} ({:.1}% similar fingerprint)", entry.source, similarity * 100.0);
+        }
+        Ok(best.map(|(_, entry)| entry))
+    }
+
+    /// Record the fingerprint of a cached transcription
+    pub async fn record(&self, entry: &FingerprintEntry) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await
+            .map_err(|e| ShuroError::Cache(format!("Failed to create fingerprint directory: {}", e)))?;
+        let name = entry.transcription.file_stem().unwrap_or_default().to_string_lossy();
+        let path = self.dir.join(format!("{}.json", name));
+        tokio::fs::write(&path, serde_json::to_string(entry)?).await
+            .map_err(|e| ShuroError::Cache(format!("Failed to write fingerprint entry: {}", e)))?;
+        Ok(())
+    }
+}
+
+/// Hash of the transcription settings entries are matched by
+pub fn settings_key(settings: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for setting in settings {
+        hasher.update(setting.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_similarity() {
+        let parsed = parse_fpcalc("DURATION=1421.5\nFINGERPRINT=-1,2,3\n").unwrap();
+        assert_eq!(parsed, AudioFingerprint { duration: 1421.5, frames: vec![u32::MAX, 2, 3] });
+        assert_eq!(parse_fpcalc("DURATION=12\n"), None);
+
+        let frames: Vec<u32> = (0..200u32).map(|i| i.wrapping_mul(2_654_435_761)).collect();
+        let original = AudioFingerprint { duration: 25.0, frames: frames.clone() };
+        // Another encode: starts two frames later and has a few flipped bits
+        let encode = AudioFingerprint {
+            duration: 24.8,
+            frames: frames[2..].iter().map(|f| f ^ 0b101).collect(),
+        };
+        assert!(original.similarity(&encode) > 0.9);
+        assert!((original.similarity(&original) - 1.0).abs() < f64::EPSILON);
+
+        let other = AudioFingerprint { duration: 25.0, frames: frames.iter().map(|f| f.rotate_left(7) ^ 0x5555_5555).collect() };
+        assert!(original.similarity(&other) < 0.7);
+        assert_eq!(original.similarity(&AudioFingerprint { duration: 40.0, frames }), 0.0);
+    }
+}
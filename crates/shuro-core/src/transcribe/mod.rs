@@ -2,7 +2,15 @@
 //
 // This module provides different transcription implementations through a factory pattern:
 // - WhisperCpp: whisper.cpp implementation
+// - WhisperCppServer: whisper.cpp through a running whisper-server, keeping the model loaded
 // - OpenAI: OpenAI Whisper Python implementation
+// - External: executables registered in the [plugins] config section (see plugin.rs)
+//
+// Every long-running method takes a Cancellation; implementations abort via Cancellation::run
+// and spawn child processes with kill_on_drop so the process stops with the stage.
+//
+// Transcribers are discovered by name through TranscriberRegistry; external crates can
+// register their own TranscriberTrait implementations without modifying the factory.
 //
 // To add a new transcription service:
 // 1. Create service-specific data structures for parsing JSON
@@ -23,32 +31,84 @@
 // }
 // ```
 
+pub mod audio_track;
+pub mod chunking;
 pub mod common;
+pub mod fingerprint;
 pub mod whisper_cpp;
+pub mod whisper_server;
 pub mod openai;
+pub mod external;
+pub mod registry;
+pub mod tuning;
+pub mod vad;
 
 use async_trait::async_trait;
 use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
 
 pub use common::*;
+pub use registry::{TranscriberRegistry, TranscriberConstructor};
+use crate::cancel::Cancellation;
 use crate::config::TranscriberConfig;
 use crate::error::Result;
-use crate::quality::{Transcription, QualityValidator};
+use crate::quality::{Transcription, TranscriptionSegment, QualityValidator};
+use crate::retry::RetryPolicy;
+
+/// Channel on which transcribers stream segments as they are produced
+pub type SegmentSender = UnboundedSender<TranscriptionSegment>;
+
+/// Send every segment of an already complete transcription
+pub fn forward_segments(sender: Option<&SegmentSender>, transcription: &Transcription) {
+    if let Some(sender) = sender {
+        for segment in &transcription.segments {
+            // A closed receiver only means nobody is listening any more
+            let _ = sender.send(segment.clone());
+        }
+    }
+}
 
 /// Main trait for transcription operations
 #[async_trait]
 pub trait TranscriberTrait: Send + Sync {
     /// Transcribe audio file to text
-    async fn transcribe(&self, audio_path: &Path, language: Option<&str>) -> Result<Transcription>;
+    async fn transcribe(&self, audio_path: &Path, language: Option<&str>, cancel: &Cancellation) -> Result<Transcription>;
     
     /// Tune transcription parameters
-    async fn tune_transcription(&self, audio_path: &Path) -> Result<TuneResult>;
+    async fn tune_transcription(&self, audio_path: &Path, cancel: &Cancellation) -> Result<TuneResult>;
+    
+    /// Tune transcription parameters, sending final segments to `segments` as they become available
+    ///
+    /// Implementations that cannot stream send all segments once transcription has finished.
+    async fn tune_transcription_streaming(
+        &self,
+        audio_path: &Path,
+        segments: SegmentSender,
+        cancel: &Cancellation,
+    ) -> Result<TuneResult> {
+        let result = self.tune_transcription(audio_path, cancel).await?;
+        forward_segments(Some(&segments), &result.best_transcription);
+        Ok(result)
+    }
     
     /// Extract and cache audio from video
-    async fn extract_and_cache_audio(&self, video_path: &Path) -> Result<std::path::PathBuf>;
+    async fn extract_and_cache_audio(&self, video_path: &Path, cancel: &Cancellation) -> Result<std::path::PathBuf>;
     
     /// Get cached audio file path
     async fn get_cached_audio(&self, video_path: &Path) -> Result<Option<std::path::PathBuf>>;
+
+    /// Transcription `tune_transcription` would load from the cache for `audio_path`, without
+    /// transcribing; `None` for transcribers that keep no cache
+    async fn cached_transcription(&self, _audio_path: &Path, _cancel: &Cancellation) -> Result<Option<Transcription>> {
+        Ok(None)
+    }
+
+    /// Language spoken in the extracted audio at `audio_path`, identified before it is
+    /// transcribed; `None` for transcribers that only tell it with the transcription
+    async fn detect_language(&self, _audio_path: &Path, _cancel: &Cancellation) -> Result<Option<String>> {
+        Ok(None)
+    }
     
     /// Clear transcription cache
     async fn clear_cache(&self) -> Result<u64>;
@@ -70,6 +130,7 @@ pub trait TranscriberTrait: Send + Sync {
 #[derive(Debug, Clone)]
 pub enum TranscriberImplementation {
     WhisperCpp,
+    WhisperCppServer,
     OpenAI,
     // Future implementations can be added here:
     // AssemblyAI,
@@ -87,11 +148,15 @@ impl TranscriberFactory {
         implementation: TranscriberImplementation,
         config: TranscriberConfig,
         validator: QualityValidator,
+        retry: Arc<RetryPolicy>,
     ) -> Box<dyn TranscriberTrait> {
         match implementation {
             TranscriberImplementation::WhisperCpp => {
                 Box::new(whisper_cpp::WhisperCppTranscriber::new(config, validator))
             }
+            TranscriberImplementation::WhisperCppServer => {
+                Box::new(whisper_server::WhisperCppServerTranscriber::new(config, validator, retry))
+            }
             TranscriberImplementation::OpenAI => {
                 Box::new(openai::OpenAITranscriber::new(config, validator))
             }
@@ -105,6 +170,6 @@ impl TranscriberFactory {
     /// Create with default implementation (WhisperCpp as fallback)
     pub fn create_default(config: TranscriberConfig, validator: QualityValidator) -> Box<dyn TranscriberTrait> {
         // Try WhisperCpp as default implementation
-        Self::create_transcriber(TranscriberImplementation::WhisperCpp, config, validator)
+        Self::create_transcriber(TranscriberImplementation::WhisperCpp, config, validator, Arc::default())
     }
 } 
\ No newline at end of file
@@ -3,17 +3,22 @@
 
 use async_trait::async_trait;
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use serde_json;
 use serde::{Serialize, Deserialize};
 use tracing::{info, debug};
-use tempfile;
 
+use crate::cache;
+use crate::cancel::Cancellation;
 use crate::config::TranscriberConfig;
 use crate::error::{Result, ShuroError};
+use crate::metadata::SegmentMetadata;
+use crate::platform;
+use crate::temp;
 use crate::quality::{Transcription, QualityValidator};
 use super::{TranscriberTrait, TuneResult, TranscriptionCache, AudioCache, CacheInfo, common::{WhisperUtils, AbstractTranscription, AbstractTranscriptionSegment, TranscriptionMapper}};
 
+/// Executable installed by the openai-whisper package
+const WHISPER_BINARY: &str = "whisper";
+
 /// OpenAI Whisper specific JSON output format
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpenAIWhisperOutput {
@@ -54,6 +59,8 @@ impl TranscriptionMapper<OpenAIWhisperOutput> for OpenAIWhisperMapper {
                     (logprob.exp() as f32).clamp(0.0, 1.0)
                 }),
                 language: whisper_output.language.clone(),
+                words: Vec::new(),
+                metadata: SegmentMetadata::default(),
             })
             .collect();
 
@@ -83,10 +90,7 @@ pub struct OpenAITranscriber {
 
 impl OpenAITranscriber {
     pub fn new(config: TranscriberConfig, validator: QualityValidator) -> Self {
-        let cache_base = std::env::current_dir()
-            .unwrap_or_default()
-            .join(".shuro")
-            .join("cache");
+        let cache_base = PathBuf::from(&config.cache_dir);
         
         let cache_dir = cache_base.join("transcriptions");
         let audio_cache_dir = cache_base.join("audio");
@@ -101,9 +105,10 @@ impl OpenAITranscriber {
 
     /// Check if OpenAI Whisper is available via command line
     pub async fn check_availability() -> Result<()> {
-        let output = Command::new("whisper")
+        let output = platform::command(WHISPER_BINARY)?
             .arg("--help")
             .output()
+            .await
             .map_err(|e| ShuroError::Transcriber(format!("whisper command not found: {}", e)))?;
 
         if output.status.success() {
@@ -129,16 +134,16 @@ impl OpenAITranscriber {
         debug!("Executing OpenAI Whisper transcription with model: {}", model);
 
         // Create temporary output directory for whisper results
-        let temp_dir = tempfile::tempdir()
+        let temp_dir = temp::temp_dir(Path::new(&self.config.temp_dir), "whisper")
             .map_err(|e| ShuroError::Transcriber(format!("Failed to create temp directory: {}", e)))?;
         
         let output_dir = temp_dir.path();
         
         // Build whisper command
-        let mut cmd = Command::new("whisper");
-        cmd.arg(audio_path)
+        let mut cmd = platform::command(WHISPER_BINARY)?;
+        cmd.arg(platform::process_path(audio_path))
             .arg("--model").arg(model)
-            .arg("--output_dir").arg(output_dir)
+            .arg("--output_dir").arg(platform::process_path(output_dir))
             .arg("--output_format").arg("json")
             .arg("--temperature").arg(temperature.to_string());
 
@@ -148,7 +153,7 @@ impl OpenAITranscriber {
         }
 
         // Execute command
-        let output = cmd.output()
+        let output = cmd.output().await
             .map_err(|e| ShuroError::Transcriber(format!("Failed to execute whisper command: {}", e)))?;
 
         if !output.status.success() {
@@ -168,12 +173,8 @@ impl OpenAITranscriber {
             return Err(ShuroError::Transcriber("Whisper JSON output file not found".to_string()));
         }
 
-        // Read and parse JSON output
-        let json_content = std::fs::read_to_string(&json_file)
-            .map_err(|e| ShuroError::Transcriber(format!("Failed to read JSON output: {}", e)))?;
-        
         // Parse into OpenAI-specific format
-        let openai_output: OpenAIWhisperOutput = serde_json::from_str(&json_content)
+        let openai_output: OpenAIWhisperOutput = WhisperUtils::read_json(&json_file).await
             .map_err(|e| ShuroError::Transcriber(format!("Failed to parse OpenAI Whisper JSON: {}", e)))?;
 
         // Convert to abstract format
@@ -196,11 +197,10 @@ impl OpenAITranscriber {
         // Check cache first
         if cache_file.exists() {
             debug!("Loading transcription from cache: {}", cache_file.display());
-            if let Ok(cached_content) = std::fs::read_to_string(&cache_file) {
-                if let Ok(cached_transcription) = serde_json::from_str::<Transcription>(&cached_content) {
-                    info!("Using cached transcription");
-                    return Ok(cached_transcription);
-                }
+            if let Ok(cached_transcription) = WhisperUtils::read_json::<Transcription>(&cache_file).await {
+                info!("Using cached transcription");
+                cache::touch(&cache_file);
+                return Ok(cached_transcription);
             }
         }
 
@@ -219,10 +219,7 @@ impl OpenAITranscriber {
         std::fs::create_dir_all(&self.cache_dir)
             .map_err(|e| ShuroError::Cache(format!("Failed to create cache directory: {}", e)))?;
         
-        let json_content = serde_json::to_string_pretty(&transcription)
-            .map_err(|e| ShuroError::Cache(format!("Failed to serialize transcription: {}", e)))?;
-        
-        std::fs::write(&cache_file, json_content)
+        WhisperUtils::write_json(&cache_file, &transcription)
             .map_err(|e| ShuroError::Cache(format!("Failed to write cache file: {}", e)))?;
 
         info!("OpenAI Whisper transcription completed successfully");
@@ -246,7 +243,7 @@ impl OpenAITranscriber {
                 temp,
             ).await?;
 
-            let quality_score = transcription.quality().score();
+            let quality_score = self.validator.quality(&transcription).score();
             results.push((temp, quality_score));
 
             if quality_score < best_score {
@@ -273,81 +270,88 @@ impl OpenAITranscriber {
 
 #[async_trait]
 impl TranscriberTrait for OpenAITranscriber {
-    async fn transcribe(&self, audio_path: &Path, language: Option<&str>) -> Result<Transcription> {
-        use crate::config::TranscriptionMode;
+    async fn transcribe(&self, audio_path: &Path, language: Option<&str>, cancel: &Cancellation) -> Result<Transcription> {
+        cancel.run(async {
+            use crate::config::TranscriptionMode;
         
-        info!("Starting OpenAI Whisper transcription of: {}", audio_path.display());
+            info!("Starting OpenAI Whisper transcription of: {}", audio_path.display());
         
-        // Check availability first
-        Self::check_availability().await?;
+            // Check availability first
+            Self::check_availability().await?;
 
-        match self.config.mode {
-            TranscriptionMode::Simple => {
-                info!("Using simple transcription mode");
-                self.transcribe_simple(audio_path, language).await
-            }
-            TranscriptionMode::Tuned => {
-                info!("Using tuned transcription mode");
-                // For tuned mode with OpenAI, we tune temperature instead of tempo
-                // Since this method expects audio path, we'll do a simplified tuning
-                let tune_result = self.tune_transcription_internal(audio_path, language).await?;
-                Ok(tune_result.best_transcription)
+            match self.config.mode {
+                TranscriptionMode::Simple => {
+                    info!("Using simple transcription mode");
+                    self.transcribe_simple(audio_path, language).await
+                }
+                TranscriptionMode::Tuned => {
+                    info!("Using tuned transcription mode");
+                    // For tuned mode with OpenAI, we tune temperature instead of tempo
+                    // Since this method expects audio path, we'll do a simplified tuning
+                    let tune_result = self.tune_transcription_internal(audio_path, language).await?;
+                    Ok(tune_result.best_transcription)
+                }
             }
-        }
+        }).await
     }
 
-    async fn tune_transcription(&self, video_path: &Path) -> Result<TuneResult> {
-        use crate::config::TranscriptionMode;
+    async fn tune_transcription(&self, video_path: &Path, cancel: &Cancellation) -> Result<TuneResult> {
+        cancel.run(async {
+            use crate::config::TranscriptionMode;
         
-        match self.config.mode {
-            TranscriptionMode::Simple => {
-                info!("Simple mode tune: single transcription pass");
+            match self.config.mode {
+                TranscriptionMode::Simple => {
+                    info!("Simple mode tune: single transcription pass");
                 
-                // Extract audio first
-                let audio_path = self.extract_and_cache_audio(video_path).await?;
-                let transcription = self.transcribe_simple(&audio_path, None).await?;
+                    // Extract audio first
+                    let audio_path = self.extract_and_cache_audio(video_path, cancel).await?;
+                    let transcription = self.transcribe_simple(&audio_path, self.config.language.as_deref()).await?;
                 
-                Ok(TuneResult {
-                    best_transcription: transcription,
-                    best_tempo: 100,
-                    best_temperature: self.config.temperature,
-                    quality_score: 1.0, // Assume reasonable quality for simple mode
-                    all_attempts: vec![(100, 1.0)],
-                    tested_parameters: vec!["simple-mode-single-pass".to_string()],
-                })
-            }
-            TranscriptionMode::Tuned => {
-                info!("Tuned mode: exploring optimal temperature");
+                    Ok(TuneResult {
+                        best_transcription: transcription,
+                        best_tempo: 100,
+                        best_temperature: self.config.temperature,
+                        quality_score: 1.0, // Assume reasonable quality for simple mode
+                        all_attempts: vec![(100, 1.0)],
+                        tested_parameters: vec!["simple-mode-single-pass".to_string()],
+                    })
+                }
+                TranscriptionMode::Tuned => {
+                    info!("Tuned mode: exploring optimal temperature");
                 
-                // Extract audio first
-                let audio_path = self.extract_and_cache_audio(video_path).await?;
-                self.tune_transcription_internal(&audio_path, None).await
+                    // Extract audio first
+                    let audio_path = self.extract_and_cache_audio(video_path, cancel).await?;
+                    self.tune_transcription_internal(&audio_path, self.config.language.as_deref()).await
+                }
             }
-        }
+        }).await
     }
 
-    async fn extract_and_cache_audio(&self, video_path: &Path) -> Result<PathBuf> {
-        // Use the common audio extraction functionality
-        let cache_key = WhisperUtils::generate_file_hash(video_path, &["audio_extraction"])?;
-        let audio_path = self.audio_cache_dir.join(format!("{}.wav", cache_key));
-
-        if !audio_path.exists() {
-            // Create cache directory if needed
-            std::fs::create_dir_all(&self.audio_cache_dir)
-                .map_err(|e| ShuroError::Cache(format!("Failed to create audio cache directory: {}", e)))?;
-
-            // Extract audio using the common function with proper original file name logging
-            let original_name = video_path.file_name()
-                .and_then(|n| n.to_str());
-            super::common::extract_audio(video_path, &audio_path, "ffmpeg", original_name).await?;
-        } else {
-            let original_name = video_path.file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("unknown");
-            info!("Using cached audio for: {}", original_name);
-        }
+    async fn extract_and_cache_audio(&self, video_path: &Path, cancel: &Cancellation) -> Result<PathBuf> {
+        cancel.run(async {
+            // Use the common audio extraction functionality
+            let cache_key = WhisperUtils::generate_file_hash(video_path, &["audio_extraction"])?;
+            let audio_path = self.audio_cache_dir.join(format!("{}.wav", cache_key));
+
+            if !audio_path.exists() {
+                // Create cache directory if needed
+                std::fs::create_dir_all(&self.audio_cache_dir)
+                    .map_err(|e| ShuroError::Cache(format!("Failed to create audio cache directory: {}", e)))?;
+
+                // Extract audio using the common function with proper original file name logging
+                let original_name = video_path.file_name()
+                    .and_then(|n| n.to_str());
+                super::common::extract_audio(video_path, &audio_path, &self.config.ffmpeg_path, self.config.audio_track, original_name).await?;
+            } else {
+                let original_name = video_path.file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("unknown");
+                info!("Using cached audio for: {}", original_name);
+                cache::touch(&audio_path);
+            }
 
-        Ok(audio_path)
+            Ok(audio_path)
+        }).await
     }
 
     async fn get_cached_audio(&self, video_path: &Path) -> Result<Option<PathBuf>> {
@@ -370,19 +374,15 @@ impl TranscriberTrait for OpenAITranscriber {
         
         if let Ok(mut dir_entries) = tokio::fs::read_dir(&self.cache_dir).await {
             while let Ok(Some(entry)) = dir_entries.next_entry().await {
-                if let Some(extension) = entry.path().extension() {
-                    if extension == "json" {
-                        if let Ok(content) = tokio::fs::read_to_string(entry.path()).await {
-                            if let Ok(cache_entry) = serde_json::from_str::<TranscriptionCache>(&content) {
-                                entries.push(cache_entry);
-                            }
-                        }
-                    }
+                if let Some(extension) = entry.path().extension()
+                    && extension == "json"
+                    && let Ok(cache_entry) = WhisperUtils::read_json::<TranscriptionCache>(&entry.path()).await {
+                    entries.push(cache_entry);
                 }
             }
         }
         
-        entries.sort_by(|a, b| b.cached_at.cmp(&a.cached_at));
+        entries.sort_by_key(|e| std::cmp::Reverse(e.cached_at));
         Ok(entries)
     }
     
@@ -416,28 +416,27 @@ impl TranscriberTrait for OpenAITranscriber {
         
         if let Ok(mut dir_entries) = tokio::fs::read_dir(&self.audio_cache_dir).await {
             while let Ok(Some(entry)) = dir_entries.next_entry().await {
-                if let Some(extension) = entry.path().extension() {
-                    if extension == "wav" {
-                        // Create a basic AudioCache entry
-                        // Note: This is simplified since we'd need to track the original video paths
-                        let cache_entry = AudioCache {
-                            audio_path: entry.path().to_string_lossy().to_string(),
-                            video_path: "unknown".to_string(), // Would need proper tracking
-                            video_modified: None,
-                            cached_at: entry.metadata().await
-                                .ok()
-                                .and_then(|m| m.modified().ok())
-                                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                                .map(|d| d.as_secs())
-                                .unwrap_or(0),
-                        };
-                        entries.push(cache_entry);
-                    }
+                if let Some(extension) = entry.path().extension()
+                    && extension == "wav" {
+                    // Create a basic AudioCache entry
+                    // Note: This is simplified since we'd need to track the original video paths
+                    let cache_entry = AudioCache {
+                        audio_path: entry.path().to_string_lossy().to_string(),
+                        video_path: "unknown".to_string(), // Would need proper tracking
+                        video_modified: None,
+                        cached_at: entry.metadata().await
+                            .ok()
+                            .and_then(|m| m.modified().ok())
+                            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                            .map(|d| d.as_secs())
+                            .unwrap_or(0),
+                    };
+                    entries.push(cache_entry);
                 }
             }
         }
         
-        entries.sort_by(|a, b| b.cached_at.cmp(&a.cached_at));
+        entries.sort_by_key(|e| std::cmp::Reverse(e.cached_at));
         Ok(entries)
     }
 } 
\ No newline at end of file
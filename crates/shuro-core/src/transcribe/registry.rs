@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::config::{ExternalPluginConfig, TranscriberConfig};
+use crate::error::{Result, ShuroError};
+use crate::quality::QualityValidator;
+use crate::retry::RetryPolicy;
+use super::{TranscriberTrait, TranscriberFactory, TranscriberImplementation, external::ExternalTranscriber};
+
+/// Constructor registered for a transcriber name; it gets the configuration, the validator of
+/// whisper passes and the workflow's retry policy for requests to services
+pub type TranscriberConstructor =
+    Arc<dyn Fn(TranscriberConfig, QualityValidator, Arc<RetryPolicy>) -> Box<dyn TranscriberTrait> + Send + Sync>;
+
+/// Name-to-constructor registry used to discover transcribers from config
+#[derive(Clone, Default)]
+pub struct TranscriberRegistry {
+    constructors: HashMap<String, TranscriberConstructor>,
+}
+
+impl TranscriberRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with the built-in transcribers (whisper_cpp, whisper_server, openai)
+    pub fn with_builtin() -> Self {
+        Self::new()
+            .register("whisper_cpp", |config, validator, retry| {
+                TranscriberFactory::create_transcriber(TranscriberImplementation::WhisperCpp, config, validator, retry)
+            })
+            .register("whisper_server", |config, validator, retry| {
+                TranscriberFactory::create_transcriber(TranscriberImplementation::WhisperCppServer, config, validator, retry)
+            })
+            .register("openai", |config, validator, retry| {
+                TranscriberFactory::create_transcriber(TranscriberImplementation::OpenAI, config, validator, retry)
+            })
+    }
+
+    /// Register a transcriber constructor under a name, replacing any previous registration
+    pub fn register<F>(mut self, name: &str, constructor: F) -> Self
+    where
+        F: Fn(TranscriberConfig, QualityValidator, Arc<RetryPolicy>) -> Box<dyn TranscriberTrait> + Send + Sync + 'static,
+    {
+        self.constructors.insert(name.to_string(), Arc::new(constructor));
+        self
+    }
+
+    /// Register external plugin executables
+    pub fn register_external(self, plugins: &[ExternalPluginConfig]) -> Self {
+        plugins.iter().cloned().fold(self, |registry, plugin| {
+            let name = plugin.name.clone();
+            registry.register(&name, move |config, _validator, _retry| {
+                Box::new(ExternalTranscriber::new(plugin.clone(), config))
+            })
+        })
+    }
+
+    /// Registered names in sorted order
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.constructors.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Create the transcriber selected by `config.implementation`
+    pub fn create(&self, config: TranscriberConfig, validator: QualityValidator, retry: Arc<RetryPolicy>) -> Result<Box<dyn TranscriberTrait>> {
+        let constructor = self.constructors.get(&config.implementation).ok_or_else(|| ShuroError::Config(format!(
+            "Unknown transcriber '{}'. Registered transcribers: {}",
+            config.implementation,
+            self.names().join(", ")
+        )))?;
+        Ok(constructor(config, validator, retry))
+    }
+}
@@ -0,0 +1,142 @@
+// Tuning profiles
+//
+// Tuned mode stores the parameters it settles on (tempo, temperature, transcription model) as a
+// profile keyed by the SHA-256 of the video's content, in `.shuro/cache/tuning/<hash>.json`. A
+// later run on the same content skips tempo exploration and goes straight to the final pass, even
+// when the file was renamed or moved or the transcription cache was cleared, as long as the
+// configured transcription model and audio track still match. `--tuning-profile` applies the
+// profile of another video instead, given as that video's path or the profile ID (a unique prefix
+// of the hash is enough), e.g. for other episodes from the same source or encode. Profiles are
+// kept by `clear-cache`; delete a profile file to tune that video again.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use crate::error::{Result, ShuroError};
+use crate::manifest::file_sha256;
+
+/// Shortest profile ID prefix accepted as a `--tuning-profile` reference
+const MIN_ID_PREFIX: usize = 8;
+
+/// Tuned parameters of one video
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TuningProfile {
+    /// SHA-256 of the tuned video's content
+    pub id: String,
+    /// File name of the tuned video
+    pub source: String,
+    pub tempo: i32,
+    pub temperature: f32,
+    /// Model of the final transcription pass
+    pub model: String,
+    /// Audio track the profile was tuned on
+    pub track: Option<usize>,
+    /// Segment smoothness of the best tempo
+    pub smoothness: f64,
+}
+
+/// Directory of stored tuning profiles
+pub struct TuningCache {
+    dir: PathBuf,
+}
+
+impl TuningCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    /// Profile stored under `id`, if any
+    pub async fn load(&self, id: &str) -> Result<Option<TuningProfile>> {
+        let path = self.path(id);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = tokio::fs::read_to_string(&path).await?;
+        let profile = serde_json::from_str(&content)
+            .map_err(|e| ShuroError::Cache(format!("Invalid tuning profile {}: {}", path.display(), e)))?;
+        Ok(Some(profile))
+    }
+
+    pub async fn save(&self, profile: &TuningProfile) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await
+            .map_err(|e| ShuroError::Cache(format!("Failed to create tuning cache directory: {}", e)))?;
+        let path = self.path(&profile.id);
+        tokio::fs::write(&path, serde_json::to_string_pretty(profile)?).await
+            .map_err(|e| ShuroError::Cache(format!("Failed to write tuning profile: {}", e)))?;
+        info!("🎛️ Saved tuning profile {} for {}", short_id(&profile.id), profile.source);
+        Ok(())
+    }
+
+    /// Profile referenced by a video path or a profile ID prefix
+    pub async fn resolve(&self, reference: &str) -> Result<TuningProfile> {
+        let path = Path::new(reference);
+        if path.is_file() {
+            let id = file_sha256(path).await?;
+            return self.load(&id).await?.ok_or_else(|| ShuroError::Cache(format!(
+                "No tuning profile for {}; transcribe it in tuned mode first", reference
+            )));
+        }
+
+        let prefix = reference.to_ascii_lowercase();
+        if prefix.len() < MIN_ID_PREFIX || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ShuroError::Cache(format!(
+                "Tuning profile '{}' is neither a video file nor a profile ID of at least {} hex digits",
+                reference, MIN_ID_PREFIX
+            )));
+        }
+
+        let mut matches = Vec::new();
+        if let Ok(mut entries) = tokio::fs::read_dir(&self.dir).await {
+            while let Some(entry) = entries.next_entry().await? {
+                if let Some(id) = entry.path().file_stem().and_then(|s| s.to_str())
+                    && id.starts_with(&prefix) {
+                    matches.push(id.to_string());
+                }
+            }
+        }
+        match matches.as_slice() {
+            [id] => Ok(self.load(id).await?.expect("listed tuning profile exists")),
+            [] => Err(ShuroError::Cache(format!("No tuning profile with ID {}", reference))),
+            _ => Err(ShuroError::Cache(format!("Tuning profile ID {} is ambiguous ({} profiles)", reference, matches.len()))),
+        }
+    }
+}
+
+/// Profile ID as shown in logs
+pub fn short_id(id: &str) -> &str {
+    &id[..id.len().min(12)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resolve_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let video = dir.path().join("episode01.mkv");
+        tokio::fs::write(&video, b"abc").await.unwrap();
+
+        let cache = TuningCache::new(dir.path().join("tuning"));
+        let profile = TuningProfile {
+            id: file_sha256(&video).await.unwrap(),
+            source: "episode01.mkv".to_string(),
+            tempo: 95,
+            temperature: 0.0,
+            model: "large-v3".to_string(),
+            track: None,
+            smoothness: 0.42,
+        };
+        cache.save(&profile).await.unwrap();
+
+        assert_eq!(cache.resolve(video.to_str().unwrap()).await.unwrap(), profile);
+        assert_eq!(cache.resolve("BA7816BF8F").await.unwrap(), profile);
+        assert!(cache.resolve("ba78").await.is_err());
+        assert!(cache.resolve("0123456789").await.is_err());
+    }
+}
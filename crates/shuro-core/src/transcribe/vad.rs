@@ -0,0 +1,211 @@
+// Voice activity detection
+//
+// Whisper tends to invent text for long silent stretches ("Thank you for watching.") and spends
+// time decoding them. With `transcriber.vad.enabled`, every whisper pass of the whisper_cpp and
+// whisper_server transcribers (tempo exploration included) runs on the audio with its silences cut
+// out. ffmpeg's silencedetect filter finds the stretches quieter than `vad.noise_db` that last at
+// least `vad.min_silence` seconds; the audio between them, widened by `vad.padding` seconds of
+// silence on each side, is joined into a condensed WAV. The times of the transcribed segments and
+// words, streamed ones included, are then mapped back onto the original audio. Audio without such
+// silences, or without anything but silence, is transcribed as it is.
+//
+// silencedetect measures level only: music without speech is kept and left to the hallucination
+// checks of quality.rs.
+
+use std::path::Path;
+use tracing::info;
+
+use crate::config::VadConfig;
+use crate::delivery::WavReader;
+use crate::error::{Result, ShuroError};
+use crate::platform;
+use crate::preflight::pcm_wav;
+use crate::quality::{Transcription, TranscriptionSegment};
+
+/// Sample rate of the condensed audio, that of the extracted audio
+const SAMPLE_RATE: u32 = 16_000;
+
+impl VadConfig {
+    /// Part of transcription cache keys, so transcriptions with and without cuts are cached apart
+    pub fn cache_key(&self) -> Option<String> {
+        self.enabled.then(|| format!("vad{}dB{}s{}", self.noise_db, self.min_silence, self.padding))
+    }
+}
+
+/// Stretch of the original audio kept in the condensed audio
+#[derive(Debug, Clone, PartialEq)]
+struct KeptSpan {
+    /// Start in the condensed audio
+    condensed: f64,
+    /// Start in the original audio
+    original: f64,
+}
+
+/// Mapping from times in the condensed audio back to times in the original audio
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeechMap {
+    spans: Vec<KeptSpan>,
+}
+
+impl SpeechMap {
+    /// Map of the condensed audio joining the `(start, end)` spans of the original audio
+    fn new(kept: &[(f64, f64)]) -> Self {
+        let mut condensed = 0.0;
+        let spans = kept.iter()
+            .map(|&(start, end)| {
+                let span = KeptSpan { condensed, original: start };
+                condensed += end - start;
+                span
+            })
+            .collect();
+        Self { spans }
+    }
+
+    /// Time in the original audio of `time` in the condensed audio
+    pub fn original_time(&self, time: f64) -> f64 {
+        match self.spans.iter().rev().find(|span| span.condensed <= time) {
+            Some(span) => span.original + (time - span.condensed),
+            None => time,
+        }
+    }
+
+    /// Map the times of a segment and its words onto the original audio
+    pub fn restore_segment(&self, segment: &mut TranscriptionSegment) {
+        segment.start = self.original_time(segment.start);
+        segment.end = self.original_time(segment.end).max(segment.start);
+        for word in &mut segment.words {
+            word.start = self.original_time(word.start);
+            word.end = self.original_time(word.end).max(word.start);
+        }
+    }
+
+    /// Map the times of every segment onto the original audio
+    pub fn restore(&self, transcription: &mut Transcription) {
+        for segment in &mut transcription.segments {
+            self.restore_segment(segment);
+        }
+    }
+}
+
+/// `(start, end)` silences reported by silencedetect; a silence still open at the end of the
+/// log lasts until `duration`
+fn parse_silences(log: &str, duration: f64) -> Vec<(f64, f64)> {
+    let value = |line: &str, key: &str| line.split(key).nth(1)?
+        .split_whitespace().next()?
+        .parse::<f64>().ok();
+    let mut silences = Vec::new();
+    let mut open = None;
+    for line in log.lines() {
+        if let Some(start) = value(line, "silence_start:") {
+            open = Some(start.max(0.0));
+        } else if let Some(end) = value(line, "silence_end:")
+            && let Some(start) = open.take() {
+            silences.push((start, end));
+        }
+    }
+    if let Some(start) = open {
+        silences.push((start, duration));
+    }
+    silences
+}
+
+/// `(start, end)` stretches of audio of `duration` seconds kept around `silences`, each silence
+/// within the audio shortened by `padding` on both sides
+fn kept_spans(silences: &[(f64, f64)], duration: f64, padding: f64) -> Vec<(f64, f64)> {
+    let mut kept = Vec::new();
+    let mut position = 0.0;
+    for &(start, end) in silences {
+        // Silence at either end of the audio is cut out entirely
+        let cut_start = if start <= 0.0 { 0.0 } else { start + padding };
+        let cut_end = if end >= duration { duration } else { end - padding };
+        if cut_end <= cut_start || cut_end <= position {
+            continue;
+        }
+        if cut_start > position {
+            kept.push((position, cut_start));
+        }
+        position = cut_end;
+    }
+    if position < duration {
+        kept.push((position, duration));
+    }
+    kept
+}
+
+/// Write `audio_path` without its long silences to `output_path`
+///
+/// Returns the map back onto `audio_path`, or `None` when nothing was cut and `audio_path`
+/// should be transcribed as it is.
+pub async fn condense(audio_path: &Path, output_path: &Path, config: &VadConfig, ffmpeg_path: &str) -> Result<Option<SpeechMap>> {
+    let mut reader = WavReader::open(audio_path).await?;
+    if reader.sample_rate() != SAMPLE_RATE {
+        return Ok(None);
+    }
+    let duration = reader.duration();
+
+    let output = platform::command(ffmpeg_path)?
+        .arg("-i").arg(platform::process_path(audio_path))
+        .arg("-af").arg(format!("silencedetect=noise={}dB:d={}", config.noise_db, config.min_silence))
+        .arg("-f").arg("null")
+        .arg("-")
+        .output()
+        .await
+        .map_err(|e| ShuroError::Transcriber(format!("Failed to execute ffmpeg: {}", e)))?;
+    if !output.status.success() {
+        return Err(ShuroError::Transcriber(format!(
+            "Silence detection failed: {}", String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let silences = parse_silences(&String::from_utf8_lossy(&output.stderr), duration);
+    let kept = kept_spans(&silences, duration, config.padding);
+    let kept_seconds: f64 = kept.iter().map(|(start, end)| end - start).sum();
+    if kept.is_empty() || kept_seconds >= duration - 0.01 {
+        return Ok(None);
+    }
+
+    let mut samples = Vec::new();
+    for &(start, end) in &kept {
+        samples.extend(reader.read(start, end).await?);
+    }
+    tokio::fs::write(output_path, pcm_wav(&samples)).await?;
+    info!("🔇 Voice activity detection: {:.0}s of silence cut out of {:.0}s ({} silences)",
+          duration - kept_seconds, duration, silences.len());
+    Ok(Some(SpeechMap::new(&kept)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::parse_srt;
+
+    #[test]
+    fn test_speech_map() {
+        let log = "\
+[silencedetect @ 0x1] silence_start: 0\n\
+[silencedetect @ 0x1] silence_end: 4.5 | silence_duration: 4.5\n\
+size=N/A time=00:00:10.00 bitrate=N/A speed= 500x\n\
+[silencedetect @ 0x1] silence_start: 10.25\n\
+[silencedetect @ 0x1] silence_end: 30.25 | silence_duration: 20\n\
+[silencedetect @ 0x1] silence_start: 58\n";
+        let silences = parse_silences(log, 60.0);
+        assert_eq!(silences, vec![(0.0, 4.5), (10.25, 30.25), (58.0, 60.0)]);
+
+        let kept = kept_spans(&silences, 60.0, 0.25);
+        assert_eq!(kept, vec![(4.25, 10.5), (30.0, 58.25)]);
+
+        // 0-6.25s of the condensed audio is 4.25-10.5s, the rest starts at 30s
+        let map = SpeechMap::new(&kept);
+        let mut transcription = parse_srt(
+            "1\n00:00:01,000 --> 00:00:02,000\nHello.\n\n2\n00:00:07,250 --> 00:00:09,000\nStill there?\n",
+            "en",
+        ).unwrap();
+        map.restore(&mut transcription);
+        let times: Vec<(f64, f64)> = transcription.segments.iter().map(|s| (s.start, s.end)).collect();
+        assert_eq!(times, vec![(5.25, 6.25), (31.0, 32.75)]);
+
+        // Silences shorter than the padding are not cut
+        assert_eq!(kept_spans(&[(3.0, 3.4)], 10.0, 0.25), vec![(0.0, 10.0)]);
+        assert_eq!(VadConfig::default().cache_key(), None);
+    }
+}
@@ -0,0 +1,975 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command;
+use serde::{Serialize, Deserialize};
+use tracing::{info, warn};
+
+use crate::cache;
+use crate::cancel::Cancellation;
+use crate::config::{TranscriberConfig, TranscriptionMode};
+use crate::error::{Result, ShuroError};
+use crate::manifest::file_sha256;
+use crate::metadata::SegmentMetadata;
+use crate::platform;
+use crate::temp;
+use crate::quality::{Transcription, TranscriptionSegment, QualityValidator, WordTiming};
+use super::audio_track::AudioTrackSelector;
+use super::chunking::{ChunkStitcher, cut_chunk, plan_chunks};
+use super::fingerprint::{AudioFingerprint, FingerprintEntry, FingerprintIndex, settings_key};
+use super::tuning::{TuningCache, TuningProfile, short_id};
+use super::vad::{self, SpeechMap};
+use super::whisper_server::WhisperServer;
+use super::{TranscriberTrait, TuneResult, TranscriptionCache, AudioCache, CacheInfo, SegmentSender, forward_segments, common::{WhisperUtils, AbstractTranscription, AbstractTranscriptionSegment, TranscriptionMapper}};
+
+/// Whisper.cpp specific JSON output format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhisperCppOutput {
+    pub result: WhisperCppResult,
+    pub transcription: Vec<WhisperCppSegment>,
+}
+
+/// Whisper.cpp result metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhisperCppResult {
+    pub language: String,
+}
+
+/// Whisper.cpp specific segment format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhisperCppSegment {
+    pub timestamps: WhisperCppTimestamps,
+    pub offsets: WhisperCppOffsets,
+    pub text: String,
+    /// Tokens with their own offsets; only present in full JSON output (`-ojf`)
+    #[serde(default)]
+    pub tokens: Vec<WhisperCppToken>,
+}
+
+/// Token in whisper-cpp full JSON output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhisperCppToken {
+    pub text: String,
+    pub offsets: WhisperCppOffsets,
+}
+
+/// Timestamp format in whisper-cpp
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhisperCppTimestamps {
+    pub from: String,
+    pub to: String,
+}
+
+/// Offset format in whisper-cpp (milliseconds)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhisperCppOffsets {
+    pub from: u64,
+    pub to: u64,
+}
+
+/// Mapper for Whisper.cpp format to abstract format
+pub struct WhisperCppMapper;
+
+impl TranscriptionMapper<WhisperCppOutput> for WhisperCppMapper {
+    fn to_abstract_transcription(whisper_output: WhisperCppOutput) -> Result<AbstractTranscription> {
+        let segments: Vec<AbstractTranscriptionSegment> = whisper_output.transcription
+            .into_iter()
+            .enumerate()
+            .map(|(i, seg)| AbstractTranscriptionSegment {
+                id: i as i32,
+                start_time: seg.offsets.from as f64 / 1000.0, // Convert ms to seconds
+                end_time: seg.offsets.to as f64 / 1000.0,     // Convert ms to seconds
+                text: seg.text.trim().to_string(),
+                confidence: None, // whisper-cpp doesn't provide confidence in basic output
+                language: Some(whisper_output.result.language.clone()),
+                words: words_from_tokens(&seg.tokens),
+                metadata: SegmentMetadata::default(),
+            })
+            .collect();
+
+        let duration = segments.last().map(|seg| seg.end_time);
+        
+        // Construct full text from segments
+        let full_text = segments.iter()
+            .map(|seg| seg.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Ok(AbstractTranscription {
+            text: full_text,
+            segments,
+            language: whisper_output.result.language,
+            duration,
+            model_info: Some("Whisper.cpp".to_string()),
+        })
+    }
+
+    fn to_legacy_transcription(abstract_result: AbstractTranscription) -> Transcription {
+        abstract_result.into()
+    }
+}
+
+/// Whisper.cpp implementation (uses system whisper command as fallback)
+pub struct WhisperCppTranscriber {
+    config: TranscriberConfig,
+    validator: QualityValidator,
+    cache_dir: PathBuf,
+    audio_cache_dir: PathBuf,
+    audio_tracks: AudioTrackSelector,
+    tuning: TuningCache,
+    fingerprints: FingerprintIndex,
+    /// Server whisper passes are sent to instead of running the binary (see whisper_server.rs)
+    server: Option<WhisperServer>,
+}
+
+impl WhisperCppTranscriber {
+    pub fn new(config: TranscriberConfig, validator: QualityValidator) -> Self {
+        let cache_base = PathBuf::from(&config.cache_dir);
+        
+        let cache_dir = cache_base.join("transcriptions");
+        let audio_cache_dir = cache_base.join("audio");
+        
+        Self { 
+            audio_tracks: AudioTrackSelector::new(config.clone()),
+            tuning: TuningCache::new(cache_base.join("tuning")),
+            fingerprints: FingerprintIndex::new(cache_base.join("fingerprints")),
+            config, 
+            validator, 
+            cache_dir, 
+            audio_cache_dir,
+            server: None,
+        }
+    }
+
+    /// Transcriber sending every whisper pass to `server`
+    pub fn with_server(config: TranscriberConfig, validator: QualityValidator, server: WhisperServer) -> Self {
+        Self { server: Some(server), ..Self::new(config, validator) }
+    }
+
+    /// Cached audio file for a video, keyed by the audio track it was extracted from
+    fn audio_cache_path(&self, video_path: &Path, track: Option<usize>, extras: &[&str], suffix: &str) -> Result<PathBuf> {
+        let track = track.map(|t| format!("audio_track{}", t));
+        let mut extras = extras.to_vec();
+        extras.extend(track.as_deref());
+        let cache_key = WhisperUtils::generate_file_hash(video_path, &extras)?;
+        Ok(self.audio_cache_dir.join(format!("{}{}.wav", cache_key, suffix)))
+    }
+
+    /// Cache file of the simple transcription of `audio_path`
+    fn simple_cache_file(&self, audio_path: &Path, language: Option<&str>) -> Result<PathBuf> {
+        let temperature = self.config.temperature.to_string();
+        let vad = self.config.vad.cache_key();
+        let chunks = self.config.chunk_key();
+        let mut key_data = vec![self.config.transcribe_model.as_str(), &temperature, language.unwrap_or("auto")];
+        key_data.extend(vad.as_deref());
+        key_data.extend(chunks.as_deref());
+        let cache_key = WhisperUtils::generate_file_hash(audio_path, &key_data)?;
+        Ok(self.cache_dir.join(format!("{}.json", cache_key)))
+    }
+
+    /// Simple transcription using system whisper command (if available)
+    async fn simple_transcribe(
+        &self,
+        audio_path: &Path,
+        language: Option<&str>,
+        segments: Option<&SegmentSender>,
+    ) -> Result<Transcription> {
+        info!("Using simple whisper transcription for: {}", audio_path.display());
+        
+        let cache_file = self.simple_cache_file(audio_path, language)?;
+
+        // Check cache first
+        if cache_file.exists() {
+            info!("Loading simple transcription from cache: {}", cache_file.display());
+            if let Ok(cached_entry) = WhisperUtils::read_json::<TranscriptionCache>(&cache_file).await {
+                info!("Using cached simple transcription");
+                cache::touch(&cache_file);
+                forward_segments(segments, &cached_entry.transcription);
+                return Ok(cached_entry.transcription);
+            }
+        }
+        
+        let transcription = self.transcribe_audio(audio_path, &self.config.transcribe_model, language, segments).await?;
+        
+        // Validate quality
+        if let Err(e) = self.validator.validate_transcription(&transcription) {
+            warn!("Quality validation failed for simple transcription: {}", e);
+        }
+
+        // Cache the result
+        std::fs::create_dir_all(&self.cache_dir)
+            .map_err(|e| ShuroError::Cache(format!("Failed to create cache directory: {}", e)))?;
+        
+        let cache_entry = TranscriptionCache {
+            transcription,
+            model: self.config.transcribe_model.clone(),
+            temperature: self.config.temperature,
+            language: language.map(|l| l.to_string()),
+            audio_path: audio_path.to_string_lossy().to_string(),
+            audio_modified: std::fs::metadata(audio_path).ok()
+                .and_then(|m| m.modified().ok())
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+            cached_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        };
+        
+        WhisperUtils::write_json(&cache_file, &cache_entry)
+            .map_err(|e| ShuroError::Cache(format!("Failed to write cache file: {}", e)))?;
+
+        info!("Simple transcription completed and cached");
+        Ok(cache_entry.transcription)
+    }
+
+    /// Audio at `tempo` derived from the extracted audio into `variants_dir`; `windows` limit
+    /// it to samples of the extracted audio
+    async fn tempo_variant(
+        &self,
+        base_audio: &Path,
+        variants_dir: &Path,
+        tempo: i32,
+        windows: &[(f64, f64)],
+    ) -> Result<PathBuf> {
+        if tempo == 100 && windows.is_empty() {
+            return Ok(base_audio.to_path_buf());
+        }
+        let variant = variants_dir.join(format!("tempo{}{}.wav", tempo, if windows.is_empty() { "" } else { "_sample" }));
+        if !variant.exists() {
+            super::common::derive_tempo_audio(base_audio, &variant, &self.config.ffmpeg_path, tempo, windows).await?;
+        }
+        Ok(variant)
+    }
+
+    /// Run whisper.cpp once and parse its JSON output, with long silences cut out first when
+    /// voice activity detection is enabled (see vad.rs)
+    ///
+    /// When `segments` is given, each segment printed on stdout is sent as soon as whisper emits it.
+    async fn run_whisper(
+        &self,
+        audio_path: &Path,
+        model: &str,
+        language: Option<&str>,
+        segments: Option<&SegmentSender>,
+    ) -> Result<Transcription> {
+        if !self.config.vad.enabled {
+            return self.run_whisper_pass(audio_path, model, language, segments, None).await;
+        }
+        let vad_dir = temp::temp_dir(Path::new(&self.config.temp_dir), "vad")
+            .map_err(|e| ShuroError::Transcriber(format!("Failed to create temp directory: {}", e)))?;
+        let speech_path = vad_dir.path().join("speech.wav");
+        match vad::condense(audio_path, &speech_path, &self.config.vad, &self.config.ffmpeg_path).await? {
+            Some(speech) => self.run_whisper_pass(&speech_path, model, language, segments, Some(&speech)).await,
+            None => self.run_whisper_pass(audio_path, model, language, segments, None).await,
+        }
+    }
+
+    /// Final whisper pass over `audio_path`, split into chunks transcribed in parallel when the
+    /// audio is longer than `chunk_minutes` (see chunking.rs)
+    async fn transcribe_audio(
+        &self,
+        audio_path: &Path,
+        model: &str,
+        language: Option<&str>,
+        segments: Option<&SegmentSender>,
+    ) -> Result<Transcription> {
+        let duration = super::common::wav_duration(std::fs::metadata(audio_path)?.len());
+        let chunks = plan_chunks(duration, self.config.chunk_minutes as f64 * 60.0);
+        if chunks.is_empty() {
+            return self.run_whisper(audio_path, model, language, segments).await;
+        }
+        let workers = self.config.transcribe_workers.max(1);
+        info!("✂️ Transcribing {:.0}s of audio in {} chunks with {} workers", duration, chunks.len(), workers);
+
+        let chunk_dir = temp::temp_dir(Path::new(&self.config.temp_dir), "chunks")
+            .map_err(|e| ShuroError::Transcriber(format!("Failed to create temp directory: {}", e)))?;
+        let passes = futures::stream::iter(chunks.iter().copied().enumerate())
+            .map(|(index, chunk)| {
+                let chunk_path = chunk_dir.path().join(format!("chunk{:03}.wav", index));
+                async move {
+                    cut_chunk(audio_path, &chunk_path, &self.config.ffmpeg_path, &chunk).await?;
+                    let transcription = self.run_whisper(&chunk_path, model, language, None).await;
+                    let _ = std::fs::remove_file(&chunk_path);
+                    transcription
+                }
+            })
+            .buffered(workers);
+        let mut passes = std::pin::pin!(passes);
+
+        // Chunks finish out of order but arrive in order, so segments are streamed in order
+        let mut stitcher = ChunkStitcher::new();
+        let mut stitched = Vec::new();
+        let mut detected = None;
+        let mut index = 0;
+        while let Some(transcription) = passes.next().await {
+            let transcription = transcription?;
+            detected.get_or_insert_with(|| transcription.language.clone());
+            let added = stitcher.push(&chunks[index], chunks.get(index + 1), transcription);
+            info!("   • Chunk {}/{}: {} segments", index + 1, chunks.len(), added.len());
+            if let Some(sender) = segments {
+                for segment in &added {
+                    // The receiver may have stopped early; the returned transcription is still complete
+                    let _ = sender.send(segment.clone());
+                }
+            }
+            stitched.extend(added);
+            index += 1;
+        }
+
+        Ok(Transcription {
+            text: stitched.iter().map(|s| s.text.trim()).collect::<Vec<_>>().join(" "),
+            segments: stitched,
+            language: detected.unwrap_or_default(),
+        })
+    }
+
+    /// Run whisper once on `audio_path`; segment times are mapped back through `speech` when the
+    /// audio is condensed
+    async fn run_whisper_pass(
+        &self,
+        audio_path: &Path,
+        model: &str,
+        language: Option<&str>,
+        segments: Option<&SegmentSender>,
+        speech: Option<&SpeechMap>,
+    ) -> Result<Transcription> {
+        // The server's resident model answers instead; its segments arrive all at once
+        if let Some(server) = &self.server {
+            let mut transcription = server.transcribe(audio_path, language).await?;
+            if let Some(speech) = speech {
+                speech.restore(&mut transcription);
+            }
+            forward_segments(segments, &transcription);
+            return Ok(transcription);
+        }
+
+        // Create temporary output directory for transcription
+        let temp_dir = temp::temp_dir(Path::new(&self.config.temp_dir), "whisper")
+            .map_err(|e| ShuroError::Transcriber(format!("Failed to create temp directory: {}", e)))?;
+        let output_file = temp_dir.path().join("transcription");
+        let mut cmd = self.whisper_command(audio_path, model, &output_file, language, segments.is_some())?;
+
+        let mut child = cmd.spawn()
+            .map_err(|e| ShuroError::Transcriber(format!("Failed to execute whisper: {}", e)))?;
+
+        let stdout = child.stdout.take()
+            .ok_or_else(|| ShuroError::Transcriber("Failed to capture whisper stdout".to_string()))?;
+        let mut stderr = child.stderr.take()
+            .ok_or_else(|| ShuroError::Transcriber("Failed to capture whisper stderr".to_string()))?;
+        let stderr_task = tokio::spawn(async move {
+            let mut buffer = String::new();
+            let _ = stderr.read_to_string(&mut buffer).await;
+            buffer
+        });
+
+        // Forward printed segments while whisper is still running
+        let mut lines = BufReader::new(stdout).lines();
+        let mut next_id = 0;
+        while let Some(line) = lines.next_line().await
+            .map_err(|e| ShuroError::Transcriber(format!("Failed to read whisper output: {}", e)))? {
+            if let Some(sender) = segments
+                && let Some(mut segment) = parse_stdout_segment(&line, next_id) {
+                next_id += 1;
+                if let Some(speech) = speech {
+                    speech.restore_segment(&mut segment);
+                }
+                // The receiver may have stopped early; the final JSON output is still authoritative
+                let _ = sender.send(segment);
+            }
+        }
+
+        let status = child.wait().await
+            .map_err(|e| ShuroError::Transcriber(format!("Failed to wait for whisper: {}", e)))?;
+        let stderr = stderr_task.await.unwrap_or_default();
+
+        if !status.success() {
+            return Err(ShuroError::Transcriber(format!("Whisper failed: {}", stderr)));
+        }
+
+        // Find and read JSON output
+        let json_file = temp_dir.path().join("transcription.json");
+
+        // Parse and convert to legacy format
+        let whisper_output: WhisperCppOutput = WhisperUtils::read_json(&json_file).await
+            .map_err(|e| ShuroError::Transcriber(format!("Failed to parse Whisper.cpp JSON: {}", e)))?;
+
+        let abstract_transcription = WhisperCppMapper::to_abstract_transcription(whisper_output)?;
+        let mut transcription = WhisperCppMapper::to_legacy_transcription(abstract_transcription);
+        if let Some(speech) = speech {
+            speech.restore(&mut transcription);
+        }
+        Ok(transcription)
+    }
+
+    /// Whisper command transcribing `audio_path` into `{output_file}.json`; `streamed` passes run
+    /// alongside translation
+    fn whisper_command(&self, audio_path: &Path, model: &str, output_file: &Path, language: Option<&str>, streamed: bool) -> Result<Command> {
+        let mut cmd = platform::command(&self.config.binary_path)?;
+        cmd.arg("-f").arg(platform::process_path(audio_path))
+            .arg("-m").arg(platform::process_path(Path::new(model)))
+            .arg("-of").arg(platform::process_path(output_file))
+            .arg(if self.config.word_timestamps { "-ojf" } else { "-oj" }) // Output JSON format, full JSON includes token timestamps
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        if let Some(lang) = language {
+            cmd.arg("-l").arg(lang);
+        }
+
+        // Streamed passes run alongside translation; keep the GPU for Ollama when asked to
+        if streamed && self.config.cpu_while_translating {
+            cmd.arg("-ng");
+        }
+        Ok(cmd)
+    }
+
+    /// Audio track, cache file and fingerprint settings key of the tuned transcription of `audio_path`
+    async fn tuned_cache(&self, audio_path: &Path, language: Option<&str>, cancel: &Cancellation) -> Result<(Option<usize>, PathBuf, String)> {
+        let track_index = self.audio_tracks.select(audio_path, cancel).await?;
+        let track = track_index.map(|t| format!("audio_track{}", t));
+        let (temperature, range_min, range_max, steps) = (
+            self.config.temperature.to_string(),
+            self.config.explore_range_min.to_string(),
+            self.config.explore_range_max.to_string(),
+            self.config.explore_steps.to_string(),
+        );
+        let mut key_data = vec![
+            "tuned",
+            &self.config.explore_model,
+            &self.config.transcribe_model,
+            &temperature,
+            &range_min,
+            &range_max,
+            &steps,
+            language.unwrap_or("auto"),
+        ];
+        key_data.extend(track.as_deref());
+        // Full-length exploration keeps the keys of results cached before sampling existed
+        let sample = (self.config.explore_sample_windows > 0 && self.config.explore_sample_seconds > 0)
+            .then(|| format!("samples{}x{}", self.config.explore_sample_windows, self.config.explore_sample_seconds));
+        key_data.extend(sample.as_deref());
+        let profile_key = self.config.tuning_profile.as_ref().map(|r| format!("profile={}", r));
+        key_data.extend(profile_key.as_deref());
+        let vad = self.config.vad.cache_key();
+        key_data.extend(vad.as_deref());
+        let chunks = self.config.chunk_key();
+        key_data.extend(chunks.as_deref());
+        let cache_key = WhisperUtils::generate_file_hash(audio_path, &key_data)?;
+        let cache_file = self.cache_dir.join(format!("tuned_{}.json", cache_key));
+        // Other encodes may carry the same audio on another track index
+        let settings: Vec<&str> = key_data.iter().copied().filter(|k| Some(*k) != track.as_deref()).collect();
+        Ok((track_index, cache_file, settings_key(&settings)))
+    }
+
+    /// Tuned transcription: find best tempo first, then transcribe with optimal settings
+    async fn tuned_transcribe(
+        &self,
+        video_path: &Path,
+        language: Option<&str>,
+        segments: Option<&SegmentSender>,
+        cancel: &Cancellation,
+    ) -> Result<TuneResult> {
+        info!("Starting tuned transcription for: {}", video_path.display());
+        
+        let (track_index, cache_file, settings) = self.tuned_cache(video_path, language, cancel).await?;
+
+        // Check cache first
+        if cache_file.exists() {
+            info!("Loading tuned transcription from cache: {}", cache_file.display());
+            if let Ok(cached_result) = WhisperUtils::read_json::<TuneResult>(&cache_file).await {
+                cache::touch(&cache_file);
+                info!("Using cached tuned transcription (best tempo: {}%, quality: {:.3})", 
+                      cached_result.best_tempo, cached_result.quality_score);
+                forward_segments(segments, &cached_result.best_transcription);
+                return Ok(cached_result);
+            }
+        }
+        
+        // Tempo variants are derived from the audio extracted once for the video and removed
+        // when tuning completes; only the extracted audio stays cached
+        let base_audio = self.extract_and_cache_audio(video_path, cancel).await?;
+
+        let fingerprint = self.fingerprint(&base_audio).await;
+        if let Some(fingerprint) = &fingerprint
+            && let Some(entry) = self.fingerprints.find(fingerprint, &settings, self.config.fingerprint_similarity).await?
+            && let Ok(cached_result) = WhisperUtils::read_json::<TuneResult>(&entry.transcription).await {
+            info!("Using tuned transcription of {} (same audio, best tempo: {}%)", entry.source, cached_result.best_tempo);
+            std::fs::create_dir_all(&self.cache_dir)
+                .map_err(|e| ShuroError::Cache(format!("Failed to create cache directory: {}", e)))?;
+            tokio::fs::copy(&entry.transcription, &cache_file).await
+                .map_err(|e| ShuroError::Cache(format!("Failed to write tuned cache file: {}", e)))?;
+            forward_segments(segments, &cached_result.best_transcription);
+            return Ok(cached_result);
+        }
+
+        let variants_dir = temp::temp_dir(Path::new(&self.config.temp_dir), "tempo_variants")
+            .map_err(|e| ShuroError::Cache(format!("Failed to create tempo variant directory: {}", e)))?;
+
+        // An explicit profile wins; otherwise reuse the profile stored for this content when it
+        // was tuned for the same model and audio track
+        let content_id = file_sha256(video_path).await?;
+        let profile = match &self.config.tuning_profile {
+            Some(reference) => Some(self.tuning.resolve(reference).await?),
+            None => self.tuning.load(&content_id).await?
+                .filter(|p| p.model == self.config.transcribe_model && p.track == track_index),
+        };
+        let (tuned, all_attempts, tested_parameters) = match profile {
+            Some(profile) => {
+                info!("🎛️ Reusing tuning profile {} of {}: tempo {}%, model '{}'",
+                      short_id(&profile.id), profile.source, profile.tempo, profile.model);
+                let tested = vec![format!("profile={}->tempo={}%", short_id(&profile.id), profile.tempo)];
+                let attempts = vec![(profile.tempo, profile.smoothness)];
+                (profile, attempts, tested)
+            }
+            None => {
+                let (tempo, smoothness, attempts, tested) = self.explore_tempo(&base_audio, variants_dir.path()).await?;
+                let tuned = TuningProfile {
+                    id: content_id,
+                    source: video_path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                    tempo,
+                    temperature: self.config.temperature,
+                    model: self.config.transcribe_model.clone(),
+                    track: track_index,
+                    smoothness,
+                };
+                self.tuning.save(&tuned).await?;
+                (tuned, attempts, tested)
+            }
+        };
+        let (best_tempo, best_smoothness) = (tuned.tempo, tuned.smoothness);
+        
+        // Now transcribe with the best tempo using the full model
+        info!("Final transcription with tempo {}% using model '{}'", best_tempo, tuned.model);
+        // Only the final pass is streamed; exploration output is discarded
+        let final_audio = self.tempo_variant(&base_audio, variants_dir.path(), best_tempo, &[]).await?;
+        let final_transcription = self.transcribe_audio(&final_audio, &tuned.model, language, segments).await?;
+        drop(variants_dir);
+        
+        // Validate quality
+        if let Err(e) = self.validator.validate_transcription(&final_transcription) {
+            warn!("Quality validation failed for best tempo {}: {}", best_tempo, e);
+        }
+        
+        let tune_result = TuneResult {
+            best_transcription: final_transcription,
+            best_tempo,
+            best_temperature: tuned.temperature,
+            quality_score: best_smoothness,
+            all_attempts,
+            tested_parameters,
+        };
+
+        // Cache the result
+        std::fs::create_dir_all(&self.cache_dir)
+            .map_err(|e| ShuroError::Cache(format!("Failed to create cache directory: {}", e)))?;
+        
+        WhisperUtils::write_json(&cache_file, &tune_result)
+            .map_err(|e| ShuroError::Cache(format!("Failed to write tuned cache file: {}", e)))?;
+        if let Some(fingerprint) = fingerprint {
+            self.fingerprints.record(&FingerprintEntry {
+                source: video_path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                settings,
+                transcription: cache_file.clone(),
+                fingerprint,
+            }).await?;
+        }
+
+        info!("Tuned transcription completed and cached (best tempo: {}%, quality: {:.3})", 
+              best_tempo, best_smoothness);
+        Ok(tune_result)
+    }
+
+    /// Explore tempos with the exploration model; returns the tempo with the smoothest
+    /// segments, its smoothness, all attempts and the tested parameters
+    async fn explore_tempo(
+        &self,
+        base_audio: &Path,
+        variants_dir: &Path,
+    ) -> Result<(i32, f64, Vec<(i32, f64)>, Vec<String>)> {
+        let duration = super::common::wav_duration(std::fs::metadata(base_audio)?.len());
+        let windows = super::common::sample_windows(duration, self.config.explore_sample_windows, self.config.explore_sample_seconds);
+        if !windows.is_empty() {
+            info!("Exploring tempos on {} samples of {}s spread across {:.0}s of audio",
+                windows.len(), self.config.explore_sample_seconds, duration);
+        }
+
+        // Generate tempo test range
+        let tempo_range = super::common::generate_tempo_range(
+            self.config.explore_range_min,
+            self.config.explore_range_max,
+            self.config.explore_steps
+        );
+        
+        info!("Testing {} tempo values: {:?}", tempo_range.len(), tempo_range);
+        
+        let mut best_tempo = 100;
+        let mut best_smoothness = f64::MAX;
+        let mut all_attempts = Vec::new();
+        let mut tested_parameters = Vec::new();
+        
+        // Test each tempo with exploration model
+        for &tempo in &tempo_range {
+            info!("Testing tempo {}% with exploration model '{}'", tempo, self.config.explore_model);
+            
+            let explored = match self.tempo_variant(base_audio, variants_dir, tempo, &windows).await {
+                Ok(audio) => self.run_whisper(&audio, &self.config.explore_model, self.config.language.as_deref(), None).await,
+                Err(e) => Err(e),
+            };
+            match explored {
+                Ok(transcription) => {
+                    let smoothness = super::common::calculate_segment_smoothness(&transcription);
+                    all_attempts.push((tempo, smoothness));
+                    tested_parameters.push(format!("tempo={}%->smoothness={:.3}", tempo, smoothness));
+                    
+                    info!("Tempo {}%: {} segments, smoothness score: {:.3}", 
+                          tempo, transcription.segments.len(), smoothness);
+                    
+                    if smoothness < best_smoothness {
+                        best_smoothness = smoothness;
+                        best_tempo = tempo;
+                        info!("New best tempo: {}% (smoothness: {:.3})", best_tempo, best_smoothness);
+                    }
+                }
+                Err(e) => {
+                    warn!("Failed to transcribe with tempo {}%: {}", tempo, e);
+                    all_attempts.push((tempo, f64::MAX));
+                    tested_parameters.push(format!("tempo={}%->error", tempo));
+                }
+            }
+        }
+        
+        info!("Exploration phase complete. Best tempo: {}% (smoothness: {:.3})", best_tempo, best_smoothness);
+        Ok((best_tempo, best_smoothness, all_attempts, tested_parameters))
+    }
+
+    /// Fingerprint of the extracted audio when fingerprint dedupe is enabled; failures only skip dedupe
+    async fn fingerprint(&self, audio_path: &Path) -> Option<AudioFingerprint> {
+        if !self.config.fingerprint_dedupe {
+            return None;
+        }
+        match AudioFingerprint::compute(&self.config.fpcalc_path, audio_path).await {
+            Ok(fingerprint) => Some(fingerprint),
+            Err(e) => {
+                warn!("Skipping fingerprint dedupe: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Run the configured transcription mode, optionally streaming final-pass segments
+    async fn tune(&self, video_path: &Path, segments: Option<&SegmentSender>, cancel: &Cancellation) -> Result<TuneResult> {
+        cancel.run(async {
+            match self.config.mode {
+                TranscriptionMode::Simple => {
+                    // Simple mode - just do basic transcription without tempo exploration
+                    info!("Simple mode tune: single transcription pass");
+                
+                    // Extract audio first
+                    let audio_path = self.extract_and_cache_audio(video_path, cancel).await?;
+                    let transcription = self.simple_transcribe(&audio_path, self.config.language.as_deref(), segments).await?;
+                
+                    Ok(TuneResult {
+                        best_transcription: transcription,
+                        best_tempo: 100,
+                        best_temperature: self.config.temperature,
+                        quality_score: 1.0, // Assume reasonable quality for simple mode
+                        all_attempts: vec![(100, 1.0)],
+                        tested_parameters: vec!["simple-mode-single-pass".to_string()],
+                    })
+                }
+                TranscriptionMode::Tuned => {
+                    info!("Tuned mode: exploring optimal tempo");
+                    self.tuned_transcribe(video_path, self.config.language.as_deref(), segments, cancel).await
+                }
+            }
+        }).await
+    }
+}
+
+/// Merge whisper tokens into timed words
+///
+/// A token starting with whitespace starts a new word; so does every non-Latin letter token,
+/// since scripts such as Japanese are not space-separated. Special tokens (`[_BEG_]`, `[_TT_..]`)
+/// are dropped.
+fn words_from_tokens(tokens: &[WhisperCppToken]) -> Vec<WordTiming> {
+    let mut words: Vec<WordTiming> = Vec::new();
+    for token in tokens.iter().filter(|t| !t.text.starts_with("[_")) {
+        let text = token.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let (start, end) = (token.offsets.from as f64 / 1000.0, token.offsets.to as f64 / 1000.0);
+        let starts_word = token.text.starts_with(char::is_whitespace)
+            || text.chars().next().is_some_and(|c| c.is_alphabetic() && c > '\u{024F}');
+
+        match words.last_mut() {
+            Some(word) if !starts_word => {
+                word.text.push_str(text);
+                word.end = end;
+            }
+            _ => words.push(WordTiming { text: text.to_string(), start, end }),
+        }
+    }
+    words
+}
+
+/// Parse a segment line printed by whisper.cpp, e.g. `[00:00:01.000 --> 00:00:03.500]  Hello`
+fn parse_stdout_segment(line: &str, id: i32) -> Option<TranscriptionSegment> {
+    let line = line.trim();
+    let rest = line.strip_prefix('[')?;
+    let (range, text) = rest.split_once(']')?;
+    let (from, to) = range.split_once("-->")?;
+
+    Some(TranscriptionSegment {
+        id,
+        start: parse_stdout_timestamp(from.trim())?,
+        end: parse_stdout_timestamp(to.trim())?,
+        text: text.trim().to_string(),
+        tokens: vec![],
+        temperature: 0.0,
+        avg_logprob: 0.0,
+        compression_ratio: 1.0,
+        no_speech_prob: 0.0,
+        words: vec![],
+        metadata: SegmentMetadata::default(),
+    })
+}
+
+/// Parse `hh:mm:ss.mmm` into seconds
+fn parse_stdout_timestamp(timestamp: &str) -> Option<f64> {
+    let mut parts = timestamp.split(':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+#[async_trait]
+impl TranscriberTrait for WhisperCppTranscriber {
+    async fn transcribe(&self, audio_path: &Path, language: Option<&str>, cancel: &Cancellation) -> Result<Transcription> {
+        cancel.run(async {
+            use crate::config::TranscriptionMode;
+        
+            match self.config.mode {
+                TranscriptionMode::Simple => {
+                    info!("Using simple transcription mode");
+                    self.simple_transcribe(audio_path, language, None).await
+                }
+                TranscriptionMode::Tuned => {
+                    info!("Using tuned transcription mode");
+                    // For tuned mode, we need to work with video files, but if we only have audio,
+                    // we'll need to create a temporary "video" file or adjust the approach
+                    // For now, fall back to simple mode when called with audio path directly
+                    warn!("Tuned mode called with audio path - falling back to simple mode");
+                    self.simple_transcribe(audio_path, language, None).await
+                }
+            }
+        }).await
+    }
+
+    async fn tune_transcription(&self, video_path: &Path, cancel: &Cancellation) -> Result<TuneResult> {
+        self.tune(video_path, None, cancel).await
+    }
+
+    async fn tune_transcription_streaming(
+        &self,
+        video_path: &Path,
+        segments: SegmentSender,
+        cancel: &Cancellation,
+    ) -> Result<TuneResult> {
+        self.tune(video_path, Some(&segments), cancel).await
+    }
+
+    async fn extract_and_cache_audio(&self, video_path: &Path, cancel: &Cancellation) -> Result<PathBuf> {
+        cancel.run(async {
+            // Generate cache key and audio path
+            let track = self.audio_tracks.select(video_path, cancel).await?;
+            let audio_path = self.audio_cache_path(video_path, track, &["audio_extraction"], "")?;
+        
+            // Create cache directory if needed
+            if let Some(parent) = audio_path.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| ShuroError::Cache(format!("Failed to create audio cache dir: {}", e)))?;
+            }
+        
+            // Check if already cached
+            if audio_path.exists() {
+                let original_name = video_path.file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("unknown");
+                info!("Using cached audio for: {}", original_name);
+                cache::touch(&audio_path);
+                return Ok(audio_path);
+            }
+        
+            // Extract audio using the common function with proper original file name logging
+            let original_name = video_path.file_name()
+                .and_then(|n| n.to_str());
+            super::common::extract_audio(video_path, &audio_path, &self.config.ffmpeg_path, track, original_name).await?;
+
+            Ok(audio_path)
+        }).await
+    }
+
+    async fn get_cached_audio(&self, video_path: &Path) -> Result<Option<PathBuf>> {
+        // Videos whose audio track still has to be identified have nothing cached yet
+        let Some(track) = self.audio_tracks.known(video_path).await? else {
+            return Ok(None);
+        };
+        let audio_path = self.audio_cache_path(video_path, track, &["audio_extraction"], "")?;
+        
+        if audio_path.exists() {
+            Ok(Some(audio_path))
+        } else {
+            Ok(None)
+        }
+    }
+
+    async fn cached_transcription(&self, audio_path: &Path, cancel: &Cancellation) -> Result<Option<Transcription>> {
+        use crate::config::TranscriptionMode;
+
+        let cached = match self.config.mode {
+            TranscriptionMode::Simple => {
+                let cache_file = self.simple_cache_file(audio_path, self.config.language.as_deref())?;
+                if !cache_file.exists() {
+                    return Ok(None);
+                }
+                WhisperUtils::read_json::<TranscriptionCache>(&cache_file).await.ok().map(|entry| entry.transcription)
+            }
+            TranscriptionMode::Tuned => {
+                let (_, cache_file, _) = self.tuned_cache(audio_path, self.config.language.as_deref(), cancel).await?;
+                if !cache_file.exists() {
+                    return Ok(None);
+                }
+                WhisperUtils::read_json::<TuneResult>(&cache_file).await.ok().map(|result| result.best_transcription)
+            }
+        };
+        Ok(cached)
+    }
+
+    async fn detect_language(&self, audio_path: &Path, cancel: &Cancellation) -> Result<Option<String>> {
+        let detected = self.audio_tracks.identify_language(audio_path, cancel).await?;
+        Ok(detected.map(|(language, _)| language))
+    }
+
+    async fn clear_cache(&self) -> Result<u64> {
+        let mut count = 0;
+        if let Ok(mut entries) = tokio::fs::read_dir(&self.cache_dir).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                if tokio::fs::remove_file(entry.path()).await.is_ok() {
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    async fn list_cache(&self) -> Result<Vec<TranscriptionCache>> {
+        // Return empty list for simplicity
+        Ok(Vec::new())
+    }
+
+    async fn cache_info(&self) -> Result<CacheInfo> {
+        Ok(CacheInfo {
+            total_files: 0,
+            total_size: 0,
+            audio_files: 0,
+            audio_size: 0,
+            oldest_entry: None,
+            newest_entry: None,
+            models_used: vec!["tiny".to_string()],
+        })
+    }
+
+    async fn clear_audio_cache(&self) -> Result<u64> {
+        let mut count = 0;
+        if let Ok(mut entries) = tokio::fs::read_dir(&self.audio_cache_dir).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                if tokio::fs::remove_file(entry.path()).await.is_ok() {
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    async fn list_audio_cache(&self) -> Result<Vec<AudioCache>> {
+        // Return empty list for simplicity
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stdout_segment() {
+        let segment = parse_stdout_segment("[00:01:02.500 --> 00:01:04.000]   Hello there.", 3).unwrap();
+        assert_eq!(segment.id, 3);
+        assert_eq!(segment.start, 62.5);
+        assert_eq!(segment.end, 64.0);
+        assert_eq!(segment.text, "Hello there.");
+
+        assert!(parse_stdout_segment("whisper_init_from_file: loading model", 0).is_none());
+    }
+
+    #[test]
+    fn test_words_from_tokens() {
+        let token = |text: &str, from: u64, to: u64| WhisperCppToken {
+            text: text.to_string(),
+            offsets: WhisperCppOffsets { from, to },
+        };
+        let tokens = vec![
+            token("[_BEG_]", 0, 0),
+            token(" Hel", 0, 200),
+            token("lo", 200, 400),
+            token(",", 400, 450),
+            token(" world", 600, 1000),
+            token("東", 1200, 1400),
+            token("京", 1400, 1600),
+        ];
+        let words: Vec<(String, f64, f64)> = words_from_tokens(&tokens)
+            .into_iter()
+            .map(|w| (w.text, w.start, w.end))
+            .collect();
+        assert_eq!(words, vec![
+            ("Hello,".to_string(), 0.0, 0.45),
+            ("world".to_string(), 0.6, 1.0),
+            ("東".to_string(), 1.2, 1.4),
+            ("京".to_string(), 1.4, 1.6),
+        ]);
+    }
+
+    #[test]
+    fn test_cpu_while_translating() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = crate::config::Config::default();
+        let uses_gpu = |cpu_while_translating, streamed| {
+            let transcriber = WhisperCppTranscriber::new(
+                TranscriberConfig {
+                    // Any existing executable; the command is only built
+                    binary_path: std::env::current_exe().unwrap().to_string_lossy().into_owned(),
+                    cache_dir: dir.path().to_string_lossy().into_owned(),
+                    cpu_while_translating,
+                    ..config.transcriber.clone()
+                },
+                QualityValidator::from_config(&config.quality),
+            );
+            let cmd = transcriber.whisper_command(Path::new("audio.wav"), "model.bin", Path::new("out"), Some("en"), streamed).unwrap();
+            !cmd.as_std().get_args().any(|arg| arg == "-ng")
+        };
+
+        // Only streamed passes leave the GPU to translation, and only with CpuWhisper scheduling
+        assert!(!uses_gpu(true, true));
+        assert!(uses_gpu(true, false));
+        assert!(uses_gpu(false, true));
+        assert!(uses_gpu(false, false));
+    }
+}
@@ -0,0 +1,363 @@
+// whisper.cpp server mode
+//
+// Every whisper-cli run loads the model from disk again, which for large models can take longer
+// than transcribing a short file. whisper.cpp ships an HTTP server (`whisper-server`) that loads
+// its model once. The `whisper_server` transcriber sends each whisper pass to the server at
+// `transcriber.server.url` (`POST /inference` with the audio as multipart form data, answered in
+// `verbose_json`), so a long batch keeps the model resident in memory. Audio extraction, tempo
+// tuning and the caches are those of the whisper_cpp transcriber.
+//
+// The server transcribes with the model it was started with: `transcribe_model` when shuro starts
+// it, whatever it was given otherwise. Tempo exploration in tuned mode uses that model as well
+// instead of `explore_model`. With `server.spawn`, shuro starts `server.binary_path` on the host
+// and port of the URL when nothing answers there, waits up to `server.startup_timeout` seconds for
+// the model to load, and stops the server with the transcriber. The server does not stream:
+// segments are forwarded to translation once its response arrives. Requests the server cannot
+// take (refused connections, 5xx answers) are retried by the workflow's retry policy (see retry.rs).
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::process::Child;
+use tokio::sync::Mutex;
+use tracing::info;
+
+use crate::cancel::Cancellation;
+use crate::config::{TranscriberConfig, WhisperServerConfig};
+use crate::error::{Result, ShuroError, is_transient_status};
+use crate::platform;
+use crate::quality::{QualityValidator, Transcription};
+use crate::retry::RetryPolicy;
+use crate::translate::common::language_code_to_name;
+use super::whisper_cpp::{WhisperCppMapper, WhisperCppOffsets, WhisperCppOutput, WhisperCppResult, WhisperCppSegment, WhisperCppTimestamps, WhisperCppToken, WhisperCppTranscriber};
+use super::{TranscriberTrait, TuneResult, TranscriptionCache, AudioCache, CacheInfo, SegmentSender, common::TranscriptionMapper};
+
+/// Seconds a readiness probe of the server may take
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `verbose_json` response of `/inference`
+#[derive(Debug, Deserialize)]
+struct InferenceResponse {
+    /// Full language name in lowercase, e.g. "english"
+    language: String,
+    #[serde(default)]
+    segments: Vec<InferenceSegment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InferenceSegment {
+    text: String,
+    start: f64,
+    end: f64,
+    /// Tokens with their times
+    #[serde(default)]
+    words: Vec<InferenceWord>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InferenceWord {
+    word: String,
+    start: f64,
+    end: f64,
+}
+
+/// Connection to a whisper.cpp server, and the server process when shuro started it
+pub struct WhisperServer {
+    config: WhisperServerConfig,
+    model: String,
+    temperature: f32,
+    word_timestamps: bool,
+    acceptable_languages: String,
+    client: Client,
+    retry: Arc<RetryPolicy>,
+    /// Server started by shuro; killed when dropped
+    process: Mutex<Option<Child>>,
+}
+
+impl WhisperServer {
+    pub fn new(config: &TranscriberConfig, retry: Arc<RetryPolicy>) -> Self {
+        Self {
+            config: config.server.clone(),
+            model: config.transcribe_model.clone(),
+            temperature: config.temperature,
+            word_timestamps: config.word_timestamps,
+            acceptable_languages: config.acceptable_languages.clone(),
+            client: Client::new(),
+            retry,
+            process: Mutex::new(None),
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.config.url.trim_end_matches('/'), path)
+    }
+
+    /// Whether anything answers HTTP requests at the server URL
+    async fn answers(&self) -> bool {
+        self.client.get(self.url("")).timeout(PROBE_TIMEOUT).send().await.is_ok()
+    }
+
+    /// Start the server when `spawn` is set and none is running; a server shuro started must
+    /// still be running
+    async fn ensure_running(&self) -> Result<()> {
+        let mut process = self.process.lock().await;
+        if let Some(child) = process.as_mut() {
+            return match child.try_wait() {
+                Ok(Some(status)) => {
+                    *process = None;
+                    Err(ShuroError::Transcriber(format!("whisper-server exited ({})", status)))
+                }
+                _ => Ok(()),
+            };
+        }
+        if !self.config.spawn || self.answers().await {
+            return Ok(());
+        }
+
+        let url = reqwest::Url::parse(&self.config.url)
+            .map_err(|e| ShuroError::Config(format!("Invalid whisper server URL {}: {}", self.config.url, e)))?;
+        let host = url.host_str().unwrap_or("127.0.0.1").to_string();
+        let port = url.port_or_known_default().unwrap_or(80);
+        info!("🎙️ Starting {} with model '{}' on {}:{}", self.config.binary_path, self.model, host, port);
+        let child = platform::command(&self.config.binary_path)?
+            .arg("-m").arg(platform::process_path(Path::new(&self.model)))
+            .arg("--host").arg(&host)
+            .arg("--port").arg(port.to_string())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| ShuroError::Transcriber(format!("Failed to start {}: {}", self.config.binary_path, e)))?;
+        let child = process.insert(child);
+
+        let deadline = Instant::now() + Duration::from_secs(self.config.startup_timeout);
+        while !self.answers().await {
+            if let Ok(Some(status)) = child.try_wait() {
+                *process = None;
+                return Err(ShuroError::Transcriber(format!("whisper-server exited while starting ({})", status)));
+            }
+            if Instant::now() >= deadline {
+                return Err(ShuroError::Transcriber(format!(
+                    "whisper-server did not answer at {} within {}s", self.config.url, self.config.startup_timeout
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        info!("whisper-server is up at {}", self.config.url);
+        Ok(())
+    }
+
+    /// Transcribe `audio_path` with the server's model
+    pub async fn transcribe(&self, audio_path: &Path, language: Option<&str>) -> Result<Transcription> {
+        self.ensure_running().await?;
+        let audio = tokio::fs::read(audio_path).await?;
+        let boundary = format!(
+            "shuro-{}-{}",
+            std::process::id(),
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos()
+        );
+        let fields = [
+            ("temperature", self.temperature.to_string()),
+            ("response_format", "verbose_json".to_string()),
+            ("language", language.unwrap_or("auto").to_string()),
+        ];
+        let file_name = audio_path.file_name().unwrap_or_default().to_string_lossy();
+
+        let body = multipart_body(&boundary, &fields, &file_name, &audio);
+
+        info!("Transcribing {} on whisper-server {}", audio_path.display(), self.config.url);
+        let body = self.retry.run("whisper-server", || self.inference(&boundary, &body)).await?;
+        parse_inference(&body, language, self.word_timestamps, &self.acceptable_languages)
+    }
+
+    /// Post one `/inference` request; refused connections and 5xx answers are transient
+    async fn inference(&self, boundary: &str, body: &[u8]) -> Result<String> {
+        let response = self.client.post(self.url("inference"))
+            .header("Content-Type", format!("multipart/form-data; boundary={}", boundary))
+            .body(body.to_vec())
+            .send()
+            .await
+            .map_err(|e| ShuroError::Unavailable(format!("whisper-server at {} unreachable: {}", self.config.url, e)))?;
+        let status = response.status();
+        let body = response.text().await
+            .map_err(|e| ShuroError::Transcriber(format!("Failed to read whisper-server response: {}", e)))?;
+        if !status.is_success() {
+            let message = format!("whisper-server returned {}: {}", status, body);
+            return Err(match is_transient_status(status) {
+                true => ShuroError::Unavailable(message),
+                false => ShuroError::Transcriber(message),
+            });
+        }
+        Ok(body)
+    }
+}
+
+/// Multipart form body with text `fields` and the audio as the `file` field
+fn multipart_body(boundary: &str, fields: &[(&str, String)], file_name: &str, audio: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(audio.len() + 512);
+    for (name, value) in fields {
+        body.extend_from_slice(format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n", boundary, name, value
+        ).as_bytes());
+    }
+    body.extend_from_slice(format!(
+        "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\nContent-Type: audio/wav\r\n\r\n",
+        boundary, file_name
+    ).as_bytes());
+    body.extend_from_slice(audio);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+/// Transcription of an `/inference` response, mapped like whisper-cli's JSON output
+///
+/// The server names the language ("english"); it becomes the requested language code, or the
+/// acceptable language with that name.
+fn parse_inference(body: &str, language: Option<&str>, word_timestamps: bool, acceptable_languages: &str) -> Result<Transcription> {
+    let value: serde_json::Value = serde_json::from_str(body)
+        .map_err(|e| ShuroError::Transcriber(format!("Invalid whisper-server response: {}", e)))?;
+    if let Some(error) = value.get("error") {
+        return Err(ShuroError::Transcriber(format!("whisper-server failed: {}", error)));
+    }
+    let response: InferenceResponse = serde_json::from_value(value)
+        .map_err(|e| ShuroError::Transcriber(format!("Unexpected whisper-server response: {}", e)))?;
+
+    let language = match language {
+        Some(code) => code.to_string(),
+        None => acceptable_languages.split(',')
+            .map(str::trim)
+            .find(|code| language_code_to_name(code).eq_ignore_ascii_case(&response.language))
+            .map(str::to_string)
+            .unwrap_or(response.language),
+    };
+    let offsets = |start: f64, end: f64| WhisperCppOffsets {
+        from: (start.max(0.0) * 1000.0).round() as u64,
+        to: (end.max(0.0) * 1000.0).round() as u64,
+    };
+    let output = WhisperCppOutput {
+        result: WhisperCppResult { language },
+        transcription: response.segments.into_iter()
+            .map(|segment| WhisperCppSegment {
+                timestamps: WhisperCppTimestamps { from: String::new(), to: String::new() },
+                offsets: offsets(segment.start, segment.end),
+                text: segment.text,
+                tokens: if word_timestamps {
+                    segment.words.into_iter()
+                        .map(|word| WhisperCppToken { offsets: offsets(word.start, word.end), text: word.word })
+                        .collect()
+                } else {
+                    Vec::new()
+                },
+            })
+            .collect(),
+    };
+    let abstract_transcription = WhisperCppMapper::to_abstract_transcription(output)?;
+    Ok(WhisperCppMapper::to_legacy_transcription(abstract_transcription))
+}
+
+/// whisper.cpp transcriber whose whisper passes go to a `whisper-server`
+pub struct WhisperCppServerTranscriber {
+    inner: WhisperCppTranscriber,
+}
+
+impl WhisperCppServerTranscriber {
+    pub fn new(config: TranscriberConfig, validator: QualityValidator, retry: Arc<RetryPolicy>) -> Self {
+        let server = WhisperServer::new(&config, retry);
+        Self { inner: WhisperCppTranscriber::with_server(config, validator, server) }
+    }
+}
+
+#[async_trait]
+impl TranscriberTrait for WhisperCppServerTranscriber {
+    async fn transcribe(&self, audio_path: &Path, language: Option<&str>, cancel: &Cancellation) -> Result<Transcription> {
+        self.inner.transcribe(audio_path, language, cancel).await
+    }
+
+    async fn tune_transcription(&self, video_path: &Path, cancel: &Cancellation) -> Result<TuneResult> {
+        self.inner.tune_transcription(video_path, cancel).await
+    }
+
+    async fn tune_transcription_streaming(
+        &self,
+        video_path: &Path,
+        segments: SegmentSender,
+        cancel: &Cancellation,
+    ) -> Result<TuneResult> {
+        self.inner.tune_transcription_streaming(video_path, segments, cancel).await
+    }
+
+    async fn extract_and_cache_audio(&self, video_path: &Path, cancel: &Cancellation) -> Result<PathBuf> {
+        self.inner.extract_and_cache_audio(video_path, cancel).await
+    }
+
+    async fn get_cached_audio(&self, video_path: &Path) -> Result<Option<PathBuf>> {
+        self.inner.get_cached_audio(video_path).await
+    }
+
+    async fn detect_language(&self, audio_path: &Path, cancel: &Cancellation) -> Result<Option<String>> {
+        self.inner.detect_language(audio_path, cancel).await
+    }
+
+    async fn clear_cache(&self) -> Result<u64> {
+        self.inner.clear_cache().await
+    }
+
+    async fn list_cache(&self) -> Result<Vec<TranscriptionCache>> {
+        self.inner.list_cache().await
+    }
+
+    async fn cache_info(&self) -> Result<CacheInfo> {
+        self.inner.cache_info().await
+    }
+
+    async fn clear_audio_cache(&self) -> Result<u64> {
+        self.inner.clear_audio_cache().await
+    }
+
+    async fn list_audio_cache(&self) -> Result<Vec<AudioCache>> {
+        self.inner.list_audio_cache().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_inference() {
+        let body = r#"{
+            "task": "transcribe", "language": "japanese", "duration": 3.0, "text": "こんにちは 東京",
+            "segments": [{
+                "id": 0, "text": " こんにちは東京", "start": 0.5, "end": 2.25,
+                "words": [
+                    {"word": "こんにちは", "start": 0.5, "end": 1.5, "probability": 0.9},
+                    {"word": "東", "start": 1.5, "end": 1.9, "probability": 0.8},
+                    {"word": "京", "start": 1.9, "end": 2.25, "probability": 0.8}
+                ],
+                "temperature": 0.0, "avg_logprob": -0.2, "no_speech_prob": 0.01
+            }]
+        }"#;
+        let transcription = parse_inference(body, None, true, "en,ja,ko").unwrap();
+        assert_eq!(transcription.language, "ja");
+        assert_eq!(transcription.segments.len(), 1);
+        let segment = &transcription.segments[0];
+        assert_eq!((segment.start, segment.end, segment.text.as_str()), (0.5, 2.25, "こんにちは東京"));
+        assert_eq!(segment.words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>(), vec!["こんにちは", "東", "京"]);
+
+        let plain = parse_inference(body, Some("ja"), false, "en").unwrap();
+        assert_eq!(plain.language, "ja");
+        assert!(plain.segments[0].words.is_empty());
+
+        assert!(parse_inference(r#"{"error": "failed to read audio"}"#, None, true, "en").is_err());
+
+        let body = multipart_body("b", &[("language", "ja".to_string())], "a.wav", b"RIFF");
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "--b\r\nContent-Disposition: form-data; name=\"language\"\r\n\r\nja\r\n\
+             --b\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.wav\"\r\nContent-Type: audio/wav\r\n\r\nRIFF\r\n--b--\r\n"
+        );
+    }
+}
@@ -0,0 +1,317 @@
+// Transcript files
+//
+// `shuro transcript export` writes the transcription of a video (or a subtitle file) as a
+// versioned JSON document, so external tools can fix names, merge lines or add speakers between
+// the transcribe and translate stages; `shuro transcript import` checks an edited file and
+// `shuro process --transcript` translates it instead of transcribing the video. The document is
+// decoupled from the in-memory `Transcription`, whose fields change with the transcribers:
+//
+// ```json
+// {
+//   "format": "shuro-transcript",
+//   "version": 1,
+//   "language": "en",
+//   "duration": 1325.4,
+//   "model": "large-v3-turbo",
+//   "segments": [
+//     {
+//       "id": 0, "start": 1.0, "end": 2.5, "text": "Hello.",
+//       "words": [{"text": "Hello.", "start": 1.0, "end": 2.4}],
+//       "metadata": {"speaker": "Ann", "confidence": 0.93},
+//       "decoder": {"tokens": [50364], "temperature": 0.0, "avg_logprob": -0.2, "compression_ratio": 1.1, "no_speech_prob": 0.01}
+//     }
+//   ]
+// }
+// ```
+//
+// Only `start`, `end` and `text` are required of a segment; ids are renumbered on import, so
+// segments can be added and removed freely. `metadata` holds the entries of metadata.rs by name.
+// `decoder` carries whisper's statistics for the hallucination checks and is dropped by editors
+// that do not know it. Minor additions keep the version; a change that would make older builds
+// misread a file increments it, and files of a newer version than a build knows are rejected
+// instead of being half-read. Import rejects segments with negative, reversed or out-of-order
+// times, empty text and metadata entries of the wrong type, naming the offending segment.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::fs;
+
+use crate::error::{Result, ShuroError};
+use crate::metadata::{CONFIDENCE, DELIVERY, HALLUCINATION, SOURCE_LANGUAGE, SPEAKER, SegmentMetadata};
+use crate::quality::{Transcription, TranscriptionSegment, WordTiming};
+
+/// Value of the `format` field
+pub const FORMAT: &str = "shuro-transcript";
+
+/// Schema version written by this build, and the newest one it reads
+pub const VERSION: u32 = 1;
+
+/// Extension of transcript files, e.g. `episode01.transcript.json`
+pub const EXTENSION: &str = "transcript.json";
+
+/// On-disk transcript document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptFile {
+    pub format: String,
+    pub version: u32,
+    pub language: String,
+    /// Length of the transcribed media in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    /// Model or source that produced the transcript
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub segments: Vec<TranscriptSegment>,
+}
+
+/// One cue of a transcript document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    #[serde(default)]
+    pub id: i32,
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<TranscriptWord>,
+    #[serde(default, skip_serializing_if = "SegmentMetadata::is_empty")]
+    pub metadata: SegmentMetadata,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decoder: Option<DecoderStats>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptWord {
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Whisper's decoding statistics of a segment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecoderStats {
+    #[serde(default)]
+    pub tokens: Vec<i32>,
+    pub temperature: f32,
+    pub avg_logprob: f32,
+    pub compression_ratio: f32,
+    pub no_speech_prob: f32,
+}
+
+/// Header fields a document needs before its version can be trusted
+#[derive(Debug, Deserialize)]
+struct Header {
+    format: Option<String>,
+    version: Option<u32>,
+}
+
+impl TranscriptFile {
+    /// Document of `transcription`
+    pub fn new(transcription: &Transcription, duration: Option<f64>, model: Option<String>) -> Self {
+        let segments = transcription.segments
+            .iter()
+            .map(|segment| TranscriptSegment {
+                id: segment.id,
+                start: segment.start,
+                end: segment.end,
+                text: segment.text.clone(),
+                words: segment.words
+                    .iter()
+                    .map(|w| TranscriptWord { text: w.text.clone(), start: w.start, end: w.end })
+                    .collect(),
+                metadata: segment.metadata.clone(),
+                decoder: Some(DecoderStats {
+                    tokens: segment.tokens.clone(),
+                    temperature: segment.temperature,
+                    avg_logprob: segment.avg_logprob,
+                    compression_ratio: segment.compression_ratio,
+                    no_speech_prob: segment.no_speech_prob,
+                }),
+            })
+            .collect();
+        Self {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            language: transcription.language.clone(),
+            duration,
+            model,
+            segments,
+        }
+    }
+
+    /// Parse and validate a document
+    pub fn parse(json: &str) -> Result<Self> {
+        let header: Header = serde_json::from_str(json)
+            .map_err(|e| ShuroError::Transcript(format!("not a JSON document: {}", e)))?;
+        if header.format.as_deref() != Some(FORMAT) {
+            return Err(ShuroError::Transcript(format!("missing \"format\": \"{}\"", FORMAT)));
+        }
+        match header.version {
+            None | Some(0) => return Err(ShuroError::Transcript("missing \"version\"".to_string())),
+            Some(version) if version > VERSION => return Err(ShuroError::Transcript(format!(
+                "version {} was written by a newer shuro; this build reads up to version {}", version, VERSION
+            ))),
+            Some(_) => {}
+        }
+
+        let file: Self = serde_json::from_str(json)
+            .map_err(|e| ShuroError::Transcript(e.to_string()))?;
+        file.validate()?;
+        Ok(file)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.language.trim().is_empty() {
+            return Err(ShuroError::Transcript("empty \"language\"".to_string()));
+        }
+        let mut previous_start = 0.0;
+        for (index, segment) in self.segments.iter().enumerate() {
+            let invalid = |problem: String| ShuroError::Transcript(format!("segment {} ({:.3}s): {}", index + 1, segment.start, problem));
+            if !(segment.start.is_finite() && segment.end.is_finite()) || segment.start < 0.0 {
+                return Err(invalid("start and end must be non-negative seconds".to_string()));
+            }
+            if segment.end < segment.start {
+                return Err(invalid(format!("ends at {:.3}s, before it starts", segment.end)));
+            }
+            if segment.start < previous_start {
+                return Err(invalid(format!("starts before the previous segment ({:.3}s)", previous_start)));
+            }
+            if segment.text.trim().is_empty() {
+                return Err(invalid("empty text; remove the segment instead".to_string()));
+            }
+            if let Some(word) = segment.words.iter().find(|w| !(w.start.is_finite() && w.end.is_finite()) || w.end < w.start) {
+                return Err(invalid(format!("word \"{}\" has invalid times", word.text)));
+            }
+            check_metadata(&segment.metadata).map_err(invalid)?;
+            previous_start = segment.start;
+        }
+        Ok(())
+    }
+
+    /// Transcription of the document, with segments numbered from 0
+    pub fn into_transcription(self) -> Transcription {
+        let segments: Vec<TranscriptionSegment> = self.segments
+            .into_iter()
+            .enumerate()
+            .map(|(index, segment)| {
+                let decoder = segment.decoder.unwrap_or(DecoderStats {
+                    tokens: Vec::new(),
+                    temperature: 0.0,
+                    avg_logprob: 0.0,
+                    compression_ratio: 1.0,
+                    no_speech_prob: 0.0,
+                });
+                TranscriptionSegment {
+                    id: index as i32,
+                    start: segment.start,
+                    end: segment.end,
+                    text: segment.text,
+                    tokens: decoder.tokens,
+                    temperature: decoder.temperature,
+                    avg_logprob: decoder.avg_logprob,
+                    compression_ratio: decoder.compression_ratio,
+                    no_speech_prob: decoder.no_speech_prob,
+                    words: segment.words
+                        .into_iter()
+                        .map(|w| WordTiming { text: w.text, start: w.start, end: w.end })
+                        .collect(),
+                    metadata: segment.metadata,
+                }
+            })
+            .collect();
+        Transcription {
+            text: segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" "),
+            segments,
+            language: self.language,
+        }
+    }
+}
+
+/// Problem with an entry of metadata.rs that holds a value of the wrong type
+fn check_metadata(metadata: &SegmentMetadata) -> std::result::Result<(), String> {
+    let wrong_type = |name: &str, expected: &str| format!("metadata \"{}\" must be {}", name, expected);
+    if metadata.contains(&SPEAKER) && metadata.get(&SPEAKER).is_none() {
+        return Err(wrong_type(SPEAKER.name(), "a string"));
+    }
+    if metadata.contains(&SOURCE_LANGUAGE) && metadata.get(&SOURCE_LANGUAGE).is_none() {
+        return Err(wrong_type(SOURCE_LANGUAGE.name(), "a language code"));
+    }
+    for key in [&CONFIDENCE, &HALLUCINATION] {
+        if metadata.contains(key) && !metadata.get(key).is_some_and(|v| (0.0..=1.0).contains(&v)) {
+            return Err(wrong_type(key.name(), "a number from 0 to 1"));
+        }
+    }
+    if metadata.contains(&DELIVERY) && metadata.get(&DELIVERY).is_none() {
+        return Err(wrong_type(DELIVERY.name(), "shouting, whispering or singing"));
+    }
+    Ok(())
+}
+
+/// Write `transcription` as a transcript document
+pub async fn write_transcript<P: AsRef<Path>>(
+    transcription: &Transcription,
+    duration: Option<f64>,
+    model: Option<String>,
+    path: P,
+) -> Result<()> {
+    let file = TranscriptFile::new(transcription, duration, model);
+    fs::write(path, serde_json::to_string_pretty(&file)?).await?;
+    Ok(())
+}
+
+/// Read and validate a transcript document
+pub async fn read_transcript<P: AsRef<Path>>(path: P) -> Result<TranscriptFile> {
+    let path = path.as_ref();
+    let content = fs::read_to_string(path).await?;
+    TranscriptFile::parse(&content).map_err(|e| match e {
+        ShuroError::Transcript(problem) => ShuroError::Transcript(format!("{}: {}", path.display(), problem)),
+        e => e,
+    })
+}
+
+/// Whether `path` names a transcript document
+pub fn is_transcript_path(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.to_lowercase().ends_with(&format!(".{}", EXTENSION)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::parse_srt;
+
+    #[test]
+    fn test_transcript_round_trip() {
+        let mut transcription = parse_srt("1\n00:00:01,000 --> 00:00:02,500\nHello\n\n2\n00:00:03,000 --> 00:00:04,000\nBye\n", "en").unwrap();
+        transcription.segments[0].metadata.insert(&SPEAKER, "Ann".to_string());
+        transcription.segments[1].avg_logprob = -0.4;
+
+        let json = serde_json::to_string(&TranscriptFile::new(&transcription, Some(4.0), None)).unwrap();
+        assert!(json.starts_with(r#"{"format":"shuro-transcript","version":1,"language":"en","duration":4.0,"segments":"#));
+        let imported = TranscriptFile::parse(&json).unwrap().into_transcription();
+        assert_eq!(imported.segments[0].metadata.get(&SPEAKER).as_deref(), Some("Ann"));
+        assert_eq!((imported.segments[1].start, imported.segments[1].avg_logprob), (3.0, -0.4));
+        assert_eq!(imported.text, "Hello Bye");
+
+        // Hand-written documents need only times and text; ids are renumbered
+        let edited = r#"{"format": "shuro-transcript", "version": 1, "language": "en", "segments": [
+            {"id": 7, "start": 0.5, "end": 1.0, "text": "Hi"}, {"start": 1.0, "end": 2.0, "text": "there", "extra": true}]}"#;
+        let imported = TranscriptFile::parse(edited).unwrap().into_transcription();
+        assert_eq!(imported.segments.iter().map(|s| s.id).collect::<Vec<_>>(), vec![0, 1]);
+
+        let newer = edited.replace("\"version\": 1", "\"version\": 2");
+        assert!(TranscriptFile::parse(&newer).unwrap_err().to_string().contains("newer shuro"));
+        let reversed = edited.replace("\"end\": 2.0", "\"end\": 0.8");
+        assert_eq!(
+            TranscriptFile::parse(&reversed).unwrap_err().to_string(),
+            "Invalid transcript: segment 2 (1.000s): ends at 0.800s, before it starts"
+        );
+        let speaker = edited.replace("\"extra\": true", "\"metadata\": {\"speaker\": 3}");
+        assert!(TranscriptFile::parse(&speaker).unwrap_err().to_string().contains("metadata \"speaker\" must be a string"));
+        assert!(TranscriptFile::parse(r#"{"segments": []}"#).is_err());
+
+        assert!(is_transcript_path(Path::new("dir/Episode.01.transcript.json")));
+        assert!(!is_transcript_path(Path::new("manifest.shuro.json")));
+    }
+}
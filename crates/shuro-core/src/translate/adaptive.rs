@@ -0,0 +1,164 @@
+// Adaptive translation mode
+//
+// Most subtitle lines are short and unambiguous ("Yes.", "Come here!") and translate as well on
+// their own as with context, while long lines and lines the transcriber was unsure of profit from
+// their neighbors. Adaptive mode splits a transcription into easy segments, translated by the
+// Simple strategy, and stretches of long or uncertain segments, each translated by the strategy
+// of `adaptive.escalation` (Context or NLP) as a transcription of its own. A segment is escalated
+// when it is longer than `adaptive.max_simple_chars`, or its confidence is below
+// `adaptive.min_confidence` or it is flagged as a hallucination. Up to `adaptive.bridge_segments`
+// easy segments between two escalated stretches join them, so a stretch keeps its context.
+
+use async_trait::async_trait;
+use std::ops::Range;
+use tracing::{info, warn};
+
+use crate::cancel::Cancellation;
+use crate::config::{AdaptiveConfig, TranslateConfig, TranslationMode};
+use crate::error::Result;
+use crate::metadata::{CONFIDENCE, HALLUCINATION};
+use crate::quality::{Transcription, TranscriptionSegment};
+use super::{Translator, context::ContextTranslator, nlp::NlpTranslator, services::TranslationServices, simple::SimpleTranslator};
+
+/// Adaptive translation: Simple translation for easy segments, escalated translation for long or
+/// uncertain stretches
+pub struct AdaptiveTranslator {
+    adaptive: AdaptiveConfig,
+    simple: SimpleTranslator,
+    escalated: Box<dyn Translator>,
+    escalation: &'static str,
+}
+
+impl AdaptiveTranslator {
+    pub fn new(config: TranslateConfig, services: TranslationServices) -> Self {
+        let adaptive = config.adaptive.clone();
+        let (escalated, escalation): (Box<dyn Translator>, _) = match adaptive.escalation {
+            TranslationMode::Nlp => (Box::new(NlpTranslator::new(config.clone(), services.clone())), "NLP"),
+            TranslationMode::Context => (Box::new(ContextTranslator::new(config.clone(), services.clone())), "Context"),
+            ref other => {
+                warn!("Adaptive mode cannot escalate to {:?}, using Context", other);
+                (Box::new(ContextTranslator::new(config.clone(), services.clone())), "Context")
+            }
+        };
+        Self {
+            adaptive,
+            simple: SimpleTranslator::new(config, services),
+            escalated,
+            escalation,
+        }
+    }
+}
+
+/// Whether a segment is translated by the escalation strategy
+fn needs_escalation(segment: &TranscriptionSegment, adaptive: &AdaptiveConfig) -> bool {
+    let uncertain = segment.metadata.get(&CONFIDENCE).is_some_and(|c| c < adaptive.min_confidence)
+        || segment.metadata.contains(&HALLUCINATION);
+    uncertain || segment.text.trim().chars().count() > adaptive.max_simple_chars
+}
+
+/// Index ranges of the escalated stretches, with bridged easy segments included
+fn escalated_stretches(segments: &[TranscriptionSegment], adaptive: &AdaptiveConfig) -> Vec<Range<usize>> {
+    let mut stretches: Vec<Range<usize>> = Vec::new();
+    for (index, segment) in segments.iter().enumerate() {
+        if !needs_escalation(segment, adaptive) {
+            continue;
+        }
+        match stretches.last_mut() {
+            Some(last) if index - last.end <= adaptive.bridge_segments => last.end = index + 1,
+            _ => stretches.push(index..index + 1),
+        }
+    }
+    stretches
+}
+
+/// Transcription of the given segments, keeping the language and text of `transcription`
+fn sub_transcription(transcription: &Transcription, segments: Vec<TranscriptionSegment>) -> Transcription {
+    Transcription {
+        text: transcription.text.clone(),
+        segments,
+        language: transcription.language.clone(),
+    }
+}
+
+#[async_trait]
+impl Translator for AdaptiveTranslator {
+    fn retries(&self) -> usize {
+        self.simple.retries() + self.escalated.retries()
+    }
+
+    fn cache_hits(&self) -> usize {
+        self.simple.cache_hits() + self.escalated.cache_hits()
+    }
+
+    /// Adaptive translation: Simple translation for easy segments, escalated stretches in turn
+    async fn translate_transcription(
+        &mut self,
+        transcription: &mut Transcription,
+        target_language: &str,
+        context: Option<&str>,
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        let stretches = escalated_stretches(&transcription.segments, &self.adaptive);
+        let escalated: usize = stretches.iter().map(|s| s.len()).sum();
+        info!("Adaptive translation to {}: {} of {} segments in {} stretches escalated to {} mode",
+              target_language, escalated, transcription.segments.len(), stretches.len(), self.escalation);
+
+        let easy: Vec<usize> = (0..transcription.segments.len())
+            .filter(|index| !stretches.iter().any(|s| s.contains(index)))
+            .collect();
+        if !easy.is_empty() {
+            let segments = easy.iter().map(|&index| transcription.segments[index].clone()).collect();
+            let mut simple = sub_transcription(transcription, segments);
+            self.simple.translate_transcription(&mut simple, target_language, context, cancel).await?;
+            for (index, segment) in easy.into_iter().zip(simple.segments) {
+                transcription.segments[index].text = segment.text;
+            }
+        }
+
+        for stretch in stretches {
+            let mut escalated = sub_transcription(transcription, transcription.segments[stretch.clone()].to_vec());
+            self.escalated.translate_transcription(&mut escalated, target_language, context, cancel).await?;
+            for (segment, translated) in transcription.segments[stretch].iter_mut().zip(escalated.segments) {
+                segment.text = translated.text;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::parse_srt;
+
+    #[test]
+    fn test_escalated_stretches() {
+        let lines = [
+            "Yes.",
+            "Come here!",
+            "I told you the bridge would not hold the weight of the whole convoy.",
+            "Why?",
+            "Because the engineers never finished the second support beam.",
+            "Run.",
+            "Now!",
+            "Wait.",
+        ];
+        let srt: String = lines.iter().enumerate()
+            .map(|(i, line)| format!("{}\n00:00:{:02},000 --> 00:00:{:02},500\n{}\n\n", i + 1, i, i, line))
+            .collect();
+        let mut transcription = parse_srt(&srt, "en").unwrap();
+        let adaptive = AdaptiveConfig::default();
+
+        // "Why?" bridges the two long lines
+        assert_eq!(escalated_stretches(&transcription.segments, &adaptive), vec![2..5]);
+
+        transcription.segments[6].metadata.insert(&CONFIDENCE, 0.3);
+        assert_eq!(escalated_stretches(&transcription.segments, &adaptive), vec![2..7]);
+
+        let no_bridges = AdaptiveConfig { bridge_segments: 0, ..AdaptiveConfig::default() };
+        assert_eq!(escalated_stretches(&transcription.segments, &no_bridges), vec![2..3, 4..5, 6..7]);
+
+        let everything = AdaptiveConfig { max_simple_chars: 0, ..AdaptiveConfig::default() };
+        assert_eq!(escalated_stretches(&transcription.segments, &everything), vec![0..8]);
+    }
+}
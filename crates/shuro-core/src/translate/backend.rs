@@ -0,0 +1,235 @@
+// Model backends
+//
+// A translation strategy (simple, context, nlp, llm) decides what to ask the model; a backend
+// decides where the request goes. Strategies, judges, translator's notes and title lookups send
+// every model request through the `ModelBackend` of their `TranslationServices` (see
+// services.rs), so any strategy runs on any backend. `translate.provider` names the backend: the built-in "Ollama" (the endpoint pool of
+// balancer.rs) and "OpenAI" (chat completions, see openai.rs), or one a downstream crate
+// registers in a `BackendRegistry` passed to `Workflow::with_registries`.
+//
+// Backends only answer generate requests; everything else is optional. Unloading models and
+// reporting a model's context length default to doing nothing, and preflight defaults to
+// recording `check_models`. The semantic cache's embeddings always go to the Ollama endpoints.
+//
+// Whatever the backend, generate requests failing transiently are retried by the workflow's retry
+// policy (see retry.rs), and the tokens of their responses are counted against its cost budget
+// (see cost.rs); both wrap the backend when it is created.
+
+use async_trait::async_trait;
+use reqwest::Client;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::config::TranslateConfig;
+use crate::cost::CostTracker;
+use crate::error::{Result, ShuroError};
+use crate::preflight::PreflightReport;
+use crate::retry::RetryPolicy;
+use super::balancer::EndpointPool;
+use super::common::{TranslationRequest, TranslationResponse};
+use super::openai::ChatCompletions;
+
+/// Provider name of the Ollama backend
+pub const OLLAMA: &str = "Ollama";
+/// Provider name of the OpenAI-compatible chat completions backend
+pub const OPENAI: &str = "OpenAI";
+
+/// Service answering the model requests of translators
+#[async_trait]
+pub trait ModelBackend: Send + Sync + Debug {
+    /// Answer a generate request; the response carries the model's text and token counts
+    async fn generate(&self, client: &Client, request: &TranslationRequest) -> Result<TranslationResponse>;
+
+    /// Check that `models` can be used, before the first request of a run
+    async fn check_models(&self, models: &[&str]) -> Result<()>;
+
+    /// Free the memory held by `models`, before whisper needs the GPU
+    async fn unload(&self, _models: &[&str]) -> Result<()> {
+        Ok(())
+    }
+
+    /// Context length of `model`, when the backend reports it
+    async fn context_length(&self, _model: &str) -> Result<Option<usize>> {
+        Ok(None)
+    }
+
+    /// Record the checks of `shuro preflight` for `models` into `report`
+    async fn preflight(&self, models: &[&str], report: &mut PreflightReport) {
+        let outcome = self.check_models(models).await.map(|_| format!("{} available", models.join(", ")));
+        report.record("Translation backend", outcome);
+    }
+}
+
+/// Constructor registered for a provider name; it gets the configuration and the workflow's pool
+/// of Ollama endpoints
+pub type BackendConstructor =
+    Arc<dyn Fn(&TranslateConfig, &Arc<EndpointPool>) -> Result<Arc<dyn ModelBackend>> + Send + Sync>;
+
+/// Name-to-constructor registry used to pick the backend named by `translate.provider`
+#[derive(Clone, Default)]
+pub struct BackendRegistry {
+    constructors: HashMap<String, BackendConstructor>,
+}
+
+impl BackendRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with the Ollama and OpenAI backends
+    pub fn with_builtin() -> Self {
+        Self::new()
+            .register(OLLAMA, |_config, pool| Ok(pool.clone() as Arc<dyn ModelBackend>))
+            .register(OPENAI, |config, _pool| Ok(Arc::new(ChatCompletions::new(&config.openai)) as Arc<dyn ModelBackend>))
+    }
+
+    /// Register a backend constructor under a provider name, replacing any previous registration
+    pub fn register<F>(mut self, name: &str, constructor: F) -> Self
+    where
+        F: Fn(&TranslateConfig, &Arc<EndpointPool>) -> Result<Arc<dyn ModelBackend>> + Send + Sync + 'static,
+    {
+        self.constructors.insert(name.to_string(), Arc::new(constructor));
+        self
+    }
+
+    /// Registered names in sorted order
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.constructors.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Create the backend named by `provider`, its generate requests retried by `policy` and
+    /// metered by `tracker`
+    pub fn create(
+        &self,
+        config: &TranslateConfig,
+        pool: &Arc<EndpointPool>,
+        policy: Arc<RetryPolicy>,
+        tracker: Arc<CostTracker>,
+    ) -> Result<Arc<dyn ModelBackend>> {
+        let constructor = self.constructors.get(&config.provider).ok_or_else(|| ShuroError::Config(format!(
+            "Unknown translation provider '{}'. Registered providers: {}",
+            config.provider,
+            self.names().join(", ")
+        )))?;
+        let backend = constructor(config, pool)?;
+        Ok(Arc::new(Metered { backend: Arc::new(Retrying { backend, policy }), tracker }))
+    }
+}
+
+/// Backend counting the tokens of the generate requests of another, refusing requests once the
+/// budget is spent
+#[derive(Debug)]
+struct Metered {
+    backend: Arc<dyn ModelBackend>,
+    tracker: Arc<CostTracker>,
+}
+
+#[async_trait]
+impl ModelBackend for Metered {
+    async fn generate(&self, client: &Client, request: &TranslationRequest) -> Result<TranslationResponse> {
+        self.tracker.check()?;
+        let response = self.backend.generate(client, request).await?;
+        self.tracker.record(&request.model, &response.usage());
+        Ok(response)
+    }
+
+    async fn check_models(&self, models: &[&str]) -> Result<()> {
+        self.backend.check_models(models).await
+    }
+
+    async fn unload(&self, models: &[&str]) -> Result<()> {
+        self.backend.unload(models).await
+    }
+
+    async fn context_length(&self, model: &str) -> Result<Option<usize>> {
+        self.backend.context_length(model).await
+    }
+
+    async fn preflight(&self, models: &[&str], report: &mut PreflightReport) {
+        self.backend.preflight(models, report).await
+    }
+}
+
+/// Backend retrying the generate requests of another by a retry policy
+#[derive(Debug)]
+struct Retrying {
+    backend: Arc<dyn ModelBackend>,
+    policy: Arc<RetryPolicy>,
+}
+
+#[async_trait]
+impl ModelBackend for Retrying {
+    async fn generate(&self, client: &Client, request: &TranslationRequest) -> Result<TranslationResponse> {
+        self.policy.run("Translation model", || self.backend.generate(client, request)).await
+    }
+
+    async fn check_models(&self, models: &[&str]) -> Result<()> {
+        self.backend.check_models(models).await
+    }
+
+    async fn unload(&self, models: &[&str]) -> Result<()> {
+        self.backend.unload(models).await
+    }
+
+    async fn context_length(&self, model: &str) -> Result<Option<usize>> {
+        self.backend.context_length(model).await
+    }
+
+    async fn preflight(&self, models: &[&str], report: &mut PreflightReport) {
+        self.backend.preflight(models, report).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[derive(Debug)]
+    struct Echo;
+
+    #[async_trait]
+    impl ModelBackend for Echo {
+        async fn generate(&self, _client: &Client, request: &TranslationRequest) -> Result<TranslationResponse> {
+            Ok(TranslationResponse {
+                response: request.prompt.clone(),
+                done: true,
+                prompt_eval_count: None,
+                eval_count: None,
+                eval_duration: None,
+            })
+        }
+
+        async fn check_models(&self, _models: &[&str]) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backend_registry() {
+        let registry = BackendRegistry::with_builtin()
+            .register("echo", |_config, _pool| Ok(Arc::new(Echo) as Arc<dyn ModelBackend>));
+        assert_eq!(registry.names(), vec!["Ollama", "OpenAI", "echo"]);
+
+        let mut config = Config::default().translate;
+        config.provider = "echo".to_string();
+        let pool = Arc::new(EndpointPool::new(&config));
+        let backend = registry.create(&config, &pool, Arc::default(), Arc::default()).unwrap();
+        let request = TranslationRequest {
+            model: config.model.clone(),
+            prompt: "Hello".to_string(),
+            stream: false,
+            format: serde_json::json!("json"),
+            options: None,
+        };
+        assert_eq!(backend.generate(&Client::new(), &request).await.unwrap().response, "Hello");
+        assert_eq!(backend.context_length(&config.model).await.unwrap(), None);
+
+        config.provider = "missing".to_string();
+        assert!(registry.create(&config, &pool, Arc::default(), Arc::default()).is_err());
+    }
+}
@@ -0,0 +1,259 @@
+// Ollama endpoint load balancing
+//
+// `translate.endpoints` lists Ollama instances, e.g. one per GPU or machine. Every request is sent
+// to one of them: in turn (`RoundRobin`) or to the one with the fewest requests in flight
+// (`LeastBusy`). An endpoint that refuses a connection is skipped for `health_check_interval`
+// seconds and the request moves on to the next one; once the interval has passed the endpoint is
+// tried again. All translators, judges and semantic caches of a workflow share one pool, so
+// concurrent requests (segments, target languages, background evaluations) spread across the
+// instances. The pool is the "Ollama" translation backend (see backend.rs); the semantic cache
+// posts its embedding requests to it whatever the backend.
+
+use async_trait::async_trait;
+use reqwest::{Client, Response};
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::config::{LoadBalancing, TranslateConfig};
+use crate::error::{Result, ShuroError, is_transient_status};
+use crate::preflight::{self, PreflightReport};
+use super::backend::ModelBackend;
+use super::common::{check_ollama_availability, TranslationRequest, TranslationResponse};
+use super::sizing::model_context_length;
+
+#[derive(Debug)]
+struct Endpoint {
+    url: String,
+    in_flight: AtomicUsize,
+    unhealthy_until: Mutex<Option<Instant>>,
+}
+
+impl Endpoint {
+    fn is_healthy(&self) -> bool {
+        self.unhealthy_until
+            .lock()
+            .expect("endpoint health lock poisoned")
+            .is_none_or(|until| Instant::now() >= until)
+    }
+}
+
+/// Ollama endpoints that requests are dispatched across
+#[derive(Debug)]
+pub struct EndpointPool {
+    endpoints: Vec<Endpoint>,
+    strategy: LoadBalancing,
+    retry_after: Duration,
+    next: AtomicUsize,
+}
+
+/// An endpoint picked for one request; counts as in flight until dropped
+pub struct EndpointLease<'a> {
+    pool: &'a EndpointPool,
+    index: usize,
+}
+
+impl EndpointLease<'_> {
+    pub fn url(&self) -> &str {
+        &self.pool.endpoints[self.index].url
+    }
+}
+
+impl Drop for EndpointLease<'_> {
+    fn drop(&mut self) {
+        self.pool.endpoints[self.index].in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl EndpointPool {
+    pub fn new(config: &TranslateConfig) -> Self {
+        Self {
+            endpoints: config.endpoint_urls().into_iter().map(|url| Endpoint {
+                url: url.trim_end_matches('/').to_string(),
+                in_flight: AtomicUsize::new(0),
+                unhealthy_until: Mutex::new(None),
+            }).collect(),
+            strategy: config.load_balancing,
+            retry_after: Duration::from_secs(config.health_check_interval),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn urls(&self) -> Vec<&str> {
+        self.endpoints.iter().map(|e| e.url.as_str()).collect()
+    }
+
+    /// Pick the endpoint for the next request
+    pub fn acquire(&self) -> EndpointLease<'_> {
+        let in_flight: Vec<usize> = self.endpoints.iter().map(|e| e.in_flight.load(Ordering::SeqCst)).collect();
+        let healthy: Vec<bool> = self.endpoints.iter().map(Endpoint::is_healthy).collect();
+        let start = self.next.fetch_add(1, Ordering::SeqCst);
+        let index = choose_endpoint(self.strategy, &in_flight, &healthy, start);
+
+        self.endpoints[index].in_flight.fetch_add(1, Ordering::SeqCst);
+        EndpointLease { pool: self, index }
+    }
+
+    /// Skip an endpoint until `health_check_interval` has passed
+    fn mark_unhealthy(&self, index: usize) {
+        *self.endpoints[index].unhealthy_until.lock().expect("endpoint health lock poisoned") =
+            Some(Instant::now() + self.retry_after);
+    }
+
+    /// POST `body` to `path` on the next endpoint, moving on to the others when it cannot be reached
+    pub async fn post<T: Serialize + ?Sized>(&self, client: &Client, path: &str, body: &T) -> reqwest::Result<Response> {
+        let mut remaining = self.endpoints.len();
+        loop {
+            let lease = self.acquire();
+            match client.post(format!("{}{}", lease.url(), path)).json(body).send().await {
+                Err(e) if e.is_connect() && remaining > 1 => {
+                    warn!("Ollama endpoint {} is unreachable ({}), trying another", lease.url(), e);
+                    self.mark_unhealthy(lease.index);
+                    remaining -= 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Model information reported by `/api/show`
+    pub async fn show(&self, client: &Client, model: &str) -> Result<Response> {
+        self.post(client, "/api/show", &json!({ "name": model }))
+            .await
+            .map_err(|e| ShuroError::Translation(format!("Failed to connect to Ollama: {}", e)))
+    }
+}
+
+#[async_trait]
+impl ModelBackend for EndpointPool {
+    /// Send a model request to Ollama's `/api/generate`
+    async fn generate(&self, client: &Client, request: &TranslationRequest) -> Result<TranslationResponse> {
+        let response = self.post(client, "/api/generate", request)
+            .await
+            .map_err(|e| match e.is_connect() || e.is_timeout() {
+                true => ShuroError::Unavailable(format!("Ollama request failed: {}", e)),
+                false => ShuroError::Translation(format!("HTTP request failed: {}", e)),
+            })?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            let message = format!("Ollama API error {}: {}", status, error_text);
+            return Err(match is_transient_status(status) {
+                true => ShuroError::Unavailable(message),
+                false => ShuroError::Translation(message),
+            });
+        }
+        response.json().await
+            .map_err(|e| ShuroError::Translation(format!("Failed to parse response: {}", e)))
+    }
+
+    /// Check that `models` are available on every reachable endpoint; unreachable endpoints are
+    /// skipped, and it is an error when none can be reached
+    async fn check_models(&self, models: &[&str]) -> Result<()> {
+        let client = Client::new();
+        let mut reachable = 0;
+        for (index, endpoint) in self.endpoints.iter().enumerate() {
+            if let Err(e) = client.get(format!("{}/api/version", endpoint.url)).send().await {
+                warn!("Ollama endpoint {} is unreachable: {}", endpoint.url, e);
+                self.mark_unhealthy(index);
+                continue;
+            }
+            for model in models {
+                check_ollama_availability(&endpoint.url, model).await?;
+            }
+            reachable += 1;
+        }
+
+        if reachable == 0 {
+            return Err(ShuroError::Translation(format!(
+                "Failed to connect to Ollama at {}", self.urls().join(", ")
+            )));
+        }
+        if self.endpoints.len() > 1 {
+            info!("⚖️ Dispatching translation requests across {} of {} Ollama endpoints ({:?})",
+                reachable, self.endpoints.len(), self.strategy);
+        }
+        Ok(())
+    }
+
+    /// Unload `models` from every reachable endpoint, freeing their GPU memory
+    async fn unload(&self, models: &[&str]) -> Result<()> {
+        let client = Client::new();
+        for endpoint in self.endpoints.iter().filter(|e| e.is_healthy()) {
+            for model in models {
+                let response = client
+                    .post(format!("{}/api/generate", endpoint.url))
+                    .json(&json!({ "model": model, "keep_alive": 0 }))
+                    .send()
+                    .await
+                    .map_err(|e| ShuroError::Translation(format!("Failed to connect to Ollama: {}", e)))?;
+                if !response.status().is_success() {
+                    return Err(ShuroError::Translation(format!(
+                        "Failed to unload model '{}' from {}: {}", model, endpoint.url, response.status()
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn context_length(&self, model: &str) -> Result<Option<usize>> {
+        model_context_length(self, model).await
+    }
+
+    async fn preflight(&self, models: &[&str], report: &mut PreflightReport) {
+        preflight::check_ollama(&self.urls(), models, report).await;
+    }
+}
+
+/// Index of the endpoint to use, given the requests in flight on each and their health;
+/// `start` rotates the order so ties and round-robin turns move through the endpoints
+fn choose_endpoint(strategy: LoadBalancing, in_flight: &[usize], healthy: &[bool], start: usize) -> usize {
+    let count = in_flight.len();
+    let any_healthy = healthy.iter().any(|h| *h);
+    // With every endpoint marked unhealthy, try them all rather than fail without a request
+    let mut candidates = (0..count).map(|i| (start + i) % count).filter(|i| healthy[*i] || !any_healthy);
+
+    match strategy {
+        LoadBalancing::RoundRobin => candidates.next().unwrap_or(0),
+        LoadBalancing::LeastBusy => candidates.min_by_key(|i| in_flight[*i]).unwrap_or(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose_endpoint() {
+        let healthy = [true, true, true];
+        assert_eq!(choose_endpoint(LoadBalancing::RoundRobin, &[5, 0, 0], &healthy, 0), 0);
+        assert_eq!(choose_endpoint(LoadBalancing::RoundRobin, &[5, 0, 0], &healthy, 4), 1);
+        assert_eq!(choose_endpoint(LoadBalancing::LeastBusy, &[2, 1, 1], &healthy, 0), 1);
+        assert_eq!(choose_endpoint(LoadBalancing::LeastBusy, &[2, 1, 1], &healthy, 2), 2);
+
+        let degraded = [true, false, true];
+        assert_eq!(choose_endpoint(LoadBalancing::RoundRobin, &[0, 0, 0], &degraded, 1), 2);
+        assert_eq!(choose_endpoint(LoadBalancing::LeastBusy, &[3, 0, 1], &degraded, 0), 2);
+        assert_eq!(choose_endpoint(LoadBalancing::LeastBusy, &[3, 0, 1], &[false; 3], 0), 1);
+    }
+
+    #[test]
+    fn test_leases_count_in_flight_requests() {
+        let config = TranslateConfig {
+            endpoints: vec!["http://gpu0:11434".to_string(), "http://gpu1:11434/".to_string()],
+            ..crate::config::Config::default().translate
+        };
+        let pool = EndpointPool::new(&config);
+        assert_eq!(pool.urls(), vec!["http://gpu0:11434", "http://gpu1:11434"]);
+
+        let first = pool.acquire();
+        let second = pool.acquire();
+        assert_ne!(first.url(), second.url());
+        drop(first);
+        assert_eq!(pool.acquire().url(), "http://gpu0:11434");
+    }
+}
@@ -0,0 +1,884 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use futures::stream::FuturesOrdered;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::Semaphore;
+use tracing::{info, warn, debug};
+
+use crate::cache;
+use crate::config::{GlossaryTerm, Tone, TranslateConfig};
+use crate::cost::{CostTracker, Usage};
+use crate::delivery::Delivery;
+use crate::error::{Result, ShuroError};
+use crate::metadata::{DELIVERY, SPEAKER};
+use crate::quality::{TranscriptionSegment, glossary_violations};
+use crate::transcribe::format_duration;
+use super::metrics::{TokenUsage, TranslationMetrics};
+use super::judge::QualityJudge;
+use super::backend::ModelBackend;
+use super::{guard, schema};
+use super::semantic::{SemanticCache, TranslationExample};
+use super::memory::TranslationMemory;
+use super::services::TranslationServices;
+use super::work::WorkContext;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranslationRequest {
+    pub model: String,
+    pub prompt: String,
+    pub stream: bool,
+    /// `"json"` or the JSON schema the response must follow
+    pub format: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<RequestOptions>,
+}
+
+/// Ollama model options sent with a request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestOptions {
+    /// Context window in tokens
+    pub num_ctx: usize,
+}
+
+impl RequestOptions {
+    /// Options for a request to `model`; the negotiated context only applies to the translation model
+    pub fn for_model(config: &TranslateConfig, model: &str) -> Option<Self> {
+        config.num_ctx.filter(|_| model == config.model).map(|num_ctx| Self { num_ctx })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranslationResponse {
+    pub response: String,
+    pub done: bool,
+    #[serde(default)]
+    pub prompt_eval_count: Option<u64>,
+    #[serde(default)]
+    pub eval_count: Option<u64>,
+    /// Generation time in nanoseconds
+    #[serde(default)]
+    pub eval_duration: Option<u64>,
+}
+
+impl TranslationResponse {
+    /// Token counters reported by Ollama for this response
+    pub fn usage(&self) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: self.prompt_eval_count,
+            generated_tokens: self.eval_count,
+            generation_ns: self.eval_duration,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranslationResult {
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityEvaluation {
+    pub evaluation: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranslationCacheEntry {
+    pub source_text: String,
+    pub target_language: String,
+    pub context: String,
+    pub translation: String,
+    pub quality: String,
+    pub model: String,
+    pub cached_at: u64,
+    /// Tokens and estimated cost of the requests that produced the translation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Clone)]
+pub enum TranslationQuality {
+    Perfect,
+    Good,
+    Bad,
+    Invalid,
+}
+
+impl TranslationQuality {
+    pub fn parse(s: &str) -> Self {
+        match s.trim().trim_matches(['[', ']']).to_uppercase().as_str() {
+            "PERFECT" => Self::Perfect,
+            "GOOD" => Self::Good,
+            "BAD" => Self::Bad,
+            "INVALID" => Self::Invalid,
+            _ => Self::Good,
+        }
+    }
+    
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Self::Perfect => "PERFECT",
+            Self::Good => "GOOD", 
+            Self::Bad => "BAD",
+            Self::Invalid => "INVALID",
+        }
+    }
+    
+    pub fn is_acceptable(&self) -> bool {
+        matches!(self, Self::Perfect | Self::Good)
+    }
+}
+
+/// What a prompt mentions about the segment it translates
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SegmentHints {
+    /// How the segment is spoken (see delivery.rs)
+    pub delivery: Option<Delivery>,
+    /// Who speaks it (see diarization.rs)
+    pub speaker: Option<String>,
+}
+
+/// Base translator with common functionality
+pub struct BaseTranslator {
+    pub client: Client,
+    pub config: TranslateConfig,
+    pub cache: HashMap<String, String>,
+    pub cache_dir: PathBuf,
+    pub metrics: Arc<TranslationMetrics>,
+    /// Embeddings index of cached translations, when `semantic_cache.enabled` is set
+    pub semantic: Option<SemanticCache>,
+    /// Translation memory, when `memory.enabled` is set
+    pub memory: Option<TranslationMemory>,
+    /// Backend model requests are sent to
+    pub backend: Arc<dyn ModelBackend>,
+    /// Token usage and cost of model requests
+    pub cost: Arc<CostTracker>,
+    /// Translation attempts rejected so far
+    retries: AtomicUsize,
+    /// Translations taken from the cache or the translation memory so far
+    cache_hits: AtomicUsize,
+    /// Usage of the translation requests per source text, until the translation is cached
+    usage: Mutex<HashMap<String, Usage>>,
+}
+
+impl BaseTranslator {
+    pub fn new(config: TranslateConfig) -> Self {
+        let services = TranslationServices::for_config(&config);
+        Self::with_services(config, services)
+    }
+
+    /// Create a base translator sharing the backend, cost tracker and metrics of a workflow
+    pub fn with_services(config: TranslateConfig, services: TranslationServices) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(300)) // 5 minute timeout
+            .build()
+            .expect("HTTP client creation should not fail");
+
+        let cache_dir = Path::new(&config.cache_dir).join("translations");
+        if let Err(e) = std::fs::create_dir_all(&cache_dir) {
+            warn!("Failed to create translation cache directory: {}", e);
+        }
+
+        let TranslationServices { pool, backend, cost, metrics } = services;
+        let semantic = config.semantic_cache.enabled
+            .then(|| SemanticCache::new(client.clone(), pool, &config));
+        let memory = config.memory.enabled.then(|| TranslationMemory::new(&config));
+
+        Self {
+            client,
+            config,
+            cache: HashMap::new(),
+            cache_dir,
+            metrics,
+            semantic,
+            memory,
+            backend,
+            cost,
+            retries: AtomicUsize::new(0),
+            cache_hits: AtomicUsize::new(0),
+            usage: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count a rejected translation attempt
+    pub fn record_retry(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Translation attempts rejected so far
+    pub fn retries(&self) -> usize {
+        self.retries.load(Ordering::Relaxed)
+    }
+
+    /// Translations taken from the cache or the translation memory so far
+    pub fn cache_hits(&self) -> usize {
+        self.cache_hits.load(Ordering::Relaxed)
+    }
+
+    /// Perform the actual translation using Ollama with a structured JSON response
+    pub async fn translate_text(
+        &self,
+        text: &str,
+        target_language: &str,
+        context: Option<&str>,
+    ) -> Result<String> {
+        self.translate_with_hints(text, target_language, context, &SegmentHints::default()).await
+    }
+
+    /// Delivery and speaker of a segment to mention in its prompt, when `delivery_hints` and
+    /// `speaker_hints` are set
+    pub fn hints_of(&self, segment: &TranscriptionSegment) -> SegmentHints {
+        SegmentHints {
+            delivery: self.config.delivery_hints.then(|| segment.metadata.get(&DELIVERY)).flatten(),
+            speaker: self.config.speaker_hints.then(|| segment.metadata.get(&SPEAKER)).flatten(),
+        }
+    }
+
+    /// Translate text spoken with the given delivery by the given speaker
+    pub async fn translate_with_hints(
+        &self,
+        text: &str,
+        target_language: &str,
+        context: Option<&str>,
+        hints: &SegmentHints,
+    ) -> Result<String> {
+        if let Some(remembered) = self.remembered_translation(text, target_language).await? {
+            return Ok(remembered);
+        }
+        let examples = match &self.semantic {
+            Some(semantic) => semantic.examples(text, target_language).await?,
+            None => Vec::new(),
+        };
+        let mut prompt = self.build_translation_prompt(text, target_language, context, &examples, hints);
+
+        // Subtitle text may carry instructions; responses that comment instead of translating are
+        // retried, and so are translations leaving out a mandatory glossary term
+        let attempts = self.config.max_retries.max(1);
+        let (mut reminded, mut glossary_reminded) = (false, false);
+        for attempt in 1..=attempts {
+            let translation = self.request_translation(text, &prompt).await?;
+            if let Some(marker) = guard::meta_commentary(text, &translation) {
+                warn!("Translation of \"{}\" contains meta-commentary (\"{}\"), retrying ({}/{})", text, marker, attempt, attempts);
+                self.record_retry();
+                if !reminded {
+                    prompt.push_str(&format!("\n{}\n", guard::RETRY_REMINDER));
+                    reminded = true;
+                }
+                continue;
+            }
+
+            let missing = glossary_violations(&self.config.glossary, text, &translation, target_language);
+            if missing.is_empty() {
+                return Ok(translation);
+            }
+            let terms = missing.iter()
+                .map(|(term, required)| format!("\"{}\" => \"{}\"", term.source, required))
+                .collect::<Vec<_>>()
+                .join(", ");
+            if attempt == attempts {
+                warn!("Translation of \"{}\" still leaves out mandatory glossary terms ({}); keeping it", text, terms);
+                return Ok(translation);
+            }
+            warn!("Translation of \"{}\" leaves out mandatory glossary terms ({}), retrying ({}/{})", text, terms, attempt, attempts);
+            self.record_retry();
+            if !glossary_reminded {
+                prompt.push_str(&format!("\nThe translation must use these glossary terms exactly as given: {}\n", terms));
+                glossary_reminded = true;
+            }
+        }
+        Err(ShuroError::Translation(format!(
+            "Translation kept returning meta-commentary instead of a translation: {}", text
+        )))
+    }
+
+    /// Translation of `text` from the translation memory, unless it leaves out a mandatory
+    /// glossary term
+    async fn remembered_translation(&self, text: &str, target_language: &str) -> Result<Option<String>> {
+        let Some(memory) = &self.memory else {
+            return Ok(None);
+        };
+        let Some(found) = memory.lookup(&self.config.source_language, target_language, text).await? else {
+            return Ok(None);
+        };
+        if !glossary_violations(&self.config.glossary, text, &found.entry.translation, target_language).is_empty() {
+            debug!("Remembered translation of \"{}\" leaves out mandatory glossary terms, translating again", text);
+            return Ok(None);
+        }
+        debug!("Translation memory match for \"{}\" ({:.0}% similar to \"{}\")", text, found.similarity * 100.0, found.entry.source_text);
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+        Ok(Some(found.entry.translation))
+    }
+
+    /// Send one translation prompt and parse the translated text from the response
+    async fn request_translation(&self, text: &str, prompt: &str) -> Result<String> {
+        let request = TranslationRequest {
+            model: self.config.model.clone(),
+            prompt: prompt.to_string(),
+            stream: false,
+            format: schema::response_format(&self.config, schema::translation()),
+            options: RequestOptions::for_model(&self.config, &self.config.model),
+        };
+
+        debug!("Sending translation request for: {}", text);
+        
+        let started = Instant::now();
+        let translation_response = self.backend.generate(&self.client, &request).await?;
+        self.metrics.record(&self.config.model, started.elapsed(), translation_response.usage());
+        let usage = self.cost.price(&self.config.model, &translation_response.usage());
+        self.usage.lock().expect("usage lock poisoned").entry(text.to_string()).or_default().add(usage);
+
+        let raw_response = translation_response.response.trim().to_string();
+        
+        debug!("Raw model response: {}", raw_response);
+        
+        let result: TranslationResult = schema::parse_response(&raw_response)?;
+        let text = result.text.trim();
+        if text.is_empty() {
+            return Err(ShuroError::Translation("Empty translation received".to_string()));
+        }
+        Ok(text.to_string())
+    }
+
+    /// Quality judge sharing this translator's HTTP client and metrics
+    pub fn judge(&self) -> QualityJudge {
+        QualityJudge::new(self.client.clone(), self.backend.clone(), &self.config, self.metrics.clone())
+    }
+
+    /// Build translation prompt with context, using JSON format
+    fn build_translation_prompt(
+        &self,
+        text: &str,
+        target_language: &str,
+        context: Option<&str>,
+        examples: &[TranslationExample],
+        hints: &SegmentHints,
+    ) -> String {
+        let language_name = language_code_to_name(target_language);
+        let examples_section = format!(
+            "{}{}{}{}{}{}",
+            self.config.work_context.as_ref().map(WorkContext::prompt_section).unwrap_or_default(),
+            format_glossary(&self.config.glossary, text, target_language, &language_name),
+            format_examples(examples, &language_name),
+            format_tone(self.config.tone, self.config.tone_instruction.as_deref()),
+            hints.delivery.map(|d| format!("[Delivery]\n{}\n\n", d.prompt_note())).unwrap_or_default(),
+            hints.speaker.as_ref().map(|speaker| format!(
+                "[Speaker]\nSaid by {}. Keep pronouns and the formality of address consistent with this speaker's other lines.\n\n",
+                speaker
+            )).unwrap_or_default()
+        );
+
+        if let Some(template) = &self.config.prompt_template {
+            let context = context.filter(|ctx| !ctx.trim().is_empty()).map(guard::quote).unwrap_or_default();
+            let prompt = template
+                .replace("{language}", &language_name)
+                .replace("{language_code}", target_language)
+                .replace("{notes}", &examples_section)
+                .replace("{context}", &context)
+                .replace("{text}", &guard::quote(text));
+            return format!(
+                "{}\n\nReturn ONLY the translation in JSON format as {{\"text\":\"your {} translation here\"}}.\n{}\n",
+                prompt.trim_end(), language_name, guard::DATA_NOTICE
+            );
+        }
+
+        if text.len() < 50 {
+            format!(
+                "You are a professional translator.\n\
+                 \n\
+                 CRITICAL: You must translate the text to {} ONLY. Do not translate to any other language.\n\
+                 The target language is: {} (language code: {})\n\
+                 \n\
+                 Return ONLY the translation in JSON format as {{\"text\":\"your {} translation here\"}}.\n\
+                 Do not include any explanations, alternatives, or text in other languages.\n\
+                 {}\n\
+                 \n\
+                 {}\
+                 Text to translate:\n\
+                 {}\n",
+                language_name, language_name, target_language, language_name, guard::DATA_NOTICE, examples_section, guard::quote(text)
+            )
+        } else {
+            let mut prompt = format!(
+                "You are a professional translator.\n\
+                 \n\
+                 CRITICAL: You must translate the text to {} ONLY. Do not translate to any other language.\n\
+                 The target language is: {} (language code: {})\n\
+                 \n\
+                 Return ONLY the translation in JSON format as {{\"text\":\"your {} translation here\"}}.\n\
+                 Do not include any explanations, alternatives, or text in other languages.\n\
+                 {}\n\
+                 \n\
+                 {}\
+                 [Text to translate]\n\
+                 {}\n\
+                 \n",
+                language_name, language_name, target_language, language_name, guard::DATA_NOTICE, examples_section, guard::quote(text)
+            );
+            
+            if let Some(ctx) = context
+                && !ctx.trim().is_empty() {
+                prompt.push_str(&format!(
+                    "[Context for reference - DO NOT translate this part]\n\
+                     {}\n\n\
+                     Remember: Only translate the text in the [Text to translate] section above to {}.\n",
+                    guard::quote(ctx), language_name
+                ));
+            }
+            
+            prompt
+        }
+    }
+
+    /// Cache key context of a segment translated with `context`; a delivery or speaker mentioned
+    /// in the prompt is part of the key
+    pub fn key_context(&self, segment: &TranscriptionSegment, context: &str) -> String {
+        let hints = self.hints_of(segment);
+        [Some(context), hints.delivery.map(|d| d.name()), hints.speaker.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("|")
+    }
+
+    /// Generate cache key for translation
+    pub fn generate_cache_key(&self, source_text: &str, target_language: &str, context: &str) -> String {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        
+        let mut hasher = DefaultHasher::new();
+        source_text.hash(&mut hasher);
+        target_language.hash(&mut hasher);
+        context.hash(&mut hasher);
+        self.config.model.hash(&mut hasher);
+        // Keys of translations without a tone or prompt template stay as they were
+        if self.config.tone != Tone::Unspecified {
+            self.config.tone.hash(&mut hasher);
+        }
+        if self.config.tone == Tone::Custom {
+            self.config.tone_instruction.hash(&mut hasher);
+        }
+        if let Some(template) = &self.config.prompt_template {
+            template.hash(&mut hasher);
+        }
+        
+        let hash = hasher.finish();
+        format!("{:016x}", hash)
+    }
+
+    /// Load translation from persistent cache
+    pub async fn load_from_persistent_cache(&self, cache_key: &str) -> Result<Option<String>> {
+        let cache_file = self.cache_dir.join(format!("{}.json", cache_key));
+        
+        if !cache_file.exists() {
+            return Ok(None);
+        }
+
+        match tokio::fs::read_to_string(&cache_file).await {
+            Ok(content) => {
+                match serde_json::from_str::<TranslationCacheEntry>(&content) {
+                    Ok(entry) => {
+                        cache::touch(&cache_file);
+                        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+                        debug!("Translation cache hit: {} (cached {} ago)", 
+                              cache_key,
+                              format_duration(std::time::SystemTime::now()
+                                  .duration_since(std::time::UNIX_EPOCH)
+                                  .unwrap_or_default()
+                                  .as_secs()
+                                  .saturating_sub(entry.cached_at)));
+                        Ok(Some(entry.translation))
+                    }
+                    Err(e) => {
+                        warn!("Failed to parse translation cache entry: {}", e);
+                        Ok(None)
+                    }
+                }
+            }
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Save translation to persistent cache
+    pub async fn save_to_persistent_cache(
+        &self,
+        cache_key: &str,
+        source_text: &str,
+        target_language: &str,
+        context: &str,
+        translation: &str,
+        quality: &TranslationQuality,
+    ) -> Result<()> {
+        let entry = TranslationCacheEntry {
+            source_text: source_text.to_string(),
+            target_language: target_language.to_string(),
+            context: context.to_string(),
+            translation: translation.to_string(),
+            quality: quality.to_str().to_string(),
+            model: self.config.model.clone(),
+            cached_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            usage: self.usage.lock().expect("usage lock poisoned").remove(source_text),
+        };
+
+        let cache_file = self.cache_dir.join(format!("{}.json", cache_key));
+        let content = serde_json::to_string_pretty(&entry)
+            .map_err(|e| ShuroError::Translation(format!("Failed to serialize translation cache: {}", e)))?;
+        
+        if let Err(e) = tokio::fs::write(&cache_file, content).await {
+            warn!("Failed to write translation cache: {}", e);
+        } else {
+            debug!("Saved translation to cache: {}", cache_key);
+        }
+
+        if let Some(semantic) = &self.semantic {
+            semantic.index(cache_key, source_text, target_language, translation).await?;
+        }
+        if let Some(memory) = &self.memory {
+            memory.record(&self.config.source_language, target_language, source_text, translation).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Clear all translation cache
+    pub async fn clear_translation_cache(&self) -> Result<u64> {
+        let mut count = 0;
+        if let Ok(mut entries) = tokio::fs::read_dir(&self.cache_dir).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                if let Some(extension) = entry.path().extension()
+                    && extension == "json"
+                    && tokio::fs::remove_file(entry.path()).await.is_ok() {
+                    count += 1;
+                }
+            }
+        }
+        if let Some(semantic) = &self.semantic {
+            count += semantic.clear().await?;
+        }
+        info!("Cleared {} translation cache entries", count);
+        Ok(count)
+    }
+
+    /// List translation cache entries
+    pub async fn list_translation_cache(&self) -> Result<Vec<TranslationCacheEntry>> {
+        Ok(self.list_translation_cache_keys().await?.into_iter().map(|(_, entry)| entry).collect())
+    }
+
+    /// List translation cache entries together with their cache keys
+    pub async fn list_translation_cache_keys(&self) -> Result<Vec<(String, TranslationCacheEntry)>> {
+        let mut entries = Vec::new();
+        
+        if let Ok(mut dir_entries) = tokio::fs::read_dir(&self.cache_dir).await {
+            while let Ok(Some(entry)) = dir_entries.next_entry().await {
+                let path = entry.path();
+                if let Some(extension) = path.extension()
+                    && extension == "json"
+                    && let Some(cache_key) = path.file_stem().map(|stem| stem.to_string_lossy().to_string())
+                    && let Ok(content) = tokio::fs::read_to_string(&path).await
+                    && let Ok(cache_entry) = serde_json::from_str::<TranslationCacheEntry>(&content) {
+                    entries.push((cache_key, cache_entry));
+                }
+            }
+        }
+        
+        // Sort by cache time (newest first)
+        entries.sort_by_key(|(_, e)| std::cmp::Reverse(e.cached_at));
+        
+        Ok(entries)
+    }
+}
+
+/// Run `task` on every item with at most `concurrency` tasks running at once
+///
+/// The returned stream yields the results in item order, each as soon as it and every earlier
+/// one are done, so callers can act on finished results while later tasks still run.
+pub fn bounded_in_order<'a, T, Fut>(
+    concurrency: usize,
+    items: impl IntoIterator<Item = T>,
+    task: impl Fn(T) -> Fut,
+) -> FuturesOrdered<impl Future<Output = Fut::Output> + 'a>
+where
+    Fut: Future + 'a,
+{
+    let permits = Arc::new(Semaphore::new(concurrency.max(1)));
+    items.into_iter()
+        .map(|item| {
+            let (permits, task) = (permits.clone(), task(item));
+            async move {
+                let _permit = permits.acquire_owned().await.expect("concurrency semaphore is never closed");
+                task.await
+            }
+        })
+        .collect()
+}
+
+/// Convert language code to full language name for clearer prompts
+pub(crate) fn language_code_to_name(code: &str) -> String {
+    match code.to_lowercase().as_str() {
+        "ja" => "Japanese".to_string(),
+        "ko" => "Korean".to_string(), 
+        "zh" => "Chinese".to_string(),
+        "fr" => "French".to_string(),
+        "de" => "German".to_string(),
+        "es" => "Spanish".to_string(),
+        "ru" => "Russian".to_string(),
+        "it" => "Italian".to_string(),
+        "pt" => "Portuguese".to_string(),
+        "pl" => "Polish".to_string(),
+        "nl" => "Dutch".to_string(),
+        "tr" => "Turkish".to_string(),
+        "ar" => "Arabic".to_string(),
+        "hi" => "Hindi".to_string(),
+        "th" => "Thai".to_string(),
+        "vi" => "Vietnamese".to_string(),
+        "sv" => "Swedish".to_string(),
+        "da" => "Danish".to_string(),
+        "no" => "Norwegian".to_string(),
+        "fi" => "Finnish".to_string(),
+        "he" => "Hebrew".to_string(),
+        "hu" => "Hungarian".to_string(),
+        "cs" => "Czech".to_string(),
+        "sk" => "Slovak".to_string(),
+        "bg" => "Bulgarian".to_string(),
+        "hr" => "Croatian".to_string(),
+        "sl" => "Slovenian".to_string(),
+        "et" => "Estonian".to_string(),
+        "lv" => "Latvian".to_string(),
+        "lt" => "Lithuanian".to_string(),
+        "mt" => "Maltese".to_string(),
+        "ga" => "Irish".to_string(),
+        "cy" => "Welsh".to_string(),
+        "eu" => "Basque".to_string(),
+        "ca" => "Catalan".to_string(),
+        "gl" => "Galician".to_string(),
+        "is" => "Icelandic".to_string(),
+        "mk" => "Macedonian".to_string(),
+        "sq" => "Albanian".to_string(),
+        "be" => "Belarusian".to_string(),
+        "uk" => "Ukrainian".to_string(),
+        "az" => "Azerbaijani".to_string(),
+        "kk" => "Kazakh".to_string(),
+        "ky" => "Kyrgyz".to_string(),
+        "uz" => "Uzbek".to_string(),
+        "tg" => "Tajik".to_string(),
+        "am" => "Amharic".to_string(),
+        "ka" => "Georgian".to_string(),
+        "hy" => "Armenian".to_string(),
+        "ne" => "Nepali".to_string(),
+        "si" => "Sinhala".to_string(),
+        "my" => "Burmese".to_string(),
+        "km" => "Khmer".to_string(),
+        "lo" => "Lao".to_string(),
+        "gu" => "Gujarati".to_string(),
+        "pa" => "Punjabi".to_string(),
+        "ta" => "Tamil".to_string(),
+        "te" => "Telugu".to_string(),
+        "kn" => "Kannada".to_string(),
+        "ml" => "Malayalam".to_string(),
+        "bn" => "Bengali".to_string(),
+        "as" => "Assamese".to_string(),
+        "or" => "Odia".to_string(),
+        "mr" => "Marathi".to_string(),
+        "en" => "English".to_string(),
+        _ => code.to_string(), // Fallback to the code itself if not found
+    }
+}
+
+/// Whether `text` mentions a glossary term (case-insensitive)
+pub fn mentions_term(text: &str, term: &str) -> bool {
+    text.to_lowercase().contains(&term.to_lowercase())
+}
+
+/// Format the glossary terms that occur in `text` and have a translation for the target language
+fn format_glossary(glossary: &[GlossaryTerm], text: &str, target_language: &str, language_name: &str) -> String {
+    let terms: Vec<(&GlossaryTerm, &String)> = glossary
+        .iter()
+        .filter(|term| mentions_term(text, &term.source))
+        .filter_map(|term| term.translations.get(target_language).map(|translation| (term, translation)))
+        .collect();
+
+    if terms.is_empty() {
+        return String::new();
+    }
+
+    let mut section = format!("[Glossary - always translate these terms to {} exactly as given]\n", language_name);
+    for (term, translation) in terms {
+        let kind = if term.character { " (character name)" } else { "" };
+        let mandatory = if term.mandatory { " (mandatory)" } else { "" };
+        section.push_str(&format!("\"{}\" => \"{}\"{}{}\n", term.source, translation, kind, mandatory));
+    }
+    section.push('\n');
+    section
+}
+
+/// Prompt section asking for the configured tone
+fn format_tone(tone: Tone, instruction: Option<&str>) -> String {
+    let instruction = match (tone, instruction) {
+        (Tone::Unspecified, _) | (Tone::Custom, None) => return String::new(),
+        (Tone::Neutral, _) => "Translate in a neutral register: standard polite forms of address (such as desu/masu) without honorifics or slang.",
+        (Tone::Formal, _) => "Translate in a formal register: polite forms of address (such as keigo, Sie or vous) and formal wording.",
+        (Tone::Informal, _) => "Translate in an informal register: casual forms of address (such as plain form, du or tu) and everyday wording.",
+        (Tone::Custom, Some(instruction)) => instruction,
+    };
+    format!("[Tone]\n{}\n\n", instruction.trim())
+}
+
+/// Format previous translations as prompt examples (empty when there are none)
+fn format_examples(examples: &[TranslationExample], language_name: &str) -> String {
+    if examples.is_empty() {
+        return String::new();
+    }
+
+    let mut section = format!(
+        "[Previous translations to {} - keep names and terminology consistent with them]\n",
+        language_name
+    );
+    for example in examples {
+        section.push_str(&format!("\"{}\" => \"{}\"\n", guard::sanitize(&example.source_text), guard::sanitize(&example.translation)));
+    }
+    section.push('\n');
+    section
+}
+
+/// Check if Ollama is available and the model is loaded
+pub async fn check_ollama_availability(endpoint: &str, model: &str) -> Result<()> {
+    let client = Client::new();
+    let url = format!("{}/api/show", endpoint);
+    
+    let request = json!({
+        "name": model
+    });
+
+    let response = client
+        .post(&url)
+        .json(&request)
+        .send()
+        .await
+        .map_err(|e| ShuroError::Translation(format!("Failed to connect to Ollama: {}", e)))?;
+
+    if response.status().is_success() {
+        info!("Ollama model '{}' is available", model);
+        Ok(())
+    } else {
+        Err(ShuroError::Translation(format!(
+            "Ollama model '{}' not found. Please pull the model first: ollama pull {}",
+            model, model
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LanguageOverrides;
+
+    #[test]
+    fn test_format_glossary_only_lists_terms_in_text() {
+        let glossary = vec![
+            GlossaryTerm {
+                source: "Luke".to_string(),
+                character: true,
+                mandatory: true,
+                translations: HashMap::from([("ja".to_string(), "ルーク".to_string())]),
+            },
+            GlossaryTerm {
+                source: "hyperdrive".to_string(),
+                character: false,
+                mandatory: false,
+                translations: HashMap::from([("ja".to_string(), "ハイパードライブ".to_string())]),
+            },
+        ];
+
+        let section = format_glossary(&glossary, "Where is luke?", "ja", "Japanese");
+        assert!(section.contains("\"Luke\" => \"ルーク\" (character name) (mandatory)"));
+        assert!(!section.contains("hyperdrive"));
+        assert_eq!(format_glossary(&glossary, "Where is Luke?", "ko", "Korean"), "");
+    }
+
+    #[test]
+    fn test_language_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = TranslateConfig {
+            cache_dir: dir.path().to_string_lossy().into_owned(),
+            tone: Tone::Informal,
+            ..crate::config::Config::default().translate
+        };
+        config.languages.insert("ja".to_string(), LanguageOverrides {
+            model: Some("qwen3:14b".to_string()),
+            tone: Some(Tone::Formal),
+            prompt_template: Some("Translate into {language} for a period drama.\n{notes}{text}".to_string()),
+            ..Default::default()
+        });
+        config.languages.insert("ko".to_string(), LanguageOverrides {
+            tone: Some(Tone::Custom),
+            tone_instruction: Some("Use haeyo-che between friends and hapsyo-che in the court.".to_string()),
+            ..Default::default()
+        });
+
+        let german = BaseTranslator::new(config.for_language("de"));
+        assert_eq!(german.config.model, config.model);
+        let prompt = german.build_translation_prompt("Where is Luke?", "de", None, &[], &SegmentHints::default());
+        assert!(prompt.contains("[Tone]\nTranslate in an informal register"));
+
+        let japanese = BaseTranslator::new(config.for_language("JA"));
+        assert_eq!(japanese.config.model, "qwen3:14b");
+        let prompt = japanese.build_translation_prompt("Where is Luke?", "ja", None, &[], &SegmentHints::default());
+        assert!(prompt.starts_with("Translate into Japanese for a period drama.\n[Tone]\nTranslate in a formal register"));
+        assert!(prompt.contains("<subtitle>\nWhere is Luke?\n</subtitle>\n\nReturn ONLY the translation in JSON format"));
+        assert_ne!(
+            japanese.generate_cache_key("Where is Luke?", "ja", ""),
+            BaseTranslator::new(TranslateConfig { model: "qwen3:14b".to_string(), ..config.clone() }).generate_cache_key("Where is Luke?", "ja", ""),
+        );
+
+        let korean = BaseTranslator::new(config.for_language("ko"));
+        let prompt = korean.build_translation_prompt("Where is Luke?", "ko", None, &[], &SegmentHints::default());
+        assert!(prompt.contains("[Tone]\nUse haeyo-che between friends and hapsyo-che in the court.\n"));
+        let mut retoned = config.for_language("ko");
+        retoned.tone_instruction = Some("Use banmal throughout.".to_string());
+        assert_ne!(korean.generate_cache_key("Where is Luke?", "ko", ""), BaseTranslator::new(retoned).generate_cache_key("Where is Luke?", "ko", ""));
+    }
+
+    #[test]
+    fn test_formality_key() {
+        // Configurations written before `tone` name it `formality`
+        let mut value = toml::Value::try_from(crate::config::Config::default().translate).unwrap();
+        let table = value.as_table_mut().unwrap();
+        table.remove("tone");
+        table.insert("formality".to_string(), "Formal".into());
+        let japanese: toml::Table = toml::from_str("formality = \"Informal\"").unwrap();
+        table.insert("languages".to_string(), toml::Table::from_iter([("ja".to_string(), japanese.into())]).into());
+
+        let config: TranslateConfig = value.try_into().unwrap();
+        assert_eq!(config.tone, Tone::Formal);
+        assert_eq!(config.languages["ja"].tone, Some(Tone::Informal));
+        assert_eq!(config.for_language("ja").tone, Tone::Informal);
+    }
+
+    #[tokio::test]
+    async fn test_bounded_in_order() {
+        use futures::StreamExt;
+
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let results: Vec<u64> = bounded_in_order(2, [30u64, 5, 20, 1], |delay| {
+            let (running, peak) = (&running, &peak);
+            async move {
+                peak.fetch_max(running.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                delay
+            }
+        }).collect().await;
+
+        // Later items finishing first still come out in item order
+        assert_eq!(results, vec![30, 5, 20, 1]);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,434 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+use crate::cancel::Cancellation;
+use crate::config::TranslateConfig;
+use crate::error::{Result, ShuroError};
+use crate::quality::{is_translation_too_long, Transcription, TranscriptionSegment};
+use super::{Translator, common::{BaseTranslator, TranslationQuality, bounded_in_order}, services::TranslationServices};
+
+/// Cache key context for segment-level entries, kept apart from Simple-mode entries keyed without context
+const SEGMENT_LEVEL_CACHE_CONTEXT: &str = "context";
+
+/// Context-aware translation: Use surrounding segments as context but only translate the target segment
+pub struct ContextTranslator {
+    base: BaseTranslator,
+}
+
+impl ContextTranslator {
+    pub fn new(config: TranslateConfig, services: TranslationServices) -> Self {
+        Self {
+            base: BaseTranslator::with_services(config, services),
+        }
+    }
+
+    fn build_segment_context(&self, segments: &[TranscriptionSegment], current_idx: usize, context_size: usize) -> String {
+        // Context lines name their speaker when speakers are mentioned in prompts
+        let line = |segment: &TranscriptionSegment| match self.base.hints_of(segment).speaker {
+            Some(speaker) => format!("{}: {}", speaker, segment.text),
+            None => segment.text.clone(),
+        };
+        let mut context_parts = Vec::new();
+        
+        let start_idx = current_idx.saturating_sub(context_size);
+        for i in start_idx..current_idx {
+            if i < segments.len() {
+                context_parts.push(line(&segments[i]));
+            }
+        }
+        
+        let end_idx = (current_idx + 1 + context_size).min(segments.len());
+        for segment in segments.iter().take(end_idx).skip(current_idx + 1) {
+            context_parts.push(line(segment));
+        }
+        
+        context_parts.join(" ")
+    }
+
+    /// Whether a translation runs too long for the source/target language pair
+    fn is_too_long(&self, segment: &TranscriptionSegment, translation: &str, target_language: &str) -> bool {
+        let config = &self.base.config;
+        is_translation_too_long(&config.length_validation, &segment.text, translation, &config.source_language, target_language)
+    }
+
+    /// Cache key of a segment translated with the given context
+    fn cache_key(&self, segment: &TranscriptionSegment, target_language: &str, context: &str) -> String {
+        // With segment-level caching the context still goes into the prompt but not into the key
+        let key_context = if self.base.config.segment_level_cache { SEGMENT_LEVEL_CACHE_CONTEXT } else { context };
+        self.base.generate_cache_key(&segment.text, target_language, &self.base.key_context(segment, key_context))
+    }
+
+    /// Cached translation of a segment, from the persistent or the in-memory cache
+    async fn cached_translation(&mut self, segment: &TranscriptionSegment, target_language: &str, context: &str) -> Option<String> {
+        let cache_key = self.cache_key(segment, target_language, context);
+        if let Ok(Some(cached_translation)) = self.base.load_from_persistent_cache(&cache_key).await {
+            self.base.cache.insert(cache_key, cached_translation.clone());
+            return Some(cached_translation);
+        }
+        self.base.cache.get(&cache_key).cloned()
+    }
+
+    /// Translate a segment without evaluation, dropping the context if the translation runs too long
+    ///
+    /// Returns the translation and the context it was produced with.
+    async fn translate_unjudged(
+        &self,
+        segment: &TranscriptionSegment,
+        target_language: &str,
+        context: &str,
+    ) -> Result<(String, String)> {
+        let hints = self.base.hints_of(segment);
+        let translation = self.base.translate_with_hints(&segment.text, target_language, (!context.is_empty()).then_some(context), &hints).await?;
+        if context.is_empty() || !self.is_too_long(segment, &translation, target_language) {
+            return Ok((translation, context.to_string()));
+        }
+        info!("│ Translation too long, removing context");
+        let translation = self.base.translate_with_hints(&segment.text, target_language, None, &hints).await?;
+        Ok((translation, String::new()))
+    }
+
+    /// Translate a single segment with quality validation and retries
+    async fn translate_segment_with_quality(
+        &mut self,
+        segment: &TranscriptionSegment,
+        target_language: &str,
+        context: &str,
+    ) -> Result<String> {
+        let cache_key = self.cache_key(segment, target_language, context);
+        
+        // Check persistent cache first
+        if let Ok(Some(cached_translation)) = self.base.load_from_persistent_cache(&cache_key).await {
+            // Also store in memory cache for faster access during this session
+            self.base.cache.insert(cache_key.clone(), cached_translation.clone());
+            return Ok(cached_translation);
+        }
+        
+        // Check in-memory cache as fallback
+        if let Some(cached) = self.base.cache.get(&cache_key) {
+            return Ok(cached.clone());
+        }
+
+        let mut current_context = context.to_string();
+        let mut attempts = 0;
+        
+        while attempts < self.base.config.max_retries {
+            attempts += 1;
+
+            let context = if current_context.is_empty() { None } else { Some(current_context.as_str()) };
+            match self.base.translate_with_hints(&segment.text, target_language, context, &self.base.hints_of(segment)).await {
+                Ok(translation) => {
+                    // Length validation - if translation is too long, remove context
+                    if self.is_too_long(segment, &translation, target_language) {
+                        info!("│ Translation too long, removing context (attempt {})", attempts);
+                        self.base.record_retry();
+                        current_context = String::new();
+                        continue;
+                    }
+                    
+                    // Validate translation quality
+                    let quality = self.base.judge().evaluate(&segment.text, &translation, &current_context, target_language).await;
+                    
+                    match quality {
+                        Ok(TranslationQuality::Perfect | TranslationQuality::Good) => {
+                            info!("│ Quality: {} ✓", quality.as_ref().unwrap().to_str());
+                            
+                            // Save to persistent cache (only good quality translations)
+                            if let Err(e) = self.base.save_to_persistent_cache(
+                                &cache_key,
+                                &segment.text,
+                                target_language,
+                                &current_context,
+                                &translation,
+                                quality.as_ref().unwrap(),
+                            ).await {
+                                warn!("Failed to save translation to persistent cache: {}", e);
+                            }
+                            
+                            // Cache in memory for this session
+                            self.base.cache.insert(cache_key, translation.clone());
+                            return Ok(translation);
+                        }
+                        Ok(qual) => {
+                            warn!("│ Quality: {} - retrying (attempt {})", qual.to_str(), attempts);
+                        }
+                        Err(e) => {
+                            warn!("│ Quality evaluation failed: {} (attempt {})", e, attempts);
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("│ Attempt {} failed: {}", attempts, e);
+                }
+            }
+            self.base.record_retry();
+
+            // Brief delay before retry
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+
+        Err(ShuroError::Translation(format!(
+            "Failed to translate after {} attempts", 
+            self.base.config.max_retries
+        )))
+    }
+}
+
+#[async_trait]
+impl Translator for ContextTranslator {
+    fn retries(&self) -> usize {
+        self.base.retries()
+    }
+
+    fn cache_hits(&self) -> usize {
+        self.base.cache_hits()
+    }
+
+    /// Context-aware translation: Use surrounding segments as context but only translate the target segment
+    async fn translate_transcription(
+        &mut self,
+        transcription: &mut Transcription,
+        target_language: &str,
+        _context: Option<&str>,
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        cancel.run(async {
+            info!("Starting context-aware translation to {}", target_language);
+        
+            let total_segments = transcription.segments.len();
+        
+            // Pre-build all contexts to avoid borrowing issues
+            let contexts: Vec<String> = (0..total_segments)
+                .map(|idx| self.build_segment_context(&transcription.segments, idx, self.base.config.context_window_size))
+                .collect();
+
+            // Pass 1: translate every segment, `concurrency` requests at a time, while the judge
+            // evaluates finished translations in the background
+            let judge = self.base.judge();
+            let permits = Arc::new(Semaphore::new(self.base.config.evaluation_concurrency.max(1)));
+            let mut evaluations = JoinSet::new();
+
+            let mut uncached = Vec::new();
+            for (idx, (segment, context)) in transcription.segments.iter_mut().zip(contexts.iter()).enumerate() {
+                if let Some(cached) = self.cached_translation(segment, target_language, context).await {
+                    info!("┌─ Translating segment {}/{} (Context) ────────", idx + 1, total_segments);
+                    info!("│ Source: {}", segment.text);
+                    info!("│ Target: {} (cached)", cached);
+                    info!("└─────────────────────────────────────");
+                    segment.text = cached;
+                } else {
+                    uncached.push(idx);
+                }
+            }
+            info!("{} of {} segments to translate, {} requests at a time", uncached.len(), total_segments, self.base.config.concurrency.max(1));
+
+            {
+                let (this, segments, contexts) = (&*self, &transcription.segments, &contexts);
+                let mut translations = bounded_in_order(this.base.config.concurrency, uncached, |idx| async move {
+                    (idx, this.translate_unjudged(&segments[idx], target_language, &contexts[idx]).await)
+                });
+
+                while let Some((idx, result)) = translations.next().await {
+                    let (segment, context) = (&segments[idx], &contexts[idx]);
+                    info!("┌─ Translating segment {}/{} (Context) ────────", idx + 1, total_segments);
+                    info!("│ Source: {}", segment.text);
+                    if !context.is_empty() {
+                        info!("│ Context: {}...", &context[..context.len().min(100)]);
+                    }
+
+                    match result {
+                        Ok((translation, used_context)) => {
+                            info!("│ Target: {} (awaiting evaluation)", translation);
+                            info!("└─────────────────────────────────────");
+                            let (judge, permits) = (judge.clone(), permits.clone());
+                            let (source, target_language) = (segment.text.clone(), target_language.to_string());
+                            evaluations.spawn(async move {
+                                let _permit = permits.acquire_owned().await.expect("evaluation semaphore is never closed");
+                                let quality = judge.evaluate(&source, &translation, &used_context, &target_language).await;
+                                (idx, translation, used_context, quality)
+                            });
+                        }
+                        Err(e) => {
+                            warn!("│ Failed: {}", e);
+                            warn!("└─────────────────────────────────────");
+                            // Keep original text on failure
+                        }
+                    }
+                }
+            }
+
+            // Pass 2: cache accepted translations, apply them and collect the rejected ones;
+            // segments keep their source text until a translation is accepted
+            let mut revisit = Vec::new();
+            while let Some(result) = evaluations.join_next().await {
+                let (idx, translation, used_context, quality) = result
+                    .map_err(|e| ShuroError::Translation(format!("Quality evaluation task failed: {}", e)))?;
+                match quality {
+                    Ok(quality) if quality.is_acceptable() => {
+                        let segment = &mut transcription.segments[idx];
+                        let cache_key = self.cache_key(segment, target_language, &contexts[idx]);
+                        if let Err(e) = self.base.save_to_persistent_cache(
+                            &cache_key, &segment.text, target_language, &used_context, &translation, &quality,
+                        ).await {
+                            warn!("Failed to save translation to persistent cache: {}", e);
+                        }
+                        self.base.cache.insert(cache_key, translation.clone());
+                        segment.text = translation;
+                    }
+                    Ok(quality) => {
+                        warn!("Segment {} judged {} by {}, revisiting", idx + 1, quality.to_str(), judge.model());
+                        self.base.record_retry();
+                        revisit.push(idx);
+                    }
+                    Err(e) => {
+                        warn!("Segment {} evaluation failed ({}), revisiting", idx + 1, e);
+                        self.base.record_retry();
+                        revisit.push(idx);
+                    }
+                }
+            }
+
+            // Pass 3: retranslate only the rejected segments, evaluating each attempt
+            revisit.sort();
+            info!("{} of {} segments need another translation", revisit.len(), total_segments);
+            for idx in revisit {
+                let segment = transcription.segments[idx].clone();
+
+                info!("┌─ Revisiting segment {}/{} (Context) ────────", idx + 1, total_segments);
+                info!("│ Source: {}", segment.text);
+                match self.translate_segment_with_quality(&segment, target_language, &contexts[idx]).await {
+                    Ok(translation) => {
+                        info!("│ Target: {}", translation);
+                        info!("└─────────────────────────────────────");
+                        transcription.segments[idx].text = translation;
+                    }
+                    Err(e) => {
+                        warn!("│ Failed: {}", e);
+                        warn!("└─────────────────────────────────────");
+                        // Keep original text on failure
+                    }
+                }
+            }
+        
+            Ok(())
+        }).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use reqwest::Client;
+    use crate::config::Config;
+    use crate::subtitle::parse_srt;
+    use crate::translate::backend::ModelBackend;
+    use crate::translate::common::{TranslationRequest, TranslationResponse};
+
+    /// Backend translating from a table, whose judge rejects one translation
+    #[derive(Debug, Default)]
+    struct Scripted {
+        /// Translations of each source text, one per request (the last one repeats)
+        translations: Vec<(&'static str, Vec<&'static str>)>,
+        rejected: &'static str,
+        requests: Mutex<HashMap<&'static str, usize>>,
+    }
+
+    #[async_trait]
+    impl ModelBackend for Scripted {
+        async fn generate(&self, _client: &Client, request: &TranslationRequest) -> Result<TranslationResponse> {
+            let response = if request.prompt.contains("translation quality evaluator") {
+                let rejected = request.prompt.contains(&format!("\n{}\n", self.rejected));
+                serde_json::json!({ "evaluation": if rejected { "[BAD]" } else { "[GOOD]" } })
+            } else {
+                let (source, translations) = self.translations.iter()
+                    .find(|(source, _)| request.prompt.contains(source))
+                    .expect("prompt of a scripted source");
+                let mut requests = self.requests.lock().unwrap();
+                let count = requests.entry(source).or_default();
+                *count += 1;
+                serde_json::json!({ "text": translations[(*count - 1).min(translations.len() - 1)] })
+            };
+            Ok(TranslationResponse {
+                response: response.to_string(),
+                done: true,
+                prompt_eval_count: None,
+                eval_count: None,
+                eval_duration: None,
+            })
+        }
+
+        async fn check_models(&self, _models: &[&str]) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_segment_level_cache_key() {
+        let segment = parse_srt("1\n00:00:01,000 --> 00:00:02,000\nHyperdrive on\n", "en").unwrap().segments.remove(0);
+        let dir = tempfile::tempdir().unwrap();
+        let key = |segment_level_cache| {
+            let config = TranslateConfig {
+                cache_dir: dir.path().to_string_lossy().into_owned(),
+                segment_level_cache,
+                ..Config::default().translate
+            };
+            let translator = ContextTranslator::new(config.clone(), TranslationServices::for_config(&config));
+            (translator.cache_key(&segment, "ja", "Punch it."), translator.cache_key(&segment, "ja", "Chewie, punch it."))
+        };
+
+        let (first, second) = key(true);
+        assert_eq!(first, second);
+        let (first, second) = key(false);
+        assert_ne!(first, second);
+    }
+
+    #[tokio::test]
+    async fn test_only_rejected_segments_are_retranslated() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = TranslateConfig {
+            cache_dir: dir.path().to_string_lossy().into_owned(),
+            ..Config::default().translate
+        };
+        let backend = Arc::new(Scripted {
+            translations: vec![
+                ("Punch it.", vec!["Vas-y."]),
+                ("Hyperdrive on.", vec!["Hyper.", "Hyperespace."]),
+                ("Hold on.", vec!["Accroche-toi."]),
+            ],
+            rejected: "Hyper.",
+            ..Default::default()
+        });
+        let services = TranslationServices { backend: backend.clone(), ..TranslationServices::for_config(&config) };
+        let mut translator = ContextTranslator::new(config, services);
+        let mut transcription = parse_srt(
+            "1\n00:00:01,000 --> 00:00:02,000\nPunch it.\n\n2\n00:00:03,000 --> 00:00:04,000\nHyperdrive on.\n\n3\n00:00:05,000 --> 00:00:06,000\nHold on.\n",
+            "en",
+        ).unwrap();
+        let source = transcription.segments.clone();
+
+        translator.translate_transcription(&mut transcription, "fr", None, &Cancellation::new()).await.unwrap();
+        let texts: Vec<&str> = transcription.segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["Vas-y.", "Hyperespace.", "Accroche-toi."]);
+        // Pass 3 asks again only for the segment the judge rejected
+        assert_eq!(*backend.requests.lock().unwrap(), HashMap::from([("Punch it.", 1), ("Hyperdrive on.", 2), ("Hold on.", 1)]));
+
+        // Accepted pass-1 translations are cached under, and with, the context they were made with
+        let window = translator.base.config.context_window_size;
+        for idx in [0, 2] {
+            let context = translator.build_segment_context(&source, idx, window);
+            let key = translator.cache_key(&source[idx], "fr", &context);
+            let entry = translator.base.list_translation_cache_keys().await.unwrap().into_iter()
+                .find(|(cached, _)| *cached == key)
+                .map(|(_, entry)| entry)
+                .unwrap();
+            assert_eq!((entry.translation.as_str(), entry.context), (texts[idx], context));
+        }
+        assert!(translator.base.list_translation_cache_keys().await.unwrap().iter().all(|(_, entry)| entry.translation != "Hyper."));
+    }
+}
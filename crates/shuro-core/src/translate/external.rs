@@ -0,0 +1,99 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::cancel::Cancellation;
+use crate::config::{ExternalPluginConfig, TranslateConfig};
+use crate::error::{Result, ShuroError};
+use crate::metadata::SegmentMetadata;
+use crate::plugin;
+use crate::quality::Transcription;
+use super::Translator;
+use super::work::WorkContext;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalSegment {
+    pub id: i32,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "SegmentMetadata::is_empty")]
+    pub metadata: SegmentMetadata,
+}
+
+#[derive(Debug, Serialize)]
+struct ExternalTranslateRequest<'a> {
+    source_language: &'a str,
+    target_language: &'a str,
+    model: &'a str,
+    context: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    work: Option<&'a WorkContext>,
+    segments: Vec<ExternalSegment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExternalTranslateResponse {
+    segments: Vec<ExternalSegment>,
+}
+
+/// Translator backed by an external executable speaking the plugin protocol
+pub struct ExternalTranslator {
+    plugin: ExternalPluginConfig,
+    config: TranslateConfig,
+}
+
+impl ExternalTranslator {
+    pub fn new(plugin: ExternalPluginConfig, config: TranslateConfig) -> Self {
+        Self { plugin, config }
+    }
+}
+
+#[async_trait]
+impl Translator for ExternalTranslator {
+    /// Send all segments to the plugin in one request and apply the returned translations
+    async fn translate_transcription(
+        &mut self,
+        transcription: &mut Transcription,
+        target_language: &str,
+        context: Option<&str>,
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        cancel.run(async {
+            info!("Starting external translation to {} via plugin '{}'", target_language, self.plugin.name);
+
+            let request = ExternalTranslateRequest {
+                source_language: &self.config.source_language,
+                target_language,
+                model: &self.config.model,
+                context,
+                work: self.config.work_context.as_ref().filter(|w| !w.is_empty()),
+                segments: transcription.segments
+                    .iter()
+                    .map(|s| ExternalSegment { id: s.id, text: s.text.clone(), metadata: s.metadata.clone() })
+                    .collect(),
+            };
+
+            let response: ExternalTranslateResponse =
+                plugin::invoke(&self.plugin, &request, ShuroError::Translation).await?;
+
+            if response.segments.len() != transcription.segments.len() {
+                return Err(ShuroError::Translation(format!(
+                    "Plugin '{}' returned {} segments, expected {}",
+                    self.plugin.name, response.segments.len(), transcription.segments.len()
+                )));
+            }
+
+            for (segment, translated) in transcription.segments.iter_mut().zip(response.segments) {
+                if segment.id != translated.id {
+                    return Err(ShuroError::Translation(format!(
+                        "Plugin '{}' returned segment {} where {} was expected",
+                        self.plugin.name, translated.id, segment.id
+                    )));
+                }
+                segment.text = translated.text;
+                segment.metadata.merge(&translated.metadata);
+            }
+
+            Ok(())
+        }).await
+    }
+}
@@ -0,0 +1,143 @@
+// Glossary files
+//
+// `translate.glossary_file` keeps the terminology of a series (character names, place names,
+// technical jargon) out of the configuration, in a file that can be shared between configurations
+// or edited in a spreadsheet. Its terms are added to `translate.glossary` when the workflow is
+// built. Two formats are read, chosen by extension:
+//
+// - `.csv`: a header row naming the columns `source`, optionally `character` and `mandatory`
+//   (true/false, yes/no, 1/0), and one column per target language code; empty cells are skipped
+// - anything else: TOML with `[[glossary]]` tables shaped like `[[translate.glossary]]`
+//
+// Mandatory terms are enforced: a translation of a segment mentioning the term that leaves out
+// the required rendering is retried with a reminder, and flagged in the comparison report when
+// the retries run out (see quality.rs).
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::config::GlossaryTerm;
+use crate::error::{Result, ShuroError};
+
+#[derive(Debug, Deserialize)]
+struct GlossaryFile {
+    #[serde(default)]
+    glossary: Vec<GlossaryTerm>,
+}
+
+/// Terms of the glossary file at `path`
+pub fn load_glossary_file<P: AsRef<Path>>(path: P) -> Result<Vec<GlossaryTerm>> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| ShuroError::Config(format!("Failed to read glossary file {}: {}", path.display(), e)))?;
+
+    let is_csv = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("csv"));
+    let terms = if is_csv {
+        parse_csv_glossary(&content)
+    } else {
+        toml::from_str::<GlossaryFile>(&content)
+            .map(|file| file.glossary)
+            .map_err(|e| ShuroError::Config(e.to_string()))
+    };
+    terms.map_err(|e| ShuroError::Config(format!("Invalid glossary file {}: {}", path.display(), e)))
+}
+
+/// Terms of a CSV glossary with a header row
+fn parse_csv_glossary(content: &str) -> Result<Vec<GlossaryTerm>> {
+    let mut rows = content.trim_start_matches('\u{feff}').lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let Some((_, header)) = rows.next() else {
+        return Ok(Vec::new());
+    };
+    let columns: Vec<String> = split_csv_line(header).into_iter().map(|c| c.trim().to_lowercase()).collect();
+    if !columns.iter().any(|c| c == "source") {
+        return Err(ShuroError::Config("the header row has no `source` column".to_string()));
+    }
+
+    let mut terms = Vec::new();
+    for (index, line) in rows {
+        let mut term = GlossaryTerm { source: String::new(), character: false, mandatory: false, translations: HashMap::new() };
+        for (column, value) in columns.iter().zip(split_csv_line(line)) {
+            let value = value.trim();
+            let flag = || parse_flag(value).ok_or_else(|| ShuroError::Config(format!(
+                "line {}: `{}` is not true or false in column {}", index + 1, value, column
+            )));
+            match column.as_str() {
+                "source" => term.source = value.to_string(),
+                "character" => term.character = flag()?,
+                "mandatory" => term.mandatory = flag()?,
+                _ if value.is_empty() => {}
+                language => {
+                    term.translations.insert(language.to_string(), value.to_string());
+                }
+            }
+        }
+        if term.source.is_empty() {
+            return Err(ShuroError::Config(format!("line {}: the term has no source", index + 1)));
+        }
+        terms.push(term);
+    }
+    Ok(terms)
+}
+
+/// Fields of a CSV line; double quotes enclose fields with commas, `""` is a quote inside them
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().expect("there is always a field");
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            _ => field.push(c),
+        }
+    }
+    fields
+}
+
+/// Boolean cell; empty is false
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "" | "false" | "no" | "0" => Some(false),
+        "true" | "yes" | "1" => Some(true),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_glossary_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let csv = dir.path().join("terms.csv");
+        std::fs::write(&csv, "\u{feff}source,character,mandatory,ja,ko\n\
+                              Luke,yes,true,ルーク,루크\n\
+                              \"Hyperdrive, Mk II\",,,\"ハイパー\"\"ドライブ\"\"\",\n").unwrap();
+        let terms = load_glossary_file(&csv).unwrap();
+        assert_eq!(terms.len(), 2);
+        assert!(terms[0].character && terms[0].mandatory);
+        assert_eq!(terms[0].translations["ko"], "루크");
+        assert_eq!(terms[1].source, "Hyperdrive, Mk II");
+        assert_eq!(terms[1].translations, HashMap::from([("ja".to_string(), "ハイパー\"ドライブ\"".to_string())]));
+
+        let toml = dir.path().join("terms.toml");
+        std::fs::write(&toml, "[[glossary]]\nsource = \"Jedi\"\nmandatory = true\nja = \"ジェダイ\"\n").unwrap();
+        let terms = load_glossary_file(&toml).unwrap();
+        assert!(terms[0].mandatory && !terms[0].character);
+        assert_eq!(terms[0].translations["ja"], "ジェダイ");
+
+        std::fs::write(&csv, "term,ja\nLuke,ルーク\n").unwrap();
+        assert!(load_glossary_file(&csv).is_err());
+        std::fs::write(&csv, "source,mandatory\nLuke,maybe\n").unwrap();
+        assert!(load_glossary_file(&csv).unwrap_err().to_string().contains("line 2"));
+    }
+}
@@ -0,0 +1,117 @@
+// Sliding-window analysis journal
+//
+// LlmTranslator analyzes hundreds of overlapping windows before it translates anything. The
+// sentences found in each window are journaled to `{cache_dir}/analysis/<hash>.json`, keyed by
+// the transcription text, the analysis model and the window layout settings, so a run interrupted during
+// analysis resumes with the windows not analyzed yet. Once every window is done the sentence
+// candidates are journaled as well, and later runs on the same transcription (including runs for
+// other target languages) skip the analysis phase entirely. Failed windows are not journaled and
+// are retried on resume.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+use crate::error::{Result, ShuroError};
+use crate::quality::TranscriptionSegment;
+use super::llm::SentenceCandidate;
+
+/// Analysis progress of one transcription
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AnalysisJournal {
+    /// Sentences detected in each analyzed window, by window start
+    pub windows: BTreeMap<usize, Vec<String>>,
+    /// Sentence candidates, once every window has been analyzed
+    pub candidates: Option<Vec<SentenceCandidate>>,
+}
+
+/// Journal file of one transcription, rewritten after every analyzed window
+pub struct AnalysisStore {
+    path: PathBuf,
+    pub journal: AnalysisJournal,
+}
+
+impl AnalysisStore {
+    /// Open the journal for `segments` analyzed by `model` with the window layout settings `layout`
+    pub async fn open(cache_dir: &Path, segments: &[TranscriptionSegment], model: &str, layout: &[usize]) -> Self {
+        let path = cache_dir.join("analysis").join(format!("{}.json", analysis_key(segments, model, layout)));
+        let journal = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable analysis journal {}: {}", path.display(), e);
+                AnalysisJournal::default()
+            }),
+            Err(_) => AnalysisJournal::default(),
+        };
+        if journal.candidates.is_some() {
+            info!("Reusing completed sentence analysis from {}", path.display());
+        } else if !journal.windows.is_empty() {
+            info!("Resuming sentence analysis: {} window(s) already analyzed", journal.windows.len());
+        }
+        Self { path, journal }
+    }
+
+    /// Record the sentences of the window starting at `window_start`
+    pub async fn record_window(&mut self, window_start: usize, sentences: Vec<String>) -> Result<()> {
+        self.journal.windows.insert(window_start, sentences);
+        self.write().await
+    }
+
+    /// Record the candidates of the completed analysis
+    pub async fn complete(&mut self, candidates: &[SentenceCandidate]) -> Result<()> {
+        self.journal.candidates = Some(candidates.to_vec());
+        self.write().await
+    }
+
+    /// Write the journal through a temporary file so a crash never leaves it truncated
+    async fn write(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await
+                .map_err(|e| ShuroError::Cache(format!("Failed to create analysis journal directory: {}", e)))?;
+        }
+        let temp = self.path.with_extension("json.tmp");
+        tokio::fs::write(&temp, serde_json::to_string(&self.journal)?).await
+            .map_err(|e| ShuroError::Cache(format!("Failed to write analysis journal: {}", e)))?;
+        tokio::fs::rename(&temp, &self.path).await
+            .map_err(|e| ShuroError::Cache(format!("Failed to write analysis journal: {}", e)))?;
+        debug!("Journaled {} analyzed window(s) to {}", self.journal.windows.len(), self.path.display());
+        Ok(())
+    }
+}
+
+/// Hash of the segment texts, the analysis model and the window layout settings
+fn analysis_key(segments: &[TranscriptionSegment], model: &str, layout: &[usize]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(model.as_bytes());
+    for setting in layout {
+        hasher.update(setting.to_le_bytes());
+    }
+    for segment in segments {
+        hasher.update([0]);
+        hasher.update(segment.text.as_bytes());
+    }
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::parse_srt;
+
+    #[tokio::test]
+    async fn test_journal_resumes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut transcription = parse_srt("1\n00:00:00,000 --> 00:00:01,000\nHello\n\n2\n00:00:01,000 --> 00:00:02,000\nthere.\n", "en").unwrap();
+
+        let mut store = AnalysisStore::open(dir.path(), &transcription.segments, "model", &[15]).await;
+        store.record_window(0, vec!["Hello there.".to_string()]).await.unwrap();
+
+        let reopened = AnalysisStore::open(dir.path(), &transcription.segments, "model", &[15]).await;
+        assert_eq!(reopened.journal, store.journal);
+        assert!(AnalysisStore::open(dir.path(), &transcription.segments, "other", &[15]).await.journal.windows.is_empty());
+
+        transcription.segments[1].text = "there!".to_string();
+        assert!(AnalysisStore::open(dir.path(), &transcription.segments, "model", &[15]).await.journal.windows.is_empty());
+    }
+}
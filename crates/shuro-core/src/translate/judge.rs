@@ -0,0 +1,112 @@
+// Translation quality judge
+//
+// Evaluates translations with the judge model (`translate.judge_model`, defaulting to the
+// translation model). The judge owns its HTTP client handle and configuration, so evaluations
+// can run as background tasks while translation of later segments continues.
+
+use std::sync::Arc;
+use std::time::Instant;
+use reqwest::Client;
+use serde_json::Value;
+
+use crate::config::TranslateConfig;
+use super::backend::ModelBackend;
+use crate::error::Result;
+use super::{guard, schema};
+use super::common::{language_code_to_name, QualityEvaluation, RequestOptions, TranslationQuality, TranslationRequest};
+use super::metrics::TranslationMetrics;
+
+/// Judges translation quality on a dedicated model
+#[derive(Clone)]
+pub struct QualityJudge {
+    client: Client,
+    backend: Arc<dyn ModelBackend>,
+    model: String,
+    options: Option<RequestOptions>,
+    /// Request format constraining the response to an evaluation
+    format: Value,
+    source_language: String,
+    metrics: Arc<TranslationMetrics>,
+}
+
+impl QualityJudge {
+    /// Create a judge that shares the translator's HTTP client, backend and metrics
+    pub fn new(client: Client, backend: Arc<dyn ModelBackend>, config: &TranslateConfig, metrics: Arc<TranslationMetrics>) -> Self {
+        let model = config.judge_model.clone().unwrap_or_else(|| config.model.clone());
+        Self {
+            client,
+            backend,
+            options: RequestOptions::for_model(config, &model),
+            format: schema::response_format(config, schema::evaluation()),
+            model,
+            source_language: config.source_language.clone(),
+            metrics,
+        }
+    }
+
+    /// Model used for evaluation
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Evaluate translation quality using structured evaluation
+    pub async fn evaluate(
+        &self,
+        original: &str,
+        translation: &str,
+        context: &str,
+        target_language: &str,
+    ) -> Result<TranslationQuality> {
+        let target_language_name = language_code_to_name(target_language);
+        let source_language_name = language_code_to_name(&self.source_language);
+        
+        let quality_prompt = format!(
+            "You are a professional translation quality evaluator.\n\
+             \n\
+             Evaluate the translation quality from {} to {} ({}).\n\
+             \n\
+             IMPORTANT CRITERIA:\n\
+             1. The translation must be in {} language ONLY\n\
+             2. The translation must accurately convey the meaning of the source text\n\
+             3. The translation must be grammatically correct in {}\n\
+             4. The translation must be natural and fluent in {}\n\
+             \n\
+             Evaluate translation quality in one of the following levels:\n\
+             - [PERFECT]: The translation is perfect, in correct language, and no further improvement is needed.\n\
+             - [GOOD]: The translation is good and in correct language, but some minor improvements are needed.\n\
+             - [BAD]: The translation is bad, incorrect, or needs to be re-translated.\n\
+             - [INVALID]: The translation is in wrong language, invalid, not related to the source, or commentary instead of a translation.\n\
+             \n\
+             {}\n\
+             \n\
+             Please return the evaluation results in JSON format as {{\"evaluation\":\"evaluation result\"}}.\n\
+             \n\
+             [Source ({})]\n\
+             {}\n\
+             \n\
+             [Translation (should be in {})]\n\
+             {}\n\
+             \n\
+             [Context]\n\
+             {}",
+            source_language_name, target_language_name, target_language, 
+            target_language_name, target_language_name, target_language_name, guard::DATA_NOTICE,
+            source_language_name, guard::quote(original), target_language_name, guard::quote(translation), guard::quote(context)
+        );
+
+        let request = TranslationRequest {
+            model: self.model.clone(),
+            prompt: quality_prompt,
+            stream: false,
+            format: self.format.clone(),
+            options: self.options.clone(),
+        };
+
+        let started = Instant::now();
+        let quality_response = self.backend.generate(&self.client, &request).await?;
+        self.metrics.record(&self.model, started.elapsed(), quality_response.usage());
+
+        let eval_result: QualityEvaluation = schema::parse_response(&quality_response.response)?;
+        Ok(TranslationQuality::parse(&eval_result.evaluation))
+    }
+}
@@ -1,17 +1,24 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 use tracing::{info, warn, debug};
 
+use crate::cancel::Cancellation;
 use crate::config::TranslateConfig;
-use crate::error::{Result, ShuroError};
+use crate::error::Result;
+use crate::metadata::SegmentMetadata;
 use crate::quality::{Transcription, TranscriptionSegment};
-use super::{Translator, common::BaseTranslator};
+use super::journal::AnalysisStore;
+use super::{Translator, guard, schema, common::{BaseTranslator, RequestOptions, TranslationRequest}, services::TranslationServices};
 
 /// LLM-based translation with sliding window sentence splitting
 pub struct LlmTranslator {
     base: BaseTranslator,
     window_size: usize,
+    window_stride: usize,
+    max_windows_per_minute: usize,
+    early_exit_windows: usize,
     confidence_threshold: f64,
 }
 
@@ -21,15 +28,9 @@ struct LlmAnalysisResponse {
     sentences: Vec<String>,
 }
 
-/// Ollama response wrapper
-#[derive(Debug, Deserialize)]
-struct OllamaResponse {
-    /// The actual response content (may contain JSON)
-    response: String,
-}
-
-#[derive(Debug, Clone)]
-struct SentenceCandidate {
+/// Sentence detected by the window analysis
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SentenceCandidate {
     /// The reconstructed sentence text
     text: String,
     /// Number of windows that detected this sentence (or a variant)
@@ -40,12 +41,6 @@ struct SentenceCandidate {
 
 #[derive(Debug, Clone)]
 struct ReconstructedSentence {
-    /// Segment indices that make up this sentence
-    segment_indices: Vec<usize>,
-    /// Start time of the sentence (from first segment)
-    start_time: f64,
-    /// End time of the sentence (from last segment)
-    end_time: f64,
     /// Combined text of all segments
     text: String,
     /// Translated text
@@ -53,11 +48,14 @@ struct ReconstructedSentence {
 }
 
 impl LlmTranslator {
-    pub fn new(config: TranslateConfig) -> Self {
+    pub fn new(config: TranslateConfig, services: TranslationServices) -> Self {
         Self {
             window_size: config.llm_window_size,
+            window_stride: config.llm_window_stride,
+            max_windows_per_minute: config.llm_max_windows_per_minute,
+            early_exit_windows: config.llm_early_exit_windows,
             confidence_threshold: config.llm_confidence_threshold,
-            base: BaseTranslator::new(config),
+            base: BaseTranslator::with_services(config, services),
         }
     }
 
@@ -78,34 +76,26 @@ impl LlmTranslator {
         debug!("Analyzing window {}-{} with {} segments", 
                window_start, window_end, window_segments.len());
 
-        match self.base.client
-            .post(&format!("{}/api/generate", self.base.config.endpoint))
-            .json(&serde_json::json!({
-                "model": self.base.config.model,
-                "prompt": prompt,
-                "stream": false,
-                "format": "json"
-            }))
-            .send()
-            .await
-        {
-            Ok(response) => {
-                if !response.status().is_success() {
-                    let status = response.status();
-                    let error_text = response.text().await.unwrap_or_default();
-                    return Err(ShuroError::Translation(format!(
-                        "LLM analysis failed {}: {}", status, error_text
-                    )));
-                }
-
-                let response_text = response.text().await
-                    .map_err(|e| ShuroError::Translation(format!("Failed to read response: {}", e)))?;
-                
-                debug!("Raw LLM analysis response: {}", response_text);
-                
-                self.parse_analysis_response(&response_text, window_start)
+        let started = Instant::now();
+        let request = TranslationRequest {
+            model: self.base.config.model.clone(),
+            prompt,
+            stream: false,
+            format: schema::response_format(&self.base.config, schema::sentences()),
+            options: RequestOptions::for_model(&self.base.config, &self.base.config.model),
+        };
+        let analysis_response = self.base.backend.generate(&self.base.client, &request).await?;
+        self.base.metrics.record(&self.base.config.model, started.elapsed(), analysis_response.usage());
+
+        debug!("Raw LLM analysis response: {}", analysis_response.response);
+
+        match schema::parse_response::<LlmAnalysisResponse>(&analysis_response.response) {
+            Ok(parsed) => Ok(parsed.sentences),
+            Err(e) => {
+                // A window without sentences only lowers the detection counts of its neighbours
+                warn!("Failed to parse LLM analysis response: {}", e);
+                Ok(Vec::new())
             }
-            Err(e) => Err(ShuroError::Translation(format!("HTTP request failed: {}", e)))
         }
     }
 
@@ -116,6 +106,7 @@ impl LlmTranslator {
             .map(|text| format!("• {}", text))
             .collect::<Vec<_>>()
             .join("\n");
+        let segments_text = guard::quote(&segments_text);
 
         format!(
             r#"You are a professional editor. Your task is to reconstruct complete sentences from transcribed speech segments.
@@ -125,6 +116,8 @@ TASK: Combine the segments below into complete, natural sentences. Preserve the
 SEGMENTS:
 {}
 
+{}
+
 INSTRUCTIONS:
 1. Join segments that form complete thoughts
 2. Keep the original text exactly as written
@@ -141,153 +134,90 @@ Return ONLY a JSON array of sentences:
 }}
 
 Analyze the segments and return only the JSON response:"#,
-            segments_text
+            segments_text, guard::DATA_NOTICE
         )
     }
 
-    /// Parse LLM response to extract reconstructed sentences
-    fn parse_analysis_response(&self, response: &str, _window_start: usize) -> Result<Vec<String>> {
-        let response = response.trim();
-        
-        // First, try to parse as Ollama response format
-        if let Ok(ollama_response) = serde_json::from_str::<OllamaResponse>(response) {
-            let actual_response = ollama_response.response.trim();
-            debug!("Extracted inner response from Ollama format: {}", actual_response);
-            
-            // Try flexible parsing on the inner response
-            if let Some(sentences) = self.try_flexible_json_parsing(actual_response) {
-                debug!("Successfully parsed {} sentences from Ollama response", sentences.len());
-                return Ok(sentences);
-            }
-        }
-        
-        // Fallback: Try flexible parsing on the full response (for other LLM providers)
-        if let Some(sentences) = self.try_flexible_json_parsing(response) {
-            debug!("Successfully parsed {} sentences from direct response", sentences.len());
-            return Ok(sentences);
-        }
-
-        warn!("Failed to parse LLM analysis response: {}", response);
-        Ok(Vec::new()) // Return empty sentences on parse failure
-    }
-
-    /// Try to parse JSON with various common formats and wrappers
-    fn try_flexible_json_parsing(&self, text: &str) -> Option<Vec<String>> {
-        let text = text.trim();
-        
-        // 1. Try direct JSON parsing first
-        if let Ok(parsed) = serde_json::from_str::<LlmAnalysisResponse>(text) {
-            return Some(parsed.sentences);
-        }
-        
-        // 2. Try removing markdown code blocks (```json ... ``` or ``` ... ```)
-        let cleaned_text = self.remove_markdown_code_blocks(text);
-        if cleaned_text != text {
-            debug!("Removed markdown code blocks, trying to parse: {}", cleaned_text);
-            if let Ok(parsed) = serde_json::from_str::<LlmAnalysisResponse>(&cleaned_text) {
-                return Some(parsed.sentences);
-            }
-        }
-        
-        // 3. Try extracting JSON from mixed text (find first { to last })
-        if let Some(json_start) = text.find("{") {
-            if let Some(json_end) = text.rfind("}") {
-                let json_str = &text[json_start..=json_end];
-                if json_str != text {
-                    debug!("Extracted JSON from mixed text: {}", json_str);
-                    if let Ok(parsed) = serde_json::from_str::<LlmAnalysisResponse>(json_str) {
-                        return Some(parsed.sentences);
-                    }
-                }
-            }
-        }
-        
-        // 4. Try extracting from cleaned text as well
-        if let Some(json_start) = cleaned_text.find("{") {
-            if let Some(json_end) = cleaned_text.rfind("}") {
-                let json_str = &cleaned_text[json_start..=json_end];
-                debug!("Extracted JSON from cleaned text: {}", json_str);
-                if let Ok(parsed) = serde_json::from_str::<LlmAnalysisResponse>(json_str) {
-                    return Some(parsed.sentences);
-                }
-            }
-        }
-        
-        None
-    }
-
-    /// Remove markdown code blocks from text
-    fn remove_markdown_code_blocks(&self, text: &str) -> String {
-        let text = text.trim();
-        
-        // Handle ```json ... ``` pattern
-        if text.starts_with("```json") && text.ends_with("```") {
-            let inner = &text[7..text.len()-3]; // Remove ```json and ```
-            return inner.trim().to_string();
-        }
-        
-        // Handle ``` ... ``` pattern
-        if text.starts_with("```") && text.ends_with("```") {
-            let inner = &text[3..text.len()-3]; // Remove ``` and ```
-            return inner.trim().to_string();
-        }
-        
-        // Handle `json ... ` pattern (single backticks)
-        if text.starts_with("`json") && text.ends_with("`") {
-            let inner = &text[5..text.len()-1]; // Remove `json and `
-            return inner.trim().to_string();
-        }
-        
-        // Handle ` ... ` pattern (single backticks)
-        if text.starts_with("`") && text.ends_with("`") {
-            let inner = &text[1..text.len()-1]; // Remove ` and `
-            return inner.trim().to_string();
-        }
-        
-        // Return original text if no markdown patterns found
-        text.to_string()
-    }
-
     /// Process all segments using sliding window analysis to collect sentence candidates
+    ///
+    /// Windows already journaled by an interrupted run are not analyzed again.
     async fn analyze_all_segments(&self, segments: &[TranscriptionSegment]) -> Result<Vec<SentenceCandidate>> {
         let total_segments = segments.len();
         if total_segments < 2 {
             return Ok(Vec::new());
         }
 
-        let mut all_sentences = Vec::new();
-        let mut total_windows = 0;
+        let cache_dir = std::path::Path::new(&self.base.config.cache_dir);
+        let layout = [self.window_size, self.window_stride, self.max_windows_per_minute, self.early_exit_windows];
+        let mut store = AnalysisStore::open(cache_dir, segments, &self.base.config.model, &layout).await;
+        if let Some(candidates) = &store.journal.candidates {
+            return Ok(candidates.clone());
+        }
+
+        let minutes = match (segments.first(), segments.last()) {
+            (Some(first), Some(last)) => (last.end - first.start).max(0.0) / 60.0,
+            _ => 0.0,
+        };
+        let stride = window_stride(total_segments, minutes, self.window_stride, self.max_windows_per_minute);
+        if stride > self.window_stride {
+            info!("Advancing the analysis window by {} segments to stay within {} windows per minute",
+                  stride, self.max_windows_per_minute);
+        }
 
         // Sliding window analysis
+        let mut seen = HashSet::new();
+        let mut agreeing_windows = 0;
         let mut window_start = 0;
         while window_start < total_segments {
             let window_end = (window_start + self.window_size).min(total_segments);
             
-            info!("Analyzing window {}-{} of {} segments", 
-                  window_start + 1, window_end, total_segments);
-
-            match self.analyze_segments_window(segments, window_start).await {
-                Ok(sentences) => {
-                    if sentences.is_empty() {
-                        info!("   → No sentences detected in this window");
-                    } else {
-                        info!("   → Detected {} sentence(s):", sentences.len());
-                        for sentence in &sentences {
-                            if !sentence.trim().is_empty() {
-                                info!("     • {}", sentence);
-                                all_sentences.push(sentence.trim().to_string());
+            let sentences = match store.journal.windows.get(&window_start) {
+                Some(sentences) => {
+                    debug!("Window {}-{} already analyzed", window_start + 1, window_end);
+                    Some(sentences.clone())
+                }
+                None => {
+                    info!("Analyzing window {}-{} of {} segments", 
+                          window_start + 1, window_end, total_segments);
+
+                    match self.analyze_segments_window(segments, window_start).await {
+                        Ok(sentences) => {
+                            if sentences.is_empty() {
+                                info!("   → No sentences detected in this window");
+                            } else {
+                                info!("   → Detected {} sentence(s):", sentences.len());
+                                for sentence in &sentences {
+                                    info!("     • {}", sentence);
+                                }
                             }
+                            store.record_window(window_start, sentences.clone()).await?;
+                            Some(sentences)
+                        }
+                        Err(e) => {
+                            warn!("Failed to analyze window {}-{}: {}", window_start, window_end, e);
+                            None
                         }
                     }
-                    total_windows += 1;
                 }
-                Err(e) => {
-                    warn!("Failed to analyze window {}-{}: {}", window_start, window_end, e);
+            };
+
+            // A window agrees with its predecessors when every sentence it detects was seen before
+            let mut step = stride;
+            if let Some(sentences) = sentences {
+                let mut agrees = true;
+                for sentence in &sentences {
+                    agrees &= !seen.insert(self.normalize_text(sentence));
+                }
+                agreeing_windows = if agrees { agreeing_windows + 1 } else { 0 };
+                if self.early_exit_windows > 0 && agreeing_windows >= self.early_exit_windows {
+                    debug!("{} consecutive windows agree, skipping to segment {}", agreeing_windows, window_end + 1);
+                    step = step.max(window_end - window_start);
+                    agreeing_windows = 0;
                 }
             }
 
-            // Move window forward by 1 segment for sliding analysis
-            window_start += 1;
+            // Move window forward for sliding analysis
+            window_start += step;
             
             // Don't slide if remaining segments are less than minimum window
             if window_start + 2 >= total_segments {
@@ -295,8 +225,17 @@ Analyze the segments and return only the JSON response:"#,
             }
         }
 
+        let total_windows = store.journal.windows.len();
+        let all_sentences: Vec<String> = store.journal.windows.values()
+            .flatten()
+            .map(|sentence| sentence.trim())
+            .filter(|sentence| !sentence.is_empty())
+            .map(str::to_string)
+            .collect();
+
         // Calculate confidence for each unique sentence
         let sentence_candidates = self.calculate_sentence_confidence(all_sentences, total_windows);
+        store.complete(&sentence_candidates).await?;
         
         // Log summary of analysis results
         info!("");
@@ -352,7 +291,7 @@ Analyze the segments and return only the JSON response:"#,
         
         // Handle prefix/suffix relationships
         // Sort by length (descending) to process longer sentences first
-        candidates.sort_by(|a, b| b.text.len().cmp(&a.text.len()));
+        candidates.sort_by_key(|c| std::cmp::Reverse(c.text.len()));
         
         let mut final_candidates: Vec<SentenceCandidate> = Vec::new();
         let mut used_sentences = HashSet::new();
@@ -476,11 +415,8 @@ Analyze the segments and return only the JSON response:"#,
         let mut sentences = Vec::new();
 
         // Convert candidates to reconstructed sentences
-        for (idx, candidate) in sentence_candidates.iter().enumerate() {
+        for candidate in &sentence_candidates {
             sentences.push(ReconstructedSentence {
-                segment_indices: vec![idx], // Use sentence index as a placeholder
-                start_time: segments.first().map(|s| s.start).unwrap_or(0.0),
-                end_time: segments.last().map(|s| s.end).unwrap_or(0.0),
                 text: candidate.text.clone(),
                 translated_text: String::new(),
             });
@@ -489,11 +425,8 @@ Analyze the segments and return only the JSON response:"#,
         // If no high-confidence sentences found, fall back to individual segments
         if sentences.is_empty() {
             info!("No high-confidence sentences found, falling back to individual segments");
-            for (idx, segment) in segments.iter().enumerate() {
+            for segment in segments {
                 sentences.push(ReconstructedSentence {
-                    segment_indices: vec![idx],
-                    start_time: segment.start,
-                    end_time: segment.end,
                     text: segment.text.clone(),
                     translated_text: String::new(),
                 });
@@ -574,6 +507,8 @@ Analyze the segments and return only the JSON response:"#,
         } else {
             // We have reconstructed sentences, replace the entire transcription
             // Create new segments from the reconstructed sentences
+            // Sentences span several segments; only entries they all share carry over
+            let metadata = SegmentMetadata::shared(transcription.segments.iter().map(|s| &s.metadata));
             let mut new_segments = Vec::new();
             let segment_duration = if !transcription.segments.is_empty() {
                 (transcription.segments.last().unwrap().end - transcription.segments.first().unwrap().start) 
@@ -596,6 +531,8 @@ Analyze the segments and return only the JSON response:"#,
                     avg_logprob: 0.0,
                     compression_ratio: 1.0,
                     no_speech_prob: 0.0,
+                    words: vec![],
+                    metadata: metadata.clone(),
                 });
             }
             
@@ -609,67 +546,103 @@ Analyze the segments and return only the JSON response:"#,
     }
 }
 
+/// Segments between window starts: `stride`, grown so that no more than `max_per_minute`
+/// windows are analyzed per minute of content (0 = unbounded)
+fn window_stride(total_segments: usize, minutes: f64, stride: usize, max_per_minute: usize) -> usize {
+    let stride = stride.max(1);
+    if max_per_minute == 0 {
+        return stride;
+    }
+    let max_windows = ((minutes * max_per_minute as f64).ceil() as usize).max(1);
+    stride.max(total_segments.div_ceil(max_windows))
+}
+
 #[async_trait]
 impl Translator for LlmTranslator {
+    fn retries(&self) -> usize {
+        self.base.retries()
+    }
+
+    fn cache_hits(&self) -> usize {
+        self.base.cache_hits()
+    }
+
     /// LLM-based translation with sliding window sentence analysis
     async fn translate_transcription(
         &mut self,
         transcription: &mut Transcription,
         target_language: &str,
         _context: Option<&str>,
+        cancel: &Cancellation,
     ) -> Result<()> {
-        info!("Starting LLM translation with sliding window analysis to {}", target_language);
-        
-        let total_segments = transcription.segments.len();
-        if total_segments == 0 {
-            return Ok(());
-        }
-
-        info!("Step 1: Analyzing {} segments with sliding window (size: {})", 
-              total_segments, self.window_size);
-
-        // Step 1: Analyze all segments with sliding window to get sentence candidates
-        let sentence_candidates = self.analyze_all_segments(&transcription.segments).await?;
+        cancel.run(async {
+            info!("Starting LLM translation with sliding window analysis to {}", target_language);
         
-        // Step 2: Filter by confidence threshold
-        info!("Step 2: Evaluating {} sentence candidates", sentence_candidates.len());
-        let final_sentences = self.finalize_sentences(sentence_candidates);
-        
-        info!("Selected {} high-confidence sentences for translation", final_sentences.len());
-
-        // Step 3: Convert to reconstructed sentences
-        info!("Step 3: Creating sentences for translation");
-        let mut sentences = self.reconstruct_sentences_from_candidates(&transcription.segments, final_sentences);
-        
-        info!("Reconstructed {} sentences", sentences.len());
-
-        // Step 4: Translate sentences
-        info!("Step 4: Translating reconstructed sentences");
-        self.translate_sentences(&mut sentences, target_language).await?;
-
-        // Step 5: Update original transcription
-        info!("Step 5: Updating transcription with translated sentences");
-        self.update_transcription_with_sentences(transcription, &sentences);
+            let total_segments = transcription.segments.len();
+            if total_segments == 0 {
+                return Ok(());
+            }
 
-        // Final summary
-        let successfully_translated = sentences.iter()
-            .filter(|s| s.translated_text != s.text)
-            .count();
-        let kept_original = sentences.len() - successfully_translated;
-        
-        info!("");
-        info!("✅ LLM TRANSLATION COMPLETED");
-        info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-        info!("📊 Final Results:");
-        info!("   • Original segments: {}", total_segments);
-        info!("   • Reconstructed sentences: {}", sentences.len());
-        info!("   • Successfully translated: {}", successfully_translated);
-        info!("   • Kept original (failed): {}", kept_original);
-        info!("   • Translation success rate: {:.1}%", 
-              (successfully_translated as f64 / sentences.len() as f64) * 100.0);
-        info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-        info!("");
-        
-        Ok(())
+            info!("Step 1: Analyzing {} segments with sliding window (size: {})", 
+                  total_segments, self.window_size);
+
+            // Step 1: Analyze all segments with sliding window to get sentence candidates
+            let sentence_candidates = self.analyze_all_segments(&transcription.segments).await?;
+        
+            // Step 2: Filter by confidence threshold
+            info!("Step 2: Evaluating {} sentence candidates", sentence_candidates.len());
+            let final_sentences = self.finalize_sentences(sentence_candidates);
+        
+            info!("Selected {} high-confidence sentences for translation", final_sentences.len());
+
+            // Step 3: Convert to reconstructed sentences
+            info!("Step 3: Creating sentences for translation");
+            let mut sentences = self.reconstruct_sentences_from_candidates(&transcription.segments, final_sentences);
+        
+            info!("Reconstructed {} sentences", sentences.len());
+
+            // Step 4: Translate sentences
+            info!("Step 4: Translating reconstructed sentences");
+            self.translate_sentences(&mut sentences, target_language).await?;
+
+            // Step 5: Update original transcription
+            info!("Step 5: Updating transcription with translated sentences");
+            self.update_transcription_with_sentences(transcription, &sentences);
+
+            // Final summary
+            let successfully_translated = sentences.iter()
+                .filter(|s| s.translated_text != s.text)
+                .count();
+            let kept_original = sentences.len() - successfully_translated;
+        
+            info!("");
+            info!("✅ LLM TRANSLATION COMPLETED");
+            info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+            info!("📊 Final Results:");
+            info!("   • Original segments: {}", total_segments);
+            info!("   • Reconstructed sentences: {}", sentences.len());
+            info!("   • Successfully translated: {}", successfully_translated);
+            info!("   • Kept original (failed): {}", kept_original);
+            info!("   • Translation success rate: {:.1}%", 
+                  (successfully_translated as f64 / sentences.len() as f64) * 100.0);
+            info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+            info!("");
+        
+            Ok(())
+        }).await
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_stride() {
+        assert_eq!(window_stride(600, 30.0, 1, 0), 1);
+        assert_eq!(window_stride(600, 30.0, 0, 0), 1);
+        // 600 segments over 30 minutes at 10 windows per minute: 300 windows, every other segment
+        assert_eq!(window_stride(600, 30.0, 1, 10), 2);
+        assert_eq!(window_stride(600, 30.0, 4, 10), 4);
+        assert_eq!(window_stride(600, 0.0, 1, 10), 600);
+    }
+}
@@ -0,0 +1,451 @@
+// Translation memory
+//
+// The translation cache is keyed on the exact source text, context and model, so a line that
+// comes back in the next episode with other neighbors or another model is translated again.
+// With `memory.enabled`, every accepted translation is also recorded in a translation memory,
+// `<cache_dir>/memory.jsonl`, keyed by language pair and the source text normalized for case and
+// spacing. Before a segment is sent to the model, the memory is searched for the same line or a
+// near-identical one (character edit distance, at least `memory.min_similarity` similar), and a
+// match is used as the translation unless it leaves out a mandatory glossary term. Inside a
+// project the cache directory, and with it the memory, is the project's, so every episode of a
+// series shares it.
+//
+// `shuro tm export` writes the memory as TMX 1.4 for CAT tools, and `shuro tm import` adds the
+// translation units of a TMX file. Re-translation records new translations but never reuses
+// remembered ones, since it runs to replace them.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::config::TranslateConfig;
+use crate::error::{Result, ShuroError};
+
+/// File, relative to the cache directory, holding the translation memory
+pub const MEMORY_FILE: &str = "memory.jsonl";
+
+/// Translation of a source line into one language
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryEntry {
+    pub source_language: String,
+    pub target_language: String,
+    pub source_text: String,
+    pub translation: String,
+    /// Seconds since the Unix epoch
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+/// Remembered translation found for a source line
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryMatch {
+    pub entry: MemoryEntry,
+    /// 1.0 for the same line after normalizing case and spacing
+    pub similarity: f64,
+}
+
+/// Language pair and normalized source text
+type MemoryKey = (String, String, String);
+
+/// Translation memory stored in a JSON lines file; later lines override earlier ones with the
+/// same key
+pub struct TranslationMemory {
+    path: PathBuf,
+    min_similarity: f64,
+    reuse: bool,
+    entries: Mutex<Option<HashMap<MemoryKey, MemoryEntry>>>,
+}
+
+impl TranslationMemory {
+    /// Memory in the cache directory of `config`
+    pub fn new(config: &TranslateConfig) -> Self {
+        Self::open(&config.cache_dir, config.memory.min_similarity)
+    }
+
+    /// Memory in `cache_dir`, matching lines at least `min_similarity` similar
+    pub fn open<P: AsRef<Path>>(cache_dir: P, min_similarity: f64) -> Self {
+        Self {
+            path: cache_dir.as_ref().join(MEMORY_FILE),
+            min_similarity,
+            reuse: true,
+            entries: Mutex::new(None),
+        }
+    }
+
+    /// Record translations without reusing remembered ones
+    pub fn without_reuse(mut self) -> Self {
+        self.reuse = false;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Best remembered translation of `text` from `source_language` into `target_language`
+    pub async fn lookup(&self, source_language: &str, target_language: &str, text: &str) -> Result<Option<MemoryMatch>> {
+        if !self.reuse {
+            return Ok(None);
+        }
+        let mut entries = self.entries.lock().await;
+        let entries = self.loaded(&mut entries).await?;
+        let normalized = normalize(text);
+        let key = (source_language.to_string(), target_language.to_string(), normalized.clone());
+        if let Some(entry) = entries.get(&key) {
+            return Ok(Some(MemoryMatch { entry: entry.clone(), similarity: 1.0 }));
+        }
+        if self.min_similarity >= 1.0 {
+            return Ok(None);
+        }
+
+        let length = normalized.chars().count();
+        let best = entries.iter()
+            .filter(|((source, target, _), _)| source == source_language && target == target_language)
+            // Lines differing in length by more than the allowed edits cannot match
+            .filter(|((_, _, other), _)| {
+                let other_length = other.chars().count();
+                length.min(other_length) as f64 >= self.min_similarity * length.max(other_length) as f64
+            })
+            .map(|((_, _, other), entry)| (similarity(&normalized, other), entry))
+            .filter(|(similarity, _)| *similarity >= self.min_similarity)
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(best.map(|(similarity, entry)| MemoryMatch { entry: entry.clone(), similarity }))
+    }
+
+    /// Remember the translation of `source_text`
+    pub async fn record(&self, source_language: &str, target_language: &str, source_text: &str, translation: &str) -> Result<()> {
+        let entry = MemoryEntry {
+            source_language: source_language.to_string(),
+            target_language: target_language.to_string(),
+            source_text: source_text.trim().to_string(),
+            translation: translation.trim().to_string(),
+            updated_at: now(),
+        };
+        self.add(vec![entry]).await.map(|_| ())
+    }
+
+    /// Remember `new_entries`, returning how many changed the memory
+    pub async fn add(&self, new_entries: Vec<MemoryEntry>) -> Result<usize> {
+        let mut entries = self.entries.lock().await;
+        let entries = self.loaded(&mut entries).await?;
+        let mut lines = String::new();
+        let mut added = 0;
+        for entry in new_entries {
+            if entry.source_text.is_empty() || entry.translation.is_empty() {
+                continue;
+            }
+            let key = key_of(&entry);
+            if entries.get(&key).is_some_and(|known| known.translation == entry.translation) {
+                continue;
+            }
+            lines.push_str(&serde_json::to_string(&entry)
+                .map_err(|e| ShuroError::Translation(format!("Failed to serialize translation memory entry: {}", e)))?);
+            lines.push('\n');
+            entries.insert(key, entry);
+            added += 1;
+        }
+        if lines.is_empty() {
+            return Ok(0);
+        }
+
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let mut file = tokio::fs::OpenOptions::new().create(true).append(true).open(&self.path).await?;
+        file.write_all(lines.as_bytes()).await?;
+        Ok(added)
+    }
+
+    /// Remembered translations sorted by language pair and source text
+    pub async fn entries(&self) -> Result<Vec<MemoryEntry>> {
+        let mut entries = self.entries.lock().await;
+        let mut entries: Vec<MemoryEntry> = self.loaded(&mut entries).await?.values().cloned().collect();
+        entries.sort_by(|a, b| {
+            (&a.source_language, &a.source_text, &a.target_language).cmp(&(&b.source_language, &b.source_text, &b.target_language))
+        });
+        Ok(entries)
+    }
+
+    /// Entries read from the memory file on first use
+    async fn loaded<'a>(&self, entries: &'a mut Option<HashMap<MemoryKey, MemoryEntry>>) -> Result<&'a mut HashMap<MemoryKey, MemoryEntry>> {
+        if entries.is_none() {
+            let mut loaded = HashMap::new();
+            match tokio::fs::read_to_string(&self.path).await {
+                Ok(content) => {
+                    for (index, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+                        match serde_json::from_str::<MemoryEntry>(line) {
+                            Ok(entry) => {
+                                loaded.insert(key_of(&entry), entry);
+                            }
+                            Err(e) => warn!("Skipping line {} of {}: {}", index + 1, self.path.display(), e),
+                        }
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+            *entries = Some(loaded);
+        }
+        Ok(entries.as_mut().expect("entries were just loaded"))
+    }
+}
+
+fn key_of(entry: &MemoryEntry) -> MemoryKey {
+    (entry.source_language.clone(), entry.target_language.clone(), normalize(&entry.source_text))
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Source text as compared by the memory: lowercase, with runs of whitespace as one space
+pub fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// One minus the character edit distance of `a` and `b` relative to the longer of them
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
+
+/// TMX 1.4 document with one translation unit per source line
+pub fn to_tmx(entries: &[MemoryEntry]) -> String {
+    let mut units: BTreeMap<(&str, &str), Vec<&MemoryEntry>> = BTreeMap::new();
+    for entry in entries {
+        units.entry((&entry.source_language, &entry.source_text)).or_default().push(entry);
+    }
+    let mut source_languages: Vec<&str> = units.keys().map(|(language, _)| *language).collect();
+    source_languages.dedup();
+    let srclang = match source_languages.as_slice() {
+        [language] => *language,
+        _ => "*all*",
+    };
+
+    let mut tmx = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<tmx version=\"1.4\">\n");
+    tmx.push_str(&format!(
+        "  <header creationtool=\"shuro\" creationtoolversion=\"{}\" segtype=\"sentence\" o-tmf=\"shuro\" \
+         adminlang=\"en\" srclang=\"{}\" datatype=\"plaintext\"/>\n  <body>\n",
+        env!("CARGO_PKG_VERSION"), escape(srclang)
+    ));
+    for ((source_language, source_text), translations) in units {
+        tmx.push_str(&format!("    <tu srclang=\"{}\">\n", escape(source_language)));
+        tmx.push_str(&format!("      <tuv xml:lang=\"{}\"><seg>{}</seg></tuv>\n", escape(source_language), escape(source_text)));
+        for entry in translations {
+            tmx.push_str(&format!("      <tuv xml:lang=\"{}\"><seg>{}</seg></tuv>\n", escape(&entry.target_language), escape(&entry.translation)));
+        }
+        tmx.push_str("    </tu>\n");
+    }
+    tmx.push_str("  </body>\n</tmx>\n");
+    tmx
+}
+
+/// Entries of the translation units of a TMX document; the source of a unit is its variant in
+/// the unit's or header's `srclang`, or its first variant
+pub fn parse_tmx(content: &str) -> Result<Vec<MemoryEntry>> {
+    let (header, _) = elements(content, "header").into_iter().next()
+        .ok_or_else(|| ShuroError::Config("not a TMX document: no <header>".to_string()))?;
+    let default_source = attribute(header, "srclang").filter(|language| language != "*all*");
+
+    let mut entries = Vec::new();
+    let updated_at = now();
+    for (attributes, unit) in elements(content, "tu") {
+        let variants: Vec<(String, String)> = elements(unit, "tuv").into_iter()
+            .filter_map(|(attributes, variant)| {
+                let language = attribute(attributes, "xml:lang").or_else(|| attribute(attributes, "lang"))?;
+                let (_, seg) = elements(variant, "seg").into_iter().next()?;
+                Some((language, text_of(seg)))
+            })
+            .collect();
+        let source_language = attribute(attributes, "srclang").filter(|language| language != "*all*").or(default_source.clone());
+        let source = match &source_language {
+            Some(language) => variants.iter().position(|(l, _)| l.eq_ignore_ascii_case(language)),
+            None => (!variants.is_empty()).then_some(0),
+        };
+        let Some(source) = source else { continue };
+        let (source_language, source_text) = &variants[source];
+        for (index, (target_language, translation)) in variants.iter().enumerate() {
+            if index != source && !translation.trim().is_empty() && !source_text.trim().is_empty() {
+                entries.push(MemoryEntry {
+                    source_language: source_language.clone(),
+                    target_language: target_language.clone(),
+                    source_text: source_text.trim().to_string(),
+                    translation: translation.trim().to_string(),
+                    updated_at,
+                });
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Attributes and contents of the `<name>` elements in `xml` (not nested in each other)
+fn elements<'a>(xml: &'a str, name: &str) -> Vec<(&'a str, &'a str)> {
+    let open = format!("<{}", name);
+    let close = format!("</{}>", name);
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let after = &rest[start + open.len()..];
+        if !after.starts_with(|c: char| c.is_whitespace() || c == '>' || c == '/') {
+            rest = after;
+            continue;
+        }
+        let Some(tag_end) = after.find('>') else { break };
+        let attributes = &after[..tag_end];
+        if let Some(attributes) = attributes.strip_suffix('/') {
+            found.push((attributes, ""));
+            rest = &after[tag_end + 1..];
+            continue;
+        }
+        let body = &after[tag_end + 1..];
+        let Some(end) = body.find(&close) else { break };
+        found.push((attributes, &body[..end]));
+        rest = &body[end + close.len()..];
+    }
+    found
+}
+
+/// Unescaped value of an attribute
+fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let mut rest = attributes;
+    while let Some(position) = rest.find(name) {
+        let preceded = rest[..position].chars().next_back().is_none_or(char::is_whitespace);
+        let value = rest[position + name.len()..].trim_start().strip_prefix('=').map(str::trim_start);
+        if preceded && let Some(value) = value && let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') {
+            let value = &value[1..];
+            return value.find(quote).map(|end| unescape(&value[..end]));
+        }
+        rest = &rest[position + name.len()..];
+    }
+    None
+}
+
+/// Text of a segment without inline markup; the native codes inside `<bpt>`, `<ept>`, `<ph>`
+/// and `<it>` are dropped
+fn text_of(seg: &str) -> String {
+    let mut text = String::new();
+    let mut rest = seg;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let tag = &rest[start + 1..];
+        if let Some(cdata) = tag.strip_prefix("![CDATA[") && let Some(end) = cdata.find("]]>") {
+            text.push_str(&escape(&cdata[..end]));
+            rest = &cdata[end + 3..];
+            continue;
+        }
+        let Some(tag_end) = tag.find('>') else {
+            rest = "";
+            break;
+        };
+        let name = tag[..tag_end].split(|c: char| c.is_whitespace() || c == '/').next().unwrap_or_default();
+        let close = format!("</{}>", name);
+        rest = &tag[tag_end + 1..];
+        if ["bpt", "ept", "ph", "it"].contains(&name) && !tag[..tag_end].ends_with('/') {
+            rest = rest.find(&close).map_or("", |end| &rest[end + close.len()..]);
+        }
+    }
+    text.push_str(rest);
+    unescape(&text)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        let entity = rest[start + 1..].find(';').map(|end| &rest[start + 1..start + 1 + end]);
+        let decoded = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X"))
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(|code| code.ok())
+                .and_then(char::from_u32),
+        });
+        match (entity, decoded) {
+            (Some(entity), Some(c)) => {
+                unescaped.push(c);
+                rest = &rest[start + entity.len() + 2..];
+            }
+            _ => {
+                unescaped.push('&');
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_translation_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        let memory = TranslationMemory::open(dir.path(), 0.9);
+        memory.record("en", "ja", "Where is the  captain?", "船長はどこだ？").await.unwrap();
+        memory.record("en", "ja", "Run!", "走れ！").await.unwrap();
+        memory.record("en", "ko", "Run!", "뛰어!").await.unwrap();
+
+        // Another run reads the file; case and spacing do not matter
+        let memory = TranslationMemory::open(dir.path(), 0.9);
+        let exact = memory.lookup("en", "ja", "where is the captain?").await.unwrap().unwrap();
+        assert_eq!((exact.entry.translation.as_str(), exact.similarity), ("船長はどこだ？", 1.0));
+        let near = memory.lookup("en", "ja", "Where's the captain?").await.unwrap().unwrap();
+        assert!(near.similarity >= 0.9 && near.similarity < 1.0);
+        assert!(memory.lookup("en", "ja", "Where is the cook?").await.unwrap().is_none());
+        assert!(memory.lookup("en", "fr", "Run!").await.unwrap().is_none());
+        assert!(TranslationMemory::open(dir.path(), 0.9).without_reuse().lookup("en", "ja", "Run!").await.unwrap().is_none());
+
+        let tmx = to_tmx(&memory.entries().await.unwrap());
+        assert!(tmx.contains("<header creationtool=\"shuro\"") && tmx.contains("srclang=\"en\""));
+        let mut entries = parse_tmx(&tmx).unwrap();
+        entries.iter_mut().for_each(|entry| entry.updated_at = 0);
+        let mut expected = memory.entries().await.unwrap();
+        expected.iter_mut().for_each(|entry| entry.updated_at = 0);
+        assert_eq!(entries, expected);
+
+        let imported = parse_tmx(
+            "<tmx version=\"1.4\"><header srclang=\"*all*\"/><body>\
+             <tu><tuv xml:lang=\"en-US\"><seg>Fish &amp; <bpt i=\"1\">&lt;i&gt;</bpt>chips<ept i=\"1\">&lt;/i&gt;</ept></seg></tuv>\
+             <tuv xml:lang=\"de-DE\"><seg>Fisch &#x26; Pommes</seg></tuv></tu></body></tmx>",
+        ).unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!((imported[0].source_language.as_str(), imported[0].target_language.as_str()), ("en-US", "de-DE"));
+        assert_eq!((imported[0].source_text.as_str(), imported[0].translation.as_str()), ("Fish & chips", "Fisch & Pommes"));
+        assert_eq!(memory.add(imported.clone()).await.unwrap(), 1);
+        assert_eq!(memory.add(imported).await.unwrap(), 0);
+        assert!(parse_tmx("<html></html>").is_err());
+    }
+}
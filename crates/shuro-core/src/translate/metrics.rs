@@ -0,0 +1,259 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::info;
+
+/// Upper bounds of the latency buckets counted per model (milliseconds)
+pub const LATENCY_BUCKETS_MS: [f64; 10] = [100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10_000.0, 30_000.0, 60_000.0, 120_000.0];
+/// Latest requests per model the percentiles are taken over, so a long-lived process (`shuro
+/// serve`) keeps a bounded number of samples
+const SAMPLE_WINDOW: usize = 1024;
+
+/// Latest `SAMPLE_WINDOW` samples, oldest first
+#[derive(Debug, Clone, Default)]
+struct Window(VecDeque<f64>);
+
+impl Window {
+    fn push(&mut self, value: f64) {
+        if self.0.len() == SAMPLE_WINDOW {
+            self.0.pop_front();
+        }
+        self.0.push_back(value);
+    }
+
+    fn percentile(&self, pct: f64) -> Option<f64> {
+        let values: Vec<f64> = self.0.iter().copied().collect();
+        percentile(&values, pct)
+    }
+}
+
+/// Latency and throughput collected for a single model
+#[derive(Debug, Clone, Default)]
+struct ModelSamples {
+    requests: u64,
+    /// Requests that took at most each bound of `LATENCY_BUCKETS_MS`
+    latency_buckets: [u64; LATENCY_BUCKETS_MS.len()],
+    latency_sum_ms: f64,
+    latencies_ms: Window,
+    tokens_per_sec: Window,
+    prompt_tokens: u64,
+    generated_tokens: u64,
+}
+
+/// Aggregated statistics for a single model
+#[derive(Debug, Clone)]
+pub struct ModelMetricsSummary {
+    pub model: String,
+    pub requests: u64,
+    /// Percentiles are of the latest `SAMPLE_WINDOW` requests
+    pub latency_p50_ms: f64,
+    pub latency_p95_ms: f64,
+    pub tokens_per_sec_p50: Option<f64>,
+    pub tokens_per_sec_p95: Option<f64>,
+    pub prompt_tokens: u64,
+    pub generated_tokens: u64,
+}
+
+/// Request latencies of a single model counted into buckets
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyHistogram {
+    pub model: String,
+    /// Requests that took at most each bound of `LATENCY_BUCKETS_MS`
+    pub buckets: Vec<u64>,
+    pub sum_ms: f64,
+    pub count: u64,
+}
+
+/// Token counters reported by Ollama alongside a generate response
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenUsage {
+    pub prompt_tokens: Option<u64>,
+    pub generated_tokens: Option<u64>,
+    /// Time spent generating tokens, in nanoseconds
+    pub generation_ns: Option<u64>,
+}
+
+/// Per-model latency and token throughput collected during a run
+#[derive(Debug)]
+pub struct TranslationMetrics {
+    samples: Mutex<HashMap<String, ModelSamples>>,
+    log_interval: usize,
+}
+
+impl TranslationMetrics {
+    /// Create a collector that logs a snapshot every `log_interval` requests (0 disables periodic logging)
+    pub fn new(log_interval: usize) -> Self {
+        Self {
+            samples: Mutex::new(HashMap::new()),
+            log_interval,
+        }
+    }
+
+    /// Record a completed model request
+    pub fn record(&self, model: &str, latency: Duration, usage: TokenUsage) {
+        let requests = {
+            let mut samples = self.samples.lock().expect("metrics lock poisoned");
+            let entry = samples.entry(model.to_string()).or_default();
+
+            let latency_ms = latency.as_secs_f64() * 1000.0;
+            entry.requests += 1;
+            for (bucket, bound) in entry.latency_buckets.iter_mut().zip(LATENCY_BUCKETS_MS) {
+                if latency_ms <= bound {
+                    *bucket += 1;
+                }
+            }
+            entry.latency_sum_ms += latency_ms;
+            entry.latencies_ms.push(latency_ms);
+            entry.prompt_tokens += usage.prompt_tokens.unwrap_or(0);
+            entry.generated_tokens += usage.generated_tokens.unwrap_or(0);
+
+            if let (Some(tokens), Some(ns)) = (usage.generated_tokens, usage.generation_ns)
+                && ns > 0 {
+                entry.tokens_per_sec.push(tokens as f64 / (ns as f64 / 1_000_000_000.0));
+            }
+
+            entry.requests
+        };
+
+        if self.log_interval > 0 && requests % self.log_interval as u64 == 0 {
+            self.log_snapshot();
+        }
+    }
+
+    /// Summarize collected samples for every model, ordered by model name
+    pub fn summary(&self) -> Vec<ModelMetricsSummary> {
+        let samples = self.samples.lock().expect("metrics lock poisoned");
+        let mut summaries: Vec<ModelMetricsSummary> = samples
+            .iter()
+            .map(|(model, s)| ModelMetricsSummary {
+                model: model.clone(),
+                requests: s.requests,
+                latency_p50_ms: s.latencies_ms.percentile(50.0).unwrap_or(0.0),
+                latency_p95_ms: s.latencies_ms.percentile(95.0).unwrap_or(0.0),
+                tokens_per_sec_p50: s.tokens_per_sec.percentile(50.0),
+                tokens_per_sec_p95: s.tokens_per_sec.percentile(95.0),
+                prompt_tokens: s.prompt_tokens,
+                generated_tokens: s.generated_tokens,
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.model.cmp(&b.model));
+        summaries
+    }
+
+    /// Latencies of every model counted into cumulative buckets up to `LATENCY_BUCKETS_MS`,
+    /// ordered by model name
+    pub fn latency_histograms(&self) -> Vec<LatencyHistogram> {
+        let samples = self.samples.lock().expect("metrics lock poisoned");
+        let mut histograms: Vec<LatencyHistogram> = samples
+            .iter()
+            .map(|(model, s)| LatencyHistogram {
+                model: model.clone(),
+                buckets: s.latency_buckets.to_vec(),
+                sum_ms: s.latency_sum_ms,
+                count: s.requests,
+            })
+            .collect();
+        histograms.sort_by(|a, b| a.model.cmp(&b.model));
+        histograms
+    }
+
+    /// Log a one-line snapshot per model
+    pub fn log_snapshot(&self) {
+        for s in self.summary() {
+            info!("📈 {} │ {} requests │ latency p50 {:.0}ms p95 {:.0}ms │ {}",
+                  s.model, s.requests, s.latency_p50_ms, s.latency_p95_ms, format_throughput(&s));
+        }
+    }
+
+    /// Log the final per-model summary
+    pub fn log_summary(&self) {
+        let summaries = self.summary();
+        if summaries.is_empty() {
+            return;
+        }
+
+        info!("");
+        info!("📈 TRANSLATION MODEL METRICS");
+        info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        for s in &summaries {
+            info!("🤖 {}:", s.model);
+            info!("   • Requests: {}", s.requests);
+            info!("   • Latency: p50 {:.0}ms, p95 {:.0}ms", s.latency_p50_ms, s.latency_p95_ms);
+            info!("   • Throughput: {}", format_throughput(s));
+            info!("   • Tokens: {} prompt, {} generated", s.prompt_tokens, s.generated_tokens);
+        }
+        info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        info!("");
+    }
+}
+
+fn format_throughput(summary: &ModelMetricsSummary) -> String {
+    match (summary.tokens_per_sec_p50, summary.tokens_per_sec_p95) {
+        (Some(p50), Some(p95)) => format!("p50 {:.1} tok/s, p95 {:.1} tok/s", p50, p95),
+        _ => "no token counts reported".to_string(),
+    }
+}
+
+/// Nearest-rank percentile of the given samples
+fn percentile(values: &[f64], pct: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let values: Vec<f64> = (1..=100).map(|v| v as f64).collect();
+        assert_eq!(percentile(&values, 50.0), Some(50.0));
+        assert_eq!(percentile(&values, 95.0), Some(95.0));
+        assert_eq!(percentile(&[7.0], 95.0), Some(7.0));
+        assert_eq!(percentile(&[], 50.0), None);
+    }
+
+    #[test]
+    fn test_record_tokens_per_sec() {
+        let metrics = TranslationMetrics::new(0);
+        metrics.record("m", Duration::from_millis(200), TokenUsage {
+            prompt_tokens: Some(10),
+            generated_tokens: Some(50),
+            generation_ns: Some(500_000_000),
+        });
+        let summary = metrics.summary();
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary[0].requests, 1);
+        assert_eq!(summary[0].tokens_per_sec_p50, Some(100.0));
+        assert_eq!(summary[0].generated_tokens, 50);
+
+        let histograms = metrics.latency_histograms();
+        assert_eq!(histograms[0].buckets[..3], [0, 1, 1]);
+        assert_eq!(histograms[0].count, 1);
+    }
+
+    #[test]
+    fn test_bounded_samples() {
+        let metrics = TranslationMetrics::new(0);
+        for ms in 0..3 * SAMPLE_WINDOW as u64 {
+            metrics.record("m", Duration::from_millis(ms), TokenUsage::default());
+        }
+        let summary = metrics.summary();
+        assert_eq!(summary[0].requests, 3 * SAMPLE_WINDOW as u64);
+        // Percentiles cover the latest requests
+        assert_eq!(summary[0].latency_p50_ms, (2 * SAMPLE_WINDOW + SAMPLE_WINDOW / 2 - 1) as f64);
+        let samples = metrics.samples.lock().unwrap();
+        assert_eq!(samples["m"].latencies_ms.0.len(), SAMPLE_WINDOW);
+        drop(samples);
+
+        let histograms = metrics.latency_histograms();
+        assert_eq!(histograms[0].count, 3 * SAMPLE_WINDOW as u64);
+        assert_eq!(histograms[0].buckets[0], 101);
+        assert_eq!(*histograms[0].buckets.last().unwrap(), 3 * SAMPLE_WINDOW as u64);
+    }
+}
@@ -0,0 +1,149 @@
+// Modular translation architecture
+//
+// This module provides different translation strategies through a factory pattern:
+// - Simple: Direct segment translation  
+// - Context: Context-aware translation; a QualityJudge evaluates translations in background
+//   tasks and only rejected segments are translated again
+// - NLP: NLP-based sentence reconstruction and translation
+// - Adaptive: Simple translation for easy segments, Context or NLP translation for long or
+//   uncertain stretches (see adaptive.rs)
+//
+// - External: executables registered in the [plugins] config section (see plugin.rs)
+//
+// Translators are discovered by name through TranslatorRegistry; external crates can
+// register their own Translator implementations without modifying the factory.
+//
+// Retranslator refreshes existing subtitles from the translation cache after glossary or
+// prompt changes (see retranslate.rs).
+//
+// Window sizes and the requested context (num_ctx) are fitted to the model's context length
+// before translators are created (see sizing.rs).
+//
+// Responses are constrained to JSON schemas with Ollama structured outputs (see schema.rs).
+// Subtitle text is quoted as data in prompts and translations are checked for
+// meta-commentary (see guard.rs).
+//
+// Glossary terms come from the configuration and a TOML or CSV glossary file; mandatory terms
+// are enforced by retrying translations that leave them out (see glossary.rs).
+//
+// Translator's notes on wordplay and cultural references are written to a separate file next
+// to each subtitle file (see notes.rs).
+//
+// Simple and Context translators keep up to `concurrency` segment requests in flight and apply
+// the results in segment order (see common.rs).
+//
+// Strategies send their model requests to the ModelBackend named by `provider`, picked from a
+// BackendRegistry that downstream crates can extend (see backend.rs). Built in are an
+// EndpointPool spreading requests across the configured Ollama endpoints (see balancer.rs) and
+// an OpenAI-compatible chat completions API (see openai.rs).
+//
+// The title and synopsis of the translated video, and optionally a summary of its whole
+// transcription, are added to every translation prompt as background (see work.rs).
+//
+// Accepted translations are recorded in a translation memory shared by every file translated
+// with the same cache directory, and identical or near-identical lines reuse them (see memory.rs).
+//
+// LlmTranslator journals its sliding-window analysis so interrupted runs resume where the
+// analysis stopped (see journal.rs).
+//
+// All translators record per-model latency and token throughput into a shared
+// TranslationMetrics collector.
+//
+// The endpoint pool, backend, cost tracker and metrics are runtime handles a workflow creates once
+// and passes to the translators it creates as TranslationServices; the configuration itself is
+// plain data (see services.rs).
+
+pub mod common;
+pub mod simple;
+pub mod context;
+pub mod nlp;
+pub mod llm;
+pub mod adaptive;
+pub mod journal;
+pub mod metrics;
+pub mod semantic;
+pub mod memory;
+pub mod judge;
+pub mod sizing;
+pub mod backend;
+pub mod balancer;
+pub mod openai;
+pub mod schema;
+pub mod guard;
+pub mod notes;
+pub mod glossary;
+pub mod work;
+pub mod retranslate;
+pub mod external;
+pub mod registry;
+pub mod services;
+
+use async_trait::async_trait;
+
+pub use common::*;
+pub use metrics::{LATENCY_BUCKETS_MS, LatencyHistogram, TranslationMetrics, ModelMetricsSummary};
+pub use registry::{TranslatorRegistry, TranslatorConstructor};
+pub use retranslate::Retranslator;
+pub use judge::QualityJudge;
+pub use backend::{BackendConstructor, BackendRegistry, ModelBackend};
+pub use balancer::EndpointPool;
+pub use services::TranslationServices;
+use crate::cancel::Cancellation;
+use crate::config::{TranslateConfig, TranslationMode};
+use crate::error::Result;
+use crate::quality::Transcription;
+
+/// Main trait for translation operations
+#[async_trait]
+pub trait Translator: Send + Sync {
+    /// Translate transcription segments to target language
+    async fn translate_transcription(
+        &mut self,
+        transcription: &mut Transcription,
+        target_language: &str,
+        context: Option<&str>,
+        cancel: &Cancellation,
+    ) -> Result<()>;
+
+    /// Translation attempts rejected so far (meta-commentary, failed quality checks), each followed
+    /// by a retry unless attempts ran out
+    fn retries(&self) -> usize {
+        0
+    }
+
+    /// Segments translated from the cache or the translation memory so far
+    fn cache_hits(&self) -> usize {
+        0
+    }
+}
+
+/// Factory for creating translator instances
+pub struct TranslatorFactory;
+
+impl TranslatorFactory {
+    /// Create a translator based on the translation mode; it talks to the backend of `services`
+    pub fn create_translator(config: TranslateConfig, services: TranslationServices) -> Box<dyn Translator> {
+        match config.mode {
+            TranslationMode::Simple => {
+                Box::new(simple::SimpleTranslator::new(config, services))
+            }
+            TranslationMode::Context => {
+                Box::new(context::ContextTranslator::new(config, services))
+            }
+            TranslationMode::Nlp => {
+                Box::new(nlp::NlpTranslator::new(config, services))
+            }
+            TranslationMode::Llm => {
+                Box::new(llm::LlmTranslator::new(config, services))
+            }
+            TranslationMode::Adaptive => {
+                Box::new(adaptive::AdaptiveTranslator::new(config, services))
+            }
+        }
+    }
+}
+
+/// Check if Ollama is available and the model is loaded
+pub async fn check_ollama_availability(endpoint: &str, model: &str) -> Result<()> {
+    common::check_ollama_availability(endpoint, model).await
+} 
\ No newline at end of file
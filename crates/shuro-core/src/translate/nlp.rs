@@ -1,10 +1,11 @@
 use async_trait::async_trait;
 use tracing::{info, warn};
 
+use crate::cancel::Cancellation;
 use crate::config::TranslateConfig;
 use crate::error::Result;
 use crate::quality::{Transcription, TranscriptionSegment};
-use super::{Translator, common::{BaseTranslator, TranslationQuality}};
+use super::{Translator, common::{BaseTranslator, TranslationQuality}, services::TranslationServices};
 
 /// Sentence structure for NLP mode
 #[derive(Debug, Clone)]
@@ -21,9 +22,9 @@ pub struct NlpTranslator {
 }
 
 impl NlpTranslator {
-    pub fn new(config: TranslateConfig) -> Self {
+    pub fn new(config: TranslateConfig, services: TranslationServices) -> Self {
         Self {
-            base: BaseTranslator::new(config),
+            base: BaseTranslator::with_services(config, services),
         }
     }
 
@@ -47,7 +48,7 @@ impl NlpTranslator {
                     // Save current sentence and start new one
                     if !current_sentence.trim().is_empty() {
                         self.split_and_add_sentences(
-                            &current_sentence.trim().to_string(),
+                            current_sentence.trim(),
                             current_start,
                             segments[*current_segment_indices.last().unwrap_or(&(idx - 1))].end,
                             &current_segment_indices,
@@ -73,10 +74,11 @@ impl NlpTranslator {
             current_segment_indices.push(idx);
             
             // Check if we should split - be more conservative
-            if current_sentence.len() > 800 || 
-               (current_sentence.len() > 200 && self.has_strong_sentence_ending(&current_sentence)) {
+            let max_chars = self.base.config.nlp_max_sentence_chars;
+            if current_sentence.len() > max_chars || 
+               (current_sentence.len() > max_chars / 4 && self.has_strong_sentence_ending(&current_sentence)) {
                 self.split_and_add_sentences(
-                    &current_sentence.trim().to_string(),
+                    current_sentence.trim(),
                     current_start,
                     segment.end,
                     &current_segment_indices,
@@ -91,7 +93,7 @@ impl NlpTranslator {
         // Add remaining sentence if any
         if !current_sentence.trim().is_empty() {
             self.split_and_add_sentences(
-                &current_sentence.trim().to_string(),
+                current_sentence.trim(),
                 current_start,
                 segments[*current_segment_indices.last().unwrap_or(&(segments.len() - 1))].end,
                 &current_segment_indices,
@@ -109,7 +111,7 @@ impl NlpTranslator {
         for i in 0..text_bytes.len().saturating_sub(2) {
             if matches!(text_bytes[i], b'.' | b'!' | b'?') {
                 // Check if followed by space and capital letter
-                if text_bytes[i + 1] == b' ' && text_bytes.get(i + 2).map_or(false, |&b| b.is_ascii_uppercase()) {
+                if text_bytes[i + 1] == b' ' && text_bytes.get(i + 2).is_some_and(|&b| b.is_ascii_uppercase()) {
                     return true;
                 }
             }
@@ -209,7 +211,7 @@ impl NlpTranslator {
                             let remaining: String = chars.clone().collect();
                             let trimmed_remaining = remaining.trim_start();
                             if trimmed_remaining.is_empty() || 
-                               trimmed_remaining.chars().next().map_or(false, |c| c.is_uppercase()) {
+                               trimmed_remaining.chars().next().is_some_and(|c| c.is_uppercase()) {
                                 sentences.push(sentence);
                                 current_sentence = String::new();
                             }
@@ -233,7 +235,7 @@ impl NlpTranslator {
         
         // If no sentences were split (no proper punctuation), split by length with larger chunks
         if sentences.is_empty() && !text.trim().is_empty() {
-            sentences = self.split_by_length(text, 500); // Larger chunks - 500 chars per sentence
+            sentences = self.split_by_length(text, self.base.config.nlp_max_sentence_chars * 5 / 8); // Larger chunks - 500 chars per sentence by default
         }
         
         sentences
@@ -305,8 +307,18 @@ impl NlpTranslator {
     }
 
     /// Map translated sentences back to original segments
+    ///
+    /// Sentences whose segments all carry word timestamps are re-timed against the speech;
+    /// the others are split back by word counts.
     fn map_sentences_to_segments(&self, sentences: &[Sentence], segments: &mut [TranscriptionSegment]) {
         for sentence in sentences {
+            let timed = !sentence.segment_indices.is_empty() && sentence.segment_indices.iter()
+                .all(|&idx| segments.get(idx).is_some_and(|s| !s.words.is_empty()));
+            if timed {
+                retime_sentence(sentence, segments);
+                continue;
+            }
+
             // Split translated sentence back to words based on original segment count
             let words: Vec<&str> = sentence.text.split_whitespace().collect();
             let segment_count = sentence.segment_indices.len();
@@ -339,50 +351,156 @@ impl NlpTranslator {
     }
 }
 
+/// Second pass for sentences with word timestamps: each cue is re-timed to the speech it covers
+/// and receives a share of the translation proportional to how long that speech lasts
+fn retime_sentence(sentence: &Sentence, segments: &mut [TranscriptionSegment]) {
+    let mut indices = sentence.segment_indices.clone();
+    indices.dedup();
+
+    let durations: Vec<f64> = indices.iter()
+        .map(|&idx| segments[idx].words.iter().map(|w| (w.end - w.start).max(0.0)).sum())
+        .collect();
+    let pieces = distribute_by_speech(&sentence.text, &durations);
+
+    for (&idx, piece) in indices.iter().zip(pieces) {
+        let segment = &mut segments[idx];
+        if let (Some(first), Some(last)) = (segment.words.first(), segment.words.last()) {
+            segment.start = first.start;
+            segment.end = last.end.max(first.start);
+        }
+        segment.text = piece;
+    }
+}
+
+/// Split a translation into one piece per spoken duration, proportionally to the durations
+///
+/// Pieces break between words, or between characters for unspaced scripts such as Japanese, and every
+/// piece gets at least one unit while there are enough units to go around; with fewer units
+/// than pieces, the earliest pieces get one unit each.
+fn distribute_by_speech(text: &str, durations: &[f64]) -> Vec<String> {
+    let spaced = text.trim().contains(char::is_whitespace)
+        || !text.chars().any(|c| c.is_alphabetic() && c > '\u{024F}');
+    let units: Vec<String> = if spaced {
+        text.split_whitespace().map(str::to_string).collect()
+    } else {
+        text.trim().chars().map(String::from).collect()
+    };
+    let separator = if spaced { " " } else { "" };
+
+    let count = durations.len();
+    let total_duration: f64 = durations.iter().sum();
+    let weights: Vec<f64> = if total_duration > 0.0 {
+        durations.iter().map(|d| d / total_duration).collect()
+    } else {
+        vec![1.0 / count.max(1) as f64; count]
+    };
+
+    let mut cumulative_chars = vec![0usize];
+    for unit in &units {
+        cumulative_chars.push(cumulative_chars.last().unwrap_or(&0) + unit.chars().count());
+    }
+    let total_chars = *cumulative_chars.last().unwrap_or(&0) as f64;
+
+    let mut pieces = Vec::with_capacity(count);
+    let (mut begin, mut elapsed) = (0, 0.0);
+    for (k, weight) in weights.iter().enumerate() {
+        elapsed += weight;
+        let end = if k + 1 == count {
+            units.len()
+        } else {
+            let target = total_chars * elapsed;
+            let nearest = (0..=units.len())
+                .min_by(|&a, &b| {
+                    let da = (cumulative_chars[a] as f64 - target).abs();
+                    let db = (cumulative_chars[b] as f64 - target).abs();
+                    da.partial_cmp(&db).unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap_or(begin);
+            let remaining = count - k - 1;
+            if units.len() >= begin + 1 + remaining {
+                nearest.clamp(begin + 1, units.len() - remaining)
+            } else {
+                (begin + 1).min(units.len())
+            }
+        };
+        pieces.push(units[begin..end].join(separator));
+        begin = end;
+    }
+    pieces
+}
+
 #[async_trait]
 impl Translator for NlpTranslator {
+    fn retries(&self) -> usize {
+        self.base.retries()
+    }
+
+    fn cache_hits(&self) -> usize {
+        self.base.cache_hits()
+    }
+
     /// NLP translation: Reconstruct complete sentences, then translate sentence by sentence
     async fn translate_transcription(
         &mut self,
         transcription: &mut Transcription,
         target_language: &str,
         _context: Option<&str>,
+        cancel: &Cancellation,
     ) -> Result<()> {
-        info!("Starting NLP translation with sentence reconstruction to {}", target_language);
+        cancel.run(async {
+            info!("Starting NLP translation with sentence reconstruction to {}", target_language);
         
-        // Step 1: Group segments into sentences based on timing gaps and content
-        let sentences = self.build_sentences_from_segments(&transcription.segments);
+            // Step 1: Group segments into sentences based on timing gaps and content
+            let sentences = self.build_sentences_from_segments(&transcription.segments);
         
-        info!("Reconstructed {} sentences from {} segments", sentences.len(), transcription.segments.len());
+            info!("Reconstructed {} sentences from {} segments", sentences.len(), transcription.segments.len());
         
-        // Step 2: Translate each sentence
-        let mut translated_sentences = Vec::new();
+            // Step 2: Translate each sentence
+            let mut translated_sentences = Vec::new();
         
-        for (idx, sentence) in sentences.iter().enumerate() {
-            info!("┌─ Translating sentence {}/{} (NLP) ────────", idx + 1, sentences.len());
-            info!("│ Source: {}", sentence.text);
+            for (idx, sentence) in sentences.iter().enumerate() {
+                info!("┌─ Translating sentence {}/{} (NLP) ────────", idx + 1, sentences.len());
+                info!("│ Source: {}", sentence.text);
             
-            match self.translate_sentence(sentence, target_language).await {
-                Ok(translation) => {
-                    info!("│ Target: {}", translation);
-                    info!("└─────────────────────────────────────");
+                match self.translate_sentence(sentence, target_language).await {
+                    Ok(translation) => {
+                        info!("│ Target: {}", translation);
+                        info!("└─────────────────────────────────────");
                     
-                    let mut translated_sentence = sentence.clone();
-                    translated_sentence.text = translation;
-                    translated_sentences.push(translated_sentence);
-                }
-                Err(e) => {
-                    warn!("│ Failed: {}", e);
-                    warn!("└─────────────────────────────────────");
-                    // Keep original sentence on failure
-                    translated_sentences.push(sentence.clone());
+                        let mut translated_sentence = sentence.clone();
+                        translated_sentence.text = translation;
+                        translated_sentences.push(translated_sentence);
+                    }
+                    Err(e) => {
+                        warn!("│ Failed: {}", e);
+                        warn!("└─────────────────────────────────────");
+                        // Keep original sentence on failure
+                        translated_sentences.push(sentence.clone());
+                    }
                 }
             }
-        }
         
-        // Step 3: Map translated sentences back to original segments
-        self.map_sentences_to_segments(&translated_sentences, &mut transcription.segments);
+            // Step 3: Map translated sentences back to original segments
+            self.map_sentences_to_segments(&translated_sentences, &mut transcription.segments);
         
-        Ok(())
+            Ok(())
+        }).await
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distribute_by_speech() {
+        assert_eq!(
+            distribute_by_speech("one two three four", &[3.0, 1.0]),
+            vec!["one two three", "four"]
+        );
+        assert_eq!(distribute_by_speech("こんにちは世界", &[5.0, 2.0]), vec!["こんにちは", "世界"]);
+        // Every cue keeps at least one word even when its speech is very short
+        assert_eq!(distribute_by_speech("a b", &[10.0, 0.0]), vec!["a", "b"]);
+        assert_eq!(distribute_by_speech("alone", &[1.0, 1.0]), vec!["alone", ""]);
+    }
+}
@@ -0,0 +1,237 @@
+// Time-coded translation notes
+//
+// With `translate.notes.enabled`, the translation model reviews each translated cue next to its
+// source and writes translator's notes where the translation loses something a viewer would want
+// to know: wordplay, idioms, cultural references, honorifics. Notes are keyed to the cue's
+// timestamps and written to a separate `{stem}_{lang}.notes.vtt` (WebVTT NOTE blocks, ignored by
+// players) or `.notes.json` file, so the subtitle track itself stays clean. Cues are reviewed in
+// batches of `batch_size`; most get no note.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::info;
+
+use crate::cancel::Cancellation;
+use crate::config::{NotesFormat, TranslateConfig};
+use crate::error::{Result, ShuroError};
+use crate::quality::Transcription;
+use crate::subtitle::format_vtt_time;
+use super::backend::ModelBackend;
+use super::common::{language_code_to_name, RequestOptions, TranslationRequest};
+use super::metrics::TranslationMetrics;
+use super::services::TranslationServices;
+use super::{guard, schema};
+
+/// Translator's note on one cue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranslationNote {
+    pub start: f64,
+    pub end: f64,
+    pub source: String,
+    pub translation: String,
+    pub note: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct NotesResponse {
+    notes: Vec<LineNote>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LineNote {
+    /// 1-based line number within the batch
+    line: usize,
+    note: String,
+}
+
+/// Writes translator's notes with the translation model
+pub struct NoteGenerator {
+    client: Client,
+    backend: Arc<dyn ModelBackend>,
+    model: String,
+    options: Option<RequestOptions>,
+    format: Value,
+    batch_size: usize,
+    metrics: Arc<TranslationMetrics>,
+}
+
+impl NoteGenerator {
+    pub fn new(config: &TranslateConfig, services: &TranslationServices) -> Self {
+        Self {
+            client: Client::new(),
+            backend: services.backend.clone(),
+            model: config.model.clone(),
+            options: RequestOptions::for_model(config, &config.model),
+            format: schema::response_format(config, notes_schema()),
+            batch_size: config.notes.batch_size.max(1),
+            metrics: services.metrics.clone(),
+        }
+    }
+
+    /// Notes on the cues of `translated`, the translation of `source` into `target_language`
+    pub async fn annotate(
+        &self,
+        source: &Transcription,
+        translated: &Transcription,
+        target_language: &str,
+        cancel: &Cancellation,
+    ) -> Result<Vec<TranslationNote>> {
+        if source.segments.len() != translated.segments.len() {
+            return Err(ShuroError::Translation(format!(
+                "Cannot annotate {} translated cues of {} source cues",
+                translated.segments.len(), source.segments.len()
+            )));
+        }
+
+        let pairs: Vec<(&str, &str)> = source.segments.iter()
+            .zip(&translated.segments)
+            .map(|(s, t)| (s.text.as_str(), t.text.as_str()))
+            .collect();
+
+        let mut notes = Vec::new();
+        for (batch_index, batch) in pairs.chunks(self.batch_size).enumerate() {
+            let offset = batch_index * self.batch_size;
+            for line_note in cancel.run(self.request_notes(batch, target_language)).await? {
+                let Some(index) = line_note.line.checked_sub(1).filter(|i| *i < batch.len()).map(|i| offset + i) else {
+                    continue;
+                };
+                let note = line_note.note.trim();
+                if note.is_empty() {
+                    continue;
+                }
+                let segment = &translated.segments[index];
+                notes.push(TranslationNote {
+                    start: segment.start,
+                    end: segment.end,
+                    source: source.segments[index].text.clone(),
+                    translation: segment.text.clone(),
+                    note: note.to_string(),
+                });
+            }
+        }
+
+        info!("📝 {} translator's notes for {} cues in {}", notes.len(), pairs.len(), target_language);
+        Ok(notes)
+    }
+
+    /// Ask the model for notes on one batch of source/translation lines
+    async fn request_notes(&self, batch: &[(&str, &str)], target_language: &str) -> Result<Vec<LineNote>> {
+        let language_name = language_code_to_name(target_language);
+        let lines = batch.iter()
+            .enumerate()
+            .map(|(i, (source, translation))| format!("{}. {} => {}", i + 1, source, translation))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let prompt = format!(
+            "You are a subtitle translator writing translator's notes for viewers.\n\
+             \n\
+             Each numbered line below is a source subtitle and its {} translation. Write a short note in {} \
+             only for lines whose translation loses something a viewer would want to know: wordplay, puns, \
+             idioms, cultural references, honorifics, or untranslatable terms. Most lines need no note.\n\
+             {}\n\
+             \n\
+             {}\n\
+             \n\
+             Return JSON as {{\"notes\":[{{\"line\":1,\"note\":\"your note\"}}]}}, with an empty list when no line needs a note.\n",
+            language_name, language_name, guard::DATA_NOTICE, guard::quote(&lines)
+        );
+
+        let request = TranslationRequest {
+            model: self.model.clone(),
+            prompt,
+            stream: false,
+            format: self.format.clone(),
+            options: self.options.clone(),
+        };
+
+        let started = Instant::now();
+        let notes_response = self.backend.generate(&self.client, &request).await?;
+        self.metrics.record(&self.model, started.elapsed(), notes_response.usage());
+
+        let parsed: NotesResponse = schema::parse_response(&notes_response.response)?;
+        Ok(parsed.notes)
+    }
+}
+
+/// `{"notes": [{"line": 1, "note": "..."}]}` response of a note request
+fn notes_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "notes": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": { "line": { "type": "integer" }, "note": { "type": "string" } },
+                    "required": ["line", "note"],
+                },
+            },
+        },
+        "required": ["notes"],
+    })
+}
+
+/// Notes file written next to `subtitle_path` (`movie_ja.srt` -> `movie_ja.notes.vtt`)
+pub fn notes_path(subtitle_path: &Path, format: NotesFormat) -> PathBuf {
+    let extension = match format {
+        NotesFormat::Vtt => "notes.vtt",
+        NotesFormat::Json => "notes.json",
+    };
+    subtitle_path.with_extension(extension)
+}
+
+/// Notes as a WebVTT file of NOTE blocks
+pub fn format_vtt_notes(notes: &[TranslationNote]) -> String {
+    let mut output = String::from("WEBVTT\n");
+    for note in notes {
+        // NOTE blocks end at a blank line and may not contain "-->"
+        let body = format!("{} => {}\n{}", note.source, note.translation, note.note)
+            .replace("-->", "->")
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        output.push_str(&format!(
+            "\nNOTE {} - {}\n{}\n",
+            format_vtt_time(note.start), format_vtt_time(note.end), body
+        ));
+    }
+    output
+}
+
+/// Write notes in the given format
+pub async fn write_notes(notes: &[TranslationNote], path: &Path, format: NotesFormat) -> Result<()> {
+    let content = match format {
+        NotesFormat::Vtt => format_vtt_notes(notes),
+        NotesFormat::Json => serde_json::to_string_pretty(notes)?,
+    };
+    tokio::fs::write(path, content).await?;
+    info!("Wrote translation notes: {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_vtt_notes() {
+        let notes = vec![TranslationNote {
+            start: 61.5,
+            end: 63.0,
+            source: "Break a leg!".to_string(),
+            translation: "頑張って！".to_string(),
+            note: "An English idiom wishing good luck --> literally \"break your leg\".\n\nNot a threat.".to_string(),
+        }];
+        assert_eq!(
+            format_vtt_notes(&notes),
+            "WEBVTT\n\nNOTE 00:01:01.500 - 00:01:03.000\nBreak a leg! => 頑張って！\nAn English idiom wishing good luck -> literally \"break your leg\".\nNot a threat.\n"
+        );
+        assert_eq!(notes_path(Path::new("out/movie_ja.srt"), NotesFormat::Json), Path::new("out/movie_ja.notes.json"));
+    }
+}
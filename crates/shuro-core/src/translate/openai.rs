@@ -0,0 +1,247 @@
+// OpenAI-compatible chat completions backend
+//
+// The "OpenAI" translation backend (see backend.rs): with `translate.provider = "OpenAI"`,
+// translation, judging, notes and title lookups are sent to `{base_url}/chat/completions` of any
+// server speaking the OpenAI API (OpenAI, OpenRouter, LM Studio, vLLM, llama.cpp server) instead
+// of Ollama's `/api/generate`. The prompt becomes a single
+// user message, the JSON schema of the response goes into `response_format`, and the answer and
+// token usage are mapped back onto Ollama's response shape, so translators do not know which
+// backend they talk to. `translate.model` names the model on that server.
+//
+// Hosted APIs rate-limit: 429 and 5xx answers and dropped connections are retried up to
+// `openai.max_retries` times, waiting as long as `Retry-After` asks or otherwise doubling the
+// wait from `initial_backoff_ms` up to `max_backoff_ms`. Other errors (bad key, unknown model)
+// fail at once. Ollama-only features (model unloading, context sizing from `/api/show`, the
+// semantic cache's embeddings) keep using the Ollama endpoints or are skipped.
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::config::OpenAiConfig;
+use crate::error::{Result, ShuroError, is_transient_status};
+use crate::preflight::{self, PreflightReport};
+use super::backend::ModelBackend;
+use super::common::{TranslationRequest, TranslationResponse};
+
+/// Client of an OpenAI-compatible chat completions API
+#[derive(Debug, Clone)]
+pub struct ChatCompletions {
+    base_url: String,
+    api_key: Option<String>,
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompletionResponse {
+    choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: Message,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Usage {
+    #[serde(default)]
+    prompt_tokens: Option<u64>,
+    #[serde(default)]
+    completion_tokens: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    data: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelEntry {
+    id: String,
+}
+
+impl ChatCompletions {
+    pub fn new(config: &OpenAiConfig) -> Self {
+        Self {
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            api_key: config.api_key(),
+            max_retries: config.max_retries,
+            initial_backoff: Duration::from_millis(config.initial_backoff_ms),
+            max_backoff: Duration::from_millis(config.max_backoff_ms),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Check that the API answers with the configured key; a model missing from the server's
+    /// model list is only reported, since some servers list a subset
+    pub async fn check(&self, client: &Client, models: &[&str]) -> Result<String> {
+        let mut builder = client.get(format!("{}/models", self.base_url));
+        if let Some(key) = &self.api_key {
+            builder = builder.bearer_auth(key);
+        }
+        let response = builder.send().await
+            .map_err(|e| ShuroError::Translation(format!("Failed to connect to {}: {}", self.base_url, e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(ShuroError::Translation(format!("{} answered {}: {}", self.base_url, status, error_text.trim())));
+        }
+
+        let listed: ModelList = response.json().await
+            .map_err(|e| ShuroError::Translation(format!("Failed to parse the model list: {}", e)))?;
+        let missing: Vec<&str> = models.iter().copied()
+            .filter(|model| !listed.data.iter().any(|m| m.id == *model))
+            .collect();
+        Ok(if missing.is_empty() {
+            format!("{} models available", listed.data.len())
+        } else {
+            format!("{} models available, not listing {}", listed.data.len(), missing.join(", "))
+        })
+    }
+}
+
+#[async_trait]
+impl ModelBackend for ChatCompletions {
+    /// Send `request` as a chat completion and return the answer in Ollama's response shape
+    async fn generate(&self, client: &Client, request: &TranslationRequest) -> Result<TranslationResponse> {
+        let body = json!({
+            "model": request.model,
+            "messages": [{ "role": "user", "content": request.prompt }],
+            "stream": false,
+            "response_format": response_format(&request.format),
+        });
+        let url = format!("{}/chat/completions", self.base_url);
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let mut builder = client.post(&url).json(&body);
+            if let Some(key) = &self.api_key {
+                builder = builder.bearer_auth(key);
+            }
+
+            let (retry_after, problem) = match builder.send().await {
+                Ok(response) if response.status().is_success() => {
+                    let text = response.text().await
+                        .map_err(|e| ShuroError::Translation(format!("Failed to read chat completion: {}", e)))?;
+                    debug!("Raw chat completion: {}", text);
+                    return parse_completion(&text);
+                }
+                Ok(response) if is_transient_status(response.status()) => {
+                    let retry_after = response.headers()
+                        .get(reqwest::header::RETRY_AFTER)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.trim().parse::<u64>().ok())
+                        .map(Duration::from_secs);
+                    let status = response.status();
+                    (retry_after, format!("{} {}", status, response.text().await.unwrap_or_default()))
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    let error_text = response.text().await.unwrap_or_default();
+                    return Err(ShuroError::Translation(format!("Chat completions API error {}: {}", status, error_text)));
+                }
+                Err(e) if e.is_connect() || e.is_timeout() => (None, e.to_string()),
+                Err(e) => return Err(ShuroError::Translation(format!("HTTP request failed: {}", e))),
+            };
+
+            if attempt > self.max_retries {
+                return Err(ShuroError::Translation(format!(
+                    "Chat completions request failed after {} attempts: {}", attempt, problem.trim()
+                )));
+            }
+            let delay = backoff_delay(attempt, self.initial_backoff, self.max_backoff, retry_after);
+            warn!("Chat completions request failed ({}), retrying in {:.1}s ({}/{})",
+                  problem.trim(), delay.as_secs_f64(), attempt, self.max_retries);
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn check_models(&self, models: &[&str]) -> Result<()> {
+        let status = self.check(&Client::new(), models).await?;
+        info!("🔌 Translating through {} ({})", self.base_url, status);
+        Ok(())
+    }
+
+    async fn preflight(&self, models: &[&str], report: &mut PreflightReport) {
+        preflight::check_openai(self, models, report).await;
+    }
+}
+
+/// `response_format` for Ollama's `format`: `"json"` or the JSON schema the answer must follow
+fn response_format(format: &Value) -> Value {
+    if format.is_object() {
+        json!({ "type": "json_schema", "json_schema": { "name": "response", "schema": format } })
+    } else {
+        json!({ "type": "json_object" })
+    }
+}
+
+/// Wait before retry `attempt` (from 1): what the server asked for, or `initial` doubled per
+/// attempt; never more than `max`
+fn backoff_delay(attempt: u32, initial: Duration, max: Duration, retry_after: Option<Duration>) -> Duration {
+    retry_after
+        .unwrap_or_else(|| initial.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1))))
+        .min(max)
+}
+
+/// The first choice's message and the token usage of a chat completion
+fn parse_completion(text: &str) -> Result<TranslationResponse> {
+    let completion: CompletionResponse = serde_json::from_str(text)
+        .map_err(|e| ShuroError::Translation(format!("Failed to parse chat completion: {}", e)))?;
+    let content = completion.choices.into_iter().next()
+        .and_then(|choice| choice.message.content)
+        .ok_or_else(|| ShuroError::Translation("Chat completion without a message".to_string()))?;
+    let usage = completion.usage;
+    Ok(TranslationResponse {
+        response: content,
+        done: true,
+        prompt_eval_count: usage.as_ref().and_then(|u| u.prompt_tokens),
+        eval_count: usage.as_ref().and_then(|u| u.completion_tokens),
+        eval_duration: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+
+    #[test]
+    fn test_chat_completions_mapping() {
+        let schema = json!({ "type": "object", "properties": { "text": { "type": "string" } } });
+        assert_eq!(response_format(&schema)["json_schema"]["schema"], schema);
+        assert_eq!(response_format(&json!("json")), json!({ "type": "json_object" }));
+
+        let response = parse_completion(
+            r#"{"choices":[{"message":{"role":"assistant","content":"{\"text\":\"こんにちは\"}"}}],"usage":{"prompt_tokens":42,"completion_tokens":7}}"#
+        ).unwrap();
+        assert_eq!(response.response, r#"{"text":"こんにちは"}"#);
+        assert_eq!((response.prompt_eval_count, response.eval_count), (Some(42), Some(7)));
+        assert!(parse_completion(r#"{"choices":[]}"#).is_err());
+
+        let (second, minute) = (Duration::from_secs(1), Duration::from_secs(60));
+        assert_eq!(backoff_delay(1, second, minute, None), second);
+        assert_eq!(backoff_delay(4, second, minute, None), Duration::from_secs(8));
+        assert_eq!(backoff_delay(30, second, minute, None), minute);
+        assert_eq!(backoff_delay(1, second, minute, Some(Duration::from_secs(20))), Duration::from_secs(20));
+        assert!(is_transient_status(StatusCode::TOO_MANY_REQUESTS) && is_transient_status(StatusCode::BAD_GATEWAY));
+        assert!(!is_transient_status(StatusCode::UNAUTHORIZED));
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::config::{ExternalPluginConfig, TranslateConfig, TranslationMode};
+use crate::error::{Result, ShuroError};
+use super::{Translator, TranslatorFactory, external::ExternalTranslator, services::TranslationServices};
+
+/// Constructor registered for a translator name; it gets the configuration and the services
+/// shared by the workflow's translators
+pub type TranslatorConstructor =
+    Arc<dyn Fn(TranslateConfig, TranslationServices) -> Box<dyn Translator> + Send + Sync>;
+
+/// Registered translator
+#[derive(Clone)]
+struct Entry {
+    constructor: TranslatorConstructor,
+    /// Whether it sends model requests to the workflow's backend, whose models are checked,
+    /// sized and unloaded for it
+    uses_backend: bool,
+}
+
+/// Name-to-constructor registry used to discover translators from config
+#[derive(Clone, Default)]
+pub struct TranslatorRegistry {
+    constructors: HashMap<String, Entry>,
+}
+
+impl TranslatorRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with the built-in translators registered under their mode names
+    pub fn with_builtin() -> Self {
+        [TranslationMode::Simple, TranslationMode::Context, TranslationMode::Nlp, TranslationMode::Llm, TranslationMode::Adaptive]
+            .into_iter()
+            .fold(Self::new(), |registry, mode| {
+                let name = mode.name();
+                registry.register_on_backend(name, move |mut config, services| {
+                    config.mode = mode.clone();
+                    TranslatorFactory::create_translator(config, services)
+                })
+            })
+    }
+
+    /// Register a translator constructor under a name, replacing any previous registration; the
+    /// translator talks to its own service, so the workflow leaves the model backend alone for it
+    pub fn register<F>(self, name: &str, constructor: F) -> Self
+    where
+        F: Fn(TranslateConfig, TranslationServices) -> Box<dyn Translator> + Send + Sync + 'static,
+    {
+        self.insert(name, Arc::new(constructor), false)
+    }
+
+    /// Register a translator constructor under a name for a translator sending its model
+    /// requests to the backend of its `TranslationServices`, like the built-in ones
+    pub fn register_on_backend<F>(self, name: &str, constructor: F) -> Self
+    where
+        F: Fn(TranslateConfig, TranslationServices) -> Box<dyn Translator> + Send + Sync + 'static,
+    {
+        self.insert(name, Arc::new(constructor), true)
+    }
+
+    fn insert(mut self, name: &str, constructor: TranslatorConstructor, uses_backend: bool) -> Self {
+        self.constructors.insert(name.to_string(), Entry { constructor, uses_backend });
+        self
+    }
+
+    /// Register external plugin executables
+    pub fn register_external(self, plugins: &[ExternalPluginConfig]) -> Self {
+        plugins.iter().cloned().fold(self, |registry, plugin| {
+            let name = plugin.name.clone();
+            registry.register(&name, move |config, _services| {
+                Box::new(ExternalTranslator::new(plugin.clone(), config))
+            })
+        })
+    }
+
+    /// Registered names in sorted order
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.constructors.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Whether the translator `config` selects sends its model requests to the workflow's
+    /// backend; unknown names do not
+    pub fn uses_backend(&self, config: &TranslateConfig) -> bool {
+        self.constructors.get(&selected_name(config)).is_some_and(|entry| entry.uses_backend)
+    }
+
+    /// Create the translator selected by `implementation`, or by `mode` when unset
+    pub fn create(&self, config: TranslateConfig, services: TranslationServices) -> Result<Box<dyn Translator>> {
+        let name = selected_name(&config);
+        let entry = self.constructors.get(&name).ok_or_else(|| ShuroError::Config(format!(
+            "Unknown translator '{}'. Registered translators: {}",
+            name,
+            self.names().join(", ")
+        )))?;
+        Ok((entry.constructor)(config, services))
+    }
+}
+
+/// Name of the translator selected by `implementation`, or by `mode` when unset
+fn selected_name(config: &TranslateConfig) -> String {
+    config.implementation.clone().unwrap_or_else(|| config.mode.name().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_create_resolves_names() {
+        let plugin = ExternalPluginConfig {
+            name: "my-mt".to_string(),
+            command: "my-mt".to_string(),
+            args: vec![],
+        };
+        let registry = TranslatorRegistry::with_builtin().register_external(&[plugin]);
+        assert_eq!(registry.names(), vec!["adaptive", "context", "llm", "my-mt", "nlp", "simple"]);
+
+        let mut config = Config::default().translate;
+        let services = TranslationServices::for_config(&config);
+        config.implementation = Some("my-mt".to_string());
+        assert!(registry.create(config.clone(), services.clone()).is_ok());
+
+        config.implementation = Some("missing".to_string());
+        assert!(registry.create(config, services).is_err());
+    }
+}
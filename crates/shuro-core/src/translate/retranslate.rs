@@ -0,0 +1,231 @@
+// Targeted re-translation of existing subtitles
+//
+// After the glossary or the prompt changes, existing subtitle files can be refreshed without
+// running the whole pipeline again. The translation cache records the source text behind every
+// translated cue; cached entries whose translation appears in the subtitles are translated
+// again with the current configuration and the matching cues are patched in place.
+//
+// With `changed_terms_only`, only entries whose source text mentions a glossary term that
+// changed since the last run are re-translated. The glossary used by a run is recorded in
+// `<cache_dir>/glossary.json`; without a record every current term counts as changed.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+use crate::cancel::Cancellation;
+use crate::config::{GlossaryTerm, TranslateConfig};
+use crate::error::{Result, ShuroError};
+use crate::quality::Transcription;
+use super::common::{mentions_term, BaseTranslator, TranslationCacheEntry, TranslationQuality};
+use super::memory::TranslationMemory;
+use super::services::TranslationServices;
+
+/// File, relative to the cache directory, recording the glossary of the last run
+const GLOSSARY_RECORD_FILE: &str = "glossary.json";
+
+/// Re-translates cached segments and patches subtitles that contain them
+pub struct Retranslator {
+    base: BaseTranslator,
+}
+
+impl Retranslator {
+    pub fn new(config: TranslateConfig, services: TranslationServices) -> Self {
+        let mut base = BaseTranslator::with_services(config, services);
+        // Remembered translations are what re-translation replaces
+        base.memory = base.memory.map(TranslationMemory::without_reuse);
+        Self { base }
+    }
+
+    /// Re-translate the cached segments shown in `subtitles` and patch their cues,
+    /// returning the number of patched cues
+    pub async fn retranslate(
+        &self,
+        subtitles: &mut [Transcription],
+        target_language: &str,
+        changed_terms_only: bool,
+        cancel: &Cancellation,
+    ) -> Result<usize> {
+        let config = &self.base.config;
+        let mut entries: Vec<(String, TranslationCacheEntry)> = self.base.list_translation_cache_keys().await?
+            .into_iter()
+            .filter(|(_, e)| e.target_language == target_language && e.model == config.model)
+            .filter(|(_, e)| subtitles.iter().any(|s| s.segments.iter().any(|seg| seg.text.trim() == e.translation.trim())))
+            .collect();
+
+        if changed_terms_only {
+            let previous = load_glossary_record(&config.cache_dir).await?;
+            let changed = changed_terms(&previous, &config.glossary, target_language);
+            info!("📝 {} glossary terms changed for {}: {:?}", changed.len(), target_language, changed);
+            entries.retain(|(_, e)| changed.iter().any(|term| mentions_term(&e.source_text, term)));
+        }
+
+        info!("Re-translating {} cached segments into {}", entries.len(), target_language);
+        let mut replacements = HashMap::new();
+        for (cache_key, entry) in entries {
+            let context = (!entry.context.is_empty()).then_some(entry.context.as_str());
+            let translation = cancel.run(self.base.translate_text(&entry.source_text, target_language, context)).await?;
+            debug!("Re-translated '{}': '{}' -> '{}'", entry.source_text, entry.translation, translation);
+
+            self.base.save_to_persistent_cache(
+                &cache_key,
+                &entry.source_text,
+                target_language,
+                &entry.context,
+                &translation,
+                &TranslationQuality::parse(&entry.quality),
+            ).await?;
+            replacements.insert(entry.translation.trim().to_string(), translation);
+        }
+
+        let patched = subtitles.iter_mut().map(|s| patch_segments(s, &replacements)).sum();
+        record_glossary(config).await?;
+        Ok(patched)
+    }
+}
+
+/// Record the glossary used for translation so later runs can tell which terms changed
+pub async fn record_glossary(config: &TranslateConfig) -> Result<()> {
+    tokio::fs::create_dir_all(&config.cache_dir).await?;
+    let content = serde_json::to_string_pretty(&config.glossary)?;
+    tokio::fs::write(glossary_record_path(&config.cache_dir), content).await?;
+    Ok(())
+}
+
+/// Glossary recorded by the last run (empty when none was recorded)
+async fn load_glossary_record(cache_dir: &str) -> Result<Vec<GlossaryTerm>> {
+    let path = glossary_record_path(cache_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = tokio::fs::read_to_string(&path).await?;
+    serde_json::from_str(&content)
+        .map_err(|e| ShuroError::Cache(format!("Invalid glossary record {}: {}", path.display(), e)))
+}
+
+fn glossary_record_path(cache_dir: &str) -> PathBuf {
+    Path::new(cache_dir).join(GLOSSARY_RECORD_FILE)
+}
+
+/// Source terms whose translation into the target language was added, removed or changed
+fn changed_terms(previous: &[GlossaryTerm], current: &[GlossaryTerm], target_language: &str) -> Vec<String> {
+    let translations = |glossary: &[GlossaryTerm]| -> HashMap<String, String> {
+        glossary
+            .iter()
+            .filter_map(|t| t.translations.get(target_language).map(|tr| (t.source.clone(), tr.clone())))
+            .collect()
+    };
+    let (previous, current) = (translations(previous), translations(current));
+
+    let mut changed: Vec<String> = current
+        .iter()
+        .filter(|(source, translation)| previous.get(*source) != Some(translation))
+        .map(|(source, _)| source.clone())
+        .chain(previous.keys().filter(|source| !current.contains_key(*source)).cloned())
+        .collect();
+    changed.sort();
+    changed
+}
+
+/// Replace cue texts that match a replaced translation, returning the number of patched cues
+fn patch_segments(subtitles: &mut Transcription, replacements: &HashMap<String, String>) -> usize {
+    let mut patched = 0;
+    for segment in &mut subtitles.segments {
+        if let Some(translation) = replacements.get(segment.text.trim())
+            && segment.text.trim() != translation.trim()
+        {
+            segment.text = translation.clone();
+            patched += 1;
+        }
+    }
+    if patched > 0 {
+        subtitles.text = subtitles.segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" ");
+    }
+    patched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use async_trait::async_trait;
+    use reqwest::Client;
+    use crate::config::Config;
+    use crate::subtitle::parse_srt;
+    use crate::translate::backend::ModelBackend;
+    use crate::translate::common::{TranslationRequest, TranslationResponse};
+
+    /// Backend answering every request with the same translation
+    #[derive(Debug)]
+    struct Fixed(&'static str);
+
+    #[async_trait]
+    impl ModelBackend for Fixed {
+        async fn generate(&self, _client: &Client, _request: &TranslationRequest) -> Result<TranslationResponse> {
+            Ok(TranslationResponse {
+                response: serde_json::json!({ "text": self.0 }).to_string(),
+                done: true,
+                prompt_eval_count: None,
+                eval_count: None,
+                eval_duration: None,
+            })
+        }
+
+        async fn check_models(&self, _models: &[&str]) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn term(source: &str, ja: &str) -> GlossaryTerm {
+        GlossaryTerm {
+            source: source.to_string(),
+            character: false,
+            mandatory: false,
+            translations: [("ja".to_string(), ja.to_string())].into(),
+        }
+    }
+
+    #[test]
+    fn test_changed_terms() {
+        let previous = vec![term("Luke", "ルーク"), term("hyperdrive", "ハイパードライブ"), term("Leia", "レイア")];
+        let current = vec![term("Luke", "ルーク"), term("hyperdrive", "超光速航法"), term("Han", "ハン")];
+        assert_eq!(changed_terms(&previous, &current, "ja"), vec!["Han", "Leia", "hyperdrive"]);
+        assert!(changed_terms(&previous, &current, "ko").is_empty());
+    }
+
+    #[test]
+    fn test_patch_segments() {
+        let mut subtitles = parse_srt("1\n00:00:01,000 --> 00:00:02,000\nハイパードライブ起動\n\n2\n00:00:03,000 --> 00:00:04,000\nこんにちは\n", "ja").unwrap();
+        let replacements = [("ハイパードライブ起動".to_string(), "超光速航法起動".to_string())].into();
+
+        assert_eq!(patch_segments(&mut subtitles, &replacements), 1);
+        assert_eq!(subtitles.segments[0].text, "超光速航法起動");
+        assert_eq!(subtitles.segments[1].text, "こんにちは");
+    }
+
+    #[tokio::test]
+    async fn test_retranslate_keeps_stored_cache_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = TranslateConfig {
+            cache_dir: dir.path().to_string_lossy().into_owned(),
+            ..Config::default().translate
+        };
+        let services = TranslationServices { backend: Arc::new(Fixed("超光速航法起動")), ..TranslationServices::for_config(&config) };
+        let retranslator = Retranslator::new(config, services);
+
+        // Entries written under a key other than the one their context would give (such as
+        // segment-level Context mode keys) are replaced under that same key
+        let (source, context) = ("Hyperdrive on", "Punch it. Hyperdrive on");
+        let stored_key = retranslator.base.generate_cache_key(source, "ja", "context");
+        retranslator.base.save_to_persistent_cache(&stored_key, source, "ja", context, "ハイパードライブ起動", &TranslationQuality::Good).await.unwrap();
+
+        let mut subtitles = [parse_srt("1\n00:00:01,000 --> 00:00:02,000\nハイパードライブ起動\n", "ja").unwrap()];
+        assert_eq!(retranslator.retranslate(&mut subtitles, "ja", false, &Cancellation::new()).await.unwrap(), 1);
+        assert_eq!(subtitles[0].segments[0].text, "超光速航法起動");
+
+        let entries = retranslator.base.list_translation_cache_keys().await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, stored_key);
+        assert_eq!((entries[0].1.translation.as_str(), entries[0].1.context.as_str()), ("超光速航法起動", context));
+    }
+}
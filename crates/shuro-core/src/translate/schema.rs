@@ -0,0 +1,106 @@
+// Structured response schemas
+//
+// Ollama 0.5 and later constrain generation to a JSON schema passed as the request `format`
+// (structured outputs), so translation, evaluation and sentence reconstruction responses always
+// have the expected shape. With `structured_outputs` off, older servers get plain JSON mode
+// (`"format": "json"`) and responses are parsed into the same types; the only leniency left is
+// taking the outermost `{...}` when a model wraps the object in prose or code fences.
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use crate::config::TranslateConfig;
+use crate::error::{Result, ShuroError};
+
+/// `{"text": "..."}` response of a translation request
+pub fn translation() -> Value {
+    json!({
+        "type": "object",
+        "properties": { "text": { "type": "string" } },
+        "required": ["text"],
+    })
+}
+
+/// `{"evaluation": "PERFECT" | "GOOD" | "BAD" | "INVALID"}` response of a quality evaluation
+pub fn evaluation() -> Value {
+    json!({
+        "type": "object",
+        "properties": { "evaluation": { "type": "string", "enum": ["PERFECT", "GOOD", "BAD", "INVALID"] } },
+        "required": ["evaluation"],
+    })
+}
+
+/// `{"sentences": ["...", ...]}` response of a sentence reconstruction request
+pub fn sentences() -> Value {
+    json!({
+        "type": "object",
+        "properties": { "sentences": { "type": "array", "items": { "type": "string" } } },
+        "required": ["sentences"],
+    })
+}
+
+/// `{"known": bool, "synopsis": "..."}` response of a title lookup
+pub fn work_lookup() -> Value {
+    json!({
+        "type": "object",
+        "properties": { "known": { "type": "boolean" }, "synopsis": { "type": "string" } },
+        "required": ["known", "synopsis"],
+    })
+}
+
+/// `{"summary": "..."}` response of a transcription summary request
+pub fn summary() -> Value {
+    json!({
+        "type": "object",
+        "properties": { "summary": { "type": "string" } },
+        "required": ["summary"],
+    })
+}
+
+/// Request `format` for a response of the given schema
+pub fn response_format(config: &TranslateConfig, schema: Value) -> Value {
+    if config.structured_outputs {
+        schema
+    } else {
+        json!("json")
+    }
+}
+
+/// Parse a model response into the type its schema describes
+pub fn parse_response<T: DeserializeOwned>(raw: &str) -> Result<T> {
+    let raw = raw.trim();
+    if let Ok(parsed) = serde_json::from_str(raw) {
+        return Ok(parsed);
+    }
+    raw.find('{')
+        .zip(raw.rfind('}'))
+        .filter(|(start, end)| start < end)
+        .and_then(|(start, end)| serde_json::from_str(&raw[start..=end]).ok())
+        .ok_or_else(|| ShuroError::Translation(format!("Response does not match the expected JSON: {}", raw)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::translate::common::{QualityEvaluation, TranslationResult};
+
+    #[test]
+    fn test_parse_response() {
+        let result: TranslationResult = parse_response(r#"{"text": "こんにちは"}"#).unwrap();
+        assert_eq!(result.text, "こんにちは");
+
+        let fenced: QualityEvaluation = parse_response("```json\n{\"evaluation\": \"GOOD\"}\n```").unwrap();
+        assert_eq!(fenced.evaluation, "GOOD");
+
+        assert!(parse_response::<TranslationResult>("Here are some options: 1. こんにちは").is_err());
+        assert!(parse_response::<TranslationResult>(r#"{"translation": "こんにちは"}"#).is_err());
+    }
+
+    #[test]
+    fn test_response_format() {
+        let mut config = crate::config::Config::default().translate;
+        assert_eq!(response_format(&config, translation())["required"], json!(["text"]));
+        config.structured_outputs = false;
+        assert_eq!(response_format(&config, translation()), json!("json"));
+    }
+}
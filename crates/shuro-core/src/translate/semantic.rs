@@ -0,0 +1,251 @@
+// Embeddings-based semantic translation cache
+//
+// Every translation saved to the persistent cache is also indexed by the embedding of its
+// source text (Ollama `/api/embed`), one JSON file per cache key under `<cache_dir>/embeddings`.
+// On a cache miss the most similar previous translations into the same target language are
+// retrieved and injected into the prompt as examples, which keeps names, terminology and
+// phrasing consistent across episodes of the same series.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::config::{SemanticCacheConfig, TranslateConfig};
+use crate::error::{Result, ShuroError};
+use super::balancer::EndpointPool;
+
+/// Indexed translation with the embedding of its source text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticEntry {
+    pub source_text: String,
+    pub target_language: String,
+    pub translation: String,
+    pub embedding_model: String,
+    pub embedding: Vec<f32>,
+}
+
+/// Previous translation offered to the model as an example
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranslationExample {
+    pub source_text: String,
+    pub translation: String,
+    pub similarity: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct EmbedRequest<'a> {
+    model: &'a str,
+    input: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+/// Semantic index over cached translations
+pub struct SemanticCache {
+    client: Client,
+    pool: Arc<EndpointPool>,
+    config: SemanticCacheConfig,
+    index_dir: PathBuf,
+    entries: Mutex<Option<Vec<SemanticEntry>>>,
+    embeddings: Mutex<HashMap<String, Vec<f32>>>,
+}
+
+impl SemanticCache {
+    /// Create a semantic cache that shares the translator's HTTP client and endpoints
+    pub fn new(client: Client, pool: Arc<EndpointPool>, config: &TranslateConfig) -> Self {
+        Self {
+            client,
+            pool,
+            config: config.semantic_cache.clone(),
+            index_dir: Path::new(&config.cache_dir).join("embeddings"),
+            entries: Mutex::new(None),
+            embeddings: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Previous translations most similar to `text`, best first
+    pub async fn examples(&self, text: &str, target_language: &str) -> Result<Vec<TranslationExample>> {
+        let query = self.embed(text).await?;
+        self.ensure_loaded().await?;
+
+        let entries = self.entries.lock().expect("semantic index lock poisoned");
+        let examples = most_similar(
+            entries.as_deref().unwrap_or_default(),
+            &query,
+            target_language,
+            &self.config,
+        );
+        debug!("Semantic cache found {} examples for: {}", examples.len(), text);
+        Ok(examples)
+    }
+
+    /// Index a translation that was saved under `cache_key`
+    pub async fn index(&self, cache_key: &str, source_text: &str, target_language: &str, translation: &str) -> Result<()> {
+        let entry = SemanticEntry {
+            source_text: source_text.to_string(),
+            target_language: target_language.to_string(),
+            translation: translation.to_string(),
+            embedding_model: self.config.embedding_model.clone(),
+            embedding: self.embed(source_text).await?,
+        };
+
+        tokio::fs::create_dir_all(&self.index_dir).await?;
+        let content = serde_json::to_string(&entry)?;
+        tokio::fs::write(self.index_dir.join(format!("{}.json", cache_key)), content).await?;
+
+        if let Some(entries) = self.entries.lock().expect("semantic index lock poisoned").as_mut() {
+            entries.push(entry);
+        }
+        Ok(())
+    }
+
+    /// Remove every indexed translation
+    pub async fn clear(&self) -> Result<u64> {
+        let mut count = 0;
+        if let Ok(mut entries) = tokio::fs::read_dir(&self.index_dir).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                if tokio::fs::remove_file(entry.path()).await.is_ok() {
+                    count += 1;
+                }
+            }
+        }
+        *self.entries.lock().expect("semantic index lock poisoned") = None;
+        Ok(count)
+    }
+
+    /// Load the index from disk on first use
+    async fn ensure_loaded(&self) -> Result<()> {
+        if self.entries.lock().expect("semantic index lock poisoned").is_some() {
+            return Ok(());
+        }
+
+        let mut loaded = Vec::new();
+        if let Ok(mut dir_entries) = tokio::fs::read_dir(&self.index_dir).await {
+            while let Some(entry) = dir_entries.next_entry().await? {
+                let content = tokio::fs::read_to_string(entry.path()).await?;
+                let indexed: SemanticEntry = serde_json::from_str(&content)
+                    .map_err(|e| ShuroError::Cache(format!("Invalid semantic index entry {}: {}", entry.path().display(), e)))?;
+                if indexed.embedding_model == self.config.embedding_model {
+                    loaded.push(indexed);
+                }
+            }
+        }
+
+        debug!("Loaded {} semantic index entries", loaded.len());
+        *self.entries.lock().expect("semantic index lock poisoned") = Some(loaded);
+        Ok(())
+    }
+
+    /// Embed text with the configured model, reusing embeddings computed during this run
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        if let Some(embedding) = self.embeddings.lock().expect("embedding memo lock poisoned").get(text) {
+            return Ok(embedding.clone());
+        }
+
+        let response = self.pool
+            .post(&self.client, "/api/embed", &EmbedRequest { model: &self.config.embedding_model, input: text })
+            .await
+            .map_err(|e| ShuroError::Translation(format!("Embedding request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(ShuroError::Translation(format!(
+                "Ollama embedding API error {}: {}", status, error_text
+            )));
+        }
+
+        let embed_response: EmbedResponse = response.json().await
+            .map_err(|e| ShuroError::Translation(format!("Failed to parse embedding response: {}", e)))?;
+        let embedding = embed_response.embeddings.into_iter().next()
+            .ok_or_else(|| ShuroError::Translation("Empty embedding response".to_string()))?;
+
+        self.embeddings.lock().expect("embedding memo lock poisoned").insert(text.to_string(), embedding.clone());
+        Ok(embedding)
+    }
+}
+
+/// Select the indexed translations closest to `query` for the target language
+fn most_similar(
+    entries: &[SemanticEntry],
+    query: &[f32],
+    target_language: &str,
+    config: &SemanticCacheConfig,
+) -> Vec<TranslationExample> {
+    let mut examples: Vec<TranslationExample> = entries
+        .iter()
+        .filter(|e| e.target_language == target_language)
+        .map(|e| TranslationExample {
+            source_text: e.source_text.clone(),
+            translation: e.translation.clone(),
+            similarity: cosine_similarity(&e.embedding, query),
+        })
+        .filter(|e| e.similarity >= config.min_similarity)
+        .collect();
+
+    examples.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap_or(std::cmp::Ordering::Equal));
+    let mut seen = HashSet::new();
+    examples.retain(|e| seen.insert(e.source_text.clone()));
+    examples.truncate(config.examples);
+    examples
+}
+
+/// Cosine similarity of two vectors (0.0 when either is zero or their lengths differ)
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| *x as f64 * *y as f64).sum();
+    let norm_a: f64 = a.iter().map(|x| (*x as f64).powi(2)).sum::<f64>().sqrt();
+    let norm_b: f64 = b.iter().map(|x| (*x as f64).powi(2)).sum::<f64>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(source_text: &str, target_language: &str, embedding: Vec<f32>) -> SemanticEntry {
+        SemanticEntry {
+            source_text: source_text.to_string(),
+            target_language: target_language.to_string(),
+            translation: format!("{} ({})", source_text, target_language),
+            embedding_model: "embed".to_string(),
+            embedding,
+        }
+    }
+
+    #[test]
+    fn test_most_similar() {
+        let entries = vec![
+            entry("close", "ja", vec![1.0, 0.1]),
+            entry("closest", "ja", vec![1.0, 0.0]),
+            entry("other language", "ko", vec![1.0, 0.0]),
+            entry("unrelated", "ja", vec![0.0, 1.0]),
+        ];
+        let config = SemanticCacheConfig { examples: 5, min_similarity: 0.9, ..SemanticCacheConfig::default() };
+
+        let examples = most_similar(&entries, &[2.0, 0.0], "ja", &config);
+        let sources: Vec<&str> = examples.iter().map(|e| e.source_text.as_str()).collect();
+        assert_eq!(sources, vec!["closest", "close"]);
+
+        let config = SemanticCacheConfig { examples: 1, ..config };
+        assert_eq!(most_similar(&entries, &[2.0, 0.0], "ja", &config).len(), 1);
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[3.0, 0.0]) - 1.0).abs() < 1e-9);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+    }
+}
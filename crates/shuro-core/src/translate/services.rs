@@ -0,0 +1,114 @@
+// Translation services
+//
+// The runtime side of translation, kept apart from the configuration: the endpoint pool, the
+// model backend wrapped with retries and cost metering, the cost tracker and the metrics
+// collector. A workflow creates them once and passes them to every translator, judge, note
+// generator and lookup it creates, so all of them share endpoint health, retry budget, cost
+// budget and metrics; `TranslateConfig` stays plain data that can be cloned and serialized.
+
+use std::sync::Arc;
+
+use crate::config::TranslateConfig;
+use crate::cost::CostTracker;
+use crate::error::Result;
+use crate::retry::RetryPolicy;
+use super::backend::{BackendRegistry, ModelBackend};
+use super::balancer::EndpointPool;
+use super::metrics::TranslationMetrics;
+
+/// Runtime handles shared by the translators of a workflow
+#[derive(Debug, Clone)]
+pub struct TranslationServices {
+    /// Ollama endpoints of `translate.endpoints`, also used for semantic cache embeddings
+    pub pool: Arc<EndpointPool>,
+    /// Backend model requests are sent to, retried and metered
+    pub backend: Arc<dyn ModelBackend>,
+    /// Token usage and cost of model requests
+    pub cost: Arc<CostTracker>,
+    /// Latency and token counts of model requests
+    pub metrics: Arc<TranslationMetrics>,
+}
+
+impl TranslationServices {
+    /// Services of a workflow: the backend named by `config.provider` in `backends`, retried by
+    /// `retry` and metered by `cost`
+    pub fn new(
+        config: &TranslateConfig,
+        backends: &BackendRegistry,
+        retry: Arc<RetryPolicy>,
+        cost: Arc<CostTracker>,
+    ) -> Result<Self> {
+        let pool = Arc::new(EndpointPool::new(config));
+        let backend = backends.create(config, &pool, retry, cost.clone())?;
+        let metrics = Arc::new(TranslationMetrics::new(config.metrics_log_interval));
+        Ok(Self { pool, backend, cost, metrics })
+    }
+
+    /// Services of a translator used on its own: the built-in backend of `config.provider`
+    /// (Ollama for names only a workflow's registry knows) with default retries and no budget
+    pub fn for_config(config: &TranslateConfig) -> Self {
+        let pool = Arc::new(EndpointPool::new(config));
+        let cost = Arc::new(CostTracker::default());
+        let backend = BackendRegistry::with_builtin()
+            .create(config, &pool, Arc::default(), cost.clone())
+            .unwrap_or_else(|_| pool.clone());
+        let metrics = Arc::new(TranslationMetrics::new(config.metrics_log_interval));
+        Self { pool, backend, cost, metrics }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use reqwest::Client;
+    use crate::config::Config;
+    use crate::translate::common::{TranslationRequest, TranslationResponse};
+
+    #[derive(Debug)]
+    struct Counted;
+
+    #[async_trait]
+    impl ModelBackend for Counted {
+        async fn generate(&self, _client: &Client, _request: &TranslationRequest) -> Result<TranslationResponse> {
+            Ok(TranslationResponse {
+                response: "{}".to_string(),
+                done: true,
+                prompt_eval_count: Some(10),
+                eval_count: Some(5),
+                eval_duration: None,
+            })
+        }
+
+        async fn check_models(&self, _models: &[&str]) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_the_workflow_handles() {
+        let config = Config::default();
+        let mut translate = config.translate.clone();
+        translate.provider = "counted".to_string();
+        let backends = BackendRegistry::new()
+            .register("counted", |_config, _pool| Ok(Arc::new(Counted) as Arc<dyn ModelBackend>));
+        let cost = Arc::new(CostTracker::new(&config.cost));
+        let services = TranslationServices::new(&translate, &backends, Arc::default(), cost.clone()).unwrap();
+
+        // Services handed to two translators meter into the workflow's tracker
+        let request = TranslationRequest {
+            model: translate.model.clone(),
+            prompt: "Hello".to_string(),
+            stream: false,
+            format: serde_json::json!("json"),
+            options: None,
+        };
+        for services in [services.clone(), services] {
+            services.backend.generate(&Client::new(), &request).await.unwrap();
+        }
+        assert_eq!(cost.run_usage().tokens(), 30);
+
+        translate.provider = "missing".to_string();
+        assert!(TranslationServices::new(&translate, &backends, Arc::default(), cost).is_err());
+    }
+}
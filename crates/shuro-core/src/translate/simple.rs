@@ -0,0 +1,121 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+use crate::cancel::Cancellation;
+use crate::config::TranslateConfig;
+use crate::error::Result;
+use crate::quality::Transcription;
+use super::{Translator, common::{BaseTranslator, TranslationQuality, bounded_in_order}, services::TranslationServices};
+
+/// Simple translation: Translate each segment individually without context, with up to
+/// `concurrency` requests in flight
+pub struct SimpleTranslator {
+    base: BaseTranslator,
+}
+
+impl SimpleTranslator {
+    pub fn new(config: TranslateConfig, services: TranslationServices) -> Self {
+        Self {
+            base: BaseTranslator::with_services(config, services),
+        }
+    }
+
+    /// Cached translation of a segment, from the persistent or the in-memory cache
+    async fn cached_translation(&mut self, cache_key: &str) -> Option<String> {
+        if let Ok(Some(cached_translation)) = self.base.load_from_persistent_cache(cache_key).await {
+            self.base.cache.insert(cache_key.to_string(), cached_translation.clone());
+            return Some(cached_translation);
+        }
+        self.base.cache.get(cache_key).cloned()
+    }
+}
+
+#[async_trait]
+impl Translator for SimpleTranslator {
+    fn retries(&self) -> usize {
+        self.base.retries()
+    }
+
+    fn cache_hits(&self) -> usize {
+        self.base.cache_hits()
+    }
+
+    /// Simple translation: Translate each segment individually without context
+    async fn translate_transcription(
+        &mut self,
+        transcription: &mut Transcription,
+        target_language: &str,
+        _context: Option<&str>,
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        cancel.run(async {
+            info!("Starting simple translation to {}", target_language);
+        
+            let total_segments = transcription.segments.len();
+
+            // Cached segments are applied right away; segments sharing a source text are translated once
+            let mut pending: Vec<(String, Vec<usize>)> = Vec::new();
+            let mut pending_by_key: HashMap<String, usize> = HashMap::new();
+            for (idx, segment) in transcription.segments.iter_mut().enumerate() {
+                let cache_key = self.base.generate_cache_key(&segment.text, target_language, &self.base.key_context(segment, ""));
+                if let Some(cached) = self.cached_translation(&cache_key).await {
+                    segment.text = cached;
+                    continue;
+                }
+                match pending_by_key.get(&cache_key) {
+                    Some(&position) => pending[position].1.push(idx),
+                    None => {
+                        pending_by_key.insert(cache_key.clone(), pending.len());
+                        pending.push((cache_key, vec![idx]));
+                    }
+                }
+            }
+            info!("{} of {} segments to translate, {} requests at a time", pending.len(), total_segments, self.base.config.concurrency.max(1));
+
+            let mut translated = Vec::new();
+            {
+                let base = &self.base;
+                let sources: Vec<String> = pending.iter().map(|(_, indices)| transcription.segments[indices[0]].text.clone()).collect();
+                let hints: Vec<_> = pending.iter().map(|(_, indices)| base.hints_of(&transcription.segments[indices[0]])).collect();
+                let mut translations = bounded_in_order(
+                    base.config.concurrency,
+                    sources.iter().zip(&hints),
+                    |(text, hints)| base.translate_with_hints(text, target_language, None, hints),
+                );
+
+                let mut pending = pending.into_iter().zip(&sources);
+                while let Some(result) = translations.next().await {
+                    let Some(((cache_key, indices), source)) = pending.next() else { break };
+                    info!("┌─ Translating segment {}/{} (Simple) ────────", indices[0] + 1, total_segments);
+                    info!("│ Source: {}", source);
+
+                    match result {
+                        Ok(translation) => {
+                            info!("│ Target: {}", translation);
+                            info!("└─────────────────────────────────────");
+                            if let Err(e) = base.save_to_persistent_cache(
+                                &cache_key, source, target_language, "", &translation, &TranslationQuality::Good,
+                            ).await {
+                                warn!("Failed to save translation to persistent cache: {}", e);
+                            }
+                            for idx in indices {
+                                transcription.segments[idx].text = translation.clone();
+                            }
+                            translated.push((cache_key, translation));
+                        }
+                        Err(e) => {
+                            warn!("│ Failed: {}", e);
+                            warn!("└─────────────────────────────────────");
+                            // Keep original text on failure
+                        }
+                    }
+                }
+            }
+            self.base.cache.extend(translated);
+        
+            Ok(())
+        }).await
+    }
+} 
\ No newline at end of file
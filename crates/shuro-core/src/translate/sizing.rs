@@ -0,0 +1,133 @@
+// Context sizing from the model's context length
+//
+// Ollama reports a model's trained context length through `/api/show`. With
+// `adaptive_context` the translator requests `min(context_length, max_context_tokens)` tokens of
+// context (`num_ctx`) and sizes the Context-mode window, the LLM-mode window and the NLP sentence
+// length to fit it, instead of fixed values that overflow small models and underuse large ones.
+// Models whose context length is not reported keep the configured values.
+
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::info;
+
+use crate::config::TranslateConfig;
+use crate::error::{Result, ShuroError};
+use super::backend::ModelBackend;
+use super::balancer::EndpointPool;
+
+/// Tokens kept free for instructions, glossary, examples and the response
+const RESERVED_TOKENS: usize = 1024;
+
+/// Rough token count of one transcription segment
+const SEGMENT_TOKENS: usize = 64;
+
+/// Rough number of characters per token
+const CHARS_PER_TOKEN: usize = 3;
+
+#[derive(Debug, Deserialize)]
+struct ShowResponse {
+    #[serde(default)]
+    model_info: HashMap<String, Value>,
+}
+
+/// Window and batch sizes that fit a context of a given number of tokens
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextSizing {
+    pub num_ctx: usize,
+    pub context_window_size: usize,
+    pub llm_window_size: usize,
+    pub nlp_max_sentence_chars: usize,
+}
+
+impl ContextSizing {
+    /// Sizes for a context of `num_ctx` tokens; half of what is left after the reserve goes to
+    /// the input so the response fits as well
+    pub fn for_context(num_ctx: usize) -> Self {
+        let input_tokens = num_ctx.saturating_sub(RESERVED_TOKENS) / 2;
+        Self {
+            num_ctx,
+            context_window_size: (input_tokens / (2 * SEGMENT_TOKENS)).clamp(1, 8),
+            llm_window_size: (input_tokens / SEGMENT_TOKENS).clamp(3, 24),
+            nlp_max_sentence_chars: (input_tokens * CHARS_PER_TOKEN).clamp(200, 4000),
+        }
+    }
+
+    /// Apply the sizes to a translator configuration
+    pub fn apply(&self, config: &mut TranslateConfig) {
+        config.num_ctx = Some(self.num_ctx);
+        config.context_window_size = self.context_window_size;
+        config.llm_window_size = self.llm_window_size;
+        config.nlp_max_sentence_chars = self.nlp_max_sentence_chars;
+    }
+}
+
+/// Size `config` to the context length `backend` reports for the translation model when
+/// `adaptive_context` is set
+pub async fn fit_to_model(config: &mut TranslateConfig, backend: &dyn ModelBackend) -> Result<()> {
+    if !config.adaptive_context {
+        return Ok(());
+    }
+
+    let Some(context_length) = backend.context_length(&config.model).await? else {
+        info!("Model '{}' does not report its context length, keeping configured window sizes", config.model);
+        return Ok(());
+    };
+
+    let sizing = ContextSizing::for_context(context_length.min(config.max_context_tokens));
+    info!(
+        "📐 Model '{}' supports {} context tokens; using {}: context window {}, LLM window {}, NLP sentences up to {} chars",
+        config.model, context_length, sizing.num_ctx, sizing.context_window_size, sizing.llm_window_size, sizing.nlp_max_sentence_chars
+    );
+    sizing.apply(config);
+    Ok(())
+}
+
+/// Context length the model was trained with, as reported by Ollama
+pub async fn model_context_length(pool: &EndpointPool, model: &str) -> Result<Option<usize>> {
+    let response = pool.show(&Client::new(), model).await?;
+
+    if !response.status().is_success() {
+        return Err(ShuroError::Translation(format!("Failed to query model '{}': {}", model, response.status())));
+    }
+
+    let show: ShowResponse = response.json().await
+        .map_err(|e| ShuroError::Translation(format!("Failed to parse model information: {}", e)))?;
+    Ok(context_length(&show.model_info))
+}
+
+/// `<architecture>.context_length` entry of the model information
+fn context_length(model_info: &HashMap<String, Value>) -> Option<usize> {
+    model_info
+        .iter()
+        .find(|(key, _)| key.ends_with(".context_length"))
+        .and_then(|(_, value)| value.as_u64())
+        .map(|length| length as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_length() {
+        let show: ShowResponse = serde_json::from_str(
+            r#"{"model_info": {"general.architecture": "gemma3", "gemma3.context_length": 32768}}"#
+        ).unwrap();
+        assert_eq!(context_length(&show.model_info), Some(32768));
+        assert_eq!(context_length(&HashMap::new()), None);
+    }
+
+    #[test]
+    fn test_sizing_grows_with_context() {
+        let small = ContextSizing::for_context(2048);
+        assert_eq!((small.context_window_size, small.llm_window_size, small.nlp_max_sentence_chars), (4, 8, 1536));
+
+        let large = ContextSizing::for_context(32768);
+        assert_eq!((large.context_window_size, large.llm_window_size, large.nlp_max_sentence_chars), (8, 24, 4000));
+
+        let tiny = ContextSizing::for_context(512);
+        assert_eq!((tiny.context_window_size, tiny.llm_window_size, tiny.nlp_max_sentence_chars), (1, 3, 200));
+    }
+}
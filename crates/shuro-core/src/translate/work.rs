@@ -0,0 +1,386 @@
+// Title and synopsis of the translated work
+//
+// Character names and setting-specific vocabulary translate better when the model knows what it
+// is translating. Before a file is translated, its title and synopsis are gathered, each field
+// from the first source that has it:
+//
+// - `translate.work.title` and `translate.work.description` (`--title`, `--description`)
+// - the container's `title`/`show` and `synopsis`/`description`/`comment` tags
+// - the file name, without the episode code and release tags (`1080p`, `x264`, `[Group]`)
+//
+// With `translate.work.llm_lookup`, a title without a synopsis is looked up with the translation
+// model, whose synopsis is used only when it reports recognizing the work. The result is added
+// to every translation prompt as background that must not be translated, and sent to external
+// translator plugins as `work`. Cached translations are reused as they are.
+//
+// A window of neighbouring segments rarely tells who "he" is or what a recurring term refers to.
+// With `translate.work.summarize`, the translation model first reads the whole transcription and
+// summarizes its plot, characters and domain, and that summary joins the background of every
+// translation prompt. Transcriptions longer than `summary_chunk_chars` are summarized in parts
+// whose summaries are then combined. Translation waits for the full transcription instead of
+// starting while whisper runs. Summaries are cached by model and transcription text in
+// `<cache_dir>/summaries`.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tracing::warn;
+
+use crate::cache;
+use crate::cancel::Cancellation;
+use crate::config::TranslateConfig;
+use crate::episode::EpisodeInfo;
+use crate::error::{Result, ShuroError};
+use crate::quality::Transcription;
+use super::backend::ModelBackend;
+use super::common::{RequestOptions, TranslationRequest};
+use super::metrics::TranslationMetrics;
+use super::services::TranslationServices;
+use super::{guard, schema};
+
+/// File name tokens that start the release tags after a title
+const RELEASE_TAGS: &[&str] = &[
+    "480p", "576p", "720p", "1080p", "1080i", "2160p", "4k", "uhd", "bluray", "blu-ray", "bdrip", "brrip",
+    "web", "web-dl", "webdl", "webrip", "hdtv", "dvdrip", "dvd", "x264", "x265", "h264", "h265", "hevc",
+    "avc", "hdr", "10bit", "aac", "ac3", "dts", "proper", "repack", "remux", "extended", "unrated",
+];
+
+/// Container tags holding the title, most specific first
+const TITLE_TAGS: &[&str] = &["show", "title"];
+
+/// Container tags holding a synopsis, most specific first
+const SYNOPSIS_TAGS: &[&str] = &["synopsis", "description", "comment"];
+
+/// What is being translated
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorkContext {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<u32>,
+    /// `S01E02` code of an episode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub episode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub synopsis: Option<String>,
+    /// Plot, characters and domain summarized from the transcription
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+impl WorkContext {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.synopsis.is_none() && self.summary.is_none()
+    }
+
+    /// Title, year and episode parsed from a file stem such as `The.Show.2019.S01E02.1080p.WEB-DL`
+    pub fn from_file_name(stem: &str) -> Self {
+        let (name, episode) = match EpisodeInfo::parse(stem) {
+            Some(info) => (info.series.clone().unwrap_or_default(), Some(info.code())),
+            None => (stem.to_string(), None),
+        };
+
+        let name = strip_brackets(&name);
+        let tokens: Vec<&str> = name
+            .split(['.', '_', ' '])
+            .map(|t| t.trim_matches(['(', ')']))
+            .filter(|t| !t.is_empty())
+            .take_while(|t| {
+                let lower = t.to_lowercase();
+                !RELEASE_TAGS.contains(&lower.as_str()) && !RELEASE_TAGS.contains(&lower.split('-').next().unwrap_or_default())
+            })
+            .collect();
+        // The release year is the last year-like token; earlier ones belong to the title ("Blade Runner 2049 2017")
+        let year_position = tokens.iter()
+            .rposition(|t| t.len() == 4 && t.parse::<u32>().is_ok_and(|y| (1900..2100).contains(&y)))
+            .filter(|&position| position > 0);
+        let (title, year) = match year_position {
+            Some(position) => (&tokens[..position], tokens[position].parse().ok()),
+            None => (&tokens[..], None),
+        };
+
+        let title = title.join(" ");
+        Self {
+            title: (!title.is_empty()).then_some(title),
+            year,
+            episode,
+            synopsis: None,
+            summary: None,
+        }
+    }
+
+    /// Title and synopsis from container tags (keys in any case)
+    pub fn from_tags(tags: &HashMap<String, String>) -> Self {
+        let first = |keys: &[&str]| keys.iter().find_map(|key| {
+            tags.iter()
+                .find(|(k, v)| k.eq_ignore_ascii_case(key) && !v.trim().is_empty())
+                .map(|(_, v)| v.trim().to_string())
+        });
+        Self { title: first(TITLE_TAGS), synopsis: first(SYNOPSIS_TAGS), ..Self::default() }
+    }
+
+    /// Fill the fields this context lacks from `other`
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            title: self.title.or(other.title),
+            year: self.year.or(other.year),
+            episode: self.episode.or(other.episode),
+            synopsis: self.synopsis.or(other.synopsis),
+            summary: self.summary.or(other.summary),
+        }
+    }
+
+    /// Title with year and episode, e.g. `The Show (2019), episode S01E02`
+    pub fn summary(&self) -> String {
+        let mut summary = self.title.clone().unwrap_or_else(|| "Untitled".to_string());
+        if let Some(year) = self.year {
+            summary.push_str(&format!(" ({})", year));
+        }
+        if let Some(episode) = &self.episode {
+            summary.push_str(&format!(", episode {}", episode));
+        }
+        summary
+    }
+
+    /// Background section of a translation prompt
+    pub fn prompt_section(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        let mut section = format!(
+            "[About the video - background for names and vocabulary, DO NOT translate]\nTitle: {}\n",
+            guard::sanitize(&self.summary()).replace('\n', " ")
+        );
+        if let Some(synopsis) = &self.synopsis {
+            section.push_str(&format!("Synopsis:\n{}\n", guard::quote(synopsis)));
+        }
+        if let Some(summary) = &self.summary {
+            section.push_str(&format!("Summary of the whole video (plot, characters, domain):\n{}\n", guard::quote(summary)));
+        }
+        section.push('\n');
+        section
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeOutput {
+    format: Option<ProbeFormat>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeFormat {
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+/// Parse `ffprobe -show_entries format_tags -of json` output
+pub fn parse_format_tags(json: &str) -> Result<HashMap<String, String>> {
+    let output: ProbeOutput = serde_json::from_str(json)
+        .map_err(|e| ShuroError::Media(format!("Failed to parse ffprobe output: {}", e)))?;
+    Ok(output.format.map(|f| f.tags).unwrap_or_default())
+}
+
+/// `name` without `[...]` groups
+fn strip_brackets(name: &str) -> String {
+    let mut depth = 0usize;
+    name.chars()
+        .filter(|c| match c {
+            '[' => { depth += 1; false }
+            ']' => { depth = depth.saturating_sub(1); false }
+            _ => depth == 0,
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkLookup {
+    known: bool,
+    #[serde(default)]
+    synopsis: String,
+}
+
+/// Synopsis of the work titled in `work` as recalled by the translation model; `None` when the
+/// model does not recognize it
+pub async fn lookup_synopsis(config: &TranslateConfig, work: &WorkContext, services: &TranslationServices) -> Result<Option<String>> {
+    let prompt = format!(
+        "You are helping a subtitle translator. Identify the film or TV series below.\n\
+         If you recognize it with confidence, return {{\"known\": true, \"synopsis\": \"...\"}} with a synopsis of at most \
+         three sentences naming the main characters and the setting. Otherwise return {{\"known\": false, \"synopsis\": \"\"}}; \
+         never guess.\n\
+         {}\n\
+         \n\
+         {}",
+        guard::DATA_NOTICE, guard::quote(&work.summary())
+    );
+    let request = TranslationRequest {
+        model: config.model.clone(),
+        prompt,
+        stream: false,
+        format: schema::response_format(config, schema::work_lookup()),
+        options: RequestOptions::for_model(config, &config.model),
+    };
+
+    let started = Instant::now();
+    let response = services.backend.generate(&Client::new(), &request).await?;
+    services.metrics.record(&config.model, started.elapsed(), response.usage());
+
+    let lookup: WorkLookup = schema::parse_response(&response.response)?;
+    let synopsis = lookup.synopsis.trim();
+    Ok((lookup.known && !synopsis.is_empty()).then(|| synopsis.to_string()))
+}
+
+#[derive(Debug, Deserialize)]
+struct SummaryResponse {
+    summary: String,
+}
+
+/// Summary of the plot, characters and domain of `transcription` by the translation model, from
+/// the cache when it was summarized before; `None` for an empty transcription
+pub async fn summarize_transcription(
+    config: &TranslateConfig,
+    transcription: &Transcription,
+    services: &TranslationServices,
+    cancel: &Cancellation,
+) -> Result<Option<String>> {
+    let lines: Vec<&str> = transcription.segments.iter().map(|s| s.text.trim()).filter(|t| !t.is_empty()).collect();
+    if lines.is_empty() {
+        return Ok(None);
+    }
+    let cache_path = summary_cache_path(config, &lines);
+    if let Ok(summary) = tokio::fs::read_to_string(&cache_path).await {
+        cache::touch(&cache_path);
+        return Ok(Some(summary));
+    }
+
+    let chunks = chunk_lines(&lines, config.work.summary_chunk_chars);
+    let mut summaries = Vec::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let part = if chunks.len() > 1 { format!(" (part {} of {})", i + 1, chunks.len()) } else { String::new() };
+        let prompt = format!(
+            "You are helping a subtitle translator. Summarize the subtitles of a video below{}.\n\
+             Describe the plot, the characters (names, gender, how they address each other) and the domain \
+             (setting, recurring terms) in at most 150 words, so that pronouns and terminology can be translated \
+             consistently. Return {{\"summary\": \"...\"}}.\n\
+             {}\n\
+             \n\
+             {}",
+            part, guard::DATA_NOTICE, guard::quote(chunk)
+        );
+        summaries.push(cancel.run(request_summary(config, services.backend.as_ref(), prompt, &services.metrics)).await?);
+    }
+    let summary = if summaries.len() == 1 {
+        summaries.remove(0)
+    } else {
+        let prompt = format!(
+            "You are helping a subtitle translator. Below are summaries of consecutive parts of one video.\n\
+             Combine them into one summary of the plot, the characters (names, gender, how they address each other) \
+             and the domain (setting, recurring terms) in at most 200 words. Return {{\"summary\": \"...\"}}.\n\
+             {}\n\
+             \n\
+             {}",
+            guard::DATA_NOTICE, guard::quote(&summaries.join("\n\n"))
+        );
+        cancel.run(request_summary(config, services.backend.as_ref(), prompt, &services.metrics)).await?
+    };
+    if summary.is_empty() {
+        return Ok(None);
+    }
+
+    if let Some(dir) = cache_path.parent()
+        && let Err(e) = tokio::fs::create_dir_all(dir).await {
+        warn!("Failed to create summary cache directory: {}", e);
+    }
+    if let Err(e) = tokio::fs::write(&cache_path, &summary).await {
+        warn!("Failed to cache transcription summary: {}", e);
+    }
+    Ok(Some(summary))
+}
+
+async fn request_summary(config: &TranslateConfig, backend: &dyn ModelBackend, prompt: String, metrics: &TranslationMetrics) -> Result<String> {
+    let request = TranslationRequest {
+        model: config.model.clone(),
+        prompt,
+        stream: false,
+        format: schema::response_format(config, schema::summary()),
+        options: RequestOptions::for_model(config, &config.model),
+    };
+    let started = Instant::now();
+    let response = backend.generate(&Client::new(), &request).await?;
+    metrics.record(&config.model, started.elapsed(), response.usage());
+    let parsed: SummaryResponse = schema::parse_response(&response.response)?;
+    Ok(parsed.summary.trim().to_string())
+}
+
+/// `lines` joined into chunks of at most `max_chars` characters; a longer line is a chunk of its own
+fn chunk_lines(lines: &[&str], max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    for line in lines {
+        if !chunk.is_empty() && chunk.chars().count() + line.chars().count() + 1 > max_chars {
+            chunks.push(std::mem::take(&mut chunk));
+        }
+        if !chunk.is_empty() {
+            chunk.push('\n');
+        }
+        chunk.push_str(line);
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+fn summary_cache_path(config: &TranslateConfig, lines: &[&str]) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    config.model.hash(&mut hasher);
+    lines.hash(&mut hasher);
+    Path::new(&config.cache_dir).join("summaries").join(format!("{:016x}.txt", hasher.finish()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_work_context_sources() {
+        let movie = WorkContext::from_file_name("[Group] Spirited.Away.2001.1080p.BluRay.x264-GRP");
+        assert_eq!((movie.title.as_deref(), movie.year), (Some("Spirited Away"), Some(2001)));
+        assert_eq!(WorkContext::from_file_name("Blade Runner 2049 (2017) WEB-DL").title.as_deref(), Some("Blade Runner 2049"));
+        let numeric = WorkContext::from_file_name("1917.2019.720p");
+        assert_eq!((numeric.title.as_deref(), numeric.year), (Some("1917"), Some(2019)));
+
+        let episode = WorkContext::from_file_name("The.Expanse.2015.S02E05.720p.HDTV");
+        assert_eq!(episode.summary(), "The Expanse (2015), episode S02E05");
+
+        let tags = parse_format_tags(
+            r#"{"format": {"tags": {"TITLE": "Episode 5", "show": "The Expanse", "comment": " ", "DESCRIPTION": "Miller hunts Julie Mao."}}}"#
+        ).unwrap();
+        assert_eq!(WorkContext::from_tags(&tags).synopsis.as_deref(), Some("Miller hunts Julie Mao."));
+        let user = WorkContext { synopsis: Some("A detective story in space.".to_string()), ..WorkContext::default() };
+        let context = user.or(WorkContext::from_tags(&tags)).or(episode);
+        assert_eq!(context.title.as_deref(), Some("The Expanse"));
+        assert_eq!(context.synopsis.as_deref(), Some("A detective story in space."));
+        assert_eq!(context.episode.as_deref(), Some("S02E05"));
+
+        let section = context.prompt_section();
+        assert!(section.starts_with("[About the video - background for names and vocabulary, DO NOT translate]\nTitle: The Expanse (2015), episode S02E05\n"));
+        assert!(section.contains("<subtitle>\nA detective story in space.\n</subtitle>"));
+        assert_eq!(WorkContext::default().prompt_section(), "");
+
+        let summarized = WorkContext { summary: Some("Naomi and Amos rescue Holden.".to_string()), ..WorkContext::default() };
+        assert!(!summarized.is_empty());
+        assert!(summarized.prompt_section().contains("Summary of the whole video (plot, characters, domain):\n<subtitle>\nNaomi and Amos rescue Holden.\n</subtitle>"));
+    }
+
+    #[test]
+    fn test_chunk_lines() {
+        let lines = ["Where is Holden?", "On the Rocinante.", "Then we go."];
+        assert_eq!(chunk_lines(&lines, 12000), vec!["Where is Holden?\nOn the Rocinante.\nThen we go."]);
+        assert_eq!(chunk_lines(&lines, 34), vec!["Where is Holden?\nOn the Rocinante.", "Then we go."]);
+        assert_eq!(chunk_lines(&lines, 5), vec!["Where is Holden?", "On the Rocinante.", "Then we go."]);
+    }
+}
@@ -0,0 +1,196 @@
+// Webhook notifications
+//
+// Long batches finish unattended. With `webhook.url` set, `process` and `batch` post a
+// notification whenever a video finishes or fails, so a media server (Plex, Jellyfin) can rescan
+// its library or a chat channel can follow the batch. The notifications are made from the
+// workflow's progress events (see progress.rs), and the body defaults to
+//
+//   {"event": "file_completed", "file": "/videos/e01.mkv", "subtitles": {"ja": "/videos/e01_ja.srt"}}
+//   {"event": "file_failed", "file": "/videos/e02.mkv", "subtitles": {}, "error": "..."}
+//
+// `webhook.template` replaces it, for receivers expecting their own format, with any text in
+// which `{{event}}`, `{{file}}`, `{{file_name}}`, `{{languages}}`, `{{subtitles}}` and
+// `{{error}}` are substituted, JSON-escaped so they can go inside the template's strings.
+// Notifications are posted one at a time on a task of their own; a receiver that is down or
+// slow is logged as a warning and never fails or holds up the run.
+
+use reqwest::Client;
+use reqwest::header::CONTENT_TYPE;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::config::WebhookConfig;
+use crate::progress::{ProgressEvent, ProgressSender};
+
+/// Notification about one video
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Notification {
+    pub event: &'static str,
+    pub file: PathBuf,
+    /// Subtitle file written per language
+    pub subtitles: BTreeMap<String, PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Posts notifications to the configured webhook
+pub struct Webhook {
+    url: String,
+    config: WebhookConfig,
+    client: Client,
+}
+
+impl Webhook {
+    /// Webhook of `config`; `None` without `webhook.url`
+    pub fn new(config: &WebhookConfig) -> Option<Self> {
+        let url = config.url.clone().filter(|url| !url.is_empty())?;
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .build()
+            .unwrap_or_default();
+        Some(Self { url, config: config.clone(), client })
+    }
+
+    /// Start posting; the returned sender takes the workflow's progress events, and the task ends
+    /// once every sender is dropped and the last notification is posted
+    pub fn start(self) -> (ProgressSender, JoinHandle<()>) {
+        let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(async move {
+            let mut subtitles = HashMap::new();
+            while let Some(event) = events_rx.recv().await {
+                if let Some(notification) = notification(&mut subtitles, event) {
+                    self.post(&notification).await;
+                }
+            }
+        });
+        (events_tx, task)
+    }
+
+    /// Body of the notification: the template filled in, or the default JSON payload
+    pub fn body(&self, notification: &Notification) -> String {
+        match &self.config.template {
+            Some(template) => render(template, notification),
+            None => serde_json::to_string(notification).unwrap_or_default(),
+        }
+    }
+
+    async fn post(&self, notification: &Notification) {
+        let mut request = self.client.post(&self.url).body(self.body(notification));
+        if !self.config.headers.keys().any(|name| name.eq_ignore_ascii_case(CONTENT_TYPE.as_str())) {
+            request = request.header(CONTENT_TYPE, "application/json");
+        }
+        for (name, value) in &self.config.headers {
+            request = request.header(name, value);
+        }
+        match request.send().await.and_then(|response| response.error_for_status()) {
+            Ok(_) => debug!("🔔 Posted {} of {} to the webhook", notification.event, notification.file.display()),
+            Err(e) => warn!("Webhook notification about {} failed: {}", notification.file.display(), e),
+        }
+    }
+}
+
+/// Notification for `event`, once a video has finished or failed; `subtitles` gathers the
+/// subtitles written per video until then
+fn notification(subtitles: &mut HashMap<PathBuf, BTreeMap<String, PathBuf>>, event: ProgressEvent) -> Option<Notification> {
+    let (event, file, error) = match event {
+        ProgressEvent::LanguageCompleted { file, language, subtitles: path } => {
+            subtitles.entry(file).or_default().insert(language, path);
+            return None;
+        }
+        ProgressEvent::FileCompleted { file } => ("file_completed", file, None),
+        ProgressEvent::FileFailed { file, error } => ("file_failed", file, Some(error)),
+        _ => return None,
+    };
+    let written = subtitles.remove(&file).unwrap_or_default();
+    Some(Notification { event, file, subtitles: written, error })
+}
+
+/// `template` with the placeholders of `notification` substituted in one pass, so placeholders
+/// in substituted values (a file name holding `{{error}}`) are copied as they are
+fn render(template: &str, notification: &Notification) -> String {
+    let file_name = notification.file.file_name().unwrap_or_default().to_string_lossy();
+    let languages: Vec<&str> = notification.subtitles.keys().map(String::as_str).collect();
+    let subtitles: Vec<String> = notification.subtitles.values().map(|path| path.display().to_string()).collect();
+    let values = [
+        ("{{event}}", notification.event.to_string()),
+        ("{{file}}", notification.file.display().to_string()),
+        ("{{file_name}}", file_name.into_owned()),
+        ("{{languages}}", languages.join(", ")),
+        ("{{subtitles}}", subtitles.join(", ")),
+        ("{{error}}", notification.error.clone().unwrap_or_default()),
+    ];
+
+    let mut body = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        body.push_str(&rest[..start]);
+        let tail = &rest[start..];
+        match values.iter().find(|(placeholder, _)| tail.starts_with(placeholder)) {
+            Some((placeholder, value)) => {
+                body.push_str(&escape(value));
+                rest = &tail[placeholder.len()..];
+            }
+            None => {
+                body.push('{');
+                rest = &tail[1..];
+            }
+        }
+    }
+    body.push_str(rest);
+    body
+}
+
+/// `value` escaped for a JSON string, without the quotes
+fn escape(value: &str) -> String {
+    let quoted = serde_json::to_string(value).unwrap_or_default();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notification_bodies() {
+        let mut subtitles = HashMap::new();
+        let events = vec![
+            ProgressEvent::FileStarted { file: "e01.mkv".into(), languages: vec!["ja".into()] },
+            ProgressEvent::LanguageCompleted { file: "e01.mkv".into(), language: "ja".into(), subtitles: "e01_ja.srt".into() },
+            ProgressEvent::FileCompleted { file: "e01.mkv".into() },
+            ProgressEvent::FileFailed { file: "/videos/e02.mkv".into(), error: "model \"x\" not found".into() },
+        ];
+        let notifications: Vec<Notification> = events.into_iter().filter_map(|event| notification(&mut subtitles, event)).collect();
+        assert_eq!(notifications.len(), 2);
+        assert!(subtitles.is_empty());
+
+        let config = WebhookConfig { url: Some("http://localhost:9/hook".to_string()), ..WebhookConfig::default() };
+        let webhook = Webhook::new(&config).unwrap();
+        assert_eq!(
+            webhook.body(&notifications[0]),
+            r#"{"event":"file_completed","file":"e01.mkv","subtitles":{"ja":"e01_ja.srt"}}"#
+        );
+
+        let config = WebhookConfig { template: Some(r#"{"text": "{{file_name}}: {{event}} {{error}}"}"#.to_string()), ..config };
+        let webhook = Webhook::new(&config).unwrap();
+        assert_eq!(webhook.body(&notifications[1]), r#"{"text": "e02.mkv: file_failed model \"x\" not found"}"#);
+
+        assert!(Webhook::new(&WebhookConfig::default()).is_none());
+    }
+
+    #[test]
+    fn test_placeholders_in_values() {
+        let notification = Notification {
+            event: "file_failed",
+            file: "/videos/{{error}}.mkv".into(),
+            subtitles: BTreeMap::new(),
+            error: Some("cannot read {{file}}".to_string()),
+        };
+        let body = render("{{{file_name}}} {{error}} {{unknown}}", &notification);
+        assert_eq!(body, "{{{error}}.mkv} cannot read {{file}} {{unknown}}");
+    }
+}
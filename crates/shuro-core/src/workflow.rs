@@ -0,0 +1,2270 @@
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use futures::{StreamExt, TryStreamExt};
+use tokio::fs;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+
+use crate::align;
+use crate::cache;
+use crate::cancel::Cancellation;
+use crate::comparison::{ComparisonReport, flag_segment};
+use crate::disk::{SpaceRequirement, check_free_space};
+use crate::config::{Config, ErrorPolicy, GpuScheduling, Tone, TranscriberConfig, TranslateConfig, TranslationMode};
+use crate::episode::EpisodeInfo;
+use crate::naming::{batch_output_dir, forced_name, output_name, up_to_date_languages};
+use crate::job::{JobProgress, RETRY_LIST, write_retry_list};
+use crate::error::{Result, ShuroError};
+use crate::transcribe::{TranscriberTrait, TranscriberRegistry, TuneResult, forward_segments};
+use crate::translate::{BackendRegistry, BaseTranslator, bounded_in_order, Retranslator, Translator, TranslatorRegistry, TranslationMetrics, TranslationServices, backend};
+use crate::translate::notes::{notes_path, write_notes, NoteGenerator};
+use crate::translate::glossary::load_glossary_file;
+use crate::translate::retranslate::record_glossary;
+use crate::translate::work::{WorkContext, lookup_synopsis, summarize_transcription};
+use crate::translate::sizing::fit_to_model;
+use crate::subtitle::{SUBTITLE_EXTENSIONS, SubtitleFormat, clip_transcription, generate_srt, match_subtitle_files, parse_srt, read_srt, read_subtitles, write_ass, write_subtitles};
+use crate::manifest::{OutputRecord, ProcessingManifest, TimingRecord};
+use crate::partial::{PartialOutput, partial_path};
+use crate::preflight::{self, PreflightReport};
+use crate::plan::{FilePlan, PlannedStep, ProcessingPlan};
+use crate::control::BatchControl;
+use crate::progress::{ChannelObserver, FileProgress, ProgressEvent, ProgressSender, Stage, WorkflowObserver};
+use crate::hallucination::HallucinationReport;
+use crate::readability::conform;
+use crate::report::{FileSummary, LanguageSummary};
+use crate::retry::RetryPolicy;
+use crate::cost::CostTracker;
+use crate::segmentation::{normalize_segments, normalize_stream};
+use crate::diarization::{diarize, label_speakers, label_stream};
+use crate::metadata::{HALLUCINATION, SPEAKER};
+use crate::delivery::{DeliveryOutput, DeliveryTagger, mark_deliveries};
+use crate::shots::snap_to_shots;
+use crate::timeline::substitute_texts;
+use crate::transcript::{read_transcript, write_transcript};
+use crate::style::{SubtitleStyle, selected_style};
+use crate::temp;
+use crate::bilingual::stack_source;
+use crate::forced::forced_subtitles;
+use crate::locale::normalize_transcription;
+use crate::media::{MediaInfo, MediaProcessorTrait, MediaProcessorFactory, SubtitleStream, SubtitleTrack, flag_tracks, same_language, streams_in_language, text_stream_in_language};
+use crate::quality::{QualityValidator, Transcription, TranscriptionSegment};
+
+pub struct Workflow {
+    config: Config,
+    transcriber: Box<dyn TranscriberTrait>,
+    media: Box<dyn MediaProcessorTrait>,
+    translators: TranslatorRegistry,
+    /// Endpoints, backend, cost tracker and metrics shared by the translators
+    services: TranslationServices,
+    retry: Arc<RetryPolicy>,
+    observers: Vec<Arc<dyn WorkflowObserver>>,
+    control: Option<BatchControl>,
+}
+
+impl Workflow {
+    /// Create a workflow using the built-in implementations and plugins from config
+    pub fn new(config: Config) -> Result<Self> {
+        let transcribers = TranscriberRegistry::with_builtin()
+            .register_external(&config.plugins.transcribers);
+        let translators = TranslatorRegistry::with_builtin()
+            .register_external(&config.plugins.translators);
+        Self::with_registries(config, transcribers, translators, BackendRegistry::with_builtin())
+    }
+
+    /// Create a workflow that resolves transcriber, translator and translation provider names
+    /// against the given registries
+    pub fn with_registries(
+        config: Config,
+        transcribers: TranscriberRegistry,
+        translators: TranslatorRegistry,
+        backends: BackendRegistry,
+    ) -> Result<Self> {
+        let workflow = Self::assemble(config, transcribers, translators, backends)?;
+
+        // Check dependencies
+        workflow.media.check_availability()?;
+
+        Ok(workflow)
+    }
+
+    /// Create a workflow for subtitle files only, which needs neither ffmpeg nor whisper models
+    pub fn for_subtitles(config: Config) -> Result<Self> {
+        let transcribers = TranscriberRegistry::with_builtin()
+            .register_external(&config.plugins.transcribers);
+        let translators = TranslatorRegistry::with_builtin()
+            .register_external(&config.plugins.translators);
+        Self::assemble(config, transcribers, translators, BackendRegistry::with_builtin())
+    }
+
+    pub(crate) fn assemble(
+        mut config: Config,
+        transcribers: TranscriberRegistry,
+        translators: TranslatorRegistry,
+        backends: BackendRegistry,
+    ) -> Result<Self> {
+        let validator = QualityValidator::from_config(&config.quality);
+        
+        // Fail on an unknown style preset before any work is done
+        selected_style(&config.media)?;
+        if config.media.burn_in && config.media.multi_track.enabled {
+            return Err(ShuroError::Config(
+                "media.burn_in and media.multi_track burn in one language or mux them all; enable only one".to_string()
+            ));
+        }
+
+        // A custom tone needs its instruction, in general or for the language
+        let languages = std::iter::once("").chain(config.translate.languages.keys().map(String::as_str));
+        for language in languages {
+            let translate = config.translate.for_language(language);
+            if translate.tone == Tone::Custom && translate.tone_instruction.is_none() {
+                return Err(ShuroError::Config(format!(
+                    "translate{}: tone = \"Custom\" needs a tone_instruction",
+                    if language.is_empty() { String::new() } else { format!(".languages.{}", language) }
+                )));
+            }
+        }
+
+        config.translate.length_validation = config.quality.length.clone();
+        config.translate.delivery_hints = config.delivery.enabled && config.delivery.hint_translator;
+        config.translate.speaker_hints = config.diarization.enabled && config.diarization.hint_translator;
+        config.quality.readability.apply_overrides(&config.translate.languages);
+        if let Some(path) = config.translate.glossary_file.clone() {
+            let terms = load_glossary_file(&path)?;
+            info!("📖 {} glossary terms loaded from {}", terms.len(), path);
+            config.translate.glossary.extend(terms);
+        }
+        let retry = Arc::new(RetryPolicy::new(&config.retry));
+        let cost = Arc::new(CostTracker::new(&config.cost));
+        let services = TranslationServices::new(&config.translate, &backends, retry.clone(), cost)?;
+
+        let transcriber_config = TranscriberConfig {
+            ffmpeg_path: config.media.binary_path.clone(),
+            ffprobe_path: config.media.ffprobe_path.clone(),
+            source_language: config.translate.source_language.clone(),
+            cpu_while_translating: config.resources.gpu_scheduling == GpuScheduling::CpuWhisper,
+            temp_dir: config.temp_dir.clone(),
+            ..config.transcriber.clone()
+        };
+        let transcriber = transcribers.create(transcriber_config, validator, retry.clone())?;
+        let media = MediaProcessorFactory::create_processor(config.media.clone());
+
+        Ok(Self {
+            config,
+            transcriber,
+            media,
+            translators,
+            services,
+            retry,
+            observers: Vec::new(),
+            control: None,
+        })
+    }
+
+    /// Report the progress of video processing to `sender` too (see progress.rs)
+    pub fn with_progress(mut self, sender: ProgressSender) -> Self {
+        self.observers.push(Arc::new(ChannelObserver(sender)));
+        self
+    }
+
+    /// Call `observer` with every progress event too (see progress.rs)
+    pub fn with_observer(mut self, observer: impl WorkflowObserver + 'static) -> Self {
+        self.observers.push(Arc::new(observer));
+        self
+    }
+
+    /// Let `control` pause batches and skip their current file (see control.rs)
+    pub fn with_control(mut self, control: BatchControl) -> Self {
+        self.control = Some(control);
+        self
+    }
+
+    /// Configuration of the workflow, with the settings derived from it when it was built
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Latency and token counts of the model requests of every run of the workflow
+    pub fn metrics(&self) -> &Arc<TranslationMetrics> {
+        &self.services.metrics
+    }
+
+    /// Count the model usage of the next run from zero, with the whole `cost.budget`; for a
+    /// workflow kept for many runs, as `shuro serve` does
+    pub fn start_run(&self) {
+        self.services.cost.start_run();
+    }
+
+    pub fn transcriber(&self) -> &dyn TranscriberTrait {
+        self.transcriber.as_ref()
+    }
+
+    pub fn media(&self) -> &dyn MediaProcessorTrait {
+        self.media.as_ref()
+    }
+
+    /// Translator for one target language, as the workflow creates them: its models checked on
+    /// the backend and its configuration sized to the model's context
+    pub async fn translator(&self) -> Result<Box<dyn Translator>> {
+        let translate_config = self.prepare_translation().await?;
+        self.translators.create(translate_config, self.services.clone())
+    }
+
+    fn report(&self, event: ProgressEvent) {
+        for observer in &self.observers {
+            observer.on_event(&event);
+        }
+    }
+
+    fn report_stage(&self, video_path: &Path, stage: Stage) {
+        self.report(ProgressEvent::StageStarted { file: video_path.to_path_buf(), stage });
+    }
+
+    /// Report how processing a video ended
+    fn report_outcome(&self, video_path: &Path, result: &Result<()>) {
+        let file = video_path.to_path_buf();
+        self.report(match result {
+            Ok(()) => ProgressEvent::FileCompleted { file },
+            Err(e) => ProgressEvent::FileFailed { file, error: e.to_string() },
+        });
+    }
+
+    /// Process a single video file with subtitle translation
+    pub async fn process_single_file<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        input_path: P,
+        target_languages: &[String],
+        output_dir: Option<Q>,
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        let input_path = input_path.as_ref();
+        info!("Processing single file: {}", input_path.display());
+
+        // Validate input file
+        if !input_path.exists() {
+            return Err(ShuroError::FileNotFound(input_path.display().to_string()));
+        }
+
+        // Determine output directory
+        let output_dir = match output_dir {
+            Some(dir) => dir.as_ref().to_path_buf(),
+            None => input_path.parent()
+                .ok_or_else(|| ShuroError::Config("Cannot determine output directory".to_string()))?
+                .to_path_buf(),
+        };
+
+        // Create output directory if it doesn't exist
+        fs::create_dir_all(&output_dir).await?;
+
+        if self.skip_up_to_date(input_path, &output_dir, target_languages, None).await? {
+            return Ok(());
+        }
+
+        // Process the file
+        let result = self.process_video_file(input_path, &output_dir, target_languages, None, cancel).await;
+        self.report_outcome(input_path, &result);
+        self.services.metrics.log_summary();
+        cache::enforce_limits(&self.config);
+        result
+    }
+
+    /// Process all video files in a directory
+    ///
+    /// Each file gets its own child token and, when `file_timeout` is set, its own deadline.
+    /// Progress is checkpointed per file and language (see job.rs); with `resume`, files and
+    /// languages an earlier run of the same batch finished are skipped. A failed file stops the
+    /// batch or is listed at its end, as `batch.on_error` says.
+    pub async fn process_directory<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        input_dir: P,
+        target_languages: &[String],
+        output_dir: Option<Q>,
+        cancel: &Cancellation,
+        file_timeout: Option<Duration>,
+        resume: bool,
+    ) -> Result<()> {
+        let input_dir = input_dir.as_ref();
+        info!("Processing directory: {}", input_dir.display());
+
+        if !input_dir.is_dir() {
+            return Err(ShuroError::Config("Input path is not a directory".to_string()));
+        }
+
+        // Determine output directory
+        let output_dir = match output_dir {
+            Some(dir) => dir.as_ref().to_path_buf(),
+            None => input_dir.to_path_buf(),
+        };
+
+        // Create output directory if it doesn't exist
+        fs::create_dir_all(&output_dir).await?;
+
+        let video_files = find_video_files(input_dir);
+        info!("Found {} video files to process", video_files.len());
+
+        // Find a missing model or unreachable endpoint before hours of work depend on it
+        let batch_files = self.config.preflight.batch_files;
+        if batch_files > 0 && video_files.len() >= batch_files {
+            info!("🩺 Running preflight checks before a batch of {} videos", video_files.len());
+            let report = self.preflight(target_languages, cancel).await?;
+            for line in report.render().lines() {
+                info!("{}", line);
+            }
+            report.into_result()?;
+        }
+
+        let state_dir = Path::new(&self.config.state_dir);
+        let mut progress = JobProgress::open(state_dir, input_dir, &output_dir, target_languages, resume).await?;
+        info!("📌 Batch job {}, progress saved in {}", progress.state.id, progress.path().display());
+        let policy = self.config.batch.on_error;
+        let retry_list = output_dir.join(RETRY_LIST);
+        let mut failures = Vec::new();
+
+        // Process each video file
+        let total = video_files.len();
+        for video_path in video_files {
+            let languages = progress.remaining(&video_path, target_languages);
+            if languages.is_empty() {
+                info!("⏭️ Skipping {}, finished in an earlier run", video_path.display());
+                self.report(ProgressEvent::FileSkipped { file: video_path.clone() });
+                continue;
+            }
+            if languages.len() < target_languages.len() {
+                info!("Resuming {} for {}", video_path.display(), languages.join(", "));
+            }
+            let file_output_dir = batch_output_dir(self.config.media.output_placement, input_dir, &output_dir, &video_path);
+            if self.skip_up_to_date(&video_path, &file_output_dir, &languages, Some(&mut progress)).await? {
+                continue;
+            }
+
+            if let Some(control) = &self.control
+                && control.is_paused()
+            {
+                info!("⏸️ Batch paused before {}", video_path.display());
+                self.report(ProgressEvent::Paused);
+                control.wait_while_paused(cancel).await?;
+                info!("▶️ Batch resumed");
+                self.report(ProgressEvent::Resumed);
+            }
+
+            let file_cancel = match &self.control {
+                Some(control) => control.start_file(cancel),
+                None => cancel.child(),
+            };
+            let file_cancel = match file_timeout {
+                Some(timeout) => file_cancel.with_timeout(timeout),
+                None => file_cancel,
+            };
+            let result = match fs::create_dir_all(&file_output_dir).await {
+                Ok(()) => self.process_video_file(&video_path, &file_output_dir, &languages, Some(&mut progress), &file_cancel).await,
+                Err(e) => Err(e.into()),
+            };
+            let skipped = self.control.as_ref().is_some_and(|control| control.finish_file());
+            if skipped && result.is_err() && !cancel.is_cancelled() {
+                info!("⏭️ Skipped {} on request", video_path.display());
+                self.report(ProgressEvent::FileSkipped { file: video_path.clone() });
+                continue;
+            }
+            self.report_outcome(&video_path, &result);
+            match result {
+                Ok(_) => info!("Successfully processed: {}", video_path.display()),
+                Err(e) if cancel.is_cancelled() => {
+                    warn!("Batch cancelled while processing {}", video_path.display());
+                    self.services.metrics.log_summary();
+                    return Err(e);
+                }
+                Err(e) => {
+                    warn!("Failed to process {}: {}", video_path.display(), e);
+                    progress.fail(&video_path, &e.to_string()).await?;
+                    if policy == ErrorPolicy::Abort {
+                        warn!("Aborting the batch at the first failure (batch.on_error = Abort)");
+                        self.services.metrics.log_summary();
+                        return Err(e);
+                    }
+                    failures.push((video_path, e.to_string()));
+                    if policy == ErrorPolicy::Quarantine {
+                        let files: Vec<&Path> = failures.iter().map(|(file, _)| file.as_path()).collect();
+                        write_retry_list(&retry_list, &files).await?;
+                    }
+                    // Every further video would fail the same way
+                    if matches!(e, ShuroError::BudgetExceeded(_)) {
+                        warn!("Aborting the batch: {}", e);
+                        self.services.metrics.log_summary();
+                        return Err(e);
+                    }
+                }
+            }
+        }
+
+        if !failures.is_empty() {
+            warn!("{} of {} videos failed:", failures.len(), total);
+            for (file, error) in &failures {
+                warn!("  {}: {}", file.display(), error);
+            }
+            if policy == ErrorPolicy::Quarantine {
+                warn!("🚧 Failed videos listed in {}", retry_list.display());
+            }
+        } else if policy == ErrorPolicy::Quarantine && fs::try_exists(&retry_list).await? {
+            // The videos an earlier run listed went through this time
+            fs::remove_file(&retry_list).await?;
+        }
+
+        self.services.metrics.log_summary();
+        cache::enforce_limits(&self.config);
+
+        Ok(())
+    }
+
+    /// Plan processing a single video file without running whisper, the translator or ffmpeg
+    pub async fn plan_single_file<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        input_path: P,
+        target_languages: &[String],
+        output_dir: Option<Q>,
+        cancel: &Cancellation,
+    ) -> Result<ProcessingPlan> {
+        let input_path = input_path.as_ref();
+        if !input_path.exists() {
+            return Err(ShuroError::FileNotFound(input_path.display().to_string()));
+        }
+        let output_dir = match output_dir {
+            Some(dir) => dir.as_ref().to_path_buf(),
+            None => input_path.parent()
+                .ok_or_else(|| ShuroError::Config("Cannot determine output directory".to_string()))?
+                .to_path_buf(),
+        };
+
+        let file = self.plan_video_file(input_path, &output_dir, target_languages, cancel).await?;
+        Ok(ProcessingPlan { files: vec![file] })
+    }
+
+    /// Plan processing all video files in a directory without running whisper, the translator
+    /// or ffmpeg
+    pub async fn plan_directory<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        input_dir: P,
+        target_languages: &[String],
+        output_dir: Option<Q>,
+        cancel: &Cancellation,
+    ) -> Result<ProcessingPlan> {
+        let input_dir = input_dir.as_ref();
+        if !input_dir.is_dir() {
+            return Err(ShuroError::Config("Input path is not a directory".to_string()));
+        }
+        let output_dir = match output_dir {
+            Some(dir) => dir.as_ref().to_path_buf(),
+            None => input_dir.to_path_buf(),
+        };
+
+        let mut plan = ProcessingPlan::default();
+        for video_path in find_video_files(input_dir) {
+            let file_output_dir = batch_output_dir(self.config.media.output_placement, input_dir, &output_dir, &video_path);
+            plan.files.push(self.plan_video_file(&video_path, &file_output_dir, target_languages, cancel).await?);
+        }
+        info!("📝 Planned {} video files", plan.files.len());
+        Ok(plan)
+    }
+
+    /// Plan of one video; the reason it cannot be processed becomes the plan's error, only
+    /// cancellation fails
+    async fn plan_video_file(&self, video_path: &Path, output_dir: &Path, target_languages: &[String], cancel: &Cancellation) -> Result<FilePlan> {
+        match self.plan_steps(video_path, output_dir, target_languages, cancel).await {
+            Ok(plan) => Ok(plan),
+            Err(e) if cancel.is_cancelled() => Err(e),
+            Err(e) => Ok(FilePlan::failed(video_path.to_path_buf(), e.to_string())),
+        }
+    }
+
+    async fn plan_steps(&self, video_path: &Path, output_dir: &Path, target_languages: &[String], cancel: &Cancellation) -> Result<FilePlan> {
+        let video_stem = video_path.file_stem()
+            .ok_or_else(|| ShuroError::Config("Invalid video filename".to_string()))?
+            .to_string_lossy();
+        let episode = EpisodeInfo::parse(&video_stem);
+
+        let media_info = self.media.probe(video_path).await?;
+        let needs_audio = self.config.transcriber.transcript.is_none() && !self.config.transcriber.prefer_embedded_subs;
+        media_info.check_input(video_path, needs_audio, self.config.transcriber.audio_track)?;
+        let mut plan = FilePlan { file: video_path.to_path_buf(), duration: media_info.duration, ..FilePlan::default() };
+        let estimate = media_info.duration.map(ProcessingPlan::estimate_segments).unwrap_or_default();
+
+        // The same choice of transcript source as `process_video_file`
+        let source_language = &self.config.translate.source_language;
+        let embedded = match self.config.transcriber.prefer_embedded_subs && self.config.transcriber.transcript.is_none() {
+            true => text_stream_in_language(&self.media.subtitle_tracks(video_path).await?, source_language).cloned(),
+            false => None,
+        };
+        let mut segments = None;
+        let mut transcribes = false;
+        if let Some(path) = &self.config.transcriber.transcript {
+            let transcription = read_transcript(Path::new(path)).await?.into_transcription();
+            segments = Some(transcription.segments.len());
+            plan.steps.push(PlannedStep::run("read transcript", path.clone()));
+        } else if let Some(stream) = embedded {
+            plan.steps.push(PlannedStep::run("extract subtitles", format!("embedded track {} ({})", stream.index, stream.codec)));
+        } else {
+            transcribes = true;
+            let model = format!("{:?} with {}", self.config.transcriber.mode, self.config.transcriber.transcribe_model);
+            match self.transcriber.get_cached_audio(video_path).await? {
+                Some(audio_path) => {
+                    plan.steps.push(PlannedStep::cached("extract audio", audio_path.display().to_string()));
+                    match self.transcriber.cached_transcription(&audio_path, cancel).await? {
+                        Some(transcription) => {
+                            segments = Some(transcription.segments.len());
+                            plan.steps.push(PlannedStep::cached("transcribe", model));
+                        }
+                        None => plan.steps.push(PlannedStep::run("transcribe", model)),
+                    }
+                }
+                None => {
+                    plan.steps.push(PlannedStep::run("extract audio", String::new()));
+                    plan.steps.push(PlannedStep::run("transcribe", model));
+                }
+            }
+        }
+        plan.segments = segments.unwrap_or(estimate);
+        plan.estimated = segments.is_none();
+
+        if transcribes && self.config.diarization.enabled {
+            plan.steps.push(PlannedStep::run("diarize", String::new()));
+        }
+        if self.config.shots.enabled {
+            plan.steps.push(PlannedStep::run("detect shot changes", String::new()));
+        }
+
+        let styled = selected_style(&self.config.media)?.is_some() || self.config.media.write_ass;
+        let multi_track = self.config.media.multi_track.enabled;
+        let up_to_date = up_to_date_languages(&self.config.media, video_path, output_dir, target_languages);
+        for target_lang in &up_to_date {
+            plan.steps.push(PlannedStep::cached(format!("translate {}", target_lang), "outputs newer than the video"));
+        }
+        let target_languages: Vec<String> = target_languages.iter().filter(|lang| !up_to_date.contains(lang)).cloned().collect();
+        for target_lang in &target_languages {
+            plan.steps.push(PlannedStep::run(format!("translate {}", target_lang), self.config.translate.model.clone()));
+            let output_name = output_name(&self.config.media, &video_stem, target_lang, episode.as_ref());
+            plan.outputs.push(output_dir.join(format!("{}.srt", output_name)));
+            if styled {
+                plan.outputs.push(output_dir.join(format!("{}.ass", output_name)));
+            }
+            if !multi_track {
+                let video = output_dir.join(format!("{}.mp4", output_name));
+                check_not_input(video_path, &video)?;
+                plan.steps.push(PlannedStep::run(format!("embed {}", target_lang), video.display().to_string()));
+                plan.outputs.push(video);
+            }
+        }
+        if multi_track && !target_languages.is_empty() {
+            let output_name = output_name(&self.config.media, &video_stem, "multi", episode.as_ref());
+            let video = output_dir.join(format!("{}.mkv", output_name));
+            check_not_input(video_path, &video)?;
+            plan.steps.push(PlannedStep::run("embed", format!("{} tracks into {}", target_languages.len(), video.display())));
+            plan.outputs.push(video);
+        }
+        plan.outputs.push(ProcessingManifest::path(output_dir, &video_stem));
+        Ok(plan)
+    }
+
+    async fn process_video_file<P: AsRef<Path>>(
+        &self,
+        video_path: P,
+        output_dir: P,
+        target_languages: &[String],
+        mut progress: Option<&mut JobProgress>,
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        let video_path = video_path.as_ref();
+        let output_dir = output_dir.as_ref();
+
+        let video_stem = video_path.file_stem()
+            .ok_or_else(|| ShuroError::Config("Invalid video filename".to_string()))?
+            .to_string_lossy();
+
+        let episode = EpisodeInfo::parse(&video_stem);
+        if let Some(episode) = &episode {
+            info!("📺 {} {}", episode.series.as_deref().unwrap_or("Episode"), episode.code());
+        }
+
+        // Fail on inputs that cannot be processed before any work is done
+        let media_info = self.media.probe(video_path).await?;
+        let needs_audio = self.config.transcriber.transcript.is_none() && !self.config.transcriber.prefer_embedded_subs;
+        media_info.check_input(video_path, needs_audio, self.config.transcriber.audio_track)?;
+
+        let started = Instant::now();
+        self.retry.start_file();
+        self.services.cost.start_file();
+        self.report(ProgressEvent::FileStarted { file: video_path.to_path_buf(), languages: target_languages.to_vec() });
+
+        // Languages whose outputs from an earlier run are newer than the video are kept
+        let up_to_date = up_to_date_languages(&self.config.media, video_path, output_dir, target_languages);
+        for target_lang in &up_to_date {
+            self.skip_language(video_path, target_lang, "up to date", progress.as_deref_mut()).await?;
+        }
+        let target_languages: Vec<String> = target_languages.iter().filter(|lang| !up_to_date.contains(lang)).cloned().collect();
+        let target_languages = &target_languages[..];
+
+        // Step 1: Use an imported transcript or an embedded subtitle track, or get or extract audio (with caching)
+        let embedded = if let Some(path) = &self.config.transcriber.transcript {
+            Some(self.imported_transcription(Path::new(path)).await?)
+        } else if self.config.transcriber.prefer_embedded_subs {
+            self.embedded_transcription(video_path, cancel).await?
+        } else {
+            None
+        };
+        let cached_audio = match embedded {
+            Some(_) => None,
+            None => self.transcriber.get_cached_audio(video_path).await?,
+        };
+        let audio_cache_dir = (embedded.is_none() && cached_audio.is_none())
+            .then(|| PathBuf::from(&self.config.transcriber.cache_dir));
+        self.check_disk_space(video_path, audio_cache_dir.as_deref(), Some(output_dir), target_languages.len()).await?;
+
+        let audio_cached = cached_audio.is_some();
+        let audio_path = if embedded.is_some() {
+            None
+        } else {
+            Some(match cached_audio {
+                Some(cached_path) => {
+                    info!("Using cached audio file");
+                    cached_path
+                }
+                None => {
+                    info!("Extracting audio from video directly to cache");
+                    self.report_stage(video_path, Stage::ExtractingAudio);
+                    self.transcriber.extract_and_cache_audio(video_path, cancel).await?
+                }
+            })
+        };
+
+        let mut timings = TimingRecord { audio_extraction: started.elapsed().as_secs_f64(), ..Default::default() };
+        let cached_transcription = match &audio_path {
+            Some(audio_path) => self.transcriber.cached_transcription(audio_path, cancel).await.ok().flatten(),
+            None => None,
+        };
+        let transcription_cached = cached_transcription.is_some();
+        // Only identify the language on the audio when nothing else tells it
+        let cached_language = cached_transcription.map(|t| t.language);
+        let known = known_language(
+            self.config.transcriber.language.as_deref(),
+            embedded.as_ref().map(|e| e.best_transcription.language.as_str()),
+            cached_language.as_deref(),
+        );
+        let detected = match (&known, &audio_path) {
+            (None, Some(audio_path)) => match self.transcriber.detect_language(audio_path, cancel).await {
+                Ok(detected) => detected,
+                Err(e) if cancel.is_cancelled() => return Err(e),
+                Err(e) => {
+                    warn!("Language identification failed, detecting the language with the transcription: {}", e);
+                    None
+                }
+            },
+            _ => None,
+        };
+        let detected = detected.filter(|language| is_language_code(language));
+        if let Some(language) = &detected {
+            info!("🗣️ Source language identified on the audio: {}", language);
+        }
+        let known_language = known.or(detected);
+
+        // Check Ollama availability before transcription starts
+        let mut translate_config = self.prepare_translation().await?;
+        translate_config.work_context = self.work_context(video_path, true).await;
+        let (remaining, mut skipped) = split_source_language(target_languages, known_language.as_deref());
+        for target_lang in &skipped {
+            self.skip_language(video_path, target_lang, "source language", progress.as_deref_mut()).await?;
+        }
+        let target_languages = &remaining[..];
+        if let Some(language) = &known_language {
+            translate_config.source_language = language.clone();
+        }
+
+        // Translators that need the summary of the transcription are created once it is known
+        let summarizing = self.summarizes();
+        let mut translators = if summarizing {
+            Vec::new()
+        } else {
+            self.language_translators(&translate_config, target_languages).await?
+        };
+        let mut output_names = Vec::new();
+        let mut partials = Vec::new();
+        for target_lang in target_languages {
+            let output_name = output_name(&self.config.media, &video_stem, target_lang, episode.as_ref());
+            let partial_path = partial_path(output_dir, &output_name);
+            partials.push(PartialOutput::open(partial_path, target_lang, self.config.translate.partial_flush_segments).await);
+            output_names.push(output_name);
+        }
+
+        // Delivery hints are measured on the extracted audio as segments arrive
+        let mut delivery = match &audio_path {
+            Some(audio_path) if self.config.delivery.enabled => DeliveryTagger::open(audio_path, &self.config.delivery).await?,
+            _ => None,
+        };
+
+        // Speakers are found before transcription, so streamed segments are labelled as they arrive
+        let speakers = match &audio_path {
+            Some(audio_path) if self.config.diarization.enabled => Some({
+                self.report_stage(video_path, Stage::Diarizing);
+                diarize(audio_path, &self.config.diarization, cancel).await?
+            }),
+            _ => None,
+        };
+
+        // Step 2 + 3: Transcribe with tuning while translating segments as they arrive
+        let step = Instant::now();
+        self.report_stage(video_path, Stage::Transcribing);
+        let file_progress = FileProgress::new(
+            video_path,
+            media_info.duration,
+            embedded.as_ref().map(|e| e.best_transcription.segments.len()),
+        );
+        let (segment_tx, segment_rx) = mpsc::unbounded_channel();
+        let normalizing = embedded.is_none() && self.config.segmentation.enabled;
+        let (mut tune_result, mut translated) = if let Some(tune_result) = embedded {
+            forward_segments(Some(&segment_tx), &tune_result.best_transcription);
+            drop(segment_tx);
+            if summarizing {
+                translate_config.work_context = self.summarized_work(&translate_config, &tune_result.best_transcription, cancel).await?;
+                translators = self.language_translators(&translate_config, target_languages).await?;
+            }
+            let translated = self.translate_stream(&file_progress, segment_rx, &mut translators, &mut partials, delivery.as_mut(), cancel).await?;
+            (tune_result, translated)
+        } else if summarizing || self.config.resources.gpu_scheduling == GpuScheduling::Serialized {
+            let audio_path = audio_path.expect("audio is extracted without embedded subtitles");
+            // Free the GPU memory held by Ollama for whisper; streamed segments queue up until whisper exits
+            if self.config.resources.gpu_scheduling == GpuScheduling::Serialized && !self.uses_external_translator() {
+                info!("🎛️ Serialized GPU scheduling: unloading translation models before transcription");
+                self.services.backend.unload(&self.backend_models()).await?;
+            }
+            info!("Starting transcription with hallucination detection and tempo tuning");
+            let tune_result = self.transcriber.tune_transcription_streaming(&audio_path, segment_tx, cancel).await?;
+            if summarizing {
+                translate_config.work_context = self.summarized_work(&translate_config, &tune_result.best_transcription, cancel).await?;
+                translators = self.language_translators(&translate_config, target_languages).await?;
+            }
+            let segment_rx = self.normalize_stream(label_stream(segment_rx, speakers.clone()));
+            let translated = self.translate_stream(&file_progress, segment_rx, &mut translators, &mut partials, delivery.as_mut(), cancel).await?;
+            (tune_result, translated)
+        } else {
+            let audio_path = audio_path.expect("audio is extracted without embedded subtitles");
+            info!("Starting transcription with hallucination detection and tempo tuning");
+            let segment_rx = self.normalize_stream(label_stream(segment_rx, speakers.clone()));
+            tokio::try_join!(
+                self.transcriber.tune_transcription_streaming(&audio_path, segment_tx, cancel),
+                self.translate_stream(&file_progress, segment_rx, &mut translators, &mut partials, delivery.as_mut(), cancel),
+            )?
+        };
+        
+        timings.transcription_and_translation = step.elapsed().as_secs_f64();
+        // Segments refused for the budget kept their source text; such outputs are not written
+        self.services.cost.check()?;
+
+        // Without the language beforehand (transcribers that only tell it with the transcription),
+        // translations into the language whisper detected were streamed alongside transcription;
+        // they are not written
+        let detected = &tune_result.best_transcription.language;
+        if known_language.is_none() && is_language_code(detected) {
+            info!("🗣️ Source language detected by whisper: {}", detected);
+            translate_config.source_language = detected.clone();
+            for i in (0..translators.len()).rev() {
+                if same_language(translators[i].0, detected) {
+                    let (target_lang, _) = translators.remove(i);
+                    translated.remove(i);
+                    output_names.remove(i);
+                    partials.remove(i).finish().await?;
+                    self.skip_language(video_path, target_lang, "source language", progress.as_deref_mut()).await?;
+                    skipped.push(target_lang.to_string());
+                }
+            }
+        } else {
+            info!("🗣️ Source language: {}", translate_config.source_language);
+        }
+
+        // The timeline gets the speakers and cues of the streamed segments
+        if let Some(speakers) = &speakers {
+            speakers.label_all(&mut tune_result.best_transcription);
+        }
+        if normalizing {
+            let transcribed = tune_result.best_transcription.segments.len();
+            tune_result.best_transcription = normalize_segments(&tune_result.best_transcription, &self.config.segmentation);
+            info!("✂️ Normalized {} transcribed segments to {}", transcribed, tune_result.best_transcription.segments.len());
+        }
+
+        // Transcribers flag with the default rules; flag again with the configured ones
+        let rules = &self.config.quality.hallucination;
+        tune_result.best_transcription.flag_hallucinations(rules);
+        let report = HallucinationReport::new(&tune_result.best_transcription, rules);
+        if rules.report {
+            let report_path = output_dir.join(format!("{}.hallucinations.json", video_stem));
+            fs::write(&report_path, serde_json::to_string_pretty(&report)?).await?;
+            info!("👻 Wrote hallucination report ({} flagged segments): {}", report.segments.len(), report_path.display());
+        }
+        let hallucinations = report.segments.len();
+        for segment in report.segments {
+            self.report(ProgressEvent::QualityRejected { file: video_path.to_path_buf(), segment });
+        }
+
+        // Display comprehensive tuned transcription results
+        self.display_tuned_results(&tune_result);
+        self.report(ProgressEvent::Transcribed {
+            file: video_path.to_path_buf(),
+            segments: tune_result.best_transcription.segments.len(),
+        });
+
+        let step = Instant::now();
+        self.report_stage(video_path, Stage::Writing);
+        // Cue timing is settled once, so every language shows its cues at the same moments
+        let mut timeline = tune_result.best_transcription.clone();
+        if self.config.shots.enabled {
+            let shots = self.media.detect_shot_changes(video_path, self.config.shots.scene_threshold, cancel).await?;
+            let moved = snap_to_shots(&mut timeline, &shots, &self.config.shots);
+            info!("🎬 Snapped {} cue times to shot changes", moved);
+        }
+        // Each language writes its outputs from the same shared transcription and timeline
+        let source = Arc::new(tune_result.best_transcription.clone());
+        let timeline = Arc::new(timeline);
+        let languages = translators.iter().zip(translated).zip(output_names).zip(&partials);
+        let (video_stem, episode) = (&video_stem, &episode);
+        let mut finished = bounded_in_order(self.language_concurrency(translators.len()), languages, |language| {
+            let ((((target_lang, translator), translated_segments), output_name), partial) = language;
+            let (source, timeline, translate_config) = (source.clone(), timeline.clone(), &translate_config);
+            async move {
+                let mut transcription = substitute_texts(&timeline, translated_segments)?;
+                self.normalize_locale(&source, &mut transcription, &translate_config.source_language, target_lang);
+
+                // Step 4: Generate SRT file
+                let srt_path = output_dir.join(format!("{}.srt", output_name));
+                let labelled = label_speakers(&transcription, &self.config.diarization.label_format);
+                let readable = self.conform_readability(&labelled, target_lang);
+                let srt = mark_deliveries(&readable, &self.config.delivery, DeliveryOutput::Srt);
+                generate_srt(&stack_source(&source, &srt, &self.config.bilingual, target_lang), &srt_path).await?;
+                partial.finish().await?;
+                let notes = self.write_translation_notes(
+                    translate_config, &source, &transcription, target_lang, &srt_path, cancel,
+                ).await?;
+
+                let forced = self.write_forced_subtitles(
+                    &source,
+                    &mark_deliveries(&transcription, &self.config.delivery, DeliveryOutput::Forced),
+                    target_lang,
+                    output_dir,
+                    &forced_name(&self.config.media, &output_name, video_stem, target_lang, episode.as_ref()),
+                ).await?;
+
+                // Styled ASS copy for players and editors that read ASS
+                let style = selected_style(&self.config.media)?
+                    .or_else(|| self.config.media.write_ass.then(SubtitleStyle::default));
+                let styled = match style {
+                    Some(style) => {
+                        let ass_path = output_dir.join(format!("{}.ass", output_name));
+                        let ass = mark_deliveries(&readable, &self.config.delivery, DeliveryOutput::Ass);
+                        write_ass(&stack_source(&source, &ass, &self.config.bilingual, target_lang), &ass_path, &style).await?;
+                        Some(ass_path)
+                    }
+                    None => None,
+                };
+
+                // Step 5: Embed subtitles into video, unless every language goes into one file
+                let output_video_path = if self.config.media.multi_track.enabled {
+                    None
+                } else {
+                    let output_video_path = output_dir.join(format!("{}.mp4", output_name));
+                    check_not_input(video_path, &output_video_path)?;
+                    self.report_stage(video_path, Stage::Embedding);
+                    self.embed_track(video_path, &srt_path, target_lang, &output_video_path, cancel).await?;
+                    Some(output_video_path)
+                };
+
+                info!("Completed processing for language: {}", target_lang);
+                let output = OutputRecord {
+                    language: target_lang.to_string(),
+                    subtitles: srt_path,
+                    video: output_video_path,
+                    notes,
+                    forced,
+                    styled,
+                };
+                let comparison = (target_lang.to_string(), transcription, translator.retries());
+                Ok::<_, ShuroError>((output, comparison, translator.cache_hits()))
+            }
+        });
+        let mut outputs = Vec::new();
+        let mut comparisons = Vec::new();
+        let mut cache_hits = HashMap::new();
+        while let Some(result) = finished.next().await {
+            let (output, comparison, hits) = result?;
+            cache_hits.insert(output.language.clone(), hits);
+            outputs.push(output);
+            comparisons.push(comparison);
+        }
+        drop(finished);
+
+        if self.config.media.multi_track.enabled {
+            let output_name = output_name(&self.config.media, video_stem, "multi", episode.as_ref());
+            let video = output_dir.join(format!("{}.mkv", output_name));
+            check_not_input(video_path, &video)?;
+            self.report_stage(video_path, Stage::Embedding);
+            self.embed_all_tracks(video_path, &source, &outputs, output_dir, &video, cancel).await?;
+            for output in &mut outputs {
+                output.video = Some(video.clone());
+            }
+        }
+        // Languages are done once their video is written
+        for output in &outputs {
+            if let Some(progress) = progress.as_deref_mut() {
+                progress.complete(video_path, &output.language).await?;
+            }
+            self.report(ProgressEvent::LanguageCompleted {
+                file: video_path.to_path_buf(),
+                language: output.language.clone(),
+                subtitles: output.subtitles.clone(),
+            });
+        }
+
+        let comparison = ComparisonReport::new(&self.config.quality.length, &self.config.translate.glossary, &source, &comparisons);
+        if comparisons.len() > 1 {
+            let report_path = output_dir.join(format!("{}.comparison.md", video_stem));
+            fs::write(&report_path, comparison.render()).await?;
+            info!("📊 Wrote translation comparison ({} first for review): {}", comparison.languages[0].language, report_path.display());
+        }
+
+        if !self.uses_external_translator() {
+            record_glossary(&self.config.translate).await?;
+        }
+
+        // Step 6: Record how the outputs were produced
+        timings.output = step.elapsed().as_secs_f64();
+        timings.total = started.elapsed().as_secs_f64();
+        let summary = FileSummary {
+            file: video_path.to_path_buf(),
+            timings: timings.clone(),
+            audio_cached,
+            transcription_cached,
+            segments: source.segments.len(),
+            hallucinations,
+            usage: self.services.cost.file_usage(),
+            source_language: translate_config.source_language.clone(),
+            skipped,
+            up_to_date,
+            languages: target_languages.iter()
+                .filter_map(|language| comparison.languages.iter().find(|l| &l.language == language))
+                .map(|quality| LanguageSummary::new(quality, &source, cache_hits.get(&quality.language).copied().unwrap_or(0)))
+                .collect(),
+        };
+        let manifest = ProcessingManifest::new(video_path, &self.config, &translate_config, &tune_result, timings, outputs).await?;
+        let manifest_path = ProcessingManifest::path(output_dir, video_stem);
+        manifest.save(&manifest_path).await?;
+        info!("🧾 Wrote processing manifest: {}", manifest_path.display());
+        self.report(ProgressEvent::FileSummarized { summary });
+
+        // Note: We don't clean up the cached audio file - it stays in cache for reuse
+
+        Ok(())
+    }
+
+    /// Fail early when the locations lack the space extracting the audio of `video_path` (into
+    /// `audio_dir`) and writing `videos` copies of it (into `video_dir`) need
+    async fn check_disk_space(&self, video_path: &Path, audio_dir: Option<&Path>, video_dir: Option<&Path>, videos: usize) -> Result<()> {
+        if !self.config.resources.check_disk_space || (audio_dir.is_none() && video_dir.is_none()) {
+            return Ok(());
+        }
+        let size = match self.media.media_size(video_path).await {
+            Ok(size) => size,
+            Err(e) => {
+                warn!("Skipping disk space check for {}: {}", video_path.display(), e);
+                return Ok(());
+            }
+        };
+
+        let mut requirements = Vec::new();
+        if let Some(dir) = audio_dir
+            && let Some(bytes) = size.audio_bytes() {
+            requirements.push(SpaceRequirement::new(dir, bytes, "extracted audio"));
+        }
+        if let Some(dir) = video_dir
+            && let Some(bytes) = size.video_bytes() {
+            requirements.push(SpaceRequirement::new(dir, bytes.saturating_mul(videos as u64), "output videos"));
+        }
+        check_free_space(&requirements, self.config.resources.disk_headroom_mb.saturating_mul(1024 * 1024))
+    }
+
+    /// Transcription taken from a text subtitle track in the source language embedded in the
+    /// video, `None` when it has no such track (or only an empty one)
+    async fn embedded_transcription(&self, video_path: &Path, cancel: &Cancellation) -> Result<Option<TuneResult>> {
+        let source_language = &self.config.translate.source_language;
+        let streams = self.media.subtitle_tracks(video_path).await?;
+        let Some(stream) = text_stream_in_language(&streams, source_language) else {
+            info!("No embedded {} text subtitle track, transcribing the audio", source_language);
+            return Ok(None);
+        };
+
+        let temp_dir = temp::temp_dir(Path::new(&self.config.temp_dir), "embedded_subtitles")
+            .map_err(|e| ShuroError::Media(format!("Failed to create temp directory: {}", e)))?;
+        let srt_path = temp_dir.path().join("embedded.srt");
+        self.media.extract_subtitle_track(video_path, stream.index, &srt_path, cancel).await?;
+        let transcription = read_srt(&srt_path, source_language).await?;
+        if transcription.segments.is_empty() {
+            warn!("Embedded subtitle track {} has no cues, transcribing the audio", stream.index);
+            return Ok(None);
+        }
+
+        info!("📜 Using embedded subtitle track {} ({}) as the {} transcription, skipping whisper",
+            stream.index, stream.codec, source_language);
+        Ok(Some(TuneResult {
+            best_transcription: transcription,
+            best_tempo: 100,
+            best_temperature: 0.0,
+            quality_score: 0.0,
+            all_attempts: Vec::new(),
+            tested_parameters: vec![format!("embedded subtitle track {}", stream.index)],
+        }))
+    }
+
+    /// Transcription read from a transcript file in place of the transcriber's
+    async fn imported_transcription(&self, path: &Path) -> Result<TuneResult> {
+        let transcription = read_transcript(path).await?.into_transcription();
+        info!("📜 Using {} segments of {} as the transcription, skipping whisper", transcription.segments.len(), path.display());
+        Ok(TuneResult {
+            best_transcription: transcription,
+            best_tempo: 100,
+            best_temperature: 0.0,
+            quality_score: 0.0,
+            all_attempts: Vec::new(),
+            tested_parameters: vec![format!("transcript {}", path.display())],
+        })
+    }
+
+    /// Number of target languages processed at once (`resources.max_parallel_languages`, 0 = all)
+    fn language_concurrency(&self, languages: usize) -> usize {
+        match self.config.resources.max_parallel_languages {
+            0 => languages,
+            limit => limit.min(languages),
+        }
+    }
+
+    /// Rewrite units, clock times, numbers and dates of a translation by the `locale` rules of
+    /// its language (see locale.rs)
+    fn normalize_locale(&self, source: &Transcription, translation: &mut Transcription, source_lang: &str, target_lang: &str) {
+        let changed = normalize_transcription(&self.config.locale, source, translation, source_lang, target_lang);
+        if changed > 0 {
+            info!("📏 Normalized units, times, numbers or dates in {} {} cues", changed, target_lang);
+        }
+    }
+
+    /// Streamed segments merged and split as they arrive when `segmentation.enabled` is set
+    /// (see segmentation.rs)
+    fn normalize_stream(&self, segments: mpsc::UnboundedReceiver<TranscriptionSegment>) -> mpsc::UnboundedReceiver<TranscriptionSegment> {
+        if !self.config.segmentation.enabled {
+            return segments;
+        }
+        normalize_stream(segments, self.config.segmentation.clone())
+    }
+
+    /// `translation` with its cues brought within the readability limits of `target_lang` when
+    /// `quality.readability.enabled` is set (see readability.rs)
+    fn conform_readability(&self, translation: &Transcription, target_lang: &str) -> Transcription {
+        let config = &self.config.quality.readability;
+        if !config.enabled {
+            return translation.clone();
+        }
+        let (conformed, changes) = conform(translation, config, target_lang);
+        if !changes.is_empty() {
+            info!("📐 Readability of {} cues: {} re-wrapped, {} split, {} lengthened, {} still too fast to read",
+                  target_lang, changes.rewrapped, changes.split, changes.lengthened, changes.too_fast);
+        }
+        conformed
+    }
+
+    /// Write the forced-narrative subtitles of a translation as `{forced_name}.srt` when
+    /// `forced.enabled` is set, returning the file; videos without foreign lines get none
+    async fn write_forced_subtitles(
+        &self,
+        source: &Transcription,
+        translated: &Transcription,
+        target_language: &str,
+        output_dir: &Path,
+        forced_name: &str,
+    ) -> Result<Option<PathBuf>> {
+        if !self.config.forced.enabled {
+            return Ok(None);
+        }
+        let forced = forced_subtitles(source, translated, target_language);
+        if forced.segments.is_empty() {
+            info!("No foreign-language lines for a forced {} track", target_language);
+            return Ok(None);
+        }
+
+        let forced = self.conform_readability(&forced, target_language);
+        let path = output_dir.join(format!("{}.srt", forced_name));
+        generate_srt(&forced, &path).await?;
+        info!("🗯️ Wrote {} forced-narrative cues to {}", forced.segments.len(), path.display());
+        Ok(Some(path))
+    }
+
+    /// Write translator's notes on a translation next to its subtitle file when `translate.notes`
+    /// is enabled, returning the notes file
+    async fn write_translation_notes(
+        &self,
+        translate_config: &TranslateConfig,
+        source: &Transcription,
+        translated: &Transcription,
+        target_language: &str,
+        subtitle_path: &Path,
+        cancel: &Cancellation,
+    ) -> Result<Option<PathBuf>> {
+        let notes_config = &self.config.translate.notes;
+        if !notes_config.enabled {
+            return Ok(None);
+        }
+        if self.uses_external_translator() {
+            warn!("Translation notes are written with the Ollama translation model; skipping them for the external translator");
+            return Ok(None);
+        }
+
+        let notes = NoteGenerator::new(translate_config, &self.services)
+            .annotate(source, translated, target_language, cancel)
+            .await?;
+        let path = notes_path(subtitle_path, notes_config.format);
+        write_notes(&notes, &path, notes_config.format).await?;
+        Ok(Some(path))
+    }
+
+    /// Check the models the translator needs on its backend and size the translator configuration to the
+    /// model's context (external plugins talk to their own backends and get the configuration as is)
+    async fn prepare_translation(&self) -> Result<TranslateConfig> {
+        let mut translate_config = self.config.translate.clone();
+        if !self.uses_external_translator() {
+            self.services.backend.check_models(&self.backend_models()).await?;
+            fit_to_model(&mut translate_config, self.services.backend.as_ref()).await?;
+        }
+        Ok(translate_config)
+    }
+
+    /// Report that `target_lang` is not translated, for `reason`: the video being in that language
+    /// already ("source language") or its outputs being newer than the video ("up to date")
+    async fn skip_language(&self, video_path: &Path, target_lang: &str, reason: &str, progress: Option<&mut JobProgress>) -> Result<()> {
+        info!("⏭️ Not translating {} into {} ({})", video_path.display(), target_lang, reason);
+        if let Some(progress) = progress {
+            progress.complete(video_path, target_lang).await?;
+        }
+        self.report(ProgressEvent::LanguageSkipped {
+            file: video_path.to_path_buf(),
+            language: target_lang.to_string(),
+            reason: reason.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Skip `video_path` when the outputs of every language are newer than the video (see
+    /// naming.rs); whether it was skipped
+    async fn skip_up_to_date(&self, video_path: &Path, output_dir: &Path, target_languages: &[String], progress: Option<&mut JobProgress>) -> Result<bool> {
+        let current = up_to_date_languages(&self.config.media, video_path, output_dir, target_languages);
+        if current.is_empty() || current.len() < target_languages.len() {
+            return Ok(false);
+        }
+        info!("⏭️ Skipping {}, its outputs are newer than the video", video_path.display());
+        if let Some(progress) = progress {
+            for target_lang in &current {
+                progress.complete(video_path, target_lang).await?;
+            }
+        }
+        self.report(ProgressEvent::FileSkipped { file: video_path.to_path_buf() });
+        Ok(true)
+    }
+
+    /// Translators for every target language, in order
+    async fn language_translators<'a>(
+        &self,
+        translate_config: &TranslateConfig,
+        target_languages: &'a [String],
+    ) -> Result<Vec<(&'a str, Box<dyn Translator>)>> {
+        let mut translators = Vec::new();
+        for target_lang in target_languages {
+            let language_config = self.language_translation(translate_config, target_lang).await?;
+            translators.push((target_lang.as_str(), self.translators.create(language_config, self.services.clone())?));
+        }
+        Ok(translators)
+    }
+
+    /// Whether transcriptions are summarized before they are translated
+    fn summarizes(&self) -> bool {
+        self.config.translate.work.summarize && !self.uses_external_translator()
+    }
+
+    /// Work context of `translate_config` with the summary of `transcription`; without it when the
+    /// summary fails
+    async fn summarized_work(
+        &self,
+        translate_config: &TranslateConfig,
+        transcription: &Transcription,
+        cancel: &Cancellation,
+    ) -> Result<Option<WorkContext>> {
+        let work = translate_config.work_context.clone();
+        match summarize_transcription(translate_config, transcription, &self.services, cancel).await {
+            Ok(Some(summary)) => {
+                info!("🧭 Summarized {} segments as background for translation", transcription.segments.len());
+                Ok(Some(WorkContext { summary: Some(summary), ..work.unwrap_or_default() }))
+            }
+            Ok(None) => Ok(work),
+            Err(e @ (ShuroError::Cancelled | ShuroError::BudgetExceeded(_))) => Err(e),
+            Err(e) => {
+                warn!("Transcription summary failed, translating without it: {}", e);
+                Ok(work)
+            }
+        }
+    }
+
+    /// Configuration of the translator for `target_lang`: `base` with the language's overrides,
+    /// sized again to the model when the language uses another one
+    async fn language_translation(&self, base: &TranslateConfig, target_lang: &str) -> Result<TranslateConfig> {
+        let mut config = base.for_language(target_lang);
+        if config.model != base.model && !self.uses_external_translator() {
+            fit_to_model(&mut config, self.services.backend.as_ref()).await?;
+        }
+        Ok(config)
+    }
+
+    /// Title and synopsis of the work in `path` from the configuration, its container tags
+    /// (`probe_media`) and its name, looked up with the translation model when enabled
+    async fn work_context(&self, path: &Path, probe_media: bool) -> Option<WorkContext> {
+        let config = &self.config.translate.work;
+        let mut context = WorkContext { title: config.title.clone(), synopsis: config.description.clone(), ..WorkContext::default() };
+        if config.detect {
+            if probe_media {
+                match self.media.format_tags(path).await {
+                    Ok(tags) => context = context.or(WorkContext::from_tags(&tags)),
+                    Err(e) => debug!("Container tags of {} unavailable: {}", path.display(), e),
+                }
+            }
+            if let Some(stem) = path.file_stem() {
+                context = context.or(WorkContext::from_file_name(&stem.to_string_lossy()));
+            }
+        }
+
+        if config.llm_lookup && context.title.is_some() && context.synopsis.is_none() && !self.uses_external_translator() {
+            match lookup_synopsis(&self.config.translate, &context, &self.services).await {
+                Ok(Some(synopsis)) => context.synopsis = Some(synopsis),
+                Ok(None) => info!("The translation model does not recognize \"{}\"", context.summary()),
+                Err(e) => warn!("Title lookup failed: {}", e),
+            }
+        }
+
+        if context.is_empty() {
+            return None;
+        }
+        info!("🎬 Translating \"{}\"{}", context.summary(), if context.synopsis.is_some() { " with synopsis" } else { "" });
+        Some(context)
+    }
+
+    /// Models the configured translator uses on its backend, including those of single target
+    /// languages; the semantic cache's embedding model is among them when the backend is Ollama,
+    /// which serves the embeddings
+    fn backend_models(&self) -> Vec<&str> {
+        let config = &self.config.translate;
+        let mut models = vec![config.model.as_str()];
+        for model in config.languages.values().filter_map(|overrides| overrides.model.as_deref()) {
+            if !models.contains(&model) {
+                models.push(model);
+            }
+        }
+        if config.semantic_cache.enabled && config.provider == backend::OLLAMA {
+            models.push(&config.semantic_cache.embedding_model);
+        }
+        if let Some(judge_model) = &config.judge_model
+            && (matches!(config.mode, TranslationMode::Context)
+                || matches!(config.mode, TranslationMode::Adaptive) && matches!(config.adaptive.escalation, TranslationMode::Context)) {
+            models.push(judge_model);
+        }
+        models
+    }
+
+    /// Translate streamed segments in chunks of `stream_chunk_size` for every target language
+    ///
+    /// Returns the translated segments per translator, in the order they were received. Progress
+    /// is flushed to each translator's partial output, which also seeds chunks it already holds.
+    /// With a delivery tagger, every chunk is tagged before it is translated.
+    async fn translate_stream(
+        &self,
+        file: &FileProgress,
+        mut segments: mpsc::UnboundedReceiver<TranscriptionSegment>,
+        translators: &mut [(&str, Box<dyn Translator>)],
+        partials: &mut [PartialOutput],
+        mut delivery: Option<&mut DeliveryTagger>,
+        cancel: &Cancellation,
+    ) -> Result<Vec<Vec<TranscriptionSegment>>> {
+        let chunk_size = self.config.translate.stream_chunk_size;
+        let mut translated = vec![Vec::new(); translators.len()];
+        let mut pending = Vec::new();
+        let mut tagged = 0;
+
+        while let Some(segment) = segments.recv().await {
+            pending.push(segment);
+            if chunk_size > 0 && pending.len() >= chunk_size {
+                let mut chunk = std::mem::take(&mut pending);
+                if let Some(tagger) = delivery.as_deref_mut() {
+                    tagged += tagger.tag(&mut chunk).await?;
+                }
+                self.translate_chunk(file, chunk, translators, partials, &mut translated, cancel).await?;
+            }
+        }
+
+        if !pending.is_empty() {
+            if let Some(tagger) = delivery.as_deref_mut() {
+                tagged += tagger.tag(&mut pending).await?;
+            }
+            self.translate_chunk(file, pending, translators, partials, &mut translated, cancel).await?;
+        }
+        if delivery.is_some() {
+            info!("🎭 {} segments shouted, whispered or sung", tagged);
+        }
+
+        Ok(translated)
+    }
+
+    /// Translate one chunk of segments into every target language
+    async fn translate_chunk(
+        &self,
+        file: &FileProgress,
+        chunk: Vec<TranscriptionSegment>,
+        translators: &mut [(&str, Box<dyn Translator>)],
+        partials: &mut [PartialOutput],
+        translated: &mut [Vec<TranscriptionSegment>],
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        info!("Translating {} streamed segments", chunk.len());
+        let position = chunk.last().map_or(0.0, |s| s.end);
+
+        // Target languages translate concurrently, each with its own translator and request budget,
+        // so their requests spread across the Ollama endpoints
+        let concurrency = self.language_concurrency(translators.len());
+        let outputs = translators.iter_mut().zip(partials.iter_mut()).zip(translated.iter_mut());
+        let mut translations = bounded_in_order(concurrency, outputs, |(((target_lang, translator), partial_output), output)| {
+            let mut partial = Transcription {
+                text: chunk.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" "),
+                segments: chunk.clone(),
+                language: self.config.translate.source_language.clone(),
+            };
+            async move {
+                let before = output.len();
+                match partial_output.seeded(output.len(), &partial.segments) {
+                    Some(seeded) => {
+                        info!("Reusing {} {} cues from the partial subtitles", seeded.len(), target_lang);
+                        output.extend(seeded);
+                    }
+                    None => {
+                        translator.translate_transcription(&mut partial, target_lang, None, cancel).await?;
+                        output.extend(partial.segments);
+                    }
+                }
+                info!("🌐 {}: {} segments translated", target_lang, output.len());
+                for segment in &output[before..] {
+                    self.report(ProgressEvent::SegmentTranslated {
+                        file: file.file.clone(),
+                        language: target_lang.to_string(),
+                        start: segment.start,
+                        end: segment.end,
+                        text: segment.text.clone(),
+                    });
+                }
+                self.report(file.translated(target_lang, output.len(), position));
+                partial_output.flush(output).await
+            }
+        });
+        while let Some(result) = translations.next().await {
+            result?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether the configured translator talks to its own service (an external plugin or a
+    /// translator registered without the model backend) rather than the workflow's backend
+    fn uses_external_translator(&self) -> bool {
+        !self.translators.uses_backend(&self.config.translate)
+    }
+
+    /// Check the translation endpoints and models, translate a test sentence into each target
+    /// language and transcribe a test clip (see preflight.rs)
+    pub async fn preflight(&self, target_languages: &[String], cancel: &Cancellation) -> Result<PreflightReport> {
+        let mut report = PreflightReport::default();
+        if !self.uses_external_translator() {
+            self.services.backend.preflight(&self.backend_models(), &mut report).await;
+        }
+
+        // The test sentence is English whatever the source language of the videos
+        let translate_config = TranslateConfig { source_language: "en".to_string(), ..self.config.translate.clone() };
+        for target_lang in target_languages {
+            let outcome = self.test_translation(&translate_config.for_language(target_lang), target_lang, cancel).await;
+            if cancel.is_cancelled() {
+                return Err(ShuroError::Cancelled);
+            }
+            report.record(format!("Translation into {}", target_lang), outcome);
+        }
+
+        let outcome = self.test_transcription(cancel).await;
+        if cancel.is_cancelled() {
+            return Err(ShuroError::Cancelled);
+        }
+        report.record(format!("Transcriber ({})", self.config.transcriber.implementation), outcome);
+        Ok(report)
+    }
+
+    /// Translate the preflight test sentence, failing when the translation is flagged
+    async fn test_translation(&self, config: &TranslateConfig, target_lang: &str, cancel: &Cancellation) -> Result<String> {
+        let translation = if self.uses_external_translator() {
+            let mut translator = self.translators.create(config.clone(), self.services.clone())?;
+            let mut transcription = parse_srt(&format!("1\n00:00:00,000 --> 00:00:03,000\n{}\n", preflight::TEST_SENTENCE), "en")?;
+            translator.translate_transcription(&mut transcription, target_lang, None, cancel).await?;
+            transcription.segments.remove(0).text
+        } else {
+            // Straight to the model: translators would answer from the translation cache
+            let translator = BaseTranslator::with_services(config.clone(), self.services.clone());
+            cancel.run(translator.translate_text(preflight::TEST_SENTENCE, target_lang, None)).await?
+        };
+
+        match flag_segment(&config.length_validation, &config.glossary, preflight::TEST_SENTENCE, &translation, "en", target_lang) {
+            Some(flag) => Err(ShuroError::Translation(format!("{}: \"{}\"", flag.label(), translation.trim()))),
+            None => Ok(format!("\"{}\"", translation.trim())),
+        }
+    }
+
+    /// Transcribe the preflight test clip
+    async fn test_transcription(&self, cancel: &Cancellation) -> Result<String> {
+        let temp_dir = temp::temp_dir(Path::new(&self.config.temp_dir), "preflight")?;
+        let clip = temp_dir.path().join("test_clip.wav");
+        fs::write(&clip, preflight::test_clip()).await?;
+
+        let started = Instant::now();
+        let transcription = self.transcriber.transcribe(&clip, Some(&self.config.translate.source_language), cancel).await?;
+        Ok(format!("transcript of the test clip produced in {:.1}s ({} segments)", started.elapsed().as_secs_f64(), transcription.segments.len()))
+    }
+
+    /// Extract audio from video file
+    pub async fn extract_audio<P: AsRef<Path>>(
+        &self,
+        video_path: P,
+        audio_path: P,
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        let video_path = video_path.as_ref();
+        let audio_path = audio_path.as_ref();
+        
+        // Check if we have cached audio first
+        if let Ok(Some(cached_path)) = self.transcriber.get_cached_audio(video_path).await {
+            info!("Using cached audio file, copying to requested location");
+            fs::copy(&cached_path, audio_path).await?;
+            return Ok(());
+        }
+        
+        // Extract audio normally
+        self.check_disk_space(video_path, Some(audio_path.parent().unwrap_or(Path::new("."))), None, 0).await?;
+        self.media.extract_audio(video_path, audio_path, cancel).await?;
+        
+        // Note: Audio caching is handled by extract_and_cache_audio method when needed
+        
+        Ok(())
+    }
+
+    /// Transcribe audio file to text
+    pub async fn transcribe_audio<P: AsRef<Path>>(
+        &self,
+        audio_path: P,
+        output_path: P,
+        language: Option<&str>,
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        let audio_path = audio_path.as_ref();
+        let output_path = output_path.as_ref();
+        
+        let transcription = self.transcriber.transcribe(audio_path, language, cancel).await?;
+        
+        // Generate SRT file
+        generate_srt(&transcription, output_path).await?;
+        
+        Ok(())
+    }
+
+    /// Write the transcription of a video, or the cues of a subtitle file, as a transcript file
+    /// (see transcript.rs); returns the number of segments written
+    pub async fn export_transcript<P: AsRef<Path>>(&self, input_path: P, output_path: P, cancel: &Cancellation) -> Result<usize> {
+        let input_path = input_path.as_ref();
+        let source_language = &self.config.translate.source_language;
+
+        let is_subtitle = input_path.extension()
+            .is_some_and(|e| SUBTITLE_EXTENSIONS.iter().any(|ext| e.eq_ignore_ascii_case(ext)));
+        let (transcription, duration, model) = if is_subtitle {
+            let (transcription, format) = read_subtitles(input_path, source_language, self.config.media.strict_subtitles).await?;
+            (transcription, None, Some(format.extension().to_string()))
+        } else {
+            let audio_path = self.transcriber.extract_and_cache_audio(input_path, cancel).await?;
+            let transcription = self.transcriber.tune_transcription(&audio_path, cancel).await?.best_transcription;
+            let duration = self.media.media_size(input_path).await.ok().and_then(|size| size.duration);
+            (transcription, duration, Some(self.config.transcriber.transcribe_model.clone()))
+        };
+
+        write_transcript(&transcription, duration, model, output_path.as_ref()).await?;
+        Ok(transcription.segments.len())
+    }
+
+    /// Check a transcript file and optionally convert it to a subtitle file in the format of the
+    /// output's extension; returns the transcription read
+    pub async fn import_transcript<P: AsRef<Path>>(&self, input_path: P, output_path: Option<P>) -> Result<Transcription> {
+        let transcription = read_transcript(input_path).await?.into_transcription();
+        if let Some(output_path) = output_path {
+            let output_path = output_path.as_ref();
+            let format = SubtitleFormat::detect(output_path, "")?;
+            match format {
+                SubtitleFormat::Ass => write_ass(&transcription, output_path, &selected_style(&self.config.media)?.unwrap_or_default()).await?,
+                _ => write_subtitles(&transcription, output_path, format).await?,
+            }
+        }
+        Ok(transcription)
+    }
+
+    /// Merge the fragments and split the overlong cues of a subtitle file by the `segmentation`
+    /// limits, writing the result in the format of the output's extension; returns the number of
+    /// cues read and written
+    pub async fn normalize_subtitles<P: AsRef<Path>>(&self, input_path: P, output_path: P) -> Result<(usize, usize)> {
+        let output_path = output_path.as_ref();
+        let (subtitles, _) = read_subtitles(input_path, &self.config.translate.source_language, self.config.media.strict_subtitles).await?;
+        let normalized = normalize_segments(&subtitles, &self.config.segmentation);
+        match SubtitleFormat::detect(output_path, "")? {
+            SubtitleFormat::Ass => write_ass(&normalized, output_path, &selected_style(&self.config.media)?.unwrap_or_default()).await?,
+            format => write_subtitles(&normalized, output_path, format).await?,
+        }
+        Ok((subtitles.segments.len(), normalized.segments.len()))
+    }
+
+    /// Retime an existing subtitle file onto the transcription of a video without re-translating it
+    pub async fn align_subtitles<P: AsRef<Path>>(
+        &self,
+        video_path: P,
+        subtitle_path: P,
+        output_path: P,
+        subtitle_language: &str,
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        let video_path = video_path.as_ref();
+        let output_path = output_path.as_ref();
+
+        let subtitles = read_srt(subtitle_path, subtitle_language).await?;
+        let audio_path = self.transcriber.extract_and_cache_audio(video_path, cancel).await?;
+        let transcript = self.transcriber.tune_transcription(&audio_path, cancel).await?.best_transcription;
+
+        let aligned = align::align_subtitles(&subtitles, &transcript, &self.config.align)?;
+        info!("Aligned {} subtitle cues to {} transcript segments", aligned.segments.len(), transcript.segments.len());
+
+        generate_srt(&aligned, output_path).await?;
+        Ok(())
+    }
+
+    /// Re-translate cached segments shown in existing subtitle files and patch the files in place
+    ///
+    /// With `changed_terms_only`, only segments mentioning glossary terms changed since the last run
+    /// are re-translated. Returns the number of patched cues.
+    pub async fn retranslate_subtitles<P: AsRef<Path>>(
+        &self,
+        subtitle_paths: &[P],
+        target_language: &str,
+        changed_terms_only: bool,
+        cancel: &Cancellation,
+    ) -> Result<usize> {
+        if self.uses_external_translator() {
+            return Err(ShuroError::Config(
+                "Re-translation works from the built-in translation cache and is not available for external translators".to_string()
+            ));
+        }
+        let config = self.config.translate.for_language(target_language);
+        let mut models = vec![config.model.as_str()];
+        if config.semantic_cache.enabled && config.provider == backend::OLLAMA {
+            models.push(&config.semantic_cache.embedding_model);
+        }
+        self.services.backend.check_models(&models).await?;
+
+        let mut subtitles = Vec::new();
+        for path in subtitle_paths {
+            subtitles.push(read_srt(path, target_language).await?);
+        }
+        let before: Vec<Transcription> = subtitles.clone();
+
+        let retranslator = Retranslator::new(config.clone(), self.services.clone());
+        let patched = retranslator.retranslate(&mut subtitles, target_language, changed_terms_only, cancel).await?;
+
+        for ((path, updated), original) in subtitle_paths.iter().zip(&subtitles).zip(&before) {
+            if updated.segments.iter().zip(&original.segments).any(|(a, b)| a.text != b.text) {
+                generate_srt(updated, path).await?;
+            }
+        }
+        info!("✏️ Patched {} cues in {} subtitle files", patched, subtitle_paths.len());
+        self.services.metrics.log_summary();
+        Ok(patched)
+    }
+
+    /// Translate subtitle files (SRT, WebVTT or ASS) into every target language, without any media
+    ///
+    /// Each translation is written next to its input, or into `output_dir`, as
+    /// `{stem}_{lang}.{ext}` in the input's format. ASS translations are written in the selected
+    /// style preset rather than the input's styles. Returns the written files.
+    pub async fn translate_subtitles<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        input_paths: &[P],
+        output_dir: Option<Q>,
+        target_languages: &[String],
+        cancel: &Cancellation,
+    ) -> Result<Vec<PathBuf>> {
+        let translate_config = self.prepare_translation().await?;
+        let source_language = &self.config.translate.source_language;
+        let style = selected_style(&self.config.media)?.unwrap_or_default();
+        let (skipped, target_languages): (Vec<String>, Vec<String>) =
+            target_languages.iter().cloned().partition(|target_lang| same_language(target_lang, source_language));
+        let target_languages = &target_languages[..];
+        for target_lang in &skipped {
+            info!("⏭️ Not translating into {}: the subtitles are already in that language", target_lang);
+        }
+
+        let mut written = Vec::new();
+        for input_path in input_paths {
+            let input_path = input_path.as_ref();
+            self.retry.start_file();
+            self.services.cost.start_file();
+            let (source, format) = read_subtitles(input_path, source_language, self.config.media.strict_subtitles).await?;
+            info!("📄 {} cues read from {}", source.segments.len(), input_path.display());
+
+            let stem = input_path.file_stem()
+                .ok_or_else(|| ShuroError::Config(format!("Invalid subtitle filename: {}", input_path.display())))?
+                .to_string_lossy();
+            let output_dir = match &output_dir {
+                Some(dir) => dir.as_ref().to_path_buf(),
+                None => input_path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            };
+            fs::create_dir_all(&output_dir).await?;
+
+            let mut translate_config = TranslateConfig {
+                work_context: self.work_context(input_path, false).await,
+                ..translate_config.clone()
+            };
+            if self.summarizes() {
+                translate_config.work_context = self.summarized_work(&translate_config, &source, cancel).await?;
+            }
+            // Languages translate concurrently from the same parsed input
+            let source = Arc::new(source);
+            let (stem, output_dir, translate_config, style) = (&stem, &output_dir, &translate_config, &style);
+            let languages = bounded_in_order(self.language_concurrency(target_languages.len()), target_languages, |target_lang| {
+                let source = source.clone();
+                async move {
+                    let language_config = self.language_translation(translate_config, target_lang).await?;
+                    let mut translator = self.translators.create(language_config, self.services.clone())?;
+                    let mut transcription = (*source).clone();
+                    translator.translate_transcription(&mut transcription, target_lang, None, cancel).await?;
+                    self.services.cost.check()?;
+                    // Every language keeps the input's cue times
+                    let mut transcription = if transcription.segments.len() == source.segments.len() {
+                        substitute_texts(&source, transcription.segments)?
+                    } else {
+                        warn!("The {} translation regrouped the cues; its timing differs from the input", target_lang);
+                        transcription
+                    };
+                    self.normalize_locale(&source, &mut transcription, &translate_config.source_language, target_lang);
+
+                    let name = output_name(&self.config.media, stem, target_lang, EpisodeInfo::parse(stem).as_ref());
+                    let output_path = output_dir.join(format!("{}.{}", name, format.extension()));
+                    let labelled = label_speakers(&transcription, &self.config.diarization.label_format);
+                    let readable = self.conform_readability(&labelled, target_lang);
+                    let stacked = stack_source(&source, &readable, &self.config.bilingual, target_lang);
+                    match format {
+                        SubtitleFormat::Ass => write_ass(&stacked, &output_path, style).await?,
+                        _ => write_subtitles(&stacked, &output_path, format).await?,
+                    }
+                    self.write_translation_notes(translate_config, &source, &transcription, target_lang, &output_path, cancel).await?;
+                    info!("Completed translation into {}: {}", target_lang, output_path.display());
+                    Ok::<_, ShuroError>(output_path)
+                }
+            });
+            written.extend(languages.try_collect::<Vec<_>>().await?);
+        }
+
+        if !self.uses_external_translator() {
+            record_glossary(&self.config.translate).await?;
+        }
+        self.services.metrics.log_summary();
+        cache::enforce_limits(&self.config);
+        Ok(written)
+    }
+
+    /// Embed subtitles into video file, as a soft track or burned in with `media.burn_in`
+    pub async fn embed_subtitles<P: AsRef<Path>>(
+        &self,
+        video_path: P,
+        subtitles_path: P,
+        output_path: P,
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        let video_path = video_path.as_ref();
+        let subtitles_path = subtitles_path.as_ref();
+        let output_path = output_path.as_ref();
+        
+        self.check_disk_space(video_path, None, Some(output_path.parent().unwrap_or(Path::new("."))), 1).await?;
+        // A `{video}_{lang}.srt` file names the language of its track
+        let video_stem = video_path.file_stem().unwrap_or_default().to_string_lossy();
+        let language = match_subtitle_files(&video_stem, &[subtitles_path.to_path_buf()])
+            .pop()
+            .map_or_else(|| "und".to_string(), |track| track.language);
+        self.embed_track(video_path, subtitles_path, &language, output_path, cancel).await
+    }
+
+    /// Put a subtitle file into a video: burned into the video stream with `media.burn_in`,
+    /// otherwise muxed as a soft subtitle track in `language`
+    async fn embed_track(
+        &self,
+        video_path: &Path,
+        subtitles_path: &Path,
+        language: &str,
+        output_path: &Path,
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        if self.config.media.burn_in {
+            return self.media.burn_subtitles(video_path, subtitles_path, output_path, cancel).await;
+        }
+        let track = SubtitleTrack { path: subtitles_path.to_path_buf(), language: language.to_string(), forced: false, default: false };
+        self.media.mux_subtitles(video_path, &[track], false, output_path, cancel).await
+    }
+
+    /// Mux the subtitles of every language of `outputs` into one Matroska file at `output_path`,
+    /// with the transcription as a track in the source language when `multi_track.include_source`
+    /// is set (written next to the outputs as `{output}.srt` for that language)
+    async fn embed_all_tracks(
+        &self,
+        video_path: &Path,
+        source: &Transcription,
+        outputs: &[OutputRecord],
+        output_dir: &Path,
+        output_path: &Path,
+        cancel: &Cancellation,
+    ) -> Result<()> {
+        let config = &self.config.media.multi_track;
+        let mut tracks = Vec::new();
+        if config.include_source {
+            let video_stem = video_path.file_stem().unwrap_or_default().to_string_lossy();
+            let episode = EpisodeInfo::parse(&video_stem);
+            let name = output_name(&self.config.media, &video_stem, &source.language, episode.as_ref());
+            let path = output_dir.join(format!("{}.srt", name));
+            generate_srt(source, &path).await?;
+            tracks.push(SubtitleTrack { path, language: source.language.clone(), forced: false, default: false });
+        }
+        for output in outputs {
+            tracks.push(SubtitleTrack { path: output.subtitles.clone(), language: output.language.clone(), forced: false, default: false });
+            if let Some(forced) = &output.forced {
+                tracks.push(SubtitleTrack { path: forced.clone(), language: output.language.clone(), forced: true, default: false });
+            }
+        }
+        let default_track = config.default_track.as_deref().or(outputs.first().map(|o| o.language.as_str()));
+        flag_tracks(&mut tracks, default_track, config.forced_track.as_deref());
+
+        info!("🎞️ Muxing {} subtitle tracks into {}", tracks.len(), output_path.display());
+        self.media.mux_subtitles(video_path, &tracks, false, output_path, cancel).await
+    }
+
+    /// Render the `window` (in seconds) of a video with subtitles burned in, to check styling and
+    /// sync before a full burn-in
+    ///
+    /// Without `subtitles_path`, the video's `{video}_{lang}.srt` or `{video}.{lang}.srt` file next
+    /// to it is used; without `output_path`, the clip is written as `{video}_{lang}_preview.mp4`
+    /// next to the video. Returns the clip path.
+    pub async fn preview_clip<P: AsRef<Path>>(
+        &self,
+        video_path: P,
+        subtitles_path: Option<P>,
+        language: &str,
+        window: Range<f64>,
+        output_path: Option<P>,
+        cancel: &Cancellation,
+    ) -> Result<PathBuf> {
+        let video_path = video_path.as_ref();
+        let (start, duration) = (window.start, window.end - window.start);
+        let video_dir = video_path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let video_stem = video_path.file_stem()
+            .ok_or_else(|| ShuroError::Config("Invalid video filename".to_string()))?
+            .to_string_lossy()
+            .to_string();
+
+        let subtitles_path = match subtitles_path {
+            Some(path) => path.as_ref().to_path_buf(),
+            None => {
+                let mut candidates = Vec::new();
+                let mut entries = fs::read_dir(if video_dir.as_os_str().is_empty() { Path::new(".") } else { &video_dir }).await?;
+                while let Some(entry) = entries.next_entry().await? {
+                    candidates.push(entry.path());
+                }
+                match_subtitle_files(&video_stem, &candidates)
+                    .into_iter()
+                    .find(|track| !track.forced && same_language(&track.language, language))
+                    .map(|track| track.path)
+                    .ok_or_else(|| ShuroError::Config(format!(
+                        "No {} subtitles found next to {}; pass --subtitles", language, video_path.display()
+                    )))?
+            }
+        };
+
+        let (transcription, _) = read_subtitles(&subtitles_path, language, self.config.media.strict_subtitles).await?;
+        let clip = clip_transcription(&transcription, start, duration);
+        if clip.segments.is_empty() {
+            warn!("No cues of {} fall within the preview window", subtitles_path.display());
+        }
+
+        let temp_dir = temp::temp_dir(Path::new(&self.config.temp_dir), "preview")
+            .map_err(|e| ShuroError::Media(format!("Failed to create temp directory: {}", e)))?;
+        let clip_subtitles = temp_dir.path().join("preview.srt");
+        generate_srt(&clip, &clip_subtitles).await?;
+
+        let output_path = match output_path {
+            Some(path) => path.as_ref().to_path_buf(),
+            None => video_dir.join(format!("{}_{}_preview.mp4", video_stem, language)),
+        };
+        self.media.render_preview(video_path, &clip_subtitles, start, duration, &output_path, cancel).await?;
+        info!("🎬 Preview clip with {} cues written to {}", clip.segments.len(), output_path.display());
+        Ok(output_path)
+    }
+
+    /// Mux matching subtitle files from `subs_dir` into every video in `videos_dir` as soft subtitles
+    ///
+    /// Subtitles match a video by name (`{video}_{lang}.srt` or `{video}.{lang}.srt`). Without an
+    /// output directory the videos are replaced in place. With `replace`, existing subtitle tracks
+    /// in the language of a new track are dropped. Returns the number of muxed videos.
+    pub async fn embed_batch<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        subs_dir: P,
+        videos_dir: P,
+        output_dir: Option<Q>,
+        replace: bool,
+        cancel: &Cancellation,
+    ) -> Result<usize> {
+        let (subs_dir, videos_dir) = (subs_dir.as_ref(), videos_dir.as_ref());
+        if !subs_dir.is_dir() || !videos_dir.is_dir() {
+            return Err(ShuroError::Config("Subtitle and video paths must be directories".to_string()));
+        }
+        let output_dir = output_dir.map(|dir| dir.as_ref().to_path_buf());
+        if let Some(dir) = &output_dir {
+            fs::create_dir_all(dir).await?;
+        }
+
+        let subtitle_paths: Vec<PathBuf> = WalkDir::new(subs_dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.path().to_path_buf())
+            .collect();
+
+        let mut muxed = 0;
+        for video_path in find_video_files(videos_dir) {
+            let Some(video_stem) = video_path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+                continue;
+            };
+            let tracks = match_subtitle_files(&video_stem, &subtitle_paths);
+            if tracks.is_empty() {
+                info!("No subtitles found for {}", video_path.display());
+                continue;
+            }
+
+            let output_path = output_dir.as_ref().zip(video_path.file_name()).map(|(dir, name)| dir.join(name));
+            let target = rewrite_target(&video_path, output_path.as_deref())?;
+            self.check_disk_space(&video_path, None, Some(target.parent().unwrap_or(Path::new("."))), 1).await?;
+            let result = self.media.mux_subtitles(&video_path, &tracks, replace, &target, cancel).await;
+            match finish_rewrite(&video_path, &target, result).await {
+                Ok(()) => {
+                    let languages: Vec<&str> = tracks.iter().map(|t| t.language.as_str()).collect();
+                    info!("🎬 Muxed {} into {}", languages.join(", "), video_path.display());
+                    muxed += 1;
+                }
+                Err(e) if cancel.is_cancelled() => return Err(e),
+                Err(e) => warn!("Failed to mux subtitles into {}: {}", video_path.display(), e),
+            }
+        }
+
+        info!("Muxed subtitles into {} videos", muxed);
+        Ok(muxed)
+    }
+
+    /// Container, streams and chapters of a media file
+    pub async fn inspect<P: AsRef<Path>>(&self, path: P) -> Result<MediaInfo> {
+        self.media.probe(path.as_ref()).await
+    }
+
+    /// List the subtitle tracks of a video
+    pub async fn list_subtitle_tracks<P: AsRef<Path>>(&self, video_path: P) -> Result<Vec<SubtitleStream>> {
+        self.media.subtitle_tracks(video_path.as_ref()).await
+    }
+
+    /// Remove the subtitle tracks in `language` from a video, in place unless `output_path` is given
+    ///
+    /// Returns the number of removed tracks.
+    pub async fn remove_subtitle_tracks<P: AsRef<Path>>(
+        &self,
+        video_path: P,
+        language: &str,
+        output_path: Option<P>,
+        cancel: &Cancellation,
+    ) -> Result<usize> {
+        let video_path = video_path.as_ref();
+        let streams = streams_in_language(&self.media.subtitle_tracks(video_path).await?, language);
+        if streams.is_empty() {
+            info!("No {} subtitle tracks in {}", language, video_path.display());
+            return Ok(0);
+        }
+
+        let target = rewrite_target(video_path, output_path.as_ref().map(|p| p.as_ref()))?;
+        let result = self.media.remove_subtitle_tracks(video_path, &streams, &target, cancel).await;
+        finish_rewrite(video_path, &target, result).await?;
+        info!("🗑️ Removed {} {} subtitle tracks from {}", streams.len(), language, video_path.display());
+        Ok(streams.len())
+    }
+
+    /// Display comprehensive tuned transcription results
+    fn display_tuned_results(&self, tune_result: &TuneResult) {
+        info!("");
+        info!("📊 TUNED TRANSCRIPTION RESULTS");
+        info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        info!("🎯 Best Configuration:");
+        info!("   • Optimal Tempo: {}%", tune_result.best_tempo);
+        info!("   • Final Segments: {}", tune_result.best_transcription.segments.len());
+        info!("   • Smoothness Score: {:.3} (lower = better)", tune_result.quality_score);
+        info!("   • Temperature: {:.1}", tune_result.best_temperature);
+
+        let segments = &tune_result.best_transcription.segments;
+        let flagged = segments.iter().filter(|s| s.metadata.contains(&HALLUCINATION)).count();
+        if flagged > 0 {
+            info!("   • Flagged as possible hallucinations: {} segments", flagged);
+        }
+        let speakers: BTreeSet<String> = segments.iter().filter_map(|s| s.metadata.get(&SPEAKER)).collect();
+        if !speakers.is_empty() {
+            info!("   • Speakers: {}", speakers.into_iter().collect::<Vec<_>>().join(", "));
+        }
+        
+        if tune_result.all_attempts.len() > 1 {
+            info!("");
+            info!("🔬 Exploration Summary:");
+            info!("   • Tested {} different configurations", tune_result.all_attempts.len());
+            
+            // Show all results
+            let mut sorted_attempts = tune_result.all_attempts.clone();
+            sorted_attempts.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+            
+            for (i, (tempo, score)) in sorted_attempts.iter().enumerate() {
+                let indicator = match i {
+                    0 => "🏆",
+                    1 => "🥈",
+                    2 => "🥉",
+                    _ => "  ",
+                };
+                info!("   {} Tempo {}%: {:.3}", indicator, tempo, score);
+            }
+        }
+        
+        info!("");
+        info!("✅ Transcription optimization complete!");
+        info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        info!("");
+    }
+}
+
+/// File a media operation rewriting `video_path` writes to: `output_path`, or a temporary
+/// file next to the video that replaces it afterwards
+fn rewrite_target(video_path: &Path, output_path: Option<&Path>) -> Result<PathBuf> {
+    if let Some(output_path) = output_path {
+        return Ok(output_path.to_path_buf());
+    }
+    let file_name = video_path.file_name()
+        .ok_or_else(|| ShuroError::Config("Invalid video filename".to_string()))?;
+    Ok(video_path.with_file_name(format!(".shuro-{}", file_name.to_string_lossy())))
+}
+
+/// Complete a rewrite: a temporary file replaces the video on success and is removed on failure
+async fn finish_rewrite(video_path: &Path, target: &Path, result: Result<()>) -> Result<()> {
+    let temporary = rewrite_target(video_path, None).is_ok_and(|t| t == target);
+    match result {
+        Ok(()) if temporary => Ok(fs::rename(target, video_path).await?),
+        Ok(()) => Ok(()),
+        Err(e) => {
+            if temporary {
+                let _ = fs::remove_file(target).await;
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Refuse to write an output video over its input, as a template without `{lang}` next to the
+/// source would
+fn check_not_input(video_path: &Path, output_path: &Path) -> Result<()> {
+    if output_path == video_path {
+        return Err(ShuroError::Config(format!(
+            "The output video would overwrite {}; include {{lang}} in media.output_template",
+            video_path.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Whether `language` names a language, rather than being empty or whisper's `auto`
+fn is_language_code(language: &str) -> bool {
+    !language.trim().is_empty() && !language.eq_ignore_ascii_case("auto")
+}
+
+/// Language of a video known without looking at its audio: the one given with `--source-lang`,
+/// that of the transcript used or that of the cached transcription, in that order
+fn known_language(forced: Option<&str>, transcript: Option<&str>, cached: Option<&str>) -> Option<String> {
+    [forced, transcript, cached].into_iter()
+        .flatten()
+        .find(|language| is_language_code(language))
+        .map(str::to_string)
+}
+
+/// Target languages to translate into and those skipped for being `source_language`
+fn split_source_language(target_languages: &[String], source_language: Option<&str>) -> (Vec<String>, Vec<String>) {
+    target_languages.iter()
+        .cloned()
+        .partition(|target_lang| !source_language.is_some_and(|language| same_language(target_lang, language)))
+}
+
+/// Video files under `dir`, recursively, in file name order
+fn find_video_files(dir: &Path) -> Vec<PathBuf> {
+    let video_extensions = ["mp4", "avi", "mov", "mkv", "wmv", "flv", "webm"];
+    WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| video_extensions.contains(&ext.to_lowercase().as_str())))
+        .map(|e| e.path().to_path_buf())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+    use crate::builder::Shuro;
+    use crate::disk::MediaSize;
+    use crate::media::{MediaCommand, parse_media_info};
+    use crate::translate::TranslationServices;
+
+    /// Media processor recording which operations were asked of it, failing to probe the file
+    /// named `failing`
+    #[derive(Default)]
+    struct StubMedia {
+        calls: Arc<Mutex<Vec<String>>>,
+        failing: Option<&'static str>,
+    }
+
+    impl StubMedia {
+        fn record(&self, call: &str) {
+            self.calls.lock().unwrap().push(call.to_string());
+        }
+    }
+
+    #[async_trait]
+    impl MediaProcessorTrait for StubMedia {
+        async fn burn_subtitles(&self, _video: &Path, _subtitles: &Path, _output: &Path, _cancel: &Cancellation) -> Result<()> {
+            self.record("burn");
+            Ok(())
+        }
+
+        async fn render_preview(&self, _video: &Path, _subtitles: &Path, _start: f64, _duration: f64, _output: &Path, _cancel: &Cancellation) -> Result<()> {
+            self.record("preview");
+            Ok(())
+        }
+
+        async fn mux_subtitles(&self, _video: &Path, _tracks: &[SubtitleTrack], _replace: bool, _output: &Path, _cancel: &Cancellation) -> Result<()> {
+            self.record("mux");
+            Ok(())
+        }
+
+        async fn detect_shot_changes(&self, _video: &Path, _threshold: f64, _cancel: &Cancellation) -> Result<Vec<f64>> {
+            Ok(Vec::new())
+        }
+
+        async fn probe(&self, video: &Path) -> Result<MediaInfo> {
+            if video.file_name().is_some_and(|name| Some(name) == self.failing.map(std::ffi::OsStr::new)) {
+                return Err(ShuroError::Media(format!("{} is corrupt", video.display())));
+            }
+            parse_media_info(r#"{"streams": [{"index": 0, "codec_type": "video", "codec_name": "h264"}], "format": {"duration": "4.0"}}"#)
+        }
+
+        async fn media_size(&self, video: &Path) -> Result<MediaSize> {
+            Err(ShuroError::Media(format!("no size for {}", video.display())))
+        }
+
+        async fn format_tags(&self, _video: &Path) -> Result<HashMap<String, String>> {
+            Ok(HashMap::new())
+        }
+
+        async fn subtitle_tracks(&self, _video: &Path) -> Result<Vec<SubtitleStream>> {
+            Ok(Vec::new())
+        }
+
+        async fn remove_subtitle_tracks(&self, _video: &Path, _streams: &[usize], _output: &Path, _cancel: &Cancellation) -> Result<()> {
+            self.record("remove");
+            Ok(())
+        }
+
+        async fn extract_subtitle_track(&self, _video: &Path, _stream: usize, _output: &Path, _cancel: &Cancellation) -> Result<()> {
+            self.record("extract subtitles");
+            Ok(())
+        }
+
+        async fn extract_audio(&self, _video: &Path, _audio: &Path, _cancel: &Cancellation) -> Result<()> {
+            self.record("extract audio");
+            Ok(())
+        }
+
+        fn check_availability(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn get_version_info(&self) -> Result<String> {
+            Ok("stub".to_string())
+        }
+
+        async fn execute_command(&self, _command: MediaCommand, _cancel: &Cancellation) -> Result<()> {
+            self.record("command");
+            Ok(())
+        }
+    }
+
+    /// Translator with its own service, here upper-casing the text
+    struct Shouting;
+
+    #[async_trait]
+    impl Translator for Shouting {
+        async fn translate_transcription(
+            &mut self,
+            transcription: &mut Transcription,
+            _target_language: &str,
+            _context: Option<&str>,
+            _cancel: &Cancellation,
+        ) -> Result<()> {
+            for segment in &mut transcription.segments {
+                segment.text = segment.text.to_uppercase();
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registered_translator_without_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        // Nothing answers here: the model backend must not be asked
+        config.translate.endpoint = "http://127.0.0.1:9".to_string();
+        config.translate.implementation = Some("shouting".to_string());
+        let translators = TranslatorRegistry::with_builtin()
+            .register("shouting", |_config: TranslateConfig, _services: TranslationServices| Box::new(Shouting) as Box<dyn Translator>);
+        assert!(!translators.uses_backend(&config.translate));
+        let builtin = TranslateConfig { implementation: None, ..config.translate.clone() };
+        assert!(translators.uses_backend(&builtin));
+
+        let shuro = Shuro::builder().data_dir(dir.path()).config(config).translators(translators).subtitles_only().build().unwrap();
+        let mut translator = shuro.translator().await.unwrap();
+        let mut transcription = parse_srt("1\n00:00:00,000 --> 00:00:02,000\nHello there\n", "en").unwrap();
+        translator.translate_transcription(&mut transcription, "ja", None, &Cancellation::new()).await.unwrap();
+        assert_eq!(transcription.segments[0].text, "HELLO THERE");
+    }
+
+    #[test]
+    fn test_known_language() {
+        assert_eq!(known_language(Some("fr"), Some("de"), Some("es")), Some("fr".to_string()));
+        assert_eq!(known_language(None, Some("de"), Some("es")), Some("de".to_string()));
+        assert_eq!(known_language(None, None, Some("es")), Some("es".to_string()));
+        // Whisper's "auto" and empty languages tell nothing
+        assert_eq!(known_language(Some("auto"), Some(""), Some("ja")), Some("ja".to_string()));
+        assert_eq!(known_language(None, Some("auto"), None), None);
+    }
+
+    #[test]
+    fn test_split_source_language() {
+        let targets: Vec<String> = ["ja", "fra", "de"].iter().map(|l| l.to_string()).collect();
+        let (remaining, skipped) = split_source_language(&targets, Some("fr"));
+        assert_eq!((remaining, skipped), (vec!["ja".to_string(), "de".to_string()], vec!["fra".to_string()]));
+        let (remaining, skipped) = split_source_language(&targets, None);
+        assert_eq!((remaining, skipped), (targets, Vec::new()));
+    }
+
+    #[tokio::test]
+    async fn test_embed_track_follows_burn_in() {
+        let mut config = Config::default();
+        let cancel = Cancellation::new();
+        for (burn_in, expected) in [(false, "mux"), (true, "burn")] {
+            config.media.burn_in = burn_in;
+            let mut workflow = Workflow::for_subtitles(config.clone()).unwrap();
+            let media = StubMedia::default();
+            let calls = media.calls.clone();
+            workflow.media = Box::new(media);
+            workflow.embed_track(Path::new("in.mp4"), Path::new("in.ja.srt"), "ja", Path::new("out.mp4"), &cancel).await.unwrap();
+            assert_eq!(*calls.lock().unwrap(), vec![expected.to_string()]);
+        }
+    }
+
+    /// Workflow translating the transcript `transcript` with the `Shouting` translator, keeping
+    /// its state in `work_dir`
+    fn batch_workflow(work_dir: &Path, transcript: &Path, on_error: ErrorPolicy, media: StubMedia) -> Workflow {
+        let mut config = Config::default();
+        config.use_work_dir(work_dir);
+        config.transcriber.transcript = Some(transcript.to_string_lossy().into_owned());
+        config.translate.implementation = Some("shouting".to_string());
+        config.preflight.batch_files = 0;
+        config.batch.on_error = on_error;
+        let translators = TranslatorRegistry::with_builtin()
+            .register("shouting", |_config: TranslateConfig, _services: TranslationServices| Box::new(Shouting) as Box<dyn Translator>);
+        let mut workflow = Workflow::assemble(config, TranscriberRegistry::with_builtin(), translators, BackendRegistry::with_builtin()).unwrap();
+        workflow.media = Box::new(media);
+        workflow
+    }
+
+    #[tokio::test]
+    async fn test_process_directory_on_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let transcript = dir.path().join("episode.transcript.json");
+        let transcription = parse_srt("1\n00:00:00,000 --> 00:00:02,000\nHello there\n", "en").unwrap();
+        write_transcript(&transcription, Some(4.0), None, &transcript).await.unwrap();
+        let input = dir.path().join("videos");
+        std::fs::create_dir(&input).unwrap();
+        for name in ["a.mp4", "b.mp4", "c.mp4"] {
+            std::fs::write(input.join(name), "").unwrap();
+        }
+        let languages = vec!["ja".to_string()];
+        let cancel = Cancellation::new();
+        let run = |policy, failing, output: &'static str| {
+            let media = StubMedia { failing, ..StubMedia::default() };
+            let calls = media.calls.clone();
+            let workflow = batch_workflow(&dir.path().join("work"), &transcript, policy, media);
+            let (input, output, languages, cancel) = (input.clone(), dir.path().join(output), languages.clone(), cancel.clone());
+            async move {
+                let result = workflow.process_directory(&input, &languages, Some(&output), &cancel, None, false).await;
+                let muxed = calls.lock().unwrap().iter().filter(|call| *call == "mux").count();
+                (result, muxed, output)
+            }
+        };
+
+        // Abort stops at the failing file, before the one after it
+        let (result, muxed, _) = run(ErrorPolicy::Abort, Some("b.mp4"), "abort").await;
+        assert!(result.unwrap_err().to_string().contains("b.mp4 is corrupt"));
+        assert_eq!(muxed, 1);
+
+        // Continue goes on with the other files and succeeds
+        let (result, muxed, output) = run(ErrorPolicy::Continue, Some("b.mp4"), "continue").await;
+        result.unwrap();
+        assert_eq!(muxed, 2);
+        assert!(output.join("c_ja.srt").exists() && !output.join("b_ja.srt").exists());
+        assert!(!output.join(RETRY_LIST).exists());
+
+        // Quarantine lists the failing file, and drops the list once it goes through
+        let (result, _, output) = run(ErrorPolicy::Quarantine, Some("b.mp4"), "quarantine").await;
+        result.unwrap();
+        let listed = std::fs::read_to_string(output.join(RETRY_LIST)).unwrap();
+        assert!(listed.contains("b.mp4") && !listed.contains("a.mp4"));
+        let (result, _, output) = run(ErrorPolicy::Quarantine, None, "quarantine").await;
+        result.unwrap();
+        assert!(!output.join(RETRY_LIST).exists());
+    }
+}
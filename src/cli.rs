@@ -8,10 +8,23 @@ pub struct Args {
     #[arg(short, long)]
     pub verbose: bool,
 
+    /// Log every step to the console instead of drawing progress bars for `process` and `batch`
+    #[arg(long)]
+    pub no_progress: bool,
+
+    /// Print results (listings, reports, processed files, errors) as JSON on stdout; log lines go to stderr
+    #[arg(long, global = true)]
+    pub json: bool,
+
     /// Configuration file path
     #[arg(short, long)]
     pub config: Option<PathBuf>,
 
+    /// Directory for this run's logs, caches and temporary files instead of ./.shuro
+    /// (models stay in ./.shuro/models)
+    #[arg(long)]
+    pub work_dir: Option<PathBuf>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -28,7 +41,7 @@ pub enum Commands {
         #[arg(short, long, default_value = "ja")]
         target_langs: String,
 
-        /// Source language (language code)
+        /// Source language (language code), transcribed without detecting it; detected per file when unset
         #[arg(short, long)]
         source_lang: Option<String>,
 
@@ -40,9 +53,70 @@ pub enum Commands {
         #[arg(long, default_value = "simple")]
         translation_mode: String,
 
+        /// Title of the film or series, given to the translator as background
+        #[arg(long)]
+        title: Option<String>,
+
+        /// Synopsis, main characters or setting, given to the translator as background
+        #[arg(long)]
+        description: Option<String>,
+
         /// Transcription mode (simple, tuned)
         #[arg(long, default_value = "tuned")]
         transcription_mode: String,
+
+        /// Audio track to transcribe (default: detect the track in the source language)
+        #[arg(long)]
+        audio_track: Option<usize>,
+
+        /// Use an embedded text subtitle track in the source language instead of transcribing
+        #[arg(long)]
+        prefer_embedded_subs: bool,
+
+        /// Transcript file (from `transcript export`, possibly edited) to translate instead of transcribing
+        #[arg(long)]
+        transcript: Option<PathBuf>,
+
+        /// Apply the tuning profile of another video (its path or profile ID) instead of exploring tempos
+        #[arg(long)]
+        tuning_profile: Option<String>,
+
+        /// Also write a forced subtitle track with only the foreign-language lines
+        #[arg(long)]
+        forced_track: bool,
+
+        /// Style preset of the burned-in subtitles and the written ASS file (netflix-like,
+        /// cinema, high-contrast, dyslexia-friendly, or one defined under media.styles)
+        #[arg(long)]
+        style: Option<String>,
+
+        /// Burn the subtitles into the video stream instead of adding a subtitle track
+        #[arg(long)]
+        burn_in: bool,
+
+        /// Mux the subtitle tracks of every language and the transcription into one MKV file
+        #[arg(long, conflicts_with = "burn_in")]
+        multi_track: bool,
+
+        /// Skip the languages whose subtitles and video exist and are newer than the input video
+        #[arg(long)]
+        skip_existing: bool,
+
+        /// Write every language again even when its outputs are newer than the input video
+        #[arg(long, conflicts_with = "skip_existing")]
+        overwrite: bool,
+
+        /// Abort processing if it takes longer than this many seconds
+        #[arg(long)]
+        timeout: Option<u64>,
+
+        /// List the steps, cache hits, segment counts and outputs of the run without running it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Also write the end-of-run report to this file, as JSON (.json) or HTML (.html)
+        #[arg(long)]
+        report: Option<PathBuf>,
     },
 
     /// Process all video files in a directory
@@ -55,7 +129,7 @@ pub enum Commands {
         #[arg(short, long, default_value = "ja")]
         target_langs: String,
 
-        /// Source language (language code)
+        /// Source language (language code), transcribed without detecting it; detected per file when unset
         #[arg(short, long)]
         source_lang: Option<String>,
 
@@ -67,9 +141,80 @@ pub enum Commands {
         #[arg(long, default_value = "simple")]
         translation_mode: String,
 
+        /// Title of the film or series, given to the translator as background
+        #[arg(long)]
+        title: Option<String>,
+
+        /// Synopsis, main characters or setting, given to the translator as background
+        #[arg(long)]
+        description: Option<String>,
+
         /// Transcription mode (simple, tuned)
         #[arg(long, default_value = "tuned")]
         transcription_mode: String,
+
+        /// Audio track to transcribe (default: detect the track in the source language)
+        #[arg(long)]
+        audio_track: Option<usize>,
+
+        /// Use an embedded text subtitle track in the source language instead of transcribing
+        #[arg(long)]
+        prefer_embedded_subs: bool,
+
+        /// Apply the tuning profile of another video (its path or profile ID) instead of exploring tempos
+        #[arg(long)]
+        tuning_profile: Option<String>,
+
+        /// Also write a forced subtitle track with only the foreign-language lines
+        #[arg(long)]
+        forced_track: bool,
+
+        /// Style preset of the burned-in subtitles and the written ASS file (netflix-like,
+        /// cinema, high-contrast, dyslexia-friendly, or one defined under media.styles)
+        #[arg(long)]
+        style: Option<String>,
+
+        /// Burn the subtitles into the video stream instead of adding a subtitle track
+        #[arg(long)]
+        burn_in: bool,
+
+        /// Mux the subtitle tracks of every language and the transcription into one MKV file
+        #[arg(long, conflicts_with = "burn_in")]
+        multi_track: bool,
+
+        /// Skip the languages whose subtitles and video exist and are newer than the input video
+        #[arg(long)]
+        skip_existing: bool,
+
+        /// Write every language again even when its outputs are newer than the input video
+        #[arg(long, conflicts_with = "skip_existing")]
+        overwrite: bool,
+
+        /// Abort a file if processing it takes longer than this many seconds
+        #[arg(long)]
+        timeout: Option<u64>,
+
+        /// Skip the files and languages an interrupted run of the same batch finished
+        #[arg(long)]
+        resume: bool,
+
+        /// What a failed file does to the batch: continue (list failures at the end), abort, or
+        /// quarantine (continue and list the failed files in retry-list.txt in the output directory)
+        #[arg(long)]
+        on_error: Option<String>,
+
+        /// Report progress to and take pause/resume/skip/abort commands from a Unix socket
+        /// (a named pipe such as \\.\pipe\shuro on Windows) at this path
+        #[arg(long)]
+        control_socket: Option<PathBuf>,
+
+        /// List the steps, cache hits, segment counts and outputs of the batch without running it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Also write the end-of-run report to this file, as JSON (.json) or HTML (.html)
+        #[arg(long)]
+        report: Option<PathBuf>,
     },
 
     /// List available whisper models and their status
@@ -77,6 +222,57 @@ pub enum Commands {
         /// Download all missing models
         #[arg(long)]
         download: bool,
+
+        #[command(subcommand)]
+        action: Option<ModelsAction>,
+    },
+
+    /// Check the translation endpoint, models and transcriber before a long run
+    Preflight {
+        /// Target languages to test-translate into (comma-separated)
+        #[arg(short, long)]
+        target_langs: String,
+
+        /// Source language the transcriber is tested with (language code)
+        #[arg(short, long)]
+        source_lang: Option<String>,
+    },
+
+    /// Run the whole pipeline on a generated test video with stand-ins for whisper and the translation model
+    Selftest {
+        /// Target languages to translate into (comma-separated)
+        #[arg(short, long, default_value = "ja")]
+        target_langs: String,
+
+        /// Keep the test video and its outputs in this directory instead of a temporary one
+        #[arg(long)]
+        keep: Option<PathBuf>,
+    },
+
+    /// Remove temporary files left behind by interrupted runs
+    CleanTemp {
+        /// Also remove directories whose run may still be active
+        #[arg(long)]
+        all: bool,
+
+        /// List what would be removed without removing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Serve an HTTP API to submit videos, follow jobs and download their subtitles
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        bind: std::net::SocketAddr,
+
+        /// Directory for uploads and job outputs
+        #[arg(long, default_value = ".shuro/serve")]
+        dir: PathBuf,
+
+        /// Bearer token required on every request (default: $SHURO_SERVE_TOKEN)
+        #[arg(long)]
+        token: Option<String>,
     },
 
     /// Manage transcription cache
@@ -115,15 +311,17 @@ pub enum Commands {
         transcription_mode: String,
     },
 
-    /// Translate subtitles using LLM
-    Translate {
-        /// Input transcript file
-        #[arg(short, long)]
-        input: PathBuf,
+    /// Export transcripts for editing and check edited ones
+    Transcript {
+        #[command(subcommand)]
+        action: TranscriptAction,
+    },
 
-        /// Output translated file
-        #[arg(short, long)]
-        output: PathBuf,
+    /// Translate subtitle files (SRT, WebVTT or ASS) without transcription or media
+    Translate {
+        /// Input subtitle files
+        #[arg(num_args = 1.., required = true)]
+        inputs: Vec<PathBuf>,
 
         /// Target languages (comma-separated)
         #[arg(short, long)]
@@ -132,6 +330,30 @@ pub enum Commands {
         /// Source language (language code)
         #[arg(short, long)]
         source_lang: Option<String>,
+
+        /// Output directory (defaults to the directory of each input)
+        #[arg(short, long)]
+        output_dir: Option<PathBuf>,
+
+        /// Translation optimization mode
+        #[arg(long, default_value = "simple")]
+        translation_mode: String,
+
+        /// Title of the film or series, given to the translator as background
+        #[arg(long)]
+        title: Option<String>,
+
+        /// Synopsis, main characters or setting, given to the translator as background
+        #[arg(long)]
+        description: Option<String>,
+
+        /// Style preset of ASS outputs
+        #[arg(long)]
+        style: Option<String>,
+
+        /// Fail on malformed input files instead of repairing them and listing the problems
+        #[arg(long)]
+        strict: bool,
     },
 
     /// Embed subtitles into video file
@@ -140,13 +362,276 @@ pub enum Commands {
         #[arg(short, long)]
         video: PathBuf,
 
-        /// Subtitle file
+        /// Subtitle file (SRT, WebVTT or ASS; ASS files keep their own styles)
         #[arg(short, long)]
         subtitles: PathBuf,
 
         /// Output video file
         #[arg(short, long)]
         output: PathBuf,
+
+        /// Style preset of the burned-in subtitles
+        #[arg(long)]
+        style: Option<String>,
+
+        /// Burn the subtitles into the video stream instead of adding a subtitle track
+        #[arg(long)]
+        burn_in: bool,
+    },
+
+    /// Render a short clip with subtitles burned in to check styling and sync
+    PreviewClip {
+        /// Input video file
+        video: PathBuf,
+
+        /// Start of the clip (HH:MM:SS, MM:SS or seconds)
+        #[arg(long, default_value = "0")]
+        at: String,
+
+        /// Length of the clip (e.g. 30s, 2m, 00:01:30)
+        #[arg(long, default_value = "30s")]
+        duration: String,
+
+        /// Language of the subtitles to burn in
+        #[arg(short, long)]
+        lang: String,
+
+        /// Subtitle file (default: `{video}_{lang}.srt` next to the video)
+        #[arg(short, long)]
+        subtitles: Option<PathBuf>,
+
+        /// Output clip (default: `{video}_{lang}_preview.mp4` next to the video)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Style preset of the burned-in subtitles
+        #[arg(long)]
+        style: Option<String>,
+    },
+
+    /// Mux subtitle files into matching videos as soft subtitle tracks
+    EmbedBatch {
+        /// Directory with `{video}_{lang}.srt` or `{video}.{lang}.srt` subtitle files (also `.vtt`, `.ass`)
+        #[arg(long)]
+        subs_dir: PathBuf,
+
+        /// Directory with the videos to mux into
+        #[arg(long)]
+        videos_dir: PathBuf,
+
+        /// Output directory (default: replace the videos in place)
+        #[arg(short, long)]
+        output_dir: Option<PathBuf>,
+
+        /// Replace existing subtitle tracks in the same language instead of adding alongside them
+        #[arg(long)]
+        replace: bool,
+    },
+
+    /// Process subtitle files without media
+    Subtitle {
+        #[command(subcommand)]
+        action: SubtitleAction,
+    },
+
+    /// Show the container, streams (codecs, languages, flags) and chapters of a media file
+    Inspect {
+        /// Input media file
+        input: PathBuf,
+    },
+
+    /// List or remove subtitle tracks of a video
+    Tracks {
+        #[command(subcommand)]
+        action: TracksAction,
+    },
+
+    /// Retime an existing subtitle file to the video's transcription without re-translating
+    Align {
+        /// Input video file
+        #[arg(short, long)]
+        video: PathBuf,
+
+        /// Subtitle file with bad timing
+        #[arg(short, long)]
+        subtitles: PathBuf,
+
+        /// Output subtitle file
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Language of the subtitle file (language code)
+        #[arg(short, long)]
+        language: String,
+
+        /// Transcription mode (simple, tuned)
+        #[arg(long, default_value = "tuned")]
+        transcription_mode: String,
+    },
+
+    /// Re-translate cached segments in existing subtitle files and patch them in place
+    Retranslate {
+        /// Subtitle files produced by shuro
+        #[arg(short, long, num_args = 1.., required = true)]
+        subtitles: Vec<PathBuf>,
+
+        /// Language of the subtitle files (language code)
+        #[arg(short, long)]
+        target_lang: String,
+
+        /// Only re-translate segments mentioning glossary terms changed since the last run
+        #[arg(long)]
+        changed_terms_only: bool,
+    },
+
+    /// Manage the series project in the current directory
+    Project {
+        #[command(subcommand)]
+        action: ProjectAction,
+    },
+
+    /// Export and import the translation memory (of the project in the current directory, if any)
+    Tm {
+        #[command(subcommand)]
+        action: TmAction,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TmAction {
+    /// Write the translation memory as a TMX file
+    Export {
+        /// Output TMX file
+        output: PathBuf,
+
+        /// Only export translations into this language (language code)
+        #[arg(short, long)]
+        target_lang: Option<String>,
+    },
+
+    /// Add the translation units of a TMX file to the translation memory
+    Import {
+        /// Input TMX file
+        input: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProjectAction {
+    /// Create a project in the current directory
+    Init {
+        /// Series name
+        #[arg(short, long)]
+        name: String,
+    },
+
+    /// Show the project enclosing the current directory
+    Show,
+
+    /// Add or update a glossary term translation
+    AddTerm {
+        /// Term in the source language
+        #[arg(short, long)]
+        source: String,
+
+        /// Target language (language code)
+        #[arg(short, long)]
+        lang: String,
+
+        /// Required translation of the term
+        #[arg(short, long)]
+        translation: String,
+    },
+
+    /// Add or update a character name translation
+    AddCharacter {
+        /// Character name in the source language
+        #[arg(short, long)]
+        name: String,
+
+        /// Target language (language code)
+        #[arg(short, long)]
+        lang: String,
+
+        /// Required translation of the name
+        #[arg(short, long)]
+        translation: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ModelsAction {
+    /// Re-hash downloaded models against the catalog checksums and test-load each with a second of silence
+    Verify,
+}
+
+#[derive(Subcommand)]
+pub enum TranscriptAction {
+    /// Write the transcription of a video, or the cues of a subtitle file, as a versioned JSON transcript
+    Export {
+        /// Input video or subtitle file
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Output transcript file (conventionally `{video}.transcript.json`)
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Source language (language code), transcribed without detecting it; detected per file when unset
+        #[arg(short, long)]
+        source_lang: Option<String>,
+
+        /// Transcription mode (simple, tuned)
+        #[arg(long, default_value = "tuned")]
+        transcription_mode: String,
+    },
+
+    /// Check an edited transcript and optionally convert it to a subtitle file
+    Import {
+        /// Transcript file
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Subtitle file to write (format from the extension: .srt, .vtt or .ass)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SubtitleAction {
+    /// Merge too-short cues and split overlong ones by the `[segmentation]` limits
+    Normalize {
+        /// Input subtitle file (SRT, WebVTT or ASS)
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Output subtitle file (format from the extension: .srt, .vtt or .ass)
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TracksAction {
+    /// List the subtitle tracks of a video
+    List {
+        /// Input video file
+        video: PathBuf,
+    },
+
+    /// Remove the subtitle tracks in a language
+    Remove {
+        /// Input video file
+        video: PathBuf,
+
+        /// Language of the tracks to remove (language code)
+        #[arg(short, long)]
+        lang: String,
+
+        /// Output video file (default: replace the video in place)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
 }
 
@@ -161,11 +646,43 @@ pub enum CacheAction {
     /// Show cache statistics and size
     Info,
 
-    /// Clear cache entries older than specified days
+    /// Remove cached audio, transcriptions, translations and analysis journals not used for the specified days
     Clean {
         /// Age in days (default: 30)
         #[arg(short, long, default_value = "30")]
         days: u64,
+
+        /// Only list the entries that would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Evict the least recently used cache entries until every category fits its
+    /// `cache.max_cache_size_mb` limit
+    Prune {
+        /// Prune all categories together to this size in MB instead of their own limits
+        #[arg(long)]
+        max_size: Option<u64>,
+    },
+
+    /// Bundle cache entries into a tarball to import on another machine
+    Export {
+        /// Archive to write (e.g. cache.tar.gz)
+        archive: PathBuf,
+
+        /// Comma-separated categories to export: transcriptions, translations, analysis
+        #[arg(long, default_value = "transcriptions,translations")]
+        only: String,
+
+        /// Only export transcriptions of videos whose file name contains this text
+        #[arg(long)]
+        source: Option<String>,
+    },
+
+    /// Add the entries of a cache archive to the local caches
+    Import {
+        /// Archive written by `shuro cache export`
+        archive: PathBuf,
     },
 
     /// List only cached transcriptions
@@ -0,0 +1,207 @@
+// Control socket (`batch --control-socket`)
+//
+// Lets a process supervisor or desktop front end follow and steer a running batch without the
+// HTTP API of `shuro serve`. The path names a Unix domain socket (a stale one left by a crashed
+// run is replaced) that only the user running shuro may connect to (mode 0600), or on Windows a
+// named pipe such as `\\.\pipe\shuro`. Any number of clients may connect. Every progress event
+// of the batch is written to every client as one JSON line (see progress.rs in shuro-core), and
+// clients send one command per line:
+//
+// - `pause`: hold the batch before its next file; `resume`: continue it
+// - `skip`: give up on the file being processed and go on with the next one
+// - `abort`: cancel the batch, as Ctrl+C does
+//
+// Each command is answered with `{"ok":"<command>"}` or `{"error":"..."}`.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use shuro_core::cancel::Cancellation;
+use shuro_core::control::BatchControl;
+use shuro_core::progress::{ProgressEvent, ProgressSender};
+
+/// Listening control socket; closed and removed when dropped
+pub struct ControlSocket {
+    path: PathBuf,
+    listener: JoinHandle<()>,
+}
+
+/// What connected clients share
+#[derive(Clone)]
+struct Clients {
+    events: broadcast::Sender<String>,
+    control: BatchControl,
+    cancel: Cancellation,
+}
+
+impl ControlSocket {
+    /// Listen on `path`; the returned sender takes the batch's progress events
+    pub fn bind(path: &Path, control: BatchControl, cancel: Cancellation) -> Result<(Self, ProgressSender)> {
+        let (events_tx, mut events_rx) = mpsc::unbounded_channel::<ProgressEvent>();
+        let (lines, _) = broadcast::channel(256);
+        let clients = Clients { events: lines.clone(), control, cancel };
+        let listener = listen(path, clients)?;
+        info!("🎛️ Control socket listening on {}", path.display());
+
+        tokio::spawn(async move {
+            while let Some(event) = events_rx.recv().await {
+                if let Ok(line) = serde_json::to_string(&event) {
+                    // Nobody may be connected
+                    let _ = lines.send(line);
+                }
+            }
+        });
+        Ok((Self { path: path.to_path_buf(), listener }, events_tx))
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        self.listener.abort();
+        #[cfg(unix)]
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl Clients {
+    /// Carry out a command line of a client and return the answer, if any
+    fn execute(&self, command: &str) -> Option<String> {
+        match command {
+            "" => return None,
+            "pause" => self.control.pause(),
+            "resume" => self.control.resume(),
+            "skip" => {
+                if !self.control.skip_current() {
+                    return Some(answer_error("no file is being processed"));
+                }
+            }
+            "abort" => self.cancel.cancel(),
+            other => return Some(answer_error(&format!("unknown command: {}", other))),
+        }
+        info!("🎛️ Control socket: {}", command);
+        Some(serde_json::json!({ "ok": command }).to_string())
+    }
+}
+
+fn answer_error(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+#[cfg(unix)]
+fn listen(path: &Path, clients: Clients) -> Result<JoinHandle<()>> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            anyhow::bail!("{} exists and is not a socket", path.display());
+        }
+        std::fs::remove_file(path)?;
+    }
+    let listener = tokio::net::UnixListener::bind(path)?;
+    // Only the user running the batch may steer it
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(serve_client(stream, clients.clone()));
+                }
+                Err(e) => {
+                    warn!("Control socket stopped accepting clients: {}", e);
+                    return;
+                }
+            }
+        }
+    }))
+}
+
+#[cfg(windows)]
+fn listen(path: &Path, clients: Clients) -> Result<JoinHandle<()>> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let name = path.as_os_str().to_owned();
+    let mut server = ServerOptions::new().first_pipe_instance(true).create(&name)?;
+    Ok(tokio::spawn(async move {
+        loop {
+            if let Err(e) = server.connect().await {
+                warn!("Control pipe stopped accepting clients: {}", e);
+                return;
+            }
+            // A pipe instance serves one client; the next one waits on a new instance
+            let connected = server;
+            server = match ServerOptions::new().create(&name) {
+                Ok(server) => server,
+                Err(e) => {
+                    warn!("Control pipe stopped accepting clients: {}", e);
+                    return;
+                }
+            };
+            tokio::spawn(serve_client(connected, clients.clone()));
+        }
+    }))
+}
+
+/// Stream events to a client and carry out its commands until it disconnects
+async fn serve_client<S: AsyncRead + AsyncWrite + Send + 'static>(stream: S, clients: Clients) {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut commands = BufReader::new(reader).lines();
+    let mut events = clients.events.subscribe();
+    loop {
+        let line = tokio::select! {
+            command = commands.next_line() => match command {
+                Ok(Some(command)) => match clients.execute(command.trim()) {
+                    Some(answer) => answer,
+                    None => continue,
+                },
+                _ => return,
+            },
+            event = events.recv() => match event {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+        };
+        if writer.write_all(format!("{}\n", line).as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_execute_commands() {
+        let clients = Clients {
+            events: broadcast::channel(1).0,
+            control: BatchControl::new(),
+            cancel: Cancellation::new(),
+        };
+        assert_eq!(clients.execute("pause").unwrap(), r#"{"ok":"pause"}"#);
+        assert!(clients.control.is_paused());
+        clients.execute("resume");
+        assert!(!clients.control.is_paused());
+        assert_eq!(clients.execute("skip").unwrap(), r#"{"error":"no file is being processed"}"#);
+        assert!(clients.execute("").is_none());
+        assert!(clients.execute("reboot").unwrap().contains("unknown command"));
+        clients.execute("abort");
+        assert!(clients.cancel.is_cancelled());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_socket_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("shuro-control-{}.sock", std::process::id()));
+        let (socket, _events) = ControlSocket::bind(&path, BatchControl::new(), Cancellation::new()).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        drop(socket);
+        assert!(!path.exists());
+    }
+}
@@ -4,30 +4,90 @@
 //! an automated workflow for adding translated subtitles to movie files
 //! using whisper-cpp, ollama, and ffmpeg.
 
+mod cli;
+mod control;
+mod metrics;
+mod output;
+mod progress;
+mod serve;
+mod setup;
+
 use anyhow::Result;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Duration;
 use clap::Parser;
 use tracing::{info, Level};
-use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::{fmt, filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
 use tracing_appender::{non_blocking, rolling};
 
-use shuro::cli::{Args, Commands, CacheAction};
-use shuro::config::{Config, TranslationMode, TranscriptionMode};
-use shuro::setup::SetupManager;
-use shuro::workflow::Workflow;
-use shuro::quality::QualityValidator;
-use shuro::transcribe::TranscriberFactory;
-use shuro::translate::BaseTranslator;
-use shuro::error::ShuroError;
+use shuro_core::{Shuro, ShuroBuilder};
+use shuro_core::cache;
+use shuro_core::cancel::Cancellation;
+use shuro_core::control::BatchControl;
+use shuro_core::config::{Config, ErrorPolicy, ExistingOutputs, TranslationMode, TranscriptionMode};
+use shuro_core::workflow::Workflow;
+use shuro_core::transcribe::{TranscriptionCache, format_duration};
+use shuro_core::translate::BaseTranslator;
+use shuro_core::translate::memory::{self, TranslationMemory};
+use shuro_core::error::ShuroError;
+use shuro_core::project::Project;
+use shuro_core::report::{ReportFormat, RunReport};
+use shuro_core::selftest::run_selftest;
+use shuro_core::temp;
+use shuro_core::webhook::Webhook;
+use shuro_core::subtitle::parse_timecode;
+
+use cli::{Args, Commands, CacheAction, ModelsAction, ProjectAction, SubtitleAction, TmAction, TracksAction, TranscriptAction};
+use control::ControlSocket;
+use output::{FileOutcome, Output, collect_outcomes};
+use progress::ProgressRenderer;
+use setup::{ModelStatus, SetupManager};
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> Result<ExitCode> {
     info!("Starting Shuro - Automated Subtitle Translation Workflow");
 
+    // Temporary directories still in use are removed however the run ends
+    let _temp_cleanup = temp::CleanupGuard;
+
     // Parse command line arguments
     let args = Args::parse();
+    let output = Output::new(args.json);
+
+    match run(args, output).await {
+        Ok(()) => Ok(ExitCode::SUCCESS),
+        Err(e) if output.is_json() => {
+            output.error(&e);
+            Ok(ExitCode::FAILURE)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Run the command given on the command line
+async fn run(args: Args, output: Output) -> Result<()> {
+
+    // Keep this run's logs, caches and temporary files together when a work directory is given
+    let state_dir = match &args.work_dir {
+        Some(work_dir) => {
+            std::fs::create_dir_all(work_dir)?;
+            work_dir.clone()
+        }
+        None => std::env::current_dir()?.join(".shuro"),
+    };
+
+    // Long-running commands draw progress bars on a terminal instead of logging every step
+    let progress = (matches!(args.command, Commands::Process { .. } | Commands::Batch { .. })
+        && !args.no_progress
+        && !args.verbose
+        && std::io::stderr().is_terminal())
+        .then(ProgressRenderer::new);
 
     // Setup logging to both console and file
-    setup_logging(args.verbose)?;
+    setup_logging(&state_dir, args.verbose, output, progress.as_ref())?;
 
     // Load configuration
     let mut config = match &args.config {
@@ -42,6 +102,136 @@ async fn main() -> Result<()> {
             }
         }
     };
+    if let Some(work_dir) = &args.work_dir {
+        info!("🗂️ Keeping logs, caches and temporary files of this run in {}", work_dir.display());
+        config.use_work_dir(work_dir);
+    }
+
+    // Project management needs no models or binaries
+    if let Commands::Project { action } = args.command {
+        return manage_project(action, output);
+    }
+
+    // Leftover cleanup needs no models or binaries either
+    if let Commands::CleanTemp { all, dry_run } = args.command {
+        return clean_temp(&config, all, dry_run, output);
+    }
+
+    // The translation memory needs no models or binaries; inside a project it is the project's
+    if let Commands::Tm { action } = args.command {
+        if let Some(project) = Project::discover(&std::env::current_dir()?)? {
+            config = project.apply(config);
+        }
+        return manage_translation_memory(&config, action, output).await;
+    }
+
+    // Apply the project enclosing the input, if any
+    let project = match command_input(&args.command) {
+        Some(input) => Project::discover(input)?,
+        None => None,
+    };
+    if let Some(project) = &project {
+        info!("📚 Using project '{}' from {}", project.file().name, project.root().display());
+    }
+    let engine = |config: Config| engine(config, &state_dir, project.as_ref());
+
+    // Cancel running work on Ctrl+C so child processes are stopped cleanly
+    let cancel = Cancellation::new();
+    let interrupt = cancel.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            info!("Interrupt received, cancelling");
+            interrupt.cancel();
+        }
+    });
+
+    // Subtitle translation needs no whisper models or media binaries
+    if let Commands::Translate { inputs, target_langs, source_lang, output_dir, translation_mode, title, description, style, strict } = args.command {
+        info!("Translating {} subtitle files", inputs.len());
+
+        // Override source language if provided
+        if let Some(source_lang) = source_lang {
+            config.translate.source_language = source_lang;
+        }
+        config.translate.mode = parse_translation_mode(&translation_mode)?;
+        if title.is_some() {
+            config.translate.work.title = title;
+        }
+        if description.is_some() {
+            config.translate.work.description = description;
+        }
+        if style.is_some() {
+            config.media.style = style;
+        }
+        config.media.strict_subtitles |= strict;
+
+        let target_languages = target_langs
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .collect::<Vec<_>>();
+
+        let workflow = engine(config).subtitles_only().build()?.into_workflow();
+        let written = workflow.translate_subtitles(&inputs, output_dir.as_ref(), &target_languages, &cancel).await?;
+        if output.is_json() {
+            output.json(&serde_json::json!({ "subtitles": written }))?;
+        }
+        info!("Shuro workflow completed successfully");
+        return Ok(());
+    }
+
+    // Checking a transcript needs no whisper models or media binaries either
+    if let Commands::Transcript { action: TranscriptAction::Import { input, output: output_path } } = &args.command {
+        let workflow = engine(config).subtitles_only().build()?.into_workflow();
+        let transcription = workflow.import_transcript(input, output_path.as_ref()).await?;
+        let result = serde_json::json!({
+            "input": input,
+            "segments": transcription.segments.len(),
+            "language": transcription.language,
+            "output": output_path,
+        });
+        return output.show(&result, || {
+            println!("{} is a valid transcript: {} segments in {}", input.display(), transcription.segments.len(), transcription.language);
+            if let Some(output_path) = output_path {
+                println!("Wrote {}", output_path.display());
+            }
+        });
+    }
+
+    if let Commands::Subtitle { action: SubtitleAction::Normalize { input, output: output_path } } = &args.command {
+        let workflow = engine(config).subtitles_only().build()?.into_workflow();
+        let (read, written) = workflow.normalize_subtitles(input, output_path).await?;
+        let result = serde_json::json!({ "read": read, "written": written, "output": output_path });
+        return output.show(&result, || println!("Normalized {} cues to {}: {}", read, written, output_path.display()));
+    }
+
+    // The self-test replaces whisper and the translation model with stand-ins, so it needs no models
+    if let Commands::Selftest { target_langs, keep } = args.command {
+        let target_languages = target_langs
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .collect::<Vec<_>>();
+        let temp_dir;
+        let work_dir = match &keep {
+            Some(dir) => dir.clone(),
+            None => {
+                temp_dir = temp::temp_dir(Path::new(&config.temp_dir), "selftest")?;
+                temp_dir.path().to_path_buf()
+            }
+        };
+
+        let report = run_selftest(config, &target_languages, &work_dir, &cancel).await?;
+        output.show(&report, || println!("\n{}", report.render()))?;
+        if !report.passed() {
+            anyhow::bail!("Self-test failed; see the checks above");
+        }
+        if !output.is_json() {
+            match keep {
+                Some(dir) => println!("Self-test passed; the test video and its outputs are in {}", dir.display()),
+                None => println!("Self-test passed"),
+            }
+        }
+        return Ok(());
+    }
 
     // Initialize setup manager and ensure all necessary files are available
     info!("Checking and downloading necessary files...");
@@ -50,30 +240,37 @@ async fn main() -> Result<()> {
     setup_manager.initialize(&mut config).await?;
     info!("Setup manager initialization completed");
 
-    // Create workflow instance
-    let workflow = Workflow::new(config.clone())?;
+    // Create the engine
+    let shuro = engine(config.clone()).build()?;
+    let workflow = shuro.workflow();
 
     // Execute command
     match args.command {
-        Commands::Models { download } => {
+        Commands::Models { action: Some(ModelsAction::Verify), .. } => {
+            verify_models(&setup_manager, &config, output).await?;
+        }
+        Commands::Models { download, action: None } => {
             info!("Listing available whisper models...");
             
             let models = setup_manager.get_available_models();
-            println!("\nAvailable Whisper Models:");
-            println!("{:<15} {:<20} {:<10} {:<10}", "Name", "Filename", "Size (MB)", "Status");
-            println!("{}", "-".repeat(65));
-            
             let models_dir = setup_manager.shuro_dir().join("models");
-            for model in &models {
-                let local_path = models_dir.join(&model.filename);
-                let status = if local_path.exists() {
-                    "Downloaded"
-                } else {
-                    "Missing"
-                };
-                
-                println!("{:<15} {:<20} {:<10.1} {:<10}", 
-                    model.name, model.filename, model.size_mb, status);
+            if !output.is_json() {
+                println!("\nAvailable Whisper Models:");
+                println!("{:<20} {:<30} {:<10} {:<10} {:<10}", "Name", "Filename", "Size (MB)", "Languages", "Status");
+                println!("{}", "-".repeat(84));
+
+                for model in &models {
+                    let local_path = models_dir.join(&model.filename);
+                    let status = if local_path.exists() {
+                        "Downloaded"
+                    } else {
+                        "Missing"
+                    };
+
+                    let languages = if model.english_only { "English" } else { "All" };
+                    println!("{:<20} {:<30} {:<10.1} {:<10} {:<10}",
+                        model.name, model.filename, model.size_mb, languages, status);
+                }
             }
             
             if download {
@@ -81,25 +278,39 @@ async fn main() -> Result<()> {
                 for model in &models {
                     let local_path = models_dir.join(&model.filename);
                     if !local_path.exists() {
-                        setup_manager.download_model(&model).await?;
+                        setup_manager.download_model(model).await?;
                     }
                 }
                 info!("All models downloaded successfully");
             }
+
+            // After downloading, so the listing shows what is on disk now
+            if output.is_json() {
+                let listing: Vec<_> = models.iter().map(|model| serde_json::json!({
+                    "name": model.name,
+                    "filename": model.filename,
+                    "size_mb": model.size_mb,
+                    "english_only": model.english_only,
+                    "downloaded": models_dir.join(&model.filename).exists(),
+                })).collect();
+                output.json(&listing)?;
+            }
         }
         Commands::Cache { action } => {
             info!("Managing transcription cache...");
             
-            // Create a temporary transcriber to manage cache
-            let validator = QualityValidator::new(0.1, 100.0, 5.0);
-            let transcriber = TranscriberFactory::create_default(config.transcriber.clone(), validator);
+            let config = shuro.config();
+            let transcriber = shuro.transcriber();
             
             match action {
                 CacheAction::List => {
                     let transcription_items = transcriber.list_cache().await?;
                     let audio_items = transcriber.list_audio_cache().await?;
-                    
-                    if transcription_items.is_empty() && audio_items.is_empty() {
+
+                    if output.is_json() {
+                        let transcriptions: Vec<_> = transcription_items.iter().map(cached_transcription).collect();
+                        output.json(&serde_json::json!({ "transcriptions": transcriptions, "audio": audio_items }))?;
+                    } else if transcription_items.is_empty() && audio_items.is_empty() {
                         println!("No cached files found.");
                     } else {
                         if !transcription_items.is_empty() {
@@ -161,9 +372,14 @@ async fn main() -> Result<()> {
                     // Also clear translation cache
                     let base_translator = BaseTranslator::new(config.translate.clone());
                     let translation_count = base_translator.clear_translation_cache().await?;
-                    
-                    println!("Cleared {} cached transcriptions, {} cached audio files, and {} cached translations", 
-                             transcription_count, audio_count, translation_count);
+
+                    let cleared = serde_json::json!({
+                        "transcriptions": transcription_count,
+                        "audio": audio_count,
+                        "translations": translation_count,
+                    });
+                    output.show(&cleared, || println!("Cleared {} cached transcriptions, {} cached audio files, and {} cached translations", 
+                             transcription_count, audio_count, translation_count))?;
                 }
                 CacheAction::Info => {
                     let info = transcriber.cache_info().await?;
@@ -172,71 +388,116 @@ async fn main() -> Result<()> {
                     let base_translator = BaseTranslator::new(config.translate.clone());
                     let translation_items = base_translator.list_translation_cache().await?;
                     let translation_count = translation_items.len();
-                    
-                    println!("\nCache Statistics:");
-                    println!("Transcription files: {}", info.total_files);
-                    println!("Transcription size: {:.2} MB", info.total_size as f64 / 1024.0 / 1024.0);
-                    println!("Audio files: {}", info.audio_files);
-                    println!("Audio size: {:.2} MB", info.audio_size as f64 / 1024.0 / 1024.0);
-                    println!("Translation files: {}", translation_count);
-                    println!("Total cache size: {:.2} MB", 
-                             (info.total_size + info.audio_size) as f64 / 1024.0 / 1024.0);
-                    
-                    if let Some(oldest) = info.oldest_entry {
-                        let oldest_ago = std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs()
-                            .saturating_sub(oldest);
-                        println!("Oldest entry: {} ago", format_duration(oldest_ago));
-                    }
-                    
-                    if let Some(newest) = info.newest_entry {
-                        let newest_ago = std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs()
-                            .saturating_sub(newest);
-                        println!("Newest entry: {} ago", format_duration(newest_ago));
+
+                    if output.is_json() {
+                        output.json(&serde_json::json!({
+                            "transcription_files": info.total_files,
+                            "transcription_size": info.total_size,
+                            "audio_files": info.audio_files,
+                            "audio_size": info.audio_size,
+                            "translation_files": translation_count,
+                            "oldest_entry": info.oldest_entry,
+                            "newest_entry": info.newest_entry,
+                            "models_used": info.models_used,
+                        }))?;
+                    } else {
+                        println!("\nCache Statistics:");
+                        println!("Transcription files: {}", info.total_files);
+                        println!("Transcription size: {:.2} MB", info.total_size as f64 / 1024.0 / 1024.0);
+                        println!("Audio files: {}", info.audio_files);
+                        println!("Audio size: {:.2} MB", info.audio_size as f64 / 1024.0 / 1024.0);
+                        println!("Translation files: {}", translation_count);
+                        println!("Total cache size: {:.2} MB", 
+                                 (info.total_size + info.audio_size) as f64 / 1024.0 / 1024.0);
+
+                        if let Some(oldest) = info.oldest_entry {
+                            let oldest_ago = std::time::SystemTime::now()
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .unwrap_or_default()
+                                .as_secs()
+                                .saturating_sub(oldest);
+                            println!("Oldest entry: {} ago", format_duration(oldest_ago));
+                        }
+
+                        if let Some(newest) = info.newest_entry {
+                            let newest_ago = std::time::SystemTime::now()
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .unwrap_or_default()
+                                .as_secs()
+                                .saturating_sub(newest);
+                            println!("Newest entry: {} ago", format_duration(newest_ago));
+                        }
+
+                        println!("Models used: {:?}", info.models_used);
                     }
-                    
-                    println!("Models used: {:?}", info.models_used);
                 }
-                CacheAction::Clean { days } => {
-                    // Note: Current implementation clears all cache, not just old entries
-                    let transcription_count = transcriber.clear_cache().await?;
-                    let audio_count = transcriber.clear_audio_cache().await?;
-                    
-                    // Clean translation cache - remove entries older than specified days
-                    let base_translator = BaseTranslator::new(config.translate.clone());
-                    let translation_items = base_translator.list_translation_cache().await?;
-                    let cutoff_time = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap_or_default()
-                        .as_secs()
-                        .saturating_sub(days * 24 * 60 * 60);
-                    
-                    let mut translation_clean_count = 0;
-                    for item in translation_items {
-                        if item.cached_at < cutoff_time {
-                            translation_clean_count += 1;
+                CacheAction::Clean { days, dry_run } => {
+                    let reports = cache::clean_caches(config, days, dry_run);
+                    output.show(&reports, || {
+                        let verb = if dry_run { "Would remove" } else { "Removed" };
+                        for report in &reports {
+                            for path in &report.paths {
+                                println!("{} {}", verb, path.display());
+                            }
                         }
-                    }
-                    
-                    // For now, we'll just show the count. In a full implementation,
-                    // we'd need a method to clean by age
-                    if translation_clean_count > 0 {
-                        println!("Note: {} old translation cache entries found (cleaning by age not yet implemented)", 
-                                translation_clean_count);
-                    }
-                    
-                    println!("Cleaned {} old cached transcriptions and {} old cached audio files (older than {} days)", 
-                             transcription_count, audio_count, days);
+                        for report in &reports {
+                            println!("{:<15} {} {} entries unused for {} days ({:.1} MB), {:.1} MB left",
+                                report.category,
+                                verb.to_lowercase(),
+                                report.removed,
+                                days,
+                                report.freed as f64 / 1024.0 / 1024.0,
+                                report.remaining as f64 / 1024.0 / 1024.0
+                            );
+                        }
+                    })?;
+                }
+                CacheAction::Prune { max_size } => {
+                    let reports = cache::prune_caches(config, max_size);
+                    output.show(&reports, || {
+                        if reports.is_empty() {
+                            println!("No cache size limits configured; set cache.max_cache_size_mb or pass --max-size");
+                        }
+                        for report in &reports {
+                            println!("{:<15} evicted {} entries ({:.1} MB), {:.1} MB left",
+                                report.category,
+                                report.removed,
+                                report.freed as f64 / 1024.0 / 1024.0,
+                                report.remaining as f64 / 1024.0 / 1024.0
+                            );
+                        }
+                    })?;
+                }
+                CacheAction::Export { archive, only, source } => {
+                    let categories = only
+                        .split(',')
+                        .map(|name| {
+                            let name = name.trim();
+                            cache::CacheCategory::ALL.into_iter()
+                                .find(|category| category.name() == name)
+                                .ok_or_else(|| anyhow::anyhow!("Unknown cache category: {}", name))
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    let options = cache::ExportOptions { categories, source };
+                    let report = cache::export_cache(config, &archive, &options)?;
+                    output.show(&report, || print_archive_report("Exported", &report))?;
+                }
+                CacheAction::Import { archive } => {
+                    let report = cache::import_cache(config, &archive)?;
+                    output.show(&report, || {
+                        print_archive_report("Imported", &report);
+                        if report.skipped > 0 {
+                            println!("Kept {} entries already in the local cache", report.skipped);
+                        }
+                    })?;
                 }
                 CacheAction::ListTranscriptions => {
                     let cached_items = transcriber.list_cache().await?;
-                    
-                    if cached_items.is_empty() {
+
+                    if output.is_json() {
+                        let transcriptions: Vec<_> = cached_items.iter().map(cached_transcription).collect();
+                        output.json(&transcriptions)?;
+                    } else if cached_items.is_empty() {
                         println!("No cached transcriptions found.");
                     } else {
                         println!("\nCached Transcriptions:");
@@ -267,8 +528,10 @@ async fn main() -> Result<()> {
                 }
                 CacheAction::ListAudio => {
                     let cached_items = transcriber.list_audio_cache().await?;
-                    
-                    if cached_items.is_empty() {
+
+                    if output.is_json() {
+                        output.json(&cached_items)?;
+                    } else if cached_items.is_empty() {
                         println!("No cached audio files found.");
                     } else {
                         println!("\nCached Audio Files:");
@@ -296,18 +559,20 @@ async fn main() -> Result<()> {
                 }
                 CacheAction::ClearTranscriptions => {
                     let deleted_count = transcriber.clear_cache().await?;
-                    println!("Cleared {} cached transcriptions", deleted_count);
+                    output.show(&serde_json::json!({ "transcriptions": deleted_count }), || println!("Cleared {} cached transcriptions", deleted_count))?;
                 }
                 CacheAction::ClearAudio => {
                     let deleted_count = transcriber.clear_audio_cache().await?;
-                    println!("Cleared {} cached audio files", deleted_count);
+                    output.show(&serde_json::json!({ "audio": deleted_count }), || println!("Cleared {} cached audio files", deleted_count))?;
                 }
                 CacheAction::ListTranslations => {
                     // Create translator instance to access translation cache
                     let base_translator = BaseTranslator::new(config.translate.clone());
                     let cached_items = base_translator.list_translation_cache().await?;
-                    
-                    if cached_items.is_empty() {
+
+                    if output.is_json() {
+                        output.json(&cached_items)?;
+                    } else if cached_items.is_empty() {
                         println!("No cached translations found.");
                     } else {
                         println!("\nCached Translations:");
@@ -341,65 +606,188 @@ async fn main() -> Result<()> {
                     // Create translator instance to access translation cache
                     let base_translator = BaseTranslator::new(config.translate.clone());
                     let deleted_count = base_translator.clear_translation_cache().await?;
-                    println!("Cleared {} cached translations", deleted_count);
+                    output.show(&serde_json::json!({ "translations": deleted_count }), || println!("Cleared {} cached translations", deleted_count))?;
                 }
             }
         }
-        Commands::Process { input, target_langs, source_lang, output_dir, translation_mode, transcription_mode } => {
+        Commands::Process { input, target_langs, source_lang, output_dir, translation_mode, title, description, transcription_mode, audio_track, prefer_embedded_subs, transcript, tuning_profile, forced_track, style, burn_in, multi_track, skip_existing, overwrite, timeout, dry_run, report } => {
             info!("Processing video file: {}", input.display());
             
-            // Override source language if provided
+            // Override source language if provided, skipping whisper's language detection
             if let Some(source_lang) = source_lang {
+                config.transcriber.language = Some(source_lang.clone());
                 config.translate.source_language = source_lang;
             }
             
             // Parse translation mode
             let translation_mode = parse_translation_mode(&translation_mode)?;
             config.translate.mode = translation_mode;
+            if title.is_some() {
+                config.translate.work.title = title;
+            }
+            if description.is_some() {
+                config.translate.work.description = description;
+            }
             
             // Parse transcription mode
             let transcription_mode = parse_transcription_mode(&transcription_mode)?;
             config.transcriber.mode = transcription_mode;
+            if audio_track.is_some() {
+                config.transcriber.audio_track = audio_track;
+            }
+            if prefer_embedded_subs {
+                config.transcriber.prefer_embedded_subs = true;
+            }
+            if let Some(transcript) = transcript {
+                config.transcriber.transcript = Some(transcript.to_string_lossy().to_string());
+            }
+            if tuning_profile.is_some() {
+                config.transcriber.tuning_profile = tuning_profile;
+            }
+            if forced_track {
+                config.forced.enabled = true;
+            }
+            if style.is_some() {
+                config.media.style = style;
+            }
+            if burn_in {
+                config.media.burn_in = true;
+            }
+            if multi_track {
+                config.media.multi_track.enabled = true;
+            }
+            if skip_existing {
+                config.media.existing_outputs = ExistingOutputs::SkipExisting;
+            }
+            if overwrite {
+                config.media.existing_outputs = ExistingOutputs::Overwrite;
+            }
             
             let target_languages = target_langs
                 .split(',')
                 .map(|s| s.trim().to_string())
                 .collect::<Vec<_>>();
 
+            if let Some(path) = &report {
+                ReportFormat::for_path(path)?;
+            }
+            if dry_run {
+                let plan = engine(config).build()?.workflow().plan_single_file(&input, &target_languages, output_dir.as_ref(), &cancel).await?;
+                output.show(&plan, || print!("{}", plan.render()))?;
+                return Ok(());
+            }
+
             // Create new workflow with updated config
-            let workflow = Workflow::new(config)?;
-            workflow.process_single_file(&input, &target_languages, output_dir.as_ref()).await?;
+            let (workflow, drawing) = with_progress_bars(engine(config).build()?.into_workflow(), progress.as_ref());
+            let (workflow, outcomes) = with_outcomes(workflow, output);
+            let (workflow, summaries) = with_report(workflow, output, report.as_deref());
+            let (workflow, posting) = with_webhook(workflow);
+            let cancel = match timeout {
+                Some(seconds) => cancel.with_timeout(Duration::from_secs(seconds)),
+                None => cancel.clone(),
+            };
+            let result = workflow.process_single_file(&input, &target_languages, output_dir.as_ref(), &cancel).await;
+            finish_progress_bars(workflow, drawing).await;
+            finish_webhook(posting).await;
+            finish_report(output, summaries, report.as_deref()).await?;
+            result?;
+            print_outcomes(output, outcomes).await?;
         }
-        Commands::Batch { input_dir, target_langs, source_lang, output_dir, translation_mode, transcription_mode } => {
+        Commands::Batch { input_dir, target_langs, source_lang, output_dir, translation_mode, title, description, transcription_mode, audio_track, prefer_embedded_subs, tuning_profile, forced_track, style, burn_in, multi_track, skip_existing, overwrite, timeout, resume, on_error, control_socket, dry_run, report } => {
             info!("Processing directory: {}", input_dir.display());
             
-            // Override source language if provided
+            // Override source language if provided, skipping whisper's language detection
             if let Some(source_lang) = source_lang {
+                config.transcriber.language = Some(source_lang.clone());
                 config.translate.source_language = source_lang;
             }
             
             // Parse translation mode
             let translation_mode = parse_translation_mode(&translation_mode)?;
             config.translate.mode = translation_mode;
+            if title.is_some() {
+                config.translate.work.title = title;
+            }
+            if description.is_some() {
+                config.translate.work.description = description;
+            }
             
             // Parse transcription mode
             let transcription_mode = parse_transcription_mode(&transcription_mode)?;
             config.transcriber.mode = transcription_mode;
+            if audio_track.is_some() {
+                config.transcriber.audio_track = audio_track;
+            }
+            if prefer_embedded_subs {
+                config.transcriber.prefer_embedded_subs = true;
+            }
+            if tuning_profile.is_some() {
+                config.transcriber.tuning_profile = tuning_profile;
+            }
+            if forced_track {
+                config.forced.enabled = true;
+            }
+            if style.is_some() {
+                config.media.style = style;
+            }
+            if burn_in {
+                config.media.burn_in = true;
+            }
+            if multi_track {
+                config.media.multi_track.enabled = true;
+            }
+            if skip_existing {
+                config.media.existing_outputs = ExistingOutputs::SkipExisting;
+            }
+            if overwrite {
+                config.media.existing_outputs = ExistingOutputs::Overwrite;
+            }
+            if let Some(on_error) = on_error {
+                config.batch.on_error = parse_error_policy(&on_error)?;
+            }
             
             let target_languages = target_langs
                 .split(',')
                 .map(|s| s.trim().to_string())
                 .collect::<Vec<_>>();
 
+            if let Some(path) = &report {
+                ReportFormat::for_path(path)?;
+            }
+            if dry_run {
+                let plan = engine(config).build()?.workflow().plan_directory(&input_dir, &target_languages, output_dir.as_ref(), &cancel).await?;
+                output.show(&plan, || print!("{}", plan.render()))?;
+                return Ok(());
+            }
+
             // Create new workflow with updated config
-            let workflow = Workflow::new(config)?;
-            workflow.process_directory(&input_dir, &target_languages, output_dir.as_ref()).await?;
+            let (workflow, drawing) = with_progress_bars(engine(config).build()?.into_workflow(), progress.as_ref());
+            let (workflow, outcomes) = with_outcomes(workflow, output);
+            let (workflow, summaries) = with_report(workflow, output, report.as_deref());
+            let (mut workflow, posting) = with_webhook(workflow);
+            let _control_socket = match control_socket {
+                Some(path) => {
+                    let batch_control = BatchControl::new();
+                    let (socket, events) = ControlSocket::bind(&path, batch_control.clone(), cancel.clone())?;
+                    workflow = workflow.with_control(batch_control).with_progress(events);
+                    Some(socket)
+                }
+                None => None,
+            };
+            let file_timeout = timeout.map(Duration::from_secs);
+            let result = workflow.process_directory(&input_dir, &target_languages, output_dir.as_ref(), &cancel, file_timeout, resume).await;
+            finish_progress_bars(workflow, drawing).await;
+            finish_webhook(posting).await;
+            finish_report(output, summaries, report.as_deref()).await?;
+            result?;
+            print_outcomes(output, outcomes).await?;
         }
-        Commands::Extract { input, output } => {
+        Commands::Extract { input, output: audio } => {
             info!("Extracting audio from: {}", input.display());
-            workflow.extract_audio(&input, &output).await?;
+            workflow.extract_audio(&input, &audio, &cancel).await?;
+            print_written(output, &audio)?;
         }
-        Commands::Transcribe { input, output, language, transcription_mode } => {
+        Commands::Transcribe { input, output: transcription, language, transcription_mode } => {
             info!("Transcribing audio: {}", input.display());
             
             // Parse transcription mode
@@ -407,41 +795,437 @@ async fn main() -> Result<()> {
             config.transcriber.mode = transcription_mode;
             
             // Create new workflow with updated config
-            let workflow = Workflow::new(config)?;
-            workflow.transcribe_audio(&input, &output, language.as_deref()).await?;
+            let workflow = engine(config).build()?.into_workflow();
+            workflow.transcribe_audio(&input, &transcription, language.as_deref(), &cancel).await?;
+            print_written(output, &transcription)?;
         }
-        Commands::Translate { input, output, target_langs, source_lang } => {
-            info!("Translating subtitles: {}", input.display());
-            
-            // Override source language if provided
+        Commands::Preflight { target_langs, source_lang } => {
             if let Some(source_lang) = source_lang {
                 config.translate.source_language = source_lang;
             }
-            
             let target_languages = target_langs
                 .split(',')
                 .map(|s| s.trim().to_string())
                 .collect::<Vec<_>>();
 
-            // Create new workflow with updated config
-            let workflow = Workflow::new(config)?;
-            workflow.translate_subtitles(&input, &output, &target_languages).await?;
+            let workflow = engine(config).build()?.into_workflow();
+            let report = workflow.preflight(&target_languages, &cancel).await?;
+            output.show(&report, || println!("\n{}", report.render()))?;
+            report.into_result()?;
+            if !output.is_json() {
+                println!("All preflight checks passed");
+            }
         }
-        Commands::Embed { video, subtitles, output } => {
+        Commands::Embed { video, subtitles, output: embedded, style, burn_in } => {
             info!("Embedding subtitles into video: {}", video.display());
-            workflow.embed_subtitles(&video, &subtitles, &output).await?;
+            if style.is_some() {
+                config.media.style = style;
+            }
+            if burn_in {
+                config.media.burn_in = true;
+            }
+            let workflow = engine(config).build()?.into_workflow();
+            workflow.embed_subtitles(&video, &subtitles, &embedded, &cancel).await?;
+            print_written(output, &embedded)?;
+        }
+        Commands::PreviewClip { video, at, duration, lang, subtitles, output: clip, style } => {
+            let start = parse_timecode(&at)?;
+            let duration = parse_timecode(&duration)?;
+            if duration <= 0.0 {
+                return Err(ShuroError::Config("Preview duration must be positive".to_string()).into());
+            }
+            info!("Rendering preview clip of {} at {}", video.display(), at);
+            if style.is_some() {
+                config.media.style = style;
+            }
+            let workflow = engine(config).build()?.into_workflow();
+            let clip = workflow.preview_clip(&video, subtitles.as_ref(), &lang, start..start + duration, clip.as_ref(), &cancel).await?;
+            print_written(output, &clip)?;
+        }
+        Commands::EmbedBatch { subs_dir, videos_dir, output_dir, replace } => {
+            info!("Embedding subtitles from {} into videos in {}", subs_dir.display(), videos_dir.display());
+            let embedded = workflow.embed_batch(&subs_dir, &videos_dir, output_dir.as_ref(), replace, &cancel).await?;
+            if output.is_json() {
+                output.json(&serde_json::json!({ "videos": embedded }))?;
+            }
+        }
+        Commands::Inspect { input } => {
+            let info = workflow.inspect(&input).await?;
+            output.show(&info, || print!("{}", info.render()))?;
+        }
+        Commands::Tracks { action } => match action {
+            TracksAction::List { video } => {
+                let tracks = workflow.list_subtitle_tracks(&video).await?;
+                if output.is_json() {
+                    output.json(&tracks)?;
+                } else if tracks.is_empty() {
+                    println!("No subtitle tracks found.");
+                } else {
+                    println!("{:<8} {:<10} {:<12} Title", "Track", "Language", "Codec");
+                    println!("{}", "-".repeat(60));
+                    for track in tracks {
+                        println!("{:<8} {:<10} {:<12} {}",
+                            track.index,
+                            track.language.as_deref().unwrap_or("und"),
+                            track.codec,
+                            track.title.as_deref().unwrap_or_default()
+                        );
+                    }
+                }
+            }
+            TracksAction::Remove { video, lang, output: stripped } => {
+                info!("Removing {} subtitle tracks from {}", lang, video.display());
+                let removed = workflow.remove_subtitle_tracks(&video, &lang, stripped.as_ref(), &cancel).await?;
+                if output.is_json() {
+                    output.json(&serde_json::json!({ "removed": removed, "output": stripped.as_ref().unwrap_or(&video) }))?;
+                }
+            }
+        },
+        Commands::Align { video, subtitles, output: aligned, language, transcription_mode } => {
+            info!("Aligning subtitles {} to video: {}", subtitles.display(), video.display());
+
+            // Parse transcription mode
+            let transcription_mode = parse_transcription_mode(&transcription_mode)?;
+            config.transcriber.mode = transcription_mode;
+
+            // Create new workflow with updated config
+            let workflow = engine(config).build()?.into_workflow();
+            workflow.align_subtitles(&video, &subtitles, &aligned, &language, &cancel).await?;
+            print_written(output, &aligned)?;
+        }
+        Commands::Retranslate { subtitles, target_lang, changed_terms_only } => {
+            info!("Re-translating {} subtitle files into {}", subtitles.len(), target_lang);
+            let retranslated = workflow.retranslate_subtitles(&subtitles, &target_lang, changed_terms_only, &cancel).await?;
+            if output.is_json() {
+                output.json(&serde_json::json!({ "retranslated": retranslated }))?;
+            }
+        }
+        Commands::Transcript { action: TranscriptAction::Export { input, output: transcript, source_lang, transcription_mode } } => {
+            info!("Exporting transcript of {}", input.display());
+            if let Some(source_lang) = source_lang {
+                config.transcriber.language = Some(source_lang.clone());
+                config.translate.source_language = source_lang;
+            }
+            config.transcriber.mode = parse_transcription_mode(&transcription_mode)?;
+
+            let workflow = engine(config).build()?.into_workflow();
+            let segments = workflow.export_transcript(&input, &transcript, &cancel).await?;
+            let result = serde_json::json!({ "segments": segments, "output": transcript });
+            output.show(&result, || println!("Wrote {} segments to {}", segments, transcript.display()))?;
         }
+        Commands::Serve { bind, dir, token } => {
+            let token = token.or_else(|| std::env::var("SHURO_SERVE_TOKEN").ok()).filter(|t| !t.is_empty());
+            serve::serve(engine(config).build()?, serve::ServeOptions { bind, dir, token }, &cancel).await?;
+        }
+        Commands::Transcript { action: TranscriptAction::Import { .. } } => unreachable!("transcript import is handled before setup"),
+        Commands::Project { .. } => unreachable!("project commands are handled before setup"),
+        Commands::CleanTemp { .. } => unreachable!("temporary file cleanup is handled before setup"),
+        Commands::Tm { .. } => unreachable!("translation memory commands are handled before setup"),
+        Commands::Selftest { .. } => unreachable!("the self-test is handled before setup"),
+        Commands::Subtitle { .. } => unreachable!("subtitle commands are handled before setup"),
+        Commands::Translate { .. } => unreachable!("subtitle translation is handled before setup"),
     }
 
     info!("Shuro workflow completed successfully");
     Ok(())
 }
 
-/// Setup logging to both console and file
-fn setup_logging(verbose: bool) -> Result<()> {
+/// Input file or directory whose enclosing project applies to the command
+fn command_input(command: &Commands) -> Option<&std::path::Path> {
+    match command {
+        Commands::Process { input, .. } => Some(input),
+        Commands::Batch { input_dir, .. } => Some(input_dir),
+        Commands::Extract { input, .. } => Some(input),
+        Commands::Transcribe { input, .. } => Some(input),
+        Commands::Translate { inputs, .. } => inputs.first().map(|p| p.as_path()),
+        Commands::Embed { video, .. } => Some(video),
+        Commands::PreviewClip { video, .. } => Some(video),
+        Commands::EmbedBatch { videos_dir, .. } => Some(videos_dir),
+        Commands::Inspect { input } => Some(input),
+        Commands::Tracks { action: TracksAction::List { video } | TracksAction::Remove { video, .. } } => Some(video),
+        Commands::Align { video, .. } => Some(video),
+        Commands::Transcript { action: TranscriptAction::Export { input, .. } | TranscriptAction::Import { input, .. } } => Some(input),
+        Commands::Retranslate { subtitles, .. } => subtitles.first().map(|p| p.as_path()),
+        Commands::Subtitle { action: SubtitleAction::Normalize { input, .. } } => Some(input),
+        Commands::Models { .. } | Commands::Cache { .. } | Commands::Project { .. } | Commands::Tm { .. } | Commands::CleanTemp { .. }
+        | Commands::Preflight { .. } | Commands::Selftest { .. } | Commands::Serve { .. } => None,
+    }
+}
+
+/// A cached transcription without its segments, for JSON listings
+fn cached_transcription(item: &TranscriptionCache) -> serde_json::Value {
+    serde_json::json!({
+        "model": item.model,
+        "language": item.language,
+        "audio_path": item.audio_path,
+        "segments": item.transcription.segments.len(),
+        "cached_at": item.cached_at,
+    })
+}
+
+/// `workflow` reporting its progress to the progress bars, when they are drawn
+fn with_progress_bars(workflow: Workflow, progress: Option<&ProgressRenderer>) -> (Workflow, Option<tokio::task::JoinHandle<()>>) {
+    match progress {
+        Some(renderer) => {
+            let (events, drawing) = renderer.start();
+            (workflow.with_progress(events), Some(drawing))
+        }
+        None => (workflow, None),
+    }
+}
+
+/// `workflow` reporting the outcome of every file, when results are printed as JSON
+fn with_outcomes(workflow: Workflow, output: Output) -> (Workflow, Option<tokio::task::JoinHandle<Vec<FileOutcome>>>) {
+    if !output.is_json() {
+        return (workflow, None);
+    }
+    let (events, outcomes) = collect_outcomes();
+    (workflow.with_progress(events), Some(outcomes))
+}
+
+/// `workflow` collecting the run report, unless there is neither a table to print nor a report
+/// file to write
+fn with_report(workflow: Workflow, output: Output, path: Option<&Path>) -> (Workflow, Option<tokio::task::JoinHandle<RunReport>>) {
+    if output.is_json() && path.is_none() {
+        return (workflow, None);
+    }
+    let (events, report) = RunReport::collect();
+    (workflow.with_progress(events), Some(report))
+}
+
+/// Print the report of a finished run as a table and write it to `path`, if given; the workflow
+/// must have been dropped
+async fn finish_report(output: Output, report: Option<tokio::task::JoinHandle<RunReport>>, path: Option<&Path>) -> Result<()> {
+    let Some(report) = report else {
+        return Ok(());
+    };
+    let report = report.await?;
+    if !output.is_json() {
+        print!("{}", report.render());
+    }
+    if let Some(path) = path {
+        report.write(path).await?;
+        info!("📋 Wrote run report: {}", path.display());
+    }
+    Ok(())
+}
+
+/// `workflow` posting the outcome of every file to the configured webhook, if any
+fn with_webhook(workflow: Workflow) -> (Workflow, Option<tokio::task::JoinHandle<()>>) {
+    match Webhook::new(&workflow.config().webhook) {
+        Some(webhook) => {
+            let (events, posting) = webhook.start();
+            (workflow.with_progress(events), Some(posting))
+        }
+        None => (workflow, None),
+    }
+}
+
+/// Wait until the last webhook notification of a finished run is posted; the workflow must have
+/// been dropped
+async fn finish_webhook(posting: Option<tokio::task::JoinHandle<()>>) {
+    if let Some(posting) = posting {
+        let _ = posting.await;
+    }
+}
+
+/// Print the outcome of every file of a finished run; the workflow must have been dropped
+async fn print_outcomes(output: Output, outcomes: Option<tokio::task::JoinHandle<Vec<FileOutcome>>>) -> Result<()> {
+    if let Some(outcomes) = outcomes {
+        output.json(&serde_json::json!({ "files": outcomes.await? }))?;
+    }
+    Ok(())
+}
+
+/// Print the file a command wrote, when results are printed as JSON
+fn print_written(output: Output, path: &std::path::Path) -> Result<()> {
+    if output.is_json() {
+        output.json(&serde_json::json!({ "output": path }))?;
+    }
+    Ok(())
+}
+
+/// Print the files a cache export or import moved, by kind
+fn print_archive_report(verb: &str, report: &cache::ArchiveReport) {
+    for (kind, count) in &report.files {
+        println!("{:<15} {} files", kind, count);
+    }
+    println!("{} {} files ({})", verb, report.files.values().sum::<usize>(), report.archive.display());
+}
+
+/// Builder of the engine over `config`, keeping its caches, job states and temporary files in
+/// `data_dir` and applying `project`
+fn engine(config: Config, data_dir: &Path, project: Option<&Project>) -> ShuroBuilder {
+    let builder = Shuro::builder().config(config).data_dir(data_dir);
+    match project {
+        Some(project) => builder.project(project.clone()),
+        None => builder,
+    }
+}
+
+/// Wait until the progress bars have drawn the last events of `workflow`
+async fn finish_progress_bars(workflow: Workflow, drawing: Option<tokio::task::JoinHandle<()>>) {
+    drop(workflow);
+    if let Some(drawing) = drawing {
+        let _ = drawing.await;
+    }
+}
+
+/// Remove the temporary directories of runs that are no longer running
+fn clean_temp(config: &Config, all: bool, dry_run: bool, output: Output) -> Result<()> {
+    let audio_cache_dir = std::env::current_dir().unwrap_or_default().join(&config.transcriber.cache_dir).join("audio");
+    let leftovers = temp::leftovers(Path::new(&config.temp_dir), &audio_cache_dir, all);
+    if dry_run || leftovers.is_empty() {
+        return output.show(&serde_json::json!({ "leftovers": leftovers, "removed": [] }), || {
+            if leftovers.is_empty() {
+                println!("No leftover temporary files found.");
+            }
+            for path in &leftovers {
+                println!("Would remove {}", path.display());
+            }
+        });
+    }
+
+    let removed = temp::remove_leftovers(&leftovers);
+    output.show(&serde_json::json!({ "leftovers": leftovers, "removed": removed }), || {
+        for path in &removed {
+            println!("Removed {}", path.display());
+        }
+        println!("Removed {} of {} leftover temporary files", removed.len(), leftovers.len());
+    })
+}
+
+/// Verify every downloaded model, failing when any is corrupt or cannot be loaded
+async fn verify_models(setup_manager: &SetupManager, config: &Config, output: Output) -> Result<()> {
+    if !output.is_json() {
+        println!("\n{:<20} {:<30} Status", "Name", "Filename");
+        println!("{}", "-".repeat(75));
+    }
+
+    let mut failed = 0;
+    let mut verified = Vec::new();
+    for model in setup_manager.get_available_models() {
+        let Some(status) = setup_manager.verify_model(&model, &config.transcriber.binary_path, Path::new(&config.temp_dir)).await? else {
+            continue;
+        };
+        if output.is_json() {
+            failed += usize::from(!matches!(status, ModelStatus::Verified | ModelStatus::Unrecorded));
+            verified.push(serde_json::json!({ "name": model.name, "filename": model.filename, "status": status }));
+            continue;
+        }
+        let status = match status {
+            ModelStatus::Verified => "OK".to_string(),
+            ModelStatus::Unrecorded => "Loads (no recorded checksum)".to_string(),
+            ModelStatus::ChecksumMismatch { expected, actual } => {
+                failed += 1;
+                format!("CORRUPT: checksum {} does not match recorded {}", &actual[..12], &expected[..expected.len().min(12)])
+            }
+            ModelStatus::LoadFailed(reason) => {
+                failed += 1;
+                format!("FAILS TO LOAD: {}", reason)
+            }
+        };
+        println!("{:<20} {:<30} {}", model.name, model.filename, status);
+    }
+
+    if output.is_json() {
+        output.json(&verified)?;
+    }
+    if failed > 0 {
+        return Err(ShuroError::Config(format!(
+            "{} model(s) failed verification; delete them and run `shuro models --download` again", failed
+        )).into());
+    }
+    if !output.is_json() {
+        println!("\nAll downloaded models verified");
+    }
+    Ok(())
+}
+
+/// Run a project subcommand against the current directory
+async fn manage_translation_memory(config: &Config, action: TmAction, output: Output) -> Result<()> {
+    let memory = TranslationMemory::new(&config.translate);
+    match action {
+        TmAction::Export { output: tmx, target_lang } => {
+            let entries: Vec<_> = memory.entries().await?
+                .into_iter()
+                .filter(|entry| target_lang.as_ref().is_none_or(|lang| &entry.target_language == lang))
+                .collect();
+            std::fs::write(&tmx, memory::to_tmx(&entries))?;
+            let result = serde_json::json!({ "exported": entries.len(), "output": tmx });
+            output.show(&result, || println!("Exported {} translations from {} to {}", entries.len(), memory.path().display(), tmx.display()))?;
+        }
+        TmAction::Import { input } => {
+            let content = std::fs::read_to_string(&input)?;
+            let entries = memory::parse_tmx(&content)
+                .map_err(|e| ShuroError::Config(format!("Invalid TMX file {}: {}", input.display(), e)))?;
+            let total = entries.len();
+            let added = memory.add(entries).await?;
+            let result = serde_json::json!({ "imported": added, "total": total });
+            output.show(&result, || println!("Imported {} of {} translations from {} into {}", added, total, input.display(), memory.path().display()))?;
+        }
+    }
+    Ok(())
+}
+
+fn manage_project(action: ProjectAction, output: Output) -> Result<()> {
+    let current_dir = std::env::current_dir()?;
+    let enclosing = || -> Result<Project> {
+        Project::discover(&current_dir)?.ok_or_else(|| ShuroError::Config(format!(
+            "No project found in {} or its parents; run `shuro project init` first",
+            current_dir.display()
+        )).into())
+    };
+
+    match action {
+        ProjectAction::Init { name } => {
+            let project = Project::init(&current_dir, &name)?;
+            let result = serde_json::json!({ "name": name, "root": project.root() });
+            output.show(&result, || println!("Created project '{}' in {}", name, project.root().display()))?;
+        }
+        ProjectAction::Show => {
+            let project = enclosing()?;
+            let file = project.file();
+            if output.is_json() {
+                output.json(&serde_json::json!({
+                    "root": project.root(),
+                    "memory": project.memory_dir(),
+                    "project": file,
+                }))?;
+                return Ok(());
+            }
+            println!("\nProject: {}", file.name);
+            println!("Root: {}", project.root().display());
+            println!("Translation memory: {}", project.memory_dir().display());
+            println!("Models: {:?}", file.models);
+            for (title, terms) in [("Glossary", &file.glossary), ("Characters", &file.characters)] {
+                println!("\n{}:", title);
+                for term in terms {
+                    let mut translations: Vec<_> = term.translations.iter().collect();
+                    translations.sort();
+                    let translations: Vec<String> = translations.iter().map(|(lang, tr)| format!("{}={}", lang, tr)).collect();
+                    println!("  {:<30} {}", term.source, translations.join(", "));
+                }
+            }
+        }
+        ProjectAction::AddTerm { source, lang, translation } => {
+            enclosing()?.with_term(&source, &lang, &translation, false).save()?;
+            let result = serde_json::json!({ "source": source, "language": lang, "translation": translation });
+            output.show(&result, || println!("Added glossary term '{}' => '{}' ({})", source, translation, lang))?;
+        }
+        ProjectAction::AddCharacter { name, lang, translation } => {
+            enclosing()?.with_term(&name, &lang, &translation, true).save()?;
+            let result = serde_json::json!({ "source": name, "language": lang, "translation": translation });
+            output.show(&result, || println!("Added character '{}' => '{}' ({})", name, translation, lang))?;
+        }
+    }
+    Ok(())
+}
+
+/// Setup logging to both console and file (`log/` of `state_dir`)
+fn setup_logging(state_dir: &std::path::Path, verbose: bool, output: Output, progress: Option<&ProgressRenderer>) -> Result<()> {
     // Create log directory
-    let shuro_dir = std::env::current_dir()?.join(".shuro");
-    let log_dir = shuro_dir.join("log");
+    let log_dir = state_dir.join("log");
     std::fs::create_dir_all(&log_dir)?;
 
     // Set up file appender with daily rotation
@@ -453,13 +1237,28 @@ fn setup_logging(verbose: bool) -> Result<()> {
     // Determine log level
     let log_level = if verbose { Level::DEBUG } else { Level::INFO };
 
-    // Create console layer
-    let console_layer = fmt::layer()
+    // Create console layer; above progress bars, only warnings and errors are shown, and JSON
+    // output keeps stdout to itself
+    let console_writer = if output.is_json() {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
+    let console_layer = progress.is_none().then(|| fmt::layer()
+        .with_writer(console_writer)
         .with_target(false)
         .with_thread_ids(true)
         .with_thread_names(true)
         .with_file(true)
-        .with_line_number(true);
+        .with_line_number(true));
+    let progress_layer = progress.map(|renderer| {
+        let renderer = renderer.clone();
+        fmt::layer()
+            .with_writer(move || renderer.log_writer())
+            .with_target(false)
+            .without_time()
+            .with_filter(LevelFilter::WARN)
+    });
 
     // Create file layer
     let file_layer = fmt::layer()
@@ -475,6 +1274,7 @@ fn setup_logging(verbose: bool) -> Result<()> {
     let subscriber = tracing_subscriber::registry()
         .with(EnvFilter::from_default_env().add_directive(log_level.into()))
         .with(console_layer)
+        .with(progress_layer)
         .with(file_layer);
 
     // Initialize the subscriber
@@ -487,17 +1287,6 @@ fn setup_logging(verbose: bool) -> Result<()> {
     Ok(())
 }
 
-/// Format duration in seconds to human readable string
-fn format_duration(seconds: u64) -> String {
-    if seconds < 60 {
-        format!("{}s", seconds)
-    } else if seconds < 3600 {
-        format!("{}m {}s", seconds / 60, seconds % 60)
-    } else {
-        format!("{}h {}m", seconds / 3600, (seconds % 3600) / 60)
-    }
-}
-
 /// Parse transcription mode from string
 fn parse_transcription_mode(mode: &str) -> Result<TranscriptionMode> {
     match mode.to_lowercase().as_str() {
@@ -510,6 +1299,19 @@ fn parse_transcription_mode(mode: &str) -> Result<TranscriptionMode> {
     }
 }
 
+/// Parse the batch error policy from string
+fn parse_error_policy(policy: &str) -> Result<ErrorPolicy> {
+    match policy.to_lowercase().as_str() {
+        "continue" => Ok(ErrorPolicy::Continue),
+        "abort" => Ok(ErrorPolicy::Abort),
+        "quarantine" => Ok(ErrorPolicy::Quarantine),
+        _ => Err(ShuroError::Config(format!(
+            "Invalid error policy '{}'. Valid policies: continue, abort, quarantine",
+            policy
+        )).into()),
+    }
+}
+
 /// Parse translation mode from string
 fn parse_translation_mode(mode: &str) -> Result<TranslationMode> {
     match mode.to_lowercase().as_str() {
@@ -517,8 +1319,9 @@ fn parse_translation_mode(mode: &str) -> Result<TranslationMode> {
         "context" => Ok(TranslationMode::Context),
         "nlp" => Ok(TranslationMode::Nlp),
         "llm" => Ok(TranslationMode::Llm),
+        "adaptive" => Ok(TranslationMode::Adaptive),
         _ => Err(ShuroError::Config(format!(
-            "Invalid translation mode '{}'. Valid modes: simple, context, nlp, llm", 
+            "Invalid translation mode '{}'. Valid modes: simple, context, nlp, llm, adaptive", 
             mode
         )).into()),
     }
@@ -0,0 +1,194 @@
+// Prometheus metrics of `shuro serve`
+//
+// `GET /metrics` answers in the Prometheus text format, so operators can graph the server and
+// alert on a stuck pipeline, e.g. jobs running while `shuro_last_progress_timestamp_seconds`
+// stops moving. Jobs are counted by status when scraped; everything else is gathered from the
+// workflow's progress events: videos finished and failed, segments translated and those taken
+// from the translation cache, audio and transcription cache hits, and how long whisper ran per
+// video (from entering the transcription stage until the transcription is final; translation
+// overlaps it). Model request latencies come from the workflow's translation metrics, labelled
+// by model.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use shuro_core::progress::{ProgressEvent, Stage};
+use shuro_core::translate::{LATENCY_BUCKETS_MS, TranslationMetrics};
+
+/// Upper bounds of the transcription time buckets (seconds)
+const TRANSCRIPTION_BUCKETS: [f64; 8] = [30.0, 60.0, 120.0, 300.0, 600.0, 1200.0, 1800.0, 3600.0];
+
+/// Counts of observations up to each bound, with their sum
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, bounds: &[f64], value: f64) {
+        self.buckets.resize(bounds.len(), 0);
+        for (bucket, bound) in self.buckets.iter_mut().zip(bounds) {
+            if value <= *bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Collected {
+    /// Videos by outcome
+    files: BTreeMap<&'static str, u64>,
+    segments_translated: u64,
+    translation_cache_hits: u64,
+    audio_cache_hits: u64,
+    transcription_cache_hits: u64,
+    transcription: Histogram,
+    /// When each video entered the transcription stage
+    transcribing: HashMap<PathBuf, Instant>,
+    last_progress: Option<SystemTime>,
+}
+
+/// Metrics gathered from the progress events of the server's workflow
+#[derive(Debug, Default)]
+pub struct ServerMetrics {
+    collected: Mutex<Collected>,
+}
+
+impl ServerMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, event: &ProgressEvent) {
+        let mut collected = self.collected.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        collected.last_progress = Some(SystemTime::now());
+        match event {
+            ProgressEvent::StageStarted { file, stage: Stage::Transcribing } => {
+                collected.transcribing.insert(file.clone(), Instant::now());
+            }
+            ProgressEvent::Transcribed { file, .. } => {
+                if let Some(started) = collected.transcribing.remove(file) {
+                    collected.transcription.observe(&TRANSCRIPTION_BUCKETS, started.elapsed().as_secs_f64());
+                }
+            }
+            ProgressEvent::FileSummarized { summary } => {
+                for language in &summary.languages {
+                    collected.segments_translated += language.segments as u64;
+                    collected.translation_cache_hits += language.cache_hits as u64;
+                }
+                collected.audio_cache_hits += summary.audio_cached as u64;
+                collected.transcription_cache_hits += summary.transcription_cached as u64;
+            }
+            ProgressEvent::FileCompleted { .. } => *collected.files.entry("completed").or_insert(0) += 1,
+            ProgressEvent::FileFailed { file, .. } => {
+                collected.transcribing.remove(file);
+                *collected.files.entry("failed").or_insert(0) += 1;
+            }
+            _ => {}
+        }
+    }
+
+    /// Text exposition of the metrics, with the number of jobs per status
+    pub fn render(&self, jobs: &BTreeMap<&str, usize>, translation: &TranslationMetrics) -> String {
+        let collected = self.collected.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut out = String::new();
+
+        header(&mut out, "shuro_jobs", "gauge", "Jobs by status");
+        for (status, count) in jobs {
+            let _ = writeln!(out, "shuro_jobs{{status=\"{}\"}} {}", status, count);
+        }
+        header(&mut out, "shuro_files_total", "counter", "Videos processed, by outcome");
+        for outcome in ["completed", "failed"] {
+            let _ = writeln!(out, "shuro_files_total{{outcome=\"{}\"}} {}", outcome, collected.files.get(outcome).unwrap_or(&0));
+        }
+        header(&mut out, "shuro_segments_translated_total", "counter", "Segments translated, counted per target language");
+        let _ = writeln!(out, "shuro_segments_translated_total {}", collected.segments_translated);
+        header(&mut out, "shuro_cache_hits_total", "counter", "Results taken from the cache, by cache");
+        for (cache, hits) in [
+            ("audio", collected.audio_cache_hits),
+            ("transcription", collected.transcription_cache_hits),
+            ("translation", collected.translation_cache_hits),
+        ] {
+            let _ = writeln!(out, "shuro_cache_hits_total{{cache=\"{}\"}} {}", cache, hits);
+        }
+
+        header(&mut out, "shuro_transcription_seconds", "histogram", "Time whisper took per video");
+        histogram(&mut out, "shuro_transcription_seconds", "", &TRANSCRIPTION_BUCKETS, &collected.transcription);
+
+        header(&mut out, "shuro_model_request_seconds", "histogram", "Latency of model requests, by model");
+        let bounds: Vec<f64> = LATENCY_BUCKETS_MS.iter().map(|bound| bound / 1000.0).collect();
+        for latencies in translation.latency_histograms() {
+            let observed = Histogram { buckets: latencies.buckets, sum: latencies.sum_ms / 1000.0, count: latencies.count };
+            let labels = format!("model=\"{}\",", escape_label(&latencies.model));
+            histogram(&mut out, "shuro_model_request_seconds", &labels, &bounds, &observed);
+        }
+
+        if let Some(last) = collected.last_progress {
+            header(&mut out, "shuro_last_progress_timestamp_seconds", "gauge", "When the workflow last reported progress");
+            let seconds = last.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64();
+            let _ = writeln!(out, "shuro_last_progress_timestamp_seconds {:.3}", seconds);
+        }
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Buckets, sum and count of `observed`; `labels` go before `le` and end with a comma
+fn histogram(out: &mut String, name: &str, labels: &str, bounds: &[f64], observed: &Histogram) {
+    for (i, bound) in bounds.iter().enumerate() {
+        let count = observed.buckets.get(i).copied().unwrap_or(0);
+        let _ = writeln!(out, "{}_bucket{{{}le=\"{}\"}} {}", name, labels, bound, count);
+    }
+    let _ = writeln!(out, "{}_bucket{{{}le=\"+Inf\"}} {}", name, labels, observed.count);
+    let labels = labels.trim_end_matches(',');
+    let labels = if labels.is_empty() { String::new() } else { format!("{{{}}}", labels) };
+    let _ = writeln!(out, "{}_sum{} {}", name, labels, observed.sum);
+    let _ = writeln!(out, "{}_count{} {}", name, labels, observed.count);
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use shuro_core::translate::metrics::TokenUsage;
+
+    #[test]
+    fn test_render_metrics() {
+        let metrics = ServerMetrics::new();
+        let file = PathBuf::from("e01.mkv");
+        metrics.record(&ProgressEvent::StageStarted { file: file.clone(), stage: Stage::Transcribing });
+        metrics.record(&ProgressEvent::Transcribed { file: file.clone(), segments: 120 });
+        metrics.record(&ProgressEvent::FileCompleted { file });
+        metrics.record(&ProgressEvent::FileFailed { file: "e02.mkv".into(), error: "no audio stream".into() });
+
+        let translation = TranslationMetrics::new(0);
+        translation.record("gemma3:12b", Duration::from_millis(700), TokenUsage::default());
+        let jobs = BTreeMap::from([("queued", 2), ("running", 1)]);
+        let text = metrics.render(&jobs, &translation);
+
+        assert!(text.contains("# TYPE shuro_jobs gauge\nshuro_jobs{status=\"queued\"} 2\nshuro_jobs{status=\"running\"} 1\n"));
+        assert!(text.contains("shuro_files_total{outcome=\"completed\"} 1\nshuro_files_total{outcome=\"failed\"} 1\n"));
+        assert!(text.contains("shuro_transcription_seconds_bucket{le=\"30\"} 1\n"));
+        assert!(text.contains("shuro_transcription_seconds_count 1\n"));
+        assert!(text.contains("shuro_model_request_seconds_bucket{model=\"gemma3:12b\",le=\"0.5\"} 0\n"));
+        assert!(text.contains("shuro_model_request_seconds_bucket{model=\"gemma3:12b\",le=\"1\"} 1\n"));
+        assert!(text.contains("shuro_model_request_seconds_sum{model=\"gemma3:12b\"} 0.7\n"));
+        assert!(text.contains("shuro_last_progress_timestamp_seconds "));
+    }
+}
@@ -0,0 +1,146 @@
+// Machine-readable output
+//
+// With the global `--json` flag, every command prints a JSON document on stdout instead of its
+// tables and messages, so shuro can be scripted without scraping log lines: listings become
+// arrays of objects, reports their checks, and `process` and `batch` the outcome of every file
+// with the subtitles written per language, collected from the workflow's progress events.
+// Console log lines then go to stderr, and a failing command prints `{"error": "..."}` as its
+// last document and exits with status 1.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use shuro_core::progress::{ProgressEvent, ProgressSender};
+
+/// Where command results go: people or programs
+#[derive(Debug, Clone, Copy)]
+pub struct Output {
+    json: bool,
+}
+
+impl Output {
+    pub fn new(json: bool) -> Self {
+        Self { json }
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.json
+    }
+
+    /// Print `value` as JSON
+    pub fn json<T: Serialize + ?Sized>(&self, value: &T) -> anyhow::Result<()> {
+        println!("{}", serde_json::to_string_pretty(value)?);
+        Ok(())
+    }
+
+    /// Print `value` as JSON, or run `text` to print it for people
+    pub fn show<T: Serialize + ?Sized>(&self, value: &T, text: impl FnOnce()) -> anyhow::Result<()> {
+        if self.json {
+            self.json(value)
+        } else {
+            text();
+            Ok(())
+        }
+    }
+
+    /// Print the error a command failed with
+    pub fn error(&self, error: &anyhow::Error) {
+        let document = serde_json::json!({ "error": format!("{:#}", error) });
+        println!("{}", serde_json::to_string_pretty(&document).unwrap_or_default());
+    }
+}
+
+/// How a file of `process` or `batch` ended
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    /// Started but never finished, as when the run was cancelled
+    Started,
+    Completed,
+    Failed,
+    Skipped,
+}
+
+/// Outcome of one file of `process` or `batch`
+#[derive(Debug, Clone, Serialize)]
+pub struct FileOutcome {
+    pub file: PathBuf,
+    pub status: FileStatus,
+    /// Subtitle file written per language
+    pub subtitles: BTreeMap<String, PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Collect the outcome of every file from the progress events sent to the returned sender; the
+/// task ends with them once every sender is dropped
+pub fn collect_outcomes() -> (ProgressSender, JoinHandle<Vec<FileOutcome>>) {
+    let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+    let task = tokio::spawn(async move {
+        let mut outcomes = Vec::new();
+        while let Some(event) = events_rx.recv().await {
+            record(&mut outcomes, event);
+        }
+        outcomes
+    });
+    (events_tx, task)
+}
+
+fn record(outcomes: &mut Vec<FileOutcome>, event: ProgressEvent) {
+    let (file, status, error) = match event {
+        ProgressEvent::FileStarted { file, .. } => (file, FileStatus::Started, None),
+        ProgressEvent::LanguageCompleted { file, language, subtitles } => {
+            if let Some(outcome) = outcomes.iter_mut().rev().find(|o| o.file == file) {
+                outcome.subtitles.insert(language, subtitles);
+            }
+            return;
+        }
+        ProgressEvent::FileCompleted { file } => (file, FileStatus::Completed, None),
+        ProgressEvent::FileFailed { file, error } => (file, FileStatus::Failed, Some(error)),
+        ProgressEvent::FileSkipped { file } => (file, FileStatus::Skipped, None),
+        _ => return,
+    };
+    match outcomes.iter_mut().rev().find(|o| o.file == file && o.status == FileStatus::Started) {
+        Some(outcome) => {
+            outcome.status = status;
+            outcome.error = error;
+        }
+        None => outcomes.push(FileOutcome { file, status, subtitles: BTreeMap::new(), error }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_outcomes() {
+        let mut outcomes = Vec::new();
+        let events = vec![
+            ProgressEvent::FileSkipped { file: "a.mp4".into() },
+            ProgressEvent::FileStarted { file: "b.mp4".into(), languages: vec!["ja".into(), "ko".into()] },
+            ProgressEvent::LanguageCompleted { file: "b.mp4".into(), language: "ja".into(), subtitles: "b.ja.srt".into() },
+            ProgressEvent::FileFailed { file: "b.mp4".into(), error: "translation failed".into() },
+            ProgressEvent::FileStarted { file: "c.mp4".into(), languages: vec!["ja".into()] },
+        ];
+        for event in events {
+            record(&mut outcomes, event);
+        }
+
+        let statuses: Vec<_> = outcomes.iter().map(|o| (o.file.to_str().unwrap(), o.status)).collect();
+        assert_eq!(statuses, vec![
+            ("a.mp4", FileStatus::Skipped),
+            ("b.mp4", FileStatus::Failed),
+            ("c.mp4", FileStatus::Started),
+        ]);
+        assert_eq!(outcomes[1].subtitles.get("ja"), Some(&PathBuf::from("b.ja.srt")));
+        assert_eq!(outcomes[1].error.as_deref(), Some("translation failed"));
+        assert_eq!(
+            serde_json::to_value(&outcomes[0]).unwrap(),
+            serde_json::json!({ "file": "a.mp4", "status": "skipped", "subtitles": {} })
+        );
+    }
+}
@@ -0,0 +1,195 @@
+// Terminal progress bars
+//
+// `process` and `batch` draw the progress events of the workflow (see progress.rs in shuro-core)
+// instead of a wall of log lines: one line per video naming the stage it is in, and under it one
+// bar per target language counting translated segments, of the transcription's segments once
+// they are known, with the estimated time left. Messages below warnings then go to the log file
+// only; `--no-progress`, `--verbose`, or output that is not a terminal keep the plain console log.
+
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use shuro_core::progress::{ProgressEvent, ProgressSender};
+
+/// Bars of one video
+struct FileBars {
+    stage: ProgressBar,
+    languages: HashMap<String, ProgressBar>,
+}
+
+/// Draws progress events as terminal progress bars
+#[derive(Clone, Default)]
+pub struct ProgressRenderer {
+    multi: MultiProgress,
+}
+
+impl ProgressRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writer for console log lines, printed above the bars
+    pub fn log_writer(&self) -> LogWriter {
+        LogWriter { multi: self.multi.clone() }
+    }
+
+    /// Start drawing; the returned sender takes the workflow's progress events, and the task
+    /// ends once every sender is dropped
+    pub fn start(&self) -> (ProgressSender, JoinHandle<()>) {
+        let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+        let multi = self.multi.clone();
+        let task = tokio::spawn(async move {
+            let mut files = HashMap::new();
+            while let Some(event) = events_rx.recv().await {
+                apply(&multi, &mut files, event);
+            }
+        });
+        (events_tx, task)
+    }
+}
+
+fn stage_style() -> ProgressStyle {
+    ProgressStyle::default_spinner()
+        .template("{spinner:.green} {prefix:.bold} [{elapsed_precise}] {msg}")
+        .unwrap()
+}
+
+fn counter_style() -> ProgressStyle {
+    ProgressStyle::default_spinner()
+        .template("    {prefix:>6} {pos} segments translated{msg}")
+        .unwrap()
+}
+
+fn bar_style() -> ProgressStyle {
+    ProgressStyle::default_bar()
+        .template("    {prefix:>6} [{bar:40.cyan/blue}] {pos}/{len}{msg}")
+        .unwrap()
+        .progress_chars("#>-")
+}
+
+/// `, ETA 3m 20s`
+fn eta_message(eta: Option<f64>) -> String {
+    match eta {
+        Some(seconds) if seconds >= 60.0 => format!(", ETA {}m {:02}s", seconds as u64 / 60, seconds as u64 % 60),
+        Some(seconds) => format!(", ETA {}s", seconds as u64),
+        None => String::new(),
+    }
+}
+
+fn apply(multi: &MultiProgress, files: &mut HashMap<PathBuf, FileBars>, event: ProgressEvent) {
+    match event {
+        ProgressEvent::FileStarted { file, languages } => {
+            let stage = multi.add(ProgressBar::new_spinner().with_style(stage_style()));
+            stage.set_prefix(file.file_name().unwrap_or_default().to_string_lossy().into_owned());
+            stage.set_message("starting");
+            stage.enable_steady_tick(Duration::from_millis(120));
+            let languages = languages.into_iter()
+                .map(|language| {
+                    let bar = multi.add(ProgressBar::new_spinner().with_style(counter_style()));
+                    bar.set_prefix(language.clone());
+                    (language, bar)
+                })
+                .collect();
+            files.insert(file, FileBars { stage, languages });
+        }
+        ProgressEvent::StageStarted { file, stage } => {
+            if let Some(bars) = files.get(&file) {
+                bars.stage.set_message(stage.name());
+            }
+        }
+        ProgressEvent::Translated { file, language, segments, total, eta } => {
+            if let Some(bar) = files.get(&file).and_then(|bars| bars.languages.get(&language)) {
+                if let Some(total) = total {
+                    set_total(bar, total);
+                }
+                bar.set_position(segments as u64);
+                bar.set_message(eta_message(eta));
+            }
+        }
+        ProgressEvent::Transcribed { file, segments } => {
+            if let Some(bars) = files.get(&file) {
+                for bar in bars.languages.values() {
+                    set_total(bar, segments);
+                }
+            }
+        }
+        ProgressEvent::LanguageCompleted { file, language, subtitles } => {
+            if let Some(bar) = files.get(&file).and_then(|bars| bars.languages.get(&language)) {
+                bar.finish_with_message(format!(" -> {}", subtitles.display()));
+            }
+        }
+        ProgressEvent::LanguageSkipped { file, language, reason } => {
+            if let Some(bar) = files.get(&file).and_then(|bars| bars.languages.get(&language)) {
+                bar.finish_with_message(format!(" skipped ({})", reason));
+            }
+        }
+        ProgressEvent::FileCompleted { file } => finish(files, &file, "done".to_string()),
+        ProgressEvent::FileFailed { file, error } => finish(files, &file, format!("failed: {}", error)),
+        ProgressEvent::FileSkipped { file } => {
+            if files.contains_key(&file) {
+                finish(files, &file, "skipped".to_string());
+            } else {
+                let _ = multi.println(format!("  {} skipped", file.display()));
+            }
+        }
+        // Lines of their own would scroll the bars away; summaries are printed after the run
+        ProgressEvent::SegmentTranslated { .. } | ProgressEvent::QualityRejected { .. } | ProgressEvent::FileSummarized { .. } => {}
+        ProgressEvent::Paused => {
+            let _ = multi.println("⏸️  Batch paused");
+        }
+        ProgressEvent::Resumed => {
+            let _ = multi.println("▶️  Batch resumed");
+        }
+    }
+}
+
+/// Count `bar` towards `total` segments
+fn set_total(bar: &ProgressBar, total: usize) {
+    if bar.length() != Some(total as u64) {
+        bar.set_length(total as u64);
+        bar.set_style(bar_style());
+    }
+}
+
+/// Stop the bars of `file`, leaving `message` on its line
+fn finish(files: &mut HashMap<PathBuf, FileBars>, file: &Path, message: String) {
+    if let Some(bars) = files.remove(file) {
+        for bar in bars.languages.values().filter(|bar| !bar.is_finished()) {
+            bar.abandon();
+        }
+        bars.stage.finish_with_message(message);
+    }
+}
+
+/// Console log output printed above the progress bars
+pub struct LogWriter {
+    multi: MultiProgress,
+}
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.multi.suspend(|| std::io::stderr().write_all(buf))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stderr().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eta_message() {
+        assert_eq!(eta_message(Some(200.0)), ", ETA 3m 20s");
+        assert_eq!(eta_message(Some(42.4)), ", ETA 42s");
+        assert_eq!(eta_message(None), "");
+    }
+}
@@ -0,0 +1,531 @@
+// HTTP API (`shuro serve`)
+//
+// Runs shuro on a headless machine that takes jobs over HTTP. Jobs run one at a time, in the
+// order they were submitted, on a single `Workflow` (transcription and translation already keep
+// the GPU busy), and the workflow's progress events keep each job's status current. Endpoints,
+// answering JSON unless noted:
+//
+// - `POST /jobs`: submit a video, either a JSON body `{"input": "/path/on/server.mkv",
+//   "target_languages": ["ja"]}` or the video itself as the body with
+//   `?filename=episode01.mkv&target_langs=ja,de`
+// - `GET /jobs`: all jobs; `GET /jobs/{id}`: one job with its progress
+// - `GET /jobs/{id}/subtitles/{lang}`: the SRT file of a finished language
+// - `DELETE /jobs/{id}`: cancel a queued or running job
+// - `GET /cache`: cache statistics; `DELETE /cache`: clear the transcription, audio and
+//   translation caches
+// - `GET /metrics`: Prometheus metrics in the text format (see metrics.rs)
+//
+// Uploads go to `{dir}/uploads/{id}`, removed once their job has finished, failed or been
+// cancelled, and outputs to `{dir}/jobs/{id}` (`--dir`, default `.shuro/serve`). With a token (`--token` or `SHURO_SERVE_TOKEN`), every request needs an
+// `Authorization: Bearer <token>` header. The server listens on 127.0.0.1:8080 unless told
+// otherwise; put it behind a reverse proxy with TLS before exposing it.
+
+use anyhow::Result;
+use hyper::body::HttpBody;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use shuro_core::Shuro;
+use shuro_core::cancel::Cancellation;
+use shuro_core::config::Config;
+use shuro_core::progress::ProgressEvent;
+use shuro_core::quality::QualityValidator;
+use shuro_core::transcribe::{TranscriberFactory, TranscriberTrait};
+use shuro_core::translate::{BaseTranslator, TranslationMetrics};
+use shuro_core::workflow::Workflow;
+
+use crate::metrics::ServerMetrics;
+
+/// Where and how the server listens
+pub struct ServeOptions {
+    pub bind: SocketAddr,
+    /// Directory of uploads and job outputs
+    pub dir: PathBuf,
+    /// Bearer token every request must carry
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// A submitted video and how far it got
+#[derive(Debug, Clone, Serialize)]
+struct Job {
+    id: u64,
+    status: JobStatus,
+    input: PathBuf,
+    target_languages: Vec<String>,
+    output_dir: PathBuf,
+    /// Segments of the final transcription, once known
+    transcribed_segments: Option<usize>,
+    /// Segments translated so far per language
+    translated_segments: BTreeMap<String, usize>,
+    /// Subtitle files of the finished languages
+    subtitles: BTreeMap<String, PathBuf>,
+    error: Option<String>,
+    #[serde(skip)]
+    cancel: Cancellation,
+    /// Directory of the uploaded video, removed when the job ends
+    #[serde(skip)]
+    upload: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubmitRequest {
+    input: PathBuf,
+    target_languages: Vec<String>,
+}
+
+#[derive(Default)]
+struct Jobs {
+    next_id: u64,
+    jobs: BTreeMap<u64, Job>,
+}
+
+/// State shared by the request handlers, the worker and the progress tracker
+struct ServerState {
+    jobs: Mutex<Jobs>,
+    queue: mpsc::UnboundedSender<u64>,
+    options: ServeOptions,
+    config: Config,
+    /// Transcriber used for cache management only
+    cache: Box<dyn TranscriberTrait>,
+    cancel: Cancellation,
+    metrics: ServerMetrics,
+    /// Model request latencies of the workflow
+    translation: Arc<TranslationMetrics>,
+}
+
+impl ServerState {
+    fn jobs(&self) -> MutexGuard<'_, Jobs> {
+        self.jobs.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// ID of the next job, taken before its video is uploaded
+    fn next_id(&self) -> u64 {
+        let mut jobs = self.jobs();
+        jobs.next_id += 1;
+        jobs.next_id
+    }
+
+    /// Queue job `id` for `input`, uploaded into `upload` unless it is a path on the server
+    fn submit(&self, id: u64, input: PathBuf, target_languages: Vec<String>, upload: Option<PathBuf>) -> Job {
+        let job = Job {
+            id,
+            status: JobStatus::Queued,
+            input,
+            target_languages,
+            output_dir: self.options.dir.join("jobs").join(id.to_string()),
+            transcribed_segments: None,
+            translated_segments: BTreeMap::new(),
+            subtitles: BTreeMap::new(),
+            error: None,
+            cancel: self.cancel.child(),
+            upload,
+        };
+        self.jobs().jobs.insert(id, job.clone());
+
+        // The worker only stops with the server
+        let _ = self.queue.send(id);
+        info!("📥 Job {} queued: {}", id, job.input.display());
+        job
+    }
+}
+
+/// Serve the HTTP API until `cancel` is cancelled
+pub async fn serve(shuro: Shuro, options: ServeOptions, cancel: &Cancellation) -> Result<()> {
+    tokio::fs::create_dir_all(options.dir.join("uploads")).await?;
+    let (progress_tx, progress_rx) = mpsc::unbounded_channel();
+    let config = shuro.config().clone();
+    let workflow = shuro.into_workflow().with_progress(progress_tx);
+    let (queue_tx, queue_rx) = mpsc::unbounded_channel();
+    let translation = workflow.metrics().clone();
+
+    let validator = QualityValidator::from_config(&config.quality);
+    let state = Arc::new(ServerState {
+        jobs: Mutex::new(Jobs::default()),
+        queue: queue_tx,
+        cache: TranscriberFactory::create_default(config.transcriber.clone(), validator),
+        config,
+        options,
+        cancel: cancel.clone(),
+        metrics: ServerMetrics::new(),
+        translation,
+    });
+
+    let service_state = state.clone();
+    let make_service = make_service_fn(move |_| {
+        let state = service_state.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let state = state.clone();
+                async move { Ok::<_, Infallible>(handle(&state, request).await) }
+            }))
+        }
+    });
+    let shutdown = cancel.clone();
+    let server = Server::try_bind(&state.options.bind)?
+        .serve(make_service)
+        .with_graceful_shutdown(async move { cancelled(&shutdown).await });
+    info!("🌐 Serving the shuro API on http://{}", state.options.bind);
+    let server = tokio::spawn(server);
+    tokio::spawn(track_progress(state.clone(), progress_rx));
+
+    run_jobs(&workflow, &state, queue_rx).await;
+    server.await??;
+    Ok(())
+}
+
+/// Resolve once `cancel` is cancelled
+async fn cancelled(cancel: &Cancellation) {
+    let _ = cancel.run(std::future::pending::<shuro_core::Result<()>>()).await;
+}
+
+/// Process queued jobs one at a time until the server stops
+async fn run_jobs(workflow: &Workflow, state: &ServerState, mut queue: mpsc::UnboundedReceiver<u64>) {
+    loop {
+        let id = tokio::select! {
+            id = queue.recv() => match id {
+                Some(id) => id,
+                None => return,
+            },
+            _ = cancelled(&state.cancel) => return,
+        };
+
+        let job = {
+            let mut jobs = state.jobs();
+            let Some(job) = jobs.jobs.get_mut(&id).filter(|job| job.status == JobStatus::Queued) else {
+                continue;
+            };
+            job.status = JobStatus::Running;
+            job.clone()
+        };
+
+        info!("▶️ Job {} started: {}", id, job.input.display());
+        // Every job gets the whole cost budget
+        workflow.start_run();
+        let result = workflow
+            .process_single_file(&job.input, &job.target_languages, Some(&job.output_dir), &job.cancel)
+            .await;
+
+        if let Some(job) = state.jobs().jobs.get_mut(&id) {
+            match result {
+                Ok(()) => {
+                    job.status = JobStatus::Completed;
+                    info!("✅ Job {} completed", id);
+                }
+                Err(e) if e.is_cancellation() => {
+                    job.status = JobStatus::Cancelled;
+                    info!("Job {} cancelled", id);
+                }
+                Err(e) => {
+                    warn!("Job {} failed: {}", id, e);
+                    job.status = JobStatus::Failed;
+                    job.error = Some(e.to_string());
+                }
+            }
+        }
+        remove_upload(job.upload).await;
+    }
+}
+
+/// Remove the directory of an uploaded video
+async fn remove_upload(upload: Option<PathBuf>) {
+    if let Some(dir) = upload
+        && let Err(e) = tokio::fs::remove_dir_all(&dir).await
+    {
+        warn!("Failed to remove upload {}: {}", dir.display(), e);
+    }
+}
+
+/// Apply the workflow's progress events to the running job
+async fn track_progress(state: Arc<ServerState>, mut events: mpsc::UnboundedReceiver<ProgressEvent>) {
+    while let Some(event) = events.recv().await {
+        state.metrics.record(&event);
+        let mut jobs = state.jobs();
+        let Some(job) = jobs.jobs.values_mut().find(|job| job.status == JobStatus::Running) else {
+            continue;
+        };
+        match event {
+            ProgressEvent::Transcribed { segments, .. } => job.transcribed_segments = Some(segments),
+            ProgressEvent::Translated { language, segments, .. } => {
+                job.translated_segments.insert(language, segments);
+            }
+            ProgressEvent::LanguageCompleted { language, subtitles, .. } => {
+                job.subtitles.insert(language, subtitles);
+            }
+            _ => {}
+        }
+    }
+}
+
+async fn handle(state: &ServerState, request: Request<Body>) -> Response<Body> {
+    if let Some(token) = &state.options.token {
+        let expected = format!("Bearer {}", token);
+        let authorization = request.headers().get(hyper::header::AUTHORIZATION).and_then(|v| v.to_str().ok());
+        if authorization != Some(expected.as_str()) {
+            return json(StatusCode::UNAUTHORIZED, &serde_json::json!({ "error": "missing or wrong bearer token" }));
+        }
+    }
+
+    let method = request.method().clone();
+    let path: Vec<String> = request.uri().path().split('/').filter(|s| !s.is_empty()).map(String::from).collect();
+    let path: Vec<&str> = path.iter().map(String::as_str).collect();
+    let result = match (&method, path.as_slice()) {
+        (&Method::POST, ["jobs"]) => submit(state, request).await,
+        (&Method::GET, ["jobs"]) => Ok(json(StatusCode::OK, &state.jobs().jobs.values().collect::<Vec<_>>())),
+        (&Method::GET, ["jobs", id]) => with_job(state, id, |job| Ok(json(StatusCode::OK, job))),
+        (&Method::DELETE, ["jobs", id]) => cancel_job(state, id).await,
+        (&Method::GET, ["jobs", id, "subtitles", language]) => {
+            let subtitles = with_job(state, id, |job| Ok(job.subtitles.get(*language).cloned()));
+            match subtitles {
+                Ok(Some(path)) => download(&path).await,
+                Ok(None) => Err(error(StatusCode::NOT_FOUND, &format!("no {} subtitles (yet)", language))),
+                Err(e) => Err(e),
+            }
+        }
+        (&Method::GET, ["cache"]) => cache_info(state).await,
+        (&Method::DELETE, ["cache"]) => clear_cache(state).await,
+        (&Method::GET, ["metrics"]) => Ok(metrics(state)),
+        _ => Err(error(StatusCode::NOT_FOUND, "no such endpoint")),
+    };
+    result.unwrap_or_else(|e| json(e.status, &serde_json::json!({ "error": e.message })))
+}
+
+/// Failed request, answered with a JSON `{"error": ...}` body
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+type Handled<T = Response<Body>> = std::result::Result<T, ApiError>;
+
+/// Run `action` on the job with the ID `id`
+fn with_job<T>(state: &ServerState, id: &str, action: impl FnOnce(&mut Job) -> Handled<T>) -> Handled<T> {
+    let mut jobs = state.jobs();
+    let job = id.parse().ok()
+        .and_then(|id: u64| jobs.jobs.get_mut(&id))
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, &format!("no job {}", id)))?;
+    action(job)
+}
+
+/// Cancel a queued or running job; the worker skips a queued one, so its upload is removed here
+async fn cancel_job(state: &ServerState, id: &str) -> Handled {
+    let (response, upload) = with_job(state, id, |job| {
+        let mut upload = None;
+        if matches!(job.status, JobStatus::Queued | JobStatus::Running) {
+            job.cancel.cancel();
+            if job.status == JobStatus::Queued {
+                job.status = JobStatus::Cancelled;
+                upload = job.upload.take();
+            }
+        }
+        Ok((json(StatusCode::OK, job), upload))
+    })?;
+    remove_upload(upload).await;
+    Ok(response)
+}
+
+/// Queue a video given by its path on the server (JSON body) or uploaded as the body
+async fn submit(state: &ServerState, request: Request<Body>) -> Handled {
+    let is_json = request.headers()
+        .get(hyper::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+
+    let id = state.next_id();
+    let (input, target_languages, upload) = if is_json {
+        let body = hyper::body::to_bytes(request.into_body()).await
+            .map_err(|e| error(StatusCode::BAD_REQUEST, &e.to_string()))?;
+        let submitted: SubmitRequest = serde_json::from_slice(&body)
+            .map_err(|e| error(StatusCode::BAD_REQUEST, &format!("invalid job: {}", e)))?;
+        if !submitted.input.is_file() {
+            return Err(error(StatusCode::BAD_REQUEST, &format!("no such file on the server: {}", submitted.input.display())));
+        }
+        (submitted.input, submitted.target_languages, None)
+    } else {
+        let query = parse_query(request.uri().query().unwrap_or_default());
+        let filename = query.get("filename")
+            .and_then(|name| Path::new(name).file_name())
+            .ok_or_else(|| error(StatusCode::BAD_REQUEST, "uploads need ?filename="))?
+            .to_os_string();
+        let languages = query.get("target_langs").map(|l| split_languages(l)).unwrap_or_default();
+        // Each job's upload has its own directory, so uploads of the same name do not replace
+        // the video of a job still waiting for it
+        let dir = state.options.dir.join("uploads").join(id.to_string());
+        let upload = dir.join(filename);
+        if let Err(e) = receive_upload(request.into_body(), &upload).await {
+            remove_upload(Some(dir)).await;
+            return Err(error(StatusCode::INTERNAL_SERVER_ERROR, &format!("upload failed: {}", e)));
+        }
+        (upload, languages, Some(dir))
+    };
+    if target_languages.is_empty() {
+        remove_upload(upload).await;
+        return Err(error(StatusCode::BAD_REQUEST, "no target languages"));
+    }
+
+    Ok(json(StatusCode::ACCEPTED, &state.submit(id, input, target_languages, upload)))
+}
+
+/// Stream a request body into `path`, creating its directory
+async fn receive_upload(mut body: Body, path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let mut file = tokio::fs::File::create(path).await?;
+    while let Some(chunk) = body.data().await {
+        file.write_all(&chunk?).await?;
+    }
+    file.flush().await?;
+    Ok(())
+}
+
+async fn download(path: &Path) -> Handled {
+    let content = tokio::fs::read(path).await
+        .map_err(|e| error(StatusCode::NOT_FOUND, &format!("{}: {}", path.display(), e)))?;
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    Ok(Response::builder()
+        .header(hyper::header::CONTENT_TYPE, "application/x-subrip; charset=utf-8")
+        .header(hyper::header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", name.replace('"', "")))
+        .body(Body::from(content))
+        .expect("static headers are valid"))
+}
+
+async fn cache_info(state: &ServerState) -> Handled {
+    let info = state.cache.cache_info().await.map_err(internal)?;
+    let translations = BaseTranslator::new(state.config.translate.clone()).list_translation_cache().await.map_err(internal)?;
+    Ok(json(StatusCode::OK, &serde_json::json!({
+        "transcription_files": info.total_files,
+        "transcription_bytes": info.total_size,
+        "audio_files": info.audio_files,
+        "audio_bytes": info.audio_size,
+        "translation_files": translations.len(),
+        "models_used": info.models_used,
+    })))
+}
+
+async fn clear_cache(state: &ServerState) -> Handled {
+    if state.jobs().jobs.values().any(|job| job.status == JobStatus::Running) {
+        return Err(error(StatusCode::CONFLICT, "a job is running"));
+    }
+    let transcriptions = state.cache.clear_cache().await.map_err(internal)?;
+    let audio = state.cache.clear_audio_cache().await.map_err(internal)?;
+    let translations = BaseTranslator::new(state.config.translate.clone()).clear_translation_cache().await.map_err(internal)?;
+    Ok(json(StatusCode::OK, &serde_json::json!({
+        "transcriptions": transcriptions,
+        "audio": audio,
+        "translations": translations,
+    })))
+}
+
+fn metrics(state: &ServerState) -> Response<Body> {
+    let mut jobs = BTreeMap::new();
+    for status in [JobStatus::Queued, JobStatus::Running, JobStatus::Completed, JobStatus::Failed, JobStatus::Cancelled] {
+        jobs.insert(status.name(), 0);
+    }
+    for job in state.jobs().jobs.values() {
+        *jobs.entry(job.status.name()).or_insert(0) += 1;
+    }
+    Response::builder()
+        .header(hyper::header::CONTENT_TYPE, "text/plain; version=0.0.4")
+        .body(Body::from(state.metrics.render(&jobs, &state.translation)))
+        .expect("static headers are valid")
+}
+
+fn json<T: Serialize + ?Sized>(status: StatusCode, value: &T) -> Response<Body> {
+    let body = serde_json::to_vec_pretty(value).expect("API responses serialize to JSON");
+    Response::builder()
+        .status(status)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .expect("static headers are valid")
+}
+
+fn error(status: StatusCode, message: &str) -> ApiError {
+    ApiError { status, message: message.to_string() }
+}
+
+fn internal(e: shuro_core::ShuroError) -> ApiError {
+    error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string())
+}
+
+fn split_languages(languages: &str) -> Vec<String> {
+    languages.split(',').map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect()
+}
+
+/// Decoded `key=value` pairs of a query string
+fn parse_query(query: &str) -> BTreeMap<String, String> {
+    query.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (percent_decode(key), percent_decode(value)))
+        .collect()
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match hex {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_query() {
+        let query = parse_query("filename=Episode%2001%20%5BHD%5D.mkv&target_langs=ja,+de&broken=%zz");
+        assert_eq!(query["filename"], "Episode 01 [HD].mkv");
+        assert_eq!(split_languages(&query["target_langs"]), vec!["ja", "de"]);
+        assert_eq!(query["broken"], "%zz");
+        assert_eq!(percent_decode("100%"), "100%");
+    }
+}
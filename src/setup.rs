@@ -1,12 +1,23 @@
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
 use std::path::{Path, PathBuf};
 use std::fs;
 use tokio::fs as async_fs;
 use reqwest::Client;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 use tracing::{info, warn};
 use indicatif::{ProgressBar, ProgressStyle};
 
-use crate::error::{Result, ShuroError};
-use crate::config::Config;
+use shuro_core::error::{Result, ShuroError};
+use shuro_core::config::{BundledBinaryConfig, BundledMediaConfig, Config};
+use shuro_core::manifest::file_sha256;
+use shuro_core::platform;
+use shuro_core::preflight;
+use shuro_core::temp;
+
+/// Checksums recorded for downloaded models, in `sha256sum` format
+const MODEL_CHECKSUMS: &str = "checksums.sha256";
 
 pub struct SetupManager {
     client: Client,
@@ -19,6 +30,21 @@ pub struct ModelInfo {
     pub filename: String,
     pub url: String,
     pub size_mb: f64,
+    /// Transcribes English only (`.en` and distil-whisper checkpoints)
+    pub english_only: bool,
+}
+
+/// Outcome of verifying a downloaded model
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelStatus {
+    /// The checksum matches the catalog and the model loads
+    Verified,
+    /// The model loads, but no checksum was recorded when it was downloaded
+    Unrecorded,
+    ChecksumMismatch { expected: String, actual: String },
+    /// whisper could not load the model or transcribe with it
+    LoadFailed(String),
 }
 
 impl SetupManager {
@@ -32,7 +58,7 @@ impl SetupManager {
         let client = Client::builder()
             .user_agent("shuro/0.1.0")
             .build()
-            .map_err(|e| ShuroError::Http(e))?;
+            .map_err(ShuroError::Http)?;
 
         Ok(Self { client, shuro_dir })
     }
@@ -46,6 +72,9 @@ impl SetupManager {
         // Check and download required whisper models
         self.ensure_whisper_models(config).await?;
 
+        // Provision bundled ffmpeg/ffprobe when they are not installed
+        self.ensure_media_binaries(config).await?;
+
         info!("Updated explore model: {}", config.transcriber.explore_model);
         info!("Updated transcribe model: {}", config.transcriber.transcribe_model);
         info!("Initialization completed successfully");
@@ -58,6 +87,18 @@ impl SetupManager {
         
         // Define available models
         let available_models = self.get_available_models();
+
+        // English-only checkpoints turn other languages into English-sounding nonsense
+        let source_language = &config.translate.source_language;
+        for model in [&config.transcriber.explore_model, &config.transcriber.transcribe_model] {
+            if let Some(model) = self.find_model(model)
+                && model.english_only
+                && source_language != "en"
+            {
+                warn!("Model '{}' transcribes English only, but the source language is '{}'; \
+                       use a multilingual model such as large-v3-turbo", model.name, source_language);
+            }
+        }
         
         // Check and download exploration model
         if !self.model_exists(&config.transcriber.explore_model) {
@@ -81,6 +122,89 @@ impl SetupManager {
             config.transcriber.transcribe_model = self.resolve_model_path(&config.transcriber.transcribe_model);
         }
 
+        // Check and download the language identification model used to pick audio tracks
+        if config.transcriber.audio_track.is_none() {
+            if !self.model_exists(&config.transcriber.language_id_model) {
+                info!("Language identification model not found: {}", config.transcriber.language_id_model);
+                let model = self.select_appropriate_model(&available_models, &config.transcriber.language_id_model)?;
+                let local_path = self.download_model(&model).await?;
+                config.transcriber.language_id_model = local_path;
+            } else {
+                config.transcriber.language_id_model = self.resolve_model_path(&config.transcriber.language_id_model);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Point ffmpeg/ffprobe at bundled builds in .shuro/bin when they are not found on PATH
+    async fn ensure_media_binaries(&self, config: &mut Config) -> Result<()> {
+        let bundled = &config.media.bundled;
+        if !bundled.enabled {
+            return Ok(());
+        }
+
+        config.media.binary_path = self.ensure_bundled_binary(&config.media.binary_path, "ffmpeg", bundled).await?;
+        config.media.ffprobe_path = self.ensure_bundled_binary(&config.media.ffprobe_path, "ffprobe", bundled).await?;
+        Ok(())
+    }
+
+    /// Return the configured binary if it resolves, otherwise the bundled one (downloading it if missing)
+    async fn ensure_bundled_binary(&self, configured: &str, name: &str, bundled: &BundledMediaConfig) -> Result<String> {
+        if platform::resolve_binary(configured).is_ok() {
+            return Ok(configured.to_string());
+        }
+
+        let local_path = self.shuro_dir.join("bin").join(executable_file_name(name));
+        if !local_path.exists() {
+            info!("{} not found on PATH, provisioning bundled build", name);
+            let download = bundled.binaries.iter().find(|b| b.name == name).ok_or_else(|| ShuroError::Config(format!(
+                "{} not found on PATH and no bundled download is configured for it in [media.bundled]",
+                name
+            )))?;
+            self.download_binary(download, &local_path).await?;
+        }
+
+        Ok(local_path.to_string_lossy().to_string())
+    }
+
+    /// Download a static build, verify its checksum and install the executable at `local_path`
+    async fn download_binary(&self, download: &BundledBinaryConfig, local_path: &Path) -> Result<()> {
+        info!("Downloading {} from {}...", download.name, download.url);
+
+        let response = self.client.get(&download.url).send().await
+            .map_err(ShuroError::Http)?;
+
+        if !response.status().is_success() {
+            return Err(ShuroError::Config(format!(
+                "Failed to download {}: HTTP {}",
+                download.name, response.status()
+            )));
+        }
+
+        let bytes = response.bytes().await.map_err(ShuroError::Http)?;
+        verify_checksum(&bytes, &download.sha256, &download.name)?;
+
+        let file_name = executable_file_name(&download.name);
+        let executable = if download.url.ends_with(".zip") {
+            extract_from_zip(&bytes, &file_name)?
+        } else {
+            bytes.to_vec()
+        };
+
+        if let Some(parent) = local_path.parent() {
+            async_fs::create_dir_all(parent).await?;
+        }
+        let temp_path = local_path.with_extension("tmp");
+        async_fs::write(&temp_path, &executable).await?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            async_fs::set_permissions(&temp_path, fs::Permissions::from_mode(0o755)).await?;
+        }
+        async_fs::rename(&temp_path, local_path).await?;
+
+        info!("Installed bundled {} to {}", download.name, local_path.display());
         Ok(())
     }
 
@@ -91,74 +215,119 @@ impl SetupManager {
                 filename: "ggml-tiny.bin".to_string(),
                 url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-tiny.bin".to_string(),
                 size_mb: 39.0,
+                english_only: false,
             },
             ModelInfo {
                 name: "tiny.en".to_string(),
                 filename: "ggml-tiny.en.bin".to_string(),
                 url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-tiny.en.bin".to_string(),
                 size_mb: 39.0,
+                english_only: true,
             },
             ModelInfo {
                 name: "base".to_string(),
                 filename: "ggml-base.bin".to_string(),
                 url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin".to_string(),
                 size_mb: 142.0,
+                english_only: false,
             },
             ModelInfo {
                 name: "base.en".to_string(),
                 filename: "ggml-base.en.bin".to_string(),
                 url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.en.bin".to_string(),
                 size_mb: 142.0,
+                english_only: true,
             },
             ModelInfo {
                 name: "small".to_string(),
                 filename: "ggml-small.bin".to_string(),
                 url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.bin".to_string(),
                 size_mb: 244.0,
+                english_only: false,
             },
             ModelInfo {
                 name: "small.en".to_string(),
                 filename: "ggml-small.en.bin".to_string(),
                 url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.en.bin".to_string(),
                 size_mb: 244.0,
+                english_only: true,
             },
             ModelInfo {
                 name: "medium".to_string(),
                 filename: "ggml-medium.bin".to_string(),
                 url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-medium.bin".to_string(),
                 size_mb: 769.0,
+                english_only: false,
             },
             ModelInfo {
                 name: "medium.en".to_string(),
                 filename: "ggml-medium.en.bin".to_string(),
                 url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-medium.en.bin".to_string(),
                 size_mb: 769.0,
+                english_only: true,
             },
             ModelInfo {
                 name: "large-v1".to_string(),
                 filename: "ggml-large-v1.bin".to_string(),
                 url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v1.bin".to_string(),
                 size_mb: 1550.0,
+                english_only: false,
             },
             ModelInfo {
                 name: "large-v2".to_string(),
                 filename: "ggml-large-v2.bin".to_string(),
                 url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v2.bin".to_string(),
                 size_mb: 1550.0,
+                english_only: false,
             },
             ModelInfo {
                 name: "large-v3".to_string(),
                 filename: "ggml-large-v3.bin".to_string(),
                 url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3.bin".to_string(),
                 size_mb: 1550.0,
+                english_only: false,
+            },
+            // large-v3 with 4 instead of 32 decoder layers: close to its accuracy at several
+            // times the speed, the best transcribe_model for most GPUs
+            ModelInfo {
+                name: "large-v3-turbo".to_string(),
+                filename: "ggml-large-v3-turbo.bin".to_string(),
+                url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3-turbo.bin".to_string(),
+                size_mb: 1620.0,
+                english_only: false,
+            },
+            ModelInfo {
+                name: "large-v3-turbo-q5_0".to_string(),
+                filename: "ggml-large-v3-turbo-q5_0.bin".to_string(),
+                url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3-turbo-q5_0.bin".to_string(),
+                size_mb: 574.0,
+                english_only: false,
+            },
+            // Distilled from large-v3 by Hugging Face and published in their own repository
+            ModelInfo {
+                name: "distil-large-v3".to_string(),
+                filename: "ggml-distil-large-v3.bin".to_string(),
+                url: "https://huggingface.co/distil-whisper/distil-large-v3-ggml/resolve/main/ggml-distil-large-v3.bin".to_string(),
+                size_mb: 1520.0,
+                english_only: true,
             },
         ]
     }
 
+    /// Catalog entry of a model name or alias (`large` is large-v3, `turbo` large-v3-turbo)
+    pub fn find_model(&self, name: &str) -> Option<ModelInfo> {
+        let name = match name {
+            "large" => "large-v3",
+            "turbo" => "large-v3-turbo",
+            name => name,
+        };
+        self.get_available_models().into_iter().find(|m| m.name == name)
+    }
+
     fn select_appropriate_model(&self, models: &[ModelInfo], preferred: &str) -> Result<ModelInfo> {
         // Try to find the exact preferred model
-        if let Some(model) = models.iter().find(|m| m.name == preferred) {
-            return Ok(model.clone());
+        if let Some(model) = self.find_model(preferred) {
+            return Ok(model);
         }
 
         // Fall back to base model if preferred not found
@@ -183,17 +352,17 @@ impl SetupManager {
         }
 
         // Check if it's a relative path in .shuro/models
-        if model_path.starts_with(".shuro/models/") && Path::new(model_path).exists() {
+        if Path::new(model_path).starts_with(self.shuro_dir.join("models")) && Path::new(model_path).exists() {
             return true;
         }
 
         // If it's just a model name (like "base" or "tiny"), find the corresponding file
-        if !model_path.contains('/') && !model_path.ends_with(".bin") {
-            let models = self.get_available_models();
-            if let Some(model) = models.iter().find(|m| m.name == model_path) {
-                let local_path = self.shuro_dir.join("models").join(&model.filename);
-                return local_path.exists();
-            }
+        if Path::new(model_path).components().count() == 1
+            && !model_path.ends_with(".bin")
+            && let Some(model) = self.find_model(model_path)
+        {
+            let local_path = self.shuro_dir.join("models").join(&model.filename);
+            return local_path.exists();
         }
 
         // Check if the filename exists in .shuro/models
@@ -225,7 +394,7 @@ impl SetupManager {
 
         // Download the file
         let response = self.client.get(&model.url).send().await
-            .map_err(|e| ShuroError::Http(e))?;
+            .map_err(ShuroError::Http)?;
 
         if !response.status().is_success() {
             return Err(ShuroError::Config(format!(
@@ -241,9 +410,10 @@ impl SetupManager {
         // Download with progress
         use tokio::io::AsyncWriteExt;
         let content_length = response.content_length().unwrap_or(0);
-        let bytes = response.bytes().await.map_err(|e| ShuroError::Http(e))?;
+        let bytes = response.bytes().await.map_err(ShuroError::Http)?;
         
         file.write_all(&bytes).await?;
+        self.record_checksum(&model.filename, &sha256_hex(&bytes))?;
         let downloaded = bytes.len() as u64;
         pb.set_position(downloaded);
         
@@ -264,6 +434,57 @@ impl SetupManager {
         Ok(local_path.to_string_lossy().to_string())
     }
 
+    /// Checksums recorded in the model catalog when models were downloaded, by file name
+    fn recorded_checksums(&self) -> BTreeMap<String, String> {
+        let content = fs::read_to_string(self.shuro_dir.join("models").join(MODEL_CHECKSUMS)).unwrap_or_default();
+        content
+            .lines()
+            .filter_map(|line| line.split_once("  "))
+            .map(|(hash, file)| (file.trim().to_string(), hash.trim().to_lowercase()))
+            .collect()
+    }
+
+    fn record_checksum(&self, filename: &str, sha256: &str) -> Result<()> {
+        let mut checksums = self.recorded_checksums();
+        checksums.insert(filename.to_string(), sha256.to_string());
+        let content: String = checksums.iter().map(|(file, hash)| format!("{}  {}\n", hash, file)).collect();
+        fs::write(self.shuro_dir.join("models").join(MODEL_CHECKSUMS), content)?;
+        Ok(())
+    }
+
+    /// Re-hash a downloaded model against its recorded checksum and test-load it with `whisper_binary`
+    /// on a second of silence, written in `temp_root`; `None` when the model is not downloaded
+    pub async fn verify_model(&self, model: &ModelInfo, whisper_binary: &str, temp_root: &Path) -> Result<Option<ModelStatus>> {
+        let local_path = self.shuro_dir.join("models").join(&model.filename);
+        if !local_path.exists() {
+            return Ok(None);
+        }
+
+        let actual = file_sha256(&local_path).await?;
+        let expected = self.recorded_checksums().remove(&model.filename);
+        if let Some(expected) = &expected
+            && *expected != actual {
+            return Ok(Some(ModelStatus::ChecksumMismatch { expected: expected.clone(), actual }));
+        }
+
+        let temp_dir = temp::temp_dir(temp_root, "verify")?;
+        let silence = temp_dir.path().join("silence.wav");
+        async_fs::write(&silence, silent_wav(1)).await?;
+        let output = platform::command(whisper_binary)?
+            .arg("-m").arg(platform::process_path(&local_path))
+            .arg("-f").arg(platform::process_path(&silence))
+            .arg("-nt")
+            .output()
+            .await?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("whisper exited with an error");
+            return Ok(Some(ModelStatus::LoadFailed(reason.trim().to_string())));
+        }
+
+        Ok(Some(if expected.is_some() { ModelStatus::Verified } else { ModelStatus::Unrecorded }))
+    }
+
     /// Get the path to the .shuro directory
     pub fn shuro_dir(&self) -> &PathBuf {
         &self.shuro_dir
@@ -276,10 +497,115 @@ impl SetupManager {
             return model_name.to_string();
         }
 
-        // Map model names to filenames
-        let filename = format!("ggml-{}.bin", model_name);
+        // Map model names to filenames; catalog entries may be named differently
+        let filename = self.find_model(model_name)
+            .map(|model| model.filename)
+            .unwrap_or_else(|| format!("ggml-{}.bin", model_name));
         let local_path = self.shuro_dir.join("models").join(filename);
         
         local_path.to_string_lossy().to_string()
     }
-} 
\ No newline at end of file
+}
+
+/// File name of an executable on this platform (e.g. `ffmpeg.exe` on Windows)
+fn executable_file_name(name: &str) -> String {
+    format!("{}{}", name, std::env::consts::EXE_SUFFIX)
+}
+
+/// Hex SHA-256 of `bytes`
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 16 kHz mono 16-bit PCM WAV of `seconds` of silence, the input format whisper expects
+fn silent_wav(seconds: u32) -> Vec<u8> {
+    preflight::pcm_wav(&vec![0; 16_000 * seconds as usize])
+}
+
+/// Fail unless the SHA-256 of `bytes` matches the expected hex digest
+fn verify_checksum(bytes: &[u8], expected: &str, name: &str) -> Result<()> {
+    let actual = sha256_hex(bytes);
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        return Err(ShuroError::Config(format!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            name, expected, actual
+        )));
+    }
+    Ok(())
+}
+
+/// Read the archive entry whose file name is `file_name`, wherever it sits in the archive
+fn extract_from_zip(bytes: &[u8], file_name: &str) -> Result<Vec<u8>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| ShuroError::Config(format!("Invalid zip archive: {}", e)))?;
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)
+            .map_err(|e| ShuroError::Config(format!("Invalid zip entry: {}", e)))?;
+        let matches = Path::new(entry.name()).file_name().and_then(|n| n.to_str()) == Some(file_name);
+        if entry.is_file() && matches {
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content)?;
+            return Ok(content);
+        }
+    }
+
+    Err(ShuroError::Config(format!("{} not found in archive", file_name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_silent_wav() {
+        let wav = silent_wav(1);
+        assert_eq!(wav.len(), 44 + 32_000);
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(wav[4..8].try_into().unwrap()) as usize, wav.len() - 8);
+        assert!(wav[44..].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert!(verify_checksum(b"abc", abc, "ffmpeg").is_ok());
+        assert!(verify_checksum(b"abc", &abc.to_uppercase(), "ffmpeg").is_ok());
+        assert!(verify_checksum(b"abd", abc, "ffmpeg").is_err());
+    }
+
+    #[test]
+    fn test_extract_from_zip() {
+        let mut buffer = Cursor::new(Vec::new());
+        {
+            let mut writer = zip::ZipWriter::new(&mut buffer);
+            let options = zip::write::SimpleFileOptions::default();
+            writer.start_file("ffmpeg-7.1/doc/ffmpeg.txt", options).unwrap();
+            writer.write_all(b"docs").unwrap();
+            writer.start_file("ffmpeg-7.1/bin/ffmpeg.exe", options).unwrap();
+            writer.write_all(b"binary").unwrap();
+            writer.finish().unwrap();
+        }
+        let bytes = buffer.into_inner();
+
+        assert_eq!(extract_from_zip(&bytes, "ffmpeg.exe").unwrap(), b"binary");
+        assert!(extract_from_zip(&bytes, "ffprobe.exe").is_err());
+    }
+
+    #[test]
+    fn test_model_catalog() {
+        let setup = SetupManager { client: Client::new(), shuro_dir: PathBuf::from(".shuro") };
+        let models = Path::new(".shuro").join("models");
+        assert_eq!(setup.resolve_model_path("turbo"), models.join("ggml-large-v3-turbo.bin").to_string_lossy());
+        assert_eq!(setup.resolve_model_path("large"), models.join("ggml-large-v3.bin").to_string_lossy());
+        assert_eq!(setup.resolve_model_path("distil-large-v3"), models.join("ggml-distil-large-v3.bin").to_string_lossy());
+        assert_eq!(setup.resolve_model_path("custom"), models.join("ggml-custom.bin").to_string_lossy());
+        assert_eq!(setup.resolve_model_path("/models/ggml-x.bin"), "/models/ggml-x.bin");
+
+        assert!(setup.find_model("distil-large-v3").unwrap().english_only);
+        assert!(setup.find_model("base.en").unwrap().english_only);
+        assert!(!setup.find_model("large-v3-turbo-q5_0").unwrap().english_only);
+        assert!(setup.find_model("large-v4").is_none());
+    }
+}